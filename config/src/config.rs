@@ -38,11 +38,12 @@
 //! // Default config for mainnet
 //! // Config::from_partial(&PartialConfig::default_mainnet());
 //! ```
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use failure::Fail;
 use log::warn;
 use serde::{Deserialize, Deserializer, Serialize};
 
@@ -50,7 +51,9 @@ use crate::defaults::{Defaults, Testnet1, Testnet3};
 use crate::dirs;
 use partial_struct::PartialStruct;
 use witnet_crypto::hash::HashFunction;
-use witnet_data_structures::chain::{ConsensusConstants, Environment, PartialConsensusConstants};
+use witnet_data_structures::chain::{
+    ConsensusConstants, Environment, PartialConsensusConstants, PublicKeyHash,
+};
 use witnet_protected::{Protected, ProtectedString};
 
 /// The total configuration object that contains all other, more
@@ -103,6 +106,16 @@ pub struct Config {
     #[partial_struct(ty = "PartialLog")]
     #[partial_struct(serde(default))]
     pub log: Log,
+
+    /// Webhook notifications configuration
+    #[partial_struct(ty = "PartialWebhooks")]
+    #[partial_struct(serde(default))]
+    pub webhooks: Webhooks,
+
+    /// Actor mailboxes configuration
+    #[partial_struct(ty = "PartialMailboxes")]
+    #[partial_struct(serde(default))]
+    pub mailboxes: Mailboxes,
 }
 
 /// Log-specific configuration.
@@ -112,6 +125,8 @@ pub struct Log {
     /// Level  for the log messages.
     #[partial_struct(serde(deserialize_with = "as_log_filter"))]
     pub level: log::LevelFilter,
+    /// Directory where the crash reports are written when the process panics.
+    pub crash_report_dir: PathBuf,
 }
 
 fn as_log_filter<'de, D>(deserializer: D) -> Result<Option<log::LevelFilter>, D::Error>
@@ -190,11 +205,108 @@ pub struct Connections {
     /// Number of seconds before giving up waiting for requested blocks
     pub blocks_timeout: i64,
 
+    /// Number of times a batch of blocks is requested again during the synchronization before
+    /// giving up, if it does not arrive or arrives empty
+    pub sync_batch_retries: u32,
+
     /// Constant to specify when consensus is achieved (in %)
     pub consensus_c: u32,
 
+    /// Consensus threshold (in %) used while waiting for consensus, defaults to `consensus_c`
+    pub consensus_c_bootstrap: u32,
+
+    /// Consensus threshold (in %) used while synchronizing, defaults to `consensus_c`
+    pub consensus_c_synchronizing: u32,
+
+    /// Consensus threshold (in %) used once synced, defaults to `consensus_c`
+    pub consensus_c_synced: u32,
+
     /// Period that indicate the validity of a checked peer
     pub bucketing_update_period: i64,
+
+    /// Period after which a peer of the new addresses bucket that has not been seen is dropped
+    pub bucketing_aging_period: i64,
+
+    /// Period of the feeler task, which tests the oldest entry of the tried addresses bucket,
+    /// demoting it if the connection fails, and a random entry of the new addresses bucket
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "feeler_peers_period_seconds"
+    ))]
+    pub feeler_peers_period: Duration,
+
+    /// Backoff before trying again an address after a failed outbound connection attempt. It is
+    /// doubled with every consecutive failure, up to `retry_backoff_max`
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "retry_backoff_base_seconds"
+    ))]
+    pub retry_backoff_base: Duration,
+
+    /// Maximum backoff before trying again an address after failed outbound connection attempts
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "retry_backoff_max_seconds"
+    ))]
+    pub retry_backoff_max: Duration,
+
+    /// Period of the keepalive pings sent to every peer
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "ping_interval_seconds"
+    ))]
+    pub ping_interval: Duration,
+
+    /// Timeout for receiving the pong of a keepalive ping before the peer is considered dead
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "ping_timeout_seconds"
+    ))]
+    pub ping_timeout: Duration,
+
+    /// Maximum time that the epoch of a block candidate can start ahead of the local clock. The
+    /// candidates within this tolerance are evaluated again in the next epoch, the rest are
+    /// rejected
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "clock_tolerance_seconds"
+    ))]
+    pub clock_tolerance: Duration,
+
+    /// Run the node without networking: no server is listening for peers and no outbound
+    /// connections are opened
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub offline: bool,
+
+    /// Additional addresses the server accepting connections from other peers should bind to,
+    /// e.g. to listen on both IPv4 and IPv6
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub listeners: Vec<Listener>,
+
+    /// Trusted peers that receive the block candidates mined by this node before any other peer,
+    /// as long as there is a session with them
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub candidate_relay_peers: Vec<SocketAddr>,
+}
+
+/// Additional address to which the server accepting connections from other peers binds
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Listener {
+    /// Socket address (interface ip and port) to bind to
+    pub addr: SocketAddr,
+    /// Maximum number of inbound connections accepted through this address. The connections
+    /// accepted through all the addresses are also limited by `inbound_limit`.
+    #[serde(default)]
+    pub inbound_limit: Option<u16>,
 }
 
 fn from_secs<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
@@ -236,9 +348,21 @@ pub struct Storage {
     #[partial_struct(serde(default))]
     #[partial_struct(serde(deserialize_with = "as_protected_string"))]
     pub password: Option<Protected>,
+    /// File containing the passphrase used to encrypt the sensitive records, like the identity
+    /// file or the peers database. The `WITNET_STORAGE_PASSPHRASE` environment variable takes
+    /// precedence over it
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub passphrase_file: Option<PathBuf>,
     /// Path to the directory that will contain the database. Used
     /// only if backend is RocksDB.
     pub db_path: PathBuf,
+    /// Path to the file containing the identity (master key) of the node. Defaults to the path
+    /// of the database with an `-identity` suffix, so it is kept out of the database directory
+    pub identity_file: PathBuf,
+    /// Check the consistency of the chain state after every consolidated block and stop the
+    /// node if it is broken. The checks always run in debug builds
+    pub check_invariants: bool,
 }
 
 fn as_protected_string<'de, D>(deserializer: D) -> Result<Option<Protected>, D::Error>
@@ -258,6 +382,10 @@ pub struct JsonRPC {
     /// JSON-RPC server address, that is, the socket address (interface ip and
     /// port) for the JSON-RPC server
     pub server_address: SocketAddr,
+    /// Maximum number of heavy queries, like block range exports, handled at the same time
+    pub max_heavy_queries: usize,
+    /// Maximum number of heavy queries waiting to be handled. Any other heavy query is rejected
+    pub max_queued_heavy_queries: usize,
 }
 
 /// Mining-related configuration
@@ -266,6 +394,64 @@ pub struct JsonRPC {
 pub struct Mining {
     /// Binary flag telling whether to enable the MiningManager or not
     pub enabled: bool,
+
+    /// Percentage of the block weight reserved for old value transfer transactions,
+    /// which are included oldest first regardless of their fee
+    pub old_transactions_weight_percentage: u8,
+
+    /// Time a value transfer transaction has to wait in the transactions pool before being
+    /// considered old
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "old_transactions_min_age_seconds"
+    ))]
+    pub old_transactions_min_age: Duration,
+
+    /// Reputation below which the node warns that it is losing its eligibility. There are no
+    /// warnings if set to 0
+    pub reputation_alert_threshold: u32,
+
+    /// Number of epochs ahead for which the expiration of the node reputation is checked
+    pub reputation_alert_epochs: u32,
+
+    /// Time after which a value transfer transaction that has not been included in a block is
+    /// dropped from the transactions pool. Transactions never expire if set to 0
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "transactions_pool_expiry_seconds"
+    ))]
+    pub transactions_pool_expiry: Duration,
+
+    /// Maximum number of value transfer transactions in the transactions pool. When it is full,
+    /// the transactions paying the lowest fee per byte are dropped. There is no limit if set to 0
+    pub transactions_pool_max_size: usize,
+
+    /// Maximum number of data requests the node commits to resolve in the same epoch, to cap the
+    /// exposure of the node. The data requests paying the highest reward per witness are chosen
+    /// first. There is no limit if set to 0
+    pub data_request_max_commits_per_epoch: u16,
+
+    /// Identities whose unspent outputs are tracked by the node besides its own, e.g. rotated
+    /// keys or a cold address, so their balance can be queried without scanning all the UTXOs
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub watched_pkhs: Vec<PublicKeyHash>,
+}
+
+/// Errors found when validating the values of a configuration
+#[derive(Debug, Fail)]
+pub enum ValidationError {
+    /// A consensus threshold is not a valid percentage
+    #[fail(
+        display = "connections.{} must be a percentage between 1 and 100, but it is {}",
+        _0, _1
+    )]
+    InvalidConsensusThreshold(&'static str, u32),
+    /// A period or timeout is zero
+    #[fail(display = "connections.{} must be greater than 0", _0)]
+    ZeroDuration(&'static str),
 }
 
 impl Config {
@@ -309,8 +495,16 @@ impl Config {
             mining: Mining::from_partial(&config.mining, defaults),
             wallet: Wallet::from_partial(&config.wallet, defaults),
             rocksdb: Rocksdb::from_partial(&config.rocksdb, defaults),
+            webhooks: Webhooks::from_partial(&config.webhooks, defaults),
+            mailboxes: Mailboxes::from_partial(&config.mailboxes, defaults),
         }
     }
+
+    /// Check that the values of the configuration are valid, so the node does not start (or
+    /// reload) with a configuration it cannot work with.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.connections.validate()
+    }
 }
 
 pub fn consensus_constants_from_partial(
@@ -370,6 +564,10 @@ impl Log {
                 .level
                 .to_owned()
                 .unwrap_or_else(|| defaults.log_level()),
+            crash_report_dir: config
+                .crash_report_dir
+                .to_owned()
+                .unwrap_or_else(|| defaults.log_crash_report_dir()),
         }
     }
 }
@@ -413,27 +611,114 @@ impl Connections {
                 .blocks_timeout
                 .to_owned()
                 .unwrap_or_else(|| defaults.connections_blocks_timeout()),
+            sync_batch_retries: config
+                .sync_batch_retries
+                .unwrap_or_else(|| defaults.connections_sync_batch_retries()),
             consensus_c: config
                 .consensus_c
                 .to_owned()
                 .unwrap_or_else(|| defaults.connections_consensus_c()),
+            consensus_c_bootstrap: config
+                .consensus_c_bootstrap
+                .or(config.consensus_c)
+                .unwrap_or_else(|| defaults.connections_consensus_c()),
+            consensus_c_synchronizing: config
+                .consensus_c_synchronizing
+                .or(config.consensus_c)
+                .unwrap_or_else(|| defaults.connections_consensus_c()),
+            consensus_c_synced: config
+                .consensus_c_synced
+                .or(config.consensus_c)
+                .unwrap_or_else(|| defaults.connections_consensus_c()),
             bucketing_update_period: config
                 .bucketing_update_period
                 .to_owned()
                 .unwrap_or_else(|| defaults.connections_bucketing_update_period()),
+            bucketing_aging_period: config
+                .bucketing_aging_period
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_bucketing_aging_period()),
+            feeler_peers_period: config
+                .feeler_peers_period
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_feeler_peers_period()),
+            retry_backoff_base: config
+                .retry_backoff_base
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_retry_backoff_base()),
+            retry_backoff_max: config
+                .retry_backoff_max
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_retry_backoff_max()),
+            ping_interval: config
+                .ping_interval
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_ping_interval()),
+            ping_timeout: config
+                .ping_timeout
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_ping_timeout()),
+            clock_tolerance: config
+                .clock_tolerance
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_clock_tolerance()),
+            offline: config.offline,
+            listeners: config.listeners.clone(),
+            candidate_relay_peers: config.candidate_relay_peers.clone(),
         }
     }
+
+    /// Check that all the consensus thresholds are valid percentages, and that the keepalive ping
+    /// interval and timeout are not zero
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let thresholds = [
+            ("consensus_c", self.consensus_c),
+            ("consensus_c_bootstrap", self.consensus_c_bootstrap),
+            ("consensus_c_synchronizing", self.consensus_c_synchronizing),
+            ("consensus_c_synced", self.consensus_c_synced),
+        ];
+        for &(name, threshold) in thresholds.iter() {
+            if threshold == 0 || threshold > 100 {
+                return Err(ValidationError::InvalidConsensusThreshold(name, threshold));
+            }
+        }
+
+        // A zero ping interval would send pings continuously, and a zero ping timeout would
+        // disconnect every peer right after pinging it
+        let durations = [
+            ("ping_interval_seconds", self.ping_interval),
+            ("ping_timeout_seconds", self.ping_timeout),
+        ];
+        for &(name, duration) in durations.iter() {
+            if duration == Duration::from_secs(0) {
+                return Err(ValidationError::ZeroDuration(name));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Storage {
     pub fn from_partial(config: &PartialStorage, defaults: &dyn Defaults) -> Self {
+        let db_path = config
+            .db_path
+            .to_owned()
+            .unwrap_or_else(|| defaults.storage_db_path());
+
         Storage {
             backend: config.backend.clone(),
             password: config.password.clone(),
-            db_path: config
-                .db_path
+            passphrase_file: config.passphrase_file.clone(),
+            db_path: db_path.clone(),
+            identity_file: config
+                .identity_file
+                .to_owned()
+                .unwrap_or_else(|| defaults.storage_identity_file(&db_path)),
+            check_invariants: config
+                .check_invariants
                 .to_owned()
-                .unwrap_or_else(|| defaults.storage_db_path()),
+                .unwrap_or_else(|| defaults.storage_check_invariants()),
         }
     }
 }
@@ -449,6 +734,14 @@ impl JsonRPC {
                 .server_address
                 .to_owned()
                 .unwrap_or_else(|| defaults.jsonrpc_server_address()),
+            max_heavy_queries: config
+                .max_heavy_queries
+                .to_owned()
+                .unwrap_or_else(|| defaults.jsonrpc_max_heavy_queries()),
+            max_queued_heavy_queries: config
+                .max_queued_heavy_queries
+                .to_owned()
+                .unwrap_or_else(|| defaults.jsonrpc_max_queued_heavy_queries()),
         }
     }
 }
@@ -460,10 +753,163 @@ impl Mining {
                 .enabled
                 .to_owned()
                 .unwrap_or_else(|| defaults.mining_enabled()),
+            old_transactions_weight_percentage: config
+                .old_transactions_weight_percentage
+                .to_owned()
+                .unwrap_or_else(|| defaults.mining_old_transactions_weight_percentage()),
+            old_transactions_min_age: config
+                .old_transactions_min_age
+                .to_owned()
+                .unwrap_or_else(|| defaults.mining_old_transactions_min_age()),
+            reputation_alert_threshold: config
+                .reputation_alert_threshold
+                .to_owned()
+                .unwrap_or_else(|| defaults.mining_reputation_alert_threshold()),
+            reputation_alert_epochs: config
+                .reputation_alert_epochs
+                .to_owned()
+                .unwrap_or_else(|| defaults.mining_reputation_alert_epochs()),
+            transactions_pool_expiry: config
+                .transactions_pool_expiry
+                .to_owned()
+                .unwrap_or_else(|| defaults.mining_transactions_pool_expiry()),
+            transactions_pool_max_size: config
+                .transactions_pool_max_size
+                .to_owned()
+                .unwrap_or_else(|| defaults.mining_transactions_pool_max_size()),
+            data_request_max_commits_per_epoch: config
+                .data_request_max_commits_per_epoch
+                .to_owned()
+                .unwrap_or_else(|| defaults.mining_data_request_max_commits_per_epoch()),
+            watched_pkhs: config.watched_pkhs.clone(),
+        }
+    }
+}
+
+/// Webhook notifications configuration
+#[derive(PartialStruct, Debug, Clone, PartialEq)]
+#[partial_struct(derive(Deserialize, Default, Debug, Clone, PartialEq))]
+pub struct Webhooks {
+    /// List of URLs that will be notified of node events, along with the events each of them is
+    /// subscribed to
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub subscribers: Vec<WebhookSubscriber>,
+
+    /// Number of times a failed notification is retried before giving up
+    pub max_retries: u32,
+
+    /// Time to wait before retrying a failed notification
+    #[partial_struct(serde(
+        default,
+        deserialize_with = "from_secs",
+        rename = "retry_delay_seconds"
+    ))]
+    pub retry_delay: Duration,
+}
+
+/// URL to be notified of node events
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct WebhookSubscriber {
+    /// URL the JSON payloads are POSTed to
+    pub url: String,
+    /// Events this URL is subscribed to
+    pub events: Vec<WebhookEvent>,
+}
+
+/// Node events that can be notified to a webhook
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A new block has been consolidated
+    Block,
+    /// A data request has been resolved, that is, its tally has been consolidated
+    DataRequestResolved,
+    /// The chain state has been rolled back to the one in storage because of a fork
+    Rollback,
+    /// The reputation of the node is going to expire below the alert threshold
+    ReputationExpiration,
+    /// A superblock has been built and persisted at the end of its period
+    Superblock,
+}
+
+impl Webhooks {
+    pub fn from_partial(config: &PartialWebhooks, defaults: &dyn Defaults) -> Self {
+        Webhooks {
+            subscribers: config.subscribers.clone(),
+            max_retries: config
+                .max_retries
+                .to_owned()
+                .unwrap_or_else(|| defaults.webhooks_max_retries()),
+            retry_delay: config
+                .retry_delay
+                .to_owned()
+                .unwrap_or_else(|| defaults.webhooks_retry_delay()),
+        }
+    }
+}
+
+/// Actor mailboxes configuration
+#[derive(PartialStruct, Debug, Clone, PartialEq)]
+#[partial_struct(derive(Deserialize, Default, Debug, Clone, PartialEq))]
+pub struct Mailboxes {
+    /// Capacity of the mailbox of the ChainManager. Block candidates received from peers are
+    /// dropped while it is full
+    pub chain_manager: usize,
+
+    /// Capacity of the mailbox of the InventoryManager
+    pub inventory_manager: usize,
+}
+
+impl Mailboxes {
+    pub fn from_partial(config: &PartialMailboxes, defaults: &dyn Defaults) -> Self {
+        Mailboxes {
+            chain_manager: config
+                .chain_manager
+                .to_owned()
+                .unwrap_or_else(|| defaults.mailboxes_chain_manager()),
+            inventory_manager: config
+                .inventory_manager
+                .to_owned()
+                .unwrap_or_else(|| defaults.mailboxes_inventory_manager()),
         }
     }
 }
 
+/// Wallet policies on reusing an address that has not received funds yet
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressReusePolicy {
+    /// Hand out the same address again
+    Allow,
+    /// Hand out the same address again, warning the client about it
+    Warn,
+    /// Refuse to hand out the same address again, so every request gets a fresh address
+    Refuse,
+}
+
+impl Default for AddressReusePolicy {
+    fn default() -> Self {
+        AddressReusePolicy::Warn
+    }
+}
+
+/// Wallet policies on the address paid the change of the transactions created by the wallet
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAddressPolicy {
+    /// Pay the change to a fresh address of the internal keychain of the account
+    Fresh,
+    /// Pay the change back to the address of the first output spent by the transaction
+    Input,
+}
+
+impl Default for ChangeAddressPolicy {
+    fn default() -> Self {
+        ChangeAddressPolicy::Fresh
+    }
+}
+
 /// Wallet-specific configuration.
 #[derive(PartialStruct, Serialize, Debug, Clone, PartialEq)]
 #[partial_struct(derive(Deserialize, Default, Debug, Clone, PartialEq))]
@@ -504,6 +950,73 @@ pub struct Wallet {
     pub session_expires_in: u64,
     /// Duration in milliseconds after which outgoing request should timeout.
     pub requests_timeout: u64,
+    /// Path of the audit log file, where every state-changing request is recorded. The audit log
+    /// is disabled if not set.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub audit_log_path: Option<PathBuf>,
+    /// Password used to encrypt the audit log entries. Required if the audit log is enabled.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub audit_log_password: Option<ProtectedString>,
+    /// Size in bytes after which the audit log file is rotated.
+    pub audit_log_max_size: u64,
+    /// What to do when a client asks again for an address that has not received funds yet.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub address_reuse: AddressReusePolicy,
+    /// Address paid the change of the transactions created by the wallet, unless a request
+    /// chooses another one.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub change_address: ChangeAddressPolicy,
+    /// Maximum number of requests per second accepted from each connection. There is no limit if
+    /// set to 0.
+    pub session_requests_per_second: u32,
+    /// Maximum number of requests to a method that each connection can have in progress at the
+    /// same time, by method name. Methods not listed here have no limit.
+    pub method_concurrency: BTreeMap<String, u32>,
+    /// Tenants served by the wallet, by API key. When set, every request must carry the
+    /// `apiKey` of a tenant, and each tenant only sees its own wallets and sessions. The wallet
+    /// serves a single application without API keys if empty.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub tenants: BTreeMap<String, String>,
+    /// Maximum number of sessions each tenant can have open at the same time. There is no limit
+    /// if set to 0.
+    pub tenant_max_sessions: u32,
+    /// Maximum number of requests per second accepted from all the connections of each tenant.
+    /// There is no limit if set to 0.
+    pub tenant_requests_per_second: u32,
+    /// Seconds during which the response of a request sent with an idempotency key is returned
+    /// again for retries of the same request. Idempotency keys are ignored if set to 0.
+    pub idempotency_window_seconds: u64,
+    /// Seconds between the checks that the wallet still receives the blocks notified by the
+    /// node. The checks are disabled if set to 0.
+    pub node_heartbeat_seconds: u64,
+    /// Epochs the last block notified by the node can lag behind the beacon of the node before
+    /// the wallet subscribes again and notifies the sessions that it stalled.
+    pub node_stall_epochs: u32,
+    /// URL of the remote storage where the encrypted database writes of the wallets are
+    /// replicated, so they can be recovered on another machine. Replication is disabled if not
+    /// set.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub remote_storage_url: Option<String>,
+    /// Value of the `Authorization` header sent in the requests to the remote storage.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub remote_storage_authorization: Option<ProtectedString>,
+    /// Socket address where the timing metrics of the wallet are served to Prometheus. The
+    /// metrics endpoint is disabled if not set.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub metrics_addr: Option<SocketAddr>,
+    /// Developer tool: allow exporting the decrypted databases of the wallets with
+    /// `exportPlaintext`. Disabled by default.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub dev_plaintext_export: bool,
 }
 
 impl Wallet {
@@ -546,6 +1059,40 @@ impl Wallet {
                 .id_hash_function
                 .clone()
                 .unwrap_or_else(|| defaults.wallet_id_hash_function()),
+            audit_log_path: config.audit_log_path.clone(),
+            audit_log_password: config.audit_log_password.clone(),
+            audit_log_max_size: config
+                .audit_log_max_size
+                .unwrap_or_else(|| defaults.wallet_audit_log_max_size()),
+            address_reuse: config.address_reuse,
+            change_address: config.change_address,
+            session_requests_per_second: config
+                .session_requests_per_second
+                .unwrap_or_else(|| defaults.wallet_session_requests_per_second()),
+            method_concurrency: config
+                .method_concurrency
+                .clone()
+                .unwrap_or_else(|| defaults.wallet_method_concurrency()),
+            tenants: config.tenants.clone(),
+            tenant_max_sessions: config
+                .tenant_max_sessions
+                .unwrap_or_else(|| defaults.wallet_tenant_max_sessions()),
+            tenant_requests_per_second: config
+                .tenant_requests_per_second
+                .unwrap_or_else(|| defaults.wallet_tenant_requests_per_second()),
+            idempotency_window_seconds: config
+                .idempotency_window_seconds
+                .unwrap_or_else(|| defaults.wallet_idempotency_window_seconds()),
+            node_heartbeat_seconds: config
+                .node_heartbeat_seconds
+                .unwrap_or_else(|| defaults.wallet_node_heartbeat_seconds()),
+            node_stall_epochs: config
+                .node_stall_epochs
+                .unwrap_or_else(|| defaults.wallet_node_stall_epochs()),
+            remote_storage_url: config.remote_storage_url.clone(),
+            remote_storage_authorization: config.remote_storage_authorization.clone(),
+            metrics_addr: config.metrics_addr,
+            dev_plaintext_export: config.dev_plaintext_export,
         }
     }
 }
@@ -621,6 +1168,11 @@ mod tests {
         let config = Storage::from_partial(&partial_config, &Testnet1);
 
         assert_eq!(config.db_path.to_str(), Testnet1.storage_db_path().to_str());
+        assert_eq!(
+            config.identity_file.to_str(),
+            Some(".witnet-rust-testnet-1-identity")
+        );
+        assert_eq!(config.check_invariants, Testnet1.storage_check_invariants());
     }
 
     #[test]
@@ -628,11 +1180,16 @@ mod tests {
         let partial_config = PartialStorage {
             backend: StorageBackend::RocksDB,
             password: None,
+            passphrase_file: None,
             db_path: Some(PathBuf::from("other")),
+            identity_file: None,
+            check_invariants: Some(true),
         };
         let config = Storage::from_partial(&partial_config, &Testnet1);
 
         assert_eq!(config.db_path.to_str(), Some("other"));
+        assert_eq!(config.identity_file.to_str(), Some("other-identity"));
+        assert!(config.check_invariants);
     }
 
     #[test]
@@ -661,6 +1218,19 @@ mod tests {
             Testnet1.connections_handshake_timeout()
         );
         assert_eq!(config.blocks_timeout, Testnet1.connections_blocks_timeout());
+        assert_eq!(
+            config.sync_batch_retries,
+            Testnet1.connections_sync_batch_retries()
+        );
+        assert_eq!(config.ping_interval, Testnet1.connections_ping_interval());
+        assert_eq!(config.ping_timeout, Testnet1.connections_ping_timeout());
+        assert_eq!(
+            config.clock_tolerance,
+            Testnet1.connections_clock_tolerance()
+        );
+        assert!(!config.offline);
+        assert!(config.listeners.is_empty());
+        assert!(config.candidate_relay_peers.is_empty());
     }
 
     #[test]
@@ -676,8 +1246,25 @@ mod tests {
             discovery_peers_period: Some(Duration::from_secs(100)),
             handshake_timeout: Some(Duration::from_secs(3)),
             blocks_timeout: Some(5),
+            sync_batch_retries: Some(1),
             consensus_c: Some(51),
+            consensus_c_bootstrap: Some(30),
+            consensus_c_synchronizing: None,
+            consensus_c_synced: Some(80),
             bucketing_update_period: Some(200),
+            bucketing_aging_period: Some(3600),
+            feeler_peers_period: Some(Duration::from_secs(120)),
+            retry_backoff_base: Some(Duration::from_secs(15)),
+            retry_backoff_max: Some(Duration::from_secs(600)),
+            ping_interval: Some(Duration::from_secs(20)),
+            ping_timeout: Some(Duration::from_secs(10)),
+            clock_tolerance: Some(Duration::from_secs(5)),
+            offline: true,
+            listeners: vec![Listener {
+                addr,
+                inbound_limit: Some(2),
+            }],
+            candidate_relay_peers: vec![addr],
         };
         let config = Connections::from_partial(&partial_config, &Testnet1);
 
@@ -690,8 +1277,50 @@ mod tests {
         assert_eq!(config.discovery_peers_period, Duration::from_secs(100));
         assert_eq!(config.handshake_timeout, Duration::from_secs(3));
         assert_eq!(config.blocks_timeout, 5);
+        assert_eq!(config.sync_batch_retries, 1);
         assert_eq!(config.consensus_c, 51);
+        assert_eq!(config.consensus_c_bootstrap, 30);
+        assert_eq!(config.consensus_c_synchronizing, 51);
+        assert_eq!(config.consensus_c_synced, 80);
         assert_eq!(config.bucketing_update_period, 200);
+        assert_eq!(config.bucketing_aging_period, 3600);
+        assert_eq!(config.feeler_peers_period, Duration::from_secs(120));
+        assert_eq!(config.retry_backoff_base, Duration::from_secs(15));
+        assert_eq!(config.retry_backoff_max, Duration::from_secs(600));
+        assert_eq!(config.ping_interval, Duration::from_secs(20));
+        assert_eq!(config.ping_timeout, Duration::from_secs(10));
+        assert_eq!(config.clock_tolerance, Duration::from_secs(5));
+        assert!(config.offline);
+        assert_eq!(config.listeners[0].addr, addr);
+        assert_eq!(config.listeners[0].inbound_limit, Some(2));
+        assert_eq!(config.candidate_relay_peers, vec![addr]);
+    }
+
+    #[test]
+    fn test_connections_validate_consensus_thresholds() {
+        let mut config = Connections::from_partial(&PartialConnections::default(), &Testnet1);
+        assert!(config.validate().is_ok());
+
+        config.consensus_c_synced = 0;
+        assert!(config.validate().is_err());
+
+        config.consensus_c_synced = 100;
+        assert!(config.validate().is_ok());
+
+        config.consensus_c_bootstrap = 101;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_connections_validate_ping_durations() {
+        let mut config = Connections::from_partial(&PartialConnections::default(), &Testnet1);
+
+        config.ping_interval = Duration::from_secs(0);
+        assert!(config.validate().is_err());
+
+        config.ping_interval = Duration::from_secs(30);
+        config.ping_timeout = Duration::from_secs(0);
+        assert!(config.validate().is_err());
     }
 
     #[test]
@@ -708,10 +1337,17 @@ mod tests {
         let partial_config = PartialJsonRPC {
             enabled: None,
             server_address: Some(addr),
+            max_heavy_queries: Some(4),
+            max_queued_heavy_queries: None,
         };
         let config = JsonRPC::from_partial(&partial_config, &Testnet1);
 
         assert_eq!(config.server_address, addr);
+        assert_eq!(config.max_heavy_queries, 4);
+        assert_eq!(
+            config.max_queued_heavy_queries,
+            Testnet1.jsonrpc_max_queued_heavy_queries()
+        );
     }
 
     #[test]
@@ -753,6 +1389,7 @@ mod tests {
             Testnet3.connections_handshake_timeout()
         );
         assert_eq!(config.storage.db_path, Testnet3.storage_db_path());
+        assert!(!config.storage.check_invariants);
         assert_eq!(
             config.jsonrpc.server_address,
             Testnet3.jsonrpc_server_address()
@@ -769,5 +1406,62 @@ mod tests {
             config.connections.bucketing_update_period,
             Testnet3.connections_bucketing_update_period()
         );
+        assert_eq!(
+            config.connections.bucketing_aging_period,
+            Testnet3.connections_bucketing_aging_period()
+        );
+        assert_eq!(
+            config.connections.feeler_peers_period,
+            Testnet3.connections_feeler_peers_period()
+        );
+        assert_eq!(
+            config.connections.retry_backoff_base,
+            Testnet3.connections_retry_backoff_base()
+        );
+        assert_eq!(
+            config.connections.retry_backoff_max,
+            Testnet3.connections_retry_backoff_max()
+        );
+        assert_eq!(config.mining.enabled, Testnet3.mining_enabled());
+        assert_eq!(
+            config.mining.old_transactions_weight_percentage,
+            Testnet3.mining_old_transactions_weight_percentage()
+        );
+        assert_eq!(
+            config.mining.old_transactions_min_age,
+            Testnet3.mining_old_transactions_min_age()
+        );
+        assert_eq!(
+            config.mining.reputation_alert_threshold,
+            Testnet3.mining_reputation_alert_threshold()
+        );
+        assert_eq!(
+            config.mining.reputation_alert_epochs,
+            Testnet3.mining_reputation_alert_epochs()
+        );
+        assert_eq!(
+            config.mining.transactions_pool_expiry,
+            Testnet3.mining_transactions_pool_expiry()
+        );
+        assert_eq!(
+            config.mining.transactions_pool_max_size,
+            Testnet3.mining_transactions_pool_max_size()
+        );
+        assert_eq!(
+            config.mining.data_request_max_commits_per_epoch,
+            Testnet3.mining_data_request_max_commits_per_epoch()
+        );
+        assert!(config.mining.watched_pkhs.is_empty());
+        assert!(config.webhooks.subscribers.is_empty());
+        assert_eq!(config.webhooks.max_retries, Testnet3.webhooks_max_retries());
+        assert_eq!(config.webhooks.retry_delay, Testnet3.webhooks_retry_delay());
+        assert_eq!(
+            config.mailboxes.chain_manager,
+            Testnet3.mailboxes_chain_manager()
+        );
+        assert_eq!(
+            config.mailboxes.inventory_manager,
+            Testnet3.mailboxes_inventory_manager()
+        );
     }
 }
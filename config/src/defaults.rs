@@ -2,9 +2,9 @@
 //!
 //! This module contains per-environment default values for the Witnet
 //! protocol params.
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use witnet_crypto::hash::HashFunction;
@@ -24,6 +24,11 @@ pub trait Defaults {
         log::LevelFilter::Info
     }
 
+    /// Default directory for crash reports: the temporary directory of the system
+    fn log_crash_report_dir(&self) -> PathBuf {
+        std::env::temp_dir()
+    }
+
     /// Default server addr
     fn connections_server_addr(&self) -> SocketAddr;
 
@@ -45,6 +50,20 @@ pub trait Defaults {
     /// Default path for the database
     fn storage_db_path(&self) -> PathBuf;
 
+    /// Default path for the identity file: the path of the database with an `-identity` suffix
+    fn storage_identity_file(&self, db_path: &Path) -> PathBuf {
+        // `components` drops the trailing separator, if any
+        let mut path = db_path.components().as_path().as_os_str().to_owned();
+        path.push("-identity");
+
+        PathBuf::from(path)
+    }
+
+    /// Check the chain state invariants in release builds: `false`
+    fn storage_check_invariants(&self) -> bool {
+        false
+    }
+
     /// Default period for bootstrap peers
     fn connections_bootstrap_peers_period(&self) -> Duration {
         Duration::from_secs(5)
@@ -75,6 +94,41 @@ pub trait Defaults {
         300
     }
 
+    /// Period after which a peer that has not been seen is demoted or dropped: `604800` (1 week)
+    fn connections_bucketing_aging_period(&self) -> i64 {
+        604_800
+    }
+
+    /// Default period for testing the oldest tried peer and a random new peer
+    fn connections_feeler_peers_period(&self) -> Duration {
+        Duration::from_secs(120)
+    }
+
+    /// Default backoff after a failed outbound connection attempt
+    fn connections_retry_backoff_base(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    /// Default maximum backoff after failed outbound connection attempts: 1 hour
+    fn connections_retry_backoff_max(&self) -> Duration {
+        Duration::from_secs(3600)
+    }
+
+    /// Default period of the keepalive pings sent to every peer
+    fn connections_ping_interval(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    /// Default timeout for receiving the pong of a keepalive ping
+    fn connections_ping_timeout(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+
+    /// Default time that the epoch of a block candidate can start ahead of the local clock
+    fn connections_clock_tolerance(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+
     /// Timestamp at the start of epoch 0
     fn consensus_constants_checkpoint_zero_timestamp(&self) -> i64;
 
@@ -97,11 +151,79 @@ pub trait Defaults {
     /// Default JSON-RPC server addr
     fn jsonrpc_server_address(&self) -> SocketAddr;
 
+    /// Heavy JSON-RPC queries handled at the same time: `2`
+    fn jsonrpc_max_heavy_queries(&self) -> usize {
+        2
+    }
+
+    /// Heavy JSON-RPC queries waiting to be handled: `32`
+    fn jsonrpc_max_queued_heavy_queries(&self) -> usize {
+        32
+    }
+
     /// MiningManager, enabled by default
     fn mining_enabled(&self) -> bool {
         true
     }
 
+    /// Percentage of the block weight reserved for old transactions: `10`
+    fn mining_old_transactions_weight_percentage(&self) -> u8 {
+        10
+    }
+
+    /// Time after which a transaction in the pool is considered old: 1 hour
+    fn mining_old_transactions_min_age(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    /// Reputation below which the node warns that it is losing its eligibility: `1`, that is,
+    /// warn before losing all the reputation
+    fn mining_reputation_alert_threshold(&self) -> u32 {
+        1
+    }
+
+    /// Number of epochs ahead for which the expiration of the node reputation is checked: `960`,
+    /// that is, one day with 90-second epochs
+    fn mining_reputation_alert_epochs(&self) -> u32 {
+        960
+    }
+
+    /// Time after which a transaction that is not included in a block is dropped from the pool:
+    /// 1 day
+    fn mining_transactions_pool_expiry(&self) -> Duration {
+        Duration::from_secs(24 * 60 * 60)
+    }
+
+    /// Maximum number of value transfer transactions in the pool: `10000`
+    fn mining_transactions_pool_max_size(&self) -> usize {
+        10_000
+    }
+
+    /// Maximum number of data requests committed to in the same epoch: `0`, that is, no limit
+    fn mining_data_request_max_commits_per_epoch(&self) -> u16 {
+        0
+    }
+
+    /// Number of retries of a failed webhook notification: `3`
+    fn webhooks_max_retries(&self) -> u32 {
+        3
+    }
+
+    /// Time to wait before retrying a failed webhook notification: 10 seconds
+    fn webhooks_retry_delay(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+
+    /// Capacity of the mailbox of the ChainManager: `1024` messages
+    fn mailboxes_chain_manager(&self) -> usize {
+        1024
+    }
+
+    /// Capacity of the mailbox of the InventoryManager: `1024` messages
+    fn mailboxes_inventory_manager(&self) -> usize {
+        1024
+    }
+
     fn consensus_constants_max_block_weight(&self) -> u32 {
         // TODO: Replace  with real max_block_weight value used in mainnet
         10_000
@@ -113,6 +235,12 @@ pub trait Defaults {
         400
     }
 
+    /// Default number of times a batch of blocks is requested again before giving up on the
+    /// synchronization: `3`
+    fn connections_sync_batch_retries(&self) -> u32 {
+        3
+    }
+
     /// An identity is considered active if it participated in the witnessing protocol at least once in the last `activity_period` epochs
     fn consensus_constants_activity_period(&self) -> u32 {
         // 1000 epochs at 90 seconds/epoch = 2 days
@@ -183,6 +311,51 @@ pub trait Defaults {
         HashFunction::Sha256
     }
 
+    /// Size in bytes after which the wallet audit log is rotated
+    fn wallet_audit_log_max_size(&self) -> u64 {
+        10 * 1024 * 1024
+    }
+
+    /// Maximum number of requests per second accepted by the wallet from each connection
+    fn wallet_session_requests_per_second(&self) -> u32 {
+        50
+    }
+
+    /// Maximum number of concurrent requests of each connection to the expensive wallet methods
+    fn wallet_method_concurrency(&self) -> BTreeMap<String, u32> {
+        vec![("exportXprv", 1), ("runRadRequest", 1)]
+            .into_iter()
+            .map(|(method, limit)| (method.to_string(), limit))
+            .collect()
+    }
+
+    /// Maximum number of sessions each tenant of the wallet can have open at the same time
+    fn wallet_tenant_max_sessions(&self) -> u32 {
+        100
+    }
+
+    /// Maximum number of requests per second accepted from all the connections of each tenant
+    fn wallet_tenant_requests_per_second(&self) -> u32 {
+        200
+    }
+
+    /// Seconds during which the wallet remembers the responses of the requests sent with an
+    /// idempotency key
+    fn wallet_idempotency_window_seconds(&self) -> u64 {
+        600
+    }
+
+    /// Seconds between the checks that the wallet still receives the blocks of the node
+    fn wallet_node_heartbeat_seconds(&self) -> u64 {
+        60
+    }
+
+    /// Epochs the blocks notified to the wallet can lag behind the node before the subscription
+    /// is considered stale
+    fn wallet_node_stall_epochs(&self) -> u32 {
+        3
+    }
+
     fn rocksdb_create_if_missing(&self) -> bool {
         true
     }
@@ -229,4 +229,34 @@ enabled = false
         assert_eq!(empty_config.mining, PartialMining::default());
         assert_eq!(config_disabled.mining.enabled, Some(false),);
     }
+
+    #[test]
+    fn test_configure_webhooks() {
+        let empty_config = super::from_str("[webhooks]").unwrap();
+        let config = super::from_str(
+            r"
+[webhooks]
+max_retries = 5
+
+[[webhooks.subscribers]]
+url = 'http://127.0.0.1:8080/blocks'
+events = ['block', 'rollback', 'superblock']
+    ",
+        )
+        .unwrap();
+
+        assert_eq!(empty_config.webhooks, PartialWebhooks::default());
+        assert_eq!(config.webhooks.max_retries, Some(5));
+        assert_eq!(
+            config.webhooks.subscribers,
+            vec![WebhookSubscriber {
+                url: "http://127.0.0.1:8080/blocks".to_string(),
+                events: vec![
+                    WebhookEvent::Block,
+                    WebhookEvent::Rollback,
+                    WebhookEvent::Superblock
+                ],
+            }]
+        );
+    }
 }
@@ -65,6 +65,45 @@ impl Mnemonic {
     pub fn from_phrase_lang_ref(phrase: &str, language: Lang) -> Result<Mnemonic, Error> {
         bip39::Mnemonic::from_phrase(phrase, language.into()).map(Mnemonic)
     }
+
+    /// Get a mnemonic from a existing phrase, detecting the language of its words.
+    pub fn from_phrase_detect(phrase: ProtectedString) -> Result<Mnemonic, Error> {
+        match Self::detect_lang(phrase.as_ref()) {
+            Some((language, _)) => Self::from_phrase_lang(phrase, language),
+            None => Err(bip39::ErrorKind::InvalidWord.into()),
+        }
+    }
+
+    /// Detect the language of the words of a phrase.
+    ///
+    /// Returns the detected language and whether the checksum of the phrase is valid, or `None`
+    /// if the words do not belong to any of the supported wordlists.
+    ///
+    /// Some wordlists share words (e.g. Simplified and Traditional Chinese), so a language in
+    /// which the checksum is valid takes precedence.
+    pub fn detect_lang(phrase: &str) -> Option<(Lang, bool)> {
+        let mut detected = None;
+
+        for language in Lang::all() {
+            match bip39::Mnemonic::validate(phrase, (*language).into()) {
+                Ok(()) => return Some((*language, true)),
+                Err(e) => {
+                    if detected.is_none() {
+                        if let Some(bip39::ErrorKind::InvalidChecksum) = e.downcast_ref() {
+                            detected = Some((*language, false));
+                        }
+                    }
+                }
+            }
+        }
+
+        detected
+    }
+
+    /// Get the language of the mnemonic words
+    pub fn lang(&self) -> Lang {
+        self.0.language().into()
+    }
 }
 
 /// BIP39 Seed generated from a Mnemonic
@@ -109,16 +148,79 @@ pub enum Length {
 }
 
 /// The language in which Mnemonics are generated
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "camelCase")
+)]
 pub enum Lang {
     /// English language
     English,
+    /// Simplified Chinese language
+    ChineseSimplified,
+    /// Traditional Chinese language
+    ChineseTraditional,
+    /// French language
+    French,
+    /// Italian language
+    Italian,
+    /// Japanese language
+    Japanese,
+    /// Korean language
+    Korean,
+    /// Spanish language
+    Spanish,
+}
+
+impl Lang {
+    /// All the supported languages, in the order used for detecting the language of a phrase
+    pub fn all() -> &'static [Lang] {
+        &[
+            Lang::English,
+            Lang::Spanish,
+            Lang::French,
+            Lang::Italian,
+            Lang::Japanese,
+            Lang::Korean,
+            Lang::ChineseSimplified,
+            Lang::ChineseTraditional,
+        ]
+    }
+}
+
+impl Default for Lang {
+    fn default() -> Self {
+        Lang::English
+    }
 }
 
 impl Into<bip39::Language> for Lang {
     fn into(self) -> bip39::Language {
         match self {
             Lang::English => bip39::Language::English,
+            Lang::ChineseSimplified => bip39::Language::ChineseSimplified,
+            Lang::ChineseTraditional => bip39::Language::ChineseTraditional,
+            Lang::French => bip39::Language::French,
+            Lang::Italian => bip39::Language::Italian,
+            Lang::Japanese => bip39::Language::Japanese,
+            Lang::Korean => bip39::Language::Korean,
+            Lang::Spanish => bip39::Language::Spanish,
+        }
+    }
+}
+
+impl From<bip39::Language> for Lang {
+    fn from(language: bip39::Language) -> Self {
+        match language {
+            bip39::Language::English => Lang::English,
+            bip39::Language::ChineseSimplified => Lang::ChineseSimplified,
+            bip39::Language::ChineseTraditional => Lang::ChineseTraditional,
+            bip39::Language::French => Lang::French,
+            bip39::Language::Italian => Lang::Italian,
+            bip39::Language::Japanese => Lang::Japanese,
+            bip39::Language::Korean => Lang::Korean,
+            bip39::Language::Spanish => Lang::Spanish,
         }
     }
 }
@@ -164,10 +266,7 @@ impl MnemonicGen {
             Length::Words21 => bip39::MnemonicType::Words21,
             Length::Words24 => bip39::MnemonicType::Words24,
         };
-        let lang = match self.lang {
-            Lang::English => bip39::Language::English,
-        };
-        let mnemonic = bip39::Mnemonic::new(mnemonic_type, lang);
+        let mnemonic = bip39::Mnemonic::new(mnemonic_type, self.lang.into());
 
         Mnemonic(mnemonic)
     }
@@ -201,6 +300,56 @@ mod tests {
         assert_eq!(words.len(), 12);
     }
 
+    #[test]
+    fn test_generate_with_lang() {
+        let mnemonic = MnemonicGen::new().with_lang(Lang::Spanish).generate();
+
+        assert_eq!(mnemonic.lang(), Lang::Spanish);
+    }
+
+    #[test]
+    fn test_detect_lang() {
+        // Languages whose wordlists do not share words
+        let languages = [
+            Lang::English,
+            Lang::Spanish,
+            Lang::Italian,
+            Lang::Japanese,
+            Lang::Korean,
+        ];
+
+        for language in &languages {
+            let mnemonic = MnemonicGen::new().with_lang(*language).generate();
+            let detected = Mnemonic::detect_lang(mnemonic.words());
+
+            assert_eq!(detected, Some((*language, true)));
+        }
+    }
+
+    #[test]
+    fn test_detect_lang_invalid_checksum() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+
+        assert_eq!(Mnemonic::detect_lang(phrase), Some((Lang::English, false)));
+    }
+
+    #[test]
+    fn test_detect_lang_unknown_words() {
+        let phrase = "these are not the words you are looking for at all my friend ok";
+
+        assert_eq!(Mnemonic::detect_lang(phrase), None);
+    }
+
+    #[test]
+    fn test_from_phrase_detect() {
+        let mnemonic = MnemonicGen::new().with_lang(Lang::French).generate();
+        let phrase = mnemonic.words().into();
+        let imported = Mnemonic::from_phrase_detect(phrase).unwrap();
+
+        assert_eq!(imported.lang(), Lang::French);
+        assert_eq!(imported.words(), mnemonic.words());
+    }
+
     #[test]
     fn test_seed_as_ref() {
         let mnemonic = MnemonicGen::new().generate();
@@ -7,13 +7,14 @@ use witnet_util::timestamp::get_timestamp;
 
 use crate::{
     chain::{
-        Block, BlockHeader, BlockTransactions, CheckpointBeacon, InventoryEntry, KeyedSignature,
+        Block, BlockHeader, BlockTransactions, CheckpointBeacon, Hash, InventoryEntry,
+        KeyedSignature,
     },
     error::BuildersError,
     transaction::Transaction,
     types::{
         Address, Command, GetPeers, InventoryAnnouncement, InventoryRequest, IpAddress, LastBeacon,
-        Message, Peers, Ping, Pong, Verack, Version,
+        Message, NetworkId, Peers, Ping, Pong, Verack, Version,
     },
 };
 
@@ -21,7 +22,11 @@ use crate::{
 // PROTOCOL MESSAGES CONSTANTS
 ////////////////////////////////////////////////////////////////////////////////////////
 /// Protocol version (used in handshake)
-pub const PROTOCOL_VERSION: u32 = 0x0000_0001;
+pub const PROTOCOL_VERSION: u32 = 0x0000_0002;
+
+/// First protocol version whose nodes answer the keepalive pings. Older nodes do not know them,
+/// so they are not pinged
+pub const KEEPALIVE_PROTOCOL_VERSION: u32 = 0x0000_0002;
 
 /// Capabilities
 pub const CAPABILITIES: u64 = 0x0000_0000_0000_0001;
@@ -75,6 +80,7 @@ impl Message {
     /// Function to build Version messages
     pub fn build_version(
         magic: u16,
+        network_id: &NetworkId,
         sender_addr: SocketAddr,
         receiver_addr: SocketAddr,
         last_epoch: u32,
@@ -90,6 +96,10 @@ impl Message {
                 user_agent: USER_AGENT.to_string(),
                 last_epoch,
                 nonce: random_nonce(),
+                network: network_id.network.clone(),
+                genesis_hash: match network_id.genesis_hash {
+                    Hash::SHA256(hash) => hash.to_vec(),
+                },
             }),
         )
     }
@@ -6,6 +6,7 @@ use secp256k1::{
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp::Reverse,
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     convert::{TryFrom, TryInto},
     fmt,
@@ -27,10 +28,12 @@ use crate::{
         DataRequestError, EpochCalculationError, OutputPointerParseError, Secp256k1ConversionError,
     },
     proto::{schema::witnet, ProtobufConvert},
+    superblock::SuperblockState,
     transaction::{
         CommitTransaction, DRTransaction, DRTransactionBody, MintTransaction, RevealTransaction,
         TallyTransaction, Transaction, VTTransaction,
     },
+    upgrades::ProtocolUpgrades,
     vrf::BlockEligibilityClaim,
 };
 
@@ -71,6 +74,17 @@ impl Default for Environment {
     }
 }
 
+impl Environment {
+    /// Name of the network of the environment, as exchanged in the version handshake
+    pub fn network_name(&self) -> &'static str {
+        match self {
+            Environment::Mainnet => "mainnet",
+            Environment::Testnet1 => "testnet-1",
+            Environment::Testnet3 => "testnet-3",
+        }
+    }
+}
+
 /// Consensus-critical configuration
 #[derive(PartialStruct, Debug, Clone, PartialEq, Serialize, Deserialize, ProtobufConvert)]
 #[partial_struct(derive(Deserialize, Default, Debug, Clone, PartialEq))]
@@ -697,6 +711,20 @@ pub struct RADRetrieve {
     pub kind: RADType,
     pub url: String,
     pub script: Vec<u8>,
+    /// Sources tried in order when the retrieval from `url` fails, using the same kind and
+    /// script. The first one that succeeds is used
+    #[serde(default)]
+    pub fallback_urls: Vec<String>,
+}
+
+/// Maximum number of fallback sources of a retrieval
+pub const MAX_RETRIEVE_FALLBACK_URLS: usize = 4;
+
+impl RADRetrieve {
+    /// Sources of the retrieval, in the order they are tried
+    pub fn urls(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.url).chain(self.fallback_urls.iter())
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, ProtobufConvert, Hash, Default)]
@@ -727,17 +755,37 @@ pub struct RADDeliver {
     pub url: String,
 }
 
-type WeightedHash = (u64, Hash);
-type WeightedVTTransaction = (u64, VTTransaction);
+/// Key of the sorted index: (priority, reversed arrival order, hash).
+///
+/// Reversing the arrival order means that, when iterating the index from
+/// the highest priority down, transactions with the same priority are
+/// visited oldest first.
+type WeightedHash = (u64, Reverse<u64>, Hash);
+/// (priority, arrival order, arrival timestamp, fee, transaction)
+type WeightedVTTransaction = (u64, u64, i64, u64, VTTransaction);
+
+/// Maximum number of unconfirmed ancestors that a value transfer transaction can have in the pool
+pub const MAX_VT_UNCONFIRMED_ANCESTORS: usize = 25;
 
 /// A pool of validated transactions that supports constant access by
 /// [`Hash`](Hash) and iteration over the
-/// transactions sorted from by transactions with bigger fees to
-/// transactions with smaller fees.
+/// transactions sorted from by transactions with bigger fee per weight
+/// unit to transactions with smaller fee per weight unit.
+/// Transactions with the same fee per weight unit are sorted by arrival
+/// time, oldest first.
+///
+/// Value transfer transactions can spend the outputs of other transactions
+/// in the pool. The fee per weight unit of such a transaction is computed
+/// along with all its unconfirmed ancestors, as they have to be included
+/// in the same block.
 #[derive(Debug, Default, Clone)]
 pub struct TransactionsPool {
     vt_transactions: HashMap<Hash, WeightedVTTransaction>,
     sorted_index: BTreeSet<WeightedHash>,
+    // A map of `transaction_hash` to the hashes of the transactions in the pool spending its outputs
+    vt_spenders: HashMap<Hash, HashSet<Hash>>,
+    // Arrival counter used to break ties between transactions with the same priority
+    vt_arrivals: u64,
     // Currently transactions related with data requests don't use weight
     dr_transactions: HashMap<Hash, DRTransaction>,
     // A map of `data_request_hash` to a map of `commit_hash` to `CommitTransaction`
@@ -774,6 +822,8 @@ impl TransactionsPool {
             co_transactions: HashMap::with_capacity(capacity),
             re_transactions: HashMap::with_capacity(capacity),
             sorted_index: BTreeSet::new(),
+            vt_spenders: HashMap::new(),
+            vt_arrivals: 0,
         }
     }
 
@@ -833,6 +883,21 @@ impl TransactionsPool {
         self.vt_transactions.len()
     }
 
+    /// Returns the number of data request transactions in the pool.
+    pub fn dr_len(&self) -> usize {
+        self.dr_transactions.len()
+    }
+
+    /// Returns the number of commit transactions in the pool.
+    pub fn commit_len(&self) -> usize {
+        self.co_transactions.values().map(HashMap::len).sum()
+    }
+
+    /// Returns the number of reveal transactions in the pool.
+    pub fn reveal_len(&self) -> usize {
+        self.re_transactions.values().map(HashMap::len).sum()
+    }
+
     /// Clear commit transactions in TransactionsPool
     pub fn clear_commits(&mut self) {
         self.co_transactions.clear();
@@ -934,14 +999,191 @@ impl TransactionsPool {
     /// assert!(!pool.vt_contains(&transaction.hash()));
     /// ```
     pub fn vt_remove(&mut self, key: &Hash) -> Option<VTTransaction> {
+        let (priority, arrival, _, _, transaction) = self.vt_transactions.remove(key)?;
+        self.sorted_index
+            .remove(&(priority, Reverse(arrival), *key));
+
+        for input in &transaction.body.inputs {
+            let parent = input.output_pointer().transaction_id;
+            if let Some(spenders) = self.vt_spenders.get_mut(&parent) {
+                spenders.remove(key);
+                if spenders.is_empty() {
+                    self.vt_spenders.remove(&parent);
+                }
+            }
+        }
+
+        // The descendants of the transaction no longer need to pay for it
+        let descendants = self.vt_descendants(key);
+        self.vt_spenders.remove(key);
+        for descendant in descendants {
+            self.vt_update_priority(&descendant);
+        }
+
+        Some(transaction)
+    }
+
+    /// Removes a value transfer transaction along with all the transactions
+    /// in the pool spending its outputs, directly or indirectly, as they
+    /// can no longer be included in a block. Returns the hashes of the
+    /// removed transactions.
+    ///
+    /// # Examples:
+    /// ```
+    /// # use witnet_data_structures::chain::{TransactionsPool, Hash, Hashable, OutputPointer, ValueTransferOutput};
+    /// # use witnet_data_structures::transaction::{Transaction, VTTransaction, VTTransactionBody};
+    /// # use witnet_data_structures::chain::Input;
+    /// let mut pool = TransactionsPool::new();
+    /// let parent = VTTransaction::new(VTTransactionBody::new(vec![], vec![ValueTransferOutput::default()]), vec![]);
+    /// let child = VTTransaction::new(VTTransactionBody::new(vec![Input::new(OutputPointer {
+    ///     transaction_id: parent.hash(),
+    ///     output_index: 0,
+    /// })], vec![]), vec![]);
+    /// pool.insert(Transaction::ValueTransfer(parent.clone()));
+    /// pool.insert(Transaction::ValueTransfer(child.clone()));
+    ///
+    /// assert_eq!(pool.vt_remove_with_descendants(&parent.hash()), vec![parent.hash(), child.hash()]);
+    /// assert!(pool.is_empty());
+    /// ```
+    pub fn vt_remove_with_descendants(&mut self, key: &Hash) -> Vec<Hash> {
+        if !self.vt_transactions.contains_key(key) {
+            return vec![];
+        }
+
+        let mut removed = vec![*key];
+        removed.extend(self.vt_descendants(key));
+        // Remove the descendants first so their priorities are not updated in vain
+        for hash in removed.iter().rev() {
+            self.vt_remove(hash);
+        }
+
+        removed
+    }
+
+    /// Returns the hashes of the transactions in the pool whose outputs
+    /// are spent by `vt_tx`, directly or indirectly, sorted so that every
+    /// transaction comes after the ones whose outputs it spends.
+    pub fn vt_unconfirmed_ancestors(&self, vt_tx: &VTTransaction) -> Vec<Hash> {
+        let mut ancestors = vec![];
+        let mut visited = HashSet::new();
+        for input in &vt_tx.body.inputs {
+            self.visit_vt_ancestors(
+                input.output_pointer().transaction_id,
+                &mut visited,
+                &mut ancestors,
+            );
+        }
+
+        ancestors
+    }
+
+    fn visit_vt_ancestors(
+        &self,
+        hash: Hash,
+        visited: &mut HashSet<Hash>,
+        ancestors: &mut Vec<Hash>,
+    ) {
+        if let Some((_, _, _, _, vt_tx)) = self.vt_transactions.get(&hash) {
+            if visited.insert(hash) {
+                for input in &vt_tx.body.inputs {
+                    self.visit_vt_ancestors(
+                        input.output_pointer().transaction_id,
+                        visited,
+                        ancestors,
+                    );
+                }
+                ancestors.push(hash);
+            }
+        }
+    }
+
+    /// Returns the hashes of the transactions in the pool spending the
+    /// outputs of the transaction identified by `key`, directly or
+    /// indirectly, sorted so that every transaction comes after the ones
+    /// whose outputs it spends.
+    fn vt_descendants(&self, key: &Hash) -> Vec<Hash> {
+        let mut descendants: Vec<Hash> = vec![];
+        let mut visited = HashSet::new();
+        let mut pending = vec![*key];
+        while let Some(hash) = pending.pop() {
+            for spender in self.vt_spenders.get(&hash).into_iter().flatten() {
+                if visited.insert(*spender) {
+                    descendants.push(*spender);
+                    pending.push(*spender);
+                }
+            }
+        }
+
+        // A descendant spending the outputs of several others must come after all of them
+        descendants.sort_by_key(|hash| {
+            self.vt_transactions
+                .get(hash)
+                .map(|(_, _, _, _, vt_tx)| self.vt_unconfirmed_ancestors(vt_tx).len())
+        });
+
+        descendants
+    }
+
+    /// Fee per weight unit (scaled by 1000 to keep precision) of a value
+    /// transfer transaction along with its unconfirmed ancestors.
+    fn vt_ancestors_priority(&self, vt_tx: &VTTransaction, fee: u64) -> u64 {
+        let (fee, weight) = self
+            .vt_unconfirmed_ancestors(vt_tx)
+            .iter()
+            .filter_map(|hash| self.vt_transactions.get(hash))
+            .fold(
+                (fee, u64::from(vt_tx.size())),
+                |(fee, weight), (_, _, _, ancestor_fee, ancestor)| {
+                    (
+                        fee.saturating_add(*ancestor_fee),
+                        weight + u64::from(ancestor.size()),
+                    )
+                },
+            );
+
+        fee.saturating_mul(1000) / weight.max(1)
+    }
+
+    fn vt_update_priority(&mut self, key: &Hash) {
+        let new_priority = match self.vt_transactions.get(key) {
+            Some((_, _, _, fee, vt_tx)) => self.vt_ancestors_priority(vt_tx, *fee),
+            None => return,
+        };
+        if let Some((priority, arrival, _, _, _)) = self.vt_transactions.get_mut(key) {
+            self.sorted_index
+                .remove(&(*priority, Reverse(*arrival), *key));
+            self.sorted_index
+                .insert((new_priority, Reverse(*arrival), *key));
+            *priority = new_priority;
+        }
+    }
+
+    /// Returns the output of a value transfer transaction in the pool,
+    /// which can be spent by other transactions before it is included in
+    /// a block.
+    pub fn vt_output(&self, output_pointer: &OutputPointer) -> Option<&ValueTransferOutput> {
         self.vt_transactions
-            .remove(key)
-            .map(|(weight, transaction)| {
-                self.sorted_index.remove(&(weight, *key));
-                transaction
+            .get(&output_pointer.transaction_id)
+            .and_then(|(_, _, _, _, vt_tx)| {
+                vt_tx.body.outputs.get(output_pointer.output_index as usize)
             })
     }
 
+    /// Returns the value transfer transaction identified by `key` preceded
+    /// by all its unconfirmed ancestors, in the order they must be included
+    /// in a block.
+    pub fn vt_package(&self, key: &Hash) -> Vec<&VTTransaction> {
+        match self.vt_get(key) {
+            Some(vt_tx) => self
+                .vt_unconfirmed_ancestors(vt_tx)
+                .iter()
+                .filter_map(|hash| self.vt_get(hash))
+                .chain(std::iter::once(vt_tx))
+                .collect(),
+            None => vec![],
+        }
+    }
+
     /// Returns an `Option` with the data request transaction for the specified hash or `None` if not exist.
     ///
     /// The `key` may be any borrowed form of the hash, but `Hash` and
@@ -1022,6 +1264,9 @@ impl TransactionsPool {
 
     /// Insert a transaction identified by `key` into the pool.
     ///
+    /// Value transfer transactions inserted this way have no fee and
+    /// no arrival timestamp, see [`insert_with_fee`](#method.insert_with_fee).
+    ///
     /// # Examples:
     ///
     /// ```
@@ -1034,13 +1279,50 @@ impl TransactionsPool {
     /// assert!(!pool.is_empty());
     /// ```
     pub fn insert(&mut self, transaction: Transaction) {
-        let weight = 0; // TODO: weight = transaction-fee / transaction-weight
+        self.insert_with_fee(transaction, 0, 0)
+    }
+
+    /// Insert a transaction identified by `key` into the pool, along
+    /// with its fee and the timestamp at which it was received.
+    ///
+    /// Value transfer transactions are prioritized by their fee per
+    /// weight unit (scaled by 1000 to keep precision), and
+    /// transactions with the same priority are sorted by arrival order.
+    /// Inserting a value transfer transaction that is already in the pool
+    /// has no effect, so it keeps its original arrival order.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// # use witnet_data_structures::chain::{TransactionsPool, Hash};
+    /// # use witnet_data_structures::transaction::{Transaction, VTTransaction};
+    /// let mut pool = TransactionsPool::new();
+    /// let transaction = Transaction::ValueTransfer(VTTransaction::default());
+    /// pool.insert_with_fee(transaction, 10, 1_000);
+    ///
+    /// assert_eq!(pool.vt_len(), 1);
+    /// ```
+    pub fn insert_with_fee(&mut self, transaction: Transaction, fee: u64, timestamp: i64) {
         let key = transaction.hash();
 
         match transaction {
             Transaction::ValueTransfer(vt_tx) => {
-                self.vt_transactions.insert(key, (weight, vt_tx));
-                self.sorted_index.insert((weight, key));
+                if self.vt_transactions.contains_key(&key) {
+                    return;
+                }
+                let priority = self.vt_ancestors_priority(&vt_tx, fee);
+                let arrival = self.vt_arrivals;
+                self.vt_arrivals += 1;
+
+                for input in &vt_tx.body.inputs {
+                    let parent = input.output_pointer().transaction_id;
+                    if self.vt_transactions.contains_key(&parent) {
+                        self.vt_spenders.entry(parent).or_default().insert(key);
+                    }
+                }
+                self.vt_transactions
+                    .insert(key, (priority, arrival, timestamp, fee, vt_tx));
+                self.sorted_index.insert((priority, Reverse(arrival), key));
             }
             Transaction::DataRequest(dr_tx) => {
                 self.dr_transactions.insert(key, dr_tx);
@@ -1074,8 +1356,9 @@ impl TransactionsPool {
     }
 
     /// An iterator visiting all the value transfer transactions
-    /// in the pool in descending-fee order, that is, transactions
-    /// with bigger fees come first.
+    /// in the pool in descending priority order, that is, transactions
+    /// with bigger fees per weight unit come first. Transactions with
+    /// the same priority are visited in arrival order, oldest first.
     ///
     /// Examples:
     ///
@@ -1099,7 +1382,46 @@ impl TransactionsPool {
         self.sorted_index
             .iter()
             .rev()
-            .filter_map(move |(_, h)| self.vt_transactions.get(h).map(|(_, t)| t))
+            .filter_map(move |(_, _, h)| self.vt_transactions.get(h).map(|(_, _, _, _, t)| t))
+    }
+
+    /// Returns the value transfer transactions that arrived to the pool
+    /// at or before `timestamp`, sorted by arrival order, oldest first.
+    ///
+    /// Transactions inserted without an arrival timestamp are considered
+    /// to have arrived at timestamp 0.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use witnet_data_structures::chain::{TransactionsPool, Hash, ValueTransferOutput};
+    /// # use witnet_data_structures::transaction::{Transaction, VTTransaction, VTTransactionBody};
+    /// let mut pool = TransactionsPool::new();
+    ///
+    /// let transaction1 = VTTransaction::default();
+    /// let transaction2 = VTTransaction::new(VTTransactionBody::new(vec![], vec![ValueTransferOutput {
+    /// value:3,
+    /// ..ValueTransferOutput::default()
+    /// }]),
+    /// vec![]);
+    ///
+    /// pool.insert_with_fee(Transaction::ValueTransfer(transaction1.clone()), 0, 100);
+    /// pool.insert_with_fee(Transaction::ValueTransfer(transaction2), 0, 200);
+    ///
+    /// assert_eq!(pool.vt_arrived_before(150), vec![&transaction1]);
+    /// ```
+    pub fn vt_arrived_before(&self, timestamp: i64) -> Vec<&VTTransaction> {
+        let mut transactions: Vec<_> = self
+            .vt_transactions
+            .values()
+            .filter(|(_, _, arrival_timestamp, _, _)| *arrival_timestamp <= timestamp)
+            .collect();
+        transactions.sort_by_key(|(_, arrival, _, _, _)| *arrival);
+
+        transactions
+            .into_iter()
+            .map(|(_, _, _, _, transaction)| transaction)
+            .collect()
     }
 
     /// An iterator visiting all the data request transactions
@@ -1108,6 +1430,22 @@ impl TransactionsPool {
         self.dr_transactions.values()
     }
 
+    /// An iterator visiting all the commit transactions
+    /// in the pool
+    pub fn co_iter(&self) -> impl Iterator<Item = &CommitTransaction> {
+        self.co_transactions
+            .values()
+            .flat_map(|commits| commits.values())
+    }
+
+    /// An iterator visiting all the reveal transactions
+    /// in the pool
+    pub fn re_iter(&self) -> impl Iterator<Item = &RevealTransaction> {
+        self.re_transactions
+            .values()
+            .flat_map(|reveals| reveals.values())
+    }
+
     /// Returns a reference to the value corresponding to the key.
     ///
     /// Examples:
@@ -1129,13 +1467,14 @@ impl TransactionsPool {
     pub fn vt_get(&self, key: &Hash) -> Option<&VTTransaction> {
         self.vt_transactions
             .get(key)
-            .map(|(_, transaction)| transaction)
+            .map(|(_, _, _, _, transaction)| transaction)
     }
 
     /// Retains only the elements specified by the predicate.
     ///
     /// In other words, remove all transactions such that
-    /// `f(&Hash, &Transaction)` returns `false`.
+    /// `f(&Hash, &Transaction)` returns `false`, along with the
+    /// transactions in the pool spending their outputs.
     ///
     /// # Examples
     ///
@@ -1163,20 +1502,118 @@ impl TransactionsPool {
     where
         F: FnMut(&VTTransaction) -> bool,
     {
-        let TransactionsPool {
-            ref mut vt_transactions,
-            ref mut sorted_index,
-            ..
-        } = *self;
-
-        vt_transactions.retain(|hash, (weight, vt_transaction)| {
-            let retain = f(vt_transaction);
-            if !retain {
-                sorted_index.remove(&(*weight, *hash));
+        let removed: Vec<Hash> = self
+            .vt_transactions
+            .iter()
+            .filter(|(_, (_, _, _, _, vt_transaction))| !f(vt_transaction))
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        for hash in &removed {
+            self.vt_remove_with_descendants(hash);
+        }
+    }
+
+    /// Remove the value transfer and data request transactions that spend any of the given
+    /// outputs, because they conflict with transactions already included in a block, along with
+    /// the value transfer transactions spending their outputs. Returns the hashes of the removed
+    /// transactions.
+    pub fn remove_spending(&mut self, spent: &HashSet<OutputPointer>) -> Vec<Hash> {
+        let spends = |inputs: &[Input]| {
+            inputs
+                .iter()
+                .any(|input| spent.contains(input.output_pointer()))
+        };
+        let conflicting_vt: Vec<Hash> = self
+            .vt_transactions
+            .iter()
+            .filter(|(_, (_, _, _, _, vt_tx))| spends(&vt_tx.body.inputs))
+            .map(|(hash, _)| *hash)
+            .collect();
+        let conflicting_dr: Vec<Hash> = self
+            .dr_transactions
+            .iter()
+            .filter(|(_, dr_tx)| spends(&dr_tx.body.inputs))
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        let mut removed = vec![];
+        for hash in &conflicting_vt {
+            removed.extend(self.vt_remove_with_descendants(hash));
+        }
+        for hash in conflicting_dr {
+            self.dr_remove(&hash);
+            removed.push(hash);
+        }
+
+        removed
+    }
+
+    /// Keep only the transactions with the given hashes, along with the unconfirmed ancestors of
+    /// the value transfer transactions kept, as they have to be included in the same block.
+    pub fn retain_hashes(&mut self, hashes: &HashSet<Hash>) {
+        let mut kept = hashes.clone();
+        for hash in hashes {
+            if let Some(vt_tx) = self.vt_get(hash) {
+                kept.extend(self.vt_unconfirmed_ancestors(vt_tx));
             }
+        }
 
-            retain
-        });
+        self.vt_retain(|vt_tx| kept.contains(&vt_tx.hash()));
+        self.dr_transactions.retain(|hash, _| kept.contains(hash));
+        for commits in self.co_transactions.values_mut() {
+            commits.retain(|_, co_tx| kept.contains(&co_tx.hash()));
+        }
+        self.co_transactions
+            .retain(|_, commits| !commits.is_empty());
+        for reveals in self.re_transactions.values_mut() {
+            reveals.retain(|_, re_tx| kept.contains(&re_tx.hash()));
+        }
+        self.re_transactions
+            .retain(|_, reveals| !reveals.is_empty());
+    }
+
+    /// Remove the value transfer transactions that arrived before the given timestamp, along with
+    /// the transactions spending their outputs, returning their hashes.
+    pub fn vt_remove_arrived_before(&mut self, timestamp: i64) -> Vec<Hash> {
+        let expired: Vec<Hash> = self
+            .vt_transactions
+            .iter()
+            .filter(|(_, (_, _, arrival_timestamp, _, _))| *arrival_timestamp < timestamp)
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        let mut removed = vec![];
+        for hash in &expired {
+            removed.extend(self.vt_remove_with_descendants(hash));
+        }
+
+        removed
+    }
+
+    /// Remove the value transfer transactions with the lowest priority until there are at most
+    /// `max_len` of them, returning the hashes of the removed transactions.
+    ///
+    /// Only the transactions whose outputs are not spent by other transactions in the pool are
+    /// removed, so a transaction is not dropped while a descendant paying for it is kept.
+    pub fn vt_truncate(&mut self, max_len: usize) -> Vec<Hash> {
+        let mut removed = vec![];
+        while self.vt_len() > max_len {
+            let lowest = self
+                .sorted_index
+                .iter()
+                .map(|(_, _, hash)| *hash)
+                .find(|hash| !self.vt_spenders.contains_key(hash));
+            match lowest {
+                Some(hash) => {
+                    self.vt_remove(&hash);
+                    removed.push(hash);
+                }
+                None => break,
+            }
+        }
+
+        removed
     }
 }
 
@@ -1437,6 +1874,41 @@ impl DataRequestState {
 
         self.stage != old_stage
     }
+
+    /// Get a summary of the live state of this data request at the given epoch
+    pub fn summary(&self, current_epoch: Epoch) -> DataRequestStateSummary {
+        DataRequestStateSummary {
+            stage: self.stage,
+            epoch: self.epoch,
+            time_locked: current_epoch < self.epoch,
+            commits: self.info.commits.len(),
+            reveals: self.info.reveals.len(),
+            witnesses: self.data_request.witnesses,
+            backup_witnesses: self.data_request.backup_witnesses,
+            seconds_to_next_epoch: None,
+        }
+    }
+}
+
+/// Summary of the live state of a data request in the data request pool
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DataRequestStateSummary {
+    /// Current stage of this data request
+    pub stage: DataRequestStage,
+    /// The epoch on which this data request has been or will be unlocked
+    pub epoch: Epoch,
+    /// Whether the data request is still waiting for its time lock
+    pub time_locked: bool,
+    /// Number of commitments already included in the chain
+    pub commits: usize,
+    /// Number of reveals already included in the chain
+    pub reveals: usize,
+    /// Number of witnesses required by the data request
+    pub witnesses: u16,
+    /// Number of backup witnesses of the data request
+    pub backup_witnesses: u16,
+    /// Seconds remaining until the next epoch, when the stage may advance
+    pub seconds_to_next_epoch: Option<i64>,
 }
 
 /// Data request current stage
@@ -1469,6 +1941,48 @@ pub struct ChainState {
     pub own_utxos: HashSet<OutputPointer>,
     /// Reputation engine
     pub reputation_engine: Option<ReputationEngine>,
+    /// Signaling state of the protocol upgrades
+    pub protocol_upgrades: ProtocolUpgrades,
+    /// Blocks of the superblock period in progress
+    pub superblock_state: SuperblockState,
+    /// Unspent outputs of the identities watched by this node, besides its own
+    pub watched_utxos: HashMap<PublicKeyHash, HashSet<OutputPointer>>,
+}
+
+/// Layout of the `ChainState` persisted by the versions of the node that did not track the
+/// signaling of protocol upgrades, the superblocks nor the outputs of watched identities.
+///
+/// Bincode does not support missing fields, so a chain state that cannot be decoded with the
+/// current layout is decoded with this one and converted with `ChainState::from`.
+#[derive(Debug, Deserialize)]
+pub struct LegacyChainState {
+    pub chain_info: Option<ChainInfo>,
+    pub unspent_outputs_pool: UnspentOutputsPool,
+    pub data_request_pool: DataRequestPool,
+    pub block_chain: Blockchain,
+    pub own_utxos: HashSet<OutputPointer>,
+    pub reputation_engine: Option<ReputationEngine>,
+}
+
+impl From<LegacyChainState> for ChainState {
+    fn from(legacy: LegacyChainState) -> Self {
+        let last_epoch = legacy.block_chain.keys().next_back().cloned();
+
+        ChainState {
+            chain_info: legacy.chain_info,
+            unspent_outputs_pool: legacy.unspent_outputs_pool,
+            data_request_pool: legacy.data_request_pool,
+            block_chain: legacy.block_chain,
+            own_utxos: legacy.own_utxos,
+            reputation_engine: legacy.reputation_engine,
+            // no upgrade was signaled before the node tracked the signals
+            protocol_upgrades: ProtocolUpgrades::default(),
+            superblock_state: last_epoch.map(SuperblockState::resume).unwrap_or_default(),
+            // the identities watched in the configuration are looked up in the unspent outputs
+            // pool when the node starts
+            watched_utxos: HashMap::default(),
+        }
+    }
 }
 
 impl ChainState {
@@ -1501,6 +2015,29 @@ impl ChainState {
 
         Ok(v)
     }
+
+    /// Start tracking the unspent outputs of an identity, looking for the ones it already has in
+    /// the unspent outputs pool. Returns `false` if the identity was already watched.
+    pub fn watch_pkh(&mut self, pkh: PublicKeyHash) -> bool {
+        if self.watched_utxos.contains_key(&pkh) {
+            return false;
+        }
+
+        let utxos = self
+            .unspent_outputs_pool
+            .iter()
+            .filter(|(_, output)| output.pkh == pkh)
+            .map(|(output_pointer, _)| output_pointer.clone())
+            .collect();
+        self.watched_utxos.insert(pkh, utxos);
+
+        true
+    }
+
+    /// Stop tracking the unspent outputs of an identity. Returns `false` if it was not watched.
+    pub fn unwatch_pkh(&mut self, pkh: &PublicKeyHash) -> bool {
+        self.watched_utxos.remove(pkh).is_some()
+    }
 }
 
 /// State related to the Reputation Engine
@@ -1526,6 +2063,17 @@ impl ReputationEngine {
             ars: ActiveReputationSet::new(activity_period),
         }
     }
+
+    /// Reputation of an identity that will expire at each alpha, sorted by alpha.
+    pub fn expirations(&self, pkh: &PublicKeyHash) -> Vec<(Alpha, Reputation)> {
+        self.trs
+            .queue()
+            .filter_map(|(alpha, mut diff)| {
+                diff.find(|(id, _)| *id == pkh)
+                    .map(|(_, reputation)| (*alpha, *reputation))
+            })
+            .collect()
+    }
 }
 
 /// Witnessing Acts Counter
@@ -1916,4 +2464,254 @@ mod tests {
             .unwrap();
         assert!(a < b);
     }
+
+    #[test]
+    fn transactions_pool_priority_and_arrival_order() {
+        use crate::transaction::VTTransactionBody;
+
+        let vt = |value| {
+            VTTransaction::new(
+                VTTransactionBody::new(
+                    vec![],
+                    vec![ValueTransferOutput {
+                        value,
+                        ..ValueTransferOutput::default()
+                    }],
+                ),
+                vec![],
+            )
+        };
+        let (t1, t2, t3, t4) = (vt(1), vt(2), vt(3), vt(4));
+        let fee = u64::from(t1.size());
+
+        let mut pool = TransactionsPool::new();
+        pool.insert_with_fee(Transaction::ValueTransfer(t1.clone()), 0, 10);
+        pool.insert_with_fee(Transaction::ValueTransfer(t2.clone()), fee, 20);
+        pool.insert_with_fee(Transaction::ValueTransfer(t3.clone()), 0, 30);
+        pool.insert_with_fee(Transaction::ValueTransfer(t4.clone()), fee, 40);
+        // Inserting again must not change the arrival order
+        pool.insert_with_fee(Transaction::ValueTransfer(t2.clone()), fee, 50);
+
+        // Higher fee per weight unit first, then oldest first
+        let sorted: Vec<_> = pool.vt_iter().cloned().collect();
+        assert_eq!(sorted, vec![t2.clone(), t4, t1.clone(), t3.clone()]);
+
+        let old: Vec<_> = pool.vt_arrived_before(30).into_iter().cloned().collect();
+        assert_eq!(old, vec![t1, t2.clone(), t3]);
+
+        pool.vt_remove(&t2.hash());
+        assert_eq!(pool.vt_iter().count(), 3);
+        assert_eq!(pool.sorted_index.len(), 3);
+    }
+
+    #[test]
+    fn transactions_pool_evictions() {
+        use crate::transaction::VTTransactionBody;
+
+        let vt = |value, inputs| {
+            VTTransaction::new(
+                VTTransactionBody::new(
+                    inputs,
+                    vec![ValueTransferOutput {
+                        value,
+                        ..ValueTransferOutput::default()
+                    }],
+                ),
+                vec![],
+            )
+        };
+        let spent = OutputPointer {
+            transaction_id: Hash::SHA256([1; 32]),
+            output_index: 0,
+        };
+        let t1 = vt(1, vec![Input::new(spent.clone())]);
+        let (t2, t3) = (vt(2, vec![]), vt(3, vec![]));
+        let fee = u64::from(t2.size());
+
+        let mut pool = TransactionsPool::new();
+        pool.insert_with_fee(Transaction::ValueTransfer(t1.clone()), fee, 10);
+        pool.insert_with_fee(Transaction::ValueTransfer(t2.clone()), 0, 20);
+        pool.insert_with_fee(Transaction::ValueTransfer(t3.clone()), fee, 30);
+
+        // The lowest fee is dropped first
+        assert_eq!(pool.vt_truncate(2), vec![t2.hash()]);
+        assert_eq!(pool.vt_truncate(2), vec![]);
+
+        let spent: HashSet<_> = vec![spent].into_iter().collect();
+        assert_eq!(pool.remove_spending(&spent), vec![t1.hash()]);
+
+        assert_eq!(pool.vt_remove_arrived_before(30), vec![]);
+        assert_eq!(pool.vt_remove_arrived_before(31), vec![t3.hash()]);
+        assert_eq!(pool.vt_len(), 0);
+        assert!(pool.sorted_index.is_empty());
+    }
+
+    #[test]
+    fn transactions_pool_chained_transactions() {
+        use crate::transaction::VTTransactionBody;
+
+        let vt = |value, inputs| {
+            VTTransaction::new(
+                VTTransactionBody::new(
+                    inputs,
+                    vec![ValueTransferOutput {
+                        value,
+                        ..ValueTransferOutput::default()
+                    }],
+                ),
+                vec![],
+            )
+        };
+        let spent = OutputPointer {
+            transaction_id: Hash::SHA256([1; 32]),
+            output_index: 0,
+        };
+        let output_of = |vt_tx: &VTTransaction| {
+            Input::new(OutputPointer {
+                transaction_id: vt_tx.hash(),
+                output_index: 0,
+            })
+        };
+        let parent = vt(1, vec![Input::new(spent.clone())]);
+        let child = vt(2, vec![output_of(&parent)]);
+        let grandchild = vt(3, vec![output_of(&child)]);
+        let other = vt(4, vec![]);
+        let fee = u64::from(parent.size());
+
+        let mut pool = TransactionsPool::new();
+        pool.insert_with_fee(Transaction::ValueTransfer(parent.clone()), 0, 10);
+        pool.insert_with_fee(
+            Transaction::ValueTransfer(other.clone()),
+            u64::from(other.size()),
+            20,
+        );
+        pool.insert_with_fee(Transaction::ValueTransfer(child.clone()), 4 * fee, 30);
+        pool.insert_with_fee(Transaction::ValueTransfer(grandchild.clone()), 0, 40);
+
+        assert_eq!(
+            pool.vt_output(output_of(&parent).output_pointer()),
+            Some(&parent.body.outputs[0])
+        );
+        assert_eq!(
+            pool.vt_unconfirmed_ancestors(&grandchild),
+            vec![parent.hash(), child.hash()]
+        );
+        assert_eq!(
+            pool.vt_package(&grandchild.hash()),
+            vec![&parent, &child, &grandchild]
+        );
+
+        // The child pays for its parent, and the grandchild for both of them
+        let sorted: Vec<_> = pool.vt_iter().cloned().collect();
+        assert_eq!(
+            sorted,
+            vec![
+                child.clone(),
+                grandchild.clone(),
+                other.clone(),
+                parent.clone()
+            ]
+        );
+
+        // The parent is not dropped while its descendants are in the pool
+        assert_eq!(pool.vt_truncate(2), vec![other.hash(), grandchild.hash()]);
+        assert_eq!(pool.vt_package(&child.hash()), vec![&parent, &child]);
+
+        // Once the parent is included in a block, the child only pays for itself
+        pool.vt_remove(&parent.hash());
+        assert_eq!(pool.vt_package(&child.hash()), vec![&child]);
+        assert_eq!(
+            pool.sorted_index
+                .iter()
+                .next()
+                .map(|(priority, _, _)| *priority),
+            Some(4 * fee * 1000 / u64::from(child.size()))
+        );
+
+        // Spending the same outputs as a block removes the descendants as well
+        let mut pool = TransactionsPool::new();
+        pool.insert_with_fee(Transaction::ValueTransfer(parent.clone()), 0, 10);
+        pool.insert_with_fee(Transaction::ValueTransfer(child.clone()), 0, 20);
+        pool.insert_with_fee(Transaction::ValueTransfer(grandchild.clone()), 0, 30);
+        let spent: HashSet<_> = vec![spent].into_iter().collect();
+        assert_eq!(
+            pool.remove_spending(&spent),
+            vec![parent.hash(), child.hash(), grandchild.hash()]
+        );
+        assert!(pool.is_empty());
+        assert!(pool.vt_spenders.is_empty());
+
+        // Keeping a transaction keeps its unconfirmed ancestors as well
+        let mut pool = TransactionsPool::new();
+        pool.insert_with_fee(Transaction::ValueTransfer(parent.clone()), 0, 10);
+        pool.insert_with_fee(Transaction::ValueTransfer(child.clone()), 0, 20);
+        pool.insert_with_fee(Transaction::ValueTransfer(grandchild.clone()), 0, 30);
+        pool.insert_with_fee(Transaction::ValueTransfer(other.clone()), 0, 40);
+        let kept: HashSet<_> = vec![child.hash()].into_iter().collect();
+        pool.retain_hashes(&kept);
+        assert_eq!(pool.vt_package(&child.hash()), vec![&parent, &child]);
+        assert_eq!(pool.vt_len(), 2);
+    }
+
+    #[test]
+    fn reputation_expirations() {
+        let pkh = PublicKeyHash { hash: [1; 20] };
+        let other_pkh = PublicKeyHash { hash: [2; 20] };
+        let mut rep_eng = ReputationEngine::new(10);
+        rep_eng
+            .trs
+            .gain(Alpha(10), vec![(pkh, Reputation(5))])
+            .unwrap();
+        rep_eng
+            .trs
+            .gain(Alpha(20), vec![(other_pkh, Reputation(2))])
+            .unwrap();
+        rep_eng
+            .trs
+            .gain(
+                Alpha(30),
+                vec![(pkh, Reputation(3)), (other_pkh, Reputation(1))],
+            )
+            .unwrap();
+
+        assert_eq!(
+            rep_eng.expirations(&pkh),
+            vec![(Alpha(10), Reputation(5)), (Alpha(30), Reputation(3))]
+        );
+
+        rep_eng.trs.expire(&Alpha(10));
+        assert_eq!(rep_eng.expirations(&pkh), vec![(Alpha(30), Reputation(3))]);
+    }
+
+    #[test]
+    fn watch_pkh_finds_existing_utxos() {
+        let pkh = PublicKeyHash { hash: [1; 20] };
+        let other_pkh = PublicKeyHash { hash: [2; 20] };
+        let output_pointer = |output_index| OutputPointer {
+            transaction_id: Hash::SHA256([1; 32]),
+            output_index,
+        };
+        let mut chain_state = ChainState::default();
+        chain_state
+            .unspent_outputs_pool
+            .insert(output_pointer(0), ValueTransferOutput { pkh, value: 1 });
+        chain_state.unspent_outputs_pool.insert(
+            output_pointer(1),
+            ValueTransferOutput {
+                pkh: other_pkh,
+                value: 2,
+            },
+        );
+
+        assert!(chain_state.watch_pkh(pkh));
+        assert!(!chain_state.watch_pkh(pkh));
+        assert_eq!(
+            chain_state.watched_utxos[&pkh],
+            vec![output_pointer(0)].into_iter().collect()
+        );
+
+        assert!(chain_state.unwatch_pkh(&pkh));
+        assert!(!chain_state.unwatch_pkh(&pkh));
+        assert!(chain_state.watched_utxos.is_empty());
+    }
 }
@@ -23,6 +23,51 @@ pub struct DataRequestPool {
     pub data_request_pool: HashMap<Hash, DataRequestState>,
     /// List of data requests that should be persisted into storage
     pub to_be_stored: Vec<DataRequestReport>,
+    /// Stage transitions of the data requests that should be notified, see `take_events`
+    #[serde(skip)]
+    pub events: Vec<DataRequestEvent>,
+}
+
+/// Stage transition of a data request, caused by the transactions of a consolidated block
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "event")]
+pub enum DataRequestEvent {
+    /// A commitment to the data request has been included in a block
+    CommitIncluded {
+        dr_pointer: Hash,
+        pkh: PublicKeyHash,
+        block_hash: Hash,
+    },
+    /// A reveal of the data request has been included in a block
+    RevealIncluded {
+        dr_pointer: Hash,
+        pkh: PublicKeyHash,
+        block_hash: Hash,
+    },
+    /// The data request has moved to another stage
+    StageChanged {
+        dr_pointer: Hash,
+        stage: DataRequestStage,
+    },
+    /// The tally of the data request has been included in a block, resolving it
+    TallyMined {
+        dr_pointer: Hash,
+        block_hash: Hash,
+        /// Result of the data request, which may be an error
+        tally: Vec<u8>,
+    },
+}
+
+impl DataRequestEvent {
+    /// Data request this event belongs to
+    pub fn dr_pointer(&self) -> Hash {
+        match self {
+            DataRequestEvent::CommitIncluded { dr_pointer, .. }
+            | DataRequestEvent::RevealIncluded { dr_pointer, .. }
+            | DataRequestEvent::StageChanged { dr_pointer, .. }
+            | DataRequestEvent::TallyMined { dr_pointer, .. } => *dr_pointer,
+        }
+    }
 }
 
 impl DataRequestPool {
@@ -107,7 +152,12 @@ impl DataRequestPool {
         // The data request must be from a previous block, and must not be timelocked.
         // This is not checked here, as it should have made the block invalid.
         if let Some(dr) = self.data_request_pool.get_mut(&dr_pointer) {
-            dr.add_commit(pkh, commit.clone())?
+            dr.add_commit(pkh, commit.clone())?;
+            self.events.push(DataRequestEvent::CommitIncluded {
+                dr_pointer,
+                pkh,
+                block_hash: *block_hash,
+            });
         } else {
             Err(DataRequestError::AddCommitFail {
                 block_hash: *block_hash,
@@ -132,7 +182,12 @@ impl DataRequestPool {
         // The data request must be from a previous block, and must not be timelocked.
         // This is not checked here, as it should have made the block invalid.
         if let Some(dr) = self.data_request_pool.get_mut(&dr_pointer) {
-            dr.add_reveal(pkh, reveal)?
+            dr.add_reveal(pkh, reveal)?;
+            self.events.push(DataRequestEvent::RevealIncluded {
+                dr_pointer,
+                pkh,
+                block_hash: *block_hash,
+            });
         } else {
             Err(DataRequestError::AddRevealFail {
                 block_hash: *block_hash,
@@ -152,6 +207,11 @@ impl DataRequestPool {
         block_hash: &Hash,
     ) -> Result<(), failure::Error> {
         let dr_report = Self::resolve_data_request(&mut self.data_request_pool, tally, block_hash)?;
+        self.events.push(DataRequestEvent::TallyMined {
+            dr_pointer: dr_report.tally.dr_pointer,
+            block_hash: *block_hash,
+            tally: dr_report.tally.tally.clone(),
+        });
 
         // Since this method does not have access to the storage, we save the
         // "to be stored" inside a vector and provide another method to store them
@@ -190,6 +250,7 @@ impl DataRequestPool {
     pub fn update_data_request_stages(&mut self) -> Vec<RevealTransaction> {
         let waiting_for_reveal = &mut self.waiting_for_reveal;
         let data_requests_by_epoch = &mut self.data_requests_by_epoch;
+        let events = &mut self.events;
         // Update the stage of the active data requests
         self.data_request_pool
             .iter_mut()
@@ -197,6 +258,10 @@ impl DataRequestPool {
                 // We can notify the user that a data request from "my_claims" is available
                 // for reveal.
                 if dr_state.update_stage() {
+                    events.push(DataRequestEvent::StageChanged {
+                        dr_pointer: *dr_pointer,
+                        stage: dr_state.stage,
+                    });
                     if let DataRequestStage::REVEAL = dr_state.stage {
                         // When a data request changes from commit stage to reveal stage, it should
                         // be removed from the "data_requests_by_epoch" map, which stores the data
@@ -311,6 +376,11 @@ impl DataRequestPool {
     pub fn finished_data_requests(&mut self) -> Vec<DataRequestReport> {
         std::mem::replace(&mut self.to_be_stored, vec![])
     }
+
+    /// Get the stage transitions of the data requests since the last call, to be notified
+    pub fn take_events(&mut self) -> Vec<DataRequestEvent> {
+        std::mem::replace(&mut self.events, vec![])
+    }
 }
 
 /// Function to calculate the value transfer reward
@@ -532,6 +602,32 @@ mod tests {
         from_commit_to_reveal(epoch, fake_block_hash, p, dr_pointer);
     }
 
+    #[test]
+    fn test_data_request_state_summary() {
+        let (epoch, fake_block_hash, p, dr_pointer) = add_data_requests();
+        let summary = p.data_request_pool[&dr_pointer].summary(epoch);
+
+        assert_eq!(summary.stage, DataRequestStage::COMMIT);
+        assert_eq!(summary.epoch, epoch);
+        assert!(!summary.time_locked);
+        assert_eq!(summary.commits, 0);
+        assert_eq!(summary.reveals, 0);
+        assert_eq!(summary.seconds_to_next_epoch, None);
+
+        // Before its epoch, the data request is still time locked
+        let mut time_locked_state = p.data_request_pool[&dr_pointer].clone();
+        time_locked_state.epoch = epoch + 10;
+        assert!(time_locked_state.summary(epoch).time_locked);
+
+        let (_fake_block_hash, p, dr_pointer) =
+            from_commit_to_reveal(epoch, fake_block_hash, p, dr_pointer);
+        let summary = p.data_request_pool[&dr_pointer].summary(epoch + 1);
+
+        assert_eq!(summary.stage, DataRequestStage::REVEAL);
+        assert_eq!(summary.commits, 1);
+        assert_eq!(summary.reveals, 0);
+    }
+
     #[test]
     fn test_from_reveal_to_tally() {
         let (epoch, fake_block_hash, p, dr_pointer) = add_data_requests();
@@ -551,6 +647,49 @@ mod tests {
         from_tally_to_storage(fake_block_hash, p, dr_pointer);
     }
 
+    #[test]
+    fn test_data_request_events() {
+        let (epoch, fake_block_hash, p, dr_pointer) = add_data_requests();
+        let (fake_block_hash, p, dr_pointer) =
+            from_commit_to_reveal(epoch, fake_block_hash, p, dr_pointer);
+        let (fake_block_hash, mut p, dr_pointer) =
+            from_reveal_to_tally(fake_block_hash, p, dr_pointer);
+        let tally_transaction = TallyTransaction::new(dr_pointer, vec![], vec![]);
+        p.process_tally(&tally_transaction, &fake_block_hash)
+            .unwrap();
+
+        let pkh = PublicKeyHash::from_public_key(&KeyedSignature::default().public_key);
+        assert_eq!(
+            p.take_events(),
+            vec![
+                DataRequestEvent::CommitIncluded {
+                    dr_pointer,
+                    pkh,
+                    block_hash: fake_block_hash,
+                },
+                DataRequestEvent::StageChanged {
+                    dr_pointer,
+                    stage: DataRequestStage::REVEAL,
+                },
+                DataRequestEvent::RevealIncluded {
+                    dr_pointer,
+                    pkh,
+                    block_hash: fake_block_hash,
+                },
+                DataRequestEvent::StageChanged {
+                    dr_pointer,
+                    stage: DataRequestStage::TALLY,
+                },
+                DataRequestEvent::TallyMined {
+                    dr_pointer,
+                    block_hash: fake_block_hash,
+                    tally: vec![],
+                },
+            ]
+        );
+        assert!(p.take_events().is_empty());
+    }
+
     #[test]
     fn my_claims() {
         // Test the `add_own_reveal` function
@@ -3,7 +3,7 @@
 use failure::Fail;
 use std::num::ParseIntError;
 
-use crate::chain::{Epoch, Hash, HashParseError, OutputPointer, PublicKeyHash};
+use crate::chain::{DataRequestStage, Epoch, Hash, HashParseError, OutputPointer, PublicKeyHash};
 
 /// The error type for operations on a [`ChainInfo`](ChainInfo)
 #[derive(Debug, PartialEq, Fail)]
@@ -38,6 +38,16 @@ pub enum TransactionError {
     /// An output with the given index wasn't found in a transaction.
     #[fail(display = "Output not found: {}", output)]
     OutputNotFound { output: OutputPointer },
+    /// The transaction spends the outputs of too many transactions that are still in the pool.
+    #[fail(
+        display = "Transaction {} has {} unconfirmed ancestors, more than the maximum of {}",
+        hash, ancestors, max
+    )]
+    TooManyUnconfirmedAncestors {
+        hash: Hash,
+        ancestors: usize,
+        max: usize,
+    },
     #[fail(display = "Data Request not found: {}", hash)]
     DataRequestNotFound { hash: Hash },
     #[fail(display = "The transaction signature is invalid")]
@@ -67,6 +77,8 @@ pub enum TransactionError {
     InvalidDataRequestValue { dr_value: u64, witnesses: u16 },
     #[fail(display = "Data Request witnesses number is not enough")]
     InsufficientWitnesses,
+    #[fail(display = "The sum of the fees of the Data Request overflows")]
+    DataRequestFeesOverflow,
     #[fail(
         display = "Mismatching between local tally ({:?}) and miner tally ({:?})",
         local_tally, miner_tally
@@ -142,6 +154,21 @@ pub enum TransactionError {
         expected, current
     )]
     TimeLock { current: i64, expected: i64 },
+    /// Transaction format version not known by this node
+    #[fail(display = "Unknown transaction version {}", version)]
+    UnknownVersion { version: u32 },
+    /// Transaction format version not activated yet
+    #[fail(
+        display = "Transaction version {} cannot be used before epoch {}",
+        version, activation_epoch
+    )]
+    InactiveVersion {
+        version: u32,
+        activation_epoch: Epoch,
+    },
+    /// Transaction feature not known by this node or not activated yet
+    #[fail(display = "Transaction feature bit {} is not active", bit)]
+    InactiveFeature { bit: u32 },
 }
 
 /// The error type for operations on a [`Block`](Block)
@@ -208,6 +235,15 @@ pub enum BlockError {
         current_epoch: Epoch,
         block_epoch: Epoch,
     },
+    #[fail(
+        display = "Block candidate's epoch ({}) starts {} seconds ahead of the local clock, more than the tolerated {} seconds",
+        block_epoch, seconds_ahead, tolerance
+    )]
+    CandidateFromFuture {
+        block_epoch: Epoch,
+        seconds_ahead: i64,
+        tolerance: i64,
+    },
     #[fail(
         display = "Commits in block ({}) are not equal to commits required ({})",
         commits, rf
@@ -311,6 +347,15 @@ pub enum DataRequestError {
     NotTallyStage,
     #[fail(display = "Cannot persist unfinished data request (with no Tally)")]
     UnfinishedDataRequest,
+    /// A retrieval has more fallback sources than allowed
+    #[fail(
+        display = "A retrieval has {} fallback sources, but the maximum is {}",
+        count, max
+    )]
+    TooManyFallbackUrls { count: usize, max: usize },
+    /// A fallback source of a retrieval is empty or repeated
+    #[fail(display = "Fallback source {:?} is empty or repeated", url)]
+    InvalidFallbackUrl { url: String },
 }
 
 /// Possible errors when converting between epoch and timestamp
@@ -323,3 +368,84 @@ pub enum EpochCalculationError {
     #[fail(display = "Overflow when calculating the epoch timestamp")]
     Overflow,
 }
+
+/// Inconsistencies found when checking the invariants of the chain state
+#[derive(Debug, PartialEq, Fail)]
+pub enum ChainStateInvariantError {
+    /// A tracked unspent output is not in the unspent outputs pool
+    #[fail(
+        display = "Unspent output {} tracked for {} is not in the unspent outputs pool",
+        output_pointer, pkh
+    )]
+    TrackedUtxoNotInPool {
+        output_pointer: OutputPointer,
+        pkh: PublicKeyHash,
+    },
+    /// A tracked unspent output belongs to a different identity
+    #[fail(
+        display = "Unspent output {} tracked for {} belongs to {}",
+        output_pointer, pkh, owner
+    )]
+    TrackedUtxoWrongOwner {
+        output_pointer: OutputPointer,
+        pkh: PublicKeyHash,
+        owner: PublicKeyHash,
+    },
+    /// An unspent output of a tracked identity is missing from its tracked outputs
+    #[fail(
+        display = "Unspent output {} of {} is not tracked",
+        output_pointer, pkh
+    )]
+    UtxoNotTracked {
+        output_pointer: OutputPointer,
+        pkh: PublicKeyHash,
+    },
+    /// A data request indexed by epoch is not in the data request pool
+    #[fail(
+        display = "Data request {} indexed at epoch {} is not in the data request pool",
+        dr_pointer, epoch
+    )]
+    IndexedDataRequestNotInPool { dr_pointer: Hash, epoch: Epoch },
+    /// The epoch index of a data request does not match its stage or its epoch
+    #[fail(
+        display = "Data request {} in stage {:?} for epoch {} is indexed at epochs {:?}",
+        dr_pointer, stage, epoch, indexed_at
+    )]
+    DataRequestWronglyIndexed {
+        dr_pointer: Hash,
+        stage: DataRequestStage,
+        epoch: Epoch,
+        indexed_at: Vec<Epoch>,
+    },
+    /// The stage of a data request does not match its commits, reveals and tally
+    #[fail(
+        display = "Data request {} is in stage {:?} with {} commits, {} reveals and tally: {}",
+        dr_pointer, stage, commits, reveals, tally
+    )]
+    DataRequestInconsistentStage {
+        dr_pointer: Hash,
+        stage: DataRequestStage,
+        commits: usize,
+        reveals: usize,
+        tally: bool,
+    },
+    /// A data request has a reveal without a matching commit
+    #[fail(
+        display = "Data request {} has a reveal from {} without a commit",
+        dr_pointer, pkh
+    )]
+    RevealWithoutCommit {
+        dr_pointer: Hash,
+        pkh: PublicKeyHash,
+    },
+    /// The total reputation of an identity does not match the sum of its reputation packets
+    #[fail(
+        display = "Identity {} has a total reputation of {}, but its reputation packets add up to {}",
+        pkh, total, packets
+    )]
+    ReputationMismatch {
+        pkh: PublicKeyHash,
+        total: u32,
+        packets: u32,
+    },
+}
@@ -0,0 +1,336 @@
+//! Consistency checks of the chain state, meant to be run after every consolidated block so that
+//! a corrupted state stops the node instead of being persisted.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    chain::{
+        ChainState, DataRequestStage, Epoch, Hash, OutputPointer, PublicKeyHash, Reputation,
+        ReputationEngine, UnspentOutputsPool,
+    },
+    data_request::DataRequestPool,
+    error::ChainStateInvariantError,
+};
+
+/// Check all the invariants of the chain state, returning every inconsistency found.
+///
+/// `own_pkh` is the identity whose unspent outputs are tracked in `own_utxos`, if known.
+pub fn check_chain_state(
+    chain_state: &ChainState,
+    own_pkh: Option<PublicKeyHash>,
+) -> Vec<ChainStateInvariantError> {
+    let mut tracked: Vec<(PublicKeyHash, &HashSet<OutputPointer>)> = chain_state
+        .watched_utxos
+        .iter()
+        .map(|(pkh, utxos)| (*pkh, utxos))
+        .collect();
+    if let Some(own_pkh) = own_pkh {
+        tracked.push((own_pkh, &chain_state.own_utxos));
+    }
+
+    let mut errors = check_tracked_utxos(&chain_state.unspent_outputs_pool, &tracked);
+    errors.extend(check_data_request_pool(&chain_state.data_request_pool));
+    if let Some(reputation_engine) = &chain_state.reputation_engine {
+        errors.extend(check_reputation_engine(reputation_engine));
+    }
+
+    errors
+}
+
+/// Every tracked unspent output must be in the unspent outputs pool and belong to the identity
+/// it is tracked for, and every unspent output of a tracked identity must be tracked.
+pub fn check_tracked_utxos(
+    unspent_outputs_pool: &UnspentOutputsPool,
+    tracked: &[(PublicKeyHash, &HashSet<OutputPointer>)],
+) -> Vec<ChainStateInvariantError> {
+    let mut errors = vec![];
+
+    for (pkh, utxos) in tracked {
+        for output_pointer in utxos.iter() {
+            match unspent_outputs_pool.get(output_pointer) {
+                None => errors.push(ChainStateInvariantError::TrackedUtxoNotInPool {
+                    output_pointer: output_pointer.clone(),
+                    pkh: *pkh,
+                }),
+                Some(output) if output.pkh != *pkh => {
+                    errors.push(ChainStateInvariantError::TrackedUtxoWrongOwner {
+                        output_pointer: output_pointer.clone(),
+                        pkh: *pkh,
+                        owner: output.pkh,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    for (output_pointer, output) in unspent_outputs_pool {
+        let untracked = tracked
+            .iter()
+            .any(|(pkh, utxos)| *pkh == output.pkh && !utxos.contains(output_pointer));
+        if untracked {
+            errors.push(ChainStateInvariantError::UtxoNotTracked {
+                output_pointer: output_pointer.clone(),
+                pkh: output.pkh,
+            });
+        }
+    }
+
+    errors
+}
+
+/// Only the data requests in commit stage are indexed by epoch, at their own epoch, and the stage
+/// of every data request matches its commits, reveals and tally.
+pub fn check_data_request_pool(pool: &DataRequestPool) -> Vec<ChainStateInvariantError> {
+    let mut errors = vec![];
+
+    let mut indexed_at: HashMap<Hash, Vec<Epoch>> = HashMap::new();
+    for (epoch, dr_pointers) in &pool.data_requests_by_epoch {
+        for dr_pointer in dr_pointers {
+            if !pool.data_request_pool.contains_key(dr_pointer) {
+                errors.push(ChainStateInvariantError::IndexedDataRequestNotInPool {
+                    dr_pointer: *dr_pointer,
+                    epoch: *epoch,
+                });
+            }
+            indexed_at.entry(*dr_pointer).or_default().push(*epoch);
+        }
+    }
+
+    // Sort by hash so that the errors are reported in a deterministic order
+    let dr_states: BTreeMap<_, _> = pool.data_request_pool.iter().collect();
+    for (dr_pointer, dr_state) in dr_states {
+        let indexed_at = indexed_at.remove(dr_pointer).unwrap_or_default();
+        let expected = match dr_state.stage {
+            DataRequestStage::COMMIT => vec![dr_state.epoch],
+            DataRequestStage::REVEAL | DataRequestStage::TALLY => vec![],
+        };
+        if indexed_at != expected {
+            errors.push(ChainStateInvariantError::DataRequestWronglyIndexed {
+                dr_pointer: *dr_pointer,
+                stage: dr_state.stage,
+                epoch: dr_state.epoch,
+                indexed_at,
+            });
+        }
+
+        let info = &dr_state.info;
+        let consistent_stage = info.tally.is_none()
+            && match dr_state.stage {
+                DataRequestStage::COMMIT => true,
+                DataRequestStage::REVEAL => !info.commits.is_empty(),
+                DataRequestStage::TALLY => !info.reveals.is_empty(),
+            };
+        if !consistent_stage {
+            errors.push(ChainStateInvariantError::DataRequestInconsistentStage {
+                dr_pointer: *dr_pointer,
+                stage: dr_state.stage,
+                commits: info.commits.len(),
+                reveals: info.reveals.len(),
+                tally: info.tally.is_some(),
+            });
+        }
+
+        for pkh in info.reveals.keys() {
+            if !info.commits.contains_key(pkh) {
+                errors.push(ChainStateInvariantError::RevealWithoutCommit {
+                    dr_pointer: *dr_pointer,
+                    pkh: *pkh,
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+/// The total reputation of every identity is the sum of its reputation packets.
+pub fn check_reputation_engine(
+    reputation_engine: &ReputationEngine,
+) -> Vec<ChainStateInvariantError> {
+    let mut packets: HashMap<PublicKeyHash, Reputation> = HashMap::new();
+    for (_alpha, packet) in reputation_engine.trs.queue() {
+        for (pkh, reputation) in packet {
+            *packets.entry(*pkh).or_default() += *reputation;
+        }
+    }
+
+    let mut pkhs: Vec<PublicKeyHash> = reputation_engine
+        .trs
+        .identities()
+        .map(|(pkh, _)| *pkh)
+        .chain(packets.keys().cloned())
+        .collect();
+    pkhs.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+    pkhs.dedup();
+
+    pkhs.into_iter()
+        .filter_map(|pkh| {
+            let total = reputation_engine.trs.get(&pkh);
+            let packets = packets.get(&pkh).cloned().unwrap_or_default();
+            if total == packets {
+                None
+            } else {
+                Some(ChainStateInvariantError::ReputationMismatch {
+                    pkh,
+                    total: total.0,
+                    packets: packets.0,
+                })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::{Alpha, DataRequestOutput, DataRequestState, ValueTransferOutput};
+
+    fn pkh(byte: u8) -> PublicKeyHash {
+        PublicKeyHash { hash: [byte; 20] }
+    }
+
+    fn output_pointer(index: u32) -> OutputPointer {
+        OutputPointer {
+            transaction_id: Hash::SHA256([1; 32]),
+            output_index: index,
+        }
+    }
+
+    fn chain_state_with_utxos() -> ChainState {
+        let mut chain_state = ChainState::default();
+        chain_state.unspent_outputs_pool.insert(
+            output_pointer(0),
+            ValueTransferOutput {
+                pkh: pkh(1),
+                value: 10,
+            },
+        );
+        chain_state.unspent_outputs_pool.insert(
+            output_pointer(1),
+            ValueTransferOutput {
+                pkh: pkh(2),
+                value: 20,
+            },
+        );
+        chain_state.own_utxos.insert(output_pointer(0));
+        chain_state.watch_pkh(pkh(2));
+
+        chain_state
+    }
+
+    #[test]
+    fn consistent_utxos() {
+        let chain_state = chain_state_with_utxos();
+
+        assert_eq!(check_chain_state(&chain_state, Some(pkh(1))), vec![]);
+    }
+
+    #[test]
+    fn own_utxo_spent_but_still_tracked() {
+        let mut chain_state = chain_state_with_utxos();
+        chain_state.unspent_outputs_pool.remove(&output_pointer(0));
+
+        assert_eq!(
+            check_chain_state(&chain_state, Some(pkh(1))),
+            vec![ChainStateInvariantError::TrackedUtxoNotInPool {
+                output_pointer: output_pointer(0),
+                pkh: pkh(1),
+            }]
+        );
+    }
+
+    #[test]
+    fn watched_utxo_missing_and_own_utxo_of_other_identity() {
+        let mut chain_state = chain_state_with_utxos();
+        chain_state.watched_utxos.get_mut(&pkh(2)).unwrap().clear();
+        chain_state.own_utxos.insert(output_pointer(1));
+
+        let mut errors = check_chain_state(&chain_state, Some(pkh(1)));
+        errors.sort_by_key(|e| e.to_string());
+        assert_eq!(
+            errors,
+            vec![
+                ChainStateInvariantError::UtxoNotTracked {
+                    output_pointer: output_pointer(1),
+                    pkh: pkh(2),
+                },
+                ChainStateInvariantError::TrackedUtxoWrongOwner {
+                    output_pointer: output_pointer(1),
+                    pkh: pkh(1),
+                    owner: pkh(2),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn data_request_in_reveal_stage_still_indexed() {
+        let dr_pointer = Hash::SHA256([2; 32]);
+        let mut pool = DataRequestPool::default();
+        let mut dr_state = DataRequestState::new(
+            DataRequestOutput::default(),
+            pkh(1),
+            3,
+            &Hash::SHA256([3; 32]),
+        );
+        pool.data_request_pool.insert(dr_pointer, dr_state.clone());
+        pool.data_requests_by_epoch
+            .entry(3)
+            .or_default()
+            .insert(dr_pointer);
+        assert_eq!(check_data_request_pool(&pool), vec![]);
+
+        dr_state.stage = DataRequestStage::REVEAL;
+        pool.data_request_pool.insert(dr_pointer, dr_state);
+        assert_eq!(
+            check_data_request_pool(&pool),
+            vec![
+                ChainStateInvariantError::DataRequestWronglyIndexed {
+                    dr_pointer,
+                    stage: DataRequestStage::REVEAL,
+                    epoch: 3,
+                    indexed_at: vec![3],
+                },
+                ChainStateInvariantError::DataRequestInconsistentStage {
+                    dr_pointer,
+                    stage: DataRequestStage::REVEAL,
+                    commits: 0,
+                    reveals: 0,
+                    tally: false,
+                },
+            ]
+        );
+
+        pool.data_request_pool.remove(&dr_pointer);
+        assert_eq!(
+            check_data_request_pool(&pool),
+            vec![ChainStateInvariantError::IndexedDataRequestNotInPool {
+                dr_pointer,
+                epoch: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn consistent_reputation() {
+        let mut reputation_engine = ReputationEngine::new(10);
+        reputation_engine
+            .trs
+            .gain(
+                Alpha(10),
+                vec![(pkh(1), Reputation(5)), (pkh(2), Reputation(3))],
+            )
+            .unwrap();
+        reputation_engine
+            .trs
+            .gain(Alpha(20), vec![(pkh(1), Reputation(7))])
+            .unwrap();
+        reputation_engine
+            .trs
+            .penalize(&pkh(1), |r| Reputation(r.0 / 2))
+            .unwrap();
+
+        assert_eq!(check_reputation_engine(&reputation_engine), vec![]);
+    }
+}
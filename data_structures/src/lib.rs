@@ -30,6 +30,15 @@ pub mod transaction;
 /// Module containing VRF-related structures
 pub mod vrf;
 
+/// Module containing the activation of protocol upgrades
+pub mod upgrades;
+
+/// Module containing the superblocks that summarize periods of epochs
+pub mod superblock;
+
+/// Module containing the consistency checks of the chain state
+pub mod invariants;
+
 /// Serialization boilerplate to allow serializing some data structures as
 /// strings or bytes depending on the serializer.
 mod serialization_helpers;
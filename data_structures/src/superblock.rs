@@ -0,0 +1,257 @@
+//! Superblocks summarize the data requests and tallies consolidated during a fixed period of
+//! epochs, along with the Active Reputation Set (ARS) at the end of that period.
+//!
+//! Bridges can keep track of the superblock roots instead of every block: a data request or a
+//! tally can be proven to be part of a superblock using the merkle roots of the blocks of its
+//! period, and an identity can be proven to be part of the ARS using the ordered list of its
+//! members.
+use serde::{Deserialize, Serialize};
+use witnet_crypto::{
+    hash::{calculate_sha256, Sha256},
+    merkle::FullMerkleTree,
+};
+
+use crate::{
+    chain::{BlockMerkleRoots, Epoch, Hash, PublicKeyHash},
+    transaction::TxInclusionProof,
+};
+
+/// Number of epochs of the period summarized by a superblock
+pub const SUPERBLOCK_PERIOD: Epoch = 10;
+
+/// Summary of the blocks consolidated during a superblock period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Superblock {
+    /// Index of the superblock, the epochs of its period are
+    /// `[index * SUPERBLOCK_PERIOD, (index + 1) * SUPERBLOCK_PERIOD)`
+    pub index: u32,
+    /// Hash of the last block of the period
+    pub last_block: Hash,
+    /// Epoch of the last block of the period
+    pub last_block_epoch: Epoch,
+    /// Merkle root of the data request merkle roots of the blocks of the period
+    pub data_request_root: Hash,
+    /// Merkle root of the tally merkle roots of the blocks of the period
+    pub tally_root: Hash,
+    /// Merkle root of the ARS members
+    pub ars_root: Hash,
+    /// Members of the ARS at the end of the period, ordered by the bytes of their public key hash
+    pub ars_members: Vec<PublicKeyHash>,
+}
+
+/// Blocks of the superblock period in progress
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuperblockState {
+    /// Index of the superblock period in progress
+    index: Option<u32>,
+    /// Hash and epoch of the last block of the period
+    last_block: Option<(Hash, Epoch)>,
+    /// Data request merkle roots of the blocks of the period
+    data_request_roots: Vec<Hash>,
+    /// Tally merkle roots of the blocks of the period
+    tally_roots: Vec<Hash>,
+    /// Whether some blocks of the period were consolidated before superblocks were tracked, in
+    /// which case the superblock of the period cannot be built
+    incomplete: bool,
+}
+
+/// Proof of membership of an identity in the ARS of a superblock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArsMerkleProof {
+    /// Index of the superblock
+    pub superblock_index: u32,
+    /// Merkle root of the ARS members
+    pub ars_root: Hash,
+    /// Leaf of the identity, `sha256(pkh)`
+    pub leaf: Hash,
+    /// Merkle path from the leaf to the root
+    pub proof: TxInclusionProof,
+}
+
+impl SuperblockState {
+    /// State of a chain whose blocks up to `epoch` were consolidated before superblocks were
+    /// tracked. The superblock of the period of `epoch` is never built, as the blocks that were
+    /// consolidated in it are unknown.
+    pub fn resume(epoch: Epoch) -> Self {
+        SuperblockState {
+            index: Some(epoch / SUPERBLOCK_PERIOD),
+            incomplete: true,
+            ..SuperblockState::default()
+        }
+    }
+
+    /// Add a consolidated block to the period in progress.
+    ///
+    /// If the block starts a new period, the superblock of the previous period is returned, built
+    /// with the given ARS members, which must be the ARS at the end of that period.
+    pub fn process_block<'a, I>(
+        &mut self,
+        epoch: Epoch,
+        block_hash: Hash,
+        merkle_roots: &BlockMerkleRoots,
+        ars_members: I,
+    ) -> Option<Superblock>
+    where
+        I: IntoIterator<Item = &'a PublicKeyHash>,
+    {
+        let index = epoch / SUPERBLOCK_PERIOD;
+        let superblock = match self.index {
+            Some(current) if current != index => self.build(ars_members),
+            _ => None,
+        };
+        if self.index != Some(index) {
+            *self = SuperblockState {
+                index: Some(index),
+                ..SuperblockState::default()
+            };
+        }
+
+        self.last_block = Some((block_hash, epoch));
+        self.data_request_roots
+            .push(merkle_roots.dr_hash_merkle_root);
+        self.tally_roots.push(merkle_roots.tally_hash_merkle_root);
+
+        superblock
+    }
+
+    /// Build the superblock of the period in progress
+    fn build<'a, I>(&self, ars_members: I) -> Option<Superblock>
+    where
+        I: IntoIterator<Item = &'a PublicKeyHash>,
+    {
+        if self.incomplete {
+            return None;
+        }
+        let index = self.index?;
+        let (last_block, last_block_epoch) = self.last_block?;
+        let mut ars_members: Vec<PublicKeyHash> = ars_members.into_iter().cloned().collect();
+        ars_members.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        Some(Superblock {
+            index,
+            last_block,
+            last_block_epoch,
+            data_request_root: merkle_root(&self.data_request_roots),
+            tally_root: merkle_root(&self.tally_roots),
+            ars_root: ars_merkle_tree(&ars_members).root().into(),
+            ars_members,
+        })
+    }
+}
+
+impl Superblock {
+    /// Proof of membership of an identity in the ARS of the superblock, or `None` if it is not a
+    /// member.
+    pub fn ars_merkle_proof(&self, pkh: &PublicKeyHash) -> Option<ArsMerkleProof> {
+        let position = self
+            .ars_members
+            .binary_search_by(|member| member.as_ref().cmp(pkh.as_ref()))
+            .ok()?;
+        let proof = ars_merkle_tree(&self.ars_members).inclusion_proof(position)?;
+
+        Some(ArsMerkleProof {
+            superblock_index: self.index,
+            ars_root: self.ars_root,
+            leaf: ars_leaf(pkh).into(),
+            proof: TxInclusionProof {
+                index: proof.proof_index(),
+                lemma: proof.lemma().iter().map(|sha| (*sha).into()).collect(),
+            },
+        })
+    }
+}
+
+/// Leaf of an identity in the merkle tree of the ARS members
+fn ars_leaf(pkh: &PublicKeyHash) -> Sha256 {
+    calculate_sha256(pkh.as_ref())
+}
+
+fn ars_merkle_tree(ars_members: &[PublicKeyHash]) -> FullMerkleTree<Sha256> {
+    FullMerkleTree::sha256(ars_members.iter().map(ars_leaf).collect())
+}
+
+fn merkle_root(hashes: &[Hash]) -> Hash {
+    FullMerkleTree::sha256(hashes.iter().map(|hash| (*hash).into()).collect())
+        .root()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkh(byte: u8) -> PublicKeyHash {
+        PublicKeyHash { hash: [byte; 20] }
+    }
+
+    #[test]
+    fn superblock_is_built_when_the_period_ends() {
+        let mut state = SuperblockState::default();
+        let roots = BlockMerkleRoots::default();
+        let members = vec![pkh(2), pkh(1)];
+
+        for epoch in 0..SUPERBLOCK_PERIOD {
+            assert_eq!(
+                state.process_block(epoch, Hash::default(), &roots, &members),
+                None
+            );
+        }
+
+        let superblock = state
+            .process_block(SUPERBLOCK_PERIOD, Hash::default(), &roots, &members)
+            .unwrap();
+        assert_eq!(superblock.index, 0);
+        assert_eq!(superblock.last_block_epoch, SUPERBLOCK_PERIOD - 1);
+        assert_eq!(superblock.ars_members, vec![pkh(1), pkh(2)]);
+    }
+
+    #[test]
+    fn superblock_of_resumed_period_is_not_built() {
+        let roots = BlockMerkleRoots::default();
+        let members = vec![pkh(1)];
+        let mut state = SuperblockState::resume(SUPERBLOCK_PERIOD + 3);
+
+        for epoch in SUPERBLOCK_PERIOD + 4..2 * SUPERBLOCK_PERIOD {
+            state.process_block(epoch, Hash::default(), &roots, &members);
+        }
+        assert_eq!(
+            state.process_block(2 * SUPERBLOCK_PERIOD, Hash::default(), &roots, &members),
+            None
+        );
+
+        // The following periods are complete
+        let superblock = state
+            .process_block(3 * SUPERBLOCK_PERIOD, Hash::default(), &roots, &members)
+            .unwrap();
+        assert_eq!(superblock.index, 2);
+    }
+
+    #[test]
+    fn ars_merkle_proof_verifies() {
+        let mut state = SuperblockState::default();
+        let roots = BlockMerkleRoots::default();
+        let members: Vec<PublicKeyHash> = (0..5).map(pkh).collect();
+
+        state.process_block(0, Hash::default(), &roots, &members);
+        let superblock = state
+            .process_block(SUPERBLOCK_PERIOD, Hash::default(), &roots, &members)
+            .unwrap();
+
+        for member in &members {
+            let ars_proof = superblock.ars_merkle_proof(member).unwrap();
+            let proof = witnet_crypto::merkle::InclusionProof::sha256(
+                ars_proof.proof.index,
+                ars_proof
+                    .proof
+                    .lemma
+                    .iter()
+                    .map(|hash| (*hash).into())
+                    .collect(),
+            );
+            assert!(proof.verify(ars_proof.leaf.into(), superblock.ars_root.into()));
+        }
+        assert_eq!(superblock.ars_merkle_proof(&pkh(9)), None);
+    }
+}
@@ -70,6 +70,83 @@ impl Transaction {
     pub fn size(&self) -> u32 {
         self.to_pb().write_to_bytes().unwrap().len() as u32
     }
+
+    /// Returns the part of the block weight taken up by the transaction.
+    ///
+    /// Only value transfer and data request transactions are limited by the block weight.
+    pub fn weight(&self) -> u32 {
+        match self {
+            Transaction::ValueTransfer(tx) => tx.weight(),
+            Transaction::DataRequest(tx) => tx.weight(),
+            _ => 0,
+        }
+    }
+}
+
+/// Weight units taken up by every byte of a value transfer transaction
+pub const VTT_WEIGHT_PER_BYTE: u32 = 1;
+
+/// Weight units taken up by every byte of a data request transaction.
+///
+/// Data requests do not count towards the block weight yet.
+pub const DR_WEIGHT_PER_BYTE: u32 = 0;
+
+/// Version of the transaction format used by the transactions created by this node
+pub const TRANSACTION_VERSION: u32 = 1;
+
+/// Epoch from which transactions with the given format version are accepted, or `None` if this
+/// node does not know the version.
+pub fn transaction_version_activation_epoch(version: u32) -> Option<Epoch> {
+    match version {
+        1 => Some(0),
+        _ => None,
+    }
+}
+
+/// Epoch from which transactions can signal the given feature bit, or `None` if this node does
+/// not know the feature.
+///
+/// No features have been defined yet.
+pub fn transaction_feature_activation_epoch(_bit: u32) -> Option<Epoch> {
+    None
+}
+
+/// Transaction wrapped with the version of its format and the features it relies on.
+///
+/// New transaction types or fields are introduced as a new version or feature bit, which nodes
+/// reject until its activation epoch, so old nodes can still deserialize the envelope and tell
+/// the transactions they do not understand apart.
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, ProtobufConvert)]
+#[protobuf_convert(pb = "witnet::VersionedTransaction")]
+pub struct VersionedTransaction {
+    pub version: u32,
+    /// Bit set of the features used by the transaction
+    pub features: u32,
+    pub transaction: Transaction,
+}
+
+impl VersionedTransaction {
+    /// Wrap a transaction using the current format version and no features.
+    pub fn new(transaction: Transaction) -> Self {
+        VersionedTransaction {
+            version: TRANSACTION_VERSION,
+            features: 0,
+            transaction,
+        }
+    }
+
+    /// Iterate over the feature bits set in this transaction.
+    pub fn feature_bits(&self) -> impl Iterator<Item = u32> {
+        let features = self.features;
+
+        (0..32).filter(move |bit| features & (1 << bit) != 0)
+    }
+}
+
+impl From<Transaction> for VersionedTransaction {
+    fn from(transaction: Transaction) -> Self {
+        VersionedTransaction::new(transaction)
+    }
 }
 
 pub fn mint(tx: &Transaction) -> Option<&MintTransaction> {
@@ -96,6 +173,11 @@ impl VTTransaction {
     pub fn size(&self) -> u32 {
         self.to_pb().write_to_bytes().unwrap().len() as u32
     }
+
+    /// Returns the part of the block weight taken up by the transaction
+    pub fn weight(&self) -> u32 {
+        self.size() * VTT_WEIGHT_PER_BYTE
+    }
 }
 
 #[derive(Debug, Default, Eq, PartialEq, Clone, Serialize, Deserialize, ProtobufConvert)]
@@ -168,6 +250,16 @@ impl DRTransaction {
         DRTransaction { body, signatures }
     }
 
+    /// Returns the byte size that a transaction will have on the wire
+    pub fn size(&self) -> u32 {
+        self.to_pb().write_to_bytes().unwrap().len() as u32
+    }
+
+    /// Returns the part of the block weight taken up by the transaction
+    pub fn weight(&self) -> u32 {
+        self.size() * DR_WEIGHT_PER_BYTE
+    }
+
     /// Creates a proof of inclusion.
     ///
     /// Returns None if the transaction is not included in this block.
@@ -505,6 +597,22 @@ impl Hashable for Transaction {
     }
 }
 
+impl Hashable for VersionedTransaction {
+    fn hash(&self) -> Hash {
+        let Hash::SHA256(transaction_bytes) = self.transaction.hash();
+
+        calculate_sha256(
+            &[
+                &self.version.to_be_bytes()[..],
+                &self.features.to_be_bytes()[..],
+                &transaction_bytes[..],
+            ]
+            .concat(),
+        )
+        .into()
+    }
+}
+
 impl<T> Hashable for T
 where
     T: MemoizedHashable,
@@ -557,4 +665,32 @@ mod tests {
         let hash = mint_tx.hash();
         assert_eq!(mint_tx.hash.get(), Some(hash));
     }
+
+    #[test]
+    fn test_versioned_transaction_protobuf() {
+        let versioned = VersionedTransaction {
+            version: 7,
+            features: 0b101,
+            transaction: Transaction::Mint(MintTransaction::default()),
+        };
+
+        assert_eq!(
+            VersionedTransaction::from_pb(versioned.to_pb()).unwrap(),
+            versioned
+        );
+        assert_eq!(versioned.feature_bits().collect::<Vec<_>>(), vec![0, 2]);
+
+        // The version and features are part of the hash
+        assert_ne!(versioned.hash(), versioned.transaction.hash());
+        let other_version = VersionedTransaction {
+            version: 8,
+            ..versioned.clone()
+        };
+        assert_ne!(other_version.hash(), versioned.hash());
+        let other_features = VersionedTransaction {
+            features: 0b100,
+            ..versioned.clone()
+        };
+        assert_ne!(other_features.hash(), versioned.hash());
+    }
 }
@@ -1,7 +1,9 @@
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    chain::{Block, CheckpointBeacon, Hashable, InventoryEntry},
+    chain::{Block, CheckpointBeacon, Hash, Hashable, InventoryEntry},
     proto::{schema::witnet, ProtobufConvert},
     transaction::Transaction,
 };
@@ -113,6 +115,48 @@ pub struct Version {
     pub user_agent: String,
     pub last_epoch: u32,
     pub nonce: u64,
+    /// Network of the sender, e.g. `testnet-3`. Empty if the sender does not advertise it
+    pub network: String,
+    /// SHA256 hash of the genesis block of the sender. Empty if the sender does not advertise it
+    pub genesis_hash: Vec<u8>,
+}
+
+impl Version {
+    /// Network the sender belongs to, `None` if the sender does not advertise it, as the nodes
+    /// older than the `network` and `genesis_hash` fields do
+    pub fn network_id(&self) -> Option<NetworkId> {
+        if self.network.is_empty() && self.genesis_hash.is_empty() {
+            return None;
+        }
+
+        // A malformed genesis hash does not match any network
+        let mut genesis_hash = [0; 32];
+        if self.genesis_hash.len() == genesis_hash.len() {
+            genesis_hash.copy_from_slice(&self.genesis_hash);
+        }
+
+        Some(NetworkId {
+            network: self.network.clone(),
+            genesis_hash: Hash::SHA256(genesis_hash),
+        })
+    }
+}
+
+/// Identifier of the network a node belongs to, exchanged in the version handshake so that nodes
+/// of different networks can tell why they refuse to connect to each other.
+#[derive(Debug, Default, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkId {
+    /// Name of the network: `mainnet`, `testnet-1`, `testnet-3`...
+    pub network: String,
+    /// Hash of the genesis block
+    pub genesis_hash: Hash,
+}
+
+impl fmt::Display for NetworkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (genesis block {})", self.network, self.genesis_hash)
+    }
 }
 
 ///////////////////////////////////////////////////////////
@@ -0,0 +1,468 @@
+//! Activation of protocol upgrades signaled by miners through the version bits of the block
+//! headers.
+//!
+//! Every protocol upgrade is assigned a bit of the block version. Once the start epoch of an
+//! upgrade is reached, miners set its bit in the blocks they mine, and the signals are counted in
+//! windows of `SIGNALING_WINDOW` epochs. When the blocks signaling an upgrade in a window reach
+//! `SIGNALING_THRESHOLD` percent of the epochs of the window, the upgrade is locked in, and it
+//! becomes active one window later. Epochs without a block count as not signaling, so a window in
+//! which few blocks were consolidated cannot lock an upgrade in. If the timeout epoch is reached before that, the upgrade fails.
+//!
+//! An active upgrade keeps the epoch in which it became active, so that blocks older than the
+//! activation, e.g. those validated again during a deep reorganization, are validated with the
+//! rules and the consensus constants that were in force when they were mined.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chain::{ConsensusConstants, Epoch};
+
+/// Number of epochs of a signaling window
+pub const SIGNALING_WINDOW: Epoch = 2016;
+
+/// Percentage of the epochs of a window whose blocks must signal an upgrade to lock it in
+pub const SIGNALING_THRESHOLD: u32 = 90;
+
+/// Mask of the top bits of the block version, which tell whether the version signals upgrades
+pub const VERSION_BITS_TOP_MASK: u32 = 0xE000_0000;
+
+/// Value of the top bits of the block versions that signal upgrades
+pub const VERSION_BITS_TOP: u32 = 0x2000_0000;
+
+/// Definition of a protocol upgrade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolUpgrade {
+    /// Name of the upgrade
+    pub name: &'static str,
+    /// Bit of the block version used to signal the upgrade
+    pub bit: u8,
+    /// Epoch from which the upgrade can be signaled
+    pub start_epoch: Epoch,
+    /// Epoch from which the upgrade fails if it has not been locked in
+    pub timeout_epoch: Epoch,
+    /// Changes of the consensus constants made by the upgrade, if any
+    pub consensus_changes: Option<fn(ConsensusConstants) -> ConsensusConstants>,
+}
+
+/// Statistical reducers and filters of the RAD engine: median, median absolute deviation,
+/// trimmed mean, quantiles and the median absolute deviation filter.
+///
+/// Data requests using them are only valid once the upgrade is active. It has not been scheduled
+/// yet, so it is not part of `PROTOCOL_UPGRADES`.
+pub const RAD_STATISTICS: &str = "rad_statistics";
+
+/// Fallback sources of the retrievals, tried in order when the main source fails.
+///
+/// Data requests using them are only valid once the upgrade is active. It has not been scheduled
+/// yet, so it is not part of `PROTOCOL_UPGRADES`.
+pub const RAD_RETRIEVE_FALLBACK: &str = "rad_retrieve_fallback";
+
+/// Deterministic handling of the HTTP redirects and compressed responses of the retrievals.
+///
+/// Until the upgrade is active, witnesses keep the default behavior of their HTTP client, so that
+/// they agree with the witnesses that do not know the upgrade. It has not been scheduled yet, so
+/// it is not part of `PROTOCOL_UPGRADES`.
+pub const RAD_HTTP_POLICY: &str = "rad_http_policy";
+
+/// Protocol upgrades known by this node.
+///
+/// No upgrades have been scheduled yet.
+pub const PROTOCOL_UPGRADES: &[ProtocolUpgrade] = &[];
+
+/// Activation status of a protocol upgrade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpgradeStatus {
+    /// The start epoch has not been reached yet
+    Defined,
+    /// Miners are signaling the upgrade
+    Started,
+    /// The signaling threshold has been reached, the upgrade is active from the next window
+    LockedIn,
+    /// The upgrade rules are enforced
+    Active,
+    /// The timeout was reached before locking the upgrade in
+    Failed,
+}
+
+impl Default for UpgradeStatus {
+    fn default() -> Self {
+        UpgradeStatus::Defined
+    }
+}
+
+/// Signaling state of a protocol upgrade
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeState {
+    /// Current status
+    pub status: UpgradeStatus,
+    /// Epoch of the first window with the current status
+    pub since: Epoch,
+    /// Number of blocks in the current window
+    pub window_blocks: u32,
+    /// Number of blocks signaling the upgrade in the current window
+    pub window_signals: u32,
+}
+
+/// Signaling state of all the protocol upgrades, updated with every consolidated block
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolUpgrades {
+    /// Index of the current signaling window
+    window: Option<Epoch>,
+    /// State of each upgrade, by name
+    states: BTreeMap<String, UpgradeState>,
+}
+
+/// Activation status of a protocol upgrade, as returned by the `getProtocolUpgrades` method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeInfo {
+    pub name: String,
+    pub bit: u8,
+    pub start_epoch: Epoch,
+    pub timeout_epoch: Epoch,
+    #[serde(flatten)]
+    pub state: UpgradeState,
+}
+
+impl ProtocolUpgrades {
+    /// Count the signals of a consolidated block, updating the status of the upgrades when the
+    /// block starts a new signaling window.
+    pub fn process_block(&mut self, upgrades: &[ProtocolUpgrade], epoch: Epoch, version: u32) {
+        let window = epoch / SIGNALING_WINDOW;
+        if self.window != Some(window) {
+            self.window = Some(window);
+            let window_start = window * SIGNALING_WINDOW;
+            for upgrade in upgrades {
+                let state = self.states.entry(upgrade.name.to_string()).or_default();
+                let status = next_status(upgrade, state, window_start);
+                if status != state.status {
+                    state.status = status;
+                    state.since = window_start;
+                }
+                state.window_blocks = 0;
+                state.window_signals = 0;
+            }
+        }
+
+        for upgrade in upgrades {
+            let state = self.states.entry(upgrade.name.to_string()).or_default();
+            if state.status == UpgradeStatus::Started {
+                state.window_blocks += 1;
+                if signals(version, upgrade.bit) {
+                    state.window_signals += 1;
+                }
+            }
+        }
+    }
+
+    /// Block version signaling all the upgrades that are being voted.
+    pub fn block_version(&self, upgrades: &[ProtocolUpgrade], epoch: Epoch) -> u32 {
+        upgrades
+            .iter()
+            .filter(|upgrade| {
+                epoch >= upgrade.start_epoch
+                    && epoch < upgrade.timeout_epoch
+                    && match self.status(upgrade.name) {
+                        UpgradeStatus::Defined | UpgradeStatus::Started => true,
+                        _ => false,
+                    }
+            })
+            .fold(VERSION_BITS_TOP, |version, upgrade| {
+                version | (1 << upgrade.bit)
+            })
+    }
+
+    /// Current status of an upgrade.
+    pub fn status(&self, name: &str) -> UpgradeStatus {
+        self.states
+            .get(name)
+            .map(|state| state.status)
+            .unwrap_or_default()
+    }
+
+    /// Whether the rules of an upgrade must be enforced.
+    pub fn is_active(&self, name: &str) -> bool {
+        self.status(name) == UpgradeStatus::Active
+    }
+
+    /// Whether the rules of an upgrade must be enforced in the given epoch, which can be older
+    /// than its activation.
+    pub fn is_active_at(&self, name: &str, epoch: Epoch) -> bool {
+        self.states
+            .get(name)
+            .map_or(false, |state| is_active_at(state, epoch))
+    }
+
+    /// Names of the upgrades whose rules are enforced in the given epoch, in the order they
+    /// became active.
+    pub fn active_at(&self, epoch: Epoch) -> Vec<&str> {
+        let mut active: Vec<(&str, Epoch)> = self
+            .states
+            .iter()
+            .filter(|(_, state)| is_active_at(state, epoch))
+            .map(|(name, state)| (name.as_str(), state.since))
+            .collect();
+        active.sort_by_key(|(_, since)| *since);
+
+        active.into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Consensus constants in force in the given epoch: the constants of the chain with the
+    /// changes of the given upgrades that are active in that epoch, applied in the order they
+    /// became active.
+    pub fn consensus_constants_at(
+        &self,
+        upgrades: &[ProtocolUpgrade],
+        chain_constants: &ConsensusConstants,
+        epoch: Epoch,
+    ) -> ConsensusConstants {
+        let mut consensus_constants = chain_constants.clone();
+        for name in self.active_at(epoch) {
+            if let Some(changes) = upgrades
+                .iter()
+                .find(|upgrade| upgrade.name == name)
+                .and_then(|upgrade| upgrade.consensus_changes)
+            {
+                consensus_constants = changes(consensus_constants);
+            }
+        }
+
+        consensus_constants
+    }
+
+    /// Activation status of all the given upgrades.
+    pub fn info(&self, upgrades: &[ProtocolUpgrade]) -> Vec<UpgradeInfo> {
+        upgrades
+            .iter()
+            .map(|upgrade| UpgradeInfo {
+                name: upgrade.name.to_string(),
+                bit: upgrade.bit,
+                start_epoch: upgrade.start_epoch,
+                timeout_epoch: upgrade.timeout_epoch,
+                state: self.states.get(upgrade.name).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// Whether an upgrade in the given state is enforced in the given epoch. Active is the last status
+/// of an upgrade, so it has been active since the epoch in which its status last changed.
+fn is_active_at(state: &UpgradeState, epoch: Epoch) -> bool {
+    state.status == UpgradeStatus::Active && state.since <= epoch
+}
+
+/// Whether a block version signals the upgrade using the given bit.
+pub fn signals(version: u32, bit: u8) -> bool {
+    (version & VERSION_BITS_TOP_MASK) == VERSION_BITS_TOP && (version & (1 << bit)) != 0
+}
+
+/// Status of an upgrade in the window starting at `window_start`, given its state in the
+/// previous window.
+fn next_status(
+    upgrade: &ProtocolUpgrade,
+    state: &UpgradeState,
+    window_start: Epoch,
+) -> UpgradeStatus {
+    match state.status {
+        UpgradeStatus::Defined if window_start >= upgrade.timeout_epoch => UpgradeStatus::Failed,
+        UpgradeStatus::Defined if window_start >= upgrade.start_epoch => UpgradeStatus::Started,
+        UpgradeStatus::Started
+            if u64::from(state.window_signals) * 100
+                >= u64::from(SIGNALING_WINDOW) * u64::from(SIGNALING_THRESHOLD) =>
+        {
+            UpgradeStatus::LockedIn
+        }
+        UpgradeStatus::Started if window_start >= upgrade.timeout_epoch => UpgradeStatus::Failed,
+        UpgradeStatus::LockedIn => UpgradeStatus::Active,
+        status => status,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UPGRADE: ProtocolUpgrade = ProtocolUpgrade {
+        name: "test",
+        bit: 1,
+        start_epoch: SIGNALING_WINDOW,
+        timeout_epoch: 10 * SIGNALING_WINDOW,
+        consensus_changes: Some(double_max_block_weight),
+    };
+
+    fn double_max_block_weight(consensus_constants: ConsensusConstants) -> ConsensusConstants {
+        ConsensusConstants {
+            max_block_weight: 2 * consensus_constants.max_block_weight,
+            ..consensus_constants
+        }
+    }
+
+    fn consensus_constants() -> ConsensusConstants {
+        ConsensusConstants {
+            checkpoint_zero_timestamp: 0,
+            checkpoints_period: 0,
+            genesis_hash: Default::default(),
+            max_block_weight: 1000,
+            activity_period: 0,
+            reputation_expire_alpha_diff: 0,
+            reputation_issuance: 0,
+            reputation_issuance_stop: 0,
+            reputation_penalization_factor: 0.0,
+        }
+    }
+
+    #[test]
+    fn upgrade_activates_after_threshold() {
+        let upgrades = [UPGRADE];
+        let mut state = ProtocolUpgrades::default();
+        let signaling = VERSION_BITS_TOP | (1 << UPGRADE.bit);
+
+        state.process_block(&upgrades, 0, signaling);
+        assert_eq!(state.status("test"), UpgradeStatus::Defined);
+
+        // The first window after the start epoch only starts the signaling
+        state.process_block(&upgrades, SIGNALING_WINDOW, signaling);
+        assert_eq!(state.status("test"), UpgradeStatus::Started);
+        for epoch in SIGNALING_WINDOW + 1..2 * SIGNALING_WINDOW {
+            // 1 of every 20 blocks does not signal the upgrade
+            let version = if epoch % 20 == 0 { 0 } else { signaling };
+            state.process_block(&upgrades, epoch, version);
+        }
+
+        state.process_block(&upgrades, 2 * SIGNALING_WINDOW, 0);
+        assert_eq!(state.status("test"), UpgradeStatus::LockedIn);
+        assert!(!state.is_active("test"));
+
+        state.process_block(&upgrades, 3 * SIGNALING_WINDOW, 0);
+        assert!(state.is_active("test"));
+    }
+
+    #[test]
+    fn upgrade_rules_are_not_applied_before_activation() {
+        let upgrades = [UPGRADE];
+        let mut state = ProtocolUpgrades::default();
+        let signaling = VERSION_BITS_TOP | (1 << UPGRADE.bit);
+
+        for epoch in SIGNALING_WINDOW..2 * SIGNALING_WINDOW {
+            state.process_block(&upgrades, epoch, signaling);
+        }
+        state.process_block(&upgrades, 2 * SIGNALING_WINDOW, 0);
+        assert!(!state.is_active_at("test", 2 * SIGNALING_WINDOW));
+
+        state.process_block(&upgrades, 3 * SIGNALING_WINDOW, 0);
+        assert!(state.is_active("test"));
+        assert!(!state.is_active_at("test", 3 * SIGNALING_WINDOW - 1));
+        assert!(state.is_active_at("test", 3 * SIGNALING_WINDOW));
+        assert!(state.is_active_at("test", 4 * SIGNALING_WINDOW));
+        assert!(state.active_at(3 * SIGNALING_WINDOW - 1).is_empty());
+        assert_eq!(state.active_at(3 * SIGNALING_WINDOW), vec!["test"]);
+    }
+
+    #[test]
+    fn upgrade_already_active_is_applied_from_its_activation() {
+        // State of a chain in which the upgrade was already active before being validated again
+        let mut states = BTreeMap::new();
+        states.insert(
+            UPGRADE.name.to_string(),
+            UpgradeState {
+                status: UpgradeStatus::Active,
+                since: 5 * SIGNALING_WINDOW,
+                ..UpgradeState::default()
+            },
+        );
+        let state = ProtocolUpgrades {
+            window: Some(7),
+            states,
+        };
+
+        assert!(!state.is_active_at("test", 5 * SIGNALING_WINDOW - 1));
+        assert!(state.is_active_at("test", 5 * SIGNALING_WINDOW));
+        assert!(state.is_active_at("test", 7 * SIGNALING_WINDOW));
+    }
+
+    #[test]
+    fn consensus_constants_change_from_activation() {
+        let upgrades = [UPGRADE];
+        let mut states = BTreeMap::new();
+        states.insert(
+            UPGRADE.name.to_string(),
+            UpgradeState {
+                status: UpgradeStatus::Active,
+                since: 3 * SIGNALING_WINDOW,
+                ..UpgradeState::default()
+            },
+        );
+        let state = ProtocolUpgrades {
+            window: Some(3),
+            states,
+        };
+        let chain_constants = consensus_constants();
+
+        assert_eq!(
+            state.consensus_constants_at(&upgrades, &chain_constants, 3 * SIGNALING_WINDOW - 1),
+            chain_constants
+        );
+        assert_eq!(
+            state
+                .consensus_constants_at(&upgrades, &chain_constants, 3 * SIGNALING_WINDOW)
+                .max_block_weight,
+            2000
+        );
+
+        // Upgrades that do not change the consensus constants keep them
+        let upgrades = [ProtocolUpgrade {
+            consensus_changes: None,
+            ..UPGRADE
+        }];
+        assert_eq!(
+            state.consensus_constants_at(&upgrades, &chain_constants, 3 * SIGNALING_WINDOW),
+            chain_constants
+        );
+    }
+
+    #[test]
+    fn upgrade_below_threshold_fails_at_timeout() {
+        let upgrades = [UPGRADE];
+        let mut state = ProtocolUpgrades::default();
+        let signaling = VERSION_BITS_TOP | (1 << UPGRADE.bit);
+
+        for window in 1..=10 {
+            // Only 1 of every 2 blocks signals the upgrade
+            state.process_block(&upgrades, window * SIGNALING_WINDOW, signaling);
+            state.process_block(&upgrades, window * SIGNALING_WINDOW + 1, 0);
+        }
+
+        assert_eq!(state.status("test"), UpgradeStatus::Failed);
+    }
+
+    #[test]
+    fn upgrade_is_not_locked_in_by_a_partial_window() {
+        let upgrades = [UPGRADE];
+        let mut state = ProtocolUpgrades::default();
+        let signaling = VERSION_BITS_TOP | (1 << UPGRADE.bit);
+
+        // Every block signals the upgrade, but there are blocks in only half of the epochs
+        for epoch in (SIGNALING_WINDOW..2 * SIGNALING_WINDOW).step_by(2) {
+            state.process_block(&upgrades, epoch, signaling);
+        }
+        state.process_block(&upgrades, 2 * SIGNALING_WINDOW, signaling);
+
+        assert_eq!(state.status("test"), UpgradeStatus::Started);
+    }
+
+    #[test]
+    fn block_version_signals_started_upgrades() {
+        let upgrades = [UPGRADE];
+        let state = ProtocolUpgrades::default();
+
+        assert_eq!(state.block_version(&upgrades, 0), VERSION_BITS_TOP);
+        assert_eq!(
+            state.block_version(&upgrades, SIGNALING_WINDOW),
+            VERSION_BITS_TOP | (1 << UPGRADE.bit)
+        );
+        assert!(signals(state.block_version(&upgrades, SIGNALING_WINDOW), 1));
+        assert!(!signals(1 << UPGRADE.bit, 1));
+    }
+}
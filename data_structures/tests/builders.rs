@@ -154,6 +154,8 @@ fn builders_build_version() {
         user_agent: USER_AGENT.to_string(),
         last_epoch: hardcoded_last_epoch,
         nonce: 1234,
+        network: "testnet-3".to_string(),
+        genesis_hash: vec![1; 32],
     });
     let msg = Message {
         kind: version_cmd,
@@ -163,8 +165,13 @@ fn builders_build_version() {
     // Build message
     let sender_sock_addr = "192.168.1.1:8000".parse().unwrap();
     let receiver_sock_addr = "192.168.1.2:8001".parse().unwrap();
+    let network_id = NetworkId {
+        network: "testnet-3".to_string(),
+        genesis_hash: Hash::SHA256([1; 32]),
+    };
     let built_msg = Message::build_version(
         0xABCD,
+        &network_id,
         sender_sock_addr,
         receiver_sock_addr,
         hardcoded_last_epoch,
@@ -180,6 +187,8 @@ fn builders_build_version() {
             receiver_address,
             user_agent,
             last_epoch,
+            network,
+            genesis_hash,
             ..
         }) => assert!(
             *version == PROTOCOL_VERSION
@@ -188,6 +197,8 @@ fn builders_build_version() {
                 && *receiver_address == receiver_addr
                 && user_agent == USER_AGENT
                 && *last_epoch == hardcoded_last_epoch
+                && *network == network_id.network
+                && *genesis_hash == vec![1; 32]
         ),
         _ => panic!("Some field/s do not match the expected value"),
     };
@@ -300,13 +300,17 @@ fn message_version_to_bytes() {
             user_agent: "asdf".to_string(),
             last_epoch: 8,
             nonce: 1,
+            network: "testnet-3".to_string(),
+            genesis_hash: vec![1; 32],
         }),
         magic: 1,
     };
     let expected_buf: Vec<u8> = [
-        8, 1, 18, 55, 10, 53, 8, 2, 16, 123, 25, 4, 0, 0, 0, 0, 0, 0, 0, 34, 8, 10, 6, 192, 168, 1,
-        1, 31, 64, 42, 8, 10, 6, 192, 168, 1, 2, 31, 65, 50, 4, 97, 115, 100, 102, 61, 8, 0, 0, 0,
-        65, 1, 0, 0, 0, 0, 0, 0, 0,
+        8, 1, 18, 100, 10, 98, 8, 2, 16, 123, 25, 4, 0, 0, 0, 0, 0, 0, 0, 34, 8, 10, 6, 192, 168,
+        1, 1, 31, 64, 42, 8, 10, 6, 192, 168, 1, 2, 31, 65, 50, 4, 97, 115, 100, 102, 61, 8, 0, 0,
+        0, 65, 1, 0, 0, 0, 0, 0, 0, 0, 74, 9, 116, 101, 115, 116, 110, 101, 116, 45, 51, 82, 32, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1,
     ]
     .to_vec();
     let result: Vec<u8> = msg.to_pb_bytes().unwrap();
@@ -334,10 +338,27 @@ fn message_version_from_bytes() {
             user_agent: "asdf".to_string(),
             last_epoch: 8,
             nonce: 1,
+            network: "testnet-3".to_string(),
+            genesis_hash: vec![1; 32],
         }),
         magic: 1,
     };
 
+    let buf: Vec<u8> = [
+        8, 1, 18, 100, 10, 98, 8, 2, 16, 123, 25, 4, 0, 0, 0, 0, 0, 0, 0, 34, 8, 10, 6, 192, 168,
+        1, 1, 31, 64, 42, 8, 10, 6, 192, 168, 1, 2, 31, 65, 50, 4, 97, 115, 100, 102, 61, 8, 0, 0,
+        0, 65, 1, 0, 0, 0, 0, 0, 0, 0, 74, 9, 116, 101, 115, 116, 110, 101, 116, 45, 51, 82, 32, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1,
+    ]
+    .to_vec();
+
+    assert_eq!(Message::from_pb_bytes(&buf).unwrap(), expected_msg);
+}
+
+#[test]
+fn message_version_without_network_from_bytes() {
+    // Version message of a node that does not advertise its network and genesis hash
     let buf: Vec<u8> = [
         8, 1, 18, 55, 10, 53, 8, 2, 16, 123, 25, 4, 0, 0, 0, 0, 0, 0, 0, 34, 8, 10, 6, 192, 168, 1,
         1, 31, 64, 42, 8, 10, 6, 192, 168, 1, 2, 31, 65, 50, 4, 97, 115, 100, 102, 61, 8, 0, 0, 0,
@@ -345,7 +366,14 @@ fn message_version_from_bytes() {
     ]
     .to_vec();
 
-    assert_eq!(Message::from_pb_bytes(&buf).unwrap(), expected_msg);
+    match Message::from_pb_bytes(&buf).unwrap().kind {
+        Command::Version(version) => {
+            assert_eq!(version.network, "");
+            assert!(version.genesis_hash.is_empty());
+            assert_eq!(version.network_id(), None);
+        }
+        _ => panic!("Expected a Version message"),
+    }
 }
 
 #[test]
@@ -368,6 +396,8 @@ fn message_version_encode_decode() {
             user_agent: "asdf".to_string(),
             last_epoch: 8,
             nonce: 1,
+            network: "testnet-3".to_string(),
+            genesis_hash: vec![1; 32],
         }),
         magic: 1,
     };
@@ -96,6 +96,7 @@ pub struct Request {
     method: String,
     params: Value,
     timeout: Duration,
+    correlation_id: Option<String>,
 }
 
 impl Request {
@@ -105,6 +106,7 @@ impl Request {
             method: method.into(),
             params: Value::Null,
             timeout: Duration::from_secs(60),
+            correlation_id: None,
         }
     }
 
@@ -125,6 +127,14 @@ impl Request {
         self.timeout = duration;
         self
     }
+
+    /// Send the correlation id of the request that originated this one, so the server can relate
+    /// its logs to the ones of the client. It is sent as the `correlationId` member of the params,
+    /// so it is only sent along with named params.
+    pub fn correlation_id<T: ToString>(mut self, correlation_id: Option<T>) -> Self {
+        self.correlation_id = correlation_id.map(|id| id.to_string());
+        self
+    }
 }
 
 impl Message for Request {
@@ -138,8 +148,9 @@ impl Handler<Request> for JsonRpcClient {
         &mut self,
         Request {
             method,
-            params,
+            mut params,
             timeout,
+            correlation_id,
         }: Request,
         ctx: &mut Self::Context,
     ) -> Self::Result {
@@ -148,6 +159,10 @@ impl Handler<Request> for JsonRpcClient {
             ctx.notify(Subscribe);
         }
 
+        if let (Some(params), Some(id)) = (params.as_object_mut(), correlation_id) {
+            params.insert("correlationId".to_string(), Value::String(id));
+        }
+
         let fut = self
             .send_request(method, params)
             .into_actor(self)
@@ -190,6 +205,14 @@ impl Handler<Subscribe> for JsonRpcClient {
 
     fn handle(&mut self, _msg: Subscribe, ctx: &mut Self::Context) -> Self::Result {
         if let Some(SetSubscriber(recipient, request)) = self.subscriber.take() {
+            // Cancel the previous subscription so the server does not keep notifying it
+            if let Some(id) = self.subscription_id.take() {
+                self.send_request("witnet_unsubscribe".to_string(), Value::from(vec![id]))
+                    .map(|_| log::debug!("Client subscription cancelled"))
+                    .map_err(|err| log::debug!("Couldn't cancel client subscription: {}", err))
+                    .into_actor(self)
+                    .spawn(ctx);
+            }
             ctx.address()
                 .send(request.clone())
                 .map_err(|err| log::error!("Couldn't subscribe: {}", err))
@@ -3,7 +3,7 @@ use actix::prelude::*;
 
 use super::{
     handlers::{EpochPayload, EveryEpochPayload},
-    ChainManager,
+    notify_rollback, ChainManager, ConsensusThresholds,
 };
 use crate::actors::{
     epoch_manager::{EpochManager, EpochManagerError::CheckpointZeroInTheFuture},
@@ -14,7 +14,7 @@ use crate::config_mngr;
 use crate::signature_mngr;
 use crate::storage_mngr;
 use witnet_data_structures::{
-    chain::{ChainInfo, ChainState, CheckpointBeacon, ReputationEngine},
+    chain::{ChainInfo, ChainState, CheckpointBeacon, LegacyChainState, ReputationEngine},
     vrf::VrfCtx,
 };
 
@@ -35,6 +35,9 @@ impl Actor for ChainManager {
 
         self.subscribe_to_epoch_manager(ctx);
 
+        // Pick up the consensus thresholds of a reloaded configuration
+        config_mngr::subscribe(ctx.address().recipient());
+
         self.get_pkh(ctx);
 
         self.vrf_ctx = VrfCtx::secp256k1()
@@ -56,9 +59,11 @@ impl ChainManager {
             // Get environment and consensus_constants parameters from config
             let environment = (&config.environment).clone();
             let consensus_constants = (&config.consensus_constants).clone();
+            let watched_pkhs = config.mining.watched_pkhs.clone();
             act.max_block_weight = consensus_constants.max_block_weight;
+            act.offline = config.connections.offline;
 
-            storage_mngr::get::<_, ChainState>(&CHAIN_STATE_KEY)
+            storage_mngr::get_or_legacy::<_, ChainState, LegacyChainState>(&CHAIN_STATE_KEY)
                 .into_actor(act)
                 .map_err(|e, _, _| error!("Error while getting chain state from storage: {}", e))
                 .and_then(move |chain_state_from_storage, act, _ctx| {
@@ -133,23 +138,61 @@ impl ChainManager {
                         }
                     }
 
+                    // Start tracking the identities watched in the configuration
+                    for pkh in watched_pkhs {
+                        if act.chain_state.watch_pkh(pkh) {
+                            act.last_chain_state.watch_pkh(pkh);
+                            debug!("Watching the unspent outputs of {}", pkh);
+                        }
+                    }
+
                     let chain_info = act.chain_state.chain_info.as_ref().unwrap();
                     info!("Actual ChainState CheckpointBeacon: epoch ({}), hash_block ({})",
                           chain_info.highest_block_checkpoint.checkpoint,
                           chain_info.highest_block_checkpoint.hash_prev_block);
 
+                    let restored_beacon = chain_info.highest_block_checkpoint;
+
+                    // The alpha of the blocks discarded by a rollback, if any, is forgotten
+                    act.truncate_alpha_history(restored_beacon.checkpoint);
+
+                    // Notify the rollback that restored this chain state, if any
+                    if let Some(discarded_beacon) = act.discarded_beacon.take() {
+                        notify_rollback(discarded_beacon, restored_beacon);
+                    }
+
                     fut::ok(())
                 })
                 .spawn(ctx);
 
-            // Store the genesis block hash
-            act.genesis_block_hash = config.consensus_constants.genesis_hash;
-
             // Do not start the MiningManager if the configuration disables it
             act.mining_enabled = config.mining.enabled;
 
-            // Get consensus parameter from config
-            act.consensus_c = config.connections.consensus_c;
+            // Get the policy for including old transactions in blocks
+            act.old_transactions_weight_percentage =
+                config.mining.old_transactions_weight_percentage;
+            act.old_transactions_min_age = config.mining.old_transactions_min_age;
+
+            // Get the limits of the transactions pool
+            act.transactions_pool_expiry = config.mining.transactions_pool_expiry;
+            act.transactions_pool_max_size = config.mining.transactions_pool_max_size;
+
+            // Get the limit of data requests committed to per epoch
+            act.data_request_max_commits_per_epoch =
+                config.mining.data_request_max_commits_per_epoch;
+
+            // Get the thresholds of the reputation expiration alerts
+            act.reputation_alert_threshold = config.mining.reputation_alert_threshold;
+            act.reputation_alert_epochs = config.mining.reputation_alert_epochs;
+
+            // Check the chain state invariants also in release builds if enabled
+            act.check_invariants = config.storage.check_invariants;
+
+            // Get the tolerance for block candidates from epochs that have not started yet
+            act.clock_tolerance = config.connections.clock_tolerance;
+
+            // Get consensus thresholds from config
+            act.consensus_thresholds = ConsensusThresholds::from_config(&config.connections);
 
             if act.mining_enabled {
                 debug!("Mining enabled!");
@@ -216,6 +259,18 @@ impl ChainManager {
             .wait(ctx);
     }
 
+    /// Update the consensus thresholds if they have changed in the configuration, so they can be
+    /// adjusted by reloading the configuration without restarting the node
+    pub fn update_consensus_thresholds(&mut self, consensus_thresholds: ConsensusThresholds) {
+        if consensus_thresholds != self.consensus_thresholds {
+            info!(
+                "Consensus thresholds changed from {:?} to {:?}",
+                self.consensus_thresholds, consensus_thresholds
+            );
+            self.consensus_thresholds = consensus_thresholds;
+        }
+    }
+
     /// Load public key hash from signature manager
     fn get_pkh(&mut self, ctx: &mut Context<Self>) {
         signature_mngr::pkh()
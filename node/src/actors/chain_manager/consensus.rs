@@ -0,0 +1,292 @@
+//! Decisions taken by the `ChainManager` from the beacons announced by its peers.
+//!
+//! The decisions do not depend on the actor, so the same code is used by the `PeersBeacons`
+//! handler and by the consensus simulation, and can be tested without a network.
+use std::net::SocketAddr;
+
+use serde::Serialize;
+
+use witnet_data_structures::chain::CheckpointBeacon;
+
+use super::StateMachine;
+use crate::utils::mode_consensus;
+
+/// What the `ChainManager` has to do to follow the consensus of its peers
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "action", content = "beacon")]
+pub enum ConsensusAction {
+    /// Nothing to do
+    None,
+    /// The local chain forked from the consensus, so the chain state has to be restored from
+    /// storage
+    Rollback,
+    /// Consolidate the block candidate of the consensus beacon, or synchronize up to the
+    /// consensus beacon if there is no such candidate or it is invalid
+    ConsolidateCandidate(CheckpointBeacon),
+    /// Synchronize the local chain up to the consensus beacon
+    Synchronize(CheckpointBeacon),
+}
+
+/// Outcome of evaluating the beacons of the peers
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsensusDecision {
+    /// Beacon announced by enough peers, if any
+    pub consensus_beacon: Option<CheckpointBeacon>,
+    /// Next state of the state machine. After consolidating the candidate of a
+    /// `ConsolidateCandidate` action, it is `Synced` instead.
+    pub state: StateMachine,
+    /// Action needed to follow the consensus
+    pub action: ConsensusAction,
+    /// Peers out of consensus, which have to be unregistered
+    pub peers_to_unregister: Vec<SocketAddr>,
+}
+
+/// Decide how to follow the beacons announced by the peers, given the state of the state
+/// machine, the beacon of the local chain and the consensus threshold (in %) of that state.
+pub fn peers_beacons_consensus(
+    state: StateMachine,
+    our_beacon: CheckpointBeacon,
+    pb: Vec<(SocketAddr, CheckpointBeacon)>,
+    consensus_threshold: u32,
+) -> ConsensusDecision {
+    let consensus_beacon =
+        mode_consensus(pb.iter().map(|(_p, b)| b), consensus_threshold as usize).cloned();
+    let peers_out_of = |beacon: CheckpointBeacon| -> Vec<SocketAddr> {
+        pb.iter()
+            .filter_map(|(p, b)| if *b != beacon { Some(*p) } else { None })
+            .collect()
+    };
+    let all_peers = || pb.iter().map(|(p, _b)| *p).collect();
+
+    match (state, consensus_beacon) {
+        // No consensus: unregister all peers
+        (StateMachine::WaitingConsensus, None) | (StateMachine::Synchronizing, None) => {
+            ConsensusDecision {
+                consensus_beacon,
+                state,
+                action: ConsensusAction::None,
+                peers_to_unregister: all_peers(),
+            }
+        }
+        (StateMachine::WaitingConsensus, Some(beacon))
+        | (StateMachine::Synchronizing, Some(beacon)) => {
+            let (state, action) = if our_beacon == beacon {
+                // Already synchronized
+                (StateMachine::Synced, ConsensusAction::None)
+            } else if our_beacon.checkpoint == beacon.checkpoint
+                && our_beacon.hash_prev_block != beacon.hash_prev_block
+            {
+                // Fork case
+                log::warn!(
+                    "[CONSENSUS]: We are on {:?} but the network is on {:?}",
+                    our_beacon,
+                    beacon
+                );
+                (StateMachine::WaitingConsensus, ConsensusAction::Rollback)
+            } else if state == StateMachine::WaitingConsensus {
+                (
+                    StateMachine::Synchronizing,
+                    ConsensusAction::ConsolidateCandidate(beacon),
+                )
+            } else {
+                (
+                    StateMachine::Synchronizing,
+                    ConsensusAction::Synchronize(beacon),
+                )
+            };
+
+            ConsensusDecision {
+                consensus_beacon,
+                state,
+                action,
+                peers_to_unregister: peers_out_of(beacon),
+            }
+        }
+        (StateMachine::Synced, Some(beacon)) if beacon == our_beacon => ConsensusDecision {
+            consensus_beacon,
+            state: StateMachine::Synced,
+            action: ConsensusAction::None,
+            peers_to_unregister: peers_out_of(our_beacon),
+        },
+        (StateMachine::Synced, Some(beacon)) => {
+            // We are out of consensus!
+            log::warn!(
+                "[CONSENSUS]: We are on {:?} but the network is on {:?}",
+                our_beacon,
+                beacon
+            );
+
+            ConsensusDecision {
+                consensus_beacon,
+                state: StateMachine::WaitingConsensus,
+                action: ConsensusAction::Rollback,
+                peers_to_unregister: peers_out_of(beacon),
+            }
+        }
+        (StateMachine::Synced, None) => {
+            if pb.is_empty() {
+                log::warn!("[CONSENSUS]: We have zero outbound peers");
+            } else {
+                // There is no consensus because of a tie, e.g. because each peer reports a
+                // different beacon
+                log::warn!(
+                    "[CONSENSUS]: We are on {:?} but the network has no consensus",
+                    our_beacon
+                );
+            }
+
+            // Unregister all peers to try to obtain a new set of trustworthy peers
+            ConsensusDecision {
+                consensus_beacon,
+                state: StateMachine::WaitingConsensus,
+                action: ConsensusAction::None,
+                peers_to_unregister: all_peers(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_data_structures::chain::Hash;
+
+    fn beacon(checkpoint: u32, byte: u8) -> CheckpointBeacon {
+        CheckpointBeacon {
+            checkpoint,
+            hash_prev_block: Hash::SHA256([byte; 32]),
+        }
+    }
+
+    fn peer(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    /// Beacons of 4 peers: 3 of them agree on `beacon(10, 1)`
+    fn peers_beacons() -> Vec<(SocketAddr, CheckpointBeacon)> {
+        vec![
+            (peer(1), beacon(10, 1)),
+            (peer(2), beacon(10, 1)),
+            (peer(3), beacon(10, 1)),
+            (peer(4), beacon(9, 2)),
+        ]
+    }
+
+    /// Each vector is the state, our beacon, the consensus threshold and the expected state,
+    /// action and peers to unregister
+    #[test]
+    fn consensus_vectors() {
+        let vectors = vec![
+            // Waiting for consensus, with consensus on our beacon
+            (
+                StateMachine::WaitingConsensus,
+                beacon(10, 1),
+                60,
+                StateMachine::Synced,
+                ConsensusAction::None,
+                vec![peer(4)],
+            ),
+            // Waiting for consensus, behind the consensus
+            (
+                StateMachine::WaitingConsensus,
+                beacon(5, 3),
+                60,
+                StateMachine::Synchronizing,
+                ConsensusAction::ConsolidateCandidate(beacon(10, 1)),
+                vec![peer(4)],
+            ),
+            // Waiting for consensus, forked at the consensus checkpoint
+            (
+                StateMachine::WaitingConsensus,
+                beacon(10, 3),
+                60,
+                StateMachine::WaitingConsensus,
+                ConsensusAction::Rollback,
+                vec![peer(4)],
+            ),
+            // Waiting for consensus, with a threshold the majority does not reach
+            (
+                StateMachine::WaitingConsensus,
+                beacon(10, 1),
+                80,
+                StateMachine::WaitingConsensus,
+                ConsensusAction::None,
+                vec![peer(1), peer(2), peer(3), peer(4)],
+            ),
+            // Synchronizing, still behind the consensus
+            (
+                StateMachine::Synchronizing,
+                beacon(5, 3),
+                60,
+                StateMachine::Synchronizing,
+                ConsensusAction::Synchronize(beacon(10, 1)),
+                vec![peer(4)],
+            ),
+            // Synchronizing, without consensus the state does not change
+            (
+                StateMachine::Synchronizing,
+                beacon(5, 3),
+                80,
+                StateMachine::Synchronizing,
+                ConsensusAction::None,
+                vec![peer(1), peer(2), peer(3), peer(4)],
+            ),
+            // Synced, on the consensus beacon
+            (
+                StateMachine::Synced,
+                beacon(10, 1),
+                60,
+                StateMachine::Synced,
+                ConsensusAction::None,
+                vec![peer(4)],
+            ),
+            // Synced, out of consensus
+            (
+                StateMachine::Synced,
+                beacon(9, 2),
+                60,
+                StateMachine::WaitingConsensus,
+                ConsensusAction::Rollback,
+                vec![peer(4)],
+            ),
+            // Synced, without consensus
+            (
+                StateMachine::Synced,
+                beacon(10, 1),
+                80,
+                StateMachine::WaitingConsensus,
+                ConsensusAction::None,
+                vec![peer(1), peer(2), peer(3), peer(4)],
+            ),
+        ];
+
+        for (i, (state, our_beacon, threshold, next_state, action, mut unregistered)) in
+            vectors.into_iter().enumerate()
+        {
+            let mut decision =
+                peers_beacons_consensus(state, our_beacon, peers_beacons(), threshold);
+            decision.peers_to_unregister.sort();
+            unregistered.sort();
+
+            assert_eq!(decision.state, next_state, "vector {}", i);
+            assert_eq!(decision.action, action, "vector {}", i);
+            assert_eq!(decision.peers_to_unregister, unregistered, "vector {}", i);
+        }
+    }
+
+    #[test]
+    fn synced_without_peers() {
+        let decision = peers_beacons_consensus(StateMachine::Synced, beacon(10, 1), vec![], 60);
+
+        assert_eq!(
+            decision,
+            ConsensusDecision {
+                consensus_beacon: None,
+                state: StateMachine::WaitingConsensus,
+                action: ConsensusAction::None,
+                peers_to_unregister: vec![],
+            }
+        );
+    }
+}
@@ -1,35 +1,56 @@
-use actix::{fut::WrapFuture, prelude::*};
-use futures::Future;
+use actix::{dev::MessageResponse, fut::WrapFuture, prelude::*};
+use futures::{Future, Poll};
 use log;
 use std::cmp::Ordering;
 
 use witnet_data_structures::{
     chain::{
-        ChainState, CheckpointBeacon, DataRequestInfo, DataRequestReport, Epoch, Hash, Hashable,
-        InventoryItem, PublicKeyHash,
+        ChainState, CheckpointBeacon, DataRequestInfo, DataRequestReport, DataRequestStateSummary,
+        Epoch, Hash, Hashable, InventoryItem, PublicKeyHash, MAX_VT_UNCONFIRMED_ANCESTORS,
     },
     error::{ChainInfoError, TransactionError, TransactionError::DataRequestNotFound},
+    superblock::{ArsMerkleProof, Superblock},
     transaction::{DRTransaction, Transaction, VTTransaction},
+    upgrades::PROTOCOL_UPGRADES,
+};
+use witnet_util::{
+    correlation::{self, CorrelationId},
+    crash,
+    timestamp::get_timestamp,
 };
 use witnet_validations::validations::{
-    compare_blocks, validate_block, validate_commit_transaction, validate_dr_transaction,
-    validate_rad_request, validate_reveal_transaction, validate_vt_transaction, UtxoDiff,
+    compare_blocks, validate_block, validate_commit_transaction, validate_data_request_output,
+    validate_dr_transaction, validate_rad_request, validate_rad_request_upgrades,
+    validate_reveal_transaction, validate_vt_transaction, UtxoDiff,
 };
 
-use super::{ChainManager, ChainManagerError, StateMachine};
-use crate::actors::messages::{GetBalance, GetDataRequestReport};
+use super::{
+    mempool_transactions, ChainManager, ChainManagerError, ConsensusThresholds, StateMachine,
+    ORPHAN_BLOCK_MAX_AGE,
+};
+use crate::actors::messages::{
+    DataRequestCostEstimate, DropReason, EstimateDataRequestCost, GetArsMerkleProof, GetBalance,
+    GetDataRequestReport, GetDataRequestState, GetEpochSummary, GetMempool, GetMempoolResult,
+    GetProtocolUpgrades, GetReputationForecast, GetSuperblock, GetTransactionStatus, GetUtxoInfo,
+    GetWatchedPkhs, ReputationForecast, TransactionStatus, UnwatchPkh, UtxoInfo, UtxoMetadata,
+    WatchPkh,
+};
 use crate::{
     actors::{
-        chain_manager::transaction_factory,
+        chain_manager::{
+            consensus::{self, ConsensusAction},
+            transaction_factory,
+        },
+        mailbox,
         messages::{
-            AddBlocks, AddCandidates, AddTransaction, Anycast, Broadcast, BuildDrt, BuildVtt,
-            EpochNotification, GetBlocksEpochRange, GetHighestCheckpointBeacon, GetState,
-            PeersBeacons, SendLastBeacon, SessionUnitResult,
+            AddCandidates, AddTransaction, BlockTemplate, BuildBlockTemplate, BuildDrt, BuildVtt,
+            ConsolidateBatch, EpochNotification, GetBlocksEpochRange, GetHighestCheckpointBeacon,
+            GetState, PeersBeacons, SessionUnitResult, SyncFailed, Traced,
         },
-        sessions_manager::SessionsManager,
+        storage_keys::superblock_key,
     },
+    config_mngr::ConfigChanged,
     storage_mngr,
-    utils::mode_consensus,
 };
 
 pub const SYNCED_BANNER: &str = r"
@@ -43,6 +64,54 @@ pub const SYNCED_BANNER: &str = r"
 ////////////////////////////////////////////////////////////////////////////////////////
 // ACTOR MESSAGE HANDLERS
 ////////////////////////////////////////////////////////////////////////////////////////
+/// Handler for messages traced with the correlation id of the request that originated them
+impl<M> Handler<Traced<M>> for ChainManager
+where
+    M: Message + 'static,
+    ChainManager: Handler<M>,
+    <ChainManager as Handler<M>>::Result: MessageResponse<ChainManager, Traced<M>>,
+{
+    type Result = <ChainManager as Handler<M>>::Result;
+
+    fn handle(&mut self, msg: Traced<M>, ctx: &mut Context<Self>) -> Self::Result {
+        let _entered = msg.correlation_id.map(correlation::enter);
+
+        <Self as Handler<M>>::handle(self, msg.message, ctx)
+    }
+}
+
+/// Actor future polled with the correlation id of the request that created it, so the log lines
+/// of its continuations keep the id. Counterpart of `correlation::Correlated` for actor futures
+struct CorrelatedActorFuture<F> {
+    correlation_id: Option<CorrelationId>,
+    inner: F,
+}
+
+impl<F> CorrelatedActorFuture<F> {
+    fn new(inner: F) -> Self {
+        Self {
+            correlation_id: correlation::current(),
+            inner,
+        }
+    }
+}
+
+impl<F: ActorFuture> ActorFuture for CorrelatedActorFuture<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+    type Actor = F::Actor;
+
+    fn poll(
+        &mut self,
+        act: &mut Self::Actor,
+        ctx: &mut <Self::Actor as Actor>::Context,
+    ) -> Poll<Self::Item, Self::Error> {
+        let _entered = self.correlation_id.map(correlation::enter);
+
+        self.inner.poll(act, ctx)
+    }
+}
+
 /// Payload for the notification for a specific epoch
 #[derive(Debug)]
 pub struct EpochPayload;
@@ -83,24 +152,38 @@ impl Handler<EpochNotification<EveryEpochPayload>> for ChainManager {
     type Result = ();
 
     fn handle(&mut self, msg: EpochNotification<EveryEpochPayload>, ctx: &mut Context<Self>) {
-        log::debug!("Periodic epoch notification received {:?}", msg.checkpoint);
+        // Every epoch starts a new block processing cycle
+        let correlation_id = CorrelationId::new();
+        let _entered = correlation::enter(correlation_id);
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "block_processing",
+            correlation_id = %correlation_id,
+            epoch = msg.checkpoint
+        );
+        #[cfg(feature = "tracing")]
+        let _span_entered = span.enter();
+
         let current_epoch = msg.checkpoint;
         self.current_epoch = Some(current_epoch);
-
-        log::debug!(
-            "EpochNotification received while StateMachine is in state {:?}",
-            self.sm_state
-        );
-        let chain_beacon = self.get_chain_beacon();
-        log::debug!(
-            "Chain state ---> checkpoint: {}, hash_prev_block: {}",
-            chain_beacon.checkpoint,
-            chain_beacon.hash_prev_block
-        );
+        crash::set_context("epoch", current_epoch.to_string());
+
+        // Forget the blocks that have been waiting for their parent for too long
+        if !self.orphan_blocks.is_empty() {
+            let expired = self
+                .orphan_blocks
+                .expire(current_epoch, ORPHAN_BLOCK_MAX_AGE);
+            if expired > 0 {
+                log::debug!("Dropped {} blocks whose parent did not arrive", expired);
+            }
+        }
 
         // Handle case consensus not achieved
         if !self.peers_beacons_received {
             log::warn!("No beacon messages received from peers. Moving to WaitingConsensus status");
+            if self.sm_state == StateMachine::Synchronizing {
+                self.stop_sync();
+            }
             self.sm_state = StateMachine::WaitingConsensus;
             // Clear candidates
             self.candidates.clear();
@@ -110,25 +193,19 @@ impl Handler<EpochNotification<EveryEpochPayload>> for ChainManager {
             StateMachine::WaitingConsensus => {
                 if let Some(chain_info) = &self.chain_state.chain_info {
                     // Send last beacon because otherwise the network cannot bootstrap
-                    SessionsManager::from_registry().do_send(Broadcast {
-                        command: SendLastBeacon {
-                            beacon: chain_info.highest_block_checkpoint,
-                        },
-                        only_inbound: true,
-                    });
+                    self.broadcast_last_beacon(chain_info.highest_block_checkpoint);
                 }
             }
             StateMachine::Synchronizing => {}
+            StateMachine::Synced if self.epoch_constants.is_none() || self.vrf_ctx.is_none() => {
+                log::error!("{}", ChainManagerError::ChainNotReady);
+            }
             StateMachine::Synced => match self.chain_state {
                 ChainState {
                     chain_info: Some(ref mut chain_info),
                     reputation_engine: Some(ref mut rep_engine),
                     ..
                 } => {
-                    if self.epoch_constants.is_none() || self.vrf_ctx.is_none() {
-                        log::error!("{}", ChainManagerError::ChainNotReady);
-                        return;
-                    }
                     // Decide the best candidate
                     // TODO: replace for loop with a try_fold
                     let mut chosen_candidate = None;
@@ -155,6 +232,7 @@ impl Handler<EpochNotification<EveryEpochPayload>> for ChainManager {
                             self.vrf_ctx.as_mut().unwrap(),
                             rep_engine,
                             self.epoch_constants.unwrap(),
+                            &self.chain_state.protocol_upgrades,
                         ) {
                             Ok(utxo_diff) => {
                                 let block_pkh = &block_candidate.block_sig.public_key.pkh();
@@ -167,7 +245,10 @@ impl Handler<EpochNotification<EveryEpochPayload>> for ChainManager {
                     }
 
                     // Consolidate the best candidate
-                    if let Some((_, _, block, utxo_diff)) = chosen_candidate {
+                    if let Some((key, _, block, utxo_diff)) = chosen_candidate {
+                        if let Some(summary) = self.epoch_summary.as_mut() {
+                            summary.chosen_candidate = Some(key);
+                        }
                         // Persist block and update ChainState
                         self.consolidate_block(ctx, &block, utxo_diff);
                     } else {
@@ -184,22 +265,21 @@ impl Handler<EpochNotification<EveryEpochPayload>> for ChainManager {
                     }
 
                     // Send last beacon in state 3 on block consolidation
-                    SessionsManager::from_registry().do_send(Broadcast {
-                        command: SendLastBeacon {
-                            beacon: self
-                                .chain_state
-                                .chain_info
-                                .as_ref()
-                                .unwrap()
-                                .highest_block_checkpoint,
-                        },
-                        only_inbound: true,
-                    });
+                    self.broadcast_last_beacon(
+                        self.chain_state
+                            .chain_info
+                            .as_ref()
+                            .unwrap()
+                            .highest_block_checkpoint,
+                    );
 
                     // TODO: Review time since commits are clear and new ones are received before to mining
                     // Remove commits because they expire every epoch
                     self.transactions_pool.clear_commits();
 
+                    // Drop the transactions that have been in the pool for too long
+                    self.remove_expired_transactions();
+
                     // Mining
                     if self.mining_enabled {
                         // Data race: the data requests should be sent after mining the block, otherwise
@@ -222,10 +302,27 @@ impl Handler<EpochNotification<EveryEpochPayload>> for ChainManager {
             },
         }
 
+        // Evaluate the candidates that arrived before the start of their epoch
+        let quarantined_candidates =
+            std::mem::replace(&mut self.quarantined_candidates, Default::default());
+        for (_hash, block) in quarantined_candidates {
+            self.process_candidate(block);
+        }
+
+        self.finish_epoch_summary(current_epoch);
         self.peers_beacons_received = false;
     }
 }
 
+/// Handler for ConfigChanged message
+impl Handler<ConfigChanged> for ChainManager {
+    type Result = ();
+
+    fn handle(&mut self, ConfigChanged(config): ConfigChanged, _ctx: &mut Context<Self>) {
+        self.update_consensus_thresholds(ConsensusThresholds::from_config(&config.connections));
+    }
+}
+
 /// Handler for GetHighestBlockCheckpoint message
 impl Handler<GetHighestCheckpointBeacon> for ChainManager {
     type Result = Result<CheckpointBeacon, failure::Error>;
@@ -244,115 +341,116 @@ impl Handler<GetHighestCheckpointBeacon> for ChainManager {
     }
 }
 
-/// Handler for AddBlocks message
-impl Handler<AddBlocks> for ChainManager {
-    type Result = SessionUnitResult;
+/// Handler for ConsolidateBatch message
+impl Handler<ConsolidateBatch> for ChainManager {
+    type Result = Result<CheckpointBeacon, failure::Error>;
 
-    fn handle(&mut self, msg: AddBlocks, ctx: &mut Context<Self>) {
+    fn handle(
+        &mut self,
+        ConsolidateBatch {
+            blocks,
+            target_beacon,
+        }: ConsolidateBatch,
+        ctx: &mut Context<Self>,
+    ) -> Self::Result {
         log::debug!(
-            "AddBlocks received while StateMachine is in state {:?}",
+            "ConsolidateBatch received while StateMachine is in state {:?}",
             self.sm_state
         );
-        match self.sm_state {
-            StateMachine::WaitingConsensus => {}
-            StateMachine::Synchronizing => {
-                if let Some(target_beacon) = self.target_beacon {
-                    let mut batch_succeeded = true;
-                    let chain_beacon = self.get_chain_beacon();
-                    if msg.blocks.is_empty() {
-                        batch_succeeded = false;
-                        log::debug!("Received an empty AddBlocks message");
-                    // FIXME(#684): this condition would be modified when genesis block exist
-                    } else if chain_beacon.hash_prev_block != self.genesis_block_hash
-                        && msg.blocks[0].hash() != chain_beacon.hash_prev_block
-                        && msg.blocks[0].block_header.beacon.checkpoint == chain_beacon.checkpoint
-                    {
-                        // Fork case
-                        batch_succeeded = false;
-                        log::error!("Mismatching blocks, fork detected");
-                        self.initialize_from_storage(ctx);
-                        log::info!("Restored chain state from storage");
-                    } else {
-                        // FIXME(#684): this condition would be deleted when genesis block exist
-                        let blocks = if chain_beacon.hash_prev_block == self.genesis_block_hash
-                            || msg.blocks[0].block_header.beacon.checkpoint
-                                > chain_beacon.checkpoint
-                        {
-                            &msg.blocks[..]
-                        } else {
-                            &msg.blocks[1..]
-                        };
-
-                        for block in blocks.iter() {
-                            // Update reputation before checking Proof-of-Eligibility
-                            let block_epoch = block.block_header.beacon.checkpoint;
-
-                            if let Some(ref mut rep_engine) = self.chain_state.reputation_engine {
-                                if let Err(e) = rep_engine.ars.update_empty(block_epoch) {
-                                    log::error!(
-                                        "Error updating reputation before processing block: {}",
-                                        e
-                                    );
-                                }
-                            }
+        if self.sm_state != StateMachine::Synchronizing {
+            return Err(ChainManagerError::NotSynchronizing.into());
+        }
 
-                            if let Err(e) = self.process_requested_block(ctx, block) {
-                                log::error!("Error processing block: {}", e);
-                                self.initialize_from_storage(ctx);
-                                log::info!("Restored chain state from storage");
-                                batch_succeeded = false;
-                                break;
-                            }
+        let mut consolidated = vec![];
+        let mut held = 0;
+        for block in blocks {
+            let block_epoch = block.block_header.beacon.checkpoint;
+            let chain_beacon = self.get_chain_beacon();
+            if self.chain_state.block_chain.get(&block_epoch) == Some(&block.hash()) {
+                // Already consolidated after its parent, while it was waiting in the orphan pool
+                continue;
+            }
+            if block.block_header.beacon.hash_prev_block != chain_beacon.hash_prev_block
+                && block_epoch > chain_beacon.checkpoint
+            {
+                self.hold_orphan_block(block);
+                held += 1;
+                continue;
+            }
 
-                            if self.get_chain_beacon() == target_beacon {
-                                break;
-                            }
-                        }
-                    }
+            // Consolidate the block, followed by the orphans waiting for it
+            let mut ready = Some(block);
+            while let Some(block) = ready.take() {
+                if let Err(e) = self.consolidate_requested_block(ctx, &block) {
+                    log::error!("Error processing block: {}", e);
+                    self.rollback(ctx);
+                    log::info!("Restored chain state from storage");
+
+                    // This happens if this node has forked but the network has a valid consensus,
+                    // or if a peer sent an invalid batch. Restoring the chain state from storage
+                    // is not enough to continue the synchronization, as that storage was
+                    // overwritten at the end of the last successful batch, so it is restarted on
+                    // the next PeersBeacons message.
+                    self.sm_state = StateMachine::WaitingConsensus;
 
-                    if batch_succeeded {
-                        self.persist_blocks_batch(ctx, msg.blocks, target_beacon);
-                        let to_be_stored =
-                            self.chain_state.data_request_pool.finished_data_requests();
-                        to_be_stored.into_iter().for_each(|dr| {
-                            self.persist_data_request(ctx, &dr);
-                        });
-                        self.persist_chain_state(ctx);
-
-                        let beacon = self.get_chain_beacon();
-
-                        if beacon == target_beacon {
-                            // Target achived, go back to state 1
-                            self.sm_state = StateMachine::WaitingConsensus;
-                        } else {
-                            // Try again, send Anycast<SendLastBeacon> to a "safu" peer, i.e. their last beacon matches our target beacon.
-                            SessionsManager::from_registry().do_send(Anycast {
-                                command: SendLastBeacon { beacon },
-                                safu: true,
-                            });
-                        }
-                    } else {
-                        // This branch will happen if this node has forked, but the network has
-                        // a valid consensus. In that case we would want to restore the node to
-                        // the state just before the fork, and restart the synchronization.
-
-                        // This branch could also happen when one peer has sent us an invalid block batch.
-                        // Ideally we would mark it as a bad peer and restart the
-                        // synchronization process, but that's not implemented yet.
-                        // Note that in order to correctly restart the synchronization process,
-                        // restoring the chain state from storage is not enough,
-                        // as that storage was overwritten at the end of the last successful batch.
-
-                        // In any case, the current behavior is to go back to WaitingConsensus
-                        // state and restart the synchronization on the next PeersBeacons message.
-                        self.sm_state = StateMachine::WaitingConsensus;
-                    }
-                } else {
-                    log::warn!("Target Beacon is None");
+                    return Err(e);
+                }
+
+                // Only one of the blocks built on the same parent can follow the chain
+                let mut children = self.orphan_blocks.take_children(block.hash()).into_iter();
+                ready = children.next();
+                for orphan in children {
+                    log::debug!(
+                        "Dropping block {} from epoch {}: another block follows its parent",
+                        orphan.hash(),
+                        orphan.block_header.beacon.checkpoint
+                    );
                 }
+                consolidated.push(block);
             }
-            StateMachine::Synced => {}
-        };
+        }
+
+        if consolidated.is_empty() && held > 0 {
+            // Nothing changed, so there is no need to restore the chain state, but the same
+            // batch would be requested again
+            self.sm_state = StateMachine::WaitingConsensus;
+
+            return Err(ChainManagerError::UnknownParent.into());
+        }
+
+        self.persist_blocks_batch(ctx, consolidated);
+        let to_be_stored = self.chain_state.data_request_pool.finished_data_requests();
+        to_be_stored.into_iter().for_each(|dr| {
+            self.persist_data_request(ctx, &dr);
+        });
+        self.persist_chain_state(ctx);
+
+        let beacon = self.get_chain_beacon();
+        if beacon == target_beacon {
+            // Target achieved, go back to state 1
+            self.sm_state = StateMachine::WaitingConsensus;
+        }
+
+        Ok(beacon)
+    }
+}
+
+/// Handler for SyncFailed message
+impl Handler<SyncFailed> for ChainManager {
+    type Result = ();
+
+    fn handle(&mut self, SyncFailed { fork }: SyncFailed, ctx: &mut Context<Self>) {
+        if self.sm_state != StateMachine::Synchronizing {
+            return;
+        }
+
+        if fork {
+            self.rollback(ctx);
+            log::info!("Restored chain state from storage");
+        }
+
+        // Restart the synchronization on the next PeersBeacons message
+        self.sm_state = StateMachine::WaitingConsensus;
     }
 }
 
@@ -361,6 +459,7 @@ impl Handler<AddCandidates> for ChainManager {
     type Result = SessionUnitResult;
 
     fn handle(&mut self, msg: AddCandidates, _ctx: &mut Context<Self>) {
+        mailbox::CANDIDATES.processed();
         // AddCandidates is needed in all states
         for block in msg.blocks {
             self.process_candidate(block);
@@ -389,14 +488,37 @@ impl Handler<AddTransaction> for ChainManager {
         let tx_hash = transaction.hash();
         let utxo_diff = UtxoDiff::new(&self.chain_state.unspent_outputs_pool);
 
-        let validation_result: Result<(), failure::Error> = match transaction {
+        // The fee is only used to prioritize value transfer transactions
+        let validation_result: Result<u64, failure::Error> = match transaction {
             Transaction::ValueTransfer(tx) => {
                 if self.transactions_pool.vt_contains(&tx_hash) {
                     log::debug!("Transaction is already in the pool: {}", tx_hash);
                     return;
                 }
 
-                validate_vt_transaction(tx, &utxo_diff).map(|_| ())
+                let ancestors = self.transactions_pool.vt_unconfirmed_ancestors(tx).len();
+                if ancestors > MAX_VT_UNCONFIRMED_ANCESTORS {
+                    Err(TransactionError::TooManyUnconfirmedAncestors {
+                        hash: tx_hash,
+                        ancestors,
+                        max: MAX_VT_UNCONFIRMED_ANCESTORS,
+                    }
+                    .into())
+                } else {
+                    // The outputs of the transactions in the pool can be spent as well, so that
+                    // transactions can be chained before they are included in a block
+                    let mut utxo_diff = UtxoDiff::new(&self.chain_state.unspent_outputs_pool);
+                    for input in &tx.body.inputs {
+                        let output_pointer = input.output_pointer();
+                        if utxo_diff.get(output_pointer).is_none() {
+                            if let Some(output) = self.transactions_pool.vt_output(output_pointer) {
+                                utxo_diff.insert_utxo(output_pointer.clone(), output.clone());
+                            }
+                        }
+                    }
+
+                    validate_vt_transaction(tx, &utxo_diff).map(|(_, _, fee)| fee)
+                }
             }
 
             Transaction::DataRequest(tx) => {
@@ -405,7 +527,13 @@ impl Handler<AddTransaction> for ChainManager {
                     return;
                 }
 
-                validate_dr_transaction(tx, &utxo_diff).map(|_| ())
+                validate_dr_transaction(
+                    tx,
+                    &utxo_diff,
+                    &self.chain_state.protocol_upgrades,
+                    self.current_epoch.unwrap_or_default(),
+                )
+                .map(|_| 0)
             }
             Transaction::Commit(tx) => {
                 let dr_pointer = tx.body.dr_pointer;
@@ -450,7 +578,7 @@ impl Handler<AddTransaction> for ChainManager {
                             current_epoch,
                             epoch_constants,
                         )
-                        .map(|_| ())
+                        .map(|_| 0)
                     }
                     _ => Err(ChainManagerError::ChainNotReady.into()),
                 }
@@ -467,19 +595,39 @@ impl Handler<AddTransaction> for ChainManager {
                     return;
                 }
 
-                validate_reveal_transaction(tx, &self.chain_state.data_request_pool).map(|_| ())
+                validate_reveal_transaction(tx, &self.chain_state.data_request_pool).map(|_| 0)
             }
             _ => Err(TransactionError::NotValidTransaction.into()),
         };
 
         match validation_result {
-            Ok(_) => {
-                log::debug!("Transaction added successfully");
-                // Broadcast valid transaction
-                self.broadcast_item(InventoryItem::Transaction(msg.transaction.clone()));
-
+            Ok(fee) => {
                 // Add valid transaction to transactions_pool
-                self.transactions_pool.insert(msg.transaction);
+                self.transactions_pool.insert_with_fee(
+                    msg.transaction.clone(),
+                    fee,
+                    get_timestamp(),
+                );
+
+                // If the pool is full, drop the transactions paying the lowest fees
+                let evicted = if self.transactions_pool_max_size > 0 {
+                    self.transactions_pool
+                        .vt_truncate(self.transactions_pool_max_size)
+                } else {
+                    vec![]
+                };
+
+                if evicted.contains(&tx_hash) {
+                    log::debug!(
+                        "Transaction {} does not pay enough fee to enter the pool",
+                        tx_hash
+                    );
+                } else {
+                    log::debug!("Transaction added successfully");
+                    // Broadcast valid transaction
+                    self.broadcast_item(InventoryItem::Transaction(msg.transaction));
+                }
+                self.transactions_dropped(evicted, DropReason::LowFee);
             }
 
             Err(e) => log::warn!("{}", e),
@@ -502,18 +650,20 @@ impl Handler<GetBlocksEpochRange> for ChainManager {
         // TODO: we should only accept this message in Synced state, but that breaks the
         // JSON-RPC getBlockChain method
 
-        let mut hashes: Vec<(Epoch, Hash)> = self
+        // Hashes Vec has not to be bigger than MAX_BLOCKS_SYNC
+        let limit = if limit == 0 {
+            usize::max_value()
+        } else {
+            limit
+        };
+        let hashes: Vec<(Epoch, Hash)> = self
             .chain_state
             .block_chain
             .range(range)
+            .take(limit)
             .map(|(k, v)| (*k, *v))
             .collect();
 
-        // Hashes Vec has not to be bigger than MAX_BLOCKS_SYNC
-        if limit != 0 {
-            hashes.truncate(limit);
-        }
-
         Ok(hashes)
     }
 }
@@ -521,8 +671,6 @@ impl Handler<GetBlocksEpochRange> for ChainManager {
 impl Handler<PeersBeacons> for ChainManager {
     type Result = <PeersBeacons as Message>::Result;
 
-    // FIXME(#676): Remove clippy skip error
-    #[allow(clippy::cognitive_complexity)]
     fn handle(
         &mut self,
         PeersBeacons { pb }: PeersBeacons,
@@ -534,209 +682,103 @@ impl Handler<PeersBeacons> for ChainManager {
         );
         // Activate peers beacons index to continue synced
         self.peers_beacons_received = true;
+        if let Some(summary) = self.epoch_summary.as_mut() {
+            summary.peers = pb.len();
+        }
 
-        let consensus_threshold = self.consensus_c as usize;
-
-        match self.sm_state {
-            StateMachine::WaitingConsensus => {
-                // As soon as there is consensus, we set the target beacon to the consensus
-                // and set the state to Synchronizing
-
-                // Run the consensus on the beacons, will return the most common beacon
-                // In case of tie returns None
-                if let Some(consensus_beacon) =
-                    mode_consensus(pb.iter().map(|(_p, b)| b), consensus_threshold).cloned()
-                {
-                    // Consensus: unregister peers which have a different beacon
-                    let peers_out_of_consensus = pb
-                        .into_iter()
-                        .filter_map(|(p, b)| if b != consensus_beacon { Some(p) } else { None })
-                        .collect();
-                    self.target_beacon = Some(consensus_beacon);
-
-                    let our_beacon = self.get_chain_beacon();
-
-                    // Check if we are already synchronized
-                    self.sm_state = if our_beacon == consensus_beacon {
-                        log::info!("{}", SYNCED_BANNER);
-                        StateMachine::Synced
-                    } else if our_beacon.checkpoint == consensus_beacon.checkpoint
-                        && our_beacon.hash_prev_block != consensus_beacon.hash_prev_block
-                    {
-                        // Fork case
-                        log::warn!(
-                            "[CONSENSUS]: We are on {:?} but the network is on {:?}",
-                            our_beacon,
-                            consensus_beacon
-                        );
+        let consensus_threshold = self.consensus_thresholds.for_state(self.sm_state);
+        let previous_state = self.sm_state;
+        let decision = consensus::peers_beacons_consensus(
+            previous_state,
+            self.get_chain_beacon(),
+            pb,
+            consensus_threshold,
+        );
 
-                        self.initialize_from_storage(ctx);
-                        log::info!("Restored chain state from storage");
+        self.sm_state = match decision.action {
+            ConsensusAction::None => decision.state,
+            ConsensusAction::Rollback => {
+                self.rollback(ctx);
+                log::info!("Restored chain state from storage");
 
-                        StateMachine::WaitingConsensus
-                    } else {
-                        // Review candidates
-                        let consensus_block_hash = consensus_beacon.hash_prev_block;
-                        // TODO: Be functional my friend
-                        if let Some(consensus_block) = self.candidates.remove(&consensus_block_hash)
-                        {
-                            match self.process_requested_block(ctx, &consensus_block) {
-                                Ok(()) => {
-                                    log::info!("Consolidate consensus candidate. Synced state");
-                                    log::info!("{}", SYNCED_BANNER);
-                                    self.persist_item(
-                                        ctx,
-                                        InventoryItem::Block(consensus_block.clone()),
-                                    );
-                                    StateMachine::Synced
-                                }
-                                Err(e) => {
-                                    log::debug!("Failed to consolidate consensus candidate: {}", e);
-
-                                    // Send Anycast<SendLastBeacon> to a safu peer in order to begin the synchronization
-                                    SessionsManager::from_registry().do_send(Anycast {
-                                        command: SendLastBeacon { beacon: our_beacon },
-                                        safu: true,
-                                    });
-
-                                    StateMachine::Synchronizing
-                                }
+                decision.state
+            }
+            ConsensusAction::ConsolidateCandidate(consensus_beacon) => {
+                // Review candidates
+                let consensus_block_hash = consensus_beacon.hash_prev_block;
+                let consolidated = match self.candidates.remove(&consensus_block_hash) {
+                    Some(consensus_block) => {
+                        match self.process_requested_block(ctx, &consensus_block) {
+                            Ok(()) => {
+                                log::info!("Consolidate consensus candidate. Synced state");
+                                self.persist_item(ctx, InventoryItem::Block(consensus_block));
+                                true
+                            }
+                            Err(e) => {
+                                log::debug!("Failed to consolidate consensus candidate: {}", e);
+                                false
                             }
-                        } else {
-                            // Send Anycast<SendLastBeacon> to a safu peer in order to begin the synchronization
-                            SessionsManager::from_registry().do_send(Anycast {
-                                command: SendLastBeacon { beacon: our_beacon },
-                                safu: true,
-                            });
-
-                            StateMachine::Synchronizing
                         }
-                    };
+                    }
+                    None => false,
+                };
 
-                    Ok(peers_out_of_consensus)
+                if consolidated {
+                    StateMachine::Synced
                 } else {
-                    // No consensus: unregister all peers
-                    let all_peers = pb.into_iter().map(|(p, _b)| p).collect();
-                    Ok(all_peers)
-                }
-            }
-            StateMachine::Synchronizing => {
-                // Run the consensus on the beacons, will return the most common beacon
-                // In case of tie returns None
-                if let Some(consensus_beacon) =
-                    mode_consensus(pb.iter().map(|(_p, b)| b), consensus_threshold).cloned()
-                {
-                    // List peers that announced a beacon out of consensus
-                    let peers_out_of_consensus = pb
-                        .into_iter()
-                        .filter_map(|(p, b)| if b != consensus_beacon { Some(p) } else { None })
-                        .collect();
-                    self.target_beacon = Some(consensus_beacon);
-
-                    let our_beacon = self.get_chain_beacon();
-
-                    // Check if we are already synchronized
-                    self.sm_state = if our_beacon == consensus_beacon {
-                        log::info!("{}", SYNCED_BANNER);
-                        StateMachine::Synced
-                    } else if our_beacon.checkpoint == consensus_beacon.checkpoint
-                        && our_beacon.hash_prev_block != consensus_beacon.hash_prev_block
-                    {
-                        // Fork case
-                        log::warn!(
-                            "[CONSENSUS]: We are on {:?} but the network is on {:?}",
-                            our_beacon,
-                            consensus_beacon
-                        );
-
-                        self.initialize_from_storage(ctx);
-                        log::info!("Restored chain state from storage");
+                    // Download the blocks from a safu peer
+                    self.start_sync(consensus_beacon);
 
-                        StateMachine::WaitingConsensus
-                    } else {
-                        StateMachine::Synchronizing
-                    };
-
-                    Ok(peers_out_of_consensus)
-                } else {
-                    // No consensus: unregister all peers
-                    let all_peers = pb.into_iter().map(|(p, _b)| p).collect();
-                    Ok(all_peers)
+                    decision.state
                 }
             }
-            StateMachine::Synced => {
-                // If we are synced and the consensus beacon is not the same as our beacon, then
-                // we need to rewind one epoch
+            ConsensusAction::Synchronize(consensus_beacon) => {
+                // Keep synchronizing towards the new consensus beacon
+                self.start_sync(consensus_beacon);
 
-                if pb.is_empty() {
-                    log::warn!("[CONSENSUS]: We have zero outbound peers");
-                    self.sm_state = StateMachine::WaitingConsensus;
-                }
-
-                let our_beacon = self.get_chain_beacon();
-
-                // We also take into account our beacon to calculate the consensus
-                let consensus_beacon =
-                    mode_consensus(pb.iter().map(|(_p, b)| b), consensus_threshold).cloned();
-
-                match consensus_beacon {
-                    Some(a) if a == our_beacon => {
-                        // Consensus: unregister peers which have a different beacon
-                        let peers_out_of_consensus = pb
-                            .into_iter()
-                            .filter_map(|(p, b)| if b != our_beacon { Some(p) } else { None })
-                            .collect();
-
-                        Ok(peers_out_of_consensus)
-                    }
-                    Some(a) => {
-                        // We are out of consensus!
-                        // Unregister peers that announced a beacon out of consensus
-                        let peers_out_of_consensus = pb
-                            .into_iter()
-                            .filter_map(|(p, b)| if b != a { Some(p) } else { None })
-                            .collect();
-
-                        log::warn!(
-                            "[CONSENSUS]: We are on {:?} but the network is on {:?}",
-                            our_beacon,
-                            consensus_beacon
-                        );
-
-                        self.initialize_from_storage(ctx);
-                        log::info!("Restored chain state from storage");
+                decision.state
+            }
+        };
 
-                        self.sm_state = StateMachine::WaitingConsensus;
+        if self.sm_state == StateMachine::Synced && previous_state != StateMachine::Synced {
+            log::info!("{}", SYNCED_BANNER);
+        }
+        if previous_state == StateMachine::Synchronizing
+            && self.sm_state != StateMachine::Synchronizing
+        {
+            self.stop_sync();
+        }
 
-                        Ok(peers_out_of_consensus)
-                    }
-                    None => {
-                        // There is no consensus because of a tie, do not rewind?
-                        // For example this could happen when each peer reports a different beacon...
-                        log::warn!(
-                            "[CONSENSUS]: We are on {:?} but the network has no consensus",
-                            our_beacon
-                        );
+        Ok(decision.peers_to_unregister)
+    }
+}
 
-                        self.sm_state = StateMachine::WaitingConsensus;
+impl Handler<BuildBlockTemplate> for ChainManager {
+    type Result = ResponseActFuture<Self, BlockTemplate, failure::Error>;
 
-                        // Unregister all peers to try to obtain a new set of trustworthy peers
-                        let all_peers = pb.into_iter().map(|(p, _b)| p).collect();
-                        Ok(all_peers)
-                    }
-                }
-            }
+    fn handle(&mut self, msg: BuildBlockTemplate, _ctx: &mut Self::Context) -> Self::Result {
+        if self.sm_state != StateMachine::Synced {
+            return Box::new(actix::fut::err(ChainManagerError::NotSynced.into()));
         }
+
+        self.build_block_template(msg.tx_hashes)
     }
 }
 
 impl Handler<BuildVtt> for ChainManager {
     type Result = ResponseActFuture<Self, Hash, failure::Error>;
 
-    fn handle(&mut self, msg: BuildVtt, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, mut msg: BuildVtt, _ctx: &mut Self::Context) -> Self::Result {
         if self.sm_state != StateMachine::Synced {
             return Box::new(actix::fut::err(ChainManagerError::NotSynced.into()));
         }
+        if msg.subtract_fee_from_amount {
+            if let Err(e) = transaction_factory::subtract_fee(&mut msg.vto, msg.fee, msg.fee_split)
+            {
+                log::error!("{}", e);
+                return Box::new(actix::fut::err(e.into()));
+            }
+        }
         match transaction_factory::build_vtt(
             msg.vto,
             msg.fee,
@@ -767,7 +809,7 @@ impl Handler<BuildVtt> for ChainManager {
                         }
                     });
 
-                Box::new(fut)
+                Box::new(CorrelatedActorFuture::new(fut))
             }
         }
     }
@@ -780,7 +822,13 @@ impl Handler<BuildDrt> for ChainManager {
         if self.sm_state != StateMachine::Synced {
             return Box::new(actix::fut::err(ChainManagerError::NotSynced.into()));
         }
-        if let Err(e) = validate_rad_request(&msg.dro.data_request) {
+        if let Err(e) = validate_rad_request(&msg.dro.data_request).and_then(|_| {
+            validate_rad_request_upgrades(
+                &msg.dro.data_request,
+                &self.chain_state.protocol_upgrades,
+                self.current_epoch.unwrap_or_default(),
+            )
+        }) {
             return Box::new(actix::fut::err(e));
         }
         match transaction_factory::build_drt(
@@ -814,7 +862,7 @@ impl Handler<BuildDrt> for ChainManager {
                         }
                     });
 
-                Box::new(fut)
+                Box::new(CorrelatedActorFuture::new(fut))
             }
         }
     }
@@ -828,6 +876,38 @@ impl Handler<GetState> for ChainManager {
     }
 }
 
+impl Handler<GetEpochSummary> for ChainManager {
+    type Result = <GetEpochSummary as Message>::Result;
+
+    fn handle(&mut self, _msg: GetEpochSummary, _ctx: &mut Self::Context) -> Self::Result {
+        self.last_epoch_summary
+            .clone()
+            .ok_or_else(|| ChainManagerError::ChainNotReady.into())
+    }
+}
+
+impl Handler<GetProtocolUpgrades> for ChainManager {
+    type Result = <GetProtocolUpgrades as Message>::Result;
+
+    fn handle(&mut self, _msg: GetProtocolUpgrades, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(self.chain_state.protocol_upgrades.info(PROTOCOL_UPGRADES))
+    }
+}
+
+impl Handler<GetReputationForecast> for ChainManager {
+    type Result = Result<ReputationForecast, failure::Error>;
+
+    fn handle(&mut self, _msg: GetReputationForecast, _ctx: &mut Self::Context) -> Self::Result {
+        if self.chain_state.chain_info.is_none() {
+            return Err(ChainManagerError::ChainNotReady.into());
+        }
+        let epoch = self.get_chain_beacon().checkpoint;
+
+        self.reputation_forecast(epoch)
+            .ok_or_else(|| ChainManagerError::ChainNotReady.into())
+    }
+}
+
 impl Handler<GetDataRequestReport> for ChainManager {
     type Result = ResponseFuture<DataRequestInfo, failure::Error>;
 
@@ -858,6 +938,94 @@ impl Handler<GetDataRequestReport> for ChainManager {
     }
 }
 
+impl Handler<GetTransactionStatus> for ChainManager {
+    type Result = Result<TransactionStatus, failure::Error>;
+
+    fn handle(
+        &mut self,
+        GetTransactionStatus { hash }: GetTransactionStatus,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let status = if self.transactions_pool.vt_contains(&hash)
+            || self.transactions_pool.dr_contains(&hash)
+        {
+            TransactionStatus::Pending
+        } else if let Some(reason) = self.dropped_transactions.get(&hash) {
+            TransactionStatus::Dropped(*reason)
+        } else {
+            TransactionStatus::Unknown
+        };
+
+        Ok(status)
+    }
+}
+
+impl Handler<GetSuperblock> for ChainManager {
+    type Result = ResponseFuture<Superblock, failure::Error>;
+
+    fn handle(
+        &mut self,
+        GetSuperblock { index }: GetSuperblock,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let fut = storage_mngr::get::<_, Superblock>(&superblock_key(index)).and_then(
+            move |superblock| match superblock {
+                Some(x) => futures::finished(x),
+                None => futures::failed(ChainManagerError::SuperblockNotFound { index }.into()),
+            },
+        );
+
+        Box::new(fut)
+    }
+}
+
+impl Handler<GetArsMerkleProof> for ChainManager {
+    type Result = ResponseFuture<ArsMerkleProof, failure::Error>;
+
+    fn handle(
+        &mut self,
+        GetArsMerkleProof { index, pkh }: GetArsMerkleProof,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let fut = <Self as Handler<GetSuperblock>>::handle(self, GetSuperblock { index }, ctx)
+            .and_then(move |superblock| {
+                superblock.ars_merkle_proof(&pkh).ok_or_else(|| {
+                    failure::Error::from(ChainManagerError::NotArsMember { index, pkh })
+                })
+            });
+
+        Box::new(fut)
+    }
+}
+
+impl Handler<GetDataRequestState> for ChainManager {
+    type Result = Result<DataRequestStateSummary, failure::Error>;
+
+    fn handle(
+        &mut self,
+        GetDataRequestState { dr_pointer }: GetDataRequestState,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let current_epoch = self.current_epoch.ok_or(ChainManagerError::ChainNotReady)?;
+        let dr_state = self
+            .chain_state
+            .data_request_pool
+            .data_request_state(&dr_pointer)
+            .ok_or(DataRequestNotFound { hash: dr_pointer })?;
+
+        let mut summary = dr_state.summary(current_epoch);
+        // Stages can only advance when a new block is consolidated, that is, in the next epoch
+        summary.seconds_to_next_epoch = self.epoch_constants.and_then(|epoch_constants| {
+            epoch_constants
+                .epoch_timestamp(current_epoch + 1)
+                .ok()
+                .map(|ts| ts - get_timestamp())
+        });
+
+        Ok(summary)
+    }
+}
+
 impl Handler<GetBalance> for ChainManager {
     type Result = Result<u64, failure::Error>;
 
@@ -866,9 +1034,159 @@ impl Handler<GetBalance> for ChainManager {
             return Err(ChainManagerError::NotSynced.into());
         }
 
+        // The outputs of this node and of the watched identities are tracked, so there is no
+        // need to scan the whole unspent outputs pool
+        if let Some(utxos) = self.tracked_utxos(&pkh) {
+            return Ok(utxos
+                .iter()
+                .filter_map(|output_pointer| {
+                    self.chain_state.unspent_outputs_pool.get(output_pointer)
+                })
+                .map(|output| output.value)
+                .sum());
+        }
+
         Ok(transaction_factory::get_total_balance(
             &self.chain_state.unspent_outputs_pool,
             pkh,
         ))
     }
 }
+
+impl Handler<GetUtxoInfo> for ChainManager {
+    type Result = Result<UtxoInfo, failure::Error>;
+
+    fn handle(
+        &mut self,
+        GetUtxoInfo { pkh }: GetUtxoInfo,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        if self.sm_state != StateMachine::Synced {
+            return Err(ChainManagerError::NotSynced.into());
+        }
+
+        let utxos = self
+            .tracked_utxos(&pkh)
+            .ok_or_else(|| ChainManagerError::PkhNotWatched { pkh })?;
+        let utxos: Vec<UtxoMetadata> = utxos
+            .iter()
+            .filter_map(|output_pointer| {
+                self.chain_state
+                    .unspent_outputs_pool
+                    .get(output_pointer)
+                    .map(|output| UtxoMetadata {
+                        output_pointer: output_pointer.clone(),
+                        value: output.value,
+                    })
+            })
+            .collect();
+        let total = utxos.iter().map(|utxo| utxo.value).sum();
+
+        Ok(UtxoInfo { utxos, total })
+    }
+}
+
+impl Handler<WatchPkh> for ChainManager {
+    type Result = Result<bool, failure::Error>;
+
+    fn handle(&mut self, WatchPkh { pkh }: WatchPkh, _ctx: &mut Self::Context) -> Self::Result {
+        if self.chain_state.chain_info.is_none() {
+            return Err(ChainManagerError::ChainNotReady.into());
+        }
+
+        // Both chain states are updated, so the watched identities survive a rollback
+        let watched = self.chain_state.watch_pkh(pkh);
+        self.last_chain_state.watch_pkh(pkh);
+        if watched {
+            log::info!("Watching the unspent outputs of {}", pkh);
+        }
+
+        Ok(watched)
+    }
+}
+
+impl Handler<UnwatchPkh> for ChainManager {
+    type Result = Result<bool, failure::Error>;
+
+    fn handle(&mut self, UnwatchPkh { pkh }: UnwatchPkh, _ctx: &mut Self::Context) -> Self::Result {
+        let unwatched = self.chain_state.unwatch_pkh(&pkh);
+        self.last_chain_state.unwatch_pkh(&pkh);
+        if unwatched {
+            log::info!("Stopped watching the unspent outputs of {}", pkh);
+        }
+
+        Ok(unwatched)
+    }
+}
+
+impl Handler<GetWatchedPkhs> for ChainManager {
+    type Result = Result<Vec<PublicKeyHash>, failure::Error>;
+
+    fn handle(&mut self, _msg: GetWatchedPkhs, _ctx: &mut Self::Context) -> Self::Result {
+        let mut pkhs: Vec<PublicKeyHash> = self.chain_state.watched_utxos.keys().cloned().collect();
+        pkhs.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        Ok(pkhs)
+    }
+}
+
+impl Handler<GetMempool> for ChainManager {
+    type Result = Result<GetMempoolResult, failure::Error>;
+
+    fn handle(&mut self, msg: GetMempool, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(mempool_transactions(&self.transactions_pool, msg))
+    }
+}
+
+impl Handler<EstimateDataRequestCost> for ChainManager {
+    type Result = Result<DataRequestCostEstimate, failure::Error>;
+
+    fn handle(
+        &mut self,
+        EstimateDataRequestCost { dr_output }: EstimateDataRequestCost,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let active_identities = self
+            .chain_state
+            .reputation_engine
+            .as_ref()
+            .map(|rep_engine| rep_engine.ars.active_identities_number() as u32)
+            .ok_or(ChainManagerError::ChainNotReady)?;
+
+        let mut issues = vec![];
+        if let Err(e) = validate_data_request_output(
+            &dr_output,
+            &self.chain_state.protocol_upgrades,
+            self.current_epoch.unwrap_or_default(),
+        ) {
+            issues.push(e.to_string());
+        }
+        let total_witnesses =
+            u32::from(dr_output.witnesses) + u32::from(dr_output.backup_witnesses);
+        if total_witnesses > active_identities {
+            issues.push(format!(
+                "{} witnesses are requested, but only {} identities are active",
+                total_witnesses, active_identities
+            ));
+        }
+        // The breakdown is also returned for invalid data requests, so the fees saturate
+        let fees = dr_output
+            .commit_fee
+            .saturating_add(dr_output.reveal_fee)
+            .saturating_add(dr_output.tally_fee);
+        let witnesses_reward = dr_output.value.saturating_sub(fees);
+
+        Ok(DataRequestCostEstimate {
+            total: dr_output.value,
+            witnesses_reward,
+            reward_per_witness: witnesses_reward
+                .checked_div(u64::from(dr_output.witnesses))
+                .unwrap_or(0),
+            commit_fee: dr_output.commit_fee,
+            reveal_fee: dr_output.reveal_fee,
+            tally_fee: dr_output.tally_fee,
+            active_identities,
+            issues,
+        })
+    }
+}
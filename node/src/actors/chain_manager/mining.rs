@@ -1,36 +1,40 @@
 use actix::{
-    ActorFuture, AsyncContext, Context, ContextFutureSpawner, Handler, System, WrapFuture,
+    ActorFuture, AsyncContext, Context, ContextFutureSpawner, Handler, ResponseActFuture, System,
+    WrapFuture,
 };
 use ansi_term::Color::{White, Yellow};
 use log::{debug, error, info, warn};
 
 use futures::future::{join_all, Future};
-use std::{convert::TryFrom, time::Duration};
+use std::{collections::HashSet, convert::TryFrom, time::Duration};
 
 use crate::{
     actors::{
-        chain_manager::{transaction_factory::sign_transaction, ChainManager},
+        chain_manager::{transaction_factory::sign_transaction, ChainManager, ChainManagerError},
         messages::{
-            AddCandidates, AddTransaction, GetHighestCheckpointBeacon, ResolveRA, RunConsensus,
+            AddTransaction, BlockTemplate, GetHighestCheckpointBeacon, ResolveRA, RunConsensus,
         },
         rad_manager::RadManager,
     },
-    signature_mngr,
+    signature_mngr::{self, SignedItemKind},
 };
 
 use witnet_data_structures::{
     chain::{
-        Block, BlockHeader, BlockMerkleRoots, BlockTransactions, CheckpointBeacon, Hashable,
-        PublicKeyHash, TransactionsPool, UnspentOutputsPool, ValueTransferOutput,
+        Block, BlockHeader, BlockMerkleRoots, BlockTransactions, CheckpointBeacon,
+        DataRequestOutput, Hash, Hashable, KeyedSignature, PublicKeyHash, TransactionsPool,
+        UnspentOutputsPool, ValueTransferOutput,
     },
     data_request::{create_tally, DataRequestPool},
     transaction::{
         CommitTransaction, CommitTransactionBody, MintTransaction, RevealTransaction,
         RevealTransactionBody, TallyTransaction, Transaction,
     },
+    upgrades::{PROTOCOL_UPGRADES, RAD_HTTP_POLICY},
     vrf::{BlockEligibilityClaim, DataRequestEligibilityClaim, VrfMessage},
 };
-use witnet_rad::types::RadonTypes;
+use witnet_rad::{http::PROTOCOL_HTTP_POLICY, types::RadonTypes};
+use witnet_util::timestamp::get_timestamp;
 use witnet_validations::validations::{
     block_reward, calculate_randpoe_threshold, calculate_reppoe_threshold, dr_transaction_fee,
     merkle_tree_root, update_utxo_diff, validate_block, vt_transaction_fee, UtxoDiff,
@@ -96,27 +100,29 @@ impl ChainManager {
             // which will construct and broadcast the block
             signature_mngr::vrf_prove(VrfMessage::block_mining(beacon))
                 .map_err(|e| error!("Failed to create block eligibility proof: {}", e))
-                .map(move |(vrf_proof, vrf_proof_hash)| {
+                .into_actor(act)
+                .and_then(move |(vrf_proof, vrf_proof_hash), act, _ctx| {
                     // invalid: vrf_hash > target_hash
                     let target_hash = calculate_randpoe_threshold(total_identities);
                     let proof_invalid = vrf_proof_hash > target_hash;
+                    if let Some(summary) = act.epoch_summary_for(current_epoch) {
+                        summary.block_eligible = Some(!proof_invalid);
+                    }
 
                     debug!("Target hash: {}", target_hash);
                     debug!("Our proof:   {}", vrf_proof_hash);
                     if proof_invalid {
                         debug!("No eligibility for mining");
-                        Err(())
+                        actix::fut::err(())
                     } else {
                         info!(
                             "{} Discovered eligibility for mining a block for epoch #{}",
                             Yellow.bold().paint("[Mining]"),
                             Yellow.bold().paint(beacon.checkpoint.to_string())
                         );
-                        Ok(vrf_proof)
+                        actix::fut::ok(vrf_proof)
                     }
                 })
-                .flatten()
-                .into_actor(act)
                 .and_then(|vrf_proof, act, _ctx| {
                     act.create_tally_transactions()
                         .map(|tally_transactions| (vrf_proof, tally_transactions))
@@ -125,31 +131,50 @@ impl ChainManager {
                 .and_then(move |(vrf_proof, tally_transactions), act, _ctx| {
                     let eligibility_claim = BlockEligibilityClaim { proof: vrf_proof };
 
+                    // Weight reserved for old transactions, and the arrival timestamp
+                    // before which a transaction is considered old
+                    let max_block_weight = act.max_block_weight_at(beacon.checkpoint);
+                    let old_transactions_weight = (u64::from(max_block_weight)
+                        * u64::from(act.old_transactions_weight_percentage.min(100))
+                        / 100) as u32;
+                    let old_transactions_timestamp =
+                        get_timestamp() - act.old_transactions_min_age.as_secs() as i64;
+
                     // Build the block using the supplied beacon and eligibility proof
-                    let (block_header, txns) = build_block(
+                    let (mut block_header, txns) = build_block(
                         (
                             &mut act.transactions_pool,
                             &act.chain_state.unspent_outputs_pool,
                             &act.chain_state.data_request_pool,
                         ),
-                        act.max_block_weight,
+                        max_block_weight,
+                        (old_transactions_weight, old_transactions_timestamp),
                         beacon,
                         eligibility_claim,
                         &tally_transactions,
                         act.own_pkh.unwrap_or_default(),
                     );
-
-                    // Sign the block hash
-                    signature_mngr::sign(&block_header)
-                        .map_err(|e| error!("Couldn't sign beacon: {}", e))
-                        .map(|block_sig| Block {
-                            block_header,
-                            block_sig,
-                            txns,
-                        })
-                        .into_actor(act)
+                    // Signal the protocol upgrades that are being voted
+                    block_header.version = act
+                        .chain_state
+                        .protocol_upgrades
+                        .block_version(PROTOCOL_UPGRADES, current_epoch);
+
+                    // Sign the block hash, recording it in the signing log
+                    signature_mngr::sign_logged(
+                        SignedItemKind::BlockCandidate,
+                        current_epoch,
+                        &block_header,
+                    )
+                    .map_err(|e| error!("Couldn't sign beacon: {}", e))
+                    .map(|block_sig| Block {
+                        block_header,
+                        block_sig,
+                        txns,
+                    })
+                    .into_actor(act)
                 })
-                .and_then(move |block, act, ctx| {
+                .and_then(move |block, act, _ctx| {
                     match validate_block(
                         &block,
                         current_epoch,
@@ -161,22 +186,19 @@ impl ChainManager {
                         act.vrf_ctx.as_mut().unwrap(),
                         act.chain_state.reputation_engine.as_ref().unwrap(),
                         act.epoch_constants.unwrap(),
+                        &act.chain_state.protocol_upgrades,
                     ) {
                         Ok(_) => {
-                            // Send AddCandidates message to self
-                            // This will run all the validations again
+                            // The block has just been validated, so it can skip the generic
+                            // candidates path and be broadcast right away
 
                             let block_hash = block.hash();
                             log::info!(
                                 "Proposed block candidate {}",
                                 Yellow.bold().paint(block_hash.to_string())
                             );
-                            act.handle(
-                                AddCandidates {
-                                    blocks: vec![block],
-                                },
-                                ctx,
-                            );
+                            act.broadcast_candidate(block.clone());
+                            act.candidates.insert(block_hash, block);
                         }
 
                         Err(e) => error!("Error trying to mine a block: {}", e),
@@ -211,6 +233,7 @@ impl ChainManager {
             .chain_state
             .data_request_pool
             .get_dr_output_pointers_by_epoch(current_epoch);
+        self.data_request_commits = 0;
 
         let rep_eng = self.chain_state.reputation_engine.as_ref().unwrap();
         let my_reputation = rep_eng.trs.get(&own_pkh);
@@ -222,13 +245,23 @@ impl ChainManager {
             my_reputation.0, total_active_reputation.0, num_active_identities,
         );
 
-        for (dr_pointer, data_request_output) in dr_pointers.into_iter().filter_map(|dr_pointer| {
-            // Filter data requests that are not in data_request_pool
-            self.chain_state
-                .data_request_pool
-                .get_dr_output(&dr_pointer)
-                .map(|data_request_output| (dr_pointer, data_request_output))
-        }) {
+        let mut data_requests: Vec<_> = dr_pointers
+            .into_iter()
+            .filter_map(|dr_pointer| {
+                // Filter data requests that are not in data_request_pool
+                self.chain_state
+                    .data_request_pool
+                    .get_dr_output(&dr_pointer)
+                    .map(|data_request_output| (dr_pointer, data_request_output))
+            })
+            .collect();
+        // The eligibility is checked in this order, so the most profitable data requests are
+        // committed to first if the number of commits per epoch is limited
+        data_requests.sort_by_key(|(_, data_request_output)| {
+            std::cmp::Reverse(data_request_priority(data_request_output))
+        });
+
+        for (dr_pointer, data_request_output) in data_requests {
             let num_witnesses =
                 data_request_output.witnesses + data_request_output.backup_witnesses;
             // The beacon used to create and verify data requests must be set to the current epoch
@@ -243,7 +276,8 @@ impl ChainManager {
                         dr_pointer, e
                     )
                 })
-                .map(move |(vrf_proof, vrf_proof_hash)| {
+                .into_actor(self)
+                .and_then(move |(vrf_proof, vrf_proof_hash), act, _ctx| {
                     // invalid: vrf_hash > target_hash
                     let target_hash = calculate_reppoe_threshold(
                         my_reputation,
@@ -252,31 +286,58 @@ impl ChainManager {
                         num_active_identities,
                     );
                     let proof_invalid = vrf_proof_hash > target_hash;
+                    if let Some(summary) = act.epoch_summary_for(current_epoch) {
+                        summary.data_requests += 1;
+                        if !proof_invalid {
+                            summary.data_requests_eligible += 1;
+                        }
+                    }
 
                     debug!("{} witnesses", num_witnesses);
                     debug!("[DR] Target hash: {}", target_hash);
                     debug!("[DR] Our proof:   {}", vrf_proof_hash);
+                    let max_commits = act.data_request_max_commits_per_epoch;
                     if proof_invalid {
                         debug!("No eligibility for data request {}", dr_pointer);
-                        Err(())
+                        actix::fut::err(())
+                    } else if max_commits > 0 && act.data_request_commits >= max_commits {
+                        info!(
+                            "Skipping data request {}: already committed to {} data requests in \
+                             epoch #{}",
+                            dr_pointer, max_commits, current_epoch
+                        );
+                        if let Some(summary) = act.epoch_summary_for(current_epoch) {
+                            summary.data_requests_over_quota += 1;
+                        }
+                        actix::fut::err(())
                     } else {
+                        act.data_request_commits += 1;
                         info!(
                             "{} Discovered eligibility for mining a data request {} for epoch #{}",
                             Yellow.bold().paint("[Mining]"),
                             Yellow.bold().paint(dr_pointer.to_string()),
                             Yellow.bold().paint(current_epoch.to_string())
                         );
-                        Ok(vrf_proof)
+                        actix::fut::ok(vrf_proof)
                     }
                 })
-                .flatten()
-                .and_then(move |vrf_proof| {
+                .and_then(move |vrf_proof, act, _ctx| {
                     let rad_request = data_request_output.data_request.clone();
+                    let http_policy =
+                        if act.chain_state.protocol_upgrades.is_active(RAD_HTTP_POLICY) {
+                            Some(PROTOCOL_HTTP_POLICY)
+                        } else {
+                            None
+                        };
 
                     // Send ResolveRA message to RADManager
                     let rad_manager_addr = System::current().registry().get::<RadManager>();
                     rad_manager_addr
-                        .send(ResolveRA { rad_request })
+                        .send(ResolveRA {
+                            dr_pointer,
+                            rad_request,
+                            http_policy,
+                        })
                         .map(|result| match result {
                             Ok(value) => Ok((vrf_proof, value)),
                             Err(e) => {
@@ -285,9 +346,10 @@ impl ChainManager {
                             }
                         })
                         .map_err(|e| log::error!("Couldn't resolve rad request: {}", e))
+                        .flatten()
+                        .into_actor(act)
                 })
-                .flatten()
-                .and_then(move |(vrf_proof, reveal_value)| {
+                .and_then(move |(vrf_proof, reveal_value), act, _ctx| {
                     let vrf_proof_dr = DataRequestEligibilityClaim { proof: vrf_proof };
 
                     let reveal_body = RevealTransactionBody::new(dr_pointer, reveal_value, own_pkh);
@@ -312,8 +374,8 @@ impl ChainManager {
                                 })
                                 .map_err(|e| log::error!("Couldn't sign commit body: {}", e))
                         })
+                        .into_actor(act)
                 })
-                .into_actor(self)
                 .and_then(move |(commit_transaction, reveal_transaction), act, ctx| {
                     // Hold reveal transaction under "waiting_for_reveal" field of data requests pool
                     act.chain_state
@@ -335,6 +397,93 @@ impl ChainManager {
         }
     }
 
+    /// Assemble the block this node would mine right now, in the same way as `try_mine_block`,
+    /// but without checking its eligibility, signing it or broadcasting it.
+    ///
+    /// If `tx_hashes` is set, only those transactions of the transactions pool are considered,
+    /// along with the unconfirmed ancestors of the value transfer transactions. The tally
+    /// transactions are always included, as they are not part of the transactions pool.
+    pub fn build_block_template(
+        &mut self,
+        tx_hashes: Option<Vec<Hash>>,
+    ) -> ResponseActFuture<Self, BlockTemplate, failure::Error> {
+        let current_epoch = match self.current_epoch {
+            Some(epoch) => epoch,
+            None => return Box::new(actix::fut::err(ChainManagerError::ChainNotReady.into())),
+        };
+        let mut beacon = match &self.chain_state.chain_info {
+            Some(chain_info) => chain_info.highest_block_checkpoint,
+            None => return Box::new(actix::fut::err(ChainManagerError::ChainNotReady.into())),
+        };
+        beacon.checkpoint = current_epoch;
+
+        let fut = self
+            .create_tally_transactions()
+            .map_err(|()| failure::err_msg("Failed to create the tally transactions"))
+            .into_actor(self)
+            .map(move |tally_transactions, act, _ctx| {
+                // The real transactions pool must not be modified
+                let mut transactions_pool = act.transactions_pool.clone();
+                if let Some(tx_hashes) = &tx_hashes {
+                    transactions_pool.retain_hashes(&tx_hashes.iter().cloned().collect());
+                }
+                let max_block_weight = act.max_block_weight_at(current_epoch);
+                let old_transactions_weight = (u64::from(max_block_weight)
+                    * u64::from(act.old_transactions_weight_percentage.min(100))
+                    / 100) as u32;
+                let old_transactions_timestamp =
+                    get_timestamp() - act.old_transactions_min_age.as_secs() as i64;
+
+                let (mut block_header, txns) = build_block(
+                    (
+                        &mut transactions_pool,
+                        &act.chain_state.unspent_outputs_pool,
+                        &act.chain_state.data_request_pool,
+                    ),
+                    max_block_weight,
+                    (old_transactions_weight, old_transactions_timestamp),
+                    beacon,
+                    BlockEligibilityClaim::default(),
+                    &tally_transactions,
+                    act.own_pkh.unwrap_or_default(),
+                );
+                block_header.version = act
+                    .chain_state
+                    .protocol_upgrades
+                    .block_version(PROTOCOL_UPGRADES, current_epoch);
+
+                let weight = txns
+                    .value_transfer_txns
+                    .iter()
+                    .map(|vt_tx| vt_tx.weight())
+                    .sum();
+                let fees = txns
+                    .mint
+                    .output
+                    .value
+                    .saturating_sub(block_reward(current_epoch));
+                let excluded = tx_hashes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|hash| !block_includes(&txns, hash))
+                    .collect();
+
+                BlockTemplate {
+                    block: Block {
+                        block_header,
+                        block_sig: KeyedSignature::default(),
+                        txns,
+                    },
+                    weight,
+                    max_block_weight,
+                    fees,
+                    excluded,
+                }
+            });
+
+        Box::new(fut)
+    }
+
     fn create_tally_transactions(
         &mut self,
     ) -> impl Future<Item = Vec<TallyTransaction>, Error = ()> {
@@ -434,12 +583,41 @@ impl ChainManager {
     }
 }
 
+/// Priority of a data request when the number of data requests committed to per epoch is
+/// limited: the reward of each witness. Witnesses do not put up any collateral yet, so this is
+/// also the reward-to-collateral ratio for a constant collateral.
+fn data_request_priority(data_request_output: &DataRequestOutput) -> u64 {
+    let fees = data_request_output
+        .commit_fee
+        .saturating_add(data_request_output.reveal_fee)
+        .saturating_add(data_request_output.tally_fee);
+    let witnesses = std::cmp::max(data_request_output.witnesses, 1);
+
+    data_request_output.value.saturating_sub(fees) / u64::from(witnesses)
+}
+
+/// Whether a block includes the transaction with the given hash
+fn block_includes(txns: &BlockTransactions, hash: &Hash) -> bool {
+    txns.value_transfer_txns.iter().any(|tx| tx.hash() == *hash)
+        || txns.data_request_txns.iter().any(|tx| tx.hash() == *hash)
+        || txns.commit_txns.iter().any(|tx| tx.hash() == *hash)
+        || txns.reveal_txns.iter().any(|tx| tx.hash() == *hash)
+        || txns.tally_txns.iter().any(|tx| tx.hash() == *hash)
+}
+
 /// Build a new Block using the supplied leadership proof and by filling transactions from the
 /// `transaction_pool`
+///
+/// Value transfer transactions are included by priority (fee per weight unit, oldest first in
+/// case of a tie), except for `old_transactions`: a weight limit and an arrival timestamp. The
+/// transactions that arrived before that timestamp are included first, oldest first and
+/// regardless of their fee, until the weight limit is reached. This way, low fee transactions
+/// are not starved by higher fee ones.
 /// Returns an unsigned block!
 fn build_block(
     pools_ref: (&mut TransactionsPool, &UnspentOutputsPool, &DataRequestPool),
     max_block_weight: u32,
+    old_transactions: (u32, i64),
     beacon: CheckpointBeacon,
     proof: BlockEligibilityClaim,
     tally_transactions: &[TallyTransaction],
@@ -455,34 +633,63 @@ fn build_block(
     let mut data_request_txns = Vec::new();
     let mut tally_txns = Vec::new();
 
+    // Old transactions can only use the weight reserved for them, while the rest of
+    // transactions can use all the block weight
+    let (old_transactions_weight, old_transactions_timestamp) = old_transactions;
+    let vt_candidates = transactions_pool
+        .vt_arrived_before(old_transactions_timestamp)
+        .into_iter()
+        .map(|vt_tx| (vt_tx, old_transactions_weight.min(max_block_weight)))
+        .chain(
+            transactions_pool
+                .vt_iter()
+                .map(|vt_tx| (vt_tx, max_block_weight)),
+        );
+    let mut included_vt_txns = HashSet::new();
+
     // Currently only value transfer transactions weight is taking into account
-    for vt_tx in transactions_pool.vt_iter() {
-        // Currently, 1 weight unit is equivalent to 1 byte
-        let transaction_weight = vt_tx.size();
-        let transaction_fee = match vt_transaction_fee(&vt_tx, &utxo_diff) {
-            Ok(x) => x,
-            Err(e) => {
-                warn!(
-                    "Error when calculating transaction fee for transaction: {}",
-                    e
-                );
-                continue;
-            }
-        };
+    for (vt_tx, weight_limit) in vt_candidates {
+        let vt_hash = vt_tx.hash();
+        if included_vt_txns.contains(&vt_hash) {
+            continue;
+        }
 
-        let new_block_weight = block_weight + transaction_weight;
+        // The unconfirmed ancestors of the transaction must be included before it, so they are
+        // only included if the whole package fits in the block
+        let package: Vec<&VTTransaction> = transactions_pool
+            .vt_package(&vt_hash)
+            .into_iter()
+            .filter(|vt_tx| !included_vt_txns.contains(&vt_tx.hash()))
+            .collect();
+        let package_weight: u32 = package.iter().map(|vt_tx| vt_tx.weight()).sum();
+        let new_block_weight = block_weight + package_weight;
+
+        if new_block_weight <= weight_limit {
+            for vt_tx in package {
+                let transaction_fee = match vt_transaction_fee(&vt_tx, &utxo_diff) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        warn!(
+                            "Error when calculating transaction fee for transaction: {}",
+                            e
+                        );
+                        break;
+                    }
+                };
 
-        if new_block_weight <= max_block_weight {
-            value_transfer_txns.push(vt_tx.clone());
+                let vt_hash = vt_tx.hash();
+                value_transfer_txns.push(vt_tx.clone());
+                included_vt_txns.insert(vt_hash);
 
-            update_utxo_diff(
-                &mut utxo_diff,
-                vt_tx.body.inputs.iter().collect(),
-                vt_tx.body.outputs.iter().collect(),
-                vt_tx.hash(),
-            );
-            transaction_fees += transaction_fee;
-            block_weight += transaction_weight;
+                update_utxo_diff(
+                    &mut utxo_diff,
+                    vt_tx.body.inputs.iter().collect(),
+                    vt_tx.body.outputs.iter().collect(),
+                    vt_hash,
+                );
+                transaction_fees += transaction_fee;
+                block_weight += vt_tx.weight();
+            }
         }
 
         if new_block_weight == max_block_weight {
@@ -586,6 +793,23 @@ mod tests {
     use witnet_protected::Protected;
     use witnet_validations::validations::validate_block_signature;
 
+    #[test]
+    fn data_request_priority_is_reward_per_witness() {
+        let data_request_output = |value, witnesses| DataRequestOutput {
+            value,
+            witnesses,
+            commit_fee: 10,
+            reveal_fee: 10,
+            tally_fee: 10,
+            ..DataRequestOutput::default()
+        };
+
+        assert_eq!(data_request_priority(&data_request_output(1030, 4)), 250);
+        assert_eq!(data_request_priority(&data_request_output(530, 1)), 500);
+        // Malformed data requests do not panic
+        assert_eq!(data_request_priority(&data_request_output(20, 0)), 0);
+    }
+
     #[test]
     fn build_empty_block() {
         // Initialize transaction_pool with 1 transaction
@@ -610,6 +834,7 @@ mod tests {
         let (block_header, txns) = build_block(
             (&mut transaction_pool, &unspent_outputs_pool, &dr_pool),
             max_block_weight,
+            (0, 0),
             block_beacon,
             block_proof,
             &[],
@@ -660,6 +885,7 @@ mod tests {
         let (block_header, txns) = build_block(
             (&mut transaction_pool, &unspent_outputs_pool, &dr_pool),
             max_block_weight,
+            (0, 0),
             block_beacon,
             block_proof,
             &[],
@@ -795,6 +1021,7 @@ mod tests {
         let (block_header, txns) = build_block(
             (&mut transaction_pool, &unspent_outputs_pool, &dr_pool),
             max_block_weight,
+            (0, 0),
             block_beacon,
             block_proof,
             &[],
@@ -816,6 +1043,134 @@ mod tests {
         assert_eq!(block.txns.value_transfer_txns[0], vt_tx1);
     }
 
+    #[test]
+    fn build_block_reserves_weight_for_old_transactions() {
+        // Transactions without inputs and with zero value outputs, so they have no fee
+        let vt_tx = |byte| {
+            VTTransaction::new(
+                VTTransactionBody::new(
+                    vec![],
+                    vec![ValueTransferOutput {
+                        pkh: PublicKeyHash::from_public_key(&PublicKey {
+                            compressed: 2,
+                            bytes: [byte; 32],
+                        }),
+                        value: 0,
+                    }],
+                ),
+                vec![],
+            )
+        };
+        let old_tx = vt_tx(1);
+        let new_tx1 = vt_tx(2);
+        let new_tx2 = vt_tx(3);
+        let tx_weight = old_tx.size();
+
+        // The new transactions claim a higher fee than the old one
+        let mut transaction_pool = TransactionsPool::default();
+        transaction_pool.insert_with_fee(Transaction::ValueTransfer(old_tx.clone()), 0, 100);
+        transaction_pool.insert_with_fee(Transaction::ValueTransfer(new_tx1.clone()), 10, 1_000);
+        transaction_pool.insert_with_fee(Transaction::ValueTransfer(new_tx2.clone()), 10, 2_000);
+
+        let unspent_outputs_pool = UnspentOutputsPool::default();
+        let dr_pool = DataRequestPool::default();
+
+        // Only two transactions fit in the block
+        let max_block_weight = 2 * tx_weight;
+
+        // Without a reserved weight, the transactions with higher fees are included
+        let (_, txns) = build_block(
+            (&mut transaction_pool, &unspent_outputs_pool, &dr_pool),
+            max_block_weight,
+            (0, 500),
+            CheckpointBeacon::default(),
+            BlockEligibilityClaim::default(),
+            &[],
+            PublicKeyHash::default(),
+        );
+        assert_eq!(txns.value_transfer_txns, vec![new_tx1.clone(), new_tx2]);
+
+        // Reserving weight for one transaction, the old transaction is included first
+        let (_, txns) = build_block(
+            (&mut transaction_pool, &unspent_outputs_pool, &dr_pool),
+            max_block_weight,
+            (tx_weight, 500),
+            CheckpointBeacon::default(),
+            BlockEligibilityClaim::default(),
+            &[],
+            PublicKeyHash::default(),
+        );
+        assert_eq!(txns.value_transfer_txns, vec![old_tx, new_tx1]);
+    }
+
+    #[test]
+    fn build_block_includes_unconfirmed_ancestors() {
+        let spent = OutputPointer {
+            transaction_id: Hash::SHA256([1; 32]),
+            output_index: 0,
+        };
+        let mut unspent_outputs_pool = UnspentOutputsPool::default();
+        unspent_outputs_pool.insert(
+            spent.clone(),
+            ValueTransferOutput {
+                pkh: PublicKeyHash::default(),
+                value: 100,
+            },
+        );
+
+        let vt_tx = |inputs, value| {
+            VTTransaction::new(
+                VTTransactionBody::new(
+                    inputs,
+                    vec![ValueTransferOutput {
+                        pkh: PublicKeyHash::default(),
+                        value,
+                    }],
+                ),
+                vec![],
+            )
+        };
+        // The parent pays no fee, but the child spending its output pays for both of them
+        let parent = vt_tx(vec![Input::new(spent)], 100);
+        let child = vt_tx(
+            vec![Input::new(OutputPointer {
+                transaction_id: parent.hash(),
+                output_index: 0,
+            })],
+            70,
+        );
+        let other = vt_tx(vec![], 0);
+
+        let mut transaction_pool = TransactionsPool::default();
+        transaction_pool.insert_with_fee(Transaction::ValueTransfer(parent.clone()), 0, 100);
+        transaction_pool.insert_with_fee(
+            Transaction::ValueTransfer(other.clone()),
+            u64::from(other.size()),
+            200,
+        );
+        transaction_pool.insert_with_fee(
+            Transaction::ValueTransfer(child.clone()),
+            30 * u64::from(child.size()),
+            300,
+        );
+        let dr_pool = DataRequestPool::default();
+
+        // Only the parent and the child fit in the block
+        let max_block_weight = parent.weight() + child.weight();
+
+        let (_, txns) = build_block(
+            (&mut transaction_pool, &unspent_outputs_pool, &dr_pool),
+            max_block_weight,
+            (0, 0),
+            CheckpointBeacon::default(),
+            BlockEligibilityClaim::default(),
+            &[],
+            PublicKeyHash::default(),
+        );
+        assert_eq!(txns.value_transfer_txns, vec![parent, child]);
+        assert_eq!(txns.mint.output.value, block_reward(0) + 30);
+    }
+
     #[test]
     fn test_signature_and_serialization() {
         let secret_key = SecretKey {
@@ -13,6 +13,8 @@
 //! * Consolidating multiple block candidates for the same checkpoint into a single valid block.
 //! * Putting valid blocks into storage by sending them to the inventory manager actor.
 //! * Having a method for letting other components get blocks by *hash* or *checkpoint*.
+//! * Finding the consensus beacon of its peers and consolidating the batches of blocks that the
+//! [SyncManager](actors::sync_manager::SyncManager) downloads to catch up with it.
 //! * Having a method for letting other components get the epoch of the current tip of the
 //! blockchain (e.g. the last epoch field required for the handshake in the Witnet network
 //! protocol).
@@ -26,8 +28,10 @@
 //!     - Removing the UTXOs that the transaction spends as inputs.
 //!     - Adding a new UTXO for every output in the transaction.
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     convert::TryFrom,
+    fmt,
+    time::Duration,
 };
 
 use actix::{
@@ -38,40 +42,75 @@ use ansi_term::Color::{Purple, White, Yellow};
 use failure::Fail;
 use itertools::Itertools;
 use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::{
     actors::{
         inventory_manager::InventoryManager,
         json_rpc::JsonRpcServer,
-        messages::{AddItem, AddTransaction, Broadcast, NewBlock, SendInventoryItem},
+        messages::{
+            AddItem, AddTransaction, Broadcast, BroadcastCandidate, DataRequestEvents,
+            DataRequestTallied, DropReason, GetMempool, GetMempoolResult, NewBlock, NotifyWebhooks,
+            ReputationExpiration, ReputationForecast, SendInventoryItem, SendLastBeacon, StartSync,
+            StopSync, TransactionDropped,
+        },
+        rad_manager::RadManager,
         sessions_manager::SessionsManager,
-        storage_keys::CHAIN_STATE_KEY,
+        storage_keys::{superblock_key, CHAIN_STATE_KEY},
+        sync_manager::SyncManager,
+        webhook_notifier::WebhookNotifier,
     },
     storage_mngr,
 };
+use witnet_config::config::{Connections, WebhookEvent};
 use witnet_data_structures::{
     chain::{
         penalize_factor, reputation_issuance, Alpha, Block, ChainState, CheckpointBeacon,
         ConsensusConstants, DataRequestReport, Epoch, EpochConstants, Hash, Hashable,
-        InventoryItem, OutputPointer, PublicKeyHash, Reputation, ReputationEngine,
-        TransactionsPool, UnspentOutputsPool,
+        InventoryItem, KeyedSignature, OutputPointer, PublicKeyHash, Reputation, ReputationEngine,
+        TransactionsPool, UnspentOutputsPool, ValueTransferOutput,
     },
     data_request::{true_revealer, DataRequestPool},
+    invariants,
+    superblock::Superblock,
     transaction::{TallyTransaction, Transaction},
+    upgrades::PROTOCOL_UPGRADES,
     vrf::VrfCtx,
 };
 use witnet_rad::types::RadonTypes;
-use witnet_validations::validations::{validate_block, validate_candidate, Diff};
+use witnet_util::{crash, timestamp::get_timestamp};
+use witnet_validations::validations::{
+    validate_block, validate_candidate, validate_candidate_timestamp, Diff,
+};
 
 mod actor;
+/// Decisions taken from the beacons of the peers
+pub mod consensus;
 mod handlers;
 mod mining;
+mod orphans;
 /// High level transaction factory
 pub mod transaction_factory;
 
 /// Maximum blocks number to be sent during synchronization process
 pub const MAX_BLOCKS_SYNC: usize = 500;
 
+/// Number of recent blocks used to estimate the number of witnessing acts per epoch
+const ALPHA_HISTORY_LENGTH: usize = 1000;
+
+/// Number of dropped transactions whose drop reason is remembered
+const DROPPED_TRANSACTIONS_LENGTH: usize = 10_000;
+
+/// Maximum number of block candidates from future epochs kept until their epoch starts
+const MAX_QUARANTINED_CANDIDATES: usize = 32;
+
+/// Maximum number of blocks received while synchronizing that are kept until their parent arrives
+const MAX_ORPHAN_BLOCKS: usize = 64;
+
+/// Number of epochs a block received while synchronizing is kept waiting for its parent
+const ORPHAN_BLOCK_MAX_AGE: u32 = 10;
+
 /// Possible errors when interacting with ChainManager
 #[derive(Debug, PartialEq, Fail)]
 pub enum ChainManagerError {
@@ -87,10 +126,39 @@ pub enum ChainManagerError {
     /// The node is not in Synced state
     #[fail(display = "The node is not yet synchronized")]
     NotSynced,
+    /// The node is not in Synchronizing state
+    #[fail(display = "The node is not synchronizing")]
+    NotSynchronizing,
+    /// None of the blocks of a batch could be consolidated because their parent is not known
+    #[fail(display = "The blocks of the batch do not follow the local chain")]
+    UnknownParent,
+    /// A superblock does not exist
+    #[fail(display = "Superblock #{} not found", index)]
+    SuperblockNotFound {
+        /// Superblock index
+        index: u32,
+    },
+    /// An identity is not a member of the ARS of a superblock
+    #[fail(
+        display = "{} is not a member of the ARS of superblock #{}",
+        pkh, index
+    )]
+    NotArsMember {
+        /// Superblock index
+        index: u32,
+        /// Identity
+        pkh: PublicKeyHash,
+    },
+    /// The unspent outputs of an identity are not tracked by this node
+    #[fail(display = "{} is not watched by this node", pkh)]
+    PkhNotWatched {
+        /// Identity
+        pkh: PublicKeyHash,
+    },
 }
 
 /// State Machine
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum StateMachine {
     /// First state, ChainManager is waiting to consensus between its peers
     WaitingConsensus,
@@ -106,6 +174,137 @@ impl Default for StateMachine {
     }
 }
 
+/// Consensus thresholds (in %) used to find the consensus beacon in each state of the
+/// `StateMachine`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConsensusThresholds {
+    /// Threshold used in `StateMachine::WaitingConsensus`
+    pub bootstrap: u32,
+    /// Threshold used in `StateMachine::Synchronizing`
+    pub synchronizing: u32,
+    /// Threshold used in `StateMachine::Synced`
+    pub synced: u32,
+}
+
+impl ConsensusThresholds {
+    /// Read the thresholds from the connections configuration
+    pub fn from_config(connections: &Connections) -> Self {
+        Self {
+            bootstrap: connections.consensus_c_bootstrap,
+            synchronizing: connections.consensus_c_synchronizing,
+            synced: connections.consensus_c_synced,
+        }
+    }
+
+    /// Threshold to use in the given state
+    pub fn for_state(&self, state: StateMachine) -> u32 {
+        match state {
+            StateMachine::WaitingConsensus => self.bootstrap,
+            StateMachine::Synchronizing => self.synchronizing,
+            StateMachine::Synced => self.synced,
+        }
+    }
+}
+
+/// Summary of what the node did during an epoch, logged at INFO level once the epoch is over
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochSummary {
+    /// Epoch
+    pub epoch: Epoch,
+    /// Chain beacon at the end of the epoch
+    pub beacon: CheckpointBeacon,
+    /// State of the state machine at the end of the epoch
+    pub state: StateMachine,
+    /// Number of block candidates received during the epoch
+    pub candidates_received: usize,
+    /// Hash of the block candidate consolidated for this epoch, if any
+    pub chosen_candidate: Option<Hash>,
+    /// Number of transactions of each type in the mempool at the end of the epoch
+    pub mempool: MempoolSummary,
+    /// Number of peers that sent their beacon during the epoch
+    pub peers: usize,
+    /// Whether the node was eligible to mine a block, or `None` if it did not try
+    pub block_eligible: Option<bool>,
+    /// Number of data requests for which the node checked its eligibility
+    pub data_requests: usize,
+    /// Number of data requests the node was eligible to resolve
+    pub data_requests_eligible: usize,
+    /// Number of eligible data requests skipped because the limit of commits per epoch was
+    /// reached
+    pub data_requests_over_quota: usize,
+}
+
+/// Number of transactions of each type in the mempool
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolSummary {
+    /// Value transfer transactions
+    pub value_transfer: usize,
+    /// Data request transactions
+    pub data_request: usize,
+    /// Commit transactions
+    pub commit: usize,
+    /// Reveal transactions
+    pub reveal: usize,
+}
+
+impl EpochSummary {
+    /// Start the summary of an epoch
+    pub fn new(epoch: Epoch) -> Self {
+        Self {
+            epoch,
+            ..Self::default()
+        }
+    }
+}
+
+impl<'a> From<&'a TransactionsPool> for MempoolSummary {
+    fn from(pool: &'a TransactionsPool) -> Self {
+        Self {
+            value_transfer: pool.vt_len(),
+            data_request: pool.dr_len(),
+            commit: pool.commit_len(),
+            reveal: pool.reveal_len(),
+        }
+    }
+}
+
+impl fmt::Display for EpochSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chosen_candidate = self
+            .chosen_candidate
+            .map_or_else(|| "none".to_string(), |hash| hash.to_string());
+        let block_eligible = match self.block_eligible {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "not tried",
+        };
+
+        write!(
+            f,
+            "Epoch #{} | {:?} | beacon #{} {} | candidates: {}, chosen: {} | \
+             mempool: {} vt, {} dr, {} commit, {} reveal | peers: {} | \
+             eligible: block {}, data requests {}/{} ({} over quota)",
+            self.epoch,
+            self.state,
+            self.beacon.checkpoint,
+            self.beacon.hash_prev_block,
+            self.candidates_received,
+            chosen_candidate,
+            self.mempool.value_transfer,
+            self.mempool.data_request,
+            self.mempool.commit,
+            self.mempool.reveal,
+            self.peers,
+            block_eligible,
+            self.data_requests_eligible,
+            self.data_requests,
+            self.data_requests_over_quota,
+        )
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////
 // ACTOR BASIC STRUCTURE
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -124,12 +323,24 @@ pub struct ChainManager {
     max_block_weight: u32,
     /// Mining enabled
     mining_enabled: bool,
-    /// Hash of the genesis block
-    genesis_block_hash: Hash,
+    /// Percentage of the block weight reserved for old value transfer transactions
+    old_transactions_weight_percentage: u8,
+    /// Time after which a value transfer transaction in the pool is considered old
+    old_transactions_min_age: Duration,
+    /// Time after which a value transfer transaction is dropped from the pool
+    transactions_pool_expiry: Duration,
+    /// Maximum number of value transfer transactions in the pool
+    transactions_pool_max_size: usize,
+    /// Maximum number of data requests committed to in the same epoch, no limit if 0
+    data_request_max_commits_per_epoch: u16,
+    /// Number of data requests committed to in the current epoch
+    data_request_commits: u16,
+    /// Reason why the recently dropped transactions were removed from the pool
+    dropped_transactions: HashMap<Hash, DropReason>,
+    /// Hashes of the recently dropped transactions, oldest first
+    dropped_transactions_order: VecDeque<Hash>,
     /// state of the state machine
     sm_state: StateMachine,
-    /// The best beacon known to this node—to which it will try to catch up
-    target_beacon: Option<CheckpointBeacon>,
     /// Map that stores candidate blocks for further validation and consolidation as tip of the blockchain
     candidates: HashMap<Hash, Block>,
     /// Our public key hash, used to create the mint transaction
@@ -138,10 +349,36 @@ pub struct ChainManager {
     vrf_ctx: Option<VrfCtx>,
     /// Peers beacons boolean
     peers_beacons_received: bool,
-    /// Consensus parameter (in %)
-    consensus_c: u32,
+    /// Consensus thresholds (in %) for each state
+    consensus_thresholds: ConsensusThresholds,
     /// Constants used to convert between epoch and timestamp
     epoch_constants: Option<EpochConstants>,
+    /// Running without networking, so there are no peers to broadcast items to
+    offline: bool,
+    /// Summary of the current epoch, still being filled in
+    epoch_summary: Option<EpochSummary>,
+    /// Summary of the last finished epoch
+    last_epoch_summary: Option<EpochSummary>,
+    /// Number of witnessing acts after each of the recent blocks, by block epoch
+    alpha_history: VecDeque<(Epoch, Alpha)>,
+    /// Reputation below which the node warns that it is losing its eligibility
+    reputation_alert_threshold: u32,
+    /// Number of epochs ahead for which the expiration of the node reputation is checked
+    reputation_alert_epochs: u32,
+    /// Whether the node already warned that its reputation is going to expire
+    reputation_alert_sent: bool,
+    /// Check the consistency of the chain state after every consolidated block, also in release
+    /// builds
+    check_invariants: bool,
+    /// Block candidates received before the start of their epoch, evaluated again once it starts
+    quarantined_candidates: HashMap<Hash, Block>,
+    /// Maximum time a block candidate can be ahead of the local clock to be quarantined
+    clock_tolerance: Duration,
+    /// Blocks received while synchronizing before their parent, consolidated once it arrives
+    orphan_blocks: orphans::OrphanPool,
+    /// Tip of the chain discarded by a rollback, notified to the webhooks along with the restored
+    /// tip once the chain state is restored from storage
+    discarded_beacon: Option<CheckpointBeacon>,
 }
 
 /// Required trait for being able to retrieve ChainManager address from registry
@@ -225,7 +462,39 @@ impl ChainManager {
             .wait(ctx);
     }
 
+    /// Method to persist a Superblock into the Storage, notifying the subscribed webhooks once
+    /// the node is synced
+    fn persist_superblock(&self, ctx: &mut Context<Self>, superblock: &Superblock) {
+        let index = superblock.index;
+        let payload = if self.sm_state == StateMachine::Synced {
+            serde_json::to_value(superblock)
+                .map_err(|e| error!("Failed to serialize superblock for webhooks: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        storage_mngr::put(&superblock_key(index), superblock)
+            .into_actor(self)
+            .map_err(|e, _, _| error!("Failed to persist superblock into storage: {}", e))
+            .and_then(move |_, _, _| {
+                debug!("Successfully persisted superblock #{} into storage", index);
+                if let Some(payload) = payload {
+                    WebhookNotifier::from_registry().do_send(NotifyWebhooks {
+                        event: WebhookEvent::Superblock,
+                        payload,
+                    });
+                }
+                fut::ok(())
+            })
+            .wait(ctx);
+    }
+
     fn broadcast_item(&self, item: InventoryItem) {
+        if self.offline {
+            debug!("Running in offline mode, the inventory item will not be broadcast");
+            return;
+        }
+
         // Get SessionsManager address
         let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
 
@@ -235,6 +504,53 @@ impl ChainManager {
         });
     }
 
+    /// Get the summary of the given epoch, if it is the one being filled in
+    fn epoch_summary_for(&mut self, epoch: Epoch) -> Option<&mut EpochSummary> {
+        self.epoch_summary
+            .as_mut()
+            .filter(|summary| summary.epoch == epoch)
+    }
+
+    /// Maximum weight of the blocks of the given epoch, including the changes of the protocol
+    /// upgrades active in it
+    fn max_block_weight_at(&self, epoch: Epoch) -> u32 {
+        self.chain_state
+            .chain_info
+            .as_ref()
+            .map_or(self.max_block_weight, |chain_info| {
+                self.chain_state
+                    .protocol_upgrades
+                    .consensus_constants_at(
+                        PROTOCOL_UPGRADES,
+                        &chain_info.consensus_constants,
+                        epoch,
+                    )
+                    .max_block_weight
+            })
+    }
+
+    /// Log the summary of the epoch that just finished and start the summary of the new one
+    fn finish_epoch_summary(&mut self, new_epoch: Epoch) {
+        if let Some(mut summary) = self.epoch_summary.replace(EpochSummary::new(new_epoch)) {
+            summary.beacon = self.get_chain_beacon();
+            summary.state = self.sm_state;
+            summary.mempool = MempoolSummary::from(&self.transactions_pool);
+            info!("{}", summary);
+            self.last_epoch_summary = Some(summary);
+        }
+    }
+
+    fn broadcast_last_beacon(&self, beacon: CheckpointBeacon) {
+        if self.offline {
+            return;
+        }
+
+        SessionsManager::from_registry().do_send(Broadcast {
+            command: SendLastBeacon { beacon },
+            only_inbound: true,
+        });
+    }
+
     fn process_requested_block(
         &mut self,
         ctx: &mut Context<Self>,
@@ -264,6 +580,7 @@ impl ChainManager {
                 vrf_ctx,
                 rep_engine,
                 epoch_constants,
+                &self.chain_state.protocol_upgrades,
             ) {
                 Ok(utxo_diff) => {
                     // Persist block and update ChainState
@@ -278,6 +595,65 @@ impl ChainManager {
         }
     }
 
+    /// Consolidate a block requested while synchronizing, after updating the reputation with the
+    /// epochs without blocks before it
+    fn consolidate_requested_block(
+        &mut self,
+        ctx: &mut Context<Self>,
+        block: &Block,
+    ) -> Result<(), failure::Error> {
+        // Update reputation before checking Proof-of-Eligibility
+        let block_epoch = block.block_header.beacon.checkpoint;
+        if let Some(ref mut rep_engine) = self.chain_state.reputation_engine {
+            if let Err(e) = rep_engine.ars.update_empty(block_epoch) {
+                error!("Error updating reputation before processing block: {}", e);
+            }
+        }
+
+        self.process_requested_block(ctx, block)
+    }
+
+    /// Keep a block requested while synchronizing whose parent is not known yet, so it does not
+    /// have to be downloaded again once the parent arrives
+    fn hold_orphan_block(&mut self, block: Block) {
+        let hash_block = block.hash();
+        let block_epoch = block.block_header.beacon.checkpoint;
+        let received_epoch = self.current_epoch.unwrap_or_default();
+
+        if self
+            .orphan_blocks
+            .insert(block, received_epoch, MAX_ORPHAN_BLOCKS)
+        {
+            debug!(
+                "Holding block {} from epoch {} until its parent arrives ({} blocks waiting)",
+                hash_block,
+                block_epoch,
+                self.orphan_blocks.len()
+            );
+        } else {
+            warn!(
+                "Dropping block {} from epoch {}: too many blocks waiting for their parent",
+                hash_block, block_epoch
+            );
+        }
+    }
+
+    /// Broadcast a block candidate mined by this node.
+    ///
+    /// Unlike `broadcast_item`, the candidate is sent at once to every consolidated session,
+    /// inbound included, starting with the trusted relay peers.
+    fn broadcast_candidate(&self, block: Block) {
+        if self.offline {
+            debug!("Running in offline mode, the block candidate will not be broadcast");
+            return;
+        }
+
+        // Get SessionsManager address
+        let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+        sessions_manager_addr.do_send(BroadcastCandidate { block });
+    }
+
     fn process_candidate(&mut self, block: Block) {
         if let (Some(current_epoch), Some(rep_engine)) = (
             self.current_epoch,
@@ -286,7 +662,16 @@ impl ChainManager {
             let hash_block = block.hash();
             let total_identities = rep_engine.ars.active_identities_number() as u32;
 
+            if block.block_header.beacon.checkpoint > current_epoch {
+                self.quarantine_candidate(hash_block, block);
+                return;
+            }
+
             if !self.candidates.contains_key(&hash_block) {
+                if let Some(summary) = self.epoch_summary.as_mut() {
+                    summary.candidates_received += 1;
+                }
+
                 match validate_candidate(
                     &block,
                     current_epoch,
@@ -305,22 +690,61 @@ impl ChainManager {
         }
     }
 
-    fn persist_blocks_batch(
-        &self,
-        ctx: &mut Context<Self>,
-        blocks: Vec<Block>,
-        target_beacon: CheckpointBeacon,
-    ) {
+    /// Keep a block candidate whose epoch has not started yet, as long as it does not start later
+    /// than the clock tolerance, so a node whose clock is slightly behind does not lose it.
+    fn quarantine_candidate(&mut self, hash_block: Hash, block: Block) {
+        let block_epoch = block.block_header.beacon.checkpoint;
+        let epoch_constants = match self.epoch_constants {
+            Some(x) => x,
+            None => {
+                warn!("ChainManager doesn't have epoch constants");
+                return;
+            }
+        };
+
+        if let Err(e) = validate_candidate_timestamp(
+            block_epoch,
+            epoch_constants,
+            get_timestamp(),
+            self.clock_tolerance.as_secs() as i64,
+        ) {
+            warn!("{}", e);
+        } else if self.quarantined_candidates.len() >= MAX_QUARANTINED_CANDIDATES
+            && !self.quarantined_candidates.contains_key(&hash_block)
+        {
+            warn!(
+                "Dropping block candidate {} from epoch {}: too many candidates from future epochs",
+                hash_block, block_epoch
+            );
+        } else {
+            debug!(
+                "Quarantining block candidate {} until epoch {} starts",
+                hash_block, block_epoch
+            );
+            self.quarantined_candidates.insert(hash_block, block);
+        }
+    }
+
+    fn persist_blocks_batch(&self, ctx: &mut Context<Self>, blocks: Vec<Block>) {
         for block in blocks {
-            let block_hash = block.hash();
             self.persist_item(ctx, InventoryItem::Block(block));
-
-            if block_hash == target_beacon.hash_prev_block {
-                break;
-            }
         }
     }
 
+    /// Start synchronizing the chain up to the consensus beacon, or update the target of the
+    /// synchronization in progress
+    fn start_sync(&self, target_beacon: CheckpointBeacon) {
+        SyncManager::from_registry().do_send(StartSync {
+            target_beacon,
+            chain_beacon: self.get_chain_beacon(),
+        });
+    }
+
+    /// Stop the synchronization in progress when leaving the Synchronizing state
+    fn stop_sync(&self) {
+        SyncManager::from_registry().do_send(StopSync);
+    }
+
     fn consolidate_block(&mut self, ctx: &mut Context<Self>, block: &Block, utxo_diff: Diff) {
         // Update chain_info and reputation_engine
         let epoch_constants = match self.epoch_constants {
@@ -354,6 +778,7 @@ impl ChainManager {
                 };
 
                 chain_info.highest_block_checkpoint = beacon;
+                crash::set_context("chain_beacon", format!("{:?}", beacon));
                 let rep_info = update_pools(
                     &block,
                     &mut self.chain_state.unspent_outputs_pool,
@@ -362,29 +787,71 @@ impl ChainManager {
                     utxo_diff,
                     self.own_pkh,
                     &mut self.chain_state.own_utxos,
+                    &mut self.chain_state.watched_utxos,
                     epoch_constants,
                 );
+                // Drop the transactions that spend the same outputs as the ones in the block
+                let conflicting = self
+                    .transactions_pool
+                    .remove_spending(&spent_outputs(block));
 
                 let miner_pkh = block.txns.mint.output.pkh;
 
+                // Build the superblock of the previous period if the block starts a new one, before
+                // the ARS is updated with the activity of this block
+                let superblock = self.chain_state.superblock_state.process_block(
+                    block_epoch,
+                    block_hash,
+                    &block.block_header.merkle_roots,
+                    reputation_engine.ars.active_identities(),
+                );
+
+                // Reputation is updated with the consensus constants in force in the epoch of the
+                // block, which protocol upgrades can change
+                let consensus_constants =
+                    self.chain_state.protocol_upgrades.consensus_constants_at(
+                        PROTOCOL_UPGRADES,
+                        &chain_info.consensus_constants,
+                        block_epoch,
+                    );
                 update_reputation(
                     reputation_engine,
-                    &chain_info.consensus_constants,
+                    &consensus_constants,
                     miner_pkh,
                     rep_info,
                     log_level,
                     block_epoch,
                 );
+                self.alpha_history
+                    .push_back((block_epoch, reputation_engine.current_alpha));
+                if self.alpha_history.len() > ALPHA_HISTORY_LENGTH {
+                    self.alpha_history.pop_front();
+                }
+
+                // Count the protocol upgrades signaled by the block
+                self.chain_state.protocol_upgrades.process_block(
+                    PROTOCOL_UPGRADES,
+                    block_epoch,
+                    block.block_header.version,
+                );
 
                 // Insert candidate block into `block_chain` state
                 self.chain_state.block_chain.insert(block_epoch, block_hash);
 
+                if let Some(superblock) = superblock {
+                    self.persist_superblock(ctx, &superblock);
+                }
+
+                self.transactions_dropped(conflicting, DropReason::Conflicted);
+
                 match self.sm_state {
                     StateMachine::Synchronizing => {
                         let _reveals = self
                             .chain_state
                             .data_request_pool
                             .update_data_request_stages();
+
+                        self.check_chain_state_invariants(block_epoch);
                     }
                     StateMachine::Synced => {
                         // Persist finished data requests into storage
@@ -393,8 +860,12 @@ impl ChainManager {
                         to_be_stored.into_iter().for_each(|dr_report| {
                             show_info_tally(&dr_report.tally, block_epoch);
                             self.persist_data_request(ctx, &dr_report);
+                            notify_data_request_resolved(&dr_report, block_epoch);
+                            notify_data_request_tallied(&dr_report);
                         });
 
+                        self.check_reputation_expiration(block_epoch);
+
                         log::trace!("{:?}", block);
                         debug!("Mint transaction hash: {:?}", block.txns.mint.hash());
 
@@ -403,6 +874,8 @@ impl ChainManager {
                             .data_request_pool
                             .update_data_request_stages();
 
+                        self.check_chain_state_invariants(block_epoch);
+
                         show_info_dr(&self.chain_state.data_request_pool, &block);
 
                         for reveal in reveals {
@@ -417,6 +890,17 @@ impl ChainManager {
                         // Persist chain_info into storage
                         self.persist_chain_state(ctx);
 
+                        // Send notification to the subscribed webhooks
+                        match serde_json::to_value(block) {
+                            Ok(payload) => {
+                                WebhookNotifier::from_registry().do_send(NotifyWebhooks {
+                                    event: WebhookEvent::Block,
+                                    payload,
+                                })
+                            }
+                            Err(e) => error!("Failed to serialize block for webhooks: {}", e),
+                        }
+
                         // Send notification to JsonRpcServer
                         JsonRpcServer::from_registry().do_send(NewBlock {
                             block: block.clone(),
@@ -424,6 +908,15 @@ impl ChainManager {
                     }
                     _ => {}
                 }
+
+                // The stage transitions of the data requests are only notified once synced
+                let dr_events = self.chain_state.data_request_pool.take_events();
+                if self.sm_state == StateMachine::Synced && !dr_events.is_empty() {
+                    JsonRpcServer::from_registry().do_send(DataRequestEvents {
+                        epoch: block_epoch,
+                        events: dr_events,
+                    });
+                }
             }
             _ => {
                 error!("No ChainInfo loaded in ChainManager");
@@ -431,6 +924,149 @@ impl ChainManager {
         }
     }
 
+    /// Unspent outputs tracked for an identity, if it is this node or one of the watched
+    /// identities.
+    fn tracked_utxos(&self, pkh: &PublicKeyHash) -> Option<&HashSet<OutputPointer>> {
+        if self.own_pkh.as_ref() == Some(pkh) {
+            Some(&self.chain_state.own_utxos)
+        } else {
+            self.chain_state.watched_utxos.get(pkh)
+        }
+    }
+
+    /// Remember why some transactions were dropped from the transactions pool, so it can be
+    /// queried with `getTransactionStatus`, and notify the JSON-RPC subscribers.
+    fn transactions_dropped(&mut self, hashes: Vec<Hash>, reason: DropReason) {
+        for hash in hashes {
+            debug!("Transaction {} dropped from the pool: {:?}", hash, reason);
+            if self.dropped_transactions.insert(hash, reason).is_none() {
+                self.dropped_transactions_order.push_back(hash);
+            }
+            JsonRpcServer::from_registry().do_send(TransactionDropped { hash, reason });
+        }
+
+        while self.dropped_transactions_order.len() > DROPPED_TRANSACTIONS_LENGTH {
+            if let Some(hash) = self.dropped_transactions_order.pop_front() {
+                self.dropped_transactions.remove(&hash);
+            }
+        }
+    }
+
+    /// Drop the value transfer transactions that have been waiting in the pool for longer than
+    /// the pool expiry time.
+    fn remove_expired_transactions(&mut self) {
+        if self.transactions_pool_expiry.as_secs() == 0 {
+            return;
+        }
+
+        let expiry_timestamp = get_timestamp() - self.transactions_pool_expiry.as_secs() as i64;
+        let expired = self
+            .transactions_pool
+            .vt_remove_arrived_before(expiry_timestamp);
+        self.transactions_dropped(expired, DropReason::Expired);
+    }
+
+    /// Restore the chain state from storage, discarding the blocks consolidated after the last
+    /// persisted one. The subscribed webhooks are notified once it is restored
+    fn rollback(&mut self, ctx: &mut Context<Self>) {
+        self.discarded_beacon = Some(self.get_chain_beacon());
+        self.initialize_from_storage(ctx);
+    }
+
+    /// Forget the alpha of the blocks after `epoch`, which are no longer part of the chain after
+    /// restoring the chain state from storage
+    fn truncate_alpha_history(&mut self, epoch: Epoch) {
+        while self
+            .alpha_history
+            .back()
+            .map_or(false, |(alpha_epoch, _)| *alpha_epoch > epoch)
+        {
+            self.alpha_history.pop_back();
+        }
+    }
+
+    /// Forecast the expiration of the node reputation, as of the given epoch
+    fn reputation_forecast(&self, epoch: Epoch) -> Option<ReputationForecast> {
+        let own_pkh = self.own_pkh?;
+        let rep_eng = self.chain_state.reputation_engine.as_ref()?;
+
+        Some(reputation_forecast(
+            rep_eng,
+            own_pkh,
+            &self.alpha_history,
+            epoch,
+        ))
+    }
+
+    /// Stop the node if the chain state is inconsistent after consolidating a block, before it
+    /// is persisted. The checks always run in debug builds, and in release builds only if
+    /// enabled in the configuration
+    fn check_chain_state_invariants(&self, block_epoch: Epoch) {
+        if !cfg!(debug_assertions) && !self.check_invariants {
+            return;
+        }
+
+        let errors = invariants::check_chain_state(&self.chain_state, self.own_pkh);
+        if errors.is_empty() {
+            return;
+        }
+
+        for e in &errors {
+            error!(
+                "Chain state invariant broken after block #{}: {}",
+                block_epoch, e
+            );
+        }
+        panic!(
+            "The chain state is inconsistent after consolidating the block of epoch #{} ({} broken invariants)",
+            block_epoch,
+            errors.len()
+        );
+    }
+
+    /// Warn if the node reputation is going to expire below the alert threshold in the next
+    /// `reputation_alert_epochs` epochs, notifying the subscribed webhooks.
+    ///
+    /// The warning is only sent once, until the forecast changes.
+    fn check_reputation_expiration(&mut self, epoch: Epoch) {
+        let threshold = self.reputation_alert_threshold;
+        if threshold == 0 {
+            return;
+        }
+        let forecast = match self.reputation_forecast(epoch) {
+            Some(forecast) => forecast,
+            None => return,
+        };
+
+        let alert_epoch = epoch.saturating_add(self.reputation_alert_epochs);
+        let projected_reputation = forecast.reputation_at(alert_epoch);
+        let expiring = forecast.reputation >= threshold && projected_reputation < threshold;
+        if expiring && !self.reputation_alert_sent {
+            warn!(
+                "The reputation of this node ({}) is expected to fall to {} by epoch #{}, below \
+                 the alert threshold of {}",
+                forecast.reputation, projected_reputation, alert_epoch, threshold
+            );
+
+            match serde_json::to_value(&forecast) {
+                Ok(forecast) => WebhookNotifier::from_registry().do_send(NotifyWebhooks {
+                    event: WebhookEvent::ReputationExpiration,
+                    payload: json!({
+                        "epoch": alert_epoch,
+                        "projected_reputation": projected_reputation,
+                        "threshold": threshold,
+                        "forecast": forecast,
+                    }),
+                }),
+                Err(e) => error!(
+                    "Failed to serialize reputation forecast for webhooks: {}",
+                    e
+                ),
+            }
+        }
+        self.reputation_alert_sent = expiring;
+    }
+
     fn get_chain_beacon(&self) -> CheckpointBeacon {
         self.chain_state
             .chain_info
@@ -485,6 +1121,25 @@ impl ReputationInfo {
 }
 
 // Helper methods
+/// Outputs spent by the value transfer and data request transactions of a block
+fn spent_outputs(block: &Block) -> HashSet<OutputPointer> {
+    let vt_inputs = block
+        .txns
+        .value_transfer_txns
+        .iter()
+        .flat_map(|vt_tx| &vt_tx.body.inputs);
+    let dr_inputs = block
+        .txns
+        .data_request_txns
+        .iter()
+        .flat_map(|dr_tx| &dr_tx.body.inputs);
+
+    vt_inputs
+        .chain(dr_inputs)
+        .map(|input| input.output_pointer().clone())
+        .collect()
+}
+
 #[allow(clippy::too_many_arguments)]
 fn update_pools(
     block: &Block,
@@ -494,6 +1149,7 @@ fn update_pools(
     utxo_diff: Diff,
     own_pkh: Option<PublicKeyHash>,
     own_utxos: &mut HashSet<OutputPointer>,
+    watched_utxos: &mut HashMap<PublicKeyHash, HashSet<OutputPointer>>,
     epoch_constants: EpochConstants,
 ) -> ReputationInfo {
     let mut rep_info = ReputationInfo::new();
@@ -557,6 +1213,25 @@ fn update_pools(
         );
     }
 
+    // Update watched_utxos:
+    if !watched_utxos.is_empty() {
+        utxo_diff.visit(
+            watched_utxos,
+            |watched_utxos, output_pointer, output| {
+                // Insert new outputs
+                if let Some(utxos) = watched_utxos.get_mut(&output.pkh) {
+                    utxos.insert(output_pointer.clone());
+                }
+            },
+            |watched_utxos, output_pointer| {
+                // Remove spent inputs
+                for utxos in watched_utxos.values_mut() {
+                    utxos.remove(output_pointer);
+                }
+            },
+        );
+    }
+
     utxo_diff.apply(unspent_outputs_pool);
 
     rep_info
@@ -719,6 +1394,147 @@ fn update_reputation(
     rep_eng.current_alpha = new_alpha;
 }
 
+/// Forecast the expiration of the reputation of an identity, estimating the epoch of each
+/// expiration from the number of witnessing acts per epoch in the recent blocks.
+fn reputation_forecast(
+    rep_eng: &ReputationEngine,
+    pkh: PublicKeyHash,
+    alpha_history: &VecDeque<(Epoch, Alpha)>,
+    epoch: Epoch,
+) -> ReputationForecast {
+    let current_alpha = rep_eng.current_alpha.0;
+    let alpha_per_epoch = match (alpha_history.front(), alpha_history.back()) {
+        (Some((first_epoch, first_alpha)), Some((last_epoch, last_alpha)))
+            if last_epoch > first_epoch && last_alpha > first_alpha =>
+        {
+            Some(f64::from(last_alpha.0 - first_alpha.0) / f64::from(last_epoch - first_epoch))
+        }
+        _ => None,
+    };
+
+    let expirations = rep_eng
+        .expirations(&pkh)
+        .into_iter()
+        .map(|(alpha, reputation)| ReputationExpiration {
+            alpha: alpha.0,
+            reputation: reputation.0,
+            epoch: alpha_per_epoch.map(|alpha_per_epoch| {
+                let epochs_left =
+                    f64::from(alpha.0.saturating_sub(current_alpha)) / alpha_per_epoch;
+
+                epoch.saturating_add(epochs_left.ceil() as Epoch)
+            }),
+        })
+        .collect();
+
+    ReputationForecast {
+        pkh,
+        epoch,
+        reputation: rep_eng.trs.get(&pkh).0,
+        alpha: current_alpha,
+        alpha_per_epoch,
+        expirations,
+    }
+}
+
+/// Hashes of the transactions of the pool matching the filters of `GetMempool`
+fn mempool_transactions(
+    pool: &TransactionsPool,
+    GetMempool { pkh, dr_hash }: GetMempool,
+) -> GetMempoolResult {
+    let involves_pkh = |signatures: &[KeyedSignature], outputs: &[ValueTransferOutput]| {
+        pkh.map_or(true, |pkh| {
+            signatures.iter().any(|ks| ks.public_key.pkh() == pkh)
+                || outputs.iter().any(|output| output.pkh == pkh)
+        })
+    };
+
+    // Filtering by data request only returns its commits and reveals
+    let (value_transfer, data_request) = if dr_hash.is_some() {
+        (vec![], vec![])
+    } else {
+        (
+            pool.vt_iter()
+                .filter(|vt| involves_pkh(&vt.signatures, &vt.body.outputs))
+                .map(Hashable::hash)
+                .collect(),
+            pool.dr_iter()
+                .filter(|dr| involves_pkh(&dr.signatures, &dr.body.outputs))
+                .map(Hashable::hash)
+                .collect(),
+        )
+    };
+    let belongs_to_dr = |dr_pointer: Hash| dr_hash.map_or(true, |dr_hash| dr_hash == dr_pointer);
+    let commit = pool
+        .co_iter()
+        .filter(|co| belongs_to_dr(co.body.dr_pointer) && involves_pkh(&co.signatures, &[]))
+        .map(Hashable::hash)
+        .collect();
+    let reveal = pool
+        .re_iter()
+        .filter(|re| {
+            belongs_to_dr(re.body.dr_pointer)
+                && (pkh == Some(re.body.pkh) || involves_pkh(&re.signatures, &[]))
+        })
+        .map(Hashable::hash)
+        .collect();
+
+    GetMempoolResult {
+        value_transfer,
+        data_request,
+        commit,
+        reveal,
+    }
+}
+
+fn notify_data_request_resolved(dr_report: &DataRequestReport, block_epoch: Epoch) {
+    match serde_json::to_value(dr_report) {
+        Ok(report) => WebhookNotifier::from_registry().do_send(NotifyWebhooks {
+            event: WebhookEvent::DataRequestResolved,
+            payload: json!({
+                "dr_pointer": dr_report.tally.dr_pointer.to_string(),
+                "epoch": block_epoch,
+                "report": report,
+            }),
+        }),
+        Err(e) => error!(
+            "Failed to serialize data request report for webhooks: {}",
+            e
+        ),
+    }
+}
+
+fn notify_rollback(discarded_beacon: CheckpointBeacon, restored_beacon: CheckpointBeacon) {
+    WebhookNotifier::from_registry().do_send(NotifyWebhooks {
+        event: WebhookEvent::Rollback,
+        payload: rollback_payload(discarded_beacon, restored_beacon),
+    });
+}
+
+/// Data of the rollback notifications: the tip of the abandoned chain and the tip of the chain
+/// restored from storage
+fn rollback_payload(
+    discarded_beacon: CheckpointBeacon,
+    restored_beacon: CheckpointBeacon,
+) -> serde_json::Value {
+    json!({
+        "discarded_epoch": discarded_beacon.checkpoint,
+        "discarded_block_hash": discarded_beacon.hash_prev_block.to_string(),
+        "epoch": restored_beacon.checkpoint,
+        "block_hash": restored_beacon.hash_prev_block.to_string(),
+    })
+}
+
+fn notify_data_request_tallied(dr_report: &DataRequestReport) {
+    System::current()
+        .registry()
+        .get::<RadManager>()
+        .do_send(DataRequestTallied {
+            dr_pointer: dr_report.tally.dr_pointer,
+            tally: dr_report.tally.tally.clone(),
+        });
+}
+
 fn show_info_tally(tally_tx: &TallyTransaction, block_epoch: Epoch) {
     let result = RadonTypes::try_from(tally_tx.tally.as_slice())
         .map(|x| x.to_string())
@@ -769,3 +1585,175 @@ fn show_info_dr(data_request_pool: &DataRequestPool, block: &Block) {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_data_structures::{
+        chain::{DataRequestOutput, PublicKey},
+        transaction::{
+            CommitTransaction, CommitTransactionBody, DRTransaction, DRTransactionBody,
+            RevealTransaction, RevealTransactionBody, VTTransaction, VTTransactionBody,
+        },
+    };
+
+    #[test]
+    fn rollback_payload_has_the_restored_tip() {
+        let discarded = CheckpointBeacon {
+            checkpoint: 120,
+            hash_prev_block: Hash::SHA256([1; 32]),
+        };
+        let restored = CheckpointBeacon {
+            checkpoint: 100,
+            hash_prev_block: Hash::SHA256([2; 32]),
+        };
+
+        assert_eq!(
+            rollback_payload(discarded, restored),
+            json!({
+                "discarded_epoch": 120,
+                "discarded_block_hash": Hash::SHA256([1; 32]).to_string(),
+                "epoch": 100,
+                "block_hash": Hash::SHA256([2; 32]).to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn alpha_history_is_truncated_to_the_restored_tip() {
+        let mut chain_manager = ChainManager::default();
+        chain_manager.alpha_history = vec![(10, Alpha(1)), (11, Alpha(3)), (13, Alpha(4))]
+            .into_iter()
+            .collect();
+
+        chain_manager.truncate_alpha_history(13);
+        assert_eq!(chain_manager.alpha_history.len(), 3);
+        chain_manager.truncate_alpha_history(12);
+        assert_eq!(
+            chain_manager.alpha_history,
+            vec![(10, Alpha(1)), (11, Alpha(3))]
+        );
+        chain_manager.truncate_alpha_history(0);
+        assert!(chain_manager.alpha_history.is_empty());
+    }
+
+    fn signature(n: u8) -> KeyedSignature {
+        KeyedSignature {
+            public_key: PublicKey {
+                compressed: 2,
+                bytes: [n; 32],
+            },
+            ..KeyedSignature::default()
+        }
+    }
+
+    fn output(pkh: PublicKeyHash) -> ValueTransferOutput {
+        ValueTransferOutput { pkh, value: 1 }
+    }
+
+    /// Pool with a value transfer from 1 to 2, a data request of 1, and the commits and reveals
+    /// of 3 and 4 for that data request and another one
+    fn mempool() -> (TransactionsPool, Hash) {
+        let mut pool = TransactionsPool::default();
+        pool.insert(Transaction::ValueTransfer(VTTransaction::new(
+            VTTransactionBody::new(vec![], vec![output(signature(2).public_key.pkh())]),
+            vec![signature(1)],
+        )));
+        let dr = DRTransaction::new(
+            DRTransactionBody::new(vec![], vec![], DataRequestOutput::default()),
+            vec![signature(1)],
+        );
+        let dr_hash = dr.hash();
+        pool.insert(Transaction::DataRequest(dr));
+        for &dr_pointer in &[dr_hash, Hash::SHA256([9; 32])] {
+            for &n in &[3, 4] {
+                pool.insert(Transaction::Commit(CommitTransaction::new(
+                    CommitTransactionBody::new(dr_pointer, Hash::default(), Default::default()),
+                    vec![signature(n)],
+                )));
+                pool.insert(Transaction::Reveal(RevealTransaction::new(
+                    RevealTransactionBody::new(dr_pointer, vec![n], signature(n).public_key.pkh()),
+                    vec![signature(n)],
+                )));
+            }
+        }
+
+        (pool, dr_hash)
+    }
+
+    #[test]
+    fn mempool_without_filters() {
+        let (pool, _) = mempool();
+
+        let result = mempool_transactions(&pool, GetMempool::default());
+        assert_eq!(result.value_transfer.len(), 1);
+        assert_eq!(result.data_request.len(), 1);
+        assert_eq!(result.commit.len(), 4);
+        assert_eq!(result.reveal.len(), 4);
+    }
+
+    #[test]
+    fn mempool_filtered_by_pkh() {
+        let (pool, dr_hash) = mempool();
+        let filter = |n| GetMempool {
+            pkh: Some(signature(n).public_key.pkh()),
+            dr_hash: None,
+        };
+
+        // Signers of the value transfer and the data request
+        let result = mempool_transactions(&pool, filter(1));
+        assert_eq!(result.value_transfer.len(), 1);
+        assert_eq!(result.data_request, vec![dr_hash]);
+        assert!(result.commit.is_empty());
+        assert!(result.reveal.is_empty());
+
+        // Receivers of the value transfer
+        let result = mempool_transactions(&pool, filter(2));
+        assert_eq!(result.value_transfer.len(), 1);
+        assert!(result.data_request.is_empty());
+
+        // Witnesses
+        let result = mempool_transactions(&pool, filter(3));
+        assert!(result.value_transfer.is_empty());
+        assert!(result.data_request.is_empty());
+        assert_eq!(result.commit.len(), 2);
+        assert_eq!(result.reveal.len(), 2);
+
+        let result = mempool_transactions(&pool, filter(5));
+        assert_eq!(result, GetMempoolResult::default());
+    }
+
+    #[test]
+    fn mempool_filtered_by_data_request() {
+        let (pool, dr_hash) = mempool();
+
+        // Only the commits and reveals of the data request are returned
+        let result = mempool_transactions(
+            &pool,
+            GetMempool {
+                pkh: None,
+                dr_hash: Some(dr_hash),
+            },
+        );
+        assert!(result.value_transfer.is_empty());
+        assert!(result.data_request.is_empty());
+        assert_eq!(result.commit.len(), 2);
+        assert_eq!(result.reveal.len(), 2);
+
+        // Both filters must match
+        let result = mempool_transactions(
+            &pool,
+            GetMempool {
+                pkh: Some(signature(4).public_key.pkh()),
+                dr_hash: Some(dr_hash),
+            },
+        );
+        assert_eq!(result.commit.len(), 1);
+        assert_eq!(result.reveal.len(), 1);
+        let reveal = pool
+            .re_iter()
+            .find(|re| re.body.dr_pointer == dr_hash && re.body.reveal == vec![4])
+            .unwrap();
+        assert_eq!(result.reveal, vec![reveal.hash()]);
+    }
+}
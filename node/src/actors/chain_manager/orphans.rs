@@ -0,0 +1,138 @@
+//! Blocks received before their parent.
+//!
+//! While catching up, a block can arrive before the block it builds on, e.g. when the blocks of
+//! a batch are not sent in order. Instead of discarding it and downloading it again, the
+//! `ChainManager` keeps it for a few epochs and consolidates it as soon as its parent becomes the
+//! tip of the local chain.
+use std::collections::HashMap;
+
+use witnet_data_structures::chain::{Block, Epoch, Hash, Hashable};
+
+/// Bounded pool of blocks whose parent is not known yet
+#[derive(Debug, Default)]
+pub struct OrphanPool {
+    /// Map block hash -> block and epoch in which it was received
+    blocks: HashMap<Hash, (Block, Epoch)>,
+}
+
+impl OrphanPool {
+    /// Keep a block received during `received_epoch` until its parent arrives.
+    ///
+    /// Returns `false` if the block was not kept because the pool already has `capacity` blocks.
+    pub fn insert(&mut self, block: Block, received_epoch: Epoch, capacity: usize) -> bool {
+        let hash = block.hash();
+        if self.blocks.len() >= capacity && !self.blocks.contains_key(&hash) {
+            return false;
+        }
+        self.blocks.insert(hash, (block, received_epoch));
+
+        true
+    }
+
+    /// Remove and return the blocks whose parent is the block with the given hash, oldest epoch
+    /// first.
+    pub fn take_children(&mut self, parent: Hash) -> Vec<Block> {
+        let hashes: Vec<Hash> = self
+            .blocks
+            .iter()
+            .filter(|(_, (block, _))| block.block_header.beacon.hash_prev_block == parent)
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        let mut children: Vec<Block> = hashes
+            .iter()
+            .filter_map(|hash| self.blocks.remove(hash))
+            .map(|(block, _)| block)
+            .collect();
+        children.sort_by_key(|block| (block.block_header.beacon.checkpoint, block.hash()));
+
+        children
+    }
+
+    /// Drop the blocks received more than `max_age` epochs before `current_epoch`, returning how
+    /// many were dropped.
+    pub fn expire(&mut self, current_epoch: Epoch, max_age: u32) -> usize {
+        let len = self.blocks.len();
+        self.blocks.retain(|_, (_, received_epoch)| {
+            current_epoch.saturating_sub(*received_epoch) <= max_age
+        });
+
+        len - self.blocks.len()
+    }
+
+    /// Number of blocks in the pool
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Whether the pool has no blocks
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_data_structures::chain::{
+        BlockHeader, BlockTransactions, CheckpointBeacon, KeyedSignature,
+    };
+
+    fn block(checkpoint: u32, hash_prev_block: Hash) -> Block {
+        Block {
+            block_header: BlockHeader {
+                beacon: CheckpointBeacon {
+                    checkpoint,
+                    hash_prev_block,
+                },
+                ..BlockHeader::default()
+            },
+            block_sig: KeyedSignature::default(),
+            txns: BlockTransactions::default(),
+        }
+    }
+
+    #[test]
+    fn children_are_taken_once_their_parent_arrives() {
+        let parent = block(1, Hash::default());
+        let child = block(2, parent.hash());
+        let grandchild = block(3, child.hash());
+        let sibling = block(2, Hash::SHA256([1; 32]));
+        let mut pool = OrphanPool::default();
+
+        assert!(pool.insert(grandchild.clone(), 10, 4));
+        assert!(pool.insert(child.clone(), 10, 4));
+        assert!(pool.insert(sibling.clone(), 10, 4));
+        assert_eq!(pool.len(), 3);
+
+        assert_eq!(pool.take_children(parent.hash()), vec![child.clone()]);
+        assert_eq!(pool.take_children(child.hash()), vec![grandchild]);
+        assert_eq!(pool.take_children(child.hash()), vec![]);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn pool_is_bounded() {
+        let mut pool = OrphanPool::default();
+
+        assert!(pool.insert(block(2, Hash::default()), 10, 2));
+        assert!(pool.insert(block(3, Hash::default()), 10, 2));
+        assert!(!pool.insert(block(4, Hash::default()), 10, 2));
+        // A block already in the pool is not rejected
+        assert!(pool.insert(block(3, Hash::default()), 10, 2));
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn old_orphans_expire() {
+        let mut pool = OrphanPool::default();
+        pool.insert(block(2, Hash::default()), 10, 4);
+        pool.insert(block(3, Hash::default()), 12, 4);
+
+        assert_eq!(pool.expire(13, 3), 0);
+        assert_eq!(pool.expire(14, 3), 1);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.expire(16, 3), 1);
+        assert!(pool.is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::signature_mngr;
 use failure::Fail;
 use futures::Future;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use witnet_data_structures::{
     chain::{
@@ -22,6 +23,108 @@ pub struct NoMoney {
     total_balance: u64,
 }
 
+/// Error when the fee cannot be subtracted from the outputs of a transaction
+#[derive(Copy, Clone, Debug, Fail, Eq, PartialEq)]
+pub enum SubtractFeeError {
+    /// An output cannot pay its share of the fee
+    #[fail(
+        display = "Cannot subtract a fee of {} from the outputs of the transaction: output {} with value {} cannot pay its share of {}",
+        transaction_fee, output_index, output_value, share
+    )]
+    FeeExceedsOutputs {
+        /// Fee to subtract
+        transaction_fee: u64,
+        /// Index of the output
+        output_index: usize,
+        /// Value of the output
+        output_value: u64,
+        /// Share of the fee paid by the output
+        share: u64,
+    },
+    /// The sum of the values of the outputs overflows
+    #[fail(
+        display = "Cannot subtract a fee of {} from the outputs of the transaction: the sum of their values overflows",
+        transaction_fee
+    )]
+    ValueOverflow {
+        /// Fee to subtract
+        transaction_fee: u64,
+    },
+}
+
+/// How the fee is split among the outputs of a transaction when it is subtracted from them
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeSplit {
+    /// Every output pays a share of the fee proportional to its value. The rounding remainder is
+    /// paid by the last output
+    Proportional,
+    /// The last output pays the whole fee
+    LastOutput,
+}
+
+impl Default for FeeSplit {
+    fn default() -> Self {
+        FeeSplit::Proportional
+    }
+}
+
+/// Subtract `fee` from the value of the outputs, as split by `fee_split`, so the recipients pay
+/// the fee instead of the sender. Every output must keep some value.
+pub fn subtract_fee(
+    outputs: &mut [ValueTransferOutput],
+    fee: u64,
+    fee_split: FeeSplit,
+) -> Result<(), SubtractFeeError> {
+    if fee == 0 {
+        return Ok(());
+    }
+    let overflow = SubtractFeeError::ValueOverflow {
+        transaction_fee: fee,
+    };
+    let total = outputs
+        .iter()
+        .try_fold(0u64, |total, output| total.checked_add(output.value))
+        .ok_or(overflow)?;
+    let last = outputs.len().saturating_sub(1);
+    let mut remaining_fee = fee;
+    let mut shares = Vec::with_capacity(outputs.len());
+    for (i, output) in outputs.iter().enumerate() {
+        let share = match fee_split {
+            _ if i == last => remaining_fee,
+            FeeSplit::Proportional => (u128::from(fee) * u128::from(output.value))
+                .checked_div(u128::from(total))
+                .unwrap_or_default() as u64,
+            FeeSplit::LastOutput => 0,
+        };
+        if share >= output.value {
+            return Err(SubtractFeeError::FeeExceedsOutputs {
+                transaction_fee: fee,
+                output_index: i,
+                output_value: output.value,
+                share,
+            });
+        }
+        remaining_fee = remaining_fee.checked_sub(share).ok_or(overflow)?;
+        shares.push(share);
+    }
+    if remaining_fee > 0 {
+        // There are no outputs to pay the fee
+        return Err(SubtractFeeError::FeeExceedsOutputs {
+            transaction_fee: fee,
+            output_index: 0,
+            output_value: 0,
+            share: fee,
+        });
+    }
+
+    for (output, share) in outputs.iter_mut().zip(shares) {
+        output.value -= share;
+    }
+
+    Ok(())
+}
+
 /// Select enough UTXOs to sum up to `amount`.
 ///
 /// On success, return a list of output pointers and their sum.
@@ -54,6 +157,106 @@ pub fn take_enough_utxos<S: std::hash::BuildHasher>(
     }
 }
 
+/// How the unspent outputs locked as the collateral of a commitment are selected
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CollateralSelection {
+    /// Minimum age, in epochs, of the outputs preferred as collateral
+    pub min_age: u32,
+    /// Outputs worth more than this number of times the collateral are only used if there is no
+    /// other choice. Every output can be used if set to 0
+    pub max_split_factor: u64,
+}
+
+/// Select the unspent outputs locked as the collateral of a commitment, worth at least
+/// `collateral`, without locking big outputs when smaller ones are enough. In order of
+/// preference:
+///
+/// 1. An output worth exactly the collateral, so there is no change.
+/// 2. The smallest output worth more than the collateral.
+/// 3. The biggest outputs, so as few as possible are locked, until they are worth the collateral.
+/// 4. The smallest big output, that is, worth more than `max_split_factor` times the collateral.
+///
+/// The first three are tried with the outputs at least `min_age` epochs old before trying them
+/// with all the outputs. The age of an output is given by `age`, and the outputs of unknown age
+/// are considered old.
+///
+/// Commitments do not lock any collateral yet, so this is not used when building them, and there
+/// is no configuration of the `selection` until they do.
+///
+/// On success, return a list of output pointers and their sum.
+/// On error, return the total sum of the output pointers in `own_utxos`.
+pub fn take_collateral_utxos<S, F>(
+    own_utxos: &HashSet<OutputPointer, S>,
+    all_utxos: &UnspentOutputsPool,
+    collateral: u64,
+    selection: CollateralSelection,
+    age: F,
+) -> Result<(Vec<OutputPointer>, u64), u64>
+where
+    S: std::hash::BuildHasher,
+    F: Fn(&OutputPointer) -> Option<u32>,
+{
+    // (output pointer, value, whether it is old)
+    let mut candidates: Vec<(OutputPointer, u64, bool)> = own_utxos
+        .iter()
+        .map(|op| {
+            let old = age(op).map_or(true, |age| age >= selection.min_age);
+
+            (op.clone(), all_utxos[op].value, old)
+        })
+        .collect();
+    let total_balance = candidates.iter().map(|(_, value, _)| value).sum();
+    if collateral == 0 {
+        // Commitments without collateral do not need any inputs
+        return Err(total_balance);
+    }
+    // Smallest first, sorted by output pointer to break ties
+    candidates.sort_by_key(|(op, value, _)| (*value, op.to_string()));
+
+    let is_big = |value: u64| {
+        selection.max_split_factor > 0
+            && value > collateral.saturating_mul(selection.max_split_factor)
+    };
+    let (big, small): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .partition(|(_, value, _)| is_big(*value));
+    let old_small: Vec<_> = small.iter().filter(|(_, _, old)| *old).cloned().collect();
+
+    for outputs in &[old_small, small] {
+        if let Some(selected) = take_small_collateral_utxos(outputs, collateral) {
+            return Ok(selected);
+        }
+    }
+
+    match big.iter().find(|(_, _, old)| *old).or_else(|| big.first()) {
+        Some((op, value, _)) => Ok((vec![op.clone()], *value)),
+        None => Err(total_balance),
+    }
+}
+
+/// Steps 1 to 3 of `take_collateral_utxos`, given outputs sorted by value, smallest first.
+fn take_small_collateral_utxos(
+    outputs: &[(OutputPointer, u64, bool)],
+    collateral: u64,
+) -> Option<(Vec<OutputPointer>, u64)> {
+    if let Some((op, value, _)) = outputs.iter().find(|(_, value, _)| *value >= collateral) {
+        // This is the exact output if there is one, since they are sorted by value
+        return Some((vec![op.clone()], *value));
+    }
+
+    let mut acc = 0;
+    let mut list = vec![];
+    for (op, value, _) in outputs.iter().rev() {
+        acc += value;
+        list.push(op.clone());
+        if acc >= collateral {
+            return Some((list, acc));
+        }
+    }
+
+    None
+}
+
 /// Get total balance
 pub fn get_total_balance(all_utxos: &UnspentOutputsPool, pkh: PublicKeyHash) -> u64 {
     // FIXME: this does not scale, we need to be able to get UTXOs by PKH
@@ -724,4 +927,178 @@ mod tests {
             Err(1_000_000 - 1_000)
         );
     }
+
+    fn output_index(error: SubtractFeeError) -> Option<usize> {
+        match error {
+            SubtractFeeError::FeeExceedsOutputs { output_index, .. } => Some(output_index),
+            SubtractFeeError::ValueOverflow { .. } => None,
+        }
+    }
+
+    #[test]
+    fn subtract_fee_proportional() {
+        let mut outputs = vec![pay_alice(1000), pay_bob(3000)];
+        subtract_fee(&mut outputs, 100, FeeSplit::Proportional).unwrap();
+        assert_eq!(outputs, vec![pay_alice(975), pay_bob(2925)]);
+
+        // The rounding remainder is paid by the last output
+        let mut outputs = vec![pay_alice(1000), pay_bob(1000), pay_me(1000)];
+        subtract_fee(&mut outputs, 100, FeeSplit::Proportional).unwrap();
+        assert_eq!(outputs, vec![pay_alice(967), pay_bob(967), pay_me(966)]);
+
+        // Outputs cannot be left without value
+        let mut outputs = vec![pay_alice(1000), pay_bob(10)];
+        assert_eq!(
+            subtract_fee(&mut outputs, 1010, FeeSplit::Proportional).map_err(output_index),
+            Err(Some(0))
+        );
+        assert_eq!(outputs, vec![pay_alice(1000), pay_bob(10)]);
+    }
+
+    #[test]
+    fn subtract_fee_last_output() {
+        let mut outputs = vec![pay_alice(1000), pay_bob(3000)];
+        subtract_fee(&mut outputs, 100, FeeSplit::LastOutput).unwrap();
+        assert_eq!(outputs, vec![pay_alice(1000), pay_bob(2900)]);
+
+        let mut outputs = vec![pay_alice(1000), pay_bob(100)];
+        assert_eq!(
+            subtract_fee(&mut outputs, 100, FeeSplit::LastOutput).map_err(output_index),
+            Err(Some(1))
+        );
+
+        // Without outputs there is nothing to subtract the fee from
+        assert!(subtract_fee(&mut [], 100, FeeSplit::LastOutput).is_err());
+        assert!(subtract_fee(&mut [], 0, FeeSplit::Proportional).is_ok());
+    }
+
+    #[test]
+    fn subtract_fee_value_overflow() {
+        let mut outputs = vec![pay_alice(u64::max_value()), pay_bob(1)];
+        assert_eq!(
+            subtract_fee(&mut outputs, 100, FeeSplit::Proportional),
+            Err(SubtractFeeError::ValueOverflow {
+                transaction_fee: 100
+            })
+        );
+        assert_eq!(outputs, vec![pay_alice(u64::max_value()), pay_bob(1)]);
+
+        // The sum is checked even if the fee is only paid by the last output
+        assert!(subtract_fee(&mut outputs, 100, FeeSplit::LastOutput).is_err());
+    }
+
+    /// Output pointer of the output of `all_utxos` with the given value
+    fn utxo_worth(all_utxos: &UnspentOutputsPool, value: u64) -> OutputPointer {
+        all_utxos
+            .iter()
+            .find(|(_, output)| output.value == value)
+            .map(|(op, _)| op.clone())
+            .unwrap()
+    }
+
+    fn collateral_values(
+        own_utxos: &HashSet<OutputPointer>,
+        all_utxos: &UnspentOutputsPool,
+        collateral: u64,
+        selection: CollateralSelection,
+        young: &[u64],
+    ) -> Result<(Vec<u64>, u64), u64> {
+        let young: HashSet<OutputPointer> = young
+            .iter()
+            .map(|value| utxo_worth(all_utxos, *value))
+            .collect();
+        take_collateral_utxos(own_utxos, all_utxos, collateral, selection, |op| {
+            Some(if young.contains(op) { 0 } else { 100 })
+        })
+        .map(|(ops, sum)| (ops.iter().map(|op| all_utxos[op].value).collect(), sum))
+    }
+
+    #[test]
+    fn collateral_exact_utxo() {
+        let outputs = vec![
+            pay_me(1000),
+            pay_me(300),
+            pay_me(100),
+            pay_me(50),
+            pay_bob(40),
+        ];
+        let (own_utxos, all_utxos) = build_utxo_set(outputs, None, vec![]);
+        let selection = CollateralSelection {
+            min_age: 10,
+            max_split_factor: 10,
+        };
+
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 100, selection, &[]),
+            Ok((vec![100], 100))
+        );
+        // Otherwise the smallest output worth more than the collateral
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 200, selection, &[]),
+            Ok((vec![300], 300))
+        );
+        // Not enough money
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 2000, selection, &[]),
+            Err(1450)
+        );
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 0, selection, &[]),
+            Err(1450)
+        );
+    }
+
+    #[test]
+    fn collateral_does_not_split_big_utxos() {
+        let outputs = vec![pay_me(10_000), pay_me(60), pay_me(50), pay_me(5)];
+        let (own_utxos, all_utxos) = build_utxo_set(outputs, None, vec![]);
+        let selection = CollateralSelection {
+            min_age: 10,
+            max_split_factor: 10,
+        };
+
+        // The biggest small outputs are combined instead
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 100, selection, &[]),
+            Ok((vec![60, 50], 110))
+        );
+        // Unless they are not enough
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 500, selection, &[]),
+            Ok((vec![10_000], 10_000))
+        );
+        // Every output can be used without a split factor
+        let selection = CollateralSelection {
+            max_split_factor: 0,
+            ..selection
+        };
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 100, selection, &[]),
+            Ok((vec![10_000], 10_000))
+        );
+    }
+
+    #[test]
+    fn collateral_prefers_old_utxos() {
+        let outputs = vec![pay_me(100), pay_me(150), pay_me(40)];
+        let (own_utxos, all_utxos) = build_utxo_set(outputs, None, vec![]);
+        let selection = CollateralSelection {
+            min_age: 10,
+            max_split_factor: 10,
+        };
+
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 100, selection, &[100]),
+            Ok((vec![150], 150))
+        );
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 100, selection, &[100, 150]),
+            Ok((vec![100], 100))
+        );
+        // Young outputs are used if the old ones are not enough
+        assert_eq!(
+            collateral_values(&own_utxos, &all_utxos, 200, selection, &[100]),
+            Ok((vec![150, 100], 250))
+        );
+    }
 }
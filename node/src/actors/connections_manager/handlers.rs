@@ -16,7 +16,11 @@ impl Handler<InboundTcpConnect> for ConnectionsManager {
     /// Method to handle the InboundTcpConnect message
     fn handle(&mut self, msg: InboundTcpConnect, _ctx: &mut Self::Context) {
         // Request the creation of a new session actor from connection
-        ConnectionsManager::request_session_creation(msg.stream, SessionType::Inbound);
+        ConnectionsManager::request_session_creation(
+            msg.stream,
+            SessionType::Inbound,
+            msg.listener,
+        );
     }
 }
 
@@ -27,11 +31,15 @@ impl Handler<OutboundTcpConnect> for ConnectionsManager {
 
     /// Method to handle the OutboundTcpConnect message
     fn handle(&mut self, msg: OutboundTcpConnect, ctx: &mut Self::Context) {
+        let address = msg.address;
+
         // Get resolver from registry and send a ConnectAddr message to it
         Resolver::from_registry()
             .send(ConnectAddr(msg.address))
             .into_actor(self)
-            .then(|res, _act, _ctx| ConnectionsManager::process_connect_addr_response(res))
+            .then(move |res, _act, _ctx| {
+                ConnectionsManager::process_connect_addr_response(res, address)
+            })
             .wait(ctx);
     }
 }
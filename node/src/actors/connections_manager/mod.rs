@@ -1,10 +1,13 @@
+use std::net::SocketAddr;
+
 use actix::prelude::*;
 use futures::Stream;
 use log;
 use tokio::net::{TcpListener, TcpStream};
 
 use crate::actors::{
-    messages::{Create, InboundTcpConnect, ResolverResult},
+    messages::{Create, InboundTcpConnect, OutboundConnectionFailed, ResolverResult},
+    peers_manager::PeersManager,
     sessions_manager::SessionsManager,
 };
 
@@ -33,21 +36,23 @@ impl ConnectionsManager {
             .and_then(|config, _, ctx| {
                 // Bind TCP listener to this address
                 // FIXME(#72): decide what to do with actor when server cannot be started
-                let listener = TcpListener::bind(&config.connections.server_addr).unwrap();
-
-                ctx.add_message_stream(
-                    listener
-                        .incoming()
-                        .map_err(|err| {
-                            log::error!("Error incoming listener: {}", err);
-                        })
-                        .map(InboundTcpConnect::new),
-                );
-
-                log::info!(
-                    "P2P server has been started at {:?}",
-                    &config.connections.server_addr
-                );
+                let server_addr = config.connections.server_addr;
+                let listener = TcpListener::bind(&server_addr).unwrap();
+                Self::listen(ctx, listener, server_addr);
+
+                log::info!("P2P server has been started at {:?}", &server_addr);
+
+                // Bind the additional listeners, which are not required to start the server
+                for listener_config in &config.connections.listeners {
+                    let addr = listener_config.addr;
+                    match TcpListener::bind(&addr) {
+                        Ok(listener) => {
+                            Self::listen(ctx, listener, addr);
+                            log::info!("P2P server is also listening at {:?}", &addr);
+                        }
+                        Err(err) => log::error!("P2P server failed to listen at {}: {}", addr, err),
+                    }
+                }
 
                 fut::ok(())
             })
@@ -55,8 +60,24 @@ impl ConnectionsManager {
             .wait(ctx);
     }
 
+    /// Method to accept the inbound connections of a TCP listener bound to the given address
+    fn listen(ctx: &mut <Self as Actor>::Context, listener: TcpListener, addr: SocketAddr) {
+        ctx.add_message_stream(
+            listener
+                .incoming()
+                .map_err(|err| {
+                    log::error!("Error incoming listener: {}", err);
+                })
+                .map(move |stream| InboundTcpConnect::from_listener(stream, addr)),
+        );
+    }
+
     /// Method to request the creation of a session actor from a TCP stream
-    fn request_session_creation(stream: TcpStream, session_type: SessionType) {
+    fn request_session_creation(
+        stream: TcpStream,
+        session_type: SessionType,
+        listener: Option<SocketAddr>,
+    ) {
         // Get sessions manager address
         let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
 
@@ -64,12 +85,15 @@ impl ConnectionsManager {
         sessions_manager_addr.do_send(Create {
             stream,
             session_type,
+            listener,
         });
     }
 
-    /// Method to process resolver ConnectAddr response
+    /// Method to process resolver ConnectAddr response. Failed connection attempts are reported
+    /// to the peers manager, which backs off from the address
     fn process_connect_addr_response(
         response: Result<ResolverResult, MailboxError>,
+        address: SocketAddr,
     ) -> actix::fut::FutureResult<(), (), Self> {
         // Process the Result<ResolverResult, MailboxError>
         match response {
@@ -81,7 +105,16 @@ impl ConnectionsManager {
                 // Process the ResolverResult
                 match res {
                     Err(error) => {
-                        log::warn!("Failed to connect to a peer with error: {:?}", error);
+                        log::warn!(
+                            "Failed to connect to peer {} with error: {:?}",
+                            address,
+                            error
+                        );
+                        System::current()
+                            .registry()
+                            .get::<PeersManager>()
+                            .do_send(OutboundConnectionFailed { address });
+
                         actix::fut::err(())
                     }
                     Ok(stream) => {
@@ -93,7 +126,11 @@ impl ConnectionsManager {
                             });
 
                         // Request the creation of a new session actor from connection
-                        ConnectionsManager::request_session_creation(stream, SessionType::Outbound);
+                        ConnectionsManager::request_session_creation(
+                            stream,
+                            SessionType::Outbound,
+                            None,
+                        );
 
                         actix::fut::ok(())
                     }
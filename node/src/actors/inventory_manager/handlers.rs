@@ -3,7 +3,10 @@ use actix::{ActorFuture, Context, Handler, ResponseActFuture, WrapFuture};
 use log;
 
 use super::{InventoryManager, InventoryManagerError};
-use crate::actors::messages::{AddItem, GetItem};
+use crate::actors::{
+    mailbox,
+    messages::{AddItem, GetItem, SendInventoryItem, ServeItem},
+};
 use crate::storage_mngr;
 use witnet_data_structures::chain::{Hash, Hashable, InventoryItem};
 
@@ -62,3 +65,34 @@ impl Handler<GetItem> for InventoryManager {
         Box::new(fut)
     }
 }
+
+/// Handler for ServeItem message
+impl Handler<ServeItem> for InventoryManager {
+    type Result = ();
+
+    fn handle(&mut self, ServeItem { hash, peer }: ServeItem, ctx: &mut Context<Self>) {
+        mailbox::INVENTORY_REQUESTS.processed();
+        let key = match hash {
+            Hash::SHA256(x) => x.to_vec(),
+        };
+
+        storage_mngr::get::<_, InventoryItem>(&key)
+            .into_actor(self)
+            .map_err(|e, _, _| log::error!("Couldn't get item from storage: {}", e))
+            .and_then(move |opt, _, _| {
+                match opt {
+                    None => {
+                        log::warn!("Inventory item {} requested by a peer does not exist", hash)
+                    }
+                    Some(item) => {
+                        if peer.do_send(SendInventoryItem { item }).is_err() {
+                            log::debug!("The session requesting inventory item {} is closed", hash);
+                        }
+                    }
+                }
+
+                fut::ok(())
+            })
+            .spawn(ctx);
+    }
+}
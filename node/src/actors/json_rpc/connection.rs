@@ -14,7 +14,17 @@ use super::{
     server::{JsonRpcServer, Unregister},
 };
 use jsonrpc_pubsub::{PubSubHandler, Session};
+use serde_json::Value;
 use std::sync::Arc;
+use witnet_util::correlation::{self, Correlated, CorrelationId};
+
+/// Maximum number of requests of a single connection handled at the same time. When it is reached,
+/// the next requests of the connection are not read until the last one is handled
+pub const MAX_IN_FLIGHT_REQUESTS: usize = 16;
+
+/// Member of the params of a request used by the clients to send the correlation id of the request
+/// that originated it
+pub const CORRELATION_ID_PARAM: &str = "correlationId";
 
 /// A single JSON-RPC connection
 pub struct JsonRpc {
@@ -27,6 +37,8 @@ pub struct JsonRpc {
     pub jsonrpc_io: Rc<PubSubHandler<Arc<Session>>>,
     /// Sender
     pub session: Arc<Session>,
+    /// Number of requests of this connection being handled
+    pub in_flight: usize,
 }
 
 impl Actor for JsonRpc {
@@ -73,18 +85,38 @@ impl StreamHandler<BytesMut, io::Error> for JsonRpc {
 
         let session = Arc::clone(&self.session);
 
-        // Handle response asynchronously
-        self.jsonrpc_io
-            .handle_request(&msg, session)
+        // Every request gets its own correlation id, which is passed along to the actors that
+        // take part in handling it. Clients such as the wallet can send theirs, so the logs of
+        // both processes can be related
+        let (msg, correlation_id) = take_correlation_id(msg);
+        let correlation_id = correlation_id.unwrap_or_else(CorrelationId::new);
+        let _entered = correlation::enter(correlation_id);
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("rpc", correlation_id = %correlation_id);
+        #[cfg(feature = "tracing")]
+        let _span_entered = span.enter();
+        debug!("Handling JSON-RPC request");
+
+        // Handle response asynchronously. The next requests of the connection are handled without
+        // waiting for this one, so their responses can be sent in a different order. The future
+        // keeps the correlation id, so the messages sent in its continuations are traced too
+        self.in_flight += 1;
+        let fut = Correlated::new(self.jsonrpc_io.handle_request(&msg, session))
             .into_actor(self)
             .then(|res, act, _ctx| {
+                act.in_flight -= 1;
                 if let Ok(Some(response)) = res {
                     act.framed.write(BytesMut::from(response));
                 }
 
                 actix::fut::ok(())
-            })
-            .wait(ctx);
+            });
+        if self.in_flight < MAX_IN_FLIGHT_REQUESTS {
+            fut.spawn(ctx);
+        } else {
+            // Stop reading the requests of this connection until this one is handled
+            fut.wait(ctx);
+        }
     }
 }
 
@@ -93,3 +125,71 @@ impl StreamHandler<String, ()> for JsonRpc {
         self.framed.write(BytesMut::from(item));
     }
 }
+
+/// Remove the correlation id sent by the client from the params of the request, so the methods do
+/// not receive it. In a batch, the id of the first request that has one is used.
+fn take_correlation_id(msg: String) -> (String, Option<CorrelationId>) {
+    // Avoid parsing the requests that do not have one
+    if !msg.contains(CORRELATION_ID_PARAM) {
+        return (msg, None);
+    }
+    let mut request: Value = match serde_json::from_str(&msg) {
+        Ok(request) => request,
+        Err(_) => return (msg, None),
+    };
+
+    let mut correlation_id = None;
+    let calls: Vec<&mut Value> = match &mut request {
+        Value::Array(calls) => calls.iter_mut().collect(),
+        call => vec![call],
+    };
+    for call in calls {
+        let id = call
+            .get_mut("params")
+            .and_then(Value::as_object_mut)
+            .and_then(|params| params.remove(CORRELATION_ID_PARAM));
+        if correlation_id.is_none() {
+            correlation_id = id
+                .as_ref()
+                .and_then(Value::as_str)
+                .and_then(|id| id.parse().ok());
+        }
+    }
+
+    (request.to_string(), correlation_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correlation_id_is_taken_from_the_params() {
+        let id = CorrelationId::new();
+        let msg = format!(
+            r#"{{"jsonrpc":"2.0","method":"getBlockChain","params":{{"epoch":0,"correlationId":"{}"}},"id":1}}"#,
+            id
+        );
+
+        let (msg, correlation_id) = take_correlation_id(msg);
+
+        assert_eq!(correlation_id, Some(id));
+        assert_eq!(
+            serde_json::from_str::<Value>(&msg).unwrap(),
+            serde_json::json!({"jsonrpc": "2.0", "method": "getBlockChain", "params": {"epoch": 0}, "id": 1})
+        );
+    }
+
+    #[test]
+    fn requests_without_correlation_id_are_not_modified() {
+        let msg = r#"{"jsonrpc":"2.0","method":"getBlock","params":["correlationId"],"id":1}"#;
+
+        let (taken, correlation_id) = take_correlation_id(msg.to_string());
+
+        assert_eq!(correlation_id, None);
+        assert_eq!(
+            serde_json::from_str::<Value>(&taken).unwrap(),
+            serde_json::from_str::<Value>(msg).unwrap()
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    convert::TryFrom,
     sync::atomic::{AtomicUsize, Ordering},
     sync::Arc,
 };
@@ -13,30 +14,43 @@ use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
 
 use witnet_data_structures::{
-    chain::{self, Block, CheckpointBeacon, Hash},
-    transaction::Transaction,
+    chain::{self, Block, CheckpointBeacon, DataRequestInfo, Epoch, Hash, Hashable, RADConsensus},
+    transaction::{Transaction, DR_WEIGHT_PER_BYTE, VTT_WEIGHT_PER_BYTE},
+    types::NetworkId,
     vrf::VrfMessage,
 };
+use witnet_rad::{report::build_tally_report, types::canonical_json::JsonFormat};
 
 use crate::actors::{
     chain_manager::{ChainManager, ChainManagerError},
     epoch_manager::EpochManager,
     inventory_manager::InventoryManager,
+    mailbox,
     messages::{
-        AddCandidates, AddTransaction, BuildDrt, BuildVtt, GetBlocksEpochRange, GetEpoch, GetItem,
-        GetState, NumSessions,
+        AddCandidates, AddTransaction, BuildBlockTemplate, BuildDrt, BuildVtt,
+        EstimateDataRequestCost, GetArsMerkleProof, GetBlocksEpochRange, GetConnectedPeersStats,
+        GetDataSourceStats, GetEpoch, GetEpochSummary, GetItem, GetKnownPeersStats,
+        GetProtocolUpgrades, GetReputationForecast, GetState, GetSuperblock, NumSessions,
+        NumSessionsResult, Traced,
     },
+    peers_manager::PeersManager,
+    rad_manager::RadManager,
     sessions_manager::SessionsManager,
 };
-use crate::signature_mngr;
+use crate::{config_mngr, signature_mngr};
 
 //use std::str::FromStr;
-use super::Subscriptions;
+use super::{lanes::HeavyQueries, Subscriptions};
 
 #[cfg(test)]
 use self::mock_actix::System;
 use crate::actors::chain_manager::StateMachine;
-use crate::actors::messages::{GetBalance, GetDataRequestReport, GetHighestCheckpointBeacon};
+#[cfg(feature = "simulation")]
+use crate::actors::chain_manager::{consensus, ConsensusThresholds};
+use crate::actors::messages::{
+    GetBalance, GetDataRequestReport, GetDataRequestState, GetHighestCheckpointBeacon, GetMempool,
+    GetTransactionStatus, GetUtxoInfo, GetWatchedPkhs, UnwatchPkh, WatchPkh,
+};
 use futures::future;
 use witnet_data_structures::chain::PublicKeyHash;
 
@@ -45,18 +59,36 @@ type JsonRpcResultAsync = Box<dyn Future<Item = Value, Error = jsonrpc_core::Err
 
 /// Define the JSON-RPC interface:
 /// All the methods available through JSON-RPC
-pub fn jsonrpc_io_handler(subscriptions: Subscriptions) -> PubSubHandler<Arc<Session>> {
+///
+/// When running `offline` the networking actors are not started, so the methods that depend on
+/// them fail instead of starting them.
+///
+/// The methods that read a range of blocks are handled in the lane of the `heavy_queries`, so they
+/// do not delay the rest of the methods.
+pub fn jsonrpc_io_handler(
+    subscriptions: Subscriptions,
+    offline: bool,
+    heavy_queries: HeavyQueries,
+) -> PubSubHandler<Arc<Session>> {
     let mut io = PubSubHandler::new(MetaIoHandler::default());
 
     io.add_method("inventory", |params: Params| inventory(params.parse()?));
-    io.add_method("getBlockChain", |params: Params| {
-        get_block_chain(params.parse())
+    let heavy = heavy_queries.clone();
+    io.add_method("getBlockChain", move |params: Params| {
+        heavy.run(move || get_block_chain(params.parse()))
+    });
+    let heavy = heavy_queries.clone();
+    io.add_method("getBlockChainChunk", move |params: Params| {
+        heavy.run(move || get_block_chain_chunk(params.parse()))
     });
     io.add_method("getBlock", |params: Params| get_block(params.parse()));
+    io.add_method("getBlockByEpoch", |params: Params| {
+        get_block_by_epoch(params.parse())
+    });
     //io.add_method("getOutput", |params: Params| get_output(params.parse()));
     io.add_method("sendRequest", |params: Params| send_request(params.parse()));
     io.add_method("sendValue", |params: Params| send_value(params.parse()));
-    io.add_method("status", |_params: Params| status());
+    io.add_method("status", move |_params: Params| status(offline));
     io.add_method("getPublicKey", |_params: Params| get_public_key());
     io.add_method("getPkh", |_params: Params| get_pkh());
     io.add_method("sign", |params: Params| sign_data(params.parse()));
@@ -64,7 +96,89 @@ pub fn jsonrpc_io_handler(subscriptions: Subscriptions) -> PubSubHandler<Arc<Ses
     io.add_method("dataRequestReport", |params: Params| {
         data_request_report(params.parse())
     });
+    io.add_method("getDataRequestState", |params: Params| {
+        get_data_request_state(params.parse())
+    });
     io.add_method("getBalance", |params: Params| get_balance(params.parse()));
+    io.add_method("getUtxoInfo", |params: Params| {
+        get_utxo_info(params.parse())
+    });
+    io.add_method("watchPkh", |params: Params| watch_pkh(params.parse()));
+    io.add_method("unwatchPkh", |params: Params| unwatch_pkh(params.parse()));
+    io.add_method("getWatchedPkhs", |_params: Params| get_watched_pkhs());
+    io.add_method("knownPeersStats", move |_params: Params| {
+        if offline {
+            offline_error()
+        } else {
+            known_peers_stats()
+        }
+    });
+    io.add_method("connectedPeersStats", move |_params: Params| {
+        if offline {
+            offline_error()
+        } else {
+            connected_peers_stats()
+        }
+    });
+    io.add_method("getDataSourceStats", |_params: Params| {
+        get_data_source_stats()
+    });
+    io.add_method("getMailboxStats", |_params: Params| get_mailbox_stats());
+    io.add_method("getSigningLog", |params: Params| match params {
+        // All the filters are optional, so the params can be omitted
+        Params::None => get_signing_log(Ok(GetSigningLogParams::default())),
+        params => get_signing_log(params.parse()),
+    });
+    io.add_method("getMempool", |params: Params| match params {
+        // All the filters are optional, so the params can be omitted
+        Params::None => get_mempool(Ok(GetMempool::default())),
+        params => get_mempool(params.parse()),
+    });
+    io.add_method("buildBlockTemplate", |params: Params| match params {
+        // The transactions are optional, so the params can be omitted
+        Params::None => build_block_template(Ok(BuildBlockTemplate::default())),
+        params => build_block_template(params.parse().map(|(tx_hashes,)| BuildBlockTemplate {
+            tx_hashes: Some(tx_hashes),
+        })),
+    });
+    io.add_method("getTransactionStatus", |params: Params| {
+        get_transaction_status(params.parse())
+    });
+    io.add_method("estimateDataRequestCost", |params: Params| {
+        estimate_data_request_cost(params.parse())
+    });
+    io.add_method("epochSummary", |_params: Params| epoch_summary());
+    io.add_method("getProtocolUpgrades", |_params: Params| {
+        get_protocol_upgrades()
+    });
+    io.add_method("getReputationForecast", |_params: Params| {
+        get_reputation_forecast()
+    });
+    io.add_method("getSuperblock", |params: Params| {
+        get_superblock(params.parse())
+    });
+    io.add_method("getArsMerkleProof", |params: Params| {
+        get_ars_merkle_proof(params.parse())
+    });
+    io.add_method("getConsensusConstants", |_params: Params| {
+        get_consensus_constants()
+    });
+    io.add_method("getNetwork", |_params: Params| get_network());
+    io.add_method("getWeightLimits", |_params: Params| get_weight_limits());
+    io.add_method("getTransactionWeight", |params: Params| {
+        get_transaction_weight(params.parse())
+    });
+    io.add_method("getMiners", move |params: Params| {
+        heavy_queries.run(move || match params {
+            // The range is optional, so the params can be omitted
+            Params::None => get_miners(Ok(GetMinersParams::default())),
+            params => get_miners(params.parse()),
+        })
+    });
+    #[cfg(feature = "simulation")]
+    io.add_method("simulatePeersBeacons", |params: Params| {
+        simulate_peers_beacons(params.parse())
+    });
 
     // We need two Arcs, one for subscribe and one for unsuscribe
     let ss = subscriptions.clone();
@@ -101,7 +215,7 @@ pub fn jsonrpc_io_handler(subscriptions: Subscriptions) -> PubSubHandler<Arc<Ses
                 // Get params, or set to Value::Null if the "params" key does not exist
                 let method_params = params_vec.get(1).cloned().unwrap_or_default();
 
-                let add_subscription = |method_name, subscriber: Subscriber| {
+                let add_subscription = |method_name, subscriber: Subscriber, method_params| {
                     if let Ok(mut s) = ss.lock() {
                         let id = SubscriptionId::String(
                             atomic_counter.fetch_add(1, Ordering::SeqCst).to_string(),
@@ -123,7 +237,35 @@ pub fn jsonrpc_io_handler(subscriptions: Subscriptions) -> PubSubHandler<Arc<Ses
                 match method_name.as_str() {
                     "newBlocks" => {
                         debug!("New subscription to newBlocks");
-                        add_subscription("newBlocks", subscriber);
+                        add_subscription("newBlocks", subscriber, method_params);
+                    }
+                    "peerEvents" => {
+                        debug!("New subscription to peerEvents");
+                        add_subscription("peerEvents", subscriber, method_params);
+                    }
+                    "droppedTransactions" => {
+                        debug!("New subscription to droppedTransactions");
+                        add_subscription("droppedTransactions", subscriber, method_params);
+                    }
+                    "watchDataRequest" => {
+                        // Store the hash as a string, so the notifications can be matched
+                        // against it
+                        match serde_json::from_value::<(Hash,)>(method_params) {
+                            Ok((dr_pointer,)) => {
+                                debug!("New subscription to watchDataRequest {}", dr_pointer);
+                                add_subscription(
+                                    "watchDataRequest",
+                                    subscriber,
+                                    Value::String(dr_pointer.to_string()),
+                                );
+                            }
+                            Err(e) => {
+                                // Ignore errors, as they mean that the connection was closed
+                                subscriber
+                                    .reject(jsonrpc_core::Error::invalid_params(e.to_string()))
+                                    .ok();
+                            }
+                        }
                     }
                     e => {
                         debug!("Unknown subscription method: {}", e);
@@ -189,6 +331,13 @@ fn internal_error_s<T: std::fmt::Display>(e: T) -> jsonrpc_core::Error {
     }
 }
 
+/// Response of the methods that are not available when running in offline mode
+fn offline_error() -> JsonRpcResultAsync {
+    Box::new(futures::failed(internal_error_s(
+        "The node is running in offline mode",
+    )))
+}
+
 /// Inventory element: block, transaction, etc
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 // TODO Remove Clippy allow
@@ -222,9 +371,10 @@ pub fn inventory(inv_elem: InventoryItem) -> JsonRpcResult {
             let chain_manager_addr = System::current().registry().get::<ChainManager>();
             // If this function was called asynchronously, it could wait for the result
             // But it's not so we just assume success
-            chain_manager_addr.do_send(AddCandidates {
+            mailbox::CANDIDATES.queued();
+            chain_manager_addr.do_send(Traced::new(AddCandidates {
                 blocks: vec![block],
-            });
+            }));
 
             // Returns a boolean indicating success
             Ok(Value::Bool(true))
@@ -237,7 +387,7 @@ pub fn inventory(inv_elem: InventoryItem) -> JsonRpcResult {
             let chain_manager_addr = System::current().registry().get::<ChainManager>();
             // If this function was called asynchronously, it could wait for the result
             // But it's not so we just assume success
-            chain_manager_addr.do_send(AddTransaction { transaction });
+            chain_manager_addr.do_send(Traced::new(AddTransaction { transaction }));
 
             // Returns a boolean indicating success
             Ok(Value::Bool(true))
@@ -275,50 +425,51 @@ pub struct GetBlockChainParams {
 pub fn get_block_chain(
     params: Result<Option<GetBlockChainParams>, jsonrpc_core::Error>,
 ) -> JsonRpcResultAsync {
-    // Helper function to convert the result of GetBlockEpochRange to a JSON value, or a JSON-RPC error
+    let GetBlockChainParams { epoch, limit } = match params {
+        Ok(x) => x.unwrap_or_default(),
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let fut = get_block_hashes(epoch, limit as usize).and_then(|vec_inv_entry| {
+        let epoch_and_hash: Vec<_> = vec_inv_entry
+            .into_iter()
+            .map(|(epoch, hash)| {
+                let hash_string = format!("{}", hash);
+                (epoch, hash_string)
+            })
+            .collect();
+
+        serde_json::to_value(epoch_and_hash).map_err(internal_error)
+    });
+
+    Box::new(fut)
+}
+
+/// Get the `(epoch, block_hash)` pairs of the blocks consolidated from `epoch`, up to `limit`
+/// blocks. A negative epoch is interpreted as "the last N epochs".
+fn get_block_hashes(
+    epoch: i64,
+    limit: usize,
+) -> Box<dyn Future<Item = Vec<(Epoch, Hash)>, Error = jsonrpc_core::Error> + Send> {
+    // Helper function to convert the result of GetBlockEpochRange to a JSON-RPC error
     fn process_get_block_chain(
         res: Result<Result<Vec<(u32, Hash)>, ChainManagerError>, MailboxError>,
-    ) -> impl Future<Item = Value, Error = jsonrpc_core::Error> {
+    ) -> Result<Vec<(u32, Hash)>, jsonrpc_core::Error> {
         match res {
-            Ok(Ok(vec_inv_entry)) => {
-                let epoch_and_hash: Vec<_> = vec_inv_entry
-                    .into_iter()
-                    .map(|(epoch, hash)| {
-                        let hash_string = format!("{}", hash);
-                        (epoch, hash_string)
-                    })
-                    .collect();
-                let value = match serde_json::to_value(epoch_and_hash) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        let err = internal_error(e);
-                        return futures::failed(err);
-                    }
-                };
-                futures::finished(value)
-            }
-            Ok(Err(e)) => {
-                let err = internal_error(e);
-                futures::failed(err)
-            }
-            Err(e) => {
-                let err = internal_error(e);
-                futures::failed(err)
-            }
+            Ok(Ok(vec_inv_entry)) => Ok(vec_inv_entry),
+            Ok(Err(e)) => Err(internal_error(e)),
+            Err(e) => Err(internal_error(e)),
         }
     }
 
-    let GetBlockChainParams { epoch, limit } = match params {
-        Ok(x) => x.unwrap_or_default(),
-        Err(e) => return Box::new(futures::failed(e)),
-    };
-
-    let limit = limit as usize;
     let chain_manager_addr = ChainManager::from_registry();
     if epoch >= 0 {
         let epoch = epoch as u32;
         let fut = chain_manager_addr
-            .send(GetBlocksEpochRange::new_with_limit(epoch.., limit))
+            .send(Traced::new(GetBlocksEpochRange::new_with_limit(
+                epoch..,
+                limit,
+            )))
             .then(process_get_block_chain);
         Box::new(fut)
     } else {
@@ -343,13 +494,99 @@ pub fn get_block_chain(
             })
             .and_then(move |epoch| {
                 chain_manager_addr
-                    .send(GetBlocksEpochRange::new_with_limit(epoch.., limit))
+                    .send(Traced::new(GetBlocksEpochRange::new_with_limit(
+                        epoch..,
+                        limit,
+                    )))
                     .then(process_get_block_chain)
             });
         Box::new(fut)
     }
 }
 
+/// Maximum number of blocks returned by `getBlockChainChunk` in a single response
+pub const MAX_BLOCK_CHAIN_CHUNK: u32 = 1_000;
+
+/// Params of getBlockChainChunk method
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct GetBlockChainChunkParams {
+    /// First epoch of the range. A negative epoch is interpreted as "the last N epochs". Ignored
+    /// if `continuation` is set
+    #[serde(default)]
+    pub epoch: i64,
+    /// Maximum number of blocks in the chunk, at most 1000. Defaults to the maximum if 0
+    #[serde(default)]
+    pub chunk_size: u32,
+    /// Token returned with the previous chunk, to get the next one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuation: Option<String>,
+}
+
+/// A chunk of the `(epoch, block_hash)` pairs of the chain, as returned by `getBlockChainChunk`
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BlockChainChunk {
+    /// Blocks of the chunk, by epoch
+    pub blocks: Vec<(Epoch, String)>,
+    /// Token to get the next chunk, `null` if this is the last one
+    pub continuation: Option<String>,
+}
+
+/// Get the list of the known block hashes in chunks of bounded size, so big ranges can be
+/// retrieved one chunk at a time following the continuation tokens.
+/* test
+{"jsonrpc": "2.0","method": "getBlockChainChunk", "id": 1, "params": {"chunk_size": 2}}
+*/
+pub fn get_block_chain_chunk(
+    params: Result<Option<GetBlockChainChunkParams>, jsonrpc_core::Error>,
+) -> JsonRpcResultAsync {
+    let GetBlockChainChunkParams {
+        epoch,
+        chunk_size,
+        continuation,
+    } = match params {
+        Ok(x) => x.unwrap_or_default(),
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+    let epoch = match continuation {
+        Some(token) => match token.parse::<Epoch>() {
+            Ok(epoch) => i64::from(epoch),
+            Err(_) => {
+                return Box::new(futures::failed(jsonrpc_core::Error::invalid_params(
+                    "Invalid continuation token",
+                )))
+            }
+        },
+        None => epoch,
+    };
+    let chunk_size = if chunk_size == 0 {
+        MAX_BLOCK_CHAIN_CHUNK
+    } else {
+        std::cmp::min(chunk_size, MAX_BLOCK_CHAIN_CHUNK)
+    } as usize;
+
+    // One more block is requested to know whether there is a next chunk, and where it starts
+    let fut = get_block_hashes(epoch, chunk_size + 1).and_then(move |mut hashes| {
+        let continuation = if hashes.len() > chunk_size {
+            hashes
+                .pop()
+                .map(|(next_epoch, _hash)| next_epoch.to_string())
+        } else {
+            None
+        };
+        let chunk = BlockChainChunk {
+            blocks: hashes
+                .into_iter()
+                .map(|(epoch, hash)| (epoch, hash.to_string()))
+                .collect(),
+            continuation,
+        };
+
+        serde_json::to_value(chunk).map_err(internal_error)
+    });
+
+    Box::new(fut)
+}
+
 /// Get block by hash
 /* test
 {"jsonrpc":"2.0","id":1,"method":"getBlock","params":["c0002c6b25615c0f71069f159dffddf8a0b3e529efb054402f0649e969715bdb"]}
@@ -393,6 +630,35 @@ pub fn get_block(hash: Result<(Hash,), jsonrpc_core::Error>) -> JsonRpcResultAsy
     )
 }
 
+/// Get the block consolidated in the given epoch
+/* test
+{"jsonrpc":"2.0","id":1,"method":"getBlockByEpoch","params":[279256]}
+*/
+pub fn get_block_by_epoch(epoch: Result<(Epoch,), jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let epoch = match epoch {
+        Ok(x) => x.0,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let chain_manager_addr = ChainManager::from_registry();
+    let fut = chain_manager_addr
+        .send(Traced::new(GetBlocksEpochRange::new(epoch..=epoch)))
+        .then(move |res| match res {
+            Ok(Ok(blocks)) => match blocks.into_iter().next() {
+                Some((_epoch, hash)) => futures::finished(hash),
+                None => futures::failed(internal_error_s(format!(
+                    "No block was consolidated in epoch {}",
+                    epoch
+                ))),
+            },
+            Ok(Err(e)) => futures::failed(internal_error(e)),
+            Err(e) => futures::failed(internal_error(e)),
+        })
+        .and_then(|hash| get_block(Ok((hash,))));
+
+    Box::new(fut)
+}
+
 /*
 /// get output
 pub fn get_output(output_pointer: Result<(String,), jsonrpc_core::Error>) -> JsonRpcResultAsync {
@@ -442,7 +708,7 @@ pub fn send_request(params: Result<BuildDrt, jsonrpc_core::Error>) -> JsonRpcRes
     match params {
         Ok(msg) => Box::new(
             ChainManager::from_registry()
-                .send(msg)
+                .send(Traced::new(msg))
                 .then(|res| match res {
                     Ok(Ok(hash)) => match serde_json::to_value(hash) {
                         Ok(x) => Box::new(futures::finished(x)),
@@ -472,7 +738,7 @@ pub fn send_value(params: Result<BuildVtt, jsonrpc_core::Error>) -> JsonRpcResul
     match params {
         Ok(msg) => Box::new(
             ChainManager::from_registry()
-                .send(msg)
+                .send(Traced::new(msg))
                 .then(|res| match res {
                     Ok(Ok(hash)) => match serde_json::to_value(hash) {
                         Ok(x) => Box::new(futures::finished(x)),
@@ -506,27 +772,37 @@ pub struct Status {
 }
 
 /// Get node status
-pub fn status() -> JsonRpcResultAsync {
+///
+/// When running `offline` there are no sessions, so the number of peers is always 0.
+pub fn status(offline: bool) -> JsonRpcResultAsync {
     let chain_manager = ChainManager::from_registry();
-    let sessions_manager = SessionsManager::from_registry();
 
-    let synchronized_fut =
-        chain_manager
-            .send(GetState)
-            .map_err(internal_error)
-            .then(|res| match res {
-                Ok(Ok(StateMachine::Synced)) => Ok(true),
-                Ok(Ok(..)) => Ok(false),
-                Ok(Err(())) => Err(internal_error(())),
-                Err(e) => Err(internal_error(e)),
-            });
-    let num_peers_fut = sessions_manager.send(NumSessions).then(|res| match res {
-        Ok(Ok(res)) => Ok(res),
-        Ok(Err(())) => Err(internal_error(())),
-        Err(e) => Err(internal_error(e)),
-    });
+    let synchronized_fut = chain_manager
+        .send(Traced::new(GetState))
+        .map_err(internal_error)
+        .then(|res| match res {
+            Ok(Ok(StateMachine::Synced)) => Ok(true),
+            Ok(Ok(..)) => Ok(false),
+            Ok(Err(())) => Err(internal_error(())),
+            Err(e) => Err(internal_error(e)),
+        });
+    let num_peers_fut: Box<
+        dyn Future<Item = NumSessionsResult, Error = jsonrpc_core::Error> + Send,
+    > = if offline {
+        Box::new(futures::finished(NumSessionsResult::default()))
+    } else {
+        Box::new(
+            SessionsManager::from_registry()
+                .send(NumSessions)
+                .then(|res| match res {
+                    Ok(Ok(res)) => Ok(res),
+                    Ok(Err(())) => Err(internal_error(())),
+                    Err(e) => Err(internal_error(e)),
+                }),
+        )
+    };
     let chain_beacon_fut = chain_manager
-        .send(GetHighestCheckpointBeacon)
+        .send(Traced::new(GetHighestCheckpointBeacon))
         .then(|res| match res {
             Ok(Ok(x)) => Ok(x),
             Ok(Err(e)) => Err(internal_error(e)),
@@ -623,9 +899,108 @@ pub fn data_request_report(params: Result<(Hash,), jsonrpc_core::Error>) -> Json
     let chain_manager_addr = System::current().registry().get::<ChainManager>();
 
     let fut = chain_manager_addr
-        .send(GetDataRequestReport { dr_pointer })
+        .send(Traced::new(GetDataRequestReport { dr_pointer }))
         .map_err(internal_error)
-        .and_then(|dr_info| match dr_info {
+        .and_then(|dr_info| dr_info.map_err(internal_error_s))
+        .and_then(move |dr_info| {
+            // The consensus script of the data request tells which reveals were out of consensus
+            let consensus = match dr_info.block_hash_dr_tx {
+                Some(block_hash) if dr_info.tally.is_some() => future::Either::A(
+                    InventoryManager::from_registry()
+                        .send(GetItem { hash: block_hash })
+                        .then(move |res| {
+                            let consensus = match res {
+                                Ok(Ok(chain::InventoryItem::Block(block))) => block
+                                    .txns
+                                    .data_request_txns
+                                    .into_iter()
+                                    .find(|dr_tx| dr_tx.hash() == dr_pointer)
+                                    .map(|dr_tx| dr_tx.body.dr_output.data_request.consensus),
+                                _ => None,
+                            };
+
+                            futures::finished(consensus)
+                        }),
+                ),
+                _ => future::Either::B(futures::finished(None)),
+            };
+
+            consensus.and_then(move |consensus| match serde_json::to_value(&dr_info) {
+                Ok(mut value) => {
+                    if let Some(map) = value.as_object_mut() {
+                        insert_tally_report(map, &dr_info, consensus.as_ref());
+                    }
+
+                    futures::finished(value)
+                }
+                Err(e) => futures::failed(internal_error_s(e)),
+            })
+        });
+
+    Box::new(fut)
+}
+
+/// Add the decoded result of a data request to its report, as canonical JSON, so consumers can
+/// parse it. The reveals are only decoded if the consensus script of the data request is known.
+fn insert_tally_report(
+    map: &mut serde_json::Map<String, Value>,
+    dr_info: &DataRequestInfo,
+    consensus: Option<&RADConsensus>,
+) {
+    let tally = match &dr_info.tally {
+        Some(tally) => tally,
+        None => return,
+    };
+    let (pkhs, reveals): (Vec<&PublicKeyHash>, Vec<&[u8]>) = dr_info
+        .reveals
+        .iter()
+        .map(|(pkh, reveal)| (pkh, reveal.body.reveal.as_slice()))
+        .unzip();
+    let default_consensus = RADConsensus::default();
+    let report = build_tally_report(
+        &tally.tally,
+        &reveals,
+        consensus.unwrap_or(&default_consensus),
+        JsonFormat::LATEST,
+    );
+
+    if let Some(result) = report.result_json {
+        map.insert("tally_result_json".to_string(), Value::from(result));
+        map.insert(
+            "json_format".to_string(),
+            Value::from(JsonFormat::LATEST.version()),
+        );
+    }
+    if let Some(error) = report.error {
+        map.insert("tally_error".to_string(), Value::from(error));
+    }
+    if consensus.is_some() {
+        let reveal_results = pkhs
+            .into_iter()
+            .zip(report.reveals)
+            .filter_map(|(pkh, reveal)| {
+                serde_json::to_value(reveal)
+                    .ok()
+                    .map(|reveal| (pkh.to_string(), reveal))
+            })
+            .collect();
+        map.insert("reveal_results".to_string(), Value::Object(reveal_results));
+    }
+}
+
+/// Get the live state of a data request
+pub fn get_data_request_state(params: Result<(Hash,), jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let dr_pointer = match params {
+        Ok(x) => x.0,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(GetDataRequestState { dr_pointer }))
+        .map_err(internal_error)
+        .and_then(|dr_state| match dr_state {
             Ok(x) => match serde_json::to_value(&x) {
                 Ok(x) => futures::finished(x),
                 Err(e) => {
@@ -649,7 +1024,7 @@ pub fn get_balance(params: Result<(PublicKeyHash,), jsonrpc_core::Error>) -> Jso
     let chain_manager_addr = System::current().registry().get::<ChainManager>();
 
     let fut = chain_manager_addr
-        .send(GetBalance { pkh })
+        .send(Traced::new(GetBalance { pkh }))
         .map_err(internal_error)
         .and_then(|dr_info| match dr_info {
             Ok(x) => match serde_json::to_value(&x) {
@@ -665,75 +1040,767 @@ pub fn get_balance(params: Result<(PublicKeyHash,), jsonrpc_core::Error>) -> Jso
     Box::new(fut)
 }
 
-#[cfg(test)]
-mod mock_actix {
-    use actix::{MailboxError, Message};
-    use futures::Future;
+/// Get the unspent outputs of this node or of a watched identity
+pub fn get_utxo_info(params: Result<(PublicKeyHash,), jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let pkh = match params {
+        Ok(x) => x.0,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
 
-    pub struct System;
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
 
-    pub struct SystemRegistry;
+    let fut = chain_manager_addr
+        .send(Traced::new(GetUtxoInfo { pkh }))
+        .map_err(internal_error)
+        .and_then(|utxo_info| match utxo_info {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
 
-    pub struct Addr;
+    Box::new(fut)
+}
 
-    impl System {
-        pub fn current() -> Self {
-            System
-        }
-        pub fn registry(&self) -> &SystemRegistry {
-            &SystemRegistry
-        }
-    }
+/// Start tracking the unspent outputs of an identity
+pub fn watch_pkh(params: Result<(PublicKeyHash,), jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let pkh = match params {
+        Ok(x) => x.0,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
 
-    impl SystemRegistry {
-        pub fn get<T>(&self) -> Addr {
-            Addr
-        }
-    }
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
 
-    impl Addr {
-        pub fn do_send<T>(&self, _msg: T) {}
-        pub fn send<T: Message>(
-            &self,
-            _msg: T,
-        ) -> impl Future<Item = T::Result, Error = MailboxError> {
-            // We cannot test methods which use `send`, so return an error
-            futures::failed(MailboxError::Closed)
-        }
-    }
+    let fut = chain_manager_addr
+        .send(Traced::new(WatchPkh { pkh }))
+        .map_err(internal_error)
+        .and_then(|watched| match watched {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
 }
 
-#[cfg(test)]
-mod tests {
-    use futures::sync::mpsc;
+/// Stop tracking the unspent outputs of an identity
+pub fn unwatch_pkh(params: Result<(PublicKeyHash,), jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let pkh = match params {
+        Ok(x) => x.0,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
 
-    use super::*;
-    use witnet_data_structures::{chain::RADRequest, transaction::*};
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
 
-    #[test]
-    fn empty_string_parse_error() {
-        // An empty message should return a parse error
-        let empty_string = "";
-        let parse_error =
-            r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":null}"#
-                .to_string();
-        let subscriptions = Subscriptions::default();
-        let (transport_sender, _transport_receiver) = mpsc::channel(0);
-        let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
-        let response = io.handle_request_sync(empty_string, meta);
-        assert_eq!(response, Some(parse_error));
-    }
+    let fut = chain_manager_addr
+        .send(Traced::new(UnwatchPkh { pkh }))
+        .map_err(internal_error)
+        .and_then(|unwatched| match unwatched {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
 
-    #[test]
-    fn inventory_method() {
-        // The expected behaviour of the inventory method
-        use witnet_data_structures::chain::*;
-        let block = block_example();
+    Box::new(fut)
+}
 
-        let inv_elem = InventoryItem::Block(block);
-        let s = serde_json::to_string(&inv_elem).unwrap();
-        let msg = format!(
+/// Get the identities whose unspent outputs are tracked, besides the node one
+pub fn get_watched_pkhs() -> JsonRpcResultAsync {
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(GetWatchedPkhs))
+        .map_err(internal_error)
+        .and_then(|pkhs| match pkhs {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get the hashes of the transactions in the transactions pool, optionally filtered by public key
+/// hash or data request
+pub fn get_mempool(params: Result<GetMempool, jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let msg = match params {
+        Ok(x) => x,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(msg))
+        .map_err(internal_error)
+        .and_then(|mempool| match mempool {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Assemble the block the node would mine right now, without signing or broadcasting it
+pub fn build_block_template(
+    params: Result<BuildBlockTemplate, jsonrpc_core::Error>,
+) -> JsonRpcResultAsync {
+    let msg = match params {
+        Ok(x) => x,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(msg))
+        .map_err(internal_error)
+        .and_then(|template| match template {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get the status of a transaction in the transactions pool, telling why it was dropped if it is
+/// no longer there
+pub fn get_transaction_status(params: Result<(Hash,), jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let hash = match params {
+        Ok(x) => x.0,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(GetTransactionStatus { hash }))
+        .map_err(internal_error)
+        .and_then(|status| match status {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Estimate the cost of a data request
+pub fn estimate_data_request_cost(
+    params: Result<(chain::DataRequestOutput,), jsonrpc_core::Error>,
+) -> JsonRpcResultAsync {
+    let dr_output = match params {
+        Ok(x) => x.0,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(EstimateDataRequestCost { dr_output }))
+        .map_err(internal_error)
+        .and_then(|estimate| match estimate {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get the summary of the last finished epoch
+pub fn epoch_summary() -> JsonRpcResultAsync {
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(GetEpochSummary))
+        .map_err(internal_error)
+        .and_then(|summary| match summary {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get the activation status of the protocol upgrades
+pub fn get_protocol_upgrades() -> JsonRpcResultAsync {
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(GetProtocolUpgrades))
+        .map_err(internal_error)
+        .and_then(|upgrades| match upgrades {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get the forecast of the expiration of the node reputation
+pub fn get_reputation_forecast() -> JsonRpcResultAsync {
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(GetReputationForecast))
+        .map_err(internal_error)
+        .and_then(|forecast| match forecast {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get a superblock built by this node
+pub fn get_superblock(params: Result<(u32,), jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let index = match params {
+        Ok(x) => x.0,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(GetSuperblock { index }))
+        .map_err(internal_error)
+        .and_then(|superblock| match superblock {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get the proof of membership of an identity in the ARS of a superblock
+pub fn get_ars_merkle_proof(
+    params: Result<(u32, PublicKeyHash), jsonrpc_core::Error>,
+) -> JsonRpcResultAsync {
+    let (index, pkh) = match params {
+        Ok(x) => x,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let chain_manager_addr = System::current().registry().get::<ChainManager>();
+
+    let fut = chain_manager_addr
+        .send(Traced::new(GetArsMerkleProof { index, pkh }))
+        .map_err(internal_error)
+        .and_then(|proof| match proof {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get the consensus constants used by the node
+pub fn get_consensus_constants() -> JsonRpcResultAsync {
+    let fut = config_mngr::get()
+        .map_err(internal_error_s)
+        .and_then(|config| {
+            serde_json::to_value(&config.consensus_constants).map_err(internal_error_s)
+        });
+
+    Box::new(fut)
+}
+
+/// Get the network of the node and the hash of its genesis block, the same identifier that is
+/// exchanged in the version handshake
+pub fn get_network() -> JsonRpcResultAsync {
+    let fut = config_mngr::get()
+        .map_err(internal_error_s)
+        .and_then(|config| {
+            serde_json::to_value(NetworkId {
+                network: config.environment.network_name().to_string(),
+                genesis_hash: config.consensus_constants.genesis_hash,
+            })
+            .map_err(internal_error_s)
+        });
+
+    Box::new(fut)
+}
+
+/// Block weight limit and weight of the transactions, as returned by `getWeightLimits`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeightLimits {
+    max_block_weight: u32,
+    vtt_weight_per_byte: u32,
+    dr_weight_per_byte: u32,
+}
+
+/// Get the block weight limit and the weight of every type of transaction
+pub fn get_weight_limits() -> JsonRpcResultAsync {
+    let fut = config_mngr::get()
+        .map_err(internal_error_s)
+        .and_then(|config| {
+            serde_json::to_value(WeightLimits {
+                max_block_weight: config.consensus_constants.max_block_weight,
+                vtt_weight_per_byte: VTT_WEIGHT_PER_BYTE,
+                dr_weight_per_byte: DR_WEIGHT_PER_BYTE,
+            })
+            .map_err(internal_error_s)
+        });
+
+    Box::new(fut)
+}
+
+/// Size and weight of a transaction, as returned by `getTransactionWeight`
+#[derive(Debug, Serialize)]
+pub struct TransactionWeight {
+    size: u32,
+    weight: u32,
+}
+
+/// Calculate the size and weight of a transaction
+pub fn get_transaction_weight(
+    params: Result<(Transaction,), jsonrpc_core::Error>,
+) -> JsonRpcResult {
+    let transaction = params?.0;
+
+    serde_json::to_value(TransactionWeight {
+        size: transaction.size(),
+        weight: transaction.weight(),
+    })
+    .map_err(internal_error_s)
+}
+
+/// Maximum number of blocks aggregated by `getMiners`
+const MAX_MINERS_BLOCKS: u32 = 10_000;
+
+/// Params of getMiners method
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetMinersParams {
+    /// First epoch of the range. A negative epoch is interpreted as "the last N epochs"
+    #[serde(default = "default_miners_epoch")]
+    pub epoch: i64,
+    /// Maximum number of blocks to aggregate, at most 10000. Defaults to the maximum if 0
+    #[serde(default)]
+    pub limit: u32,
+}
+
+fn default_miners_epoch() -> i64 {
+    -1000
+}
+
+impl Default for GetMinersParams {
+    fn default() -> Self {
+        Self {
+            epoch: default_miners_epoch(),
+            limit: 0,
+        }
+    }
+}
+
+/// Participation of an identity in a range of blocks
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinerStats {
+    pkh: PublicKeyHash,
+    blocks_mined: u32,
+    data_requests_witnessed: u32,
+}
+
+/// Participation of every identity in a range of blocks, as returned by `getMiners`
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinersLeaderboard {
+    from_epoch: Option<Epoch>,
+    to_epoch: Option<Epoch>,
+    blocks: usize,
+    miners: Vec<MinerStats>,
+}
+
+impl MinersLeaderboard {
+    /// Count the blocks mined and the data requests witnessed (that is, revealed) by every
+    /// identity, sorted by blocks mined and then by data requests witnessed, highest first
+    fn from_blocks(blocks: &[(Epoch, Block)]) -> Self {
+        fn entry(
+            stats: &mut HashMap<PublicKeyHash, MinerStats>,
+            pkh: PublicKeyHash,
+        ) -> &mut MinerStats {
+            stats.entry(pkh).or_insert(MinerStats {
+                pkh,
+                blocks_mined: 0,
+                data_requests_witnessed: 0,
+            })
+        }
+
+        let mut stats = HashMap::new();
+        for (_epoch, block) in blocks {
+            entry(&mut stats, block.txns.mint.output.pkh).blocks_mined += 1;
+            for reveal in &block.txns.reveal_txns {
+                if let Some(signature) = reveal.signatures.get(0) {
+                    entry(&mut stats, signature.public_key.pkh()).data_requests_witnessed += 1;
+                }
+            }
+        }
+
+        let mut miners: Vec<MinerStats> = stats.into_iter().map(|(_, stats)| stats).collect();
+        miners.sort_by(|a, b| {
+            (b.blocks_mined, b.data_requests_witnessed)
+                .cmp(&(a.blocks_mined, a.data_requests_witnessed))
+                .then_with(|| a.pkh.as_ref().cmp(b.pkh.as_ref()))
+        });
+
+        Self {
+            from_epoch: blocks.first().map(|(epoch, _)| *epoch),
+            to_epoch: blocks.last().map(|(epoch, _)| *epoch),
+            blocks: blocks.len(),
+            miners,
+        }
+    }
+}
+
+/// Get the number of blocks mined and data requests witnessed by every identity in a range of
+/// recent blocks
+/* test
+{"jsonrpc":"2.0","id":1,"method":"getMiners","params":{"epoch":-100}}
+*/
+pub fn get_miners(params: Result<GetMinersParams, jsonrpc_core::Error>) -> JsonRpcResultAsync {
+    let GetMinersParams { epoch, limit } = match params {
+        Ok(x) => x,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+    let limit = if limit == 0 {
+        MAX_MINERS_BLOCKS
+    } else {
+        limit.min(MAX_MINERS_BLOCKS)
+    };
+
+    let inventory_manager = InventoryManager::from_registry();
+    let fut = get_block_hashes(epoch, limit as usize)
+        .and_then(move |hashes| {
+            let blocks = hashes.into_iter().map(move |(epoch, hash)| {
+                inventory_manager
+                    .send(GetItem { hash })
+                    .then(move |res| match res {
+                        Ok(Ok(chain::InventoryItem::Block(block))) => Ok((epoch, block)),
+                        Ok(Ok(chain::InventoryItem::Transaction(_))) => Err(internal_error_s(
+                            format!("Not a block, {} is a transaction", hash),
+                        )),
+                        Ok(Err(e)) => Err(internal_error(e)),
+                        Err(e) => Err(internal_error(e)),
+                    })
+            });
+
+            futures::future::join_all(blocks)
+        })
+        .and_then(|blocks| {
+            serde_json::to_value(MinersLeaderboard::from_blocks(&blocks)).map_err(internal_error_s)
+        });
+
+    Box::new(fut)
+}
+
+/// Params of simulatePeersBeacons method
+#[cfg(feature = "simulation")]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatePeersBeaconsParams {
+    /// State of the state machine when the beacons are received
+    pub state: StateMachine,
+    /// Beacon of the local chain
+    pub our_beacon: CheckpointBeacon,
+    /// Beacons announced by the peers
+    pub peers_beacons: Vec<(std::net::SocketAddr, CheckpointBeacon)>,
+    /// Consensus threshold (in %). Defaults to the configured threshold of `state`
+    #[serde(default)]
+    pub threshold: Option<u32>,
+}
+
+/// Run the consensus on a synthetic set of peers beacons and return the decision the
+/// `ChainManager` would take, without changing the state of the node
+#[cfg(feature = "simulation")]
+pub fn simulate_peers_beacons(
+    params: Result<SimulatePeersBeaconsParams, jsonrpc_core::Error>,
+) -> JsonRpcResultAsync {
+    let SimulatePeersBeaconsParams {
+        state,
+        our_beacon,
+        peers_beacons,
+        threshold,
+    } = match params {
+        Ok(x) => x,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let fut = config_mngr::get()
+        .map_err(internal_error_s)
+        .and_then(move |config| {
+            let threshold = threshold.unwrap_or_else(|| {
+                ConsensusThresholds::from_config(&config.connections).for_state(state)
+            });
+            let decision =
+                consensus::peers_beacons_consensus(state, our_beacon, peers_beacons, threshold);
+
+            serde_json::to_value(decision).map_err(internal_error_s)
+        });
+
+    Box::new(fut)
+}
+
+/// Get the statistics of the known peers buckets
+pub fn known_peers_stats() -> JsonRpcResultAsync {
+    let peers_manager_addr = System::current().registry().get::<PeersManager>();
+
+    let fut = peers_manager_addr
+        .send(GetKnownPeersStats)
+        .map_err(internal_error)
+        .and_then(|stats| match stats {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Get the reliability statistics of the sources of the retrievals, by domain
+pub fn get_data_source_stats() -> JsonRpcResultAsync {
+    let rad_manager_addr = System::current().registry().get::<RadManager>();
+
+    let fut = rad_manager_addr
+        .send(GetDataSourceStats)
+        .map_err(internal_error)
+        .and_then(|stats| match stats {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+/// Capacity and queue depth of the mailboxes of the ChainManager and the InventoryManager, as
+/// returned by `getMailboxStats`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MailboxStats {
+    chain_manager_capacity: usize,
+    candidates: mailbox::QueueStats,
+    inventory_manager_capacity: usize,
+    inventory_requests: mailbox::QueueStats,
+}
+
+/// Get the depth of the queues of messages that are dropped when their actor is overloaded
+pub fn get_mailbox_stats() -> JsonRpcResultAsync {
+    let fut = config_mngr::get()
+        .map_err(internal_error_s)
+        .and_then(|config| {
+            serde_json::to_value(MailboxStats {
+                chain_manager_capacity: config.mailboxes.chain_manager,
+                candidates: mailbox::CANDIDATES.stats(),
+                inventory_manager_capacity: config.mailboxes.inventory_manager,
+                inventory_requests: mailbox::INVENTORY_REQUESTS.stats(),
+            })
+            .map_err(internal_error_s)
+        });
+
+    Box::new(fut)
+}
+
+/// Params of getSigningLog method
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSigningLogParams {
+    /// Only return the entries of this epoch or later
+    #[serde(default)]
+    pub since_epoch: Epoch,
+    /// Only return the last `limit` entries. All of them are returned if 0
+    #[serde(default)]
+    pub limit: usize,
+}
+
+/// Get the signatures recorded in the signing log of the node
+pub fn get_signing_log(
+    params: Result<GetSigningLogParams, jsonrpc_core::Error>,
+) -> JsonRpcResultAsync {
+    let params = match params {
+        Ok(x) => x,
+        Err(e) => return Box::new(futures::failed(e)),
+    };
+
+    let fut = signature_mngr::signing_log(params.since_epoch, params.limit)
+        .map_err(internal_error_s)
+        .and_then(|entries| serde_json::to_value(entries).map_err(internal_error_s));
+
+    Box::new(fut)
+}
+
+/// Get the statistics of the consolidated sessions, including the latency of each peer
+pub fn connected_peers_stats() -> JsonRpcResultAsync {
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    let fut = sessions_manager_addr
+        .send(GetConnectedPeersStats)
+        .map_err(internal_error)
+        .and_then(|stats| match stats {
+            Ok(x) => match serde_json::to_value(&x) {
+                Ok(x) => futures::finished(x),
+                Err(e) => {
+                    let err = internal_error_s(e);
+                    futures::failed(err)
+                }
+            },
+            Err(e) => futures::failed(internal_error_s(e)),
+        });
+
+    Box::new(fut)
+}
+
+#[cfg(test)]
+mod mock_actix {
+    use actix::{MailboxError, Message};
+    use futures::Future;
+
+    pub struct System;
+
+    pub struct SystemRegistry;
+
+    pub struct Addr;
+
+    impl System {
+        pub fn current() -> Self {
+            System
+        }
+        pub fn registry(&self) -> &SystemRegistry {
+            &SystemRegistry
+        }
+    }
+
+    impl SystemRegistry {
+        pub fn get<T>(&self) -> Addr {
+            Addr
+        }
+    }
+
+    impl Addr {
+        pub fn do_send<T>(&self, _msg: T) {}
+        pub fn send<T: Message>(
+            &self,
+            _msg: T,
+        ) -> impl Future<Item = T::Result, Error = MailboxError> {
+            // We cannot test methods which use `send`, so return an error
+            futures::failed(MailboxError::Closed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::sync::mpsc;
+
+    use super::*;
+    use witnet_data_structures::{chain::RADRequest, transaction::*};
+
+    #[test]
+    fn empty_string_parse_error() {
+        // An empty message should return a parse error
+        let empty_string = "";
+        let parse_error =
+            r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":null}"#
+                .to_string();
+        let subscriptions = Subscriptions::default();
+        let (transport_sender, _transport_receiver) = mpsc::channel(0);
+        let meta = Arc::new(Session::new(transport_sender));
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
+        let response = io.handle_request_sync(empty_string, meta);
+        assert_eq!(response, Some(parse_error));
+    }
+
+    #[test]
+    fn inventory_method() {
+        // The expected behaviour of the inventory method
+        use witnet_data_structures::chain::*;
+        let block = block_example();
+
+        let inv_elem = InventoryItem::Block(block);
+        let s = serde_json::to_string(&inv_elem).unwrap();
+        let msg = format!(
             r#"{{"jsonrpc":"2.0","method":"inventory","params":{},"id":1}}"#,
             s
         );
@@ -743,7 +1810,7 @@ mod tests {
         let subscriptions = Subscriptions::default();
         let (transport_sender, _transport_receiver) = mpsc::channel(0);
         let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
         let response = io.handle_request_sync(&msg, meta);
         assert_eq!(response, Some(expected));
     }
@@ -756,7 +1823,7 @@ mod tests {
         let subscriptions = Subscriptions::default();
         let (transport_sender, _transport_receiver) = mpsc::channel(0);
         let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
         let response = io.handle_request_sync(&msg, meta);
         // Compare only the first N characters
         let response =
@@ -764,6 +1831,18 @@ mod tests {
         assert_eq!(response, Some(expected));
     }
 
+    #[test]
+    fn block_chain_chunk_invalid_continuation() {
+        let msg = r#"{"jsonrpc":"2.0","method":"getBlockChainChunk","params":{"continuation":"abc"},"id":1}"#;
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid continuation token"},"id":1}"#.to_string();
+        let subscriptions = Subscriptions::default();
+        let (transport_sender, _transport_receiver) = mpsc::channel(0);
+        let meta = Arc::new(Session::new(transport_sender));
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
+        let response = io.handle_request_sync(&msg, meta);
+        assert_eq!(response, Some(expected));
+    }
+
     #[test]
     fn inventory_unimplemented_type() {
         // What happens when the inventory method is called with an unimplemented type?
@@ -774,7 +1853,7 @@ mod tests {
         let subscriptions = Subscriptions::default();
         let (transport_sender, _transport_receiver) = mpsc::channel(0);
         let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
         let response = io.handle_request_sync(&msg, meta);
         assert_eq!(response, Some(expected));
     }
@@ -793,11 +1872,100 @@ mod tests {
         let subscriptions = Subscriptions::default();
         let (transport_sender, _transport_receiver) = mpsc::channel(0);
         let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
+        let response = io.handle_request_sync(&msg, meta);
+        assert_eq!(response, Some(expected));
+    }
+
+    #[test]
+    fn transaction_weight() {
+        // The weight of a value transfer transaction is its size in bytes
+        use witnet_data_structures::transaction::VTTransaction;
+        let transaction = Transaction::ValueTransfer(VTTransaction::default());
+        let msg = format!(
+            r#"{{"jsonrpc":"2.0","method":"getTransactionWeight","params":[{}],"id":1}}"#,
+            serde_json::to_string(&transaction).unwrap()
+        );
+        let expected = format!(
+            r#"{{"jsonrpc":"2.0","result":{{"size":{0},"weight":{0}}},"id":1}}"#,
+            transaction.size()
+        );
+        let subscriptions = Subscriptions::default();
+        let (transport_sender, _transport_receiver) = mpsc::channel(0);
+        let meta = Arc::new(Session::new(transport_sender));
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
         let response = io.handle_request_sync(&msg, meta);
         assert_eq!(response, Some(expected));
     }
 
+    #[test]
+    fn miners_leaderboard() {
+        use witnet_data_structures::chain::*;
+
+        let public_key = |byte| PublicKey {
+            compressed: 2,
+            bytes: [byte; 32],
+        };
+        let block = |miner: u8, revealers: Vec<u8>| {
+            let mut block = block_example();
+            block.txns.mint.output.pkh = public_key(miner).pkh();
+            block.txns.reveal_txns = revealers
+                .into_iter()
+                .map(|revealer| {
+                    RevealTransaction::new(
+                        RevealTransactionBody::default(),
+                        vec![KeyedSignature {
+                            public_key: public_key(revealer),
+                            ..KeyedSignature::default()
+                        }],
+                    )
+                })
+                .collect();
+
+            block
+        };
+        let blocks = vec![
+            (10, block(1, vec![2, 3])),
+            (11, block(2, vec![3])),
+            (13, block(2, vec![])),
+        ];
+
+        assert_eq!(
+            MinersLeaderboard::from_blocks(&blocks),
+            MinersLeaderboard {
+                from_epoch: Some(10),
+                to_epoch: Some(13),
+                blocks: 3,
+                miners: vec![
+                    MinerStats {
+                        pkh: public_key(2).pkh(),
+                        blocks_mined: 2,
+                        data_requests_witnessed: 1,
+                    },
+                    MinerStats {
+                        pkh: public_key(1).pkh(),
+                        blocks_mined: 1,
+                        data_requests_witnessed: 0,
+                    },
+                    MinerStats {
+                        pkh: public_key(3).pkh(),
+                        blocks_mined: 0,
+                        data_requests_witnessed: 2,
+                    },
+                ],
+            }
+        );
+        assert_eq!(
+            MinersLeaderboard::from_blocks(&[]),
+            MinersLeaderboard {
+                from_epoch: None,
+                to_epoch: None,
+                blocks: 0,
+                miners: vec![],
+            }
+        );
+    }
+
     #[test]
     fn subscribe_invalid_method() {
         // Try to subscribe to a non-existent subscription?
@@ -808,7 +1976,7 @@ mod tests {
         let subscriptions = Subscriptions::default();
         let (transport_sender, _transport_receiver) = mpsc::channel(0);
         let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
         let response = io.handle_request_sync(&msg, meta);
         assert_eq!(response, Some(expected));
     }
@@ -821,11 +1989,38 @@ mod tests {
         let subscriptions = Subscriptions::default();
         let (transport_sender, _transport_receiver) = mpsc::channel(0);
         let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
         let response = io.handle_request_sync(&msg, meta);
         assert_eq!(response, Some(expected));
     }
 
+    #[test]
+    fn subscribe_watch_data_request() {
+        let dr_pointer = "c0002c6b25615c0f71069f159dffddf8a0b3e529efb054402f0649e969715bdb";
+        let msg = format!(
+            r#"{{"jsonrpc":"2.0","method":"witnet_subscribe","params":["watchDataRequest",["{}"]],"id":1}}"#,
+            dr_pointer
+        );
+        let expected = r#"{"jsonrpc":"2.0","result":"1","id":1}"#.to_string();
+        let subscriptions = Subscriptions::default();
+        let (transport_sender, _transport_receiver) = mpsc::channel(0);
+        let meta = Arc::new(Session::new(transport_sender));
+        let io = jsonrpc_io_handler(subscriptions.clone(), false, HeavyQueries::default());
+        let response = io.handle_request_sync(&msg, meta.clone());
+        assert_eq!(response, Some(expected));
+        {
+            let subscriptions = subscriptions.lock().unwrap();
+            let (_sink, params) = subscriptions["watchDataRequest"].values().next().unwrap();
+            assert_eq!(params, &Value::String(dr_pointer.to_string()));
+        }
+
+        // The hash of the data request is required
+        let msg =
+            r#"{"jsonrpc":"2.0","method":"witnet_subscribe","params":["watchDataRequest"],"id":1}"#;
+        let response = io.handle_request_sync(&msg, meta).unwrap();
+        assert!(response.contains(r#""code":-32602"#), "{}", response);
+    }
+
     #[test]
     fn unsubscribe_returns_true() {
         // Check that unsubscribe returns true
@@ -834,7 +2029,7 @@ mod tests {
         let subscriptions = Subscriptions::default();
         let (transport_sender, _transport_receiver) = mpsc::channel(0);
         let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
         // But first, subscribe to newBlocks
         let msg1 = r#"{"jsonrpc":"2.0","method":"witnet_subscribe","params":["newBlocks"],"id":1}"#;
         let _response1 = io.handle_request_sync(&msg1, meta.clone());
@@ -850,7 +2045,7 @@ mod tests {
         let subscriptions = Subscriptions::default();
         let (transport_sender, _transport_receiver) = mpsc::channel(0);
         let meta = Arc::new(Session::new(transport_sender));
-        let io = jsonrpc_io_handler(subscriptions);
+        let io = jsonrpc_io_handler(subscriptions, false, HeavyQueries::default());
         let response = io.handle_request_sync(&msg, meta);
         assert_eq!(response, Some(expected));
     }
@@ -862,7 +2057,7 @@ mod tests {
         let block = block_example();
         let inv_elem = InventoryItem::Block(block);
         let s = serde_json::to_string(&inv_elem).unwrap();
-        let expected = r#"{"block":{"block_header":{"version":0,"beacon":{"checkpoint":0,"hashPrevBlock":"0000000000000000000000000000000000000000000000000000000000000000"},"merkle_roots":{"mint_hash":"0000000000000000000000000000000000000000000000000000000000000000","vt_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000","dr_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000","commit_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000","reveal_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000","tally_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000"},"proof":{"proof":{"proof":[],"public_key":{"compressed":0,"bytes":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}}}},"block_sig":{"signature":{"Secp256k1":{"der":[]}},"public_key":{"compressed":0,"bytes":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}},"txns":{"mint":{"epoch":0,"output":{"pkh":"0000000000000000000000000000000000000000","value":0}},"value_transfer_txns":[],"data_request_txns":[{"body":{"inputs":[{"output_pointer":"0000000000000000000000000000000000000000000000000000000000000000:0"}],"outputs":[{"pkh":"0000000000000000000000000000000000000000","value":0}],"dr_output":{"data_request":{"not_before":0,"retrieve":[{"kind":"HTTP-GET","url":"https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22","script":[]},{"kind":"HTTP-GET","url":"https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22","script":[]}],"aggregate":{"script":[]},"consensus":{"script":[]},"deliver":[{"kind":"HTTP-GET","url":"https://hooks.zapier.com/hooks/catch/3860543/l2awcd/"},{"kind":"HTTP-GET","url":"https://hooks.zapier.com/hooks/catch/3860543/l1awcw/"}]},"value":0,"witnesses":0,"backup_witnesses":0,"commit_fee":0,"reveal_fee":0,"tally_fee":0,"time_lock":0}},"signatures":[{"signature":{"Secp256k1":{"der":[]}},"public_key":{"compressed":0,"bytes":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}}]}],"commit_txns":[],"reveal_txns":[],"tally_txns":[]}}}"#;
+        let expected = r#"{"block":{"block_header":{"version":0,"beacon":{"checkpoint":0,"hashPrevBlock":"0000000000000000000000000000000000000000000000000000000000000000"},"merkle_roots":{"mint_hash":"0000000000000000000000000000000000000000000000000000000000000000","vt_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000","dr_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000","commit_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000","reveal_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000","tally_hash_merkle_root":"0000000000000000000000000000000000000000000000000000000000000000"},"proof":{"proof":{"proof":[],"public_key":{"compressed":0,"bytes":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}}}},"block_sig":{"signature":{"Secp256k1":{"der":[]}},"public_key":{"compressed":0,"bytes":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}},"txns":{"mint":{"epoch":0,"output":{"pkh":"0000000000000000000000000000000000000000","value":0}},"value_transfer_txns":[],"data_request_txns":[{"body":{"inputs":[{"output_pointer":"0000000000000000000000000000000000000000000000000000000000000000:0"}],"outputs":[{"pkh":"0000000000000000000000000000000000000000","value":0}],"dr_output":{"data_request":{"not_before":0,"retrieve":[{"kind":"HTTP-GET","url":"https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22","script":[],"fallback_urls":[]},{"kind":"HTTP-GET","url":"https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22","script":[],"fallback_urls":[]}],"aggregate":{"script":[]},"consensus":{"script":[]},"deliver":[{"kind":"HTTP-GET","url":"https://hooks.zapier.com/hooks/catch/3860543/l2awcd/"},{"kind":"HTTP-GET","url":"https://hooks.zapier.com/hooks/catch/3860543/l1awcw/"}]},"value":0,"witnesses":0,"backup_witnesses":0,"commit_fee":0,"reveal_fee":0,"tally_fee":0,"time_lock":0}},"signatures":[{"signature":{"Secp256k1":{"der":[]}},"public_key":{"compressed":0,"bytes":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}}]}],"commit_txns":[],"reveal_txns":[],"tally_txns":[]}}}"#;
         assert_eq!(s, expected, "\n{}\n", s);
     }
 
@@ -875,12 +2070,14 @@ mod tests {
             kind: RADType::HttpGet,
             url: "https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22".to_string(),
             script: vec![0],
+            fallback_urls: vec![],
         };
 
         let rad_retrieve_2 = RADRetrieve {
             kind: RADType::HttpGet,
             url: "https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22".to_string(),
             script: vec![0],
+            fallback_urls: vec![],
         };
 
         let rad_consensus = RADConsensus { script: vec![0] };
@@ -907,7 +2104,7 @@ mod tests {
 
         let inv_elem = InventoryItem::Transaction(transaction);
         let s = serde_json::to_string(&inv_elem).unwrap();
-        let expected = r#"{"transaction":{"DataRequest":{"body":{"inputs":[{"output_pointer":"0909090909090909090909090909090909090909090909090909090909090909:0"}],"outputs":[],"dr_output":{"data_request":{"not_before":0,"retrieve":[{"kind":"HTTP-GET","url":"https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22","script":[0]},{"kind":"HTTP-GET","url":"https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22","script":[0]}],"aggregate":{"script":[0]},"consensus":{"script":[0]},"deliver":[{"kind":"HTTP-GET","url":"https://hooks.zapier.com/hooks/catch/3860543/l2awcd/"},{"kind":"HTTP-GET","url":"https://hooks.zapier.com/hooks/catch/3860543/l1awcw/"}]},"value":0,"witnesses":0,"backup_witnesses":0,"commit_fee":0,"reveal_fee":0,"tally_fee":0,"time_lock":0}},"signatures":[{"signature":{"Secp256k1":{"der":[]}},"public_key":{"compressed":0,"bytes":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}}]}}}"#;
+        let expected = r#"{"transaction":{"DataRequest":{"body":{"inputs":[{"output_pointer":"0909090909090909090909090909090909090909090909090909090909090909:0"}],"outputs":[],"dr_output":{"data_request":{"not_before":0,"retrieve":[{"kind":"HTTP-GET","url":"https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22","script":[0],"fallback_urls":[]},{"kind":"HTTP-GET","url":"https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22","script":[0],"fallback_urls":[]}],"aggregate":{"script":[0]},"consensus":{"script":[0]},"deliver":[{"kind":"HTTP-GET","url":"https://hooks.zapier.com/hooks/catch/3860543/l2awcd/"},{"kind":"HTTP-GET","url":"https://hooks.zapier.com/hooks/catch/3860543/l1awcw/"}]},"value":0,"witnesses":0,"backup_witnesses":0,"commit_fee":0,"reveal_fee":0,"tally_fee":0,"time_lock":0}},"signatures":[{"signature":{"Secp256k1":{"der":[]}},"public_key":{"compressed":0,"bytes":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}}]}}}"#;
         assert_eq!(s, expected, "\n{}\n", s);
     }
 
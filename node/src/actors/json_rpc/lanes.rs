@@ -0,0 +1,232 @@
+//! # Heavy queries
+//!
+//! Queries that read a range of blocks (`getBlockChain`, `getBlockChainChunk` and `getMiners`),
+//! like the exports of explorers backfilling the chain, take much longer than the operational
+//! queries of the node, like `status`. They are handled in their own lane: at most
+//! `max_heavy_queries` of them at the same time and at most `max_queued_heavy_queries` waiting for
+//! their turn, so they cannot take over the actors that serve every other query. Any other heavy
+//! query is rejected until one of them finishes.
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use futures::{future, sync::oneshot, Async, Future, Poll};
+use jsonrpc_core::Value;
+use witnet_util::correlation;
+
+/// Error code of the heavy queries rejected because their lane is full
+pub const SERVER_BUSY: i64 = -32001;
+
+type QueryResult = Box<dyn Future<Item = Value, Error = jsonrpc_core::Error> + Send>;
+
+/// Lane of the heavy queries, shared by all the connections of the JSON-RPC server
+#[derive(Clone)]
+pub struct HeavyQueries {
+    lane: Arc<Mutex<Lane>>,
+}
+
+struct Lane {
+    /// Queries being handled
+    running: usize,
+    /// Queries waiting for their turn, oldest first
+    waiting: VecDeque<oneshot::Sender<()>>,
+    max_running: usize,
+    max_waiting: usize,
+}
+
+impl Default for HeavyQueries {
+    /// A lane handling one query at a time, without queue
+    fn default() -> Self {
+        Self::new(1, 0)
+    }
+}
+
+impl HeavyQueries {
+    /// Create a lane handling at most `max_running` queries at the same time, with at most
+    /// `max_waiting` queries waiting for their turn.
+    pub fn new(max_running: usize, max_waiting: usize) -> Self {
+        HeavyQueries {
+            lane: Arc::new(Mutex::new(Lane {
+                running: 0,
+                waiting: VecDeque::new(),
+                max_running: std::cmp::max(max_running, 1),
+                max_waiting,
+            })),
+        }
+    }
+
+    /// Handle a heavy query once it gets its turn. The query is not started until then, keeping
+    /// the correlation id of the request.
+    pub fn run<F, R>(&self, query: F) -> QueryResult
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Future<Item = Value, Error = jsonrpc_core::Error> + Send + 'static,
+    {
+        let correlation_id = correlation::current();
+        let fut = self.acquire().and_then(move |permit| {
+            let query = match correlation_id {
+                Some(correlation_id) => correlation::scope(correlation_id, query),
+                None => query(),
+            };
+
+            query.then(move |res| {
+                drop(permit);
+
+                res
+            })
+        });
+
+        Box::new(fut)
+    }
+
+    /// Wait for the turn of a query, or fail if there are too many queries waiting.
+    fn acquire(&self) -> Box<dyn Future<Item = Permit, Error = jsonrpc_core::Error> + Send> {
+        let mut lane = match self.lane.lock() {
+            Ok(lane) => lane,
+            Err(_) => {
+                return Box::new(future::err(jsonrpc_core::Error::internal_error()));
+            }
+        };
+
+        if lane.running < lane.max_running {
+            lane.running += 1;
+
+            Box::new(future::ok(Permit {
+                lane: Arc::clone(&self.lane),
+            }))
+        } else if lane.waiting.len() < lane.max_waiting {
+            let (sender, receiver) = oneshot::channel();
+            lane.waiting.push_back(sender);
+
+            Box::new(Acquire {
+                lane: Arc::clone(&self.lane),
+                receiver,
+            })
+        } else {
+            Box::new(future::err(jsonrpc_core::Error {
+                code: jsonrpc_core::ErrorCode::ServerError(SERVER_BUSY),
+                message: "Too many heavy queries in progress, try again later".to_string(),
+                data: None,
+            }))
+        }
+    }
+}
+
+/// Give the turn of a finished query to the oldest waiting query, if any.
+fn release(lane: &Mutex<Lane>) {
+    if let Ok(mut lane) = lane.lock() {
+        while let Some(waiting) = lane.waiting.pop_front() {
+            // The query gets the turn unless it was dropped while waiting
+            if waiting.send(()).is_ok() {
+                return;
+            }
+        }
+        lane.running = lane.running.saturating_sub(1);
+    }
+}
+
+/// Turn of a query in the lane, released when dropped
+struct Permit {
+    lane: Arc<Mutex<Lane>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        release(&self.lane);
+    }
+}
+
+/// Future of the turn of a query waiting in the lane
+struct Acquire {
+    lane: Arc<Mutex<Lane>>,
+    receiver: oneshot::Receiver<()>,
+}
+
+impl Future for Acquire {
+    type Item = Permit;
+    type Error = jsonrpc_core::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.receiver.poll() {
+            Ok(Async::Ready(())) => Ok(Async::Ready(Permit {
+                lane: Arc::clone(&self.lane),
+            })),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(oneshot::Canceled) => Err(jsonrpc_core::Error::internal_error()),
+        }
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        // A query dropped after getting its turn, but before starting, gives it to the next one
+        self.receiver.close();
+        if let Ok(Some(())) = self.receiver.try_recv() {
+            release(&self.lane);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running(queries: &HeavyQueries) -> usize {
+        queries.lane.lock().unwrap().running
+    }
+
+    #[test]
+    fn queries_wait_for_their_turn() {
+        let queries = HeavyQueries::new(1, 1);
+        let first = queries.acquire().wait().unwrap();
+        let second = queries.acquire();
+        match queries.acquire().wait() {
+            Err(e) => assert_eq!(e.code, jsonrpc_core::ErrorCode::ServerError(SERVER_BUSY)),
+            Ok(_) => panic!("a query was queued in a full lane"),
+        }
+
+        drop(first);
+        let second = second.wait().unwrap();
+        assert_eq!(running(&queries), 1);
+        drop(second);
+        assert_eq!(running(&queries), 0);
+    }
+
+    #[test]
+    fn dropped_queries_give_their_turn() {
+        let queries = HeavyQueries::new(1, 2);
+        let first = queries.acquire().wait().unwrap();
+        let second = queries.acquire();
+        let third = queries.acquire();
+
+        // Dropped while waiting
+        drop(second);
+        drop(first);
+        let third = third.wait().unwrap();
+        assert_eq!(running(&queries), 1);
+
+        // Dropped after getting its turn
+        let fourth = queries.acquire();
+        drop(third);
+        drop(fourth);
+        assert_eq!(running(&queries), 0);
+    }
+
+    #[test]
+    fn queries_start_on_their_turn() {
+        let queries = HeavyQueries::new(1, 1);
+        let first = queries.acquire().wait().unwrap();
+        let started = Arc::new(Mutex::new(false));
+        let started_query = Arc::clone(&started);
+        let query = queries.run(move || {
+            *started_query.lock().unwrap() = true;
+
+            future::ok(Value::Bool(true))
+        });
+        assert!(!*started.lock().unwrap());
+
+        drop(first);
+        assert_eq!(query.wait().unwrap(), Value::Bool(true));
+        assert!(*started.lock().unwrap());
+        assert_eq!(running(&queries), 0);
+    }
+}
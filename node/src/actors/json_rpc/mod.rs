@@ -1,6 +1,7 @@
 mod connection;
 /// JSON-RPC methods
 pub mod json_rpc_methods;
+mod lanes;
 mod newline_codec;
 mod server;
 
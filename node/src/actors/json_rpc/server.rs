@@ -11,17 +11,24 @@ use tokio::{
 
 use futures::{sync::mpsc, Stream};
 use log::*;
-use std::{collections::HashMap, collections::HashSet, net::SocketAddr, rc::Rc, sync::Arc};
+use std::{
+    collections::HashMap, collections::HashSet, convert::TryFrom, net::SocketAddr, rc::Rc,
+    sync::Arc,
+};
 
 use super::{
-    connection::JsonRpc, json_rpc_methods::jsonrpc_io_handler, newline_codec::NewLineCodec,
-    SubscriptionResult, Subscriptions,
+    connection::JsonRpc, json_rpc_methods::jsonrpc_io_handler, lanes::HeavyQueries,
+    newline_codec::NewLineCodec, SubscriptionResult, Subscriptions,
 };
 use crate::{
-    actors::messages::{InboundTcpConnect, NewBlock},
+    actors::messages::{
+        DataRequestEvents, InboundTcpConnect, NewBlock, PeerEvent, TransactionDropped,
+    },
     config_mngr,
 };
 use jsonrpc_pubsub::{PubSubHandler, Session};
+use witnet_data_structures::data_request::DataRequestEvent;
+use witnet_rad::types::{canonical_json::JsonFormat, RadonTypes};
 
 /// JSON RPC server
 #[derive(Default)]
@@ -60,7 +67,15 @@ impl JsonRpcServer {
                 let server_addr = config.jsonrpc.server_address;
                 act.server_addr = Some(server_addr);
                 // Create and store the JSON-RPC method handler
-                let jsonrpc_io = jsonrpc_io_handler(act.subscriptions.clone());
+                let heavy_queries = HeavyQueries::new(
+                    config.jsonrpc.max_heavy_queries,
+                    config.jsonrpc.max_queued_heavy_queries,
+                );
+                let jsonrpc_io = jsonrpc_io_handler(
+                    act.subscriptions.clone(),
+                    config.connections.offline,
+                    heavy_queries,
+                );
                 act.jsonrpc_io = Some(Rc::new(jsonrpc_io));
 
                 // Bind TCP listener to this address
@@ -112,6 +127,7 @@ impl JsonRpcServer {
                 parent,
                 jsonrpc_io,
                 session: Arc::new(Session::new(transport_sender)),
+                in_flight: 0,
             }
         });
 
@@ -122,6 +138,51 @@ impl JsonRpcServer {
     fn remove_connection(&mut self, addr: &Addr<JsonRpc>) {
         self.open_connections.remove(addr);
     }
+
+    /// Send a notification with the given result to all the subscribers of a method
+    fn notify_subscribers(
+        &mut self,
+        ctx: &mut <Self as Actor>::Context,
+        method_name: &str,
+        result: serde_json::Value,
+    ) {
+        self.notify_subscribers_with_params(ctx, method_name, result, |_params| true)
+    }
+
+    /// Send a notification with the given result to the subscribers of a method whose
+    /// subscription params match the given filter
+    fn notify_subscribers_with_params<F>(
+        &mut self,
+        ctx: &mut <Self as Actor>::Context,
+        method_name: &str,
+        result: serde_json::Value,
+        filter: F,
+    ) where
+        F: Fn(&serde_json::Value) -> bool,
+    {
+        if let Ok(subs) = self.subscriptions.lock() {
+            let empty_map = HashMap::new();
+            for (subscription, (sink, subscription_params)) in
+                subs.get(method_name).unwrap_or(&empty_map)
+            {
+                if !filter(subscription_params) {
+                    continue;
+                }
+                debug!("Sending {} notification!", method_name);
+                let r = SubscriptionResult {
+                    result: result.clone(),
+                    subscription: subscription.clone(),
+                };
+                ctx.spawn(
+                    sink.notify(r.into())
+                        .into_actor(self)
+                        .then(|_res, _act, _ctx| actix::fut::ok(())),
+                );
+            }
+        } else {
+            error!("Failed to adquire lock in {} notification", method_name);
+        }
+    }
 }
 
 impl Actor for JsonRpcServer {
@@ -166,24 +227,61 @@ impl Handler<NewBlock> for JsonRpcServer {
     fn handle(&mut self, msg: NewBlock, ctx: &mut Self::Context) -> Self::Result {
         debug!("Got NewBlock message, sending notifications...");
         let block = serde_json::to_value(msg.block).unwrap();
-        if let Ok(subs) = self.subscriptions.lock() {
-            let empty_map = HashMap::new();
-            for (subscription, (sink, _subscription_params)) in
-                subs.get("newBlocks").unwrap_or(&empty_map)
-            {
-                debug!("Sending NewBlock notification!");
-                let r = SubscriptionResult {
-                    result: block.clone(),
-                    subscription: subscription.clone(),
-                };
-                ctx.spawn(
-                    sink.notify(r.into())
-                        .into_actor(self)
-                        .then(|_res, _act, _ctx| actix::fut::ok(())),
-                );
+        self.notify_subscribers(ctx, "newBlocks", block);
+    }
+}
+
+impl Handler<TransactionDropped> for JsonRpcServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: TransactionDropped, ctx: &mut Self::Context) -> Self::Result {
+        debug!("Got TransactionDropped message, sending notifications...");
+        let event = serde_json::to_value(msg).unwrap();
+        self.notify_subscribers(ctx, "droppedTransactions", event);
+    }
+}
+
+impl Handler<PeerEvent> for JsonRpcServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: PeerEvent, ctx: &mut Self::Context) -> Self::Result {
+        debug!("Got PeerEvent message, sending notifications...");
+        let event = serde_json::to_value(msg).unwrap();
+        self.notify_subscribers(ctx, "peerEvents", event);
+    }
+}
+
+impl Handler<DataRequestEvents> for JsonRpcServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: DataRequestEvents, ctx: &mut Self::Context) -> Self::Result {
+        debug!("Got DataRequestEvents message, sending notifications...");
+        for event in msg.events {
+            let dr_pointer = serde_json::Value::String(event.dr_pointer().to_string());
+            let mut result = serde_json::to_value(&event).unwrap();
+            if let Some(map) = result.as_object_mut() {
+                map.insert("epoch".to_string(), msg.epoch.into());
+                // Decode the result of the tallies, as done by `dataRequestReport`
+                if let DataRequestEvent::TallyMined { tally, .. } = &event {
+                    match RadonTypes::try_from(tally.as_slice())
+                        .and_then(|value| value.to_canonical_json(JsonFormat::LATEST))
+                    {
+                        Ok(json) => {
+                            map.insert("tally_result_json".to_string(), json.into());
+                            map.insert(
+                                "json_format".to_string(),
+                                JsonFormat::LATEST.version().into(),
+                            );
+                        }
+                        Err(e) => {
+                            map.insert("error".to_string(), e.to_string().into());
+                        }
+                    }
+                }
             }
-        } else {
-            error!("Failed to adquire lock in NewBlock handle");
+            self.notify_subscribers_with_params(ctx, "watchDataRequest", result, |params| {
+                *params == dr_pointer
+            });
         }
     }
 }
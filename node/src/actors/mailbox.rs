@@ -0,0 +1,109 @@
+//! # Mailbox metrics
+//!
+//! Depth of the queues of messages that are dropped when the mailbox of their actor is full, so
+//! an overloaded node can be told apart from a stuck one.
+//!
+//! Only the messages triggered by the peers are shed, with `try_send`:
+//! - the block candidates sent to the ChainManager, which are the bulk of the traffic during a
+//!   candidate flood, and losing some of them only delays the consolidation of a block,
+//! - the inventory items requested by the peers to the InventoryManager, which the peers request
+//!   again if they do not receive them.
+//!
+//! Every other message, like the blocks requested while synchronizing, bypasses the capacity of
+//! the mailbox with `do_send`, or waits until there is room in it with `send`, and is never
+//! dropped.
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Serialize;
+
+/// Counters of a queue of messages
+pub struct QueueMetrics {
+    depth: AtomicUsize,
+    max_depth: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+impl QueueMetrics {
+    /// Create a queue with all its counters set to zero
+    pub const fn new() -> Self {
+        QueueMetrics {
+            depth: AtomicUsize::new(0),
+            max_depth: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Record a message sent to the actor
+    pub fn queued(&self) {
+        let depth = self.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        if depth > self.max_depth.load(Ordering::Relaxed) {
+            self.max_depth.store(depth, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a message handled by the actor
+    pub fn processed(&self) {
+        // Never wrap around, even if a message was sent without being recorded
+        let depth = self.depth.load(Ordering::Relaxed);
+        if depth > 0 {
+            self.depth.store(depth - 1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a message dropped because the mailbox of the actor was full
+    pub fn dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current value of the counters, as returned by `getMailboxStats`
+    pub fn stats(&self) -> QueueStats {
+        QueueStats {
+            depth: self.depth.load(Ordering::Relaxed),
+            max_depth: self.max_depth.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counters of a queue of messages, as returned by `getMailboxStats`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStats {
+    /// Messages sent and not handled yet
+    pub depth: usize,
+    /// Highest depth since the node started
+    pub max_depth: usize,
+    /// Messages dropped because the mailbox was full
+    pub dropped: usize,
+}
+
+/// Block candidates sent by the sessions to the ChainManager
+pub static CANDIDATES: QueueMetrics = QueueMetrics::new();
+
+/// Inventory items requested by the peers to the InventoryManager
+pub static INVENTORY_REQUESTS: QueueMetrics = QueueMetrics::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_metrics() {
+        let queue = QueueMetrics::new();
+        queue.queued();
+        queue.queued();
+        queue.processed();
+        queue.dropped();
+        queue.processed();
+        queue.processed();
+
+        assert_eq!(
+            queue.stats(),
+            QueueStats {
+                depth: 0,
+                max_depth: 2,
+                dropped: 1,
+            }
+        );
+    }
+}
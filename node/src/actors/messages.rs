@@ -1,32 +1,48 @@
 use std::{
+    collections::BTreeMap,
     fmt,
     fmt::Debug,
     marker::Send,
     net::SocketAddr,
     ops::{Bound, RangeBounds},
+    time::Duration,
 };
 
-use actix::{actors::resolver::ResolverError, dev::ToEnvelope, Actor, Addr, Handler, Message};
+use actix::{
+    actors::resolver::ResolverError, dev::ToEnvelope, Actor, Addr, Handler, Message, Recipient,
+};
 use serde::{Deserialize, Serialize};
 use tokio::net::TcpStream;
 
+use witnet_config::config::WebhookEvent;
 use witnet_data_structures::{
     chain::{
-        Block, CheckpointBeacon, DataRequestInfo, DataRequestOutput, Epoch, EpochConstants, Hash,
-        InventoryEntry, InventoryItem, PublicKeyHash, RADConsensus, RADRequest,
-        ValueTransferOutput,
+        Block, CheckpointBeacon, DataRequestInfo, DataRequestOutput, DataRequestStateSummary,
+        Epoch, EpochConstants, Hash, InventoryEntry, InventoryItem, OutputPointer, PublicKeyHash,
+        RADConsensus, RADRequest, ValueTransferOutput,
     },
+    data_request::DataRequestEvent,
+    superblock::{ArsMerkleProof, Superblock},
     transaction::Transaction,
+    upgrades::UpgradeInfo,
+};
+use witnet_p2p::{
+    peers::PeersStats,
+    sessions::{SessionStatus, SessionType},
 };
-use witnet_p2p::sessions::{SessionStatus, SessionType};
-use witnet_rad::error::RadError;
+use witnet_rad::{error::RadError, http::HttpPolicy};
+use witnet_util::correlation::{self, CorrelationId};
 
 use super::{
-    chain_manager::{ChainManagerError, StateMachine, MAX_BLOCKS_SYNC},
+    chain_manager::{
+        transaction_factory::FeeSplit, ChainManagerError, EpochSummary, StateMachine,
+        MAX_BLOCKS_SYNC,
+    },
     epoch_manager::{
         AllEpochSubscription, EpochManagerError, SendableNotification, SingleEpochSubscription,
     },
     inventory_manager::InventoryManagerError,
+    rad_manager::DataSourceReport,
     session::Session,
 };
 
@@ -37,6 +53,29 @@ use super::{
 /// Message result of unit
 pub type SessionUnitResult = ();
 
+/// Wrapper of a message sent to the `ChainManager` that carries the correlation id of the request
+/// that originated it, so the logs of both actors can be related
+pub struct Traced<M> {
+    /// Correlation id of the originating request, if any
+    pub correlation_id: Option<CorrelationId>,
+    /// Wrapped message
+    pub message: M,
+}
+
+impl<M> Traced<M> {
+    /// Wrap a message using the correlation id of the request being handled in this thread
+    pub fn new(message: M) -> Self {
+        Self {
+            correlation_id: correlation::current(),
+            message,
+        }
+    }
+}
+
+impl<M: Message> Message for Traced<M> {
+    type Result = M::Result;
+}
+
 /// Message to obtain the highest block checkpoint managed by the `ChainManager`
 /// actor.
 pub struct GetHighestCheckpointBeacon;
@@ -45,16 +84,6 @@ impl Message for GetHighestCheckpointBeacon {
     type Result = Result<CheckpointBeacon, failure::Error>;
 }
 
-/// Add a new block
-pub struct AddBlocks {
-    /// Blocks
-    pub blocks: Vec<Block>,
-}
-
-impl Message for AddBlocks {
-    type Result = SessionUnitResult;
-}
-
 /// Add a new candidate
 pub struct AddCandidates {
     /// Candidates
@@ -142,6 +171,28 @@ impl Message for PeersBeacons {
     type Result = Result<Vec<SocketAddr>, ()>;
 }
 
+/// Consolidate a batch of blocks requested during the synchronization, returning the beacon of
+/// the chain once they are consolidated
+pub struct ConsolidateBatch {
+    /// Blocks to consolidate, ending at the target beacon at most. Blocks that arrive before
+    /// their parent are kept until it is consolidated
+    pub blocks: Vec<Block>,
+    /// Beacon the synchronization is trying to reach
+    pub target_beacon: CheckpointBeacon,
+}
+
+impl Message for ConsolidateBatch {
+    type Result = Result<CheckpointBeacon, failure::Error>;
+}
+
+/// The synchronization failed, so the node has to wait for a new consensus to start it again
+#[derive(Message)]
+pub struct SyncFailed {
+    /// Whether the failure was caused by a fork, so the chain state has to be restored from
+    /// storage
+    pub fork: bool,
+}
+
 /// Builds a `ValueTransferTransaction` from a list of `ValueTransferOutput`s
 #[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BuildVtt {
@@ -149,6 +200,12 @@ pub struct BuildVtt {
     pub vto: Vec<ValueTransferOutput>,
     /// Fee
     pub fee: u64,
+    /// Subtract the fee from the outputs instead of paying it on top of them
+    #[serde(default)]
+    pub subtract_fee_from_amount: bool,
+    /// How the fee is split among the outputs if it is subtracted from them
+    #[serde(default)]
+    pub fee_split: FeeSplit,
 }
 
 impl Message for BuildVtt {
@@ -187,6 +244,17 @@ impl Message for GetDataRequestReport {
     type Result = Result<DataRequestInfo, failure::Error>;
 }
 
+/// Get the live state of a data request in the data request pool
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetDataRequestState {
+    /// `DataRequest` transaction hash
+    pub dr_pointer: Hash,
+}
+
+impl Message for GetDataRequestState {
+    type Result = Result<DataRequestStateSummary, failure::Error>;
+}
+
 /// Get Balance
 #[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct GetBalance {
@@ -198,6 +266,285 @@ impl Message for GetBalance {
     type Result = Result<u64, failure::Error>;
 }
 
+/// Start tracking the unspent outputs of an identity, besides the ones of this node
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WatchPkh {
+    /// Public key hash
+    pub pkh: PublicKeyHash,
+}
+
+impl Message for WatchPkh {
+    type Result = Result<bool, failure::Error>;
+}
+
+/// Stop tracking the unspent outputs of a watched identity
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UnwatchPkh {
+    /// Public key hash
+    pub pkh: PublicKeyHash,
+}
+
+impl Message for UnwatchPkh {
+    type Result = Result<bool, failure::Error>;
+}
+
+/// Get the identities whose unspent outputs are tracked, besides the ones of this node
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetWatchedPkhs;
+
+impl Message for GetWatchedPkhs {
+    type Result = Result<Vec<PublicKeyHash>, failure::Error>;
+}
+
+/// Get the unspent outputs of this node or of a watched identity
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetUtxoInfo {
+    /// Public key hash
+    pub pkh: PublicKeyHash,
+}
+
+impl Message for GetUtxoInfo {
+    type Result = Result<UtxoInfo, failure::Error>;
+}
+
+/// Unspent outputs of an identity
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UtxoInfo {
+    /// Unspent outputs
+    pub utxos: Vec<UtxoMetadata>,
+    /// Sum of the values of the unspent outputs
+    pub total: u64,
+}
+
+/// Unspent output of an identity
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UtxoMetadata {
+    /// Output pointer
+    pub output_pointer: OutputPointer,
+    /// Value of the output
+    pub value: u64,
+}
+
+/// Get the hashes of the transactions in the transactions pool.
+///
+/// When filters are set, only the transactions matching all of them are returned.
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetMempool {
+    /// Only return transactions involving this public key hash, that is, transactions signed by
+    /// it or with outputs to it
+    #[serde(default)]
+    pub pkh: Option<PublicKeyHash>,
+    /// Only return the commits and reveals for this data request
+    #[serde(default)]
+    pub dr_hash: Option<Hash>,
+}
+
+/// Hashes of the transactions in the transactions pool, by type
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetMempoolResult {
+    /// Value transfer transactions
+    pub value_transfer: Vec<Hash>,
+    /// Data request transactions
+    pub data_request: Vec<Hash>,
+    /// Commit transactions
+    pub commit: Vec<Hash>,
+    /// Reveal transactions
+    pub reveal: Vec<Hash>,
+}
+
+impl Message for GetMempool {
+    type Result = Result<GetMempoolResult, failure::Error>;
+}
+
+/// Assemble the block this node would mine right now, without signing or broadcasting it
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BuildBlockTemplate {
+    /// Only consider these transactions of the transactions pool
+    #[serde(default)]
+    pub tx_hashes: Option<Vec<Hash>>,
+}
+
+/// Block assembled by `BuildBlockTemplate`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockTemplate {
+    /// Unsigned block, with an empty eligibility proof
+    pub block: Block,
+    /// Weight of the value transfer transactions, the only ones limited by `max_block_weight`
+    pub weight: u32,
+    /// Maximum weight of the value transfer transactions of a block
+    pub max_block_weight: u32,
+    /// Sum of the fees of the transactions
+    pub fees: u64,
+    /// Requested transactions that were not included in the block
+    pub excluded: Vec<Hash>,
+}
+
+impl Message for BuildBlockTemplate {
+    type Result = Result<BlockTemplate, failure::Error>;
+}
+
+/// Get the status of a transaction in the transactions pool
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetTransactionStatus {
+    /// Transaction hash
+    pub hash: Hash,
+}
+
+impl Message for GetTransactionStatus {
+    type Result = Result<TransactionStatus, failure::Error>;
+}
+
+/// Status of a transaction in the transactions pool
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status", content = "reason")]
+pub enum TransactionStatus {
+    /// The transaction is in the pool, waiting to be included in a block
+    Pending,
+    /// The transaction was dropped from the pool
+    Dropped(DropReason),
+    /// The transaction is not in the pool and it was not dropped recently: either it is unknown
+    /// to this node or it has already been included in a block
+    Unknown,
+}
+
+/// Reason why a transaction was dropped from the transactions pool
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DropReason {
+    /// The transaction was not included in a block before the pool expiry time
+    Expired,
+    /// A block included another transaction spending the same outputs
+    Conflicted,
+    /// The pool was full and the transaction paid one of the lowest fees
+    LowFee,
+}
+
+/// Get the summary of the last finished epoch
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetEpochSummary;
+
+impl Message for GetEpochSummary {
+    type Result = Result<EpochSummary, failure::Error>;
+}
+
+/// Get the activation status of the protocol upgrades
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetProtocolUpgrades;
+
+impl Message for GetProtocolUpgrades {
+    type Result = Result<Vec<UpgradeInfo>, failure::Error>;
+}
+
+/// Get the forecast of the expiration of the node reputation
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetReputationForecast;
+
+impl Message for GetReputationForecast {
+    type Result = Result<ReputationForecast, failure::Error>;
+}
+
+/// Forecast of the expiration of the reputation of an identity
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationForecast {
+    /// Identity
+    pub pkh: PublicKeyHash,
+    /// Epoch of the last consolidated block
+    pub epoch: Epoch,
+    /// Current reputation
+    pub reputation: u32,
+    /// Current number of witnessing acts
+    pub alpha: u32,
+    /// Average number of witnessing acts per epoch in the recent blocks, used to estimate the
+    /// expiration epochs. `None` until there are enough blocks to estimate it
+    pub alpha_per_epoch: Option<f64>,
+    /// Reputation that will expire, sorted by expiration
+    pub expirations: Vec<ReputationExpiration>,
+}
+
+/// Reputation that will expire once the given number of witnessing acts is reached
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationExpiration {
+    /// Number of witnessing acts at which the reputation expires
+    pub alpha: u32,
+    /// Reputation that expires
+    pub reputation: u32,
+    /// Estimated epoch of the expiration
+    pub epoch: Option<Epoch>,
+}
+
+impl ReputationForecast {
+    /// Reputation that the identity will still have at the given epoch, if it does not gain any
+    /// more reputation.
+    pub fn reputation_at(&self, epoch: Epoch) -> u32 {
+        self.expirations
+            .iter()
+            .filter(|expiration| expiration.epoch.map_or(false, |e| e <= epoch))
+            .fold(self.reputation, |reputation, expiration| {
+                reputation.saturating_sub(expiration.reputation)
+            })
+    }
+}
+
+/// Get a superblock built by this node
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetSuperblock {
+    /// Superblock index
+    pub index: u32,
+}
+
+impl Message for GetSuperblock {
+    type Result = Result<Superblock, failure::Error>;
+}
+
+/// Get the proof of membership of an identity in the ARS of a superblock
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GetArsMerkleProof {
+    /// Superblock index
+    pub index: u32,
+    /// Identity
+    pub pkh: PublicKeyHash,
+}
+
+impl Message for GetArsMerkleProof {
+    type Result = Result<ArsMerkleProof, failure::Error>;
+}
+
+/// Estimate the cost of a data request, along with the current network conditions
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EstimateDataRequestCost {
+    /// Data request to estimate
+    pub dr_output: DataRequestOutput,
+}
+
+/// Cost breakdown of a data request
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DataRequestCostEstimate {
+    /// Total value spent by the data request, fees included
+    pub total: u64,
+    /// Reward shared between all the witnesses
+    pub witnesses_reward: u64,
+    /// Reward for each witness
+    pub reward_per_witness: u64,
+    /// Fee for the miners of the commit transactions
+    pub commit_fee: u64,
+    /// Fee for the miners of the reveal transactions
+    pub reveal_fee: u64,
+    /// Fee for the miner of the tally transaction
+    pub tally_fee: u64,
+    /// Number of identities currently active in the witnessing protocol
+    pub active_identities: u32,
+    /// Problems that would make the data request invalid or unlikely to be resolved
+    pub issues: Vec<String>,
+}
+
+impl Message for EstimateDataRequestCost {
+    type Result = Result<DataRequestCostEstimate, failure::Error>;
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////
 // MESSAGES FROM CONNECTIONS MANAGER
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -207,12 +554,26 @@ impl Message for GetBalance {
 pub struct InboundTcpConnect {
     /// Tcp stream of the inbound connections
     pub stream: TcpStream,
+    /// Address of the listener that accepted the connection, if known
+    pub listener: Option<SocketAddr>,
 }
 
 impl InboundTcpConnect {
     /// Method to create a new InboundTcpConnect message from a TCP stream
     pub fn new(stream: TcpStream) -> InboundTcpConnect {
-        InboundTcpConnect { stream }
+        InboundTcpConnect {
+            stream,
+            listener: None,
+        }
+    }
+
+    /// Method to create a new InboundTcpConnect message from a TCP stream accepted by the
+    /// listener bound to the given address
+    pub fn from_listener(stream: TcpStream, listener: SocketAddr) -> InboundTcpConnect {
+        InboundTcpConnect {
+            stream,
+            listener: Some(listener),
+        }
     }
 }
 
@@ -343,6 +704,18 @@ impl Message for GetItem {
     type Result = Result<InventoryItem, InventoryManagerError>;
 }
 
+/// Send an item identified by its hash to the peer that requested it
+pub struct ServeItem {
+    /// item hash
+    pub hash: Hash,
+    /// Session of the peer
+    pub peer: Recipient<SendInventoryItem>,
+}
+
+impl Message for ServeItem {
+    type Result = ();
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////
 // MESSAGES FROM PEERS MANAGER
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -385,6 +758,31 @@ impl Message for RemovePeers {
     type Result = PeersSocketAddrsResult;
 }
 
+/// Message to record a peer that belongs to another network
+pub struct RemoveOtherNetworkPeer {
+    /// Socket address of the peer
+    pub address: SocketAddr,
+
+    /// Session type. Only the addresses of outbound sessions are removed from the list, the
+    /// addresses of inbound sessions are not the ones of the peer server
+    pub session_type: SessionType,
+}
+
+impl Message for RemoveOtherNetworkPeer {
+    type Result = ();
+}
+
+/// Message to record a failed outbound connection attempt, so that the address is not tried
+/// again until its retry backoff expires
+pub struct OutboundConnectionFailed {
+    /// Address of the peer
+    pub address: SocketAddr,
+}
+
+impl Message for OutboundConnectionFailed {
+    type Result = ();
+}
+
 /// Message to get a (random) peer address from the list
 pub struct GetRandomPeer;
 
@@ -399,6 +797,13 @@ impl Message for RequestPeers {
     type Result = PeersSocketAddrsResult;
 }
 
+/// Message to get the statistics of the known peers buckets
+pub struct GetKnownPeersStats;
+
+impl Message for GetKnownPeersStats {
+    type Result = Result<PeersStats, failure::Error>;
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////
 // MESSAGES FROM RAD MANAGER
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -407,8 +812,13 @@ impl Message for RequestPeers {
 /// request.
 #[derive(Debug)]
 pub struct ResolveRA {
+    /// Pointer to the data request being resolved
+    pub dr_pointer: Hash,
     /// RAD request to be executed
     pub rad_request: RADRequest,
+    /// Handling of the HTTP responses of the retrievals, or `None` to use the defaults of the
+    /// HTTP client until the `rad_http_policy` protocol upgrade is active
+    pub http_policy: Option<HttpPolicy>,
 }
 
 /// Message for running the consensus step of a data request.
@@ -428,6 +838,27 @@ impl Message for RunConsensus {
     type Result = Result<Vec<u8>, RadError>;
 }
 
+/// Message to compare the results of the sources used to resolve a data request with its tally
+#[derive(Debug)]
+pub struct DataRequestTallied {
+    /// Pointer to the data request
+    pub dr_pointer: Hash,
+    /// Serialized result of the tally
+    pub tally: Vec<u8>,
+}
+
+impl Message for DataRequestTallied {
+    type Result = ();
+}
+
+/// Message to get the reliability statistics of the sources of the retrievals, by domain
+#[derive(Debug)]
+pub struct GetDataSourceStats;
+
+impl Message for GetDataSourceStats {
+    type Result = Result<BTreeMap<String, DataSourceReport>, failure::Error>;
+}
+
 ////////////////////////////////////////////////////////////////////////////////////////
 // MESSAGES FROM SESSION
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -487,7 +918,38 @@ impl fmt::Display for SendLastBeacon {
 
 /// Message to close an open session
 #[derive(Clone, Debug, Message)]
-pub struct CloseSession;
+pub struct CloseSession {
+    /// Reason for closing the session
+    pub reason: DisconnectReason,
+}
+
+/// Reason why a session with a peer was closed
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DisconnectReason {
+    /// The peer uses different consensus constants (mismatching magic number)
+    ConsensusConstantsMismatch,
+    /// The peer belongs to another network or has another genesis block
+    NetworkMismatch,
+    /// The peer beacon is out of the consensus of the other peers
+    OutOfConsensus,
+    /// The session could not be registered, e.g. because the sessions limit was reached
+    Rejected,
+    /// The handshake with the peer did not succeed
+    HandshakeFailure,
+    /// The handshake was not completed in time
+    HandshakeTimeout,
+    /// The peer did not answer a keepalive ping in time
+    KeepaliveTimeout,
+    /// The peer did not send the requested blocks in time
+    BlocksTimeout,
+    /// Error in the local node while handling the session
+    InternalError,
+    /// The connection was closed, by the peer or by this node on shutdown
+    Closed,
+    /// The session was a feeler connection, closed right after a successful handshake
+    Feeler,
+}
 
 ////////////////////////////////////////////////////////////////////////////////////////
 // MESSAGES FROM SESSIONS MANAGER
@@ -503,6 +965,9 @@ pub struct Create {
 
     /// Session type
     pub session_type: SessionType,
+
+    /// Address of the listener that accepted the connection, for inbound sessions
+    pub listener: Option<SocketAddr>,
 }
 
 impl Message for Create {
@@ -535,12 +1000,25 @@ pub struct Unregister {
 
     /// Session status
     pub status: SessionStatus,
+
+    /// Reason for closing the session
+    pub reason: DisconnectReason,
 }
 
 impl Message for Unregister {
     type Result = SessionsUnitResult;
 }
 
+/// Message indicating a feeler connection needs to be started: a short-lived outbound connection
+/// which is closed right after the handshake, used to test an address of the new addresses
+/// bucket before promoting it to the tried addresses bucket, or an address of the tried addresses
+/// bucket before demoting it to the new addresses bucket
+#[derive(Message)]
+pub struct FeelerConnect {
+    /// Address to be tested
+    pub address: SocketAddr,
+}
+
 /// Message indicating a session needs to be consolidated
 pub struct Consolidate {
     /// Socket address which identifies the peer
@@ -565,6 +1043,9 @@ pub struct Anycast<T> {
     pub command: T,
     /// Safu flag: use only outbound peers in consensus with us?
     pub safu: bool,
+    /// Low latency flag: pick the session among the peers with the lowest latency instead of a
+    /// random one, for time-critical requests
+    pub low_latency: bool,
 }
 
 impl<T> Message for Anycast<T>
@@ -593,6 +1074,17 @@ where
     type Result = ();
 }
 
+/// Message indicating a block candidate mined by this node is to be sent at once to all the
+/// consolidated sessions, starting with the trusted relay peers
+pub struct BroadcastCandidate {
+    /// Block candidate
+    pub block: Block,
+}
+
+impl Message for BroadcastCandidate {
+    type Result = ();
+}
+
 /// Message indicating the last beacon received from a peer
 #[derive(Clone, Debug, Message)]
 pub struct PeerBeacon {
@@ -602,6 +1094,34 @@ pub struct PeerBeacon {
     pub beacon: CheckpointBeacon,
 }
 
+/// Message indicating the round-trip time of the last keepalive ping sent to a peer
+#[derive(Clone, Debug, Message)]
+pub struct PeerLatency {
+    /// Socket address which identifies the peer
+    pub address: SocketAddr,
+    /// Time elapsed between sending the ping and receiving the pong
+    pub latency: Duration,
+}
+
+/// Get statistics about the consolidated sessions
+#[derive(Clone, Debug)]
+pub struct GetConnectedPeersStats;
+
+impl Message for GetConnectedPeersStats {
+    type Result = Result<Vec<ConnectedPeerStats>, failure::Error>;
+}
+
+/// Statistics about a consolidated session
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConnectedPeerStats {
+    /// Socket address which identifies the peer
+    pub address: SocketAddr,
+    /// Session type: "inbound" or "outbound"
+    pub session_type: String,
+    /// Round-trip time in milliseconds of the last keepalive ping, if it was answered
+    pub latency_ms: Option<u64>,
+}
+
 /// Get number of inbound and outbound sessions
 #[derive(Clone, Debug)]
 pub struct NumSessions;
@@ -627,3 +1147,88 @@ pub struct NewBlock {
     /// Block
     pub block: Block,
 }
+
+/// Stage transitions of the data requests caused by a new block
+#[derive(Message)]
+pub struct DataRequestEvents {
+    /// Epoch of the block
+    pub epoch: Epoch,
+    /// Stage transitions, in the order they happened
+    pub events: Vec<DataRequestEvent>,
+}
+
+/// Notification of a transaction dropped from the transactions pool
+#[derive(Clone, Debug, Message, Serialize)]
+pub struct TransactionDropped {
+    /// Transaction hash
+    pub hash: Hash,
+    /// Why the transaction was dropped
+    pub reason: DropReason,
+}
+
+/// Peer connection event notification
+#[derive(Clone, Debug, Message, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerEvent {
+    /// Socket address of the peer
+    pub address: SocketAddr,
+    /// Session type
+    pub session_type: SessionType,
+    /// What happened to the session
+    #[serde(flatten)]
+    pub kind: PeerEventKind,
+}
+
+/// Kind of peer connection event
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "reason")]
+pub enum PeerEventKind {
+    /// A session with the peer was registered
+    Connected,
+    /// The session with the peer was closed
+    Disconnected(DisconnectReason),
+    /// The peer was unregistered because its beacon is out of consensus, its session will be
+    /// closed next
+    Unregistered(DisconnectReason),
+}
+
+////////////////////////////////////////////////////////////////////////////////////////
+// MESSAGES FROM SYNC MANAGER
+////////////////////////////////////////////////////////////////////////////////////////
+
+/// Start synchronizing the chain up to the target beacon, or update the target of the
+/// synchronization in progress
+#[derive(Message)]
+pub struct StartSync {
+    /// Beacon agreed by the peers
+    pub target_beacon: CheckpointBeacon,
+    /// Beacon of the local chain
+    pub chain_beacon: CheckpointBeacon,
+}
+
+/// Stop the synchronization in progress, if any
+#[derive(Message)]
+pub struct StopSync;
+
+/// Add a batch of blocks requested during the synchronization
+pub struct AddBlocks {
+    /// Blocks
+    pub blocks: Vec<Block>,
+}
+
+impl Message for AddBlocks {
+    type Result = SessionUnitResult;
+}
+
+////////////////////////////////////////////////////////////////////////////////////////
+// MESSAGES FROM WEBHOOK NOTIFIER
+////////////////////////////////////////////////////////////////////////////////////////
+
+/// Message indicating a node event to be notified to the subscribed webhooks
+#[derive(Clone, Debug, Message)]
+pub struct NotifyWebhooks {
+    /// Type of the event
+    pub event: WebhookEvent,
+    /// JSON description of the event
+    pub payload: serde_json::Value,
+}
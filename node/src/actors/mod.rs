@@ -7,6 +7,9 @@ pub mod node;
 /// Actor messages module
 pub mod messages;
 
+/// Mailbox metrics module
+pub mod mailbox;
+
 /// Peers manager actor module
 pub mod peers_manager;
 
@@ -28,6 +31,9 @@ pub mod epoch_manager;
 /// ChainManager actor module
 pub mod chain_manager;
 
+/// SyncManager actor module
+pub mod sync_manager;
+
 /// InventoryManager actor module
 pub mod inventory_manager;
 
@@ -36,3 +42,6 @@ pub mod rad_manager;
 
 /// JSON RPC server
 pub mod json_rpc;
+
+/// WebhookNotifier actor module
+pub mod webhook_notifier;
@@ -9,6 +9,7 @@ use crate::actors::{
     chain_manager::ChainManager, connections_manager::ConnectionsManager,
     epoch_manager::EpochManager, inventory_manager::InventoryManager, json_rpc::JsonRpcServer,
     peers_manager::PeersManager, rad_manager::RadManager, sessions_manager::SessionsManager,
+    sync_manager::SyncManager, webhook_notifier::WebhookNotifier,
 };
 use crate::config_mngr;
 use crate::signature_mngr;
@@ -17,6 +18,12 @@ use witnet_config::config::Config;
 
 /// Function to run the main system
 pub fn run(config: Config, callback: fn()) -> Result<(), failure::Error> {
+    // Refuse to start with an invalid configuration
+    config.validate()?;
+
+    let offline = config.connections.offline;
+    let mailboxes = config.mailboxes.clone();
+
     // Init system
     let system = System::new("node");
 
@@ -30,34 +37,52 @@ pub fn run(config: Config, callback: fn()) -> Result<(), failure::Error> {
     storage_mngr::start();
     signature_mngr::start();
 
-    // Start PeersManager actor
-    let peers_manager_addr = PeersManager::default().start();
-    System::current().registry().set(peers_manager_addr);
+    if offline {
+        info!("Running in offline mode: networking actors will not be started");
+    } else {
+        // Start PeersManager actor
+        let peers_manager_addr = PeersManager::default().start();
+        System::current().registry().set(peers_manager_addr);
 
-    // Start ConnectionsManager actor
-    let connections_manager_addr = ConnectionsManager::default().start();
-    System::current().registry().set(connections_manager_addr);
+        // Start ConnectionsManager actor
+        let connections_manager_addr = ConnectionsManager::default().start();
+        System::current().registry().set(connections_manager_addr);
 
-    // Start SessionManager actor
-    let sessions_manager_addr = SessionsManager::default().start();
-    System::current().registry().set(sessions_manager_addr);
+        // Start SessionManager actor
+        let sessions_manager_addr = SessionsManager::default().start();
+        System::current().registry().set(sessions_manager_addr);
+    }
 
     // Start EpochManager actor
     let epoch_manager_addr = EpochManager::default().start();
     System::current().registry().set(epoch_manager_addr);
 
     // Start ChainManager actor
-    let chain_manager_addr = ChainManager::default().start();
+    let chain_manager_addr = ChainManager::create(|ctx| {
+        ctx.set_mailbox_capacity(mailboxes.chain_manager);
+        ChainManager::default()
+    });
     System::current().registry().set(chain_manager_addr);
 
+    // Start SyncManager actor
+    let sync_manager_addr = SyncManager::default().start();
+    System::current().registry().set(sync_manager_addr);
+
     // Start InventoryManager actor
-    let inventory_manager_addr = InventoryManager::default().start();
+    let inventory_manager_addr = InventoryManager::create(|ctx| {
+        ctx.set_mailbox_capacity(mailboxes.inventory_manager);
+        InventoryManager::default()
+    });
     System::current().registry().set(inventory_manager_addr);
 
     // Start RadManager actor
     let rad_manager_addr = RadManager::default().start();
     System::current().registry().set(rad_manager_addr);
 
+    // Start WebhookNotifier actor
+    let webhook_notifier_addr = WebhookNotifier::default().start();
+    System::current().registry().set(webhook_notifier_addr);
+
     // Start JSON RPC server
     let json_rpc_server_addr = JsonRpcServer::default().start();
     System::current().registry().set(json_rpc_server_addr);
@@ -31,6 +31,16 @@ impl Actor for PeersManager {
                 // Get bucketing update period
                 act.bucketing_update_period = config.connections.bucketing_update_period;
 
+                // Get bucketing aging period
+                act.bucketing_aging_period = config.connections.bucketing_aging_period;
+
+                // Get retry backoff after failed outbound connection attempts
+                act.retry_backoff_base = config.connections.retry_backoff_base;
+                act.retry_backoff_max = config.connections.retry_backoff_max;
+
+                // Get feeler peers period
+                let feeler_peers_period = config.connections.feeler_peers_period;
+
                 // Get handshake time_out
                 act.handshake_timeout = config.connections.handshake_timeout;
 
@@ -44,7 +54,7 @@ impl Actor for PeersManager {
                     Err(e) => error!("Error when adding peer addresses from config: {}", e),
                 }
 
-                storage_mngr::get::<_, Peers>(&PEERS_KEY)
+                storage_mngr::get_sealed::<_, Peers>(&PEERS_KEY)
                     .into_actor(act)
                     .map_err(|e, _, _| error!("Couldn't get peers from storage: {}", e))
                     .and_then(move |peers_from_storage, act, _| {
@@ -62,6 +72,9 @@ impl Actor for PeersManager {
                 // Start the storage peers process on SessionsManager start
                 act.persist_peers(ctx, storage_peers_period);
 
+                // Start the feeler peers process
+                act.feeler_peers(ctx, feeler_peers_period);
+
                 fut::ok(())
             })
             .map_err(|err, _, _| log::error!("Peer discovery failed: {}", err))
@@ -3,9 +3,11 @@ use log;
 
 use super::PeersManager;
 use crate::actors::messages::{
-    AddConsolidatedPeer, AddPeers, GetRandomPeer, PeersSocketAddrResult, PeersSocketAddrsResult,
-    RemovePeers, RequestPeers,
+    AddConsolidatedPeer, AddPeers, GetKnownPeersStats, GetRandomPeer, OutboundConnectionFailed,
+    PeersSocketAddrResult, PeersSocketAddrsResult, RemoveOtherNetworkPeer, RemovePeers,
+    RequestPeers,
 };
+use witnet_p2p::{peers::PeersStats, sessions::SessionType};
 use witnet_util::timestamp::get_timestamp;
 
 /// Handler for AddPeers message
@@ -30,6 +32,7 @@ impl Handler<AddConsolidatedPeer> for PeersManager {
             msg.address
         );
         let current_ts = get_timestamp();
+        self.tried_feelers.remove(&msg.address);
 
         let index = self.peers.tried_bucket_index(&msg.address);
         match self.peers.tried_bucket_get_timestamp(index) {
@@ -53,6 +56,47 @@ impl Handler<RemovePeers> for PeersManager {
     }
 }
 
+/// Handler for RemoveOtherNetworkPeer message
+impl Handler<RemoveOtherNetworkPeer> for PeersManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: RemoveOtherNetworkPeer, _: &mut Context<Self>) -> Self::Result {
+        let address = match msg.session_type {
+            SessionType::Outbound => Some(msg.address),
+            SessionType::Inbound => None,
+        };
+        if self.peers.remove_other_network(address) {
+            log::debug!(
+                "Removed the address of a peer of another network: {}",
+                msg.address
+            );
+        }
+    }
+}
+
+/// Handler for OutboundConnectionFailed message
+impl Handler<OutboundConnectionFailed> for PeersManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: OutboundConnectionFailed, _: &mut Context<Self>) -> Self::Result {
+        let current_ts = get_timestamp();
+        if let Some(retry_after) = self.peers.record_failed_attempt(
+            &msg.address,
+            current_ts,
+            self.retry_backoff_base.as_secs() as i64,
+            self.retry_backoff_max.as_secs() as i64,
+        ) {
+            log::debug!(
+                "Failed to connect to {} ({} consecutive failures), retrying in {} seconds",
+                msg.address,
+                self.peers.failed_attempts(&msg.address),
+                retry_after - current_ts
+            );
+        }
+        self.tried_feeler_failed(msg.address, current_ts);
+    }
+}
+
 /// Handler for GetRandomPeer message
 impl Handler<GetRandomPeer> for PeersManager {
     type Result = PeersSocketAddrResult;
@@ -86,3 +130,12 @@ impl Handler<RequestPeers> for PeersManager {
         self.peers.get_all_from_tried()
     }
 }
+
+/// Handler for GetKnownPeersStats message
+impl Handler<GetKnownPeersStats> for PeersManager {
+    type Result = Result<PeersStats, failure::Error>;
+
+    fn handle(&mut self, _msg: GetKnownPeersStats, _: &mut Context<Self>) -> Self::Result {
+        Ok(self.peers.stats())
+    }
+}
@@ -1,5 +1,5 @@
 use log;
-use std::{net::SocketAddr, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
 
 use actix::{
     prelude::*, ActorFuture, AsyncContext, Context, ContextFutureSpawner, Supervised,
@@ -8,7 +8,9 @@ use actix::{
 
 use crate::{
     actors::{
-        connections_manager::ConnectionsManager, messages::OutboundTcpConnect,
+        connections_manager::ConnectionsManager,
+        messages::{FeelerConnect, OutboundTcpConnect},
+        sessions_manager::SessionsManager,
         storage_keys::PEERS_KEY,
     },
     storage_mngr,
@@ -39,8 +41,17 @@ pub struct PeersManager {
     peers: Peers,
     /// Period to consider if a peer is updated
     pub bucketing_update_period: i64,
+    /// Period after which a peer that has not been seen is demoted or dropped
+    pub bucketing_aging_period: i64,
     /// Timeout for handshake
     pub handshake_timeout: Duration,
+    /// Backoff after a failed outbound connection attempt, doubled with every consecutive failure
+    pub retry_backoff_base: Duration,
+    /// Maximum backoff after failed outbound connection attempts
+    pub retry_backoff_max: Duration,
+    /// Pending feeler connections to addresses of the tried bucket, along with the timestamp at
+    /// which they were started
+    tried_feelers: HashMap<SocketAddr, i64>,
 }
 
 impl PeersManager {
@@ -48,7 +59,15 @@ impl PeersManager {
     fn persist_peers(&self, ctx: &mut Context<Self>, storage_peers_period: Duration) {
         // Schedule the discovery_peers with a given period
         ctx.run_later(storage_peers_period, move |act, ctx| {
-            storage_mngr::put(&PEERS_KEY, &act.peers)
+            // Drop the new peers that have not been seen for a long time
+            let dropped = act
+                .peers
+                .age_new_bucket(get_timestamp(), act.bucketing_aging_period);
+            if !dropped.is_empty() {
+                log::debug!("Dropped peers from new bucket: {:?}", dropped);
+            }
+
+            storage_mngr::put_sealed(&PEERS_KEY, &act.peers)
                 .into_actor(act)
                 .and_then(|_, _, _| {
                     log::debug!("PeersManager successfully persisted peers to storage");
@@ -63,6 +82,54 @@ impl PeersManager {
         });
     }
 
+    /// Method to periodically test the oldest peer of the tried addresses bucket, which is
+    /// demoted to the new addresses bucket if the feeler connection fails, and a random peer of the
+    /// new addresses bucket, which is promoted to the tried addresses bucket if the handshake
+    /// succeeds
+    fn feeler_peers(&self, ctx: &mut Context<Self>, feeler_peers_period: Duration) {
+        ctx.run_later(feeler_peers_period, move |act, ctx| {
+            let current_ts = get_timestamp();
+            let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+            // Feelers which did not finish after twice the handshake timeout are forgotten, as
+            // the SessionsManager does
+            let max_age = 2 * act.handshake_timeout.as_secs() as i64;
+            act.tried_feelers
+                .retain(|_, started_at| current_ts - *started_at <= max_age);
+
+            if let Some(address) = act
+                .peers
+                .get_oldest_tried(current_ts, act.bucketing_update_period)
+            {
+                // The peer is consolidated again into the tried addresses bucket if the feeler
+                // connection succeeds, or demoted to the new addresses bucket if it fails
+                act.tried_feelers.insert(address, current_ts);
+                sessions_manager_addr.do_send(FeelerConnect { address });
+            }
+
+            if let Some(address) = act.peers.get_random_new(current_ts) {
+                sessions_manager_addr.do_send(FeelerConnect { address });
+            }
+
+            act.feeler_peers(ctx, feeler_peers_period);
+        });
+    }
+
+    /// Demote an address of the tried addresses bucket if there was a feeler connection to it in
+    /// progress, which has failed
+    fn tried_feeler_failed(&mut self, address: SocketAddr, current_ts: i64) {
+        if self.tried_feelers.remove(&address).is_none() {
+            return;
+        }
+
+        if let Some(address) = self.peers.demote_from_tried(&address, current_ts) {
+            log::debug!(
+                "Demoted peer {} from tried to new bucket after a failed feeler connection",
+                address
+            );
+        }
+    }
+
     fn import_peers(
         &mut self,
         peers: Peers,
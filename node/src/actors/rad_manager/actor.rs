@@ -1,15 +1,36 @@
-use super::RadManager;
-use actix::{Actor, Context, Supervised, SystemService};
+use super::{DataSourcesStats, RadManager};
+use actix::prelude::*;
 use log;
 
+use crate::actors::storage_keys::DATA_SOURCE_STATS_KEY;
+use crate::storage_mngr;
+
 /// Implement Actor trait for `RadManager`
 impl Actor for RadManager {
     /// Every actor has to provide execution `Context` in which it can run
     type Context = Context<Self>;
 
     /// Method to be executed when the actor is started
-    fn started(&mut self, _ctx: &mut Self::Context) {
+    fn started(&mut self, ctx: &mut Self::Context) {
         log::debug!("RadManager actor has been started!");
+
+        storage_mngr::get::<_, DataSourcesStats>(&DATA_SOURCE_STATS_KEY)
+            .into_actor(self)
+            .map_err(|e, _, _| log::error!("Couldn't get data source stats from storage: {}", e))
+            .and_then(|stats_from_storage, act, _| {
+                // The statistics recorded since the actor started are added to the stored ones
+                if let Some(stats_from_storage) = stats_from_storage {
+                    for (domain, stats) in stats_from_storage {
+                        act.data_source_stats
+                            .entry(domain)
+                            .or_default()
+                            .merge(&stats);
+                    }
+                }
+
+                fut::ok(())
+            })
+            .wait(ctx);
     }
 }
 
@@ -1,30 +1,80 @@
 //! Message handlers for `RadManager`
 use actix::{Handler, Message};
-use std::convert::TryFrom;
+use std::{cell::RefCell, collections::BTreeMap, convert::TryFrom, time::Instant};
 use witnet_rad as rad;
 use witnet_rad::types::RadonTypes;
 
-use super::RadManager;
-use crate::actors::messages::{ResolveRA, RunConsensus};
+use super::{deviation, source_domain, RadManager};
+use crate::actors::messages::{DataRequestTallied, GetDataSourceStats, ResolveRA, RunConsensus};
 
 impl Handler<ResolveRA> for RadManager {
     type Result = <ResolveRA as Message>::Result;
 
-    fn handle(&mut self, msg: ResolveRA, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: ResolveRA, ctx: &mut Self::Context) -> Self::Result {
         let retrieve_scripts = msg.rad_request.retrieve;
         let aggregate_script = msg.rad_request.aggregate;
+        let http_policy = msg.http_policy;
 
+        let client = http_policy
+            .as_ref()
+            .map(|policy| policy.client())
+            .transpose()?;
+        let get = |url: &str| match (&http_policy, &client) {
+            (Some(policy), Some(client)) => policy.get(client, url),
+            _ => rad::http::get_with_defaults(url),
+        };
+
+        let mut source_results = vec![];
         let retrieve_responses = retrieve_scripts
             .iter()
             .filter_map(|retrieve| {
-                rad::run_retrieval(retrieve)
-                    .map_err(|err| {
+                // Every request to a source, and the last source that returned a response
+                let attempts = RefCell::new(vec![]);
+                let last_response = RefCell::new(None);
+                let result = rad::run_retrieval_with_sources(retrieve, |url| {
+                    let start = Instant::now();
+                    let response = get(url);
+                    let latency_ms = start.elapsed().as_millis() as u64;
+                    let domain = source_domain(url);
+                    attempts
+                        .borrow_mut()
+                        .push((domain.clone(), response.is_ok(), latency_ms));
+                    if response.is_ok() {
+                        *last_response.borrow_mut() = Some(domain);
+                    }
+
+                    response
+                });
+
+                for (domain, success, latency_ms) in attempts.into_inner() {
+                    self.data_source_stats
+                        .entry(domain)
+                        .or_default()
+                        .record_retrieval(success, latency_ms);
+                }
+
+                match result {
+                    Ok(value) => {
+                        if let Some(domain) = last_response.into_inner() {
+                            source_results.push((domain, value.clone()));
+                        }
+
+                        Some(value)
+                    }
+                    Err(err) => {
                         log::error!("{:?}", err);
-                    })
-                    .ok()
+
+                        None
+                    }
+                }
             })
             .collect();
 
+        if !source_results.is_empty() {
+            self.pending_results.insert(msg.dr_pointer, source_results);
+        }
+        self.persist_data_source_stats(ctx);
+
         rad::run_aggregation(retrieve_responses, &aggregate_script)
     }
 }
@@ -44,3 +94,43 @@ impl Handler<RunConsensus> for RadManager {
         rad::run_consensus(radon_types_vec, &packed_script)
     }
 }
+
+impl Handler<DataRequestTallied> for RadManager {
+    type Result = <DataRequestTallied as Message>::Result;
+
+    fn handle(&mut self, msg: DataRequestTallied, ctx: &mut Self::Context) -> Self::Result {
+        // Only the data requests resolved by this node have results to compare
+        let source_results = match self.pending_results.remove(&msg.dr_pointer) {
+            Some(source_results) => source_results,
+            None => return,
+        };
+
+        let tally = match RadonTypes::try_from(msg.tally.as_slice()) {
+            Ok(tally) => tally,
+            Err(e) => {
+                log::debug!("Couldn't decode the tally of {}: {}", msg.dr_pointer, e);
+                return;
+            }
+        };
+
+        for (domain, result) in source_results {
+            self.data_source_stats
+                .entry(domain)
+                .or_default()
+                .record_tally(deviation(&result, &tally));
+        }
+        self.persist_data_source_stats(ctx);
+    }
+}
+
+impl Handler<GetDataSourceStats> for RadManager {
+    type Result = <GetDataSourceStats as Message>::Result;
+
+    fn handle(&mut self, _msg: GetDataSourceStats, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(self
+            .data_source_stats
+            .iter()
+            .map(|(domain, stats)| (domain.clone(), stats.report()))
+            .collect::<BTreeMap<_, _>>())
+    }
+}
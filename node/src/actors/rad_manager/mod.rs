@@ -6,9 +6,350 @@
 //! [Data Requests]: https://docs.witnet.io/protocol/data-requests/overview/
 //! [RAD Engine]: https://docs.witnet.io/protocol/data-requests/overview/#the-rad-engine
 
+use std::collections::{BTreeMap, HashMap};
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use witnet_data_structures::chain::Hash;
+use witnet_rad::types::{RadonType, RadonTypes};
+
+use crate::{actors::storage_keys::DATA_SOURCE_STATS_KEY, storage_mngr};
+
 mod actor;
 mod handlers;
 
+/// Maximum number of data requests whose source results are kept until their tally. Once
+/// reached, the results of the oldest data request are discarded, as it may never be tallied
+const MAX_PENDING_RESULTS: usize = 1_000;
+
 /// RadManager actor
 #[derive(Default)]
-pub struct RadManager;
+pub struct RadManager {
+    /// Reliability statistics of the sources of the retrievals, by domain
+    data_source_stats: DataSourcesStats,
+    /// Results of the sources used to resolve the data requests witnessed by this node, waiting
+    /// for their tally
+    pending_results: PendingResults,
+}
+
+/// Results of the sources used to resolve data requests, by data request, along with the order
+/// in which they were resolved
+#[derive(Debug, Default)]
+struct PendingResults {
+    results: HashMap<Hash, (u64, Vec<(String, RadonTypes)>)>,
+    resolved: u64,
+}
+
+impl PendingResults {
+    /// Keep the results of the sources of a data request, discarding the results of the oldest
+    /// data request if there are `MAX_PENDING_RESULTS` already
+    fn insert(&mut self, dr_pointer: Hash, source_results: Vec<(String, RadonTypes)>) {
+        if self.results.len() >= MAX_PENDING_RESULTS && !self.results.contains_key(&dr_pointer) {
+            let oldest = self
+                .results
+                .iter()
+                .min_by_key(|(_, (resolved, _))| *resolved)
+                .map(|(dr_pointer, _)| *dr_pointer);
+            if let Some(oldest) = oldest {
+                log::debug!(
+                    "Too many data requests waiting for their tally, the deviation of the sources \
+                     of {} will not be tracked",
+                    oldest
+                );
+                self.results.remove(&oldest);
+            }
+        }
+        self.resolved += 1;
+        self.results
+            .insert(dr_pointer, (self.resolved, source_results));
+    }
+
+    /// Take the results of the sources of a data request
+    fn remove(&mut self, dr_pointer: &Hash) -> Option<Vec<(String, RadonTypes)>> {
+        self.results
+            .remove(dr_pointer)
+            .map(|(_, source_results)| source_results)
+    }
+}
+
+impl RadManager {
+    /// Persist the reliability statistics of the sources into storage
+    fn persist_data_source_stats(&self, ctx: &mut Context<Self>) {
+        storage_mngr::put(&DATA_SOURCE_STATS_KEY, &self.data_source_stats)
+            .into_actor(self)
+            .map_err(|e, _, _| log::error!("Couldn't persist data source stats: {}", e))
+            .spawn(ctx);
+    }
+}
+
+/// Reliability statistics of the sources of the retrievals, by domain
+pub type DataSourcesStats = BTreeMap<String, DataSourceStats>;
+
+/// Reliability statistics of the source of the retrievals of a domain
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataSourceStats {
+    /// Number of requests to the source
+    pub retrievals: u64,
+    /// Number of requests to the source that failed
+    pub failures: u64,
+    /// Sum of the latencies of the successful requests, in milliseconds
+    pub total_latency_ms: u64,
+    /// Number of results of the source compared to the tally of their data request
+    pub tallied: u64,
+    /// Sum of the relative deviations of the results of the source from the tally of their data
+    /// request
+    pub total_deviation: f64,
+}
+
+impl DataSourceStats {
+    /// Record a request to the source
+    pub fn record_retrieval(&mut self, success: bool, latency_ms: u64) {
+        self.retrievals += 1;
+        if success {
+            self.total_latency_ms = self.total_latency_ms.saturating_add(latency_ms);
+        } else {
+            self.failures += 1;
+        }
+    }
+
+    /// Record how much a result of the source deviated from the tally of its data request.
+    /// Deviations that are not finite are ignored, as they would poison the average
+    pub fn record_tally(&mut self, deviation: f64) {
+        if deviation.is_finite() {
+            self.tallied += 1;
+            self.total_deviation += deviation;
+        }
+    }
+
+    /// Add the statistics of another instance, e.g. the ones loaded from storage. The deviations
+    /// of the other instance are ignored if they are not finite
+    pub fn merge(&mut self, other: &DataSourceStats) {
+        self.retrievals += other.retrievals;
+        self.failures += other.failures;
+        self.total_latency_ms = self.total_latency_ms.saturating_add(other.total_latency_ms);
+        if other.total_deviation.is_finite() {
+            self.tallied += other.tallied;
+            self.total_deviation += other.total_deviation;
+        }
+    }
+
+    /// Summary of the statistics, as returned by `getDataSourceStats`
+    pub fn report(&self) -> DataSourceReport {
+        let successes = self.retrievals - self.failures;
+
+        DataSourceReport {
+            retrievals: self.retrievals,
+            failures: self.failures,
+            success_rate: ratio(successes as f64, self.retrievals),
+            average_latency_ms: ratio(self.total_latency_ms as f64, successes),
+            tallied: self.tallied,
+            average_deviation: ratio(self.total_deviation, self.tallied),
+        }
+    }
+}
+
+/// Summary of the reliability statistics of a source, as returned by `getDataSourceStats`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataSourceReport {
+    pub retrievals: u64,
+    pub failures: u64,
+    /// Share of the requests that succeeded, `null` if there were no requests
+    pub success_rate: Option<f64>,
+    /// Average latency of the successful requests, in milliseconds
+    pub average_latency_ms: Option<f64>,
+    pub tallied: u64,
+    /// Average relative deviation of the results from the tally of their data requests
+    pub average_deviation: Option<f64>,
+}
+
+fn ratio(sum: f64, count: u64) -> Option<f64> {
+    if count == 0 {
+        None
+    } else {
+        Some(sum / count as f64)
+    }
+}
+
+/// Domain of the url of a source, used to group its statistics
+pub fn source_domain(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Relative deviation of the result of a source from the tally of its data request: the relative
+/// difference for numbers, or `0` if they are equal and `1` otherwise for the rest of types and
+/// for the numbers whose relative difference is not finite, such as `NaN`
+pub fn deviation(result: &RadonTypes, tally: &RadonTypes) -> f64 {
+    let as_f64 = |value: &RadonTypes| match value {
+        RadonTypes::Float(float) => Some(float.value()),
+        RadonTypes::Integer(integer) => Some(integer.value() as f64),
+        _ => None,
+    };
+
+    match (as_f64(result), as_f64(tally)) {
+        (Some(result), Some(tally)) => {
+            let difference = (result - tally).abs();
+            let deviation = if difference < std::f64::EPSILON {
+                0.0
+            } else if tally.abs() < std::f64::EPSILON {
+                1.0
+            } else {
+                difference / tally.abs()
+            };
+            if deviation.is_finite() {
+                deviation
+            } else if result == tally {
+                0.0
+            } else {
+                1.0
+            }
+        }
+        _ if result == tally => 0.0,
+        _ => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use witnet_rad::types::{float::RadonFloat, integer::RadonInteger, string::RadonString};
+
+    use super::*;
+
+    #[test]
+    fn deviation_from_tally() {
+        let float = |x: f64| RadonTypes::from(RadonFloat::from(x));
+        let string = |x: &str| RadonTypes::from(RadonString::from(x));
+        let assert_deviation = |result: RadonTypes, tally: RadonTypes, expected: f64| {
+            let deviation = deviation(&result, &tally);
+            assert!(
+                (deviation - expected).abs() < 1e-9,
+                "{} != {}",
+                deviation,
+                expected
+            );
+        };
+
+        assert_deviation(float(100.0), float(100.0), 0.0);
+        assert_deviation(float(90.0), float(100.0), 0.1);
+        assert_deviation(float(110.0), float(100.0), 0.1);
+        assert_deviation(RadonTypes::from(RadonInteger::from(50)), float(100.0), 0.5);
+        assert_deviation(float(1.0), float(0.0), 1.0);
+        assert_deviation(string("a"), string("a"), 0.0);
+        assert_deviation(string("a"), string("b"), 1.0);
+        assert_deviation(string("1"), float(1.0), 1.0);
+        assert_deviation(float(std::f64::NAN), float(100.0), 1.0);
+        assert_deviation(float(100.0), float(std::f64::NAN), 1.0);
+        assert_deviation(float(std::f64::NAN), float(std::f64::NAN), 1.0);
+        assert_deviation(float(std::f64::INFINITY), float(100.0), 1.0);
+        assert_deviation(float(std::f64::INFINITY), float(std::f64::INFINITY), 0.0);
+        assert_deviation(float(std::f64::MAX), float(-std::f64::MAX), 1.0);
+    }
+
+    #[test]
+    fn non_finite_deviations_are_ignored() {
+        let mut stats = DataSourceStats::default();
+        stats.record_tally(0.5);
+        stats.record_tally(std::f64::NAN);
+        stats.record_tally(std::f64::INFINITY);
+        assert_eq!(stats.report().tallied, 1);
+        assert_eq!(stats.report().average_deviation, Some(0.5));
+
+        // Statistics poisoned by a NaN, e.g. stored by a previous version, are ignored
+        let poisoned = DataSourceStats {
+            retrievals: 2,
+            tallied: 2,
+            total_deviation: std::f64::NAN,
+            ..DataSourceStats::default()
+        };
+        stats.merge(&poisoned);
+        assert_eq!(stats.report().retrievals, 2);
+        assert_eq!(stats.report().tallied, 1);
+        assert_eq!(stats.report().average_deviation, Some(0.5));
+    }
+
+    #[test]
+    fn pending_results_are_bounded() {
+        let dr_pointer = |i: usize| {
+            let mut bytes = [0; 32];
+            bytes[..8].copy_from_slice(&(i as u64).to_le_bytes());
+            Hash::SHA256(bytes)
+        };
+        let result = |i: usize| {
+            vec![(
+                i.to_string(),
+                RadonTypes::from(RadonInteger::from(i as i128)),
+            )]
+        };
+        let mut pending_results = PendingResults::default();
+        for i in 0..MAX_PENDING_RESULTS {
+            pending_results.insert(dr_pointer(i), result(i));
+        }
+        assert_eq!(pending_results.remove(&dr_pointer(1)), Some(result(1)));
+        assert_eq!(pending_results.remove(&dr_pointer(1)), None);
+
+        // There is room for one more, and then the oldest one is discarded
+        pending_results.insert(dr_pointer(MAX_PENDING_RESULTS), result(MAX_PENDING_RESULTS));
+        pending_results.insert(
+            dr_pointer(MAX_PENDING_RESULTS + 1),
+            result(MAX_PENDING_RESULTS + 1),
+        );
+        assert_eq!(pending_results.results.len(), MAX_PENDING_RESULTS);
+        assert_eq!(pending_results.remove(&dr_pointer(0)), None);
+        assert_eq!(pending_results.remove(&dr_pointer(2)), Some(result(2)));
+        assert_eq!(
+            pending_results.remove(&dr_pointer(MAX_PENDING_RESULTS + 1)),
+            Some(result(MAX_PENDING_RESULTS + 1))
+        );
+    }
+
+    #[test]
+    fn data_source_report() {
+        let mut stats = DataSourceStats::default();
+        assert_eq!(
+            stats.report(),
+            DataSourceReport {
+                retrievals: 0,
+                failures: 0,
+                success_rate: None,
+                average_latency_ms: None,
+                tallied: 0,
+                average_deviation: None,
+            }
+        );
+
+        stats.record_retrieval(true, 100);
+        stats.record_retrieval(true, 300);
+        stats.record_retrieval(false, 5000);
+        stats.record_retrieval(true, 200);
+        stats.record_tally(0.5);
+        stats.record_tally(0.0);
+        let mut merged = stats.clone();
+        merged.merge(&stats);
+
+        assert_eq!(
+            merged.report(),
+            DataSourceReport {
+                retrievals: 8,
+                failures: 2,
+                success_rate: Some(0.75),
+                average_latency_ms: Some(200.0),
+                tallied: 4,
+                average_deviation: Some(0.25),
+            }
+        );
+    }
+
+    #[test]
+    fn source_domains() {
+        assert_eq!(
+            source_domain("https://api.coindesk.com/v1/bpi/currentprice.json"),
+            "api.coindesk.com"
+        );
+        assert_eq!(source_domain("http://127.0.0.1:8080/"), "127.0.0.1");
+        assert_eq!(source_domain("not a url"), "not a url");
+    }
+}
@@ -1,18 +1,21 @@
 use actix::{
-    Actor, ActorContext, ActorFuture, AsyncContext, Context, ContextFutureSpawner, Running, System,
-    WrapFuture,
+    Actor, ActorFuture, AsyncContext, Context, ContextFutureSpawner, Running, System, WrapFuture,
 };
 use log::{debug, error, info, warn};
+use std::time::Instant;
 
-use witnet_data_structures::types::Message as WitnetMessage;
+use witnet_data_structures::{
+    builders::KEEPALIVE_PROTOCOL_VERSION,
+    types::{Command, Message as WitnetMessage, Ping},
+};
 use witnet_p2p::sessions::{SessionStatus, SessionType};
 
 use super::{handlers::EveryEpochPayload, Session};
 use crate::actors::{
-    chain_manager::ChainManager,
     epoch_manager::{EpochManager, EpochManagerError::CheckpointZeroInTheFuture},
-    messages::{AddBlocks, GetEpoch, Register, Subscribe, Unregister},
+    messages::{AddBlocks, DisconnectReason, GetEpoch, Register, Subscribe, Unregister},
     sessions_manager::SessionsManager,
+    sync_manager::SyncManager,
 };
 use witnet_util::timestamp::pretty_print;
 
@@ -31,10 +34,13 @@ impl Actor for Session {
                     act.remote_addr
                 );
 
-                ctx.stop();
+                act.disconnect(ctx, DisconnectReason::HandshakeTimeout);
             }
         });
 
+        // Periodically ping the peer to detect silently dropped connections
+        ctx.run_interval(self.ping_interval, |act, ctx| act.keepalive(ctx));
+
         self.subscribe_to_epoch_manager(ctx);
 
         // Get SessionsManager address
@@ -64,7 +70,7 @@ impl Actor for Session {
                         error!("Session register into Session Manager failed");
                         // FIXME(#72): a full stop of the session is not correct (unregister should
                         // be skipped)
-                        ctx.stop();
+                        act.disconnect(ctx, DisconnectReason::Rejected);
 
                         actix::fut::err(())
                     }
@@ -76,11 +82,13 @@ impl Actor for Session {
                     // FIXME(#142): include the checkpoint of the current tip of the local blockchain
                     let version_msg = WitnetMessage::build_version(
                         act.magic_number,
+                        &act.network_id,
                         act.server_addr,
                         act.remote_addr,
                         0,
                     );
                     act.send_message(version_msg);
+                    act.version_sent_at = Some(Instant::now());
                     // Set HandshakeFlag of sent version message
                     act.handshake_flags.version_tx = true;
                 }
@@ -100,14 +108,15 @@ impl Actor for Session {
             address: self.remote_addr,
             session_type: self.session_type,
             status: self.status,
+            reason: self.disconnect_reason,
         });
 
-        // When session unregisters, notify ChainManager to stop waiting for new blocks
+        // When session unregisters, notify SyncManager to stop waiting for new blocks
         if self.blocks_timestamp != 0 {
-            // Get ChainManager address
-            let chain_manager_addr = System::current().registry().get::<ChainManager>();
+            // Get SyncManager address
+            let sync_manager_addr = System::current().registry().get::<SyncManager>();
 
-            chain_manager_addr.do_send(AddBlocks { blocks: vec![] });
+            sync_manager_addr.do_send(AddBlocks { blocks: vec![] });
             warn!("Session disconnected during block exchange");
         }
 
@@ -116,6 +125,40 @@ impl Actor for Session {
 }
 
 impl Session {
+    /// Send a keepalive ping to the peer and stop the session if the pong does not arrive in time
+    fn keepalive(&mut self, ctx: &mut Context<Session>) {
+        // Pings are only exchanged once the handshake is completed, and only one at a time
+        if self.status != SessionStatus::Consolidated || self.pending_ping.is_some() {
+            return;
+        }
+        // Peers running a protocol version without keepalive would not answer the pings
+        if !self
+            .peer_version
+            .map_or(false, |version| version >= KEEPALIVE_PROTOCOL_VERSION)
+        {
+            return;
+        }
+
+        let ping_msg = WitnetMessage::build_ping(self.magic_number);
+        if let Command::Ping(Ping { nonce }) = ping_msg.kind {
+            self.pending_ping = Some((nonce, Instant::now()));
+
+            // Stop the session if this ping is still waiting for a pong after the timeout
+            ctx.run_later(self.ping_timeout, move |act, ctx| match act.pending_ping {
+                Some((pending_nonce, _)) if pending_nonce == nonce => {
+                    info!(
+                        "Keepalive timeout expired, disconnecting session with peer {:?}",
+                        act.remote_addr
+                    );
+
+                    act.disconnect(ctx, DisconnectReason::KeepaliveTimeout);
+                }
+                _ => {}
+            });
+        }
+        self.send_message(ping_msg);
+    }
+
     /// Get epoch from EpochManager and subscribe to future epochs
     fn subscribe_to_epoch_manager(&mut self, ctx: &mut Context<Session>) {
         // Get EpochManager address from registry
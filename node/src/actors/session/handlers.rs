@@ -1,12 +1,12 @@
 use std::io::Error;
 
 use actix::io::WriteHandler;
+use actix::prelude::SendError;
 use actix::{
-    ActorContext, ActorFuture, Context, ContextFutureSpawner, Handler, StreamHandler, System,
+    ActorFuture, AsyncContext, Context, ContextFutureSpawner, Handler, StreamHandler, System,
     SystemService, WrapFuture,
 };
 use ansi_term::Color::Green;
-use futures::future;
 use log::{debug, error, info, trace, warn};
 
 use witnet_data_structures::{
@@ -16,27 +16,36 @@ use witnet_data_structures::{
     transaction::Transaction,
     types::{
         Address, Command, InventoryAnnouncement, InventoryRequest, LastBeacon,
-        Message as WitnetMessage, Peers, Version,
+        Message as WitnetMessage, Peers, Ping, Pong, Version,
     },
 };
-use witnet_p2p::sessions::{SessionStatus, SessionType};
+use witnet_p2p::{
+    error::SessionsError,
+    sessions::{SessionStatus, SessionType},
+};
 
-use super::Session;
+use super::{Session, MAX_SEEN_CANDIDATES};
 use crate::actors::{
     chain_manager::ChainManager,
     codec::BytesMut,
     inventory_manager::InventoryManager,
+    mailbox,
     messages::{
         AddBlocks, AddCandidates, AddPeers, AddTransaction, CloseSession, Consolidate,
-        EpochNotification, GetBlocksEpochRange, GetHighestCheckpointBeacon, GetItem, PeerBeacon,
-        RequestPeers, SendGetPeers, SendInventoryAnnouncement, SendInventoryItem, SendLastBeacon,
-        SessionUnitResult,
+        DisconnectReason, EpochNotification, GetBlocksEpochRange, GetHighestCheckpointBeacon,
+        PeerBeacon, PeerLatency, RemoveOtherNetworkPeer, RequestPeers, SendGetPeers,
+        SendInventoryAnnouncement, SendInventoryItem, SendLastBeacon, ServeItem, SessionUnitResult,
     },
     peers_manager::PeersManager,
     sessions_manager::SessionsManager,
+    sync_manager::SyncManager,
+};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
 };
-use std::net::SocketAddr;
 use witnet_util::timestamp::get_timestamp;
+use witnet_validations::validations::pre_validate_candidate;
 
 /// Implement WriteHandler for Session
 impl WriteHandler<Error> for Session {}
@@ -65,15 +74,16 @@ impl Handler<EpochNotification<EveryEpochPayload>> for Session {
     fn handle(&mut self, msg: EpochNotification<EveryEpochPayload>, ctx: &mut Context<Self>) {
         debug!("Periodic epoch notification received {:?}", msg.checkpoint);
         self.current_epoch = Some(msg.checkpoint);
+        self.seen_candidates.clear();
 
         let now = get_timestamp();
         if self.blocks_timestamp != 0 && now - self.blocks_timestamp > self.blocks_timeout {
-            // Get ChainManager address
-            let chain_manager_addr = System::current().registry().get::<ChainManager>();
+            // Get SyncManager address
+            let sync_manager_addr = System::current().registry().get::<SyncManager>();
 
-            chain_manager_addr.do_send(AddBlocks { blocks: vec![] });
+            sync_manager_addr.do_send(AddBlocks { blocks: vec![] });
             warn!("Timeout for waiting blocks achieved");
-            ctx.stop();
+            self.disconnect(ctx, DisconnectReason::BlocksTimeout);
         }
     }
 }
@@ -94,6 +104,33 @@ impl StreamHandler<BytesMut, Error> for Session {
                 );
                 trace!("\t{:?}", msg);
 
+                // Network validation between nodes, checked before the magic number so that the
+                // peers of other networks are reported as such. Peers which do not advertise their
+                // network are only checked by the magic number
+                if let Some(peer_network_id) = match &msg.kind {
+                    Command::Version(version) => version.network_id(),
+                    _ => None,
+                } {
+                    if peer_network_id != self.network_id {
+                        error!(
+                            "Refusing to connect to peer {}: it belongs to network {}, \
+                             but this node belongs to network {}",
+                            self.remote_addr, peer_network_id, self.network_id
+                        );
+
+                        // Forget the peer so that the node does not connect to it again
+                        let peers_manager_addr = System::current().registry().get::<PeersManager>();
+                        peers_manager_addr.do_send(RemoveOtherNetworkPeer {
+                            address: self.remote_addr,
+                            session_type: self.session_type,
+                        });
+
+                        // Stop this session
+                        self.disconnect(ctx, DisconnectReason::NetworkMismatch);
+                        return;
+                    }
+                }
+
                 // Consensus constants validation between nodes
                 if msg.magic != self.magic_number {
                     error!(
@@ -103,7 +140,7 @@ impl StreamHandler<BytesMut, Error> for Session {
                     );
 
                     // Stop this session
-                    ctx.stop();
+                    self.disconnect(ctx, DisconnectReason::ConsensusConstantsMismatch);
                     return;
                 }
 
@@ -115,9 +152,13 @@ impl StreamHandler<BytesMut, Error> for Session {
                     (
                         _,
                         SessionStatus::Unconsolidated,
-                        Command::Version(Version { sender_address, .. }),
+                        Command::Version(Version {
+                            version,
+                            sender_address,
+                            ..
+                        }),
                     ) => {
-                        let msgs = handshake_version(self, &sender_address);
+                        let msgs = handshake_version(self, version, &sender_address);
                         for msg in msgs {
                             self.send_message(msg);
                         }
@@ -141,6 +182,17 @@ impl StreamHandler<BytesMut, Error> for Session {
                     (_, SessionStatus::Consolidated, Command::Peers(Peers { peers })) => {
                         peer_discovery_peers(&peers, self.remote_addr);
                     }
+                    ///////////////
+                    // KEEPALIVE //
+                    ///////////////
+                    // Handle Ping message
+                    (_, SessionStatus::Consolidated, Command::Ping(Ping { nonce })) => {
+                        keepalive_ping(self, nonce);
+                    }
+                    // Handle Pong message
+                    (_, SessionStatus::Consolidated, Command::Pong(Pong { nonce })) => {
+                        keepalive_pong(self, nonce);
+                    }
                     ///////////////////////
                     // INVENTORY_REQUEST //
                     ///////////////////////
@@ -150,30 +202,32 @@ impl StreamHandler<BytesMut, Error> for Session {
                         Command::InventoryRequest(InventoryRequest { inventory }),
                     ) => {
                         let inventory_mngr = System::current().registry().get::<InventoryManager>();
-                        let item_requests: Vec<_> = inventory
-                            .iter()
-                            .filter_map(|item| match item {
-                                InventoryEntry::Block(hash) | InventoryEntry::Tx(hash) => {
-                                    Some(inventory_mngr.send(GetItem { hash: *hash }))
+                        let peer = ctx.address().recipient();
+                        for item in &inventory {
+                            let hash = match item {
+                                InventoryEntry::Block(hash) | InventoryEntry::Tx(hash) => *hash,
+                                _ => continue,
+                            };
+                            // The requests of the peers are the first messages to be dropped
+                            // when the InventoryManager is overloaded
+                            match inventory_mngr.try_send(ServeItem {
+                                hash,
+                                peer: peer.clone(),
+                            }) {
+                                Ok(()) => mailbox::INVENTORY_REQUESTS.queued(),
+                                Err(SendError::Full(_)) => {
+                                    mailbox::INVENTORY_REQUESTS.dropped();
+                                    debug!(
+                                        "Dropping inventory request {} from peer {:?}: \
+                                         InventoryManager mailbox is full",
+                                        hash, self.remote_addr
+                                    );
                                 }
-                                _ => None,
-                            })
-                            .collect();
-
-                        future::join_all(item_requests)
-                            .into_actor(self)
-                            .map_err(|e, _, _| error!("Inventory request error: {}", e))
-                            .and_then(|item_responses, session, _| {
-                                for item_response in item_responses {
-                                    match item_response {
-                                        Ok(item) => send_inventory_item_msg(session, item),
-                                        Err(e) => warn!("Inventory result is error: {}", e),
-                                    }
+                                Err(SendError::Closed(_)) => {
+                                    error!("InventoryManager mailbox is closed")
                                 }
-
-                                actix::fut::ok(())
-                            })
-                            .wait(ctx);
+                            }
+                        }
                     }
                     //////////////////////////
                     // TRANSACTION RECEIVED //
@@ -292,8 +346,8 @@ impl Handler<SendLastBeacon> for Session {
 impl Handler<CloseSession> for Session {
     type Result = SessionUnitResult;
 
-    fn handle(&mut self, _msg: CloseSession, ctx: &mut Context<Self>) {
-        ctx.stop();
+    fn handle(&mut self, msg: CloseSession, ctx: &mut Context<Self>) {
+        self.disconnect(ctx, msg.reason);
     }
 }
 
@@ -333,6 +387,15 @@ fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
 
                     actix::fut::ok(())
                 }
+                Ok(Err(ref error))
+                    if error.downcast_ref::<SessionsError>()
+                        == Some(&SessionsError::FeelerCompleted) =>
+                {
+                    debug!("Closing feeler session {:?}", act.remote_addr);
+                    act.disconnect(ctx, DisconnectReason::Feeler);
+
+                    actix::fut::err(())
+                }
                 _ => {
                     warn!(
                         "Failed to consolidate session {:?} in SessionManager",
@@ -340,7 +403,7 @@ fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
                     );
                     // FIXME(#72): a full stop of the session is not correct (unregister should
                     // be skipped)
-                    ctx.stop();
+                    act.disconnect(ctx, DisconnectReason::HandshakeFailure);
 
                     actix::fut::err(())
                 }
@@ -377,7 +440,7 @@ fn peer_discovery_get_peers(session: &mut Session, ctx: &mut Context<Session>) {
                     warn!("Failed to receive peer addresses from PeersManager");
                     // FIXME(#72): a full stop of the session is not correct (unregister should
                     // be skipped)
-                    ctx.stop();
+                    act.disconnect(ctx, DisconnectReason::InternalError);
                 }
             }
             actix::fut::ok(())
@@ -400,6 +463,43 @@ fn peer_discovery_peers(peers: &[Address], src_address: SocketAddr) {
     });
 }
 
+/// Function called when Ping message is received
+fn keepalive_ping(session: &mut Session, nonce: u64) {
+    // Answer with a pong carrying the same nonce
+    let pong_msg = WitnetMessage::build_pong(session.magic_number, nonce);
+    session.send_message(pong_msg);
+}
+
+/// Function called when Pong message is received
+fn keepalive_pong(session: &mut Session, nonce: u64) {
+    match session.pending_ping {
+        Some((pending_nonce, sent_at)) if pending_nonce == nonce => {
+            session.pending_ping = None;
+
+            // Report the round-trip time of the ping
+            update_latency(session, sent_at.elapsed());
+        }
+        _ => debug!(
+            "Ignoring unexpected pong with nonce {} from peer {:?}",
+            nonce, session.remote_addr
+        ),
+    }
+}
+
+/// Function to store the last round-trip time measured with the peer and report it to the
+/// SessionsManager, which prefers low-latency peers for time-critical requests
+fn update_latency(session: &mut Session, latency: Duration) {
+    session.latency = Some(latency);
+
+    // Get SessionsManager address
+    let sessions_manager_addr = System::current().registry().get::<SessionsManager>();
+
+    sessions_manager_addr.do_send(PeerLatency {
+        address: session.remote_addr,
+        latency,
+    });
+}
+
 /// Function called when Block message is received
 fn inventory_process_block(session: &mut Session, _ctx: &mut Context<Session>, block: Block) {
     // Get ChainManager address
@@ -409,11 +509,44 @@ fn inventory_process_block(session: &mut Session, _ctx: &mut Context<Session>, b
     let block_hash = block.hash();
 
     if Some(block_epoch) == session.current_epoch {
+        // Drop duplicated and obviously invalid candidates here, so a flood of candidates does
+        // not fill the mailbox of the ChainManager
+        if session.seen_candidates.contains(&block_hash) {
+            debug!(
+                "Dropping duplicated block candidate {} from peer {:?}",
+                block_hash, session.remote_addr
+            );
+            return;
+        }
+        if let Err(e) = pre_validate_candidate(&block, block_epoch) {
+            warn!(
+                "Dropping block candidate {} from peer {:?}: {}",
+                block_hash, session.remote_addr, e
+            );
+            return;
+        }
+        // Only candidates passing the pre-validation are recorded, so an invalid candidate does
+        // not shadow a valid one with the same hash
+        if session.seen_candidates.len() < MAX_SEEN_CANDIDATES {
+            session.seen_candidates.insert(block_hash);
+        }
+
         debug!("Send Candidate");
-        // Send a message to the ChainManager to try to add a new candidate
-        chain_manager_addr.do_send(AddCandidates {
+        // Send a message to the ChainManager to try to add a new candidate. Candidates are the
+        // first messages to be dropped when the ChainManager is overloaded
+        match chain_manager_addr.try_send(AddCandidates {
             blocks: vec![block],
-        });
+        }) {
+            Ok(()) => mailbox::CANDIDATES.queued(),
+            Err(SendError::Full(_)) => {
+                mailbox::CANDIDATES.dropped();
+                debug!(
+                    "Dropping block candidate {} from peer {:?}: ChainManager mailbox is full",
+                    block_hash, session.remote_addr
+                );
+            }
+            Err(SendError::Closed(_)) => error!("ChainManager mailbox is closed"),
+        }
     } else {
         // Add block to requested_blocks
         if session.requested_block_hashes.contains(&block_hash) {
@@ -432,17 +565,20 @@ fn inventory_process_block(session: &mut Session, _ctx: &mut Context<Session>, b
                     blocks_vector.push(block);
                 } else {
                     // As soon as there is a missing block, stop processing the other
-                    // blocks, send a empty message to the ChainManager and close the session
+                    // blocks and send an empty batch to the SyncManager
                     blocks_vector.clear();
-                    chain_manager_addr.do_send(AddBlocks { blocks: vec![] });
                     warn!("Unexpected missing block");
+                    break;
                 }
             }
 
-            // Send a message to the ChainManager to try to add a new block
-            chain_manager_addr.do_send(AddBlocks {
-                blocks: blocks_vector,
-            });
+            // Send the batch to the SyncManager, which forwards its blocks to the ChainManager
+            System::current()
+                .registry()
+                .get::<SyncManager>()
+                .do_send(AddBlocks {
+                    blocks: blocks_vector,
+                });
 
             // Clear requested block structures
             session.blocks_timestamp = 0;
@@ -502,10 +638,20 @@ fn handshake_verack(session: &mut Session) {
 
     // Set verack_rx flag
     flags.verack_rx = true;
+
+    // The verack answers our version message, so the time elapsed since sending it is a first
+    // measure of the latency with the peer
+    if let Some(sent_at) = session.version_sent_at.take() {
+        update_latency(session, sent_at.elapsed());
+    }
 }
 
 /// Function called when Version message is received
-fn handshake_version(session: &mut Session, sender_address: &Address) -> Vec<WitnetMessage> {
+fn handshake_version(
+    session: &mut Session,
+    version: u32,
+    sender_address: &Address,
+) -> Vec<WitnetMessage> {
     let flags = &mut session.handshake_flags;
 
     if flags.version_rx {
@@ -514,6 +660,7 @@ fn handshake_version(session: &mut Session, sender_address: &Address) -> Vec<Wit
 
     // Placeholder for version fields verification
     session.remote_sender_addr = Some(from_address(sender_address));
+    session.peer_version = Some(version);
 
     // Set version_rx flag, indicating reception of a version message from the peer
     flags.version_rx = true;
@@ -528,11 +675,13 @@ fn handshake_version(session: &mut Session, sender_address: &Address) -> Vec<Wit
         flags.version_tx = true;
         let version = WitnetMessage::build_version(
             session.magic_number,
+            &session.network_id,
             session.server_addr,
             session.remote_addr,
             0,
         );
         responses.push(version);
+        session.version_sent_at = Some(Instant::now());
     }
 
     responses
@@ -622,7 +771,7 @@ fn session_last_beacon_inbound(
                     warn!("Failed to get highest checkpoint beacon from ChainManager");
                     // FIXME(#72): a full stop of the session is not correct (unregister should
                     // be skipped)
-                    ctx.stop();
+                    act.disconnect(ctx, DisconnectReason::InternalError);
 
                     actix::fut::err(())
                 }
@@ -1,6 +1,10 @@
-use std::{collections::HashMap, net::SocketAddr, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
-use actix::io::FramedWrite;
+use actix::{io::FramedWrite, ActorContext, Context};
 
 use ansi_term::Color::Green;
 
@@ -11,17 +15,22 @@ use tokio::{io::WriteHalf, net::TcpStream};
 use witnet_data_structures::{
     chain::{Block, Hash},
     proto::ProtobufConvert,
-    types::Message as WitnetMessage,
+    types::{Message as WitnetMessage, NetworkId},
 };
 use witnet_p2p::sessions::{SessionStatus, SessionType};
 
-use crate::actors::codec::P2PCodec;
+use crate::actors::{codec::P2PCodec, messages::DisconnectReason};
 use witnet_data_structures::chain::Epoch;
 
 mod actor;
 
 mod handlers;
 
+/// Maximum number of block candidates recorded per epoch to drop the duplicated ones. Once
+/// reached, the candidates are no longer deduplicated, but the memory used by a peer flooding
+/// the session with candidates is bounded
+const MAX_SEEN_CANDIDATES: usize = 1_000;
+
 /// HandshakeFlags
 #[derive(Default)]
 struct HandshakeFlags {
@@ -72,9 +81,16 @@ pub struct Session {
     /// Magic number
     magic_number: u16,
 
+    /// Network of this node, which must match the one of the peer
+    network_id: NetworkId,
+
     /// Current epoch
     current_epoch: Option<Epoch>,
 
+    /// Hashes of the valid block candidates received from the peer during the current epoch,
+    /// cleared on every epoch
+    seen_candidates: HashSet<Hash>,
+
     /// Requested block hashes vector
     requested_block_hashes: Vec<Hash>,
 
@@ -86,11 +102,33 @@ pub struct Session {
 
     /// Timestamp for requested blocks
     blocks_timestamp: i64,
+
+    /// Period of the keepalive pings
+    ping_interval: Duration,
+
+    /// Timeout for receiving the pong of a keepalive ping
+    ping_timeout: Duration,
+
+    /// Nonce and sending time of the last keepalive ping still waiting for a pong
+    pending_ping: Option<(u64, Instant)>,
+
+    /// Protocol version advertised by the peer in the handshake
+    peer_version: Option<u32>,
+
+    /// Sending time of the version message, used to measure the latency during the handshake
+    version_sent_at: Option<Instant>,
+
+    /// Last round-trip time measured with the peer, during the handshake or with a keepalive ping
+    latency: Option<Duration>,
+
+    /// Reason for closing the session, reported when unregistering it
+    disconnect_reason: DisconnectReason,
 }
 
 /// Session helper methods
 impl Session {
     /// Method to create a new session
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         server_addr: SocketAddr,
         remote_addr: SocketAddr,
@@ -98,7 +136,10 @@ impl Session {
         framed: FramedWrite<WriteHalf<TcpStream>, P2PCodec>,
         handshake_timeout: Duration,
         magic_number: u16,
+        network_id: NetworkId,
         blocks_timeout: i64,
+        ping_interval: Duration,
+        ping_timeout: Duration,
     ) -> Session {
         Session {
             server_addr,
@@ -110,13 +151,29 @@ impl Session {
             handshake_flags: HandshakeFlags::default(),
             remote_sender_addr: None,
             magic_number,
+            network_id,
             current_epoch: None,
+            seen_candidates: HashSet::new(),
             requested_block_hashes: vec![],
             requested_blocks: HashMap::new(),
             blocks_timeout,
             blocks_timestamp: 0,
+            ping_interval,
+            ping_timeout,
+            pending_ping: None,
+            version_sent_at: None,
+            peer_version: None,
+            latency: None,
+            disconnect_reason: DisconnectReason::Closed,
         }
     }
+
+    /// Method to stop the session, recording the reason for closing it
+    fn disconnect(&mut self, ctx: &mut Context<Self>, reason: DisconnectReason) {
+        self.disconnect_reason = reason;
+        ctx.stop();
+    }
+
     /// Method to send a Witnet message to the remote peer
     fn send_message(&mut self, msg: WitnetMessage) {
         debug!(
@@ -3,7 +3,7 @@ use crate::config_mngr;
 use actix::prelude::*;
 use log;
 use witnet_crypto::hash::calculate_sha256;
-use witnet_data_structures::proto::ProtobufConvert;
+use witnet_data_structures::{proto::ProtobufConvert, types::NetworkId};
 
 /// Make actor from `SessionsManager`
 impl Actor for SessionsManager {
@@ -30,14 +30,31 @@ impl Actor for SessionsManager {
                     config.connections.inbound_limit,
                     config.connections.outbound_limit,
                 );
+                act.sessions.set_listeners(
+                    config
+                        .connections
+                        .listeners
+                        .iter()
+                        .map(|listener| (listener.addr, listener.inbound_limit)),
+                );
                 act.sessions
                     .set_handshake_timeout(config.connections.handshake_timeout);
                 act.sessions
                     .set_blocks_timeout(config.connections.blocks_timeout);
+                act.sessions.set_ping_interval_and_timeout(
+                    config.connections.ping_interval,
+                    config.connections.ping_timeout,
+                );
+
+                act.candidate_relay_peers = config.connections.candidate_relay_peers.clone();
 
                 let magic = calculate_sha256(&consensus_constants.to_pb_bytes().unwrap());
                 let magic = u16::from(magic.0[0]) << 8 | (u16::from(magic.0[1]));
                 act.sessions.set_magic_number(magic);
+                act.network_id = NetworkId {
+                    network: config.environment.network_name().to_string(),
+                    genesis_hash: consensus_constants.genesis_hash,
+                };
 
                 // The peers bootstrapping process begins upon SessionsManager's start
                 act.bootstrap_peers(ctx, bootstrap_peers_period);
@@ -10,22 +10,47 @@ use actix::{
 use log::{debug, error, warn};
 use tokio::{codec::FramedRead, io::AsyncRead};
 
-use super::SessionsManager;
+use super::{notify_peer_event, SessionsManager};
 use crate::actors::{
     codec::P2PCodec,
+    connections_manager::ConnectionsManager,
     messages::{
-        AddConsolidatedPeer, Anycast, Broadcast, Consolidate, Create, EpochNotification,
-        NumSessions, NumSessionsResult, PeerBeacon, Register, SessionsUnitResult, Unregister,
+        AddConsolidatedPeer, Anycast, Broadcast, BroadcastCandidate, ConnectedPeerStats,
+        Consolidate, Create, DisconnectReason, EpochNotification, FeelerConnect,
+        GetConnectedPeersStats, NumSessions, NumSessionsResult, OutboundConnectionFailed,
+        OutboundTcpConnect, PeerBeacon, PeerEvent, PeerEventKind, PeerLatency, Register,
+        SendInventoryItem, SessionsUnitResult, Unregister,
     },
     peers_manager::PeersManager,
     session::Session,
 };
+use witnet_data_structures::chain::InventoryItem;
+use witnet_p2p::{error::SessionsError, sessions::SessionType};
+use witnet_util::timestamp::get_timestamp;
 
 /// Handler for Create message.
 impl Handler<Create> for SessionsManager {
     type Result = ();
 
     fn handle(&mut self, msg: Create, _ctx: &mut Context<Self>) {
+        // Refuse the connection if its listener has reached its limit of inbound sessions
+        if let Some(listener) = msg.listener {
+            let remote_addr = match msg.stream.peer_addr() {
+                Ok(address) => address,
+                Err(err) => {
+                    warn!("Peer address error in inbound stream: {}", err);
+                    return;
+                }
+            };
+            if !self.sessions.accept_inbound(listener, remote_addr) {
+                debug!(
+                    "Refusing connection from {}: listener {} reached its inbound limit",
+                    remote_addr, listener
+                );
+                return;
+            }
+        }
+
         // Get handshake timeout
         let handshake_timeout = self.sessions.handshake_timeout;
 
@@ -35,9 +60,16 @@ impl Handler<Create> for SessionsManager {
         // Get magic number
         let magic_number = self.sessions.magic_number;
 
+        // Get network id
+        let network_id = self.network_id.clone();
+
         // Get blocks timeout
         let blocks_timeout = self.sessions.blocks_timeout;
 
+        // Get keepalive ping interval and timeout
+        let ping_interval = self.sessions.ping_interval;
+        let ping_timeout = self.sessions.ping_timeout;
+
         // Create a Session actor
         Session::create(move |ctx| {
             // Get server address (if not present, send local address instead)
@@ -60,7 +92,10 @@ impl Handler<Create> for SessionsManager {
                 FramedWrite::new(w, P2PCodec, ctx),
                 handshake_timeout,
                 magic_number,
+                network_id,
                 blocks_timeout,
+                ping_interval,
+                ping_timeout,
             )
         });
     }
@@ -77,10 +112,17 @@ impl Handler<Register> for SessionsManager {
             .register_session(msg.session_type, msg.address, msg.actor);
 
         match &result {
-            Ok(_) => debug!(
-                "Session (type {:?}) registered for peer {}",
-                msg.session_type, msg.address
-            ),
+            Ok(_) => {
+                debug!(
+                    "Session (type {:?}) registered for peer {}",
+                    msg.session_type, msg.address
+                );
+                notify_peer_event(PeerEvent {
+                    address: msg.address,
+                    session_type: msg.session_type,
+                    kind: PeerEventKind::Connected,
+                });
+            }
             Err(error) => error!(
                 "Error while registering peer {} (session type {:?}): {}",
                 msg.address, msg.session_type, error
@@ -96,16 +138,39 @@ impl Handler<Unregister> for SessionsManager {
     type Result = SessionsUnitResult;
 
     fn handle(&mut self, msg: Unregister, _: &mut Context<Self>) -> Self::Result {
+        // The feeler connection finishes when its session is closed. It failed unless it was
+        // closed right after a successful handshake
+        let is_feeler = match msg.session_type {
+            SessionType::Outbound => self.sessions.complete_feeler(&msg.address),
+            _ => false,
+        };
+        if is_feeler && msg.reason != DisconnectReason::Feeler {
+            debug!("Feeler connection to {} failed", msg.address);
+            System::current()
+                .registry()
+                .get::<PeersManager>()
+                .do_send(OutboundConnectionFailed {
+                    address: msg.address,
+                });
+        }
+
         // Call method register session from sessions library
         let result = self
             .sessions
             .unregister_session(msg.session_type, msg.status, msg.address);
 
         match &result {
-            Ok(_) => debug!(
-                "Session (type {:?}) unregistered for peer {}",
-                msg.session_type, msg.address
-            ),
+            Ok(_) => {
+                debug!(
+                    "Session (type {:?}) unregistered for peer {}: {:?}",
+                    msg.session_type, msg.address, msg.reason
+                );
+                notify_peer_event(PeerEvent {
+                    address: msg.address,
+                    session_type: msg.session_type,
+                    kind: PeerEventKind::Disconnected(msg.reason),
+                });
+            }
             Err(error) => error!(
                 "Error while unregistering peer {} (session type {:?}): {}",
                 msg.address, msg.session_type, error
@@ -121,14 +186,27 @@ impl Handler<Consolidate> for SessionsManager {
     type Result = SessionsUnitResult;
 
     fn handle(&mut self, msg: Consolidate, _: &mut Context<Self>) -> Self::Result {
+        // Get peers manager address
+        let peers_manager_addr = System::current().registry().get::<PeersManager>();
+
+        // A feeler connection is not consolidated: the address is reachable, so it is added to
+        // the tried addresses bucket and the session is closed, which finishes the feeler
+        if let SessionType::Outbound = msg.session_type {
+            if self.sessions.is_feeler(&msg.address) {
+                debug!("Feeler connection to {} succeeded", msg.address);
+                peers_manager_addr.do_send(AddConsolidatedPeer {
+                    address: msg.address,
+                });
+
+                return Err(SessionsError::FeelerCompleted.into());
+            }
+        }
+
         // Call method register session from sessions library
         let result = self
             .sessions
             .consolidate_session(msg.session_type, msg.address);
 
-        // Get peers manager address
-        let peers_manager_addr = System::current().registry().get::<PeersManager>();
-
         // Send AddConsolidatedPeer message to the peers manager
         // Try to add this potential peer in the tried addresses bucket
         peers_manager_addr.do_send(AddConsolidatedPeer {
@@ -150,6 +228,43 @@ impl Handler<Consolidate> for SessionsManager {
     }
 }
 
+/// Handler for FeelerConnect message.
+impl Handler<FeelerConnect> for SessionsManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: FeelerConnect, _: &mut Context<Self>) {
+        if !self.sessions.register_feeler(msg.address, get_timestamp()) {
+            debug!(
+                "The address {} is not eligible for a feeler connection",
+                msg.address
+            );
+            // A consolidated session with the address proves that it is reachable
+            if self
+                .sessions
+                .outbound_consolidated
+                .collection
+                .contains_key(&msg.address)
+            {
+                System::current()
+                    .registry()
+                    .get::<PeersManager>()
+                    .do_send(AddConsolidatedPeer {
+                        address: msg.address,
+                    });
+            }
+            return;
+        }
+
+        debug!("Feeler connection to {}", msg.address);
+        System::current()
+            .registry()
+            .get::<ConnectionsManager>()
+            .do_send(OutboundTcpConnect {
+                address: msg.address,
+            });
+    }
+}
+
 /// Handler for Anycast message
 impl<T: 'static> Handler<Anycast<T>> for SessionsManager
 where
@@ -165,9 +280,13 @@ where
             msg.command
         );
 
-        // Request a random consolidated outbound session
-        self.sessions
-            .get_random_anycast_session(msg.safu)
+        // Request one of the fastest or a random consolidated outbound session
+        let session = if msg.low_latency {
+            self.sessions.get_fastest_anycast_session(msg.safu)
+        } else {
+            self.sessions.get_random_anycast_session(msg.safu)
+        };
+        session
             .map(|session_addr| {
                 // Send message to session and await for response
                 session_addr
@@ -223,6 +342,40 @@ where
     }
 }
 
+/// Handler for BroadcastCandidate message
+impl Handler<BroadcastCandidate> for SessionsManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: BroadcastCandidate, _ctx: &mut Context<Self>) {
+        let (relay_sessions, mut other_sessions): (Vec<_>, Vec<_>) = self
+            .sessions
+            .get_all_consolidated_sessions_with_address()
+            .partition(|(address, _)| self.candidate_relay_peers.contains(address));
+        // The other peers receive it in order of latency, the unknown ones last
+        other_sessions.sort_by_key(|(address, _)| {
+            let latency = self.sessions.get_latency(address);
+
+            (latency.is_none(), latency)
+        });
+
+        debug!(
+            "Sending block candidate {} to {} trusted relay peers and {} other sessions",
+            msg.block.hash(),
+            relay_sessions.len(),
+            other_sessions.len()
+        );
+
+        // Trusted relay peers receive the candidate before any other peer
+        let command = SendInventoryItem {
+            item: InventoryItem::Block(msg.block),
+        };
+        for (_, session_addr) in relay_sessions.into_iter().chain(other_sessions) {
+            // Send message to session and ignore errors
+            session_addr.do_send(command.clone());
+        }
+    }
+}
+
 impl Handler<EpochNotification<()>> for SessionsManager {
     type Result = ();
 
@@ -272,3 +425,45 @@ impl Handler<NumSessions> for SessionsManager {
         })
     }
 }
+
+impl Handler<PeerLatency> for SessionsManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: PeerLatency, _ctx: &mut Context<Self>) {
+        self.sessions.set_latency(msg.address, msg.latency);
+    }
+}
+
+impl Handler<GetConnectedPeersStats> for SessionsManager {
+    type Result = <GetConnectedPeersStats as Message>::Result;
+
+    fn handle(&mut self, _msg: GetConnectedPeersStats, _ctx: &mut Context<Self>) -> Self::Result {
+        let inbound = self
+            .sessions
+            .inbound_consolidated
+            .collection
+            .keys()
+            .map(|address| (address, "inbound"));
+        let outbound = self
+            .sessions
+            .outbound_consolidated
+            .collection
+            .keys()
+            .map(|address| (address, "outbound"));
+
+        let mut stats: Vec<ConnectedPeerStats> = inbound
+            .chain(outbound)
+            .map(|(address, session_type)| ConnectedPeerStats {
+                address: *address,
+                session_type: session_type.to_string(),
+                latency_ms: self
+                    .sessions
+                    .get_latency(address)
+                    .map(|latency| latency.as_millis() as u64),
+            })
+            .collect();
+        stats.sort_by_key(|peer| peer.address);
+
+        Ok(stats)
+    }
+}
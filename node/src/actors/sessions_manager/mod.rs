@@ -8,21 +8,22 @@ use actix::{
 
 use ansi_term::Color::Cyan;
 
-use witnet_p2p::sessions::Sessions;
+use witnet_p2p::sessions::{SessionType, Sessions};
 
 use crate::actors::{
     chain_manager::ChainManager,
     connections_manager::ConnectionsManager,
     epoch_manager::EpochManager,
+    json_rpc::JsonRpcServer,
     messages::{
-        Anycast, CloseSession, GetRandomPeer, OutboundTcpConnect, PeersBeacons,
-        PeersSocketAddrResult, SendGetPeers, Subscribe,
+        Anycast, CloseSession, DisconnectReason, GetRandomPeer, OutboundTcpConnect, PeerEvent,
+        PeerEventKind, PeersBeacons, PeersSocketAddrResult, SendGetPeers, Subscribe,
     },
     peers_manager::PeersManager,
     session::Session,
 };
 use std::collections::{HashMap, HashSet};
-use witnet_data_structures::chain::CheckpointBeacon;
+use witnet_data_structures::{chain::CheckpointBeacon, types::NetworkId};
 
 mod actor;
 mod handlers;
@@ -34,6 +35,10 @@ pub struct SessionsManager {
     sessions: Sessions<Addr<Session>>,
     // List of beacons of outbound sessions
     beacons: HashMap<SocketAddr, Option<CheckpointBeacon>>,
+    // Trusted peers that receive the block candidates mined by this node first
+    candidate_relay_peers: Vec<SocketAddr>,
+    // Network of this node, sent in the version handshake
+    network_id: NetworkId,
 }
 
 impl SessionsManager {
@@ -96,6 +101,7 @@ impl SessionsManager {
             ctx.notify(Anycast {
                 command: SendGetPeers {},
                 safu: false,
+                low_latency: false,
             });
             act.discovery_peers(ctx, discovery_peers_period);
         });
@@ -207,7 +213,13 @@ impl SessionsManager {
                             if let Some(a) =
                                 act.sessions.outbound_consolidated.collection.get(&peer)
                             {
-                                a.reference.do_send(CloseSession);
+                                let reason = DisconnectReason::OutOfConsensus;
+                                notify_peer_event(PeerEvent {
+                                    address: peer,
+                                    session_type: SessionType::Outbound,
+                                    kind: PeerEventKind::Unregistered(reason),
+                                });
+                                a.reference.do_send(CloseSession { reason });
                             }
                             peers_to_keep.remove(&peer);
                         }
@@ -233,6 +245,11 @@ impl SessionsManager {
     }
 }
 
+/// Notify a peer connection event to the JSON-RPC subscribers
+fn notify_peer_event(event: PeerEvent) {
+    JsonRpcServer::from_registry().do_send(event);
+}
+
 /// Required traits for being able to retrieve SessionsManager address from registry
 impl actix::Supervised for SessionsManager {}
 
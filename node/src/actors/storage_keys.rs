@@ -4,5 +4,14 @@ pub static PEERS_KEY: &[u8] = b"peers";
 /// Constant to specify the chain state key for the storage
 pub static CHAIN_STATE_KEY: &[u8] = b"chain";
 
-/// Constant to specify the secret key key for the storage
+/// Constant to specify the secret key key for the storage. The master key is now kept in the
+/// identity file, this key is only read to move it there
 pub static MASTER_KEY: &[u8] = b"master_key";
+
+/// Constant to specify the data source statistics key for the storage
+pub static DATA_SOURCE_STATS_KEY: &[u8] = b"data_source_stats";
+
+/// Function to get the key used to persist the superblock with the given index
+pub fn superblock_key(index: u32) -> String {
+    format!("SUPERBLOCK-{}", index)
+}
@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+use actix::prelude::*;
+use log::{debug, error};
+
+use super::SyncManager;
+use crate::config_mngr;
+
+/// Implement Actor trait for `SyncManager`
+impl Actor for SyncManager {
+    /// Every actor has to provide execution `Context` in which it can run
+    type Context = Context<Self>;
+
+    /// Method to be executed when the actor is started
+    fn started(&mut self, ctx: &mut Self::Context) {
+        debug!("SyncManager actor has been started!");
+
+        config_mngr::get()
+            .into_actor(self)
+            .and_then(|config, act, _ctx| {
+                act.genesis_block_hash = config.consensus_constants.genesis_hash;
+                act.batch_timeout = Duration::from_secs(config.connections.blocks_timeout as u64);
+                act.max_batch_retries = config.connections.sync_batch_retries;
+
+                fut::ok(())
+            })
+            .map_err(|err, _, _| error!("SyncManager startup error: {}", err))
+            .wait(ctx);
+    }
+}
+
+impl Supervised for SyncManager {}
+
+impl SystemService for SyncManager {}
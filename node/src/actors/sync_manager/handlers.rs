@@ -0,0 +1,66 @@
+//! Message handlers for `SyncManager`
+use actix::{Context, Handler};
+use log::{debug, error};
+
+use super::{split_batch, BatchSplit, SyncManager, SyncState};
+use crate::actors::messages::{AddBlocks, SessionUnitResult, StartSync, StopSync};
+
+impl Handler<StartSync> for SyncManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: StartSync, ctx: &mut Context<Self>) {
+        match self.sync.as_mut() {
+            Some(sync) => {
+                // Keep downloading the blocks from where the synchronization is
+                sync.target_beacon = msg.target_beacon;
+            }
+            None => {
+                debug!(
+                    "Synchronizing from {:?} to {:?}",
+                    msg.chain_beacon, msg.target_beacon
+                );
+                self.sync = Some(SyncState::new(msg.target_beacon, msg.chain_beacon));
+                self.request_batch(ctx);
+            }
+        }
+    }
+}
+
+impl Handler<StopSync> for SyncManager {
+    type Result = ();
+
+    fn handle(&mut self, _msg: StopSync, _ctx: &mut Context<Self>) {
+        self.sync = None;
+    }
+}
+
+impl Handler<AddBlocks> for SyncManager {
+    type Result = SessionUnitResult;
+
+    fn handle(&mut self, msg: AddBlocks, ctx: &mut Context<Self>) {
+        let sync = match self.sync {
+            Some(sync) if !sync.consolidating => sync,
+            _ => {
+                debug!("AddBlocks received while not waiting for blocks");
+                return;
+            }
+        };
+
+        match split_batch(
+            msg.blocks,
+            sync.chain_beacon,
+            sync.target_beacon,
+            self.genesis_block_hash,
+        ) {
+            BatchSplit::Empty => {
+                debug!("Received an empty AddBlocks message");
+                self.retry_batch(ctx);
+            }
+            BatchSplit::Fork => {
+                error!("Mismatching blocks, fork detected");
+                self.fail(true);
+            }
+            BatchSplit::Blocks(blocks) => self.consolidate_batch(ctx, blocks),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! # SyncManager actor
+//!
+//! This module contains the `SyncManager` actor, which drives the synchronization of the local
+//! chain with the network once the `ChainManager` finds a consensus beacon ahead of it: it
+//! requests the batches of blocks needed to reach that beacon, splits them into the blocks the
+//! `ChainManager` has to consolidate and requests them again if they do not arrive in time.
+use std::time::Duration;
+
+use actix::{ActorFuture, AsyncContext, Context, ContextFutureSpawner, SystemService, WrapFuture};
+use log::{debug, error, warn};
+
+use witnet_data_structures::chain::{Block, CheckpointBeacon, Hash, Hashable};
+
+use crate::actors::{
+    chain_manager::ChainManager,
+    messages::{Anycast, ConsolidateBatch, SendLastBeacon, SyncFailed},
+    sessions_manager::SessionsManager,
+};
+
+mod actor;
+mod handlers;
+
+/// SyncManager actor
+#[derive(Debug, Default)]
+pub struct SyncManager {
+    /// Synchronization in progress, if any
+    sync: Option<SyncState>,
+    /// Hash of the genesis block
+    genesis_block_hash: Hash,
+    /// Time to wait for a requested batch of blocks before requesting it again
+    batch_timeout: Duration,
+    /// Number of times a batch is requested again before giving up
+    max_batch_retries: u32,
+    /// Number of batch requests sent, used to tell apart the timeouts of the old requests
+    batch_requests: u64,
+}
+
+/// Progress of a synchronization
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SyncState {
+    /// Beacon agreed by the peers, to which the node is trying to catch up
+    pub target_beacon: CheckpointBeacon,
+    /// Beacon of the local chain
+    pub chain_beacon: CheckpointBeacon,
+    /// Number of times the current batch was requested again
+    pub retries: u32,
+    /// Whether a batch is being consolidated by the `ChainManager`
+    pub consolidating: bool,
+}
+
+impl SyncState {
+    /// Start a synchronization from `chain_beacon` to `target_beacon`
+    pub fn new(target_beacon: CheckpointBeacon, chain_beacon: CheckpointBeacon) -> Self {
+        Self {
+            target_beacon,
+            chain_beacon,
+            retries: 0,
+            consolidating: false,
+        }
+    }
+}
+
+/// Blocks of a batch that the `ChainManager` has to consolidate
+#[derive(Debug, Eq, PartialEq)]
+pub enum BatchSplit {
+    /// The batch is empty, e.g. because the peer did not send all the blocks in time
+    Empty,
+    /// The first block of the batch does not follow the local chain
+    Fork,
+    /// Blocks to consolidate, none if the batch only contained already consolidated blocks
+    Blocks(Vec<Block>),
+}
+
+/// Select the blocks of a batch that have to be consolidated to move the local chain, whose last
+/// block is `chain_beacon`, towards `target_beacon`.
+///
+/// Peers send the last block of the local chain as the first block of the batch, so it is
+/// skipped, and the blocks after the target beacon are dropped.
+pub fn split_batch(
+    mut blocks: Vec<Block>,
+    chain_beacon: CheckpointBeacon,
+    target_beacon: CheckpointBeacon,
+    genesis_block_hash: Hash,
+) -> BatchSplit {
+    if blocks.is_empty() {
+        return BatchSplit::Empty;
+    }
+
+    // FIXME(#684): this condition would be modified when genesis block exist
+    let first_checkpoint = blocks[0].block_header.beacon.checkpoint;
+    if chain_beacon.hash_prev_block != genesis_block_hash
+        && first_checkpoint <= chain_beacon.checkpoint
+    {
+        if first_checkpoint == chain_beacon.checkpoint
+            && blocks[0].hash() != chain_beacon.hash_prev_block
+        {
+            return BatchSplit::Fork;
+        }
+        blocks.remove(0);
+    }
+
+    if let Some(position) = blocks
+        .iter()
+        .position(|block| block.hash() == target_beacon.hash_prev_block)
+    {
+        blocks.truncate(position + 1);
+    }
+
+    BatchSplit::Blocks(blocks)
+}
+
+impl SyncManager {
+    /// Ask a peer whose last beacon matches the consensus for the blocks after the local chain,
+    /// requesting them again if they do not arrive before the batch timeout
+    fn request_batch(&mut self, ctx: &mut Context<Self>) {
+        let chain_beacon = match self.sync {
+            Some(sync) => sync.chain_beacon,
+            None => return,
+        };
+
+        self.batch_requests += 1;
+        let batch_request = self.batch_requests;
+
+        SessionsManager::from_registry().do_send(Anycast {
+            command: SendLastBeacon {
+                beacon: chain_beacon,
+            },
+            safu: true,
+            // The blocks are downloaded from the peer receiving the beacon
+            low_latency: true,
+        });
+
+        ctx.run_later(self.batch_timeout, move |act, ctx| {
+            let waiting = act.batch_requests == batch_request
+                && act.sync.map_or(false, |sync| !sync.consolidating);
+            if waiting {
+                warn!("Timeout waiting for the requested blocks");
+                act.retry_batch(ctx);
+            }
+        });
+    }
+
+    /// Request the current batch again, or give up on the synchronization if it was already
+    /// requested too many times
+    fn retry_batch(&mut self, ctx: &mut Context<Self>) {
+        let max_batch_retries = self.max_batch_retries;
+        match self.sync.as_mut() {
+            Some(sync) if sync.retries < max_batch_retries => {
+                sync.retries += 1;
+                debug!(
+                    "Requesting the blocks after {:?} again ({}/{})",
+                    sync.chain_beacon, sync.retries, max_batch_retries
+                );
+                self.request_batch(ctx);
+            }
+            Some(_) => {
+                warn!("Giving up on the synchronization, the blocks did not arrive");
+                self.fail(false);
+            }
+            None => {}
+        }
+    }
+
+    /// Send the blocks to consolidate to the `ChainManager`, and continue with the next batch
+    /// if they were consolidated successfully
+    fn consolidate_batch(&mut self, ctx: &mut Context<Self>, blocks: Vec<Block>) {
+        let target_beacon = match self.sync.as_mut() {
+            Some(sync) => {
+                sync.consolidating = true;
+                sync.target_beacon
+            }
+            None => return,
+        };
+
+        ChainManager::from_registry()
+            .send(ConsolidateBatch {
+                blocks,
+                target_beacon,
+            })
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                match res {
+                    Ok(Ok(chain_beacon)) => act.batch_consolidated(ctx, chain_beacon),
+                    Ok(Err(e)) => {
+                        debug!("Failed to consolidate the batch of blocks: {}", e);
+                        act.sync = None;
+                    }
+                    Err(e) => {
+                        error!("Unsuccessful communication with ChainManager: {}", e);
+                        act.sync = None;
+                    }
+                }
+
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    fn batch_consolidated(&mut self, ctx: &mut Context<Self>, chain_beacon: CheckpointBeacon) {
+        let sync = match self.sync.as_mut() {
+            Some(sync) => sync,
+            None => return,
+        };
+        sync.chain_beacon = chain_beacon;
+        sync.retries = 0;
+        sync.consolidating = false;
+
+        if chain_beacon == sync.target_beacon {
+            debug!("Synchronization target {:?} reached", chain_beacon);
+            self.sync = None;
+        } else {
+            self.request_batch(ctx);
+        }
+    }
+
+    /// Stop the synchronization and let the `ChainManager` know, so it waits for a new consensus
+    fn fail(&mut self, fork: bool) {
+        self.sync = None;
+        ChainManager::from_registry().do_send(SyncFailed { fork });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_data_structures::chain::{BlockHeader, BlockTransactions, KeyedSignature};
+
+    fn block(checkpoint: u32, hash_prev_block: Hash) -> Block {
+        Block {
+            block_header: BlockHeader {
+                beacon: CheckpointBeacon {
+                    checkpoint,
+                    hash_prev_block,
+                },
+                ..BlockHeader::default()
+            },
+            block_sig: KeyedSignature::default(),
+            txns: BlockTransactions::default(),
+        }
+    }
+
+    fn beacon(block: &Block) -> CheckpointBeacon {
+        CheckpointBeacon {
+            checkpoint: block.block_header.beacon.checkpoint,
+            hash_prev_block: block.hash(),
+        }
+    }
+
+    /// Chain of blocks built on top of the genesis block, one per epoch starting at 1
+    fn chain(genesis_block_hash: Hash, len: u32) -> Vec<Block> {
+        let mut blocks: Vec<Block> = vec![];
+        for checkpoint in 1..=len {
+            let hash_prev_block = blocks.last().map_or(genesis_block_hash, Hashable::hash);
+            blocks.push(block(checkpoint, hash_prev_block));
+        }
+
+        blocks
+    }
+
+    #[test]
+    fn split_empty_batch() {
+        let genesis = Hash::default();
+        let chain_beacon = CheckpointBeacon {
+            checkpoint: 0,
+            hash_prev_block: genesis,
+        };
+
+        assert_eq!(
+            split_batch(vec![], chain_beacon, chain_beacon, genesis),
+            BatchSplit::Empty
+        );
+    }
+
+    #[test]
+    fn split_batch_from_genesis() {
+        let genesis = Hash::default();
+        let blocks = chain(genesis, 5);
+        let chain_beacon = CheckpointBeacon {
+            checkpoint: 0,
+            hash_prev_block: genesis,
+        };
+        let target_beacon = beacon(&blocks[2]);
+
+        assert_eq!(
+            split_batch(blocks.clone(), chain_beacon, target_beacon, genesis),
+            BatchSplit::Blocks(blocks[..3].to_vec())
+        );
+    }
+
+    #[test]
+    fn split_batch_skips_consolidated_block() {
+        let genesis = Hash::default();
+        let blocks = chain(genesis, 5);
+        let chain_beacon = beacon(&blocks[1]);
+        let target_beacon = beacon(&blocks[4]);
+
+        assert_eq!(
+            split_batch(blocks[1..].to_vec(), chain_beacon, target_beacon, genesis),
+            BatchSplit::Blocks(blocks[2..].to_vec())
+        );
+        assert_eq!(
+            split_batch(blocks[1..2].to_vec(), chain_beacon, target_beacon, genesis),
+            BatchSplit::Blocks(vec![])
+        );
+    }
+
+    #[test]
+    fn split_batch_detects_fork() {
+        let genesis = Hash::default();
+        let blocks = chain(genesis, 3);
+        let chain_beacon = beacon(&blocks[1]);
+        let target_beacon = beacon(&blocks[2]);
+        let mut fork = blocks[1..].to_vec();
+        fork[0] = block(2, Hash::SHA256([1; 32]));
+
+        assert_eq!(
+            split_batch(fork, chain_beacon, target_beacon, genesis),
+            BatchSplit::Fork
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use actix::prelude::*;
+use log::{debug, error};
+use reqwest::r#async::Client;
+
+use super::WebhookNotifier;
+use crate::config_mngr;
+
+/// Implement Actor trait for `WebhookNotifier`
+impl Actor for WebhookNotifier {
+    /// Every actor has to provide execution `Context` in which it can run
+    type Context = Context<Self>;
+
+    /// Method to be executed when the actor is started
+    fn started(&mut self, ctx: &mut Self::Context) {
+        debug!("WebhookNotifier actor has been started!");
+
+        config_mngr::get()
+            .into_actor(self)
+            .and_then(|config, act, _ctx| {
+                act.subscribers = config.webhooks.subscribers.clone();
+                act.max_retries = config.webhooks.max_retries;
+                act.retry_delay = config.webhooks.retry_delay;
+
+                if !act.subscribers.is_empty() {
+                    act.client = Some(Client::new());
+                }
+
+                fut::ok(())
+            })
+            .map_err(|err, _, _| error!("WebhookNotifier startup error: {}", err))
+            .wait(ctx);
+    }
+}
+
+impl Supervised for WebhookNotifier {}
+
+impl SystemService for WebhookNotifier {}
@@ -0,0 +1,34 @@
+//! Message handlers for `WebhookNotifier`
+use std::sync::Arc;
+
+use actix::{Context, Handler};
+use serde_json::json;
+
+use super::WebhookNotifier;
+use crate::actors::messages::NotifyWebhooks;
+
+impl Handler<NotifyWebhooks> for WebhookNotifier {
+    type Result = ();
+
+    fn handle(&mut self, msg: NotifyWebhooks, ctx: &mut Context<Self>) {
+        let urls: Vec<String> = self
+            .subscribers
+            .iter()
+            .filter(|subscriber| subscriber.events.contains(&msg.event))
+            .map(|subscriber| subscriber.url.clone())
+            .collect();
+
+        if urls.is_empty() {
+            return;
+        }
+
+        let body = Arc::new(json!({
+            "event": msg.event,
+            "data": msg.payload,
+        }));
+
+        for url in urls {
+            self.post(ctx, url, body.clone(), self.max_retries);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! # WebhookNotifier actor
+//!
+//! This module contains the `WebhookNotifier` actor which is in charge of POSTing a JSON payload
+//! to the configured webhook URLs every time one of the node events they are subscribed to
+//! happens, retrying the failed notifications.
+use std::{sync::Arc, time::Duration};
+
+use actix::{ActorFuture, AsyncContext, Context, ContextFutureSpawner, WrapFuture};
+use futures::Future;
+use log::{debug, error, warn};
+use reqwest::r#async::Client;
+
+use witnet_config::config::WebhookSubscriber;
+
+mod actor;
+mod handlers;
+
+/// WebhookNotifier actor
+#[derive(Default)]
+pub struct WebhookNotifier {
+    /// Webhook URLs and the events they are subscribed to
+    subscribers: Vec<WebhookSubscriber>,
+    /// Number of times a failed notification is retried
+    max_retries: u32,
+    /// Time to wait before retrying a failed notification
+    retry_delay: Duration,
+    /// HTTP client used to send the notifications
+    client: Option<Client>,
+}
+
+impl WebhookNotifier {
+    /// POST the body to the given URL, retrying up to `retries_left` times if it fails
+    fn post(
+        &self,
+        ctx: &mut Context<Self>,
+        url: String,
+        body: Arc<serde_json::Value>,
+        retries_left: u32,
+    ) {
+        let client = match self.client {
+            Some(ref client) => client,
+            None => {
+                error!("WebhookNotifier is not initialized");
+                return;
+            }
+        };
+
+        client
+            .post(&url)
+            .json(body.as_ref())
+            .send()
+            .and_then(|response| response.error_for_status())
+            .into_actor(self)
+            .then(move |res, act, ctx| {
+                match res {
+                    Ok(_) => debug!("Successfully notified webhook {}", url),
+                    Err(e) if retries_left > 0 => {
+                        warn!(
+                            "Failed to notify webhook {}: {}. Retrying in {:?}",
+                            url, e, act.retry_delay
+                        );
+                        ctx.run_later(act.retry_delay, move |act, ctx| {
+                            act.post(ctx, url, body, retries_left - 1)
+                        });
+                    }
+                    Err(e) => error!("Failed to notify webhook {}, giving up: {}", url, e),
+                }
+
+                actix::fut::ok(())
+            })
+            .spawn(ctx);
+    }
+}
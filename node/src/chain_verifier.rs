@@ -0,0 +1,591 @@
+//! # Chain verifier
+//!
+//! Offline re-validation of the chain stored in the node storage, useful after disk errors or
+//! when debugging consensus bugs. It does not need network access nor a running node, but the
+//! node must be stopped so the storage can be opened.
+use bincode::{deserialize, serialize};
+use failure::Fail;
+
+use crate::{
+    actors::storage_keys::{superblock_key, CHAIN_STATE_KEY},
+    storage_mngr,
+};
+use witnet_config::config::Config;
+use witnet_data_structures::{
+    chain::{Block, ChainState, Epoch, Hash, Hashable, InventoryItem, LegacyChainState},
+    superblock::{Superblock, SuperblockState, SUPERBLOCK_PERIOD},
+};
+use witnet_storage::storage::Storage;
+use witnet_validations::validations::{validate_block_signature, validate_merkle_tree};
+
+/// An inconsistency found in a stored block
+#[derive(Debug, Fail)]
+pub enum ChainInconsistency {
+    /// The block indexed in the chain is not in the storage
+    #[fail(display = "block {} is missing from the storage", _0)]
+    MissingBlock(Hash),
+    /// The hash of the stored block does not match the indexed hash
+    #[fail(
+        display = "block hash {} does not match the indexed hash {}",
+        found, expected
+    )]
+    HashMismatch {
+        /// Indexed hash
+        expected: Hash,
+        /// Hash of the stored block
+        found: Hash,
+    },
+    /// The checkpoint of the block beacon does not match the epoch the block is indexed at
+    #[fail(display = "block beacon checkpoint is {}", _0)]
+    EpochMismatch(Epoch),
+    /// The block does not point to the previous block in the chain
+    #[fail(
+        display = "block points to previous block {}, but the previous block is {}",
+        found, expected
+    )]
+    BrokenLink {
+        /// Hash of the previous block in the chain
+        expected: Hash,
+        /// Previous block hash in the block beacon
+        found: Hash,
+    },
+    /// The merkle roots of the block header do not match the block transactions
+    #[fail(display = "merkle roots do not match the block transactions")]
+    InvalidMerkleRoots,
+    /// The block signature is not valid
+    #[fail(display = "invalid block signature: {}", _0)]
+    InvalidSignature(String),
+    /// The epoch of the mint transaction does not match the block epoch
+    #[fail(display = "mint transaction epoch is {}", _0)]
+    InvalidMintEpoch(Epoch),
+    /// The highest block checkpoint of the chain info does not match the last block
+    #[fail(
+        display = "chain tip is block {}, but the last block is {}",
+        found, expected
+    )]
+    TipMismatch {
+        /// Hash of the last block in the chain
+        expected: Hash,
+        /// Hash of the chain tip in the chain info
+        found: Hash,
+    },
+    /// The stored superblock does not match the one built from the blocks of its period
+    #[fail(display = "superblock #{} does not match the blocks of its period", _0)]
+    SuperblockMismatch(u32),
+}
+
+/// Result of verifying the stored chain
+#[derive(Debug)]
+pub struct ChainVerification {
+    /// Number of blocks that were checked
+    pub blocks_checked: usize,
+    /// Number of stored superblocks that were checked
+    pub superblocks_checked: usize,
+    /// First inconsistency found, along with the epoch of the block
+    pub first_inconsistency: Option<(Epoch, ChainInconsistency)>,
+}
+
+/// Re-validate the blocks of the stored chain starting from `from_epoch`, stopping at the first
+/// inconsistency.
+///
+/// For every block it checks that the block is in the storage, that its hash and epoch match
+/// the chain index, that it points to the previous block, and that its merkle roots, signature
+/// and mint transaction epoch are valid. Every stored superblock whose period is fully checked
+/// is compared with the one built from the checked blocks.
+pub fn verify_chain(
+    config: &Config,
+    from_epoch: Epoch,
+) -> Result<ChainVerification, failure::Error> {
+    let storage = storage_mngr::create_appropriate_backend(&config.storage)?;
+
+    verify_stored_chain(
+        storage.as_ref(),
+        config.consensus_constants.genesis_hash,
+        from_epoch,
+    )
+}
+
+/// Re-validate the blocks of the chain stored in `storage`, see `verify_chain`
+fn verify_stored_chain(
+    storage: &dyn Storage,
+    genesis_hash: Hash,
+    from_epoch: Epoch,
+) -> Result<ChainVerification, failure::Error> {
+    let chain_state: ChainState = storage
+        .get(&serialize(&CHAIN_STATE_KEY)?)?
+        .map(|bytes| storage_mngr::deserialize_or_legacy::<ChainState, LegacyChainState>(&bytes))
+        .transpose()?
+        .ok_or_else(|| failure::format_err!("There is no chain state in the storage"))?;
+
+    // The block before `from_epoch`, or the genesis block, is the first previous block
+    let mut previous_hash = chain_state
+        .block_chain
+        .range(..from_epoch)
+        .next_back()
+        .map(|(_, hash)| *hash)
+        .unwrap_or(genesis_hash);
+    let mut blocks_checked = 0;
+    let mut superblocks_checked = 0;
+
+    // The superblock of the period of `from_epoch` cannot be built if some blocks of the period
+    // are not checked
+    let mut superblock_state = if from_epoch % SUPERBLOCK_PERIOD == 0 {
+        SuperblockState::default()
+    } else {
+        SuperblockState::resume(from_epoch)
+    };
+    let mut previous_epoch = None;
+
+    for (&epoch, &hash) in chain_state.block_chain.range(from_epoch..) {
+        let block = match get(storage, &hash_key(hash))? {
+            Some(InventoryItem::Block(block)) => block,
+            _ => {
+                return Ok(ChainVerification {
+                    blocks_checked,
+                    superblocks_checked,
+                    first_inconsistency: Some((epoch, ChainInconsistency::MissingBlock(hash))),
+                });
+            }
+        };
+        blocks_checked += 1;
+
+        if let Err(inconsistency) = verify_block(&block, epoch, hash, previous_hash) {
+            return Ok(ChainVerification {
+                blocks_checked,
+                superblocks_checked,
+                first_inconsistency: Some((epoch, inconsistency)),
+            });
+        }
+
+        // When the block starts a new period, the superblock of the previous one is built. The ARS
+        // cannot be recomputed without replaying the reputation, so the stored members are used,
+        // which still checks that the ARS root matches them
+        let stored_superblock = match previous_epoch {
+            Some(previous_epoch)
+                if previous_epoch / SUPERBLOCK_PERIOD != epoch / SUPERBLOCK_PERIOD =>
+            {
+                get::<_, Superblock>(storage, &superblock_key(previous_epoch / SUPERBLOCK_PERIOD))?
+            }
+            _ => None,
+        };
+        let ars_members = stored_superblock
+            .as_ref()
+            .map(|superblock| superblock.ars_members.clone())
+            .unwrap_or_default();
+        let superblock = superblock_state.process_block(
+            epoch,
+            hash,
+            &block.block_header.merkle_roots,
+            &ars_members,
+        );
+        // The periods consolidated before superblocks were tracked have no stored superblock
+        if let (Some(stored), Some(built)) = (stored_superblock, superblock) {
+            superblocks_checked += 1;
+            if stored != built {
+                return Ok(ChainVerification {
+                    blocks_checked,
+                    superblocks_checked,
+                    first_inconsistency: Some((
+                        epoch,
+                        ChainInconsistency::SuperblockMismatch(stored.index),
+                    )),
+                });
+            }
+        }
+
+        previous_hash = hash;
+        previous_epoch = Some(epoch);
+    }
+
+    // The chain tip must be the last block
+    let first_inconsistency = match (
+        &chain_state.chain_info,
+        chain_state.block_chain.iter().last(),
+    ) {
+        (Some(chain_info), Some((&epoch, &hash)))
+            if chain_info.highest_block_checkpoint.hash_prev_block != hash =>
+        {
+            Some((
+                epoch,
+                ChainInconsistency::TipMismatch {
+                    expected: hash,
+                    found: chain_info.highest_block_checkpoint.hash_prev_block,
+                },
+            ))
+        }
+        _ => None,
+    };
+
+    Ok(ChainVerification {
+        blocks_checked,
+        superblocks_checked,
+        first_inconsistency,
+    })
+}
+
+/// Check a block against the chain index and its own contents
+fn verify_block(
+    block: &Block,
+    epoch: Epoch,
+    hash: Hash,
+    previous_hash: Hash,
+) -> Result<(), ChainInconsistency> {
+    let block_hash = block.hash();
+    if block_hash != hash {
+        return Err(ChainInconsistency::HashMismatch {
+            expected: hash,
+            found: block_hash,
+        });
+    }
+
+    let beacon = block.block_header.beacon;
+    if beacon.checkpoint != epoch {
+        return Err(ChainInconsistency::EpochMismatch(beacon.checkpoint));
+    }
+    if beacon.hash_prev_block != previous_hash {
+        return Err(ChainInconsistency::BrokenLink {
+            expected: previous_hash,
+            found: beacon.hash_prev_block,
+        });
+    }
+
+    if !validate_merkle_tree(block) {
+        return Err(ChainInconsistency::InvalidMerkleRoots);
+    }
+
+    validate_block_signature(block)
+        .map_err(|e| ChainInconsistency::InvalidSignature(e.to_string()))?;
+
+    if block.txns.mint.epoch != epoch {
+        return Err(ChainInconsistency::InvalidMintEpoch(block.txns.mint.epoch));
+    }
+
+    Ok(())
+}
+
+/// Storage key of an inventory item, as used by the InventoryManager
+fn hash_key(hash: Hash) -> Vec<u8> {
+    match hash {
+        Hash::SHA256(h) => h.to_vec(),
+    }
+}
+
+/// Get a value from the storage, serializing the key like the StorageManager does
+fn get<K, T>(storage: &dyn Storage, key: &K) -> Result<Option<T>, failure::Error>
+where
+    K: serde::Serialize,
+    T: serde::de::DeserializeOwned,
+{
+    let key_bytes = serialize(key)?;
+
+    match storage.get(&key_bytes)? {
+        Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::{
+        PublicKey as Secp256k1_PublicKey, Secp256k1, SecretKey as Secp256k1_SecretKey,
+    };
+    use witnet_crypto::signature::sign;
+    use witnet_data_structures::{
+        chain::{
+            BlockHeader, BlockMerkleRoots, BlockTransactions, ChainInfo, CheckpointBeacon,
+            KeyedSignature, PublicKey, PublicKeyHash, SecretKey, Signature,
+        },
+        transaction::{MintTransaction, ValueTransferOutput},
+        vrf::{BlockEligibilityClaim, VrfCtx},
+    };
+    use witnet_protected::Protected;
+    use witnet_storage::backends::hashmap::Backend;
+    use witnet_validations::validations::merkle_tree_root;
+
+    /// Build a valid block of `epoch` pointing to `previous_hash`
+    fn signed_block(epoch: Epoch, previous_hash: Hash) -> Block {
+        let beacon = CheckpointBeacon {
+            checkpoint: epoch,
+            hash_prev_block: previous_hash,
+        };
+        let vrf = &mut VrfCtx::secp256k1().unwrap();
+        let secret_key = SecretKey {
+            bytes: Protected::from(vec![0xcd; 32]),
+        };
+        let proof = BlockEligibilityClaim::create(vrf, &secret_key, beacon).unwrap();
+
+        let txns = BlockTransactions {
+            mint: MintTransaction::new(epoch, ValueTransferOutput::default()),
+            ..BlockTransactions::default()
+        };
+        let merkle_roots = BlockMerkleRoots {
+            mint_hash: txns.mint.hash(),
+            vt_hash_merkle_root: merkle_tree_root(&txns.value_transfer_txns),
+            dr_hash_merkle_root: merkle_tree_root(&txns.data_request_txns),
+            commit_hash_merkle_root: merkle_tree_root(&txns.commit_txns),
+            reveal_hash_merkle_root: merkle_tree_root(&txns.reveal_txns),
+            tally_hash_merkle_root: merkle_tree_root(&txns.tally_txns),
+        };
+        let block_header = BlockHeader {
+            beacon,
+            merkle_roots,
+            proof,
+            ..BlockHeader::default()
+        };
+
+        let Hash::SHA256(data) = block_header.hash();
+        let secret_key =
+            Secp256k1_SecretKey::from_slice(&[0xcd; 32]).expect("32 bytes, within curve order");
+        let public_key = Secp256k1_PublicKey::from_secret_key(&Secp256k1::new(), &secret_key);
+
+        Block {
+            block_header,
+            block_sig: KeyedSignature {
+                signature: Signature::from(sign(secret_key, &data)),
+                public_key: PublicKey::from(public_key),
+            },
+            txns,
+        }
+    }
+
+    /// Store the blocks and a chain state indexing them, with the last block as the chain tip
+    fn store_chain(storage: &mut dyn Storage, blocks: &[Block]) {
+        let mut chain_state = ChainState::default();
+        for block in blocks {
+            let hash = block.hash();
+            chain_state
+                .block_chain
+                .insert(block.block_header.beacon.checkpoint, hash);
+            storage
+                .put(
+                    serialize(&hash_key(hash)).unwrap(),
+                    serialize(&InventoryItem::Block(block.clone())).unwrap(),
+                )
+                .unwrap();
+        }
+        if let Some(block) = blocks.last() {
+            let config = Config::default();
+            chain_state.chain_info = Some(ChainInfo {
+                environment: config.environment,
+                consensus_constants: config.consensus_constants,
+                highest_block_checkpoint: CheckpointBeacon {
+                    checkpoint: block.block_header.beacon.checkpoint,
+                    hash_prev_block: block.hash(),
+                },
+            });
+        }
+        storage
+            .put(
+                serialize(&CHAIN_STATE_KEY).unwrap(),
+                serialize(&chain_state).unwrap(),
+            )
+            .unwrap();
+    }
+
+    fn chain(genesis_hash: Hash, epochs: &[Epoch]) -> Vec<Block> {
+        let mut previous_hash = genesis_hash;
+        epochs
+            .iter()
+            .map(|&epoch| {
+                let block = signed_block(epoch, previous_hash);
+                previous_hash = block.hash();
+                block
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_chain() {
+        let genesis_hash = Hash::SHA256([1; 32]);
+        let mut storage = Backend::new();
+        store_chain(&mut storage, &chain(genesis_hash, &[1, 2, 4]));
+
+        let verification = verify_stored_chain(&storage, genesis_hash, 0).unwrap();
+        assert_eq!(verification.blocks_checked, 3);
+        assert!(verification.first_inconsistency.is_none());
+
+        // The blocks before `from_epoch` are not checked, but link the first checked block
+        let verification = verify_stored_chain(&storage, genesis_hash, 2).unwrap();
+        assert_eq!(verification.blocks_checked, 2);
+        assert!(verification.first_inconsistency.is_none());
+    }
+
+    #[test]
+    fn missing_chain_state() {
+        let storage = Backend::new();
+
+        assert!(verify_stored_chain(&storage, Hash::default(), 0).is_err());
+    }
+
+    #[test]
+    fn broken_link() {
+        let genesis_hash = Hash::SHA256([1; 32]);
+        let mut blocks = chain(genesis_hash, &[1, 2]);
+        blocks.push(signed_block(3, genesis_hash));
+        let mut storage = Backend::new();
+        store_chain(&mut storage, &blocks);
+
+        let verification = verify_stored_chain(&storage, genesis_hash, 0).unwrap();
+        assert_eq!(verification.blocks_checked, 3);
+        match verification.first_inconsistency {
+            Some((3, ChainInconsistency::BrokenLink { expected, found })) => {
+                assert_eq!(expected, blocks[1].hash());
+                assert_eq!(found, genesis_hash);
+            }
+            other => panic!("unexpected inconsistency: {:?}", other),
+        }
+
+        // A wrong genesis hash breaks the link of the first block
+        let verification = verify_stored_chain(&storage, Hash::default(), 0).unwrap();
+        assert_eq!(verification.blocks_checked, 1);
+        match verification.first_inconsistency {
+            Some((1, ChainInconsistency::BrokenLink { .. })) => {}
+            other => panic!("unexpected inconsistency: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_block() {
+        let genesis_hash = Hash::SHA256([1; 32]);
+        let blocks = chain(genesis_hash, &[1, 2, 3]);
+        let mut storage = Backend::new();
+        store_chain(&mut storage, &blocks);
+        storage
+            .delete(&serialize(&hash_key(blocks[1].hash())).unwrap())
+            .unwrap();
+
+        let verification = verify_stored_chain(&storage, genesis_hash, 0).unwrap();
+        assert_eq!(verification.blocks_checked, 1);
+        match verification.first_inconsistency {
+            Some((2, ChainInconsistency::MissingBlock(hash))) => {
+                assert_eq!(hash, blocks[1].hash())
+            }
+            other => panic!("unexpected inconsistency: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tip_mismatch() {
+        let genesis_hash = Hash::SHA256([1; 32]);
+        let blocks = chain(genesis_hash, &[1, 2]);
+        let mut storage = Backend::new();
+        store_chain(&mut storage, &blocks);
+        // The last block is indexed, but the chain tip is still the previous block
+        let key = serialize(&CHAIN_STATE_KEY).unwrap();
+        let mut chain_state: ChainState =
+            deserialize(&storage.get(&key).unwrap().unwrap()).unwrap();
+        chain_state
+            .chain_info
+            .as_mut()
+            .unwrap()
+            .highest_block_checkpoint
+            .hash_prev_block = blocks[0].hash();
+        storage.put(key, serialize(&chain_state).unwrap()).unwrap();
+
+        let verification = verify_stored_chain(&storage, genesis_hash, 0).unwrap();
+        assert_eq!(verification.blocks_checked, 2);
+        match verification.first_inconsistency {
+            Some((2, ChainInconsistency::TipMismatch { expected, found })) => {
+                assert_eq!(expected, blocks[1].hash());
+                assert_eq!(found, blocks[0].hash());
+            }
+            other => panic!("unexpected inconsistency: {:?}", other),
+        }
+    }
+
+    /// Build the superblock of the first period of `blocks` and store it
+    fn store_superblock(storage: &mut dyn Storage, blocks: &[Block]) -> Superblock {
+        let ars_members = vec![PublicKeyHash::default()];
+        let mut state = SuperblockState::default();
+        let superblock = blocks
+            .iter()
+            .filter_map(|block| {
+                state.process_block(
+                    block.block_header.beacon.checkpoint,
+                    block.hash(),
+                    &block.block_header.merkle_roots,
+                    &ars_members,
+                )
+            })
+            .next()
+            .unwrap();
+        storage
+            .put(
+                serialize(&superblock_key(superblock.index)).unwrap(),
+                serialize(&superblock).unwrap(),
+            )
+            .unwrap();
+
+        superblock
+    }
+
+    #[test]
+    fn superblocks_are_checked() {
+        let genesis_hash = Hash::SHA256([1; 32]);
+        let blocks = chain(
+            genesis_hash,
+            &[1, 2, SUPERBLOCK_PERIOD, SUPERBLOCK_PERIOD + 1],
+        );
+        let mut storage = Backend::new();
+        store_chain(&mut storage, &blocks);
+        let mut superblock = store_superblock(&mut storage, &blocks);
+
+        let verification = verify_stored_chain(&storage, genesis_hash, 0).unwrap();
+        assert_eq!(verification.superblocks_checked, 1);
+        assert!(verification.first_inconsistency.is_none());
+
+        // The superblock of a period that is not fully checked cannot be built
+        let verification = verify_stored_chain(&storage, genesis_hash, 2).unwrap();
+        assert_eq!(verification.superblocks_checked, 0);
+        assert!(verification.first_inconsistency.is_none());
+
+        superblock.tally_root = genesis_hash;
+        storage
+            .put(
+                serialize(&superblock_key(0)).unwrap(),
+                serialize(&superblock).unwrap(),
+            )
+            .unwrap();
+        let verification = verify_stored_chain(&storage, genesis_hash, 0).unwrap();
+        match verification.first_inconsistency {
+            Some((SUPERBLOCK_PERIOD, ChainInconsistency::SuperblockMismatch(0))) => {}
+            other => panic!("unexpected inconsistency: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_blocks() {
+        let genesis_hash = Hash::SHA256([1; 32]);
+        let block = signed_block(1, genesis_hash);
+        let hash = block.hash();
+        assert!(verify_block(&block, 1, hash, genesis_hash).is_ok());
+
+        match verify_block(&block, 1, genesis_hash, genesis_hash) {
+            Err(ChainInconsistency::HashMismatch { expected, found }) => {
+                assert_eq!(expected, genesis_hash);
+                assert_eq!(found, hash);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match verify_block(&block, 2, hash, genesis_hash) {
+            Err(ChainInconsistency::EpochMismatch(1)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // Changing the transactions does not change the block hash, but the merkle roots
+        let mut tampered = block.clone();
+        tampered.txns.mint = MintTransaction::new(2, ValueTransferOutput::default());
+        match verify_block(&tampered, 1, hash, genesis_hash) {
+            Err(ChainInconsistency::InvalidMerkleRoots) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut tampered = block.clone();
+        tampered.block_sig.signature = signed_block(2, genesis_hash).block_sig.signature;
+        match verify_block(&tampered, 1, hash, genesis_hash) {
+            Err(ChainInconsistency::InvalidSignature(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
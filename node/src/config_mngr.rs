@@ -24,6 +24,13 @@ pub fn set(config: Config) -> impl Future<Item = (), Error = failure::Error> {
     addr.send(Set(config)).flatten()
 }
 
+/// Subscribe to the changes of the configuration: the given recipient gets a
+/// `ConfigChanged` message with every configuration later set or loaded in the manager
+pub fn subscribe(recipient: actix::Recipient<ConfigChanged>) {
+    let addr = actix::System::current().registry().get::<ConfigManager>();
+    addr.do_send(Subscribe(recipient));
+}
+
 /// Substitute configuration in the manager with the one loaded from the
 /// given filename.
 pub fn load_from_file(filename: PathBuf) -> impl Future<Item = (), Error = failure::Error> {
@@ -36,10 +43,10 @@ pub fn load_from_file(filename: PathBuf) -> impl Future<Item = (), Error = failu
 /// This actor is in charge of reading the configuration for the
 /// application from a given source and using a given format, and
 /// supports messages for giving access to the configuration it holds.
-#[derive(Debug)]
 struct ConfigManager {
     config: Arc<Config>,
     config_source: Source,
+    subscribers: Vec<actix::Recipient<ConfigChanged>>,
 }
 
 /// Message to obtain a reference to the configuration managed by the
@@ -53,6 +60,14 @@ struct Set(Config);
 /// Message to load additional configuration from a source.
 struct Load(Source);
 
+/// Message to subscribe to the changes of the configuration managed by the
+/// `ConfigManager` actor.
+struct Subscribe(actix::Recipient<ConfigChanged>);
+
+/// Message sent to the subscribers of the `ConfigManager` actor with the new
+/// configuration, every time it changes.
+pub struct ConfigChanged(pub Arc<Config>);
+
 /// Different kinds of configuration sources
 #[derive(Debug)]
 enum Source {
@@ -67,6 +82,7 @@ impl Default for ConfigManager {
         Self {
             config: Arc::new(Default::default()),
             config_source: Source::Default,
+            subscribers: vec![],
         }
     }
 }
@@ -95,6 +111,14 @@ impl actix::Message for Load {
     type Result = Result<(), failure::Error>;
 }
 
+impl actix::Message for Subscribe {
+    type Result = ();
+}
+
+impl actix::Message for ConfigChanged {
+    type Result = ();
+}
+
 impl actix::Handler<Get> for ConfigManager {
     type Result = <Get as actix::Message>::Result;
 
@@ -107,9 +131,15 @@ impl actix::Handler<Set> for ConfigManager {
     type Result = <Set as actix::Message>::Result;
 
     fn handle(&mut self, Set(config): Set, _ctx: &mut Self::Context) -> Self::Result {
-        self.config = Arc::new(config);
+        self.set_config(config)
+    }
+}
 
-        Ok(())
+impl actix::Handler<Subscribe> for ConfigManager {
+    type Result = ();
+
+    fn handle(&mut self, Subscribe(recipient): Subscribe, _ctx: &mut Self::Context) {
+        self.subscribers.push(recipient);
     }
 }
 
@@ -140,7 +170,18 @@ impl ConfigManager {
             Source::File(filename) => Config::from_partial(&toml::from_file(filename)?),
         };
 
-        self.config = Arc::new(new_config);
+        self.set_config(new_config)
+    }
+
+    /// Replace the configuration with a valid one and notify the subscribers, forgetting those
+    /// that are gone
+    fn set_config(&mut self, config: Config) -> Result<(), failure::Error> {
+        config.validate()?;
+        self.config = Arc::new(config);
+
+        let config = &self.config;
+        self.subscribers
+            .retain(|subscriber| subscriber.do_send(ConfigChanged(config.clone())).is_ok());
 
         Ok(())
     }
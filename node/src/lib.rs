@@ -10,6 +10,8 @@
 /// Actors module
 pub mod actors;
 
+pub mod chain_verifier;
+
 /// Config Manager Actor API
 pub mod config_mngr;
 pub mod signature_mngr;
@@ -3,13 +3,25 @@
 //! This module provides a Signature Manager, which, after being
 //! initialized with a key, can be used repeatedly to sign data with
 //! that key.
+//!
+//! The block candidates signed by the node are also recorded in an
+//! append-only signing log, one JSON entry per line, so that signing the
+//! same kind of item twice for an epoch can be analyzed afterwards.
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
 use actix::prelude::*;
 use failure;
 use failure::bail;
 use futures::future::Future;
 use log;
+use serde::{Deserialize, Serialize};
 
-use crate::{actors::storage_keys::MASTER_KEY, storage_mngr};
+use crate::{actors::storage_keys::MASTER_KEY, config_mngr, storage_mngr};
 
 use witnet_crypto::{
     key::{ExtendedSK, MasterKeyGen, SignEngine, PK, SK},
@@ -18,12 +30,16 @@ use witnet_crypto::{
 };
 use witnet_data_structures::{
     chain::{
-        ExtendedSecretKey, Hash, Hashable, KeyedSignature, PublicKey, PublicKeyHash, SecretKey,
-        Signature,
+        Epoch, ExtendedSecretKey, Hash, Hashable, KeyedSignature, PublicKey, PublicKeyHash,
+        SecretKey, Signature,
     },
     vrf::{VrfCtx, VrfMessage, VrfProof},
 };
 use witnet_protected::ProtectedString;
+use witnet_util::timestamp::get_timestamp;
+
+/// Name of the signing log file, inside the database directory
+pub const SIGNING_LOG_FILE: &str = "signing_log.jsonl";
 
 /// Start the signature manager
 pub fn start() {
@@ -61,6 +77,42 @@ pub fn sign_data(data: [u8; 32]) -> impl Future<Item = KeyedSignature, Error = f
     addr.send(Sign(data.to_vec())).flatten()
 }
 
+/// Sign a piece of (Hashable) data with the stored key, recording the
+/// signature in the signing log.
+///
+/// This might fail if the manager has not been initialized with a key
+pub fn sign_logged<T>(
+    kind: SignedItemKind,
+    epoch: Epoch,
+    data: &T,
+) -> impl Future<Item = KeyedSignature, Error = failure::Error>
+where
+    T: Hashable,
+{
+    let addr = actix::System::current()
+        .registry()
+        .get::<SignatureManager>();
+    addr.send(SignLogged {
+        kind,
+        epoch,
+        hash: data.hash(),
+    })
+    .flatten()
+}
+
+/// Get the entries of the signing log for the given epoch or later, the
+/// most recent ones last. If `limit` is not 0, only the last `limit`
+/// entries are returned
+pub fn signing_log(
+    since_epoch: Epoch,
+    limit: usize,
+) -> impl Future<Item = Vec<SigningLogEntry>, Error = failure::Error> {
+    let addr = actix::System::current()
+        .registry()
+        .get::<SignatureManager>();
+    addr.send(GetSigningLog { since_epoch, limit }).flatten()
+}
+
 /// Get the public key hash.
 ///
 /// This might fail if the manager has not been initialized with a key
@@ -91,12 +143,43 @@ pub fn vrf_prove(
     addr.send(VrfProve(message)).flatten()
 }
 
+/// Kind of the items whose signatures are recorded in the signing log
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignedItemKind {
+    /// Header of a block candidate mined by the node
+    BlockCandidate,
+}
+
+/// Entry of the signing log
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningLogEntry {
+    /// Kind of the signed item
+    pub kind: SignedItemKind,
+    /// Epoch of the signed item
+    pub epoch: Epoch,
+    /// Hash of the signed item
+    pub hash: Hash,
+    /// Signature of the hash
+    pub signature: KeyedSignature,
+    /// Timestamp in seconds of the signature
+    pub timestamp: i64,
+}
+
 #[derive(Debug, Default)]
 struct SignatureManager {
     /// Secret and public key
     keypair: Option<(SK, PK)>,
     /// VRF context
     vrf_ctx: Option<VrfCtx>,
+    /// Path of the signing log
+    signing_log: Option<PathBuf>,
+    /// Entries of the signing log, the most recent ones last, so they are not read from the file
+    /// every time they are queried
+    signing_log_entries: Vec<SigningLogEntry>,
+    /// Last epoch and hash signed of every kind of item, to detect double signing
+    last_signed: HashMap<SignedItemKind, (Epoch, Hash)>,
 }
 
 impl SignatureManager {
@@ -105,23 +188,141 @@ impl SignatureManager {
         self.keypair = Some((key, public_key));
         log::debug!("Signature Manager received a key and is ready to sign");
     }
+
+    fn sign(&self, data: &[u8]) -> Result<KeyedSignature, failure::Error> {
+        match self.keypair {
+            Some((secret, public)) => {
+                let signature = signature::sign(secret, data);
+                let keyed_signature = KeyedSignature {
+                    signature: Signature::from(signature),
+                    public_key: PublicKey::from(public),
+                };
+
+                Ok(keyed_signature)
+            }
+            None => bail!("Signature Manager cannot sign because it contains no key"),
+        }
+    }
+
+    fn set_signing_log(&mut self, path: PathBuf) {
+        match read_signing_log(&path) {
+            Ok(entries) => {
+                for entry in &entries {
+                    self.last_signed
+                        .insert(entry.kind, (entry.epoch, entry.hash));
+                }
+                self.signing_log_entries = entries;
+            }
+            Err(e) => log::error!("Couldn't read the signing log {:?}: {}", path, e),
+        }
+        self.signing_log = Some(path);
+    }
+
+    /// Record a signature in the signing log.
+    ///
+    /// Returns the hash signed before for the same kind of item and epoch, if it is a different
+    /// one, i.e. if the item has been double signed.
+    fn record_signature(&mut self, entry: SigningLogEntry) -> Option<Hash> {
+        let double_signed = match self.last_signed.get(&entry.kind) {
+            Some((epoch, hash)) if *epoch == entry.epoch && *hash != entry.hash => {
+                log::warn!(
+                    "Signing a second {:?} for epoch {}: {} after {}",
+                    entry.kind,
+                    entry.epoch,
+                    entry.hash,
+                    hash
+                );
+
+                Some(*hash)
+            }
+            _ => None,
+        };
+        self.last_signed
+            .insert(entry.kind, (entry.epoch, entry.hash));
+
+        if let Some(path) = &self.signing_log {
+            if let Err(e) = append_signing_log(path, &entry) {
+                log::error!("Couldn't record signature in the signing log: {}", e);
+            }
+        }
+        self.signing_log_entries.push(entry);
+
+        double_signed
+    }
+
+    /// Entries of the signing log for the given epoch or later, the most recent ones last. If
+    /// `limit` is not 0, only the last `limit` entries are returned
+    fn signing_log_entries(&self, since_epoch: Epoch, limit: usize) -> Vec<SigningLogEntry> {
+        let mut entries: Vec<SigningLogEntry> = self
+            .signing_log_entries
+            .iter()
+            .filter(|entry| entry.epoch >= since_epoch)
+            .cloned()
+            .collect();
+        if limit > 0 && entries.len() > limit {
+            entries.drain(..entries.len() - limit);
+        }
+
+        entries
+    }
+}
+
+fn read_signing_log(path: &Path) -> Result<Vec<SigningLogEntry>, failure::Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut entries = vec![];
+    for line in BufReader::new(file).lines() {
+        // An interrupted write can leave a truncated last line
+        match serde_json::from_str(&line?) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => log::warn!("Skipping invalid entry of the signing log: {}", e),
+        }
+    }
+
+    Ok(entries)
+}
+
+fn append_signing_log(path: &Path, entry: &SigningLogEntry) -> Result<(), failure::Error> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(&line)?;
+    file.sync_data()?;
+
+    Ok(())
 }
 
 struct SetKey(SK);
 struct Sign(Vec<u8>);
+struct SignLogged {
+    kind: SignedItemKind,
+    epoch: Epoch,
+    hash: Hash,
+}
+struct GetSigningLog {
+    since_epoch: Epoch,
+    limit: usize,
+}
 struct GetPkh;
 struct GetPublicKey;
 struct VrfProve(VrfMessage);
 
-fn persist_master_key(master_key: ExtendedSK) -> impl Future<Item = (), Error = failure::Error> {
+fn persist_master_key(
+    identity_file: PathBuf,
+    master_key: ExtendedSK,
+) -> impl Future<Item = (), Error = failure::Error> {
     let master_key = ExtendedSecretKey::from(master_key);
 
-    storage_mngr::put(&MASTER_KEY, &master_key).inspect(|_| {
-        log::debug!("Successfully persisted the extended secret key into storage");
+    storage_mngr::put_identity(identity_file, &master_key).inspect(|_| {
+        log::debug!("Successfully persisted the extended secret key into the identity file");
     })
 }
 
-fn create_master_key() -> Box<dyn Future<Item = SK, Error = failure::Error>> {
+fn create_master_key(identity_file: PathBuf) -> Box<dyn Future<Item = SK, Error = failure::Error>> {
     log::info!("Generating and persisting a new master key for this node");
 
     // Create a new master key
@@ -129,7 +330,8 @@ fn create_master_key() -> Box<dyn Future<Item = SK, Error = failure::Error>> {
     let seed = mnemonic.seed(&ProtectedString::new(""));
     match MasterKeyGen::new(seed).generate() {
         Ok(master_key) => {
-            let fut = persist_master_key(master_key.clone()).map(move |_| master_key.into());
+            let fut = persist_master_key(identity_file, master_key.clone())
+                .map(move |_| master_key.into());
 
             Box::new(fut)
         }
@@ -141,6 +343,54 @@ fn create_master_key() -> Box<dyn Future<Item = SK, Error = failure::Error>> {
     }
 }
 
+/// Load the master key from the identity file. If there is no identity file, the master key
+/// stored in the database by previous versions is moved into it, and if there is none either, a
+/// new master key is created.
+fn load_master_key(identity_file: PathBuf) -> Box<dyn Future<Item = SK, Error = failure::Error>> {
+    let fut = storage_mngr::get_identity::<ExtendedSecretKey>(identity_file.clone()).and_then(
+        move |master_key_from_file| match master_key_from_file {
+            Some(master_key) => {
+                let master_key: ExtendedSK = master_key.into();
+                let fut = futures::future::ok(master_key.into());
+
+                Box::new(fut) as Box<dyn Future<Item = SK, Error = failure::Error>>
+            }
+            None => migrate_master_key(identity_file),
+        },
+    );
+
+    Box::new(fut)
+}
+
+/// Move the master key stored in the database into the identity file, or create a new one
+fn migrate_master_key(
+    identity_file: PathBuf,
+) -> Box<dyn Future<Item = SK, Error = failure::Error>> {
+    let fut = storage_mngr::get_sealed::<_, ExtendedSecretKey>(&MASTER_KEY).and_then(
+        move |master_key_from_storage| match master_key_from_storage {
+            Some(master_key) => {
+                log::info!(
+                    "Moving the master key from the database into the identity file {}",
+                    identity_file.display()
+                );
+                let fut = storage_mngr::put_identity(identity_file, &master_key)
+                    // The key is only removed from the database once the identity file is written
+                    .and_then(|_| storage_mngr::delete_sealed(&MASTER_KEY))
+                    .map(move |_| {
+                        let master_key: ExtendedSK = master_key.into();
+
+                        master_key.into()
+                    });
+
+                Box::new(fut) as Box<dyn Future<Item = SK, Error = failure::Error>>
+            }
+            None => create_master_key(identity_file),
+        },
+    );
+
+    Box::new(fut)
+}
+
 impl Actor for SignatureManager {
     type Context = Context<Self>;
 
@@ -155,19 +405,18 @@ impl Actor for SignatureManager {
             })
             .ok();
 
-        storage_mngr::get::<_, ExtendedSecretKey>(&MASTER_KEY)
-            .and_then(move |master_key_from_storage| {
-                master_key_from_storage.map_or_else(create_master_key, |master_key| {
-                    let master_key: ExtendedSK = master_key.into();
-                    let fut = futures::future::ok(master_key.into());
+        config_mngr::get()
+            .and_then(|config| {
+                let signing_log = config.storage.db_path.join(SIGNING_LOG_FILE);
 
-                    Box::new(fut)
-                })
+                load_master_key(config.storage.identity_file.clone())
+                    .map(move |secret_key| (secret_key, signing_log))
             })
             .map_err(|e| log::error!("Couldn't initialize Signature Manager: {}", e))
             .into_actor(self)
-            .map(|secret_key, act, _ctx| {
+            .map(|(secret_key, signing_log), act, _ctx| {
                 act.set_key(secret_key);
+                act.set_signing_log(signing_log);
             })
             .wait(ctx);
     }
@@ -185,6 +434,14 @@ impl Message for Sign {
     type Result = Result<KeyedSignature, failure::Error>;
 }
 
+impl Message for SignLogged {
+    type Result = Result<KeyedSignature, failure::Error>;
+}
+
+impl Message for GetSigningLog {
+    type Result = Result<Vec<SigningLogEntry>, failure::Error>;
+}
+
 impl Message for GetPkh {
     type Result = Result<PublicKeyHash, failure::Error>;
 }
@@ -211,18 +468,33 @@ impl Handler<Sign> for SignatureManager {
     type Result = <Sign as Message>::Result;
 
     fn handle(&mut self, Sign(data): Sign, _ctx: &mut Self::Context) -> Self::Result {
-        match self.keypair {
-            Some((secret, public)) => {
-                let signature = signature::sign(secret, &data);
-                let keyed_signature = KeyedSignature {
-                    signature: Signature::from(signature),
-                    public_key: PublicKey::from(public),
-                };
+        self.sign(&data)
+    }
+}
 
-                Ok(keyed_signature)
-            }
-            None => bail!("Signature Manager cannot sign because it contains no key"),
-        }
+impl Handler<SignLogged> for SignatureManager {
+    type Result = <SignLogged as Message>::Result;
+
+    fn handle(&mut self, msg: SignLogged, _ctx: &mut Self::Context) -> Self::Result {
+        let Hash::SHA256(data) = msg.hash;
+        let signature = self.sign(&data)?;
+        self.record_signature(SigningLogEntry {
+            kind: msg.kind,
+            epoch: msg.epoch,
+            hash: msg.hash,
+            signature: signature.clone(),
+            timestamp: get_timestamp(),
+        });
+
+        Ok(signature)
+    }
+}
+
+impl Handler<GetSigningLog> for SignatureManager {
+    type Result = <GetSigningLog as Message>::Result;
+
+    fn handle(&mut self, msg: GetSigningLog, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(self.signing_log_entries(msg.since_epoch, msg.limit))
     }
 }
 
@@ -256,6 +528,7 @@ impl Handler<VrfProve> for SignatureManager {
             Self {
                 keypair: Some((secret, _public)),
                 vrf_ctx: Some(vrf),
+                ..
             } => {
                 // This conversion is cheap, it's just a memcpy
                 let sk = SecretKey::from(*secret);
@@ -272,3 +545,113 @@ impl Handler<VrfProve> for SignatureManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn signing_log_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "witnet_signing_log_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        path
+    }
+
+    fn signature_manager() -> SignatureManager {
+        let mut manager = SignatureManager::default();
+        manager.set_key(SK::from_slice(&[0xcd; 32]).expect("32 bytes, within curve order"));
+
+        manager
+    }
+
+    fn entry(manager: &SignatureManager, epoch: Epoch, hash: Hash) -> SigningLogEntry {
+        let Hash::SHA256(data) = hash;
+
+        SigningLogEntry {
+            kind: SignedItemKind::BlockCandidate,
+            epoch,
+            hash,
+            signature: manager.sign(&data).unwrap(),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn double_signing_is_detected() {
+        let mut manager = signature_manager();
+        let (first, second) = (Hash::SHA256([1; 32]), Hash::SHA256([2; 32]));
+
+        assert_eq!(manager.record_signature(entry(&manager, 10, first)), None);
+        // Signing the same item again is not a double signature
+        assert_eq!(manager.record_signature(entry(&manager, 10, first)), None);
+        assert_eq!(
+            manager.record_signature(entry(&manager, 10, second)),
+            Some(first)
+        );
+        assert_eq!(manager.record_signature(entry(&manager, 11, first)), None);
+    }
+
+    #[test]
+    fn signing_log_is_filtered_by_epoch_and_limit() {
+        let mut manager = signature_manager();
+        let entries: Vec<SigningLogEntry> = (10..15)
+            .map(|epoch| entry(&manager, epoch, Hash::SHA256([epoch as u8; 32])))
+            .collect();
+        for entry in &entries {
+            manager.record_signature(entry.clone());
+        }
+
+        assert_eq!(manager.signing_log_entries(0, 0), entries);
+        assert_eq!(manager.signing_log_entries(12, 0), entries[2..].to_vec());
+        assert_eq!(manager.signing_log_entries(0, 2), entries[3..].to_vec());
+        assert_eq!(manager.signing_log_entries(13, 5), entries[3..].to_vec());
+        assert!(manager.signing_log_entries(15, 0).is_empty());
+    }
+
+    #[test]
+    fn signing_log_is_kept_across_restarts() {
+        let path = signing_log_path("restarts");
+        let mut manager = signature_manager();
+        manager.set_signing_log(path.clone());
+        let first = entry(&manager, 10, Hash::SHA256([1; 32]));
+        let second = entry(&manager, 11, Hash::SHA256([2; 32]));
+        manager.record_signature(first.clone());
+        manager.record_signature(second.clone());
+
+        let mut restarted = signature_manager();
+        restarted.set_signing_log(path.clone());
+        assert_eq!(
+            restarted.signing_log_entries(0, 0),
+            vec![first, second.clone()]
+        );
+        // Double signatures of the items signed before restarting are detected too
+        assert_eq!(
+            restarted.record_signature(entry(&restarted, 11, Hash::SHA256([3; 32]))),
+            Some(second.hash)
+        );
+        assert_eq!(read_signing_log(&path).unwrap().len(), 3);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn truncated_entries_of_the_signing_log_are_skipped() {
+        let path = signing_log_path("truncated");
+        let manager = signature_manager();
+        let first = entry(&manager, 10, Hash::SHA256([1; 32]));
+        append_signing_log(&path, &first).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"kind\":\"blockCandi").unwrap();
+
+        assert_eq!(read_signing_log(&path).unwrap(), vec![first]);
+        // A missing signing log is empty
+        let _ = fs::remove_file(&path);
+        assert!(read_signing_log(&path).unwrap().is_empty());
+    }
+}
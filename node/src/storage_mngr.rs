@@ -1,17 +1,66 @@
 //! # Storage Manager
 //!
 //! This module provides a Storage Manager
-use std::sync::Arc;
+//!
+//! Sensitive records, like the peers database, are stored with `get_sealed` and
+//! `put_sealed`. If a storage passphrase is provided, through the `WITNET_STORAGE_PASSPHRASE`
+//! environment variable or the `storage.passphrase_file` setting, they are encrypted at rest with
+//! a key derived from it, which is only kept in memory.
+//!
+//! The master key of the node is not kept in the database, but in its own identity file, so it
+//! can be backed up alone and the database can be wiped without losing it. The identity file is
+//! also encrypted if a storage passphrase is provided, with a key derived from a salt kept in the
+//! file itself, so it does not depend on the database.
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
 
 use actix::prelude::*;
 use bincode::{deserialize, serialize};
+use failure::{bail, format_err};
 use futures::future::Future;
 use log;
-use serde;
+use serde::{self, Deserialize, Serialize};
 
 use crate::config_mngr;
 use witnet_config::config;
+use witnet_crypto::{cipher, pbkdf2::pbkdf2_sha256};
+use witnet_protected::Protected;
 use witnet_storage::{backends, storage};
+use witnet_util::crash;
+
+/// Maximum time to wait for the storage to be flushed after a panic
+const CRASH_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Environment variable holding the passphrase used to encrypt the sensitive records. It takes
+/// precedence over `storage.passphrase_file`
+pub const PASSPHRASE_ENV_VAR: &str = "WITNET_STORAGE_PASSPHRASE";
+
+/// Prefix of the storage keys of the encrypted sensitive records
+const SEALED_PREFIX: &[u8] = b"sealed-";
+
+/// Storage key of the salt used to derive the encryption key from the passphrase
+const SEALING_SALT_KEY: &[u8] = b"sealing-salt";
+
+/// Storage key of a known value encrypted with the encryption key, used to check the passphrase
+const SEALING_CHECK_KEY: &[u8] = b"sealing-check";
+
+const SEALING_CHECK_VALUE: &[u8] = b"witnet";
+
+const SEALING_SALT_LENGTH: usize = 32;
+
+const SEALING_IV_LENGTH: usize = 16;
+
+const SEALING_HASH_ITERATIONS: u32 = 10_000;
+
+/// Version of the format of the identity file. Version 1 files were encrypted with the key of the
+/// database records, and version 2 files keep their own salt and check value
+const IDENTITY_FILE_VERSION: u32 = 2;
 
 macro_rules! as_failure {
     ($e:expr) => {
@@ -47,6 +96,47 @@ where
         })
 }
 
+/// Get value associated to key, decoding it with the layout `L` of older versions of the value if
+/// it cannot be decoded with the current layout `T`
+pub fn get_or_legacy<K, T, L>(key: &K) -> impl Future<Item = Option<T>, Error = failure::Error>
+where
+    K: serde::Serialize,
+    T: serde::de::DeserializeOwned,
+    L: serde::de::DeserializeOwned + Into<T>,
+{
+    let addr = actix::System::current()
+        .registry()
+        .get::<StorageManagerAdapter>();
+
+    futures::future::result(serialize(key))
+        .map_err(|e| as_failure!(e))
+        .and_then(move |key_bytes| addr.send(Get(key_bytes)).flatten())
+        .and_then(|opt| match opt {
+            Some(bytes) => match deserialize_or_legacy::<T, L>(bytes.as_slice()) {
+                Ok(v) => futures::future::ok(Some(v)),
+                Err(e) => futures::future::err(as_failure!(e)),
+            },
+            None => futures::future::ok(None),
+        })
+}
+
+/// Decode a value with its current layout `T`, or with the layout `L` of older versions of the
+/// value if that fails. The error of the current layout is returned if both fail.
+pub fn deserialize_or_legacy<T, L>(bytes: &[u8]) -> Result<T, bincode::Error>
+where
+    T: serde::de::DeserializeOwned,
+    L: serde::de::DeserializeOwned + Into<T>,
+{
+    deserialize::<T>(bytes).or_else(|err| match deserialize::<L>(bytes) {
+        Ok(legacy) => {
+            log::info!("Decoded a value stored by an older version, upgrading its layout");
+
+            Ok(legacy.into())
+        }
+        Err(_) => Err(err),
+    })
+}
+
 /// Put a value associated to the key into the storage
 pub fn put<K, V>(key: &K, value: &V) -> impl Future<Item = (), Error = failure::Error>
 where
@@ -63,6 +153,95 @@ where
         .and_then(move |(key_bytes, value_bytes)| addr.send(Put(key_bytes, value_bytes)).flatten())
 }
 
+/// Get the value associated to the key of a sensitive record, see `put_sealed`
+pub fn get_sealed<K, T>(key: &K) -> impl Future<Item = Option<T>, Error = failure::Error>
+where
+    K: serde::Serialize,
+    T: serde::de::DeserializeOwned,
+{
+    let addr = actix::System::current()
+        .registry()
+        .get::<StorageManagerAdapter>();
+
+    futures::future::result(serialize(key))
+        .map_err(|e| as_failure!(e))
+        .and_then(move |key_bytes| addr.send(GetSealed(key_bytes)).flatten())
+        .and_then(|opt| match opt {
+            Some(bytes) => match deserialize(bytes.as_slice()) {
+                Ok(v) => futures::future::ok(Some(v)),
+                Err(e) => futures::future::err(as_failure!(e)),
+            },
+            None => futures::future::ok(None),
+        })
+}
+
+/// Put a sensitive record into the storage, encrypted if a storage passphrase is provided
+pub fn put_sealed<K, V>(key: &K, value: &V) -> impl Future<Item = (), Error = failure::Error>
+where
+    K: serde::Serialize,
+    V: serde::Serialize,
+{
+    let addr = actix::System::current()
+        .registry()
+        .get::<StorageManagerAdapter>();
+
+    futures::future::result(serialize(key))
+        .join(futures::future::result(serialize(value)))
+        .map_err(|e| as_failure!(e))
+        .and_then(move |(key_bytes, value_bytes)| {
+            addr.send(PutSealed(key_bytes, value_bytes)).flatten()
+        })
+}
+
+/// Delete a sensitive record stored with `put_sealed`, whether it is encrypted or not
+pub fn delete_sealed<K>(key: &K) -> impl Future<Item = (), Error = failure::Error>
+where
+    K: serde::Serialize,
+{
+    let addr = actix::System::current()
+        .registry()
+        .get::<StorageManagerAdapter>();
+
+    futures::future::result(serialize(key))
+        .map_err(|e| as_failure!(e))
+        .and_then(move |key_bytes| addr.send(DeleteSealed(key_bytes)).flatten())
+}
+
+/// Read the identity of the node from its file, see `put_identity`
+pub fn get_identity<T>(path: PathBuf) -> impl Future<Item = Option<T>, Error = failure::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let addr = actix::System::current()
+        .registry()
+        .get::<StorageManagerAdapter>();
+
+    addr.send(ReadIdentity(path))
+        .flatten()
+        .and_then(|opt| match opt {
+            Some(bytes) => match deserialize(bytes.as_slice()) {
+                Ok(v) => futures::future::ok(Some(v)),
+                Err(e) => futures::future::err(as_failure!(e)),
+            },
+            None => futures::future::ok(None),
+        })
+}
+
+/// Write the identity of the node into its own file, only accessible by its owner, and encrypted
+/// if a storage passphrase is provided
+pub fn put_identity<V>(path: PathBuf, value: &V) -> impl Future<Item = (), Error = failure::Error>
+where
+    V: serde::Serialize,
+{
+    let addr = actix::System::current()
+        .registry()
+        .get::<StorageManagerAdapter>();
+
+    futures::future::result(serialize(value))
+        .map_err(|e| as_failure!(e))
+        .and_then(move |value_bytes| addr.send(WriteIdentity(path, value_bytes)).flatten())
+}
+
 /// Delete value associated to key
 pub fn delete<K>(key: &K) -> impl Future<Item = (), Error = failure::Error>
 where
@@ -79,12 +258,18 @@ where
 
 struct StorageManager {
     backend: Box<dyn storage::Storage>,
+    /// Key used to encrypt the sensitive records, if a storage passphrase is provided
+    sealing_key: Option<Protected>,
+    /// Storage passphrase, used to derive the key of the identity file from its own salt
+    passphrase: Option<Protected>,
 }
 
 impl Default for StorageManager {
     fn default() -> Self {
         StorageManager {
             backend: Box::new(backends::nobackend::Backend),
+            sealing_key: None,
+            passphrase: None,
         }
     }
 }
@@ -104,9 +289,13 @@ impl Handler<Configure> for StorageManager {
 
     fn handle(&mut self, Configure(conf): Configure, _ctx: &mut Self::Context) -> Self::Result {
         let storage_conf = &conf.storage;
-        let backend = create_appropriate_backend(storage_conf)?;
+        let mut backend = create_appropriate_backend(storage_conf)?;
+        let passphrase = read_passphrase(storage_conf)?;
+        let sealing_key = derive_sealing_key(backend.as_mut(), passphrase.clone())?;
 
         self.backend = backend;
+        self.sealing_key = sealing_key;
+        self.passphrase = passphrase;
         log::info!(
             "Configured {:#?} as the storage backend",
             storage_conf.backend
@@ -114,6 +303,9 @@ impl Handler<Configure> for StorageManager {
         if storage_conf.password.is_some() {
             log::info!("Storage backend is using encryption");
         }
+        if self.sealing_key.is_some() {
+            log::info!("Sensitive storage records are encrypted with the storage passphrase");
+        }
 
         Ok(())
     }
@@ -161,6 +353,153 @@ impl Handler<Delete> for StorageManager {
     }
 }
 
+struct GetSealed(Vec<u8>);
+
+impl Message for GetSealed {
+    type Result = Result<Option<Vec<u8>>, failure::Error>;
+}
+
+impl Handler<GetSealed> for StorageManager {
+    type Result = <GetSealed as Message>::Result;
+
+    fn handle(&mut self, GetSealed(key): GetSealed, _ctx: &mut Self::Context) -> Self::Result {
+        let sealing_key = match &self.sealing_key {
+            Some(sealing_key) => sealing_key,
+            None => return self.backend.get(key.as_ref()),
+        };
+
+        match self.backend.get(&sealed_key(&key))? {
+            Some(sealed) => unseal(sealing_key, &sealed).map(Some),
+            None => {
+                // Records stored before the passphrase was provided are encrypted when first read
+                let value = self.backend.get(key.as_ref())?;
+                if let Some(value) = &value {
+                    log::info!("Encrypting sensitive storage record");
+                    self.backend
+                        .put(sealed_key(&key), seal(sealing_key, value)?)?;
+                    self.backend.delete(key.as_ref())?;
+                }
+
+                Ok(value)
+            }
+        }
+    }
+}
+
+struct PutSealed(Vec<u8>, Vec<u8>);
+
+impl Message for PutSealed {
+    type Result = Result<(), failure::Error>;
+}
+
+impl Handler<PutSealed> for StorageManager {
+    type Result = <PutSealed as Message>::Result;
+
+    fn handle(
+        &mut self,
+        PutSealed(key, value): PutSealed,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        match &self.sealing_key {
+            Some(sealing_key) => {
+                self.backend
+                    .put(sealed_key(&key), seal(sealing_key, &value)?)?;
+                self.backend.delete(key.as_ref())
+            }
+            None => self.backend.put(key, value),
+        }
+    }
+}
+
+struct DeleteSealed(Vec<u8>);
+
+impl Message for DeleteSealed {
+    type Result = Result<(), failure::Error>;
+}
+
+impl Handler<DeleteSealed> for StorageManager {
+    type Result = <DeleteSealed as Message>::Result;
+
+    fn handle(
+        &mut self,
+        DeleteSealed(key): DeleteSealed,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.backend.delete(&sealed_key(&key))?;
+        self.backend.delete(key.as_ref())
+    }
+}
+
+struct ReadIdentity(PathBuf);
+
+impl Message for ReadIdentity {
+    type Result = Result<Option<Vec<u8>>, failure::Error>;
+}
+
+impl Handler<ReadIdentity> for StorageManager {
+    type Result = <ReadIdentity as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ReadIdentity(path): ReadIdentity,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        read_identity_file(&path, self.passphrase.as_ref(), self.sealing_key.as_ref())
+    }
+}
+
+struct WriteIdentity(PathBuf, Vec<u8>);
+
+impl Message for WriteIdentity {
+    type Result = Result<(), failure::Error>;
+}
+
+impl Handler<WriteIdentity> for StorageManager {
+    type Result = <WriteIdentity as Message>::Result;
+
+    fn handle(
+        &mut self,
+        WriteIdentity(path, value): WriteIdentity,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        write_identity_file(&path, &value, self.passphrase.as_ref())
+    }
+}
+
+struct Flush;
+
+impl Message for Flush {
+    type Result = Result<(), failure::Error>;
+}
+
+impl Handler<Flush> for StorageManager {
+    type Result = <Flush as Message>::Result;
+
+    fn handle(&mut self, _msg: Flush, _ctx: &mut Self::Context) -> Self::Result {
+        self.backend.flush()
+    }
+}
+
+/// Flush handler used by the crash reports.
+///
+/// The flush is requested from a new thread, because the panic may have happened in the storage
+/// thread itself, and it is only waited for a limited time.
+fn crash_flush_handler(storage: Addr<StorageManager>) -> crash::FlushHandler {
+    Box::new(move || {
+        let storage = storage.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(storage.send(Flush).flatten().wait());
+        });
+
+        match rx.recv_timeout(CRASH_FLUSH_TIMEOUT) {
+            Ok(Ok(())) => log::info!("Storage flushed"),
+            Ok(Err(e)) => log::error!("Failed to flush storage: {}", e),
+            Err(_) => log::error!("Timed out while flushing storage"),
+        }
+    })
+}
+
 macro_rules! encrypted_backend {
     ($backend:expr, $password_opt:expr) => {
         if let Some(password) = $password_opt {
@@ -172,7 +511,7 @@ macro_rules! encrypted_backend {
     };
 }
 
-fn create_appropriate_backend(
+pub(crate) fn create_appropriate_backend(
     conf: &config::Storage,
 ) -> Result<Box<dyn storage::Storage>, failure::Error> {
     let passwd = conf.password.clone();
@@ -192,6 +531,280 @@ fn create_appropriate_backend(
     }
 }
 
+/// Read the storage passphrase from the environment or from the configured file, if any. The
+/// environment variable is removed once read
+fn read_passphrase(conf: &config::Storage) -> Result<Option<Protected>, failure::Error> {
+    if let Some(passphrase) = env::var_os(PASSPHRASE_ENV_VAR) {
+        env::remove_var(PASSPHRASE_ENV_VAR);
+        let passphrase = passphrase
+            .into_string()
+            .map_err(|_| format_err!("{} is not valid UTF-8", PASSPHRASE_ENV_VAR))?;
+
+        return Ok(Some(Protected::new(passphrase)));
+    }
+
+    match &conf.passphrase_file {
+        Some(path) => {
+            let mut passphrase = fs::read_to_string(path).map_err(|e| {
+                format_err!(
+                    "Failed to read the storage passphrase file {}: {}",
+                    path.display(),
+                    e
+                )
+            })?;
+            let len = passphrase.trim_end_matches(&['\r', '\n'][..]).len();
+            passphrase.truncate(len);
+
+            Ok(Some(Protected::new(passphrase)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Derive the key used to encrypt the sensitive records from the storage passphrase, checking
+/// that it is the same passphrase used to encrypt the records already stored
+fn derive_sealing_key(
+    backend: &mut dyn storage::Storage,
+    passphrase: Option<Protected>,
+) -> Result<Option<Protected>, failure::Error> {
+    let check = backend.get(SEALING_CHECK_KEY)?;
+    let passphrase = match passphrase {
+        Some(passphrase) => passphrase,
+        None if check.is_some() => bail!(
+            "The storage contains encrypted records, but no storage passphrase was provided \
+             through {} or storage.passphrase_file",
+            PASSPHRASE_ENV_VAR
+        ),
+        None => return Ok(None),
+    };
+
+    let salt = match backend.get(SEALING_SALT_KEY)? {
+        Some(salt) => salt,
+        None => {
+            let salt = cipher::generate_random(SEALING_SALT_LENGTH)?;
+            backend.put(SEALING_SALT_KEY.to_vec(), salt.clone())?;
+
+            salt
+        }
+    };
+    let sealing_key = pbkdf2_sha256(passphrase.as_ref(), &salt, SEALING_HASH_ITERATIONS);
+
+    match check {
+        Some(check) => {
+            let valid = unseal(&sealing_key, &check)
+                .map(|value| value == SEALING_CHECK_VALUE)
+                .unwrap_or(false);
+            if !valid {
+                bail!("Wrong storage passphrase");
+            }
+        }
+        None => backend.put(
+            SEALING_CHECK_KEY.to_vec(),
+            seal(&sealing_key, SEALING_CHECK_VALUE)?,
+        )?,
+    }
+
+    Ok(Some(sealing_key))
+}
+
+/// Storage key of the encrypted version of a record
+fn sealed_key(key: &[u8]) -> Vec<u8> {
+    [SEALED_PREFIX, key].concat()
+}
+
+/// Encrypt a sensitive record as `iv || ciphertext`
+fn seal(sealing_key: &[u8], value: &[u8]) -> Result<Vec<u8>, failure::Error> {
+    let mut sealed = cipher::generate_random(SEALING_IV_LENGTH)?;
+    let encrypted = cipher::encrypt_aes_cbc(sealing_key, value, &sealed)?;
+    sealed.extend(encrypted);
+
+    Ok(sealed)
+}
+
+/// Decrypt a sensitive record encrypted with `seal`
+fn unseal(sealing_key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, failure::Error> {
+    if sealed.len() < SEALING_IV_LENGTH {
+        bail!("Encrypted storage record is too short");
+    }
+    let (iv, encrypted) = sealed.split_at(SEALING_IV_LENGTH);
+
+    cipher::decrypt_aes_cbc(sealing_key, encrypted, iv).map_err(Into::into)
+}
+
+/// Contents of the identity file
+#[derive(Debug, Deserialize, Serialize)]
+struct IdentityFile {
+    version: u32,
+    /// Whether the identity is encrypted with the storage passphrase
+    sealed: bool,
+    /// Hex-encoded salt used to derive the encryption key from the storage passphrase, if sealed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    /// Hex-encoded `SEALING_CHECK_VALUE` encrypted as `iv || ciphertext`, if sealed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    check: Option<String>,
+    /// Hex-encoded identity, encrypted as `iv || ciphertext` if sealed
+    identity: String,
+}
+
+/// Derive the key of a sealed identity file from the storage passphrase and the salt of the file,
+/// checking it against the check value of the file
+fn identity_file_key(
+    path: &Path,
+    file: &IdentityFile,
+    passphrase: &Protected,
+) -> Result<Protected, failure::Error> {
+    let decode = |field: &Option<String>, name: &str| -> Result<Vec<u8>, failure::Error> {
+        let value = field.as_ref().ok_or_else(|| {
+            format_err!("Invalid identity file {}: missing {}", path.display(), name)
+        })?;
+
+        hex::decode(value)
+            .map_err(|e| format_err!("Invalid identity file {}: {}", path.display(), e))
+    };
+    let salt = decode(&file.salt, "salt")?;
+    let check = decode(&file.check, "check value")?;
+
+    let key = pbkdf2_sha256(passphrase.as_ref(), &salt, SEALING_HASH_ITERATIONS);
+    let valid = unseal(&key, &check)
+        .map(|value| value == SEALING_CHECK_VALUE)
+        .unwrap_or(false);
+    if !valid {
+        bail!("Wrong storage passphrase for identity file");
+    }
+
+    Ok(key)
+}
+
+/// Read the identity file, if it exists, checking that it is only accessible by its owner.
+/// Sealed identity files of version 1 are decrypted with `legacy_sealing_key`, the key of the
+/// database records they were encrypted with, and written again with their own salt
+fn read_identity_file(
+    path: &Path,
+    passphrase: Option<&Protected>,
+    legacy_sealing_key: Option<&Protected>,
+) -> Result<Option<Vec<u8>>, failure::Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    check_identity_file_permissions(path)?;
+
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format_err!("Failed to read identity file {}: {}", path.display(), e))?;
+    let file: IdentityFile = serde_json::from_str(&contents)
+        .map_err(|e| format_err!("Invalid identity file {}: {}", path.display(), e))?;
+    if file.version != IDENTITY_FILE_VERSION && file.version != 1 {
+        bail!(
+            "Unsupported version {} of identity file {}",
+            file.version,
+            path.display()
+        );
+    }
+    let identity = hex::decode(&file.identity)
+        .map_err(|e| format_err!("Invalid identity file {}: {}", path.display(), e))?;
+
+    match (file.sealed, passphrase) {
+        (false, _) => Ok(Some(identity)),
+        (true, Some(passphrase)) => {
+            if file.version == 1 {
+                let identity = legacy_sealing_key
+                    .and_then(|key| unseal(key, &identity).ok())
+                    .ok_or_else(|| format_err!("Wrong storage passphrase for identity file"))?;
+                write_identity_file(path, &identity, Some(passphrase))?;
+
+                return Ok(Some(identity));
+            }
+            let key = identity_file_key(path, &file, passphrase)?;
+
+            unseal(&key, &identity)
+                .map(Some)
+                .map_err(|_| format_err!("Wrong storage passphrase for identity file"))
+        }
+        (true, None) => bail!(
+            "The identity file {} is encrypted, but no storage passphrase was provided through {} \
+             or storage.passphrase_file",
+            path.display(),
+            PASSPHRASE_ENV_VAR
+        ),
+    }
+}
+
+/// Write the identity file, creating it with permissions only for its owner. The file is written
+/// next to its final path and then renamed, so an existing identity is never left half-written
+fn write_identity_file(
+    path: &Path,
+    identity: &[u8],
+    passphrase: Option<&Protected>,
+) -> Result<(), failure::Error> {
+    let file = match passphrase {
+        Some(passphrase) => {
+            let salt = cipher::generate_random(SEALING_SALT_LENGTH)?;
+            let key = pbkdf2_sha256(passphrase.as_ref(), &salt, SEALING_HASH_ITERATIONS);
+
+            IdentityFile {
+                version: IDENTITY_FILE_VERSION,
+                sealed: true,
+                salt: Some(hex::encode(salt)),
+                check: Some(hex::encode(seal(&key, SEALING_CHECK_VALUE)?)),
+                identity: hex::encode(seal(&key, identity)?),
+            }
+        }
+        None => IdentityFile {
+            version: IDENTITY_FILE_VERSION,
+            sealed: false,
+            salt: None,
+            check: None,
+            identity: hex::encode(identity),
+        },
+    };
+    let contents = serde_json::to_string_pretty(&file)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut tmp_file = options.open(&tmp_path)?;
+    tmp_file.write_all(contents.as_bytes())?;
+    tmp_file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    log::info!("Node identity saved into {}", path.display());
+
+    Ok(())
+}
+
+/// Refuse to use an identity file that other users can access
+#[cfg(unix)]
+fn check_identity_file_permissions(path: &Path) -> Result<(), failure::Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)?.permissions().mode();
+    if mode & 0o077 != 0 {
+        bail!(
+            "The identity file {} can be accessed by other users (mode {:o}), restrict its \
+             permissions with `chmod 600`",
+            path.display(),
+            mode & 0o777
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_identity_file_permissions(_path: &Path) -> Result<(), failure::Error> {
+    Ok(())
+}
+
 struct StorageManagerAdapter {
     storage: Addr<StorageManager>,
 }
@@ -199,6 +812,8 @@ struct StorageManagerAdapter {
 impl Default for StorageManagerAdapter {
     fn default() -> Self {
         let storage = SyncArbiter::start(1, StorageManager::default);
+        crash::set_flush_handler(crash_flush_handler(storage.clone()));
+
         Self { storage }
     }
 }
@@ -241,6 +856,46 @@ impl Handler<Put> for StorageManagerAdapter {
     }
 }
 
+impl Handler<GetSealed> for StorageManagerAdapter {
+    type Result = ResponseFuture<Option<Vec<u8>>, failure::Error>;
+
+    fn handle(&mut self, msg: GetSealed, _ctx: &mut Self::Context) -> Self::Result {
+        Box::new(self.storage.send(msg).flatten())
+    }
+}
+
+impl Handler<PutSealed> for StorageManagerAdapter {
+    type Result = ResponseFuture<(), failure::Error>;
+
+    fn handle(&mut self, msg: PutSealed, _ctx: &mut Self::Context) -> Self::Result {
+        Box::new(self.storage.send(msg).flatten())
+    }
+}
+
+impl Handler<DeleteSealed> for StorageManagerAdapter {
+    type Result = ResponseFuture<(), failure::Error>;
+
+    fn handle(&mut self, msg: DeleteSealed, _ctx: &mut Self::Context) -> Self::Result {
+        Box::new(self.storage.send(msg).flatten())
+    }
+}
+
+impl Handler<ReadIdentity> for StorageManagerAdapter {
+    type Result = ResponseFuture<Option<Vec<u8>>, failure::Error>;
+
+    fn handle(&mut self, msg: ReadIdentity, _ctx: &mut Self::Context) -> Self::Result {
+        Box::new(self.storage.send(msg).flatten())
+    }
+}
+
+impl Handler<WriteIdentity> for StorageManagerAdapter {
+    type Result = ResponseFuture<(), failure::Error>;
+
+    fn handle(&mut self, msg: WriteIdentity, _ctx: &mut Self::Context) -> Self::Result {
+        Box::new(self.storage.send(msg).flatten())
+    }
+}
+
 impl Handler<Delete> for StorageManagerAdapter {
     type Result = ResponseFuture<(), failure::Error>;
 
@@ -248,3 +903,92 @@ impl Handler<Delete> for StorageManagerAdapter {
         Box::new(self.storage.send(msg).flatten())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_and_unseal() {
+        let sealing_key = pbkdf2_sha256(b"passphrase", b"salt", 1);
+        let sealed = seal(&sealing_key, b"master key").unwrap();
+
+        assert_ne!(&sealed[SEALING_IV_LENGTH..], b"master key");
+        assert_eq!(unseal(&sealing_key, &sealed).unwrap(), b"master key");
+
+        let other_key = pbkdf2_sha256(b"other passphrase", b"salt", 1);
+        assert_ne!(
+            unseal(&other_key, &sealed).unwrap_or_default(),
+            b"master key".to_vec()
+        );
+        assert!(unseal(&sealing_key, b"short").is_err());
+    }
+
+    #[test]
+    fn sealing_key_checks_passphrase() {
+        let mut backend = backends::hashmap::Backend::new();
+
+        // Without passphrase nothing is encrypted
+        assert!(derive_sealing_key(&mut backend, None).unwrap().is_none());
+
+        let sealing_key = derive_sealing_key(&mut backend, Some("passphrase".into()))
+            .unwrap()
+            .unwrap();
+        let same_key = derive_sealing_key(&mut backend, Some("passphrase".into()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(sealing_key, same_key);
+
+        assert!(derive_sealing_key(&mut backend, Some("wrong".into())).is_err());
+        assert!(derive_sealing_key(&mut backend, None).is_err());
+    }
+
+    #[test]
+    fn identity_file_roundtrip() {
+        let path = env::temp_dir().join(format!("witnet_identity_test_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(read_identity_file(&path, None, None).unwrap(), None);
+
+        write_identity_file(&path, b"master key", None).unwrap();
+        assert_eq!(
+            read_identity_file(&path, None, None).unwrap(),
+            Some(b"master key".to_vec())
+        );
+
+        let passphrase = Protected::from("passphrase");
+        let mut backend = backends::hashmap::Backend::new();
+        let sealing_key = derive_sealing_key(&mut backend, Some(passphrase.clone())).unwrap();
+        write_identity_file(&path, b"master key", Some(&passphrase)).unwrap();
+        assert!(!fs::read_to_string(&path)
+            .unwrap()
+            .contains(&hex::encode("master key")));
+        assert_eq!(
+            read_identity_file(&path, Some(&passphrase), sealing_key.as_ref()).unwrap(),
+            Some(b"master key".to_vec())
+        );
+
+        // Wiping the database creates a new salt for the sealed records, which does not affect
+        // the identity file
+        drop(backend);
+        let mut backend = backends::hashmap::Backend::new();
+        let new_sealing_key = derive_sealing_key(&mut backend, Some(passphrase.clone())).unwrap();
+        assert_ne!(sealing_key, new_sealing_key);
+        assert_eq!(
+            read_identity_file(&path, Some(&passphrase), new_sealing_key.as_ref()).unwrap(),
+            Some(b"master key".to_vec())
+        );
+
+        assert!(read_identity_file(&path, None, None).is_err());
+        let wrong_passphrase = Protected::from("wrong");
+        assert!(read_identity_file(&path, Some(&wrong_passphrase), None).is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            assert!(read_identity_file(&path, Some(&passphrase), None).is_err());
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+}
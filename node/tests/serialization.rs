@@ -1,6 +1,6 @@
 use bincode::{deserialize, serialize};
 use serde::{de::DeserializeOwned, Serialize};
-use std::fmt::Debug;
+use std::{collections::HashSet, fmt::Debug};
 use witnet_data_structures::chain::*;
 
 fn t<T>(al: T)
@@ -39,3 +39,40 @@ fn chain_state() {
     };
     t(c);
 }
+
+#[test]
+fn legacy_chain_state() {
+    let mut block_chain = Blockchain::default();
+    block_chain.insert(1, Hash::SHA256([4; 32]));
+    let own_utxos: HashSet<OutputPointer> = vec![OutputPointer {
+        transaction_id: Hash::SHA256([5; 32]),
+        output_index: 0,
+    }]
+    .into_iter()
+    .collect();
+    // Chain state stored before the node tracked the signaling of protocol upgrades
+    let legacy = serialize(&(
+        None::<ChainInfo>,
+        UnspentOutputsPool::default(),
+        witnet_data_structures::data_request::DataRequestPool::default(),
+        block_chain.clone(),
+        own_utxos.clone(),
+        Some(ReputationEngine::new(1000)),
+    ))
+    .unwrap();
+
+    assert!(deserialize::<ChainState>(&legacy).is_err());
+    let chain_state = ChainState::from(deserialize::<LegacyChainState>(&legacy).unwrap());
+    assert_eq!(chain_state.block_chain, block_chain);
+    assert_eq!(chain_state.own_utxos, own_utxos);
+    assert_eq!(
+        chain_state.reputation_engine,
+        Some(ReputationEngine::new(1000))
+    );
+    assert_eq!(chain_state.protocol_upgrades, Default::default());
+    assert_eq!(
+        chain_state.superblock_state,
+        witnet_data_structures::superblock::SuperblockState::resume(1)
+    );
+    assert!(chain_state.watched_utxos.is_empty());
+}
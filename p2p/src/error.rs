@@ -17,6 +17,9 @@ pub enum SessionsError {
     /// Errors when updating sessions
     #[fail(display = "Is not an outbound consolidated peer")]
     NotOutboundConsolidatedPeer,
+    /// The session was a feeler connection, which is closed instead of being consolidated
+    #[fail(display = "Feeler connection completed, the session is not consolidated")]
+    FeelerCompleted,
 }
 
 /// Sessions Errors under different operations
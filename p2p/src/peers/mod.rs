@@ -12,6 +12,46 @@ use witnet_util::timestamp::get_timestamp;
 struct PeerInfo {
     address: SocketAddr,
     timestamp: i64,
+    /// Failed outbound connection attempts (not persisted)
+    #[serde(skip)]
+    retry: RetryState,
+}
+
+impl PeerInfo {
+    fn new(address: SocketAddr, timestamp: i64) -> Self {
+        PeerInfo {
+            address,
+            timestamp,
+            retry: RetryState::default(),
+        }
+    }
+}
+
+/// Consecutive failed outbound connection attempts to an address, and the timestamp before which
+/// it should not be tried again
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct RetryState {
+    failed_attempts: u32,
+    retry_after: i64,
+}
+
+/// Statistics about the occupancy of the buckets and the evictions performed on them
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeersStats {
+    /// Number of addresses in the new addresses bucket
+    pub new_bucket_occupancy: usize,
+    /// Number of addresses in the tried addresses bucket
+    pub tried_bucket_occupancy: usize,
+    /// Number of addresses demoted from the tried to the new addresses bucket
+    pub demoted: u64,
+    /// Number of addresses dropped from the new addresses bucket
+    pub dropped: u64,
+    /// Number of peers rejected during the handshake because they belong to another network
+    pub network_mismatches: u64,
+    /// Number of failed outbound connection attempts
+    pub failed_attempts: u64,
+    /// Number of addresses that are not tried again until their backoff expires
+    pub backing_off: usize,
 }
 
 /// Peers TBD
@@ -23,6 +63,18 @@ pub struct Peers {
     new_bucket: HashMap<u16, PeerInfo>,
     /// Nonce value
     sk: u64,
+    /// Number of addresses demoted since start (not persisted)
+    #[serde(skip)]
+    demoted_count: u64,
+    /// Number of addresses dropped since start (not persisted)
+    #[serde(skip)]
+    dropped_count: u64,
+    /// Number of peers of another network found since start (not persisted)
+    #[serde(skip)]
+    network_mismatch_count: u64,
+    /// Number of failed outbound connection attempts since start (not persisted)
+    #[serde(skip)]
+    failed_attempt_count: u64,
 }
 
 impl Peers {
@@ -32,6 +84,10 @@ impl Peers {
             sk: thread_rng().gen(),
             tried_bucket: HashMap::new(),
             new_bucket: HashMap::new(),
+            demoted_count: 0,
+            dropped_count: 0,
+            network_mismatch_count: 0,
+            failed_attempt_count: 0,
         }
     }
 
@@ -81,7 +137,7 @@ impl Peers {
     }
 
     /// Add multiple peer addresses and save timestamp in the new addresses bucket
-    /// If an address did already exist, it gets overwritten
+    /// If an address did already exist, it gets overwritten, keeping its retry backoff
     /// Returns all the overwritten addresses
     pub fn add_to_new(
         &mut self,
@@ -96,16 +152,14 @@ impl Peers {
             .filter(|address| !address.ip().is_unspecified())
             .filter_map(|address| {
                 let index = self.new_bucket_index(&address, &src_address);
+                let mut info = PeerInfo::new(address, get_timestamp());
+                if let Some(old) = self.new_bucket.get(&index) {
+                    if old.address == address {
+                        info.retry = old.retry;
+                    }
+                }
 
-                self.new_bucket
-                    .insert(
-                        index,
-                        PeerInfo {
-                            address,
-                            timestamp: get_timestamp(), //msg.timestamp,
-                        },
-                    )
-                    .map(|v| v.address)
+                self.new_bucket.insert(index, info).map(|v| v.address)
             })
             .collect();
 
@@ -114,6 +168,7 @@ impl Peers {
 
     /// Add multiple peer addresses and save timestamp in the tried addresses bucket
     /// If an address did already exist, it gets overwritten
+    /// As the address has been successfully connected to, its retry backoff is reset
     /// Returns all the overwritten or rejected addresses
     pub fn add_to_tried(
         &mut self,
@@ -122,15 +177,14 @@ impl Peers {
         // Insert address
         let result = if !address.ip().is_unspecified() {
             let index = self.tried_bucket_index(&address);
+            for info in self.new_bucket.values_mut() {
+                if info.address == address {
+                    info.retry = RetryState::default();
+                }
+            }
 
             self.tried_bucket
-                .insert(
-                    index,
-                    PeerInfo {
-                        address,
-                        timestamp: get_timestamp(), //msg.timestamp,
-                    },
-                )
+                .insert(index, PeerInfo::new(address, get_timestamp()))
                 .map(|v| v.address)
         } else {
             None
@@ -159,24 +213,60 @@ impl Peers {
     }
 
     /// Get a random socket address from the peers list
+    /// Addresses whose retry backoff has not expired yet are not selected
     pub fn get_random(&self) -> Result<Option<SocketAddr>, failure::Error> {
-        let bucket = match (self.new_bucket.is_empty(), self.tried_bucket.is_empty()) {
+        let current_ts = get_timestamp();
+        let eligible = |bucket: &HashMap<u16, PeerInfo>| -> Vec<SocketAddr> {
+            bucket
+                .values()
+                .filter(|info| info.retry.retry_after <= current_ts)
+                .map(|info| info.address)
+                .collect()
+        };
+        let tried = eligible(&self.tried_bucket);
+        let new = eligible(&self.new_bucket);
+
+        let addresses = match (new.is_empty(), tried.is_empty()) {
             (true, true) => return Ok(None),
-            (true, false) => &self.tried_bucket,
-            (false, true) => &self.new_bucket,
+            (true, false) => tried,
+            (false, true) => new,
             (false, false) => {
                 if thread_rng().gen() {
-                    &self.tried_bucket
+                    tried
                 } else {
-                    &self.new_bucket
+                    new
                 }
             }
         };
 
         // Random index with range [0, len) of the peers vector
-        let index = thread_rng().gen_range(0, bucket.len());
+        let index = thread_rng().gen_range(0, addresses.len());
 
-        Ok(bucket.values().nth(index).map(|v| v.address.to_owned()))
+        Ok(addresses.get(index).cloned())
+    }
+
+    /// Get a random address from the new addresses bucket which is not in the tried bucket and is
+    /// not backing off after a failed connection attempt, to be tested by a feeler connection
+    pub fn get_random_new(&self, current_ts: i64) -> Option<SocketAddr> {
+        let candidates: Vec<SocketAddr> = self
+            .new_bucket
+            .values()
+            .filter(|info| info.retry.retry_after <= current_ts)
+            .filter(|info| {
+                self.tried_bucket
+                    .get(&self.tried_bucket_index(&info.address))
+                    .map(|tried| tried.address != info.address)
+                    .unwrap_or(true)
+            })
+            .map(|info| info.address)
+            .collect();
+
+        if candidates.is_empty() {
+            return None;
+        }
+        let index = thread_rng().gen_range(0, candidates.len());
+
+        candidates.get(index).cloned()
     }
 
     /// Get all the peers from the tried bucket
@@ -189,6 +279,154 @@ impl Peers {
         Ok(self.new_bucket.values().map(|v| v.address).collect())
     }
 
+    /// Get the address from the tried bucket which has not been seen for the longest time, as long
+    /// as it has not been seen for more than `period` seconds
+    pub fn get_oldest_tried(&self, current_ts: i64, period: i64) -> Option<SocketAddr> {
+        self.tried_bucket
+            .values()
+            .filter(|info| current_ts - info.timestamp > period)
+            .min_by_key(|info| info.timestamp)
+            .map(|info| info.address)
+    }
+
+    /// Move an address from the tried addresses bucket to the new addresses bucket, once a feeler
+    /// connection to it has failed
+    /// Returns the demoted address, if it was found in the tried bucket
+    pub fn demote_from_tried(
+        &mut self,
+        address: &SocketAddr,
+        current_ts: i64,
+    ) -> Option<SocketAddr> {
+        let index = self.tried_bucket_index(address);
+        match self.tried_bucket.get(&index) {
+            Some(info) if info.address == *address => {}
+            _ => return None,
+        }
+
+        let info = self.tried_bucket.remove(&index)?;
+        let new_index = self.new_bucket_index(address, address);
+        self.new_bucket.insert(
+            new_index,
+            PeerInfo {
+                timestamp: current_ts,
+                ..info
+            },
+        );
+        self.demoted_count += 1;
+
+        Some(*address)
+    }
+
+    /// Age the new addresses bucket: addresses that have not been seen for more than `max_age`
+    /// seconds are dropped. The addresses of the tried bucket are not aged, as they are only
+    /// demoted after a failed feeler connection.
+    /// Returns the dropped addresses
+    pub fn age_new_bucket(&mut self, current_ts: i64, max_age: i64) -> Vec<SocketAddr> {
+        let stale_new: Vec<u16> = self
+            .new_bucket
+            .iter()
+            .filter(|(_, info)| current_ts - info.timestamp > max_age)
+            .map(|(index, _)| *index)
+            .collect();
+        let dropped: Vec<SocketAddr> = stale_new
+            .into_iter()
+            .filter_map(|index| self.new_bucket.remove(&index))
+            .map(|info| info.address)
+            .collect();
+        self.dropped_count += dropped.len() as u64;
+
+        dropped
+    }
+
+    /// Record a failed outbound connection attempt to an address. The address is not tried again
+    /// until an exponential backoff expires: `base_backoff` seconds after the first failure,
+    /// doubling with every consecutive failure up to `max_backoff` seconds. A random jitter of up
+    /// to half the backoff is subtracted, so that addresses that failed together are not tried
+    /// again together.
+    /// Returns the timestamp before which the address will not be tried again, if it was in any
+    /// of the buckets
+    pub fn record_failed_attempt(
+        &mut self,
+        address: &SocketAddr,
+        current_ts: i64,
+        base_backoff: i64,
+        max_backoff: i64,
+    ) -> Option<i64> {
+        self.failed_attempt_count += 1;
+
+        let mut retry_after = None;
+        for info in self
+            .tried_bucket
+            .values_mut()
+            .chain(self.new_bucket.values_mut())
+            .filter(|info| info.address == *address)
+        {
+            let failed_attempts = info.retry.failed_attempts.saturating_add(1);
+            let backoff = retry_backoff(failed_attempts, base_backoff, max_backoff);
+            let jitter = if backoff > 1 {
+                thread_rng().gen_range(0, backoff / 2 + 1)
+            } else {
+                0
+            };
+            info.retry = RetryState {
+                failed_attempts,
+                retry_after: current_ts + backoff - jitter,
+            };
+            retry_after = Some(info.retry.retry_after);
+        }
+
+        retry_after
+    }
+
+    /// Get the number of consecutive failed outbound connection attempts to an address
+    pub fn failed_attempts(&self, address: &SocketAddr) -> u32 {
+        self.tried_bucket
+            .values()
+            .chain(self.new_bucket.values())
+            .filter(|info| info.address == *address)
+            .map(|info| info.retry.failed_attempts)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Get statistics about the buckets
+    pub fn stats(&self) -> PeersStats {
+        let current_ts = get_timestamp();
+
+        PeersStats {
+            new_bucket_occupancy: self.new_bucket.len(),
+            tried_bucket_occupancy: self.tried_bucket.len(),
+            demoted: self.demoted_count,
+            dropped: self.dropped_count,
+            network_mismatches: self.network_mismatch_count,
+            failed_attempts: self.failed_attempt_count,
+            backing_off: self
+                .tried_bucket
+                .values()
+                .chain(self.new_bucket.values())
+                .filter(|info| info.retry.retry_after > current_ts)
+                .count(),
+        }
+    }
+
+    /// Record a peer that belongs to another network. Its address, if known, is removed from both
+    /// buckets so that the node does not try to connect to it again.
+    /// Returns whether the address was in any of the buckets
+    pub fn remove_other_network(&mut self, address: Option<SocketAddr>) -> bool {
+        self.network_mismatch_count += 1;
+
+        match address {
+            Some(address) => {
+                let in_tried = !self.remove_from_tried(&[address]).is_empty();
+                let new_len = self.new_bucket.len();
+                self.new_bucket.retain(|_, info| info.address != address);
+
+                in_tried || self.new_bucket.len() < new_len
+            }
+            None => false,
+        }
+    }
+
     /// Clear tried addresses bucket
     pub fn clear_tried_bucket(&mut self) {
         self.tried_bucket.clear();
@@ -200,6 +438,17 @@ impl Peers {
     }
 }
 
+/// Backoff (in seconds) after a number of consecutive failed attempts: `base_backoff` doubled for
+/// every failure after the first one, up to `max_backoff`
+fn retry_backoff(failed_attempts: u32, base_backoff: i64, max_backoff: i64) -> i64 {
+    let exponent = failed_attempts.saturating_sub(1).min(62);
+
+    base_backoff
+        .max(0)
+        .saturating_mul(1 << exponent)
+        .min(max_backoff)
+}
+
 /// Returns the ip and ip split
 fn split_socket_addresses(socket_addr: &SocketAddr) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
     match socket_addr {
@@ -1,5 +1,5 @@
 //! Library for managing the sessions
-use std::{collections::HashMap, net::SocketAddr};
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
 
 use crate::error::SessionsError;
 
@@ -7,6 +7,8 @@ use crate::error::SessionsError;
 pub struct SessionInfo<T> {
     /// Session reference (e.g. actor address)
     pub reference: T,
+    /// Last round-trip time measured with the peer, if any
+    pub latency: Option<Duration>,
 }
 
 /// Sessions struct contains:
@@ -53,7 +55,13 @@ impl<T> BoundedSessions<T> {
             Err(SessionsError::AddressAlreadyRegistered)?
         }
         // Insert session into the right collection
-        self.collection.insert(address, SessionInfo { reference });
+        self.collection.insert(
+            address,
+            SessionInfo {
+                reference,
+                latency: None,
+            },
+        );
 
         // Return success
         Ok(())
@@ -69,4 +77,14 @@ impl<T> BoundedSessions<T> {
             None => Err(SessionsError::AddressNotFound)?,
         }
     }
+    /// Method to set the latency of a session, returning whether the session was found
+    pub fn set_latency(&mut self, address: SocketAddr, latency: Duration) -> bool {
+        match self.collection.get_mut(&address) {
+            Some(info) => {
+                info.latency = Some(latency);
+                true
+            }
+            None => false,
+        }
+    }
 }
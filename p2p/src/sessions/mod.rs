@@ -3,14 +3,22 @@
 /// Bounded sessions module
 pub mod bounded_sessions;
 
-use std::{net::SocketAddr, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
 
 use rand::{thread_rng, Rng};
+use serde::Serialize;
 
-use super::{error::SessionsError, sessions::bounded_sessions::BoundedSessions};
+use super::{
+    error::SessionsError,
+    sessions::bounded_sessions::{BoundedSessions, SessionInfo},
+};
+
+/// Number of lowest-latency sessions among which `get_fastest_anycast_session` picks one
+pub const FASTEST_ANYCAST_CANDIDATES: usize = 3;
 
 /// Session type
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub enum SessionType {
     /// Inbound session
     Inbound,
@@ -38,6 +46,13 @@ where
 {
     /// Server address listening to incoming connections
     pub server_address: Option<SocketAddr>,
+    /// Additional addresses listening to incoming connections, along with their limit of inbound
+    /// sessions (`None` if only `inbound_consolidated` limits them)
+    pub listeners: HashMap<SocketAddr, Option<u16>>,
+    /// Listener through which each inbound session was accepted
+    inbound_listeners: HashMap<SocketAddr, SocketAddr>,
+    /// Pending feeler connections, along with the timestamp at which they were started
+    feelers: HashMap<SocketAddr, i64>,
     /// Inbound consolidated sessions: __known__ peers sessions that connect to the server
     pub inbound_consolidated: BoundedSessions<T>,
     /// Inbound sessions: __untrusted__ peers that connect to the server
@@ -58,6 +73,10 @@ where
     pub magic_number: u16,
     /// Timeout for requested blocks
     pub blocks_timeout: i64,
+    /// Period of the keepalive pings
+    pub ping_interval: Duration,
+    /// Timeout for receiving the pong of a keepalive ping
+    pub ping_timeout: Duration,
 }
 
 /// Default trait implementation
@@ -68,6 +87,9 @@ where
     fn default() -> Self {
         Self {
             server_address: None,
+            listeners: HashMap::default(),
+            inbound_listeners: HashMap::default(),
+            feelers: HashMap::default(),
             inbound_consolidated: BoundedSessions::default(),
             inbound_unconsolidated: BoundedSessions::default(),
             outbound_consolidated: BoundedSessions::default(),
@@ -76,6 +98,8 @@ where
             handshake_timeout: Duration::default(),
             magic_number: 0 as u16,
             blocks_timeout: 0 as i64,
+            ping_interval: Duration::default(),
+            ping_timeout: Duration::default(),
         }
     }
 }
@@ -105,6 +129,31 @@ where
     pub fn set_server_address(&mut self, server_address: SocketAddr) {
         self.server_address = Some(server_address);
     }
+    /// Method to set the additional listeners and their limits of inbound sessions
+    pub fn set_listeners<I>(&mut self, listeners: I)
+    where
+        I: IntoIterator<Item = (SocketAddr, Option<u16>)>,
+    {
+        self.listeners = listeners.into_iter().collect();
+    }
+    /// Method to check whether a new inbound connection accepted through `listener` can become a
+    /// session, keeping track of it if so. The connection is refused if the listener has reached
+    /// its limit of inbound sessions.
+    pub fn accept_inbound(&mut self, listener: SocketAddr, address: SocketAddr) -> bool {
+        if let Some(Some(limit)) = self.listeners.get(&listener) {
+            let sessions = self
+                .inbound_listeners
+                .values()
+                .filter(|session_listener| **session_listener == listener)
+                .count();
+            if sessions >= usize::from(*limit) {
+                return false;
+            }
+        }
+        self.inbound_listeners.insert(address, listener);
+
+        true
+    }
     /// Method to set the sessions limits
     pub fn set_limits(&mut self, inbound_limit: u16, outbound_consolidated_limit: u16) {
         self.inbound_consolidated.set_limit(inbound_limit);
@@ -125,6 +174,15 @@ where
     pub fn set_blocks_timeout(&mut self, blocks_timeout: i64) {
         self.blocks_timeout = blocks_timeout;
     }
+    /// Method to set the keepalive ping interval and timeout
+    pub fn set_ping_interval_and_timeout(
+        &mut self,
+        ping_interval: Duration,
+        ping_timeout: Duration,
+    ) {
+        self.ping_interval = ping_interval;
+        self.ping_timeout = ping_timeout;
+    }
     /// Method to check if a socket address is eligible as outbound peer
     pub fn is_outbound_address_eligible(&self, candidate_addr: SocketAddr) -> bool {
         // Check if address is already used as outbound session (consolidated or unconsolidated)
@@ -137,18 +195,58 @@ where
             .collection
             .contains_key(&candidate_addr);
 
-        // Check if address is the server address
+        // Check if address is the server address or any of the additional listeners
         let is_server = self
             .server_address
             .map(|address| address == candidate_addr)
-            .unwrap_or(false);
+            .unwrap_or(false)
+            || self.listeners.contains_key(&candidate_addr);
+
+        // Check if a feeler connection to the address is in progress
+        let is_feeler = self.feelers.contains_key(&candidate_addr);
 
         // Return true if the address has not been used as outbound session or server address
-        !is_outbound_consolidated && !is_outbound_unconsolidated && !is_server
+        !is_outbound_consolidated && !is_outbound_unconsolidated && !is_server && !is_feeler
     }
-    /// Method to get total number of outbound peers
+    /// Method to get total number of outbound peers, not counting the feeler connections
     pub fn get_num_outbound_sessions(&self) -> usize {
+        let num_feelers = self
+            .feelers
+            .keys()
+            .filter(|address| {
+                self.outbound_unconsolidated
+                    .collection
+                    .contains_key(address)
+            })
+            .count();
+
         self.outbound_consolidated.collection.len() + self.outbound_unconsolidated.collection.len()
+            - num_feelers
+    }
+    /// Method to start a feeler connection: a short-lived outbound connection used only to check
+    /// that a peer address is reachable. Feelers which did not finish after twice the handshake
+    /// timeout are forgotten.
+    /// Returns false if the address is not eligible as outbound peer
+    pub fn register_feeler(&mut self, address: SocketAddr, current_ts: i64) -> bool {
+        let max_age = 2 * self.handshake_timeout.as_secs() as i64;
+        self.feelers
+            .retain(|_, started_at| current_ts - *started_at <= max_age);
+
+        if !self.is_outbound_address_eligible(address) {
+            return false;
+        }
+        self.feelers.insert(address, current_ts);
+
+        true
+    }
+    /// Method to check whether there is a feeler connection in progress to an address
+    pub fn is_feeler(&self, address: &SocketAddr) -> bool {
+        self.feelers.contains_key(address)
+    }
+    /// Method to finish a feeler connection
+    /// Returns false if there was no feeler connection in progress to the address
+    pub fn complete_feeler(&mut self, address: &SocketAddr) -> bool {
+        self.feelers.remove(address).is_some()
     }
     /// Method to get number of inbound peers
     pub fn get_num_inbound_sessions(&self) -> usize {
@@ -189,6 +287,29 @@ where
             .nth(index)
             .map(|info| info.reference.clone())
     }
+    /// Method to get a random consolidated outbound session among the ones with the lowest
+    /// latency, so time-critical requests are sent to the fastest peers without always using the
+    /// same one. Sessions whose latency is still unknown are only used if there are not enough
+    /// sessions with a known latency.
+    pub fn get_fastest_anycast_session(&self, safu: bool) -> Option<T> {
+        let sessions = if safu {
+            &self.outbound_consolidated_consensus
+        } else {
+            &self.outbound_consolidated
+        };
+
+        let mut candidates: Vec<&SessionInfo<T>> = sessions.collection.values().collect();
+        candidates.sort_by_key(|info| (info.latency.is_none(), info.latency));
+        candidates.truncate(FASTEST_ANYCAST_CANDIDATES);
+
+        if candidates.is_empty() {
+            None
+        } else {
+            let index = thread_rng().gen_range(0, candidates.len());
+
+            Some(candidates[index].reference.clone())
+        }
+    }
     /// Method to get all the consolidated sessions (inbound and outbound)
     pub fn get_all_consolidated_sessions<'a>(&'a self) -> impl Iterator<Item = &T> + 'a {
         self.outbound_consolidated
@@ -198,6 +319,18 @@ where
             .map(|info| &info.reference)
     }
 
+    /// Method to get all the consolidated sessions (inbound and outbound) along with their
+    /// addresses
+    pub fn get_all_consolidated_sessions_with_address<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = (&SocketAddr, &T)> + 'a {
+        self.outbound_consolidated
+            .collection
+            .iter()
+            .chain(self.inbound_consolidated.collection.iter())
+            .map(|(address, info)| (address, &info.reference))
+    }
+
     /// Method to get all the consolidated sessions (inbound and outbound)
     pub fn get_all_consolidated_inbound_sessions<'a>(&'a self) -> impl Iterator<Item = &T> + 'a {
         self.inbound_consolidated
@@ -237,6 +370,11 @@ where
             }
         }
 
+        // Inbound sessions no longer count towards the limit of their listener
+        if let SessionType::Inbound = session_type {
+            self.inbound_listeners.remove(&address);
+        }
+
         // Get map to insert session to
         let sessions = self.get_sessions(session_type, status);
 
@@ -258,17 +396,29 @@ where
         // Get map to insert session to
         let cons_sessions = self.get_sessions(session_type, SessionStatus::Consolidated);
 
-        // Register session into consolidated collection
-        cons_sessions.register_session(address, session_info.reference)
+        // Register session into consolidated collection, keeping the latency measured during
+        // the handshake
+        cons_sessions.register_session(address, session_info.reference)?;
+        if let Some(latency) = session_info.latency {
+            cons_sessions.set_latency(address, latency);
+        }
+
+        Ok(())
     }
     /// Method to mark a session as consensus safe
     pub fn consensus_session(&mut self, address: SocketAddr) -> Result<(), failure::Error> {
         if let Some(session_info) = self.outbound_consolidated.collection.get(&address) {
-            let session_info = session_info.reference.clone();
+            let reference = session_info.reference.clone();
+            let latency = session_info.latency;
             // Get map to insert session to
             let cons_sessions = &mut self.outbound_consolidated_consensus;
             // Register session into consolidated collection
-            cons_sessions.register_session(address, session_info)
+            cons_sessions.register_session(address, reference)?;
+            if let Some(latency) = latency {
+                cons_sessions.set_latency(address, latency);
+            }
+
+            Ok(())
         } else {
             Err(SessionsError::NotOutboundConsolidatedPeer)?
         }
@@ -282,6 +432,32 @@ where
         cons_sessions.unregister_session(address).map(|_| ())
     }
 
+    /// Method to set the last round-trip time measured with a peer in all the collections the
+    /// session belongs to
+    pub fn set_latency(&mut self, address: SocketAddr, latency: Duration) {
+        for sessions in &mut [
+            &mut self.inbound_unconsolidated,
+            &mut self.inbound_consolidated,
+            &mut self.outbound_unconsolidated,
+            &mut self.outbound_consolidated,
+            &mut self.outbound_consolidated_consensus,
+        ] {
+            sessions.set_latency(address, latency);
+        }
+    }
+    /// Method to get the last round-trip time measured with a peer, if any
+    pub fn get_latency(&self, address: &SocketAddr) -> Option<Duration> {
+        [
+            &self.inbound_unconsolidated,
+            &self.inbound_consolidated,
+            &self.outbound_unconsolidated,
+            &self.outbound_consolidated,
+        ]
+        .iter()
+        .filter_map(|sessions| sessions.collection.get(address))
+        .find_map(|info| info.latency)
+    }
+
     /// Show the addresses of all the sessions
     pub fn show_ips(&self) -> Vec<String> {
         ["Inbound Unconsolidated".to_string()]
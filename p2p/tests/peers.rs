@@ -1,6 +1,7 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 use witnet_p2p::peers::*;
+use witnet_util::timestamp::get_timestamp;
 
 #[test]
 fn p2p_peers_add_to_new() {
@@ -75,6 +76,27 @@ fn p2p_peers_remove() {
     assert_eq!(peers.remove_from_tried(&[address, address]), vec![]);
 }
 
+#[test]
+fn p2p_peers_remove_other_network() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    // Add the address to both buckets
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let src_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add_to_tried(address).unwrap();
+    peers.add_to_new(vec![address], src_address).unwrap();
+
+    // The address is removed from both buckets
+    assert!(peers.remove_other_network(Some(address)));
+    assert!(peers.get_all_from_tried().unwrap().is_empty());
+    assert!(peers.get_all_from_new().unwrap().is_empty());
+
+    // Peers without a known address are only counted
+    assert!(!peers.remove_other_network(None));
+    assert_eq!(peers.stats().network_mismatches, 2);
+}
+
 #[test]
 fn p2p_peers_get_all_from_new() {
     // Create peers struct
@@ -105,3 +127,129 @@ fn p2p_peers_get_all_from_tried() {
     assert!(peers.get_all_from_new().unwrap().is_empty());
     assert!(!peers.get_all_from_tried().unwrap().is_empty());
 }
+
+#[test]
+fn p2p_peers_age_new_bucket() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let tried_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    let src_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)), 8080);
+    peers.add_to_new(vec![address], src_address).unwrap();
+    peers.add_to_tried(tried_address).unwrap();
+
+    // Nothing is stale yet
+    assert_eq!(peers.age_new_bucket(get_timestamp(), 10), vec![]);
+
+    // Stale new addresses are dropped, and tried addresses are kept
+    assert_eq!(
+        peers.age_new_bucket(get_timestamp() + 100, 10),
+        vec![address]
+    );
+    assert!(peers.get_all_from_new().unwrap().is_empty());
+    assert_eq!(peers.get_all_from_tried().unwrap(), vec![tried_address]);
+
+    assert_eq!(
+        peers.stats(),
+        PeersStats {
+            new_bucket_occupancy: 0,
+            tried_bucket_occupancy: 1,
+            demoted: 0,
+            dropped: 1,
+            network_mismatches: 0,
+            failed_attempts: 0,
+            backing_off: 0,
+        }
+    );
+}
+
+#[test]
+fn p2p_peers_get_oldest_tried() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    peers.add_to_tried(address).unwrap();
+
+    // The address has been seen recently
+    assert_eq!(peers.get_oldest_tried(get_timestamp(), 10), None);
+    // The address has not been seen for more than 10 seconds
+    assert_eq!(
+        peers.get_oldest_tried(get_timestamp() + 100, 10),
+        Some(address)
+    );
+
+    // Demoting moves the address to the new bucket, where it is seen at the given time
+    let current_ts = get_timestamp() + 100;
+    assert_eq!(peers.demote_from_tried(&address, current_ts), Some(address));
+    assert_eq!(peers.demote_from_tried(&address, current_ts), None);
+    assert_eq!(peers.get_all_from_new().unwrap(), vec![address]);
+    assert_eq!(peers.age_new_bucket(current_ts + 10, 10), vec![]);
+    assert_eq!(peers.stats().demoted, 1);
+}
+
+#[test]
+fn p2p_peers_get_random_new() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let src_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    assert_eq!(peers.get_random_new(get_timestamp()), None);
+
+    peers.add_to_new(vec![address], src_address).unwrap();
+    assert_eq!(peers.get_random_new(get_timestamp()), Some(address));
+
+    // Addresses backing off are not tested
+    let ts = get_timestamp();
+    let retry_after = peers
+        .record_failed_attempt(&address, ts, 1000, 1000)
+        .unwrap();
+    assert_eq!(peers.get_random_new(ts), None);
+    assert_eq!(peers.get_random_new(retry_after), Some(address));
+
+    // Addresses already in the tried bucket do not need to be tested
+    peers.add_to_tried(address).unwrap();
+    assert_eq!(peers.get_random_new(get_timestamp()), None);
+}
+
+#[test]
+fn p2p_peers_failed_attempts_backoff() {
+    // Create peers struct
+    let mut peers = Peers::default();
+
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let src_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    peers.add_to_new(vec![address], src_address).unwrap();
+
+    // Unknown addresses have no backoff
+    let unknown = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)), 8080);
+    assert_eq!(peers.record_failed_attempt(&unknown, 0, 10, 100), None);
+
+    // The backoff doubles with every failure, minus a jitter of up to half of it
+    let mut expected_backoff = 10;
+    for failed_attempts in 1..=6 {
+        let retry_after = peers
+            .record_failed_attempt(&address, 1000, 10, 100)
+            .unwrap();
+        assert!(retry_after <= 1000 + expected_backoff);
+        assert!(retry_after >= 1000 + expected_backoff - expected_backoff / 2);
+        assert_eq!(peers.failed_attempts(&address), failed_attempts);
+        expected_backoff = (expected_backoff * 2).min(100);
+    }
+
+    // The address is not selected while backing off, even if it is added again
+    let ts = get_timestamp();
+    peers.record_failed_attempt(&address, ts, 1000, 1000);
+    peers.add_to_new(vec![address], src_address).unwrap();
+    assert_eq!(peers.get_random().unwrap(), None);
+    assert_eq!(peers.stats().failed_attempts, 8);
+    assert_eq!(peers.stats().backing_off, 1);
+
+    // A successful connection resets the backoff
+    peers.add_to_tried(address).unwrap();
+    assert_eq!(peers.failed_attempts(&address), 0);
+    assert_eq!(peers.get_random().unwrap(), Some(address));
+    assert_eq!(peers.stats().backing_off, 0);
+}
@@ -82,6 +82,22 @@ fn p2p_sessions_set_handshake_timeout() {
     assert_eq!(sessions.handshake_timeout, handshake_timeout);
 }
 
+/// Check setting the keepalive ping interval and timeout
+#[test]
+fn p2p_sessions_set_ping_interval_and_timeout() {
+    // Create sessions struct
+    let mut sessions = Sessions::<String>::default();
+
+    // Set ping interval and timeout
+    let ping_interval = Duration::from_secs(30);
+    let ping_timeout = Duration::from_secs(15);
+    sessions.set_ping_interval_and_timeout(ping_interval, ping_timeout);
+
+    // Check ping interval and timeout are now set
+    assert_eq!(sessions.ping_interval, ping_interval);
+    assert_eq!(sessions.ping_timeout, ping_timeout);
+}
+
 /// Check if addresses are eligible as outbound addresses
 #[test]
 fn p2p_sessions_is_outbound_address_eligible() {
@@ -293,6 +309,45 @@ fn p2p_sessions_get_random_anycast_session() {
     );
 }
 
+/// Check the function to get one of the fastest outbound consolidated sessions
+#[test]
+fn p2p_sessions_get_fastest_anycast_session() {
+    // Create sessions struct
+    let mut sessions = Sessions::<String>::default();
+
+    // Check that the function returns None when there are no sessions in the collection
+    assert_eq!(sessions.get_fastest_anycast_session(false), None);
+
+    // Register 5 outbound sessions, with latencies from 50ms (first) to 10ms (last)
+    for i in 0..5 {
+        let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001 + i);
+        assert!(sessions
+            .register_session(SessionType::Outbound, address, format!("reference{}", i))
+            .is_ok());
+        // The latency measured during the handshake is kept after consolidating the session
+        sessions.set_latency(address, Duration::from_millis(50 - 10 * u64::from(i)));
+        assert!(sessions
+            .consolidate_session(SessionType::Outbound, address)
+            .is_ok());
+        assert_eq!(
+            sessions.get_latency(&address),
+            Some(Duration::from_millis(50 - 10 * u64::from(i)))
+        );
+    }
+
+    // Only the sessions with the lowest latency are returned
+    for _ in 0..1000 {
+        match sessions.get_fastest_anycast_session(false) {
+            Some(reference) => assert!(
+                ["reference2", "reference3", "reference4"].contains(&reference.as_str()),
+                "Session {} is not one of the fastest",
+                reference
+            ),
+            None => panic!("Get fastest function should retrieve a session"),
+        }
+    }
+}
+
 /// Check the registration of sessions
 #[test]
 fn p2p_sessions_register() {
@@ -518,3 +573,90 @@ fn p2p_sessions_consensus() {
     assert!(sessions.consensus_session(outbound_address).is_err());
     assert!(sessions.unconsensus_session(outbound_address).is_err());
 }
+
+/// Check the limits of inbound sessions of the additional listeners
+#[test]
+fn p2p_sessions_listener_limits() {
+    // Create sessions struct
+    let mut sessions = Sessions::<String>::default();
+
+    // Set a limited and an unlimited listener
+    let limited = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8000);
+    let unlimited = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    sessions.set_listeners(vec![(limited, Some(1)), (unlimited, None)]);
+
+    // Listeners are not eligible as outbound addresses
+    assert!(!sessions.is_outbound_address_eligible(limited));
+
+    // Only one inbound session is accepted through the limited listener
+    let inbound_address_1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 9000);
+    let inbound_address_2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)), 9000);
+    assert!(sessions.accept_inbound(limited, inbound_address_1));
+    assert!(!sessions.accept_inbound(limited, inbound_address_2));
+    assert!(sessions.accept_inbound(unlimited, inbound_address_2));
+
+    // Unregistering the session frees its place in the listener
+    assert!(sessions
+        .register_session(
+            SessionType::Inbound,
+            inbound_address_1,
+            "reference1".to_string()
+        )
+        .is_ok());
+    assert!(sessions
+        .unregister_session(
+            SessionType::Inbound,
+            SessionStatus::Unconsolidated,
+            inbound_address_1
+        )
+        .is_ok());
+    let inbound_address_3 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 4)), 9000);
+    assert!(sessions.accept_inbound(limited, inbound_address_3));
+}
+
+/// Check that feeler connections do not count as outbound sessions
+#[test]
+fn p2p_sessions_feelers() {
+    // Create sessions struct
+    let mut sessions = Sessions::<String>::default();
+    sessions.set_handshake_timeout(Duration::from_secs(5));
+    sessions.set_limits(1, 1);
+
+    let feeler_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8001);
+    assert!(sessions.register_feeler(feeler_address, 1000));
+    assert!(sessions.is_feeler(&feeler_address));
+
+    // The address is not eligible for another outbound session while the feeler is in progress
+    assert!(!sessions.is_outbound_address_eligible(feeler_address));
+    assert!(!sessions.register_feeler(feeler_address, 1000));
+
+    // The feeler session does not prevent the outbound bootstrap
+    sessions
+        .register_session(
+            SessionType::Outbound,
+            feeler_address,
+            "reference1".to_string(),
+        )
+        .unwrap();
+    assert_eq!(sessions.get_num_outbound_sessions(), 0);
+    assert!(sessions.is_outbound_bootstrap_needed());
+
+    // Completing the feeler only succeeds once
+    assert!(sessions.complete_feeler(&feeler_address));
+    assert!(!sessions.complete_feeler(&feeler_address));
+    assert!(!sessions.is_feeler(&feeler_address));
+    sessions
+        .unregister_session(
+            SessionType::Outbound,
+            SessionStatus::Unconsolidated,
+            feeler_address,
+        )
+        .unwrap();
+
+    // Feelers which do not finish in time are forgotten
+    let stale_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8002);
+    assert!(sessions.register_feeler(stale_address, 1000));
+    assert!(sessions.register_feeler(feeler_address, 1011));
+    assert!(!sessions.is_feeler(&stale_address));
+    assert!(sessions.is_feeler(&feeler_address));
+}
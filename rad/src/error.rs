@@ -70,6 +70,18 @@ pub enum RadError {
         reducer, inner_type
     )]
     UnsupportedReducer { inner_type: String, reducer: String },
+    /// The given filter is not implemented for the type of the input Array
+    #[fail(
+        display = "Filter `{}` is not implemented for Array with inner type `{}`",
+        filter, inner_type
+    )]
+    UnsupportedFilter { inner_type: String, filter: String },
+    /// The script uses features of a protocol upgrade that is not active yet
+    #[fail(
+        display = "The script uses features of the protocol upgrade `{}`, which is not active yet",
+        upgrade
+    )]
+    UpgradeNotActive { upgrade: String },
     /// The given arguments are not valid for the given operator
     #[fail(
         display = "Wrong `{}::{}()` arguments: `{:?}`",
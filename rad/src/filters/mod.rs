@@ -0,0 +1,123 @@
+// FIXME: https://github.com/rust-num/num-derive/issues/20
+#![allow(clippy::useless_attribute)]
+
+use crate::error::RadError;
+use crate::reducers::{deviation::median_absolute_of_sorted, median_of_sorted, sorted_floats};
+use crate::types::{array::RadonArray, RadonType, RadonTypes};
+
+use num_derive::FromPrimitive;
+use serde_cbor::value::Value;
+use std::fmt;
+
+#[derive(Debug, FromPrimitive, PartialEq)]
+pub enum RadonFilters {
+    /// Drop the values further from the median than the given number of median absolute
+    /// deviations
+    DeviationMedianAbsolute = 0x09,
+}
+
+impl fmt::Display for RadonFilters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RadonFilters::{:?}", self)
+    }
+}
+
+pub fn filter(
+    input: &RadonArray,
+    filter_code: RadonFilters,
+    args: &[Value],
+) -> Result<RadonTypes, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: "RadonArray".to_string(),
+        operator: "Filter".to_string(),
+        args: args.to_vec(),
+    };
+
+    if !input.is_homogeneous() {
+        return Err(RadError::UnsupportedFilter {
+            inner_type: format!("{:?}", input.inner_type()),
+            filter: filter_code.to_string(),
+        });
+    }
+
+    match filter_code {
+        RadonFilters::DeviationMedianAbsolute => {
+            let times = args.first().and_then(number).ok_or_else(wrong_args)?;
+            if times.is_nan() || times < 0f64 {
+                return Err(wrong_args());
+            }
+
+            let values = sorted_floats(input);
+            let median = median_of_sorted(&values);
+            let max_deviation = times * median_absolute_of_sorted(&values);
+
+            let kept: Vec<RadonTypes> = input
+                .value()
+                .into_iter()
+                .filter(|item| match item {
+                    RadonTypes::Float(f64_value) => {
+                        (f64_value.value() - median).abs() <= max_deviation
+                    }
+                    _ => false,
+                })
+                .collect();
+
+            Ok(RadonArray::from(kept).into())
+        }
+    }
+}
+
+/// Numeric argument, which can be encoded either as an integer or as a float.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(integer) => Some(*integer as f64),
+        Value::Float(float) => Some(*float),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_filter_deviation_median_absolute() {
+    use crate::types::float::RadonFloat;
+
+    let input = RadonArray::from(
+        [10f64, 100f64, 11f64, 12f64, 13f64]
+            .iter()
+            .map(|value| RadonFloat::from(*value).into())
+            .collect::<Vec<RadonTypes>>(),
+    );
+    let expected: RadonTypes = RadonArray::from(
+        [10f64, 11f64, 12f64, 13f64]
+            .iter()
+            .map(|value| RadonFloat::from(*value).into())
+            .collect::<Vec<RadonTypes>>(),
+    )
+    .into();
+
+    let output = filter(
+        &input,
+        RadonFilters::DeviationMedianAbsolute,
+        &[Value::Integer(3)],
+    )
+    .unwrap();
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_filter_wrong_args() {
+    use crate::types::float::RadonFloat;
+
+    let input = RadonArray::from(vec![RadonFloat::from(1f64).into()]);
+
+    let result = filter(
+        &input,
+        RadonFilters::DeviationMedianAbsolute,
+        &[Value::Integer(-1)],
+    );
+
+    assert_eq!(
+        &result.unwrap_err().to_string(),
+        "Wrong `RadonArray::Filter()` arguments: `[Integer(-1)]`"
+    );
+}
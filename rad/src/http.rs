@@ -0,0 +1,197 @@
+//! Deterministic HTTP client for the retrievals of the data requests.
+//!
+//! Witnesses of the same data request must get the same response from a source, so the redirects
+//! and the content encodings of the responses are handled in the same way by all of them, instead
+//! of depending on the defaults of the HTTP client.
+
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use reqwest::{
+    header::{ACCEPT_ENCODING, CONTENT_ENCODING},
+    Client, RedirectPolicy,
+};
+
+use crate::error::RadError;
+use crate::Result;
+
+/// Handling of the redirects and the compressed responses of the sources
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HttpPolicy {
+    /// Maximum number of redirects followed. Redirects to another scheme are never followed
+    pub max_redirects: usize,
+    /// Whether `gzip` and `deflate` encoded responses are accepted and decoded
+    pub decompression: bool,
+}
+
+/// Handling of the HTTP responses of the retrievals once the `rad_http_policy` protocol upgrade is
+/// active. It is part of the protocol rather than of the configuration of each node, as witnesses
+/// following a different number of redirects could get different responses from the same source
+pub const PROTOCOL_HTTP_POLICY: HttpPolicy = HttpPolicy {
+    max_redirects: 3,
+    decompression: true,
+};
+
+impl Default for HttpPolicy {
+    fn default() -> Self {
+        PROTOCOL_HTTP_POLICY
+    }
+}
+
+impl HttpPolicy {
+    /// Build an HTTP client following the redirects allowed by this policy. The client does not
+    /// decode the responses by itself, see `get`.
+    pub fn client(&self) -> Result<Client> {
+        let max_redirects = self.max_redirects;
+
+        Client::builder()
+            .gzip(false)
+            .redirect(RedirectPolicy::custom(move |attempt| {
+                // The previous urls include the one of the original request
+                let previous = attempt.previous();
+                let same_scheme = previous
+                    .last()
+                    .map_or(true, |url| url.scheme() == attempt.url().scheme());
+                if previous.len() > max_redirects {
+                    attempt.too_many_redirects()
+                } else if !same_scheme {
+                    let message = format!("redirect to another scheme: {}", attempt.url());
+                    attempt.error(message)
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .map_err(RadError::from)
+    }
+
+    /// Get the body of the response of a source, decoded as UTF-8 text
+    pub fn get(&self, client: &Client, url: &str) -> Result<String> {
+        let accept_encoding = if self.decompression {
+            "gzip, deflate"
+        } else {
+            "identity"
+        };
+        let mut response = client
+            .get(url)
+            .header(ACCEPT_ENCODING, accept_encoding)
+            .send()?;
+        let content_encoding = response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .map(|value| value.to_str().map(str::to_string))
+            .transpose()
+            .map_err(|e| RadError::Http {
+                message: e.to_string(),
+            })?;
+        let mut body = vec![];
+        response.copy_to(&mut body)?;
+
+        let body = self.decode(content_encoding.as_ref().map(String::as_str), body)?;
+
+        String::from_utf8(body).map_err(|e| RadError::Http {
+            message: e.to_string(),
+        })
+    }
+
+    /// Decode a response body given its `Content-Encoding`. Only a single `gzip` or `deflate`
+    /// encoding is supported, and only if decompression is enabled
+    pub fn decode(&self, content_encoding: Option<&str>, body: Vec<u8>) -> Result<Vec<u8>> {
+        let encoding = content_encoding.map(|encoding| encoding.trim().to_ascii_lowercase());
+        let mut decoded = vec![];
+        let read = match encoding.as_ref().map(String::as_str) {
+            None | Some("") | Some("identity") => return Ok(body),
+            Some("gzip") | Some("x-gzip") if self.decompression => {
+                GzDecoder::new(body.as_slice()).read_to_end(&mut decoded)
+            }
+            Some("deflate") if self.decompression => {
+                ZlibDecoder::new(body.as_slice()).read_to_end(&mut decoded)
+            }
+            Some(encoding) => {
+                return Err(RadError::Http {
+                    message: format!("unsupported content encoding: {}", encoding),
+                })
+            }
+        };
+        read.map_err(|e| RadError::Http {
+            message: format!("failed to decode response: {}", e),
+        })?;
+
+        Ok(decoded)
+    }
+}
+
+/// Get the body of the response of a source with the defaults of the HTTP client, as done until
+/// the `rad_http_policy` protocol upgrade is active
+pub fn get_with_defaults(url: &str) -> Result<String> {
+    reqwest::get(url)?.text().map_err(RadError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{
+        write::{GzEncoder, ZlibEncoder},
+        Compression,
+    };
+
+    use super::*;
+
+    #[test]
+    fn protocol_http_policy() {
+        // Changing these values changes the responses of the sources, so it needs a new upgrade
+        assert_eq!(PROTOCOL_HTTP_POLICY.max_redirects, 3);
+        assert!(PROTOCOL_HTTP_POLICY.decompression);
+        assert_eq!(HttpPolicy::default(), PROTOCOL_HTTP_POLICY);
+    }
+
+    #[test]
+    fn decode_identity() {
+        let policy = HttpPolicy::default();
+
+        assert_eq!(policy.decode(None, b"84".to_vec()).unwrap(), b"84");
+        assert_eq!(
+            policy.decode(Some("identity"), b"84".to_vec()).unwrap(),
+            b"84"
+        );
+    }
+
+    #[test]
+    fn decode_gzip_and_deflate() {
+        let policy = HttpPolicy::default();
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"84").unwrap();
+        let gzip = encoder.finish().unwrap();
+        assert_eq!(policy.decode(Some("gzip"), gzip.clone()).unwrap(), b"84");
+        assert_eq!(policy.decode(Some(" GZIP "), gzip).unwrap(), b"84");
+
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"84").unwrap();
+        let deflate = encoder.finish().unwrap();
+        assert_eq!(policy.decode(Some("deflate"), deflate).unwrap(), b"84");
+
+        // Corrupted bodies are an error
+        assert!(policy.decode(Some("gzip"), b"84".to_vec()).is_err());
+    }
+
+    #[test]
+    fn decode_unsupported() {
+        let policy = HttpPolicy {
+            decompression: false,
+            ..HttpPolicy::default()
+        };
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"84").unwrap();
+        let gzip = encoder.finish().unwrap();
+        assert!(policy.decode(Some("gzip"), gzip).is_err());
+
+        let policy = HttpPolicy::default();
+        assert!(policy.decode(Some("br"), b"84".to_vec()).is_err());
+        assert!(policy
+            .decode(Some("gzip, deflate"), b"84".to_vec())
+            .is_err());
+    }
+}
@@ -2,17 +2,19 @@
 
 use std::convert::TryInto;
 
-use reqwest;
-
 use crate::error::RadError;
+use crate::http::HttpPolicy;
 use crate::script::{execute_radon_script, unpack_radon_script};
 use crate::types::{array::RadonArray, string::RadonString, RadonTypes};
 use witnet_data_structures::chain::{RADAggregate, RADConsensus, RADRetrieve, RADType};
 
 pub mod error;
+pub mod filters;
 pub mod hash_functions;
+pub mod http;
 pub mod operators;
 pub mod reducers;
+pub mod report;
 pub mod script;
 pub mod types;
 
@@ -31,15 +33,48 @@ pub fn run_retrieval_with_data(retrieve: &RADRetrieve, response: String) -> Resu
 }
 
 /// Run retrieval stage of a data request.
+///
+/// The sources are tried in order, and the result of the first one that succeeds is used.
 pub fn run_retrieval(retrieve: &RADRetrieve) -> Result<RadonTypes> {
+    run_retrieval_with_sources(retrieve, http::get_with_defaults)
+}
+
+/// Run retrieval stage of a data request, handling the redirects and the compressed responses of
+/// the sources as defined by an `HttpPolicy`.
+///
+/// The sources are tried in order, and the result of the first one that succeeds is used.
+pub fn run_retrieval_with_policy(
+    retrieve: &RADRetrieve,
+    policy: &HttpPolicy,
+) -> Result<RadonTypes> {
+    let client = policy.client()?;
+
+    run_retrieval_with_sources(retrieve, |url| policy.get(&client, url))
+}
+
+/// Run retrieval stage of a data request, getting the response of each source with `get`.
+///
+/// A source fails if the response cannot be retrieved or the script fails on it, in which case
+/// the next fallback source is tried. If all of them fail, the error of the last one is returned.
+pub fn run_retrieval_with_sources<F>(retrieve: &RADRetrieve, get: F) -> Result<RadonTypes>
+where
+    F: Fn(&str) -> Result<String>,
+{
     match retrieve.kind {
         RADType::HttpGet => {
-            let response = reqwest::get(&retrieve.url)
-                .map_err(RadError::from)?
-                .text()
-                .map_err(RadError::from)?;
-
-            run_retrieval_with_data(retrieve, response)
+            let mut last_error = None;
+            for url in retrieve.urls() {
+                match get(url).and_then(|response| run_retrieval_with_data(retrieve, response)) {
+                    Ok(result) => return Ok(result),
+                    Err(e) => {
+                        log::debug!("Retrieval from {} failed: {}", url, e);
+                        last_error = Some(e);
+                    }
+                }
+            }
+
+            // There is always at least one source, so there is always an error
+            Err(last_error.expect("retrievals have at least one source"))
         }
     }
 }
@@ -88,7 +123,8 @@ fn test_run_retrieval() {
     let retrieve = RADRetrieve {
         kind: RADType::HttpGet,
         url: "https://openweathermap.org/data/2.5/weather?id=2950159&appid=b6907d289e10d714a6e88b30761fae22".to_string(),
-        script
+        script,
+        fallback_urls: vec![],
     };
     let response = r#"{"coord":{"lon":13.41,"lat":52.52},"weather":[{"id":500,"main":"Rain","description":"light rain","icon":"10d"}],"base":"stations","main":{"temp":17.59,"pressure":1022,"humidity":67,"temp_min":15,"temp_max":20},"visibility":10000,"wind":{"speed":3.6,"deg":260},"rain":{"1h":0.51},"clouds":{"all":20},"dt":1567501321,"sys":{"type":1,"id":1275,"message":0.0089,"country":"DE","sunrise":1567484402,"sunset":1567533129},"timezone":7200,"id":2950159,"name":"Berlin","cod":200}"#;
 
@@ -140,6 +176,7 @@ fn test_run_retrieval_random_api() {
         kind: RADType::HttpGet,
         url: "http://qrng.anu.edu.au/API/jsonI.php?length=1&type=uint8".to_string(),
         script,
+        fallback_urls: vec![],
     };
 
     let response = r#"{"type":"uint8","length":1,"data":[4],"success":true}"#;
@@ -159,6 +196,7 @@ fn test_run_all_risk_premium() {
         kind: RADType::HttpGet,
         url: "https://wrapapi.com/use/aesedepece/ffzz/prima/0.0.3?wrapAPIKey=ql4DVWylABdXCpt1NUTLNEDwPH57aHGm".to_string(),
         script: vec![129, 24, 65],
+        fallback_urls: vec![],
     };
     let response = "84";
     let aggregate = RADAggregate {
@@ -192,6 +230,7 @@ fn test_run_all_murders() {
         kind: RADType::HttpGet,
         url: "https://wrapapi.com/use/aesedepece/ffzz/murders/0.0.2?wrapAPIKey=ql4DVWylABdXCpt1NUTLNEDwPH57aHGm".to_string(),
         script: vec![129, 24, 65],
+        fallback_urls: vec![],
     };
     let response = "307";
     let aggregate = RADAggregate {
@@ -228,6 +267,7 @@ fn test_run_all_air_quality() {
             135, 24, 69, 24, 112, 130, 24, 85, 0, 130, 24, 97, 101, 104, 111, 114, 97, 48, 24, 116,
             130, 24, 97, 101, 118, 97, 108, 111, 114, 24, 114,
         ],
+        fallback_urls: vec![],
     };
     // This response was modified because the original was about 100KB.
     let response = r#"[{"estacion_nombre":"Pza. de España","estacion_numero":4,"fecha":"03092019","hora0":{"estado":"Pasado","valor":"00008"}}]"#;
@@ -263,6 +303,7 @@ fn test_run_all_elections() {
         kind: RADType::HttpGet,
         url: "https://wrapapi.com/use/aesedepece/ffzz/generales/0.0.3?wrapAPIKey=ql4DVWylABdXCpt1NUTLNEDwPH57aHGm".to_string(),
         script: vec![132, 24, 69, 24, 116, 130, 24, 97, 100, 80, 83, 79, 69, 24, 114],
+        fallback_urls: vec![],
     };
     let response = r#"{"PSOE":123,"PP":66,"Cs":57,"UP":42,"VOX":24,"ERC-SOBIRANISTES":15,"JxCAT-JUNTS":7,"PNV":6,"EH Bildu":4,"CCa-PNC":2,"NA+":2,"COMPROMÍS 2019":1,"PRC":1,"PACMA":0,"FRONT REPUBLICÀ":0,"BNG":0,"RECORTES CERO-GV":0,"NCa":0,"PACT":0,"ARA-MES-ESQUERRA":0,"GBAI":0,"PUM+J":0,"EN MAREA":0,"PCTE":0,"EL PI":0,"AxSI":0,"PCOE":0,"PCPE":0,"AVANT ADELANTE LOS VERDES":0,"EB":0,"CpM":0,"SOMOS REGIÓN":0,"PCPA":0,"PH":0,"UIG-SOM-CUIDES":0,"ERPV":0,"IZQP":0,"PCPC":0,"AHORA CANARIAS":0,"CxG":0,"PPSO":0,"CNV":0,"PREPAL":0,"C.Ex-C.R.Ex-P.R.Ex":0,"PR+":0,"P-LIB":0,"CILU-LINARES":0,"ANDECHA ASTUR":0,"JF":0,"PYLN":0,"FIA":0,"FE de las JONS":0,"SOLIDARIA":0,"F8":0,"DPL":0,"UNIÓN REGIONALISTA":0,"centrados":0,"DP":0,"VOU":0,"PDSJE-UDEC":0,"IZAR":0,"RISA":0,"C 21":0,"+MAS+":0,"UDT":0}"#;
     let aggregate = RADAggregate {
@@ -289,3 +330,41 @@ fn test_run_all_elections() {
         err => panic!("Error in run_retrieval: {:?}", err),
     }
 }
+
+#[test]
+fn test_run_retrieval_fallback_sources() {
+    let retrieve = RADRetrieve {
+        kind: RADType::HttpGet,
+        url: "http://unreachable.example".to_string(),
+        // [StringAsFloat]
+        script: vec![129, 24, 65],
+        fallback_urls: vec![
+            "http://malformed.example".to_string(),
+            "http://working.example".to_string(),
+        ],
+    };
+    let get = |url: &str| match url {
+        "http://malformed.example" => Ok("not a number".to_string()),
+        "http://working.example" => Ok("84".to_string()),
+        _ => Err(RadError::Http {
+            message: "unreachable".to_string(),
+        }),
+    };
+
+    let result = run_retrieval_with_sources(&retrieve, get).unwrap();
+    assert_eq!(
+        result,
+        RadonTypes::from(types::float::RadonFloat::from(84f64))
+    );
+
+    let retrieve = RADRetrieve {
+        fallback_urls: vec![],
+        ..retrieve
+    };
+    assert_eq!(
+        run_retrieval_with_sources(&retrieve, get).unwrap_err(),
+        RadError::Http {
+            message: "unreachable".to_string(),
+        }
+    );
+}
@@ -1,4 +1,5 @@
 use crate::error::RadError;
+use crate::filters::{self, RadonFilters};
 use crate::reducers::{self, RadonReducers};
 use crate::script::{execute_radon_script, unpack_radon_call};
 use crate::types::{array::RadonArray, RadonType, RadonTypes};
@@ -18,7 +19,21 @@ pub fn reduce(input: &RadonArray, args: &[Value]) -> Result<RadonTypes, RadError
     let reducer_integer = from_value::<i64>(arg).map_err(|_| wrong_args())?;
     let reducer_code = RadonReducers::from_i64(reducer_integer).ok_or_else(wrong_args)?;
 
-    reducers::reduce(input, reducer_code)
+    reducers::reduce(input, reducer_code, &args[1..])
+}
+
+pub fn filter(input: &RadonArray, args: &[Value]) -> Result<RadonTypes, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: "RadonArray".to_string(),
+        operator: "Filter".to_string(),
+        args: args.to_vec(),
+    };
+
+    let arg = args.first().ok_or_else(wrong_args)?.to_owned();
+    let filter_integer = from_value::<i64>(arg).map_err(|_| wrong_args())?;
+    let filter_code = RadonFilters::from_i64(filter_integer).ok_or_else(wrong_args)?;
+
+    filters::filter(input, filter_code, &args[1..])
 }
 
 pub fn get(input: &RadonArray, args: &[Value]) -> Result<RadonTypes, RadError> {
@@ -123,3 +138,35 @@ fn test_reduce_average_mean_float() {
 
     assert_eq!(output, expected);
 }
+
+#[test]
+fn test_reduce_quantile_float() {
+    use crate::types::float::RadonFloat;
+
+    let input = &RadonArray::from(vec![
+        RadonFloat::from(1f64).into(),
+        RadonFloat::from(2f64).into(),
+        RadonFloat::from(3f64).into(),
+    ]);
+    let args = &[Value::Integer(0x12), Value::Integer(50)]; // This is RadonReducers::Quantile
+    let expected = RadonTypes::from(RadonFloat::from(2f64));
+
+    let output = reduce(input, args).unwrap();
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_filter_unknown_filter() {
+    use crate::types::float::RadonFloat;
+
+    let input = &RadonArray::from(vec![RadonFloat::from(1f64).into()]);
+    let args = &[Value::Integer(-1)]; // This doesn't match any filter code in RadonFilters
+
+    let result = filter(input, args);
+
+    assert_eq!(
+        &result.unwrap_err().to_string(),
+        "Wrong `RadonArray::Filter()` arguments: `[Integer(-1)]`"
+    );
+}
@@ -1,4 +1,5 @@
 use crate::error::RadError;
+use crate::reducers::{median_of_sorted, sorted_floats};
 use crate::types::{array::RadonArray, float::RadonFloat, RadonType, RadonTypes};
 
 use std::ops::Div;
@@ -22,3 +23,62 @@ pub fn mean(input: &RadonArray) -> Result<RadonTypes, RadError> {
 
     Ok(RadonTypes::from(RadonFloat::from(mean_value)))
 }
+
+pub fn median(input: &RadonArray) -> Result<RadonTypes, RadError> {
+    let median_value = median_of_sorted(&sorted_floats(input));
+
+    Ok(RadonTypes::from(RadonFloat::from(median_value)))
+}
+
+/// Mean of the values left after dropping `percentage`% of the values from each end. The values
+/// are summed in ascending order, so every implementation gets the same rounding.
+pub fn mean_trimmed(input: &RadonArray, percentage: u8) -> Result<RadonTypes, RadError> {
+    let values = sorted_floats(input);
+    let trimmed = values.len() * usize::from(percentage) / 100;
+    let kept = &values[trimmed..values.len() - trimmed];
+
+    let sum: f64 = kept.iter().fold(0f64, |sum, value| sum + value);
+    let mean_value = sum.div(kept.len() as f64);
+
+    Ok(RadonTypes::from(RadonFloat::from(mean_value)))
+}
+
+#[test]
+fn test_median_odd_and_even() {
+    let floats = |values: &[f64]| {
+        RadonArray::from(
+            values
+                .iter()
+                .map(|value| RadonFloat::from(*value).into())
+                .collect::<Vec<RadonTypes>>(),
+        )
+    };
+
+    assert_eq!(
+        median(&floats(&[3f64, 1f64, 2f64])).unwrap(),
+        RadonTypes::from(RadonFloat::from(2f64))
+    );
+    assert_eq!(
+        median(&floats(&[4f64, 1f64, 3f64, 2f64])).unwrap(),
+        RadonTypes::from(RadonFloat::from(2.5f64))
+    );
+}
+
+#[test]
+fn test_mean_trimmed_drops_outliers() {
+    let input = RadonArray::from(
+        [1f64, 100f64, 3f64, 4f64, 2f64]
+            .iter()
+            .map(|value| RadonFloat::from(*value).into())
+            .collect::<Vec<RadonTypes>>(),
+    );
+
+    assert_eq!(
+        mean_trimmed(&input, 20).unwrap(),
+        RadonTypes::from(RadonFloat::from(3f64))
+    );
+    assert_eq!(
+        mean_trimmed(&input, 0).unwrap(),
+        RadonTypes::from(RadonFloat::from(22f64))
+    );
+}
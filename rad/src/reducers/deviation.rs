@@ -0,0 +1,39 @@
+use crate::error::RadError;
+use crate::reducers::{median_of_sorted, sorted_floats};
+use crate::types::{array::RadonArray, float::RadonFloat, RadonTypes};
+
+use std::cmp::Ordering;
+
+/// Median absolute deviation: median of the absolute differences between the values and their
+/// median.
+pub fn median_absolute(input: &RadonArray) -> Result<RadonTypes, RadError> {
+    let values = sorted_floats(input);
+
+    Ok(RadonTypes::from(RadonFloat::from(
+        median_absolute_of_sorted(&values),
+    )))
+}
+
+/// Median absolute deviation of sorted values.
+pub(crate) fn median_absolute_of_sorted(values: &[f64]) -> f64 {
+    let median = median_of_sorted(values);
+    let mut deviations: Vec<f64> = values.iter().map(|value| (value - median).abs()).collect();
+    deviations.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    median_of_sorted(&deviations)
+}
+
+#[test]
+fn test_median_absolute() {
+    let input = RadonArray::from(
+        [1f64, 1f64, 2f64, 2f64, 4f64, 6f64, 9f64]
+            .iter()
+            .map(|value| RadonFloat::from(*value).into())
+            .collect::<Vec<RadonTypes>>(),
+    );
+
+    assert_eq!(
+        median_absolute(&input).unwrap(),
+        RadonTypes::from(RadonFloat::from(1f64))
+    );
+}
@@ -2,12 +2,16 @@
 #![allow(clippy::useless_attribute)]
 
 use crate::error::RadError;
-use crate::types::{array::RadonArray, RadonTypes};
+use crate::types::{array::RadonArray, RadonType, RadonTypes};
 
 mod average;
+pub(crate) mod deviation;
+mod quantile;
 
 use num_derive::FromPrimitive;
-use std::fmt;
+use serde_cbor::value::{from_value, Value};
+use std::{cmp::Ordering, fmt};
+use witnet_data_structures::upgrades::RAD_STATISTICS;
 
 #[derive(Debug, FromPrimitive, PartialEq)]
 pub enum RadonReducers {
@@ -22,6 +26,11 @@ pub enum RadonReducers {
     DeviationAverageAbsolute = 0x08,
     DeviationMedianAbsolute = 0x09,
     DeviationMaximumAbsolute = 0x10,
+    /// Mean of the values left after dropping the given percentage of the lowest and the highest
+    /// ones
+    AverageMeanTrimmed = 0x11,
+    /// Value at the given percentile, using the nearest-rank method
+    Quantile = 0x12,
 }
 
 impl fmt::Display for RadonReducers {
@@ -30,7 +39,25 @@ impl fmt::Display for RadonReducers {
     }
 }
 
-pub fn reduce(input: &RadonArray, reducer_code: RadonReducers) -> Result<RadonTypes, RadError> {
+impl RadonReducers {
+    /// Protocol upgrade that must be active before data requests can use this reducer, because
+    /// nodes without it would not reach consensus on its result.
+    pub fn upgrade(&self) -> Option<&'static str> {
+        match self {
+            RadonReducers::AverageMedian
+            | RadonReducers::DeviationMedianAbsolute
+            | RadonReducers::AverageMeanTrimmed
+            | RadonReducers::Quantile => Some(RAD_STATISTICS),
+            _ => None,
+        }
+    }
+}
+
+pub fn reduce(
+    input: &RadonArray,
+    reducer_code: RadonReducers,
+    args: &[Value],
+) -> Result<RadonTypes, RadError> {
     let error = || {
         Err(RadError::UnsupportedReducer {
             inner_type: format!("{:?}", input.inner_type()),
@@ -41,9 +68,64 @@ pub fn reduce(input: &RadonArray, reducer_code: RadonReducers) -> Result<RadonTy
     if input.is_homogeneous() {
         match reducer_code {
             RadonReducers::AverageMean => average::mean(input),
+            RadonReducers::AverageMedian => average::median(input),
+            RadonReducers::AverageMeanTrimmed => {
+                average::mean_trimmed(input, percentage_arg(args, 49)?)
+            }
+            RadonReducers::DeviationMedianAbsolute => deviation::median_absolute(input),
+            RadonReducers::Quantile => quantile::quantile(input, percentage_arg(args, 100)?),
             _ => error(),
         }
     } else {
         error()
     }
 }
+
+/// Sorted numeric values of an array, which is how the statistical reducers and filters see their
+/// input.
+///
+/// NaN values are skipped, so the result does not depend on the order of the input.
+pub(crate) fn sorted_floats(input: &RadonArray) -> Vec<f64> {
+    let mut values: Vec<f64> = input
+        .value()
+        .iter()
+        .filter_map(|item| match item {
+            RadonTypes::Float(f64_value) => Some(f64_value.value()),
+            // Skip any non-numeric RadonType
+            _ => None,
+        })
+        .filter(|value| !value.is_nan())
+        .collect();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    values
+}
+
+/// Median of sorted values, the mean of the two middle ones if there is an even number of them.
+pub(crate) fn median_of_sorted(values: &[f64]) -> f64 {
+    let len = values.len();
+    if len == 0 {
+        std::f64::NAN
+    } else if len % 2 == 1 {
+        values[len / 2]
+    } else {
+        (values[len / 2 - 1] + values[len / 2]) / 2f64
+    }
+}
+
+/// Integer percentage given as the first argument of a reducer, up to `max`.
+fn percentage_arg(args: &[Value], max: u8) -> Result<u8, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: "RadonArray".to_string(),
+        operator: "Reduce".to_string(),
+        args: args.to_vec(),
+    };
+
+    let arg = args.first().ok_or_else(wrong_args)?.to_owned();
+    let percentage = from_value::<u8>(arg).map_err(|_| wrong_args())?;
+    if percentage > max {
+        Err(wrong_args())
+    } else {
+        Ok(percentage)
+    }
+}
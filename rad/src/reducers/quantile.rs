@@ -0,0 +1,42 @@
+use crate::error::RadError;
+use crate::reducers::sorted_floats;
+use crate::types::{array::RadonArray, float::RadonFloat, RadonTypes};
+
+/// Value at the given percentile using the nearest-rank method, which always returns one of the
+/// input values: the smallest value such that at least `percentage`% of the values are lower or
+/// equal to it.
+pub fn quantile(input: &RadonArray, percentage: u8) -> Result<RadonTypes, RadError> {
+    let values = sorted_floats(input);
+    let len = values.len();
+
+    let quantile_value = if len == 0 {
+        std::f64::NAN
+    } else {
+        // ceil(percentage * len / 100), computed with integers so it is exact
+        let rank = (usize::from(percentage) * len + 99) / 100;
+        values[rank.max(1) - 1]
+    };
+
+    Ok(RadonTypes::from(RadonFloat::from(quantile_value)))
+}
+
+#[test]
+fn test_quantile_nearest_rank() {
+    use crate::types::RadonType;
+
+    let input = RadonArray::from(
+        (1..=10)
+            .map(|value| RadonFloat::from(f64::from(value)).into())
+            .collect::<Vec<RadonTypes>>(),
+    );
+    let at = |percentage| match quantile(&input, percentage).unwrap() {
+        RadonTypes::Float(float) => float.value(),
+        _ => panic!("quantile is not a float"),
+    };
+
+    assert_eq!(at(0), 1f64);
+    assert_eq!(at(50), 5f64);
+    assert_eq!(at(90), 9f64);
+    assert_eq!(at(91), 10f64);
+    assert_eq!(at(100), 10f64);
+}
@@ -0,0 +1,172 @@
+//! Decoded results of the data requests.
+//!
+//! The tally and reveal transactions only have the CBOR encoding of the values. The node decodes
+//! them with `build_tally_report` when serving the reports of the data requests through JSON-RPC,
+//! so that explorers and front-ends get the values as canonical JSON instead of decoding the CBOR
+//! themselves.
+use std::convert::TryFrom;
+
+use serde::Serialize;
+
+use witnet_data_structures::chain::RADConsensus;
+
+use crate::{
+    operators::RadonOpCodes,
+    script::{execute_radon_script, unpack_radon_script, RadonScript},
+    types::{array::RadonArray, canonical_json::JsonFormat, RadonType, RadonTypes},
+};
+
+/// Decoded result of a data request
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TallyReport {
+    /// Result of the tally, as canonical JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_json: Option<String>,
+    /// Error decoding the result of the tally, or encoding it as canonical JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Decoded reveals, in the same order as the reveals the report was built from
+    pub reveals: Vec<RevealReport>,
+}
+
+/// Decoded value revealed by a witness
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RevealReport {
+    /// Revealed value, as canonical JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_json: Option<String>,
+    /// Error decoding the revealed value, or encoding it as canonical JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether the value was taken into account by the consensus script. Values that cannot be
+    /// decoded, or that are dropped by the filters of the script, are out of consensus.
+    pub in_consensus: bool,
+}
+
+/// Decode the result of a data request and the values revealed by its witnesses, flagging the
+/// reveals that were out of consensus.
+pub fn build_tally_report(
+    tally: &[u8],
+    reveals: &[&[u8]],
+    consensus: &RADConsensus,
+    format: JsonFormat,
+) -> TallyReport {
+    let (result_json, error) = decode(tally, format);
+    let values: Vec<Option<RadonTypes>> = reveals
+        .iter()
+        .map(|reveal| RadonTypes::try_from(*reveal).ok())
+        .collect();
+    let in_consensus = in_consensus(&values, consensus);
+
+    let reveals = reveals
+        .iter()
+        .zip(in_consensus)
+        .map(|(reveal, in_consensus)| {
+            let (result_json, error) = decode(reveal, format);
+
+            RevealReport {
+                result_json,
+                error,
+                in_consensus,
+            }
+        })
+        .collect();
+
+    TallyReport {
+        result_json,
+        error,
+        reveals,
+    }
+}
+
+/// Decode a value as canonical JSON, or return the error decoding or encoding it.
+fn decode(value: &[u8], format: JsonFormat) -> (Option<String>, Option<String>) {
+    match RadonTypes::try_from(value).and_then(|value| value.to_canonical_json(format)) {
+        Ok(json) => (Some(json), None),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Whether each of the decoded values is kept by the filters at the start of the consensus
+/// script, which run before reducing the values.
+fn in_consensus(values: &[Option<RadonTypes>], consensus: &RADConsensus) -> Vec<bool> {
+    let filters: RadonScript = unpack_radon_script(&consensus.script)
+        .unwrap_or_default()
+        .into_iter()
+        .take_while(|(op_code, _)| *op_code == RadonOpCodes::ArrayFilter)
+        .collect();
+    let decoded: Vec<RadonTypes> = values.iter().flatten().cloned().collect();
+    let mut kept = match execute_radon_script(RadonArray::from(decoded).into(), &filters) {
+        Ok(RadonTypes::Array(array)) => array.value(),
+        _ => vec![],
+    };
+
+    values
+        .iter()
+        .map(|value| {
+            // Equal values are only kept as many times as they were kept by the filters
+            let position = value
+                .as_ref()
+                .and_then(|value| kept.iter().position(|kept| kept == value));
+            match position {
+                Some(position) => {
+                    kept.swap_remove(position);
+                    true
+                }
+                None => false,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+    use crate::types::float::RadonFloat;
+
+    fn float(value: f64) -> Vec<u8> {
+        RadonTypes::Float(RadonFloat::from(value))
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn reveals_are_decoded() {
+        // [[ArrayReduce, AverageMean]]
+        let consensus = RADConsensus {
+            script: vec![0x81, 0x82, 0x18, 0x57, 0x03],
+        };
+        let (tally, one, three) = (float(2.0), float(1.0), float(3.0));
+        let reveals = [one.as_slice(), three.as_slice(), &[0xff]];
+
+        let report = build_tally_report(&tally, &reveals, &consensus, JsonFormat::LATEST);
+
+        assert_eq!(report.result_json, Some("2e0".to_string()));
+        assert_eq!(report.error, None);
+        assert_eq!(report.reveals[0].result_json, Some("1e0".to_string()));
+        assert_eq!(report.reveals[1].result_json, Some("3e0".to_string()));
+        assert!(report.reveals[2].error.is_some());
+        let in_consensus: Vec<bool> = report.reveals.iter().map(|r| r.in_consensus).collect();
+        assert_eq!(in_consensus, vec![true, true, false]);
+    }
+
+    #[test]
+    fn filtered_reveals_are_out_of_consensus() {
+        // [[ArrayFilter, DeviationMedianAbsolute, 3], [ArrayReduce, AverageMean]]
+        let consensus = RADConsensus {
+            script: vec![0x82, 0x83, 0x18, 0x53, 0x09, 0x03, 0x82, 0x18, 0x57, 0x03],
+        };
+        let reveals: Vec<Vec<u8>> = [10.0, 100.0, 11.0, 12.0, 12.0]
+            .iter()
+            .map(|value| float(*value))
+            .collect();
+        let reveals: Vec<&[u8]> = reveals.iter().map(Vec::as_slice).collect();
+
+        let report = build_tally_report(&float(11.25), &reveals, &consensus, JsonFormat::LATEST);
+
+        let in_consensus: Vec<bool> = report.reveals.iter().map(|r| r.in_consensus).collect();
+        assert_eq!(in_consensus, vec![true, false, true, true, true]);
+    }
+}
@@ -7,7 +7,9 @@ use serde_cbor::{
 
 use crate::error::RadError;
 use crate::operators::{operate, RadonOpCodes};
+use crate::reducers::RadonReducers;
 use crate::types::RadonTypes;
+use witnet_data_structures::upgrades::RAD_STATISTICS;
 
 pub type RadonCall = (RadonOpCodes, Option<Vec<Value>>);
 
@@ -67,6 +69,40 @@ fn unpack_compound_call(array: &[Value]) -> Result<RadonCall, RadError> {
         .unwrap_or_else(Err)
 }
 
+/// Protocol upgrades that must be active before a script can be used in a data request, because
+/// nodes without them would not be able to run it or would not reach consensus on its result.
+pub fn required_upgrades(script: &[RadonCall]) -> Vec<&'static str> {
+    let mut upgrades = vec![];
+    for (op_code, args) in script {
+        let args = args.as_ref().map(Vec::as_slice).unwrap_or_default();
+        match op_code {
+            RadonOpCodes::ArrayFilter => upgrades.push(RAD_STATISTICS),
+            RadonOpCodes::ArrayReduce => {
+                let reducer = args
+                    .first()
+                    .and_then(|arg| from_value::<i64>(arg.to_owned()).ok())
+                    .and_then(RadonReducers::from_i64);
+                if let Some(upgrade) = reducer.and_then(|reducer| reducer.upgrade()) {
+                    upgrades.push(upgrade);
+                }
+            }
+            // The arguments of a map are the calls of its subscript
+            RadonOpCodes::ArrayMap => {
+                let subscript: Vec<RadonCall> = args
+                    .iter()
+                    .filter_map(|arg| unpack_radon_call(arg).ok())
+                    .collect();
+                upgrades.extend(required_upgrades(&subscript));
+            }
+            _ => {}
+        }
+    }
+    upgrades.sort();
+    upgrades.dedup();
+
+    upgrades
+}
+
 fn errorify(kind: RadError) -> RadError {
     error!("Error unpacking a RADON script: {:?}", kind);
 
@@ -125,3 +161,23 @@ fn test_unpack_radon_script() {
 
     assert_eq!(output, expected)
 }
+
+#[test]
+fn test_required_upgrades() {
+    let script = vec![
+        (RadonOpCodes::BytesAsArray, None),
+        (
+            RadonOpCodes::ArrayMap,
+            Some(vec![Value::Array(vec![
+                Value::Integer(RadonOpCodes::ArrayReduce as i128),
+                Value::Integer(RadonReducers::AverageMedian as i128),
+            ])]),
+        ),
+        (
+            RadonOpCodes::ArrayReduce,
+            Some(vec![Value::Integer(RadonReducers::AverageMean as i128)]),
+        ),
+    ];
+    assert_eq!(required_upgrades(&script), vec![RAD_STATISTICS]);
+    assert!(required_upgrades(&script[2..]).is_empty());
+}
@@ -0,0 +1,266 @@
+//! Canonical JSON encoding of `RadonTypes`.
+//!
+//! Unlike `to_string()` or the derived `Serialize` implementations, the output of this encoder is
+//! stable JSON that consumers can rely on:
+//!
+//! - map keys are sorted,
+//! - integers are written in decimal notation,
+//! - floats are always written in scientific notation with the shortest digits that round-trip
+//!   (e.g. `1.5e0`), and non-finite floats as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`,
+//! - byte arrays are written as hex strings.
+//!
+//! Only maps with text keys can be encoded, as the keys of other types could not be told apart
+//! from text keys, e.g. the integer key `1` from the text key `"1"`.
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+use serde_cbor::value::Value;
+
+use crate::error::RadError;
+use crate::types::{RadonType, RadonTypes};
+use crate::Result;
+
+/// Version of the canonical JSON format.
+///
+/// Consumers should check the version before parsing, as future versions may change the encoding
+/// of some types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JsonFormat {
+    /// First version of the format.
+    V1,
+}
+
+impl JsonFormat {
+    /// Latest version of the format.
+    pub const LATEST: JsonFormat = JsonFormat::V1;
+
+    /// Numeric version of the format.
+    pub fn version(self) -> u8 {
+        match self {
+            JsonFormat::V1 => 1,
+        }
+    }
+}
+
+impl RadonTypes {
+    /// Encode as canonical JSON using the given format version. Fails if the value contains a
+    /// map with a key that is not text.
+    pub fn to_canonical_json(&self, format: JsonFormat) -> Result<String> {
+        let mut out = String::new();
+
+        match format {
+            JsonFormat::V1 => write_radon_types(self, &mut out)?,
+        }
+
+        Ok(out)
+    }
+}
+
+fn write_radon_types(radon_types: &RadonTypes, out: &mut String) -> Result<()> {
+    match radon_types {
+        RadonTypes::Array(array) => {
+            out.push('[');
+            for (i, item) in array.value().iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_radon_types(item, out)?;
+            }
+            out.push(']');
+        }
+        RadonTypes::Boolean(boolean) => write_bool(boolean.value(), out),
+        RadonTypes::Float(float) => write_float(float.value(), out),
+        RadonTypes::Map(map) => {
+            let entries: BTreeMap<String, Value> = map
+                .value()
+                .into_iter()
+                .map(|(key, value)| (key, value.value()))
+                .collect();
+            out.push('{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(value, out)?;
+            }
+            out.push('}');
+        }
+        RadonTypes::Bytes(bytes) => write_value(&bytes.value(), out)?,
+        RadonTypes::String(string) => write_string(&string.value(), out),
+        RadonTypes::Integer(integer) => write_integer(integer.value(), out),
+    }
+
+    Ok(())
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<()> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(boolean) => write_bool(*boolean, out),
+        Value::Integer(integer) => write_integer(*integer, out),
+        Value::Float(float) => write_float(*float, out),
+        Value::Bytes(bytes) => write_string(&hex::encode(bytes), out),
+        Value::Text(text) => write_string(text, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::Map(map) => {
+            let entries = map
+                .iter()
+                .map(|(key, value)| match key {
+                    Value::Text(text) => Ok((text, value)),
+                    _ => Err(RadError::Encode {
+                        from: "map with a non-text key".to_string(),
+                        to: "canonical JSON".to_string(),
+                    }),
+                })
+                .collect::<Result<BTreeMap<&String, &Value>>>()?;
+            out.push('{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(value, out)?;
+            }
+            out.push('}');
+        }
+        // Tags carry no meaning for consumers, only the tagged value is encoded
+        Value::Tag(_, value) => write_value(value, out)?,
+        _ => out.push_str("null"),
+    }
+
+    Ok(())
+}
+
+fn write_bool(boolean: bool, out: &mut String) {
+    out.push_str(if boolean { "true" } else { "false" });
+}
+
+fn write_integer(integer: i128, out: &mut String) {
+    write!(out, "{}", integer).unwrap();
+}
+
+fn write_float(float: f64, out: &mut String) {
+    if float.is_nan() {
+        out.push_str("\"NaN\"");
+    } else if float.is_infinite() {
+        out.push_str(if float > 0.0 {
+            "\"Infinity\""
+        } else {
+            "\"-Infinity\""
+        });
+    } else {
+        write!(out, "{:e}", float).unwrap();
+    }
+}
+
+fn write_string(string: &str, out: &mut String) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::{
+        array::RadonArray, bytes::RadonBytes, float::RadonFloat, integer::RadonInteger,
+        map::RadonMap, string::RadonString,
+    };
+
+    fn to_json(radon_types: RadonTypes) -> String {
+        radon_types.to_canonical_json(JsonFormat::V1).unwrap()
+    }
+
+    #[test]
+    fn canonical_json_floats() {
+        assert_eq!(to_json(RadonFloat::from(1.5).into()), "1.5e0");
+        assert_eq!(to_json(RadonFloat::from(100.0).into()), "1e2");
+        assert_eq!(to_json(RadonFloat::from(-0.001).into()), "-1e-3");
+        assert_eq!(to_json(RadonFloat::from(std::f64::NAN).into()), "\"NaN\"");
+        assert_eq!(
+            to_json(RadonFloat::from(std::f64::NEG_INFINITY).into()),
+            "\"-Infinity\""
+        );
+    }
+
+    #[test]
+    fn canonical_json_map_keys_are_sorted() {
+        let mut map = HashMap::new();
+        map.insert("b".to_string(), RadonBytes::from(Value::Integer(2)));
+        map.insert(
+            "a".to_string(),
+            RadonBytes::from(Value::Bytes(vec![0xca, 0xfe])),
+        );
+        map.insert(
+            "c".to_string(),
+            RadonBytes::from(Value::Text("x\"y".to_string())),
+        );
+
+        assert_eq!(
+            to_json(RadonMap::from(map).into()),
+            r#"{"a":"cafe","b":2,"c":"x\"y"}"#
+        );
+    }
+
+    #[test]
+    fn canonical_json_array() {
+        let array = RadonArray::from(vec![
+            RadonInteger::from(-3).into(),
+            RadonString::from("hello\n").into(),
+        ]);
+
+        assert_eq!(to_json(array.into()), r#"[-3,"hello\n"]"#);
+    }
+
+    #[test]
+    fn canonical_json_non_text_map_keys() {
+        // The integer key would be encoded as the text key
+        let mut inner = BTreeMap::new();
+        inner.insert(Value::Integer(1), Value::Integer(2));
+        inner.insert(Value::Text("1".to_string()), Value::Integer(3));
+        let mut map = HashMap::new();
+        map.insert("a".to_string(), RadonBytes::from(Value::Map(inner)));
+
+        match RadonTypes::from(RadonMap::from(map)).to_canonical_json(JsonFormat::V1) {
+            Err(RadError::Encode { .. }) => {}
+            result => panic!("encoded a map with a non-text key: {:?}", result),
+        }
+
+        // Nested maps with text keys are sorted too
+        let mut inner = BTreeMap::new();
+        inner.insert(Value::Text("y".to_string()), Value::Null);
+        inner.insert(Value::Text("x".to_string()), Value::Bool(true));
+        let mut map = HashMap::new();
+        map.insert("a".to_string(), RadonBytes::from(Value::Map(inner)));
+
+        assert_eq!(
+            to_json(RadonMap::from(map).into()),
+            r#"{"a":{"x":true,"y":null}}"#
+        );
+    }
+}
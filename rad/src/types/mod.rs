@@ -20,6 +20,7 @@ use crate::{
 pub mod array;
 pub mod boolean;
 pub mod bytes;
+pub mod canonical_json;
 pub mod float;
 pub mod integer;
 pub mod map;
@@ -0,0 +1,38 @@
+//! Test vectors of the statistical reducers and filters.
+//!
+//! The vectors are kept in `vectors/statistics.json` so other implementations of the RAD engine
+//! can check that they produce exactly the same results, which is required for the witnesses to
+//! reach consensus. Each vector has a packed script in hex, spaces allowed, an array of float
+//! inputs, where `"NaN"` stands for a NaN value, and the expected float output.
+use witnet_rad::{
+    script::{execute_radon_script, unpack_radon_script},
+    types::{array::RadonArray, float::RadonFloat, RadonTypes},
+};
+
+#[test]
+fn statistics_test_vectors() {
+    let vectors = json::parse(include_str!("vectors/statistics.json")).unwrap();
+
+    for vector in vectors.members() {
+        let name = vector["name"].as_str().unwrap();
+        let script_hex: String = vector["script"]
+            .as_str()
+            .unwrap()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        let script = unpack_radon_script(&hex::decode(script_hex).unwrap()).unwrap();
+        let input: Vec<RadonTypes> = vector["input"]
+            .members()
+            .map(|value| {
+                let value = value.as_f64().unwrap_or(std::f64::NAN);
+                RadonFloat::from(value).into()
+            })
+            .collect();
+        let expected = RadonTypes::from(RadonFloat::from(vector["output"].as_f64().unwrap()));
+
+        let output = execute_radon_script(RadonArray::from(input).into(), &script).unwrap();
+
+        assert_eq!(output, expected, "{}", name);
+    }
+}
@@ -5,9 +5,12 @@ use structopt::StructOpt;
 use terminal_size as term;
 
 use witnet_config as config;
+#[cfg(not(feature = "tracing"))]
+use witnet_util::{correlation, crash};
 
 mod node;
 mod wallet;
+mod wallet_cli;
 
 pub fn from_args() -> Cli {
     Cli::from_args()
@@ -21,8 +24,8 @@ pub fn exec(command: Cli) -> Result<(), failure::Error> {
             trace,
             no_timestamp,
             no_module_path,
+            json,
             cmd,
-            ..
         } => {
             let mut log_opts = LogOptions::default();
             let config = get_config(config.or_else(config::dirs::find_config))?;
@@ -48,41 +51,91 @@ pub fn exec(command: Cli) -> Result<(), failure::Error> {
             }
 
             init_logger(log_opts);
-            exec_cmd(cmd, config)
+            exec_cmd(cmd, config, json)
         }
     }
 }
 
-fn exec_cmd(command: Command, config: config::config::Config) -> Result<(), failure::Error> {
+fn exec_cmd(
+    command: Command,
+    config: config::config::Config,
+    json: bool,
+) -> Result<(), failure::Error> {
     match command {
-        Command::Node(cmd) => node::exec_cmd(cmd, config),
+        Command::Node(cmd) => node::exec_cmd(cmd, config, json),
         Command::Wallet(cmd) => wallet::exec_cmd(cmd, config),
+        Command::WalletCli(cmd) => wallet_cli::exec_cmd(cmd, config, json),
     }
 }
 
+#[cfg(not(feature = "tracing"))]
 fn init_logger(opts: LogOptions) {
-    println!(
+    use std::io::Write;
+
+    eprintln!(
         "Setting log level to: {}, source: {:?}",
         opts.level, opts.source
     );
+    let LogOptions {
+        timestamp,
+        module_path,
+        ..
+    } = opts;
     env_logger::Builder::from_env(env_logger::Env::default())
-        .default_format_timestamp(opts.timestamp)
-        .default_format_module_path(opts.module_path)
+        .format(move |buf, record| {
+            write!(buf, "[")?;
+            if timestamp {
+                write!(buf, "{} ", buf.timestamp())?;
+            }
+            write!(buf, "{:<5}", buf.default_styled_level(record.level()))?;
+            if module_path {
+                if let Some(module_path) = record.module_path() {
+                    write!(buf, " {}", module_path)?;
+                }
+            }
+            write!(buf, "]")?;
+            // Show the correlation id of the request being handled, if any
+            if let Some(correlation_id) = correlation::current() {
+                write!(buf, " [{}]", correlation_id)?;
+            }
+            writeln!(buf, " {}", record.args())?;
+            // Keep the recent lines so they can be included in a crash report
+            crash::record_log(format!("{:<5} {}", record.level(), record.args()));
+
+            Ok(())
+        })
         .filter_level(log::LevelFilter::Info)
         .filter_module("witnet", opts.level)
         .init();
 }
 
+/// With the `tracing` feature, log records are forwarded to a `tracing` subscriber so they are
+/// printed along with the spans of the RPC calls and block processing cycles.
+#[cfg(feature = "tracing")]
+fn init_logger(opts: LogOptions) {
+    eprintln!(
+        "Setting log level to: {}, source: {:?} (tracing enabled)",
+        opts.level, opts.source
+    );
+    tracing_log::LogTracer::init_with_filter(opts.level)
+        .expect("Failed to forward log records to tracing");
+    let subscriber = tracing_subscriber::fmt::Subscriber::builder()
+        .with_max_level(tracing_core::Level::TRACE)
+        .finish();
+    tracing_core::dispatcher::set_global_default(tracing_core::Dispatch::new(subscriber))
+        .expect("Failed to set the tracing subscriber");
+}
+
 fn get_config(path: Option<PathBuf>) -> Result<config::config::Config, failure::Error> {
     match path {
         Some(p) => {
-            println!("Loading config from: {}", p.display());
+            eprintln!("Loading config from: {}", p.display());
             let config = config::loaders::toml::from_file(p)
                 .map(|p| config::config::Config::from_partial(&p))?;
             Ok(config)
         }
         None => {
-            println!("HEADS UP! No configuration specified/found. Using default one!");
+            eprintln!("HEADS UP! No configuration specified/found. Using default one!");
             Ok(config::config::Config::default())
         }
     }
@@ -105,6 +158,9 @@ pub struct Cli {
     /// Do not show module path in logs.
     #[structopt(long = "no-module-path")]
     no_module_path: bool,
+    /// Print the output of the node and wallet-cli commands as JSON, including the errors.
+    #[structopt(long = "json", raw(global = "true"))]
+    json: bool,
     #[structopt(subcommand)]
     cmd: Command,
 }
@@ -115,6 +171,11 @@ enum Command {
     Node(node::Command),
     #[structopt(name = "wallet", about = "Witnet wallet.")]
     Wallet(wallet::Command),
+    #[structopt(
+        name = "wallet-cli",
+        about = "Manage wallets through a running wallet server."
+    )]
+    WalletCli(wallet_cli::Command),
 }
 
 struct LogOptions {
@@ -154,8 +215,7 @@ lazy_static! {
     };
 }
 
-static CONFIG_HELP: &str =
-    r#"Load configuration from this file. If not specified will try to find a configuration
+static CONFIG_HELP: &str = r#"Load configuration from this file. If not specified will try to find a configuration
 in these paths:
 - current path
 - standard configuration path:
@@ -10,7 +10,10 @@ use failure::Fail;
 use serde::Deserialize;
 
 use witnet_data_structures::chain::{OutputPointer, PublicKeyHash, ValueTransferOutput};
-use witnet_node::actors::{json_rpc::json_rpc_methods::GetBlockChainParams, messages::BuildVtt};
+use witnet_node::actors::{
+    json_rpc::json_rpc_methods::{BlockChainChunk, GetBlockChainChunkParams, GetBlockChainParams},
+    messages::BuildVtt,
+};
 
 pub fn raw(addr: SocketAddr) -> Result<(), failure::Error> {
     let mut stream = start_client(addr)?;
@@ -30,7 +33,12 @@ pub fn raw(addr: SocketAddr) -> Result<(), failure::Error> {
     }
 }
 
-pub fn get_blockchain(addr: SocketAddr, epoch: u32, limit: u32) -> Result<(), failure::Error> {
+pub fn get_blockchain(
+    addr: SocketAddr,
+    epoch: u32,
+    limit: u32,
+    json: bool,
+) -> Result<(), failure::Error> {
     let mut stream = start_client(addr)?;
     let params = GetBlockChainParams {
         epoch: i64::from(epoch),
@@ -44,6 +52,9 @@ pub fn get_blockchain(addr: SocketAddr, epoch: u32, limit: u32) -> Result<(), fa
         ),
     )?;
     log::info!("{}", response);
+    if json {
+        return print_json_result(&response);
+    }
     let block_chain: ResponseBlockChain<'_> = parse_response(&response)?;
 
     for (epoch, hash) in block_chain {
@@ -53,7 +64,53 @@ pub fn get_blockchain(addr: SocketAddr, epoch: u32, limit: u32) -> Result<(), fa
     Ok(())
 }
 
-pub fn get_balance(addr: SocketAddr, pkh: Option<PublicKeyHash>) -> Result<(), failure::Error> {
+/// Get all the block hashes from `epoch` on, requesting them in chunks with `getBlockChainChunk`
+/// until there are no more
+pub fn get_blockchain_all(addr: SocketAddr, epoch: u32, json: bool) -> Result<(), failure::Error> {
+    let mut stream = start_client(addr)?;
+    let mut params = GetBlockChainChunkParams {
+        epoch: i64::from(epoch),
+        ..GetBlockChainChunkParams::default()
+    };
+    let mut all_blocks = vec![];
+
+    loop {
+        let response = send_request(
+            &mut stream,
+            &format!(
+                r#"{{"jsonrpc": "2.0","method": "getBlockChainChunk", "params": {}, "id": 1}}"#,
+                serde_json::to_string(&params)?
+            ),
+        )?;
+        let chunk: BlockChainChunk = parse_response(&response)?;
+        log::info!("Received {} block hashes", chunk.blocks.len());
+
+        if json {
+            all_blocks.extend(chunk.blocks);
+        } else {
+            for (epoch, hash) in chunk.blocks {
+                println!("block for epoch #{} had digest {}", epoch, hash);
+            }
+        }
+
+        match chunk.continuation {
+            Some(continuation) => params.continuation = Some(continuation),
+            None => break,
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string(&all_blocks)?);
+    }
+
+    Ok(())
+}
+
+pub fn get_balance(
+    addr: SocketAddr,
+    pkh: Option<PublicKeyHash>,
+    json: bool,
+) -> Result<(), failure::Error> {
     let mut stream = start_client(addr)?;
 
     let pkh = match pkh {
@@ -75,6 +132,9 @@ pub fn get_balance(addr: SocketAddr, pkh: Option<PublicKeyHash>) -> Result<(), f
     );
     let response = send_request(&mut stream, &request)?;
     log::info!("{}", response);
+    if json {
+        return print_json_result(&response);
+    }
     let amount = parse_response::<u64>(&response)?;
 
     println!("{}", amount);
@@ -82,11 +142,14 @@ pub fn get_balance(addr: SocketAddr, pkh: Option<PublicKeyHash>) -> Result<(), f
     Ok(())
 }
 
-pub fn get_pkh(addr: SocketAddr) -> Result<(), failure::Error> {
+pub fn get_pkh(addr: SocketAddr, json: bool) -> Result<(), failure::Error> {
     let mut stream = start_client(addr)?;
     let request = r#"{"jsonrpc": "2.0","method": "getPkh", "id": "1"}"#;
     let response = send_request(&mut stream, &request)?;
     log::info!("{}", response);
+    if json {
+        return print_json_result(&response);
+    }
     let pkh = parse_response::<PublicKeyHash>(&response)?;
 
     println!("{}", pkh);
@@ -94,20 +157,39 @@ pub fn get_pkh(addr: SocketAddr) -> Result<(), failure::Error> {
     Ok(())
 }
 
-pub fn get_block(addr: SocketAddr, hash: String) -> Result<(), failure::Error> {
+pub fn get_block(addr: SocketAddr, hash: String, json: bool) -> Result<(), failure::Error> {
     let mut stream = start_client(addr)?;
     let request = format!(
         r#"{{"jsonrpc": "2.0","method": "getBlock", "params": [{:?}], "id": "1"}}"#,
         hash,
     );
     let response = send_request(&mut stream, &request)?;
+    if json {
+        return print_json_result(&response);
+    }
+
+    println!("{}", response);
+
+    Ok(())
+}
+
+pub fn get_block_by_epoch(addr: SocketAddr, epoch: u32, json: bool) -> Result<(), failure::Error> {
+    let mut stream = start_client(addr)?;
+    let request = format!(
+        r#"{{"jsonrpc": "2.0","method": "getBlockByEpoch", "params": [{}], "id": "1"}}"#,
+        epoch,
+    );
+    let response = send_request(&mut stream, &request)?;
+    if json {
+        return print_json_result(&response);
+    }
 
     println!("{}", response);
 
     Ok(())
 }
 
-pub fn get_output(addr: SocketAddr, pointer: String) -> Result<(), failure::Error> {
+pub fn get_output(addr: SocketAddr, pointer: String, json: bool) -> Result<(), failure::Error> {
     let mut _stream = start_client(addr)?;
     let output_pointer = OutputPointer::from_str(&pointer)?;
     let request_payload = serde_json::to_string(&output_pointer)?;
@@ -116,6 +198,9 @@ pub fn get_output(addr: SocketAddr, pointer: String) -> Result<(), failure::Erro
         request_payload,
     );
     //let response = send_request(&mut stream, &request)?;
+    if json {
+        return Err(failure::format_err!("getOutput is not implemented yet"));
+    }
     let response = "unimplemented yet";
 
     println!("{}", response);
@@ -128,23 +213,56 @@ pub fn send_vtt(
     pkh: PublicKeyHash,
     value: u64,
     fee: u64,
+    subtract_fee: bool,
+    json: bool,
 ) -> Result<(), failure::Error> {
     let mut stream = start_client(addr)?;
     let params = BuildVtt {
         vto: vec![ValueTransferOutput { pkh, value }],
         fee,
+        subtract_fee_from_amount: subtract_fee,
+        ..BuildVtt::default()
     };
     let request = format!(
         r#"{{"jsonrpc": "2.0","method": "sendValue", "params": {}, "id": "1"}}"#,
         serde_json::to_string(&params)?
     );
     let response = send_request(&mut stream, &request)?;
+    if json {
+        return print_json_result(&response);
+    }
 
     println!("{}", response);
 
     Ok(())
 }
 
+/// Print an error as a JSON object with its `code` and `message`, which is the output of the
+/// failed commands when the `--json` flag is used.
+///
+/// The code is the one of the JSON-RPC error returned by the server, or `null` if the error
+/// happened in the client, e.g. if the node could not be reached.
+pub fn print_json_error(err: &failure::Error) {
+    println!("{}", json_error(err));
+}
+
+fn json_error(err: &failure::Error) -> serde_json::Value {
+    let (code, message) = match err.downcast_ref::<ServerError>() {
+        Some(ServerError { code, message }) => (Some(*code), message.clone()),
+        None => (None, err.to_string()),
+    };
+
+    serde_json::json!({ "error": { "code": code, "message": message } })
+}
+
+/// Print the result of a JSON-RPC response as a single line of JSON.
+fn print_json_result(response: &str) -> Result<(), failure::Error> {
+    let result: serde_json::Value = parse_response(response)?;
+    println!("{}", result);
+
+    Ok(())
+}
+
 // Response of the getBlockChain JSON-RPC method
 type ResponseBlockChain<'a> = Vec<(u32, &'a str)>;
 
@@ -268,6 +386,23 @@ mod tests {
         assert!(block_chain.is_err());
     }
 
+    #[test]
+    fn json_server_error() {
+        let response =
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}"#;
+        let err = parse_response::<serde_json::Value>(&response).unwrap_err();
+        assert_eq!(
+            json_error(&err).to_string(),
+            r#"{"error":{"code":-32601,"message":"Method not found"}}"#
+        );
+
+        let err = failure::format_err!("Connection refused");
+        assert_eq!(
+            json_error(&err).to_string(),
+            r#"{"error":{"code":null,"message":"Connection refused"}}"#
+        );
+    }
+
     #[test]
     fn parse_get_block_chain() {
         let response = r#"{"jsonrpc":"2.0","result":[[0,"ed28899af8c3148a4162736af942bc68c4466da93c5124dabfaa7c582af49e30"],[1,"9c9038cfb31a7050796920f91b17f4a68c7e9a795ee8962916b35d39fc1efefc"]],"id":1}"#;
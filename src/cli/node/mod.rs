@@ -1,6 +1,8 @@
 #[cfg(feature = "node")]
 mod json_rpc_client;
 #[cfg(feature = "node")]
+mod repl;
+#[cfg(feature = "node")]
 mod with_node;
 #[cfg(not(feature = "node"))]
 mod without_node;
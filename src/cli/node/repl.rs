@@ -0,0 +1,249 @@
+//! Interactive prompt for the node commands.
+//!
+//! Every line is parsed as the arguments of `witnet node`, with a few additions:
+//!
+//! - `@<name>` is replaced with the public key hash saved in the address book as `<name>`.
+//! - The node address set with the `node` built-in is used when `--node` is omitted, and it is
+//!   remembered across sessions, along with the address book.
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, Write},
+    iter,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use witnet_config::{config::Config, dirs};
+use witnet_data_structures::chain::PublicKeyHash;
+
+use super::with_node::{exec, Command};
+
+/// Node commands available in the REPL. Running the node server or reading raw requests from
+/// stdin do not make sense inside the prompt.
+const NODE_COMMANDS: &[&str] = &[
+    "block",
+    "blockByEpoch",
+    "blockchain",
+    "getBalance",
+    "getPkh",
+    "output",
+    "send",
+    "verifyChain",
+];
+
+/// Commands implemented by the REPL itself.
+const BUILTIN_COMMANDS: &[&str] = &[
+    "contact",
+    "contacts",
+    "exit",
+    "help",
+    "node",
+    "quit",
+    "remove-contact",
+];
+
+const PROMPT: &str = "witnet> ";
+
+/// Settings of the REPL persisted across sessions.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ReplState {
+    /// Default address of the node to query.
+    node: Option<SocketAddr>,
+    /// Public key hashes by name, referenced as `@<name>` in the commands.
+    #[serde(default)]
+    address_book: BTreeMap<String, String>,
+}
+
+impl ReplState {
+    fn load(path: &Path) -> Result<Self, failure::Error> {
+        if path.exists() {
+            Ok(toml::from_str(&fs::read_to_string(path)?)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), failure::Error> {
+        fs::write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+}
+
+/// Run the REPL until the user exits with `exit`, `quit` or Ctrl-D.
+pub fn run(config: Config, json: bool) -> Result<(), failure::Error> {
+    let data_dir = dirs::data_dir();
+    fs::create_dir_all(&data_dir)?;
+    let state_path = data_dir.join("repl.toml");
+
+    let mut repl = Repl {
+        config,
+        json,
+        state: ReplState::load(&state_path)?,
+        state_path,
+    };
+
+    println!("Type `help` to list the available commands, `exit` or Ctrl-D to leave.");
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("{}", PROMPT);
+        io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match repl.eval(&line) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+struct Repl {
+    config: Config,
+    json: bool,
+    state: ReplState,
+    state_path: PathBuf,
+}
+
+impl Repl {
+    /// Evaluate a line of input, returning whether the REPL should keep running.
+    fn eval(&mut self, line: &str) -> Result<bool, failure::Error> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return Ok(true),
+        };
+
+        match (command, args) {
+            ("exit", []) | ("quit", []) => return Ok(false),
+            ("help", []) => print_help(),
+            ("node", []) => println!("{}", self.node_address()),
+            ("node", [address]) => {
+                self.state.node = Some(address.parse()?);
+                self.state.save(&self.state_path)?;
+            }
+            ("contacts", []) => {
+                for (name, pkh) in &self.state.address_book {
+                    println!("{}\t{}", name, pkh);
+                }
+            }
+            ("contact", [name, pkh]) => {
+                let pkh = PublicKeyHash::from_str(pkh)?;
+                self.state
+                    .address_book
+                    .insert(name.to_string(), pkh.to_string());
+                self.state.save(&self.state_path)?;
+            }
+            ("remove-contact", [name]) => {
+                if self.state.address_book.remove(*name).is_none() {
+                    failure::bail!("No contact named {}", name);
+                }
+                self.state.save(&self.state_path)?;
+            }
+            (command, _) if BUILTIN_COMMANDS.contains(&command) => {
+                failure::bail!("Wrong arguments for {}, type `help` for its usage", command);
+            }
+            (command, _) if NODE_COMMANDS.contains(&command) => {
+                let args = expand_contacts(&words, &self.state.address_book)?;
+                match Command::from_iter_safe(iter::once("node".to_string()).chain(args)) {
+                    Ok(command) => {
+                        let mut config = self.config.clone();
+                        config.jsonrpc.server_address = self.node_address();
+                        exec(command, config, self.json)?;
+                    }
+                    // Also used to print the help of a command
+                    Err(e) => println!("{}", e.message),
+                }
+            }
+            (command, _) => {
+                failure::bail!(
+                    "Unknown command {}, type `help` to list the commands",
+                    command
+                )
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn node_address(&self) -> SocketAddr {
+        self.state
+            .node
+            .unwrap_or(self.config.jsonrpc.server_address)
+    }
+}
+
+fn print_help() {
+    println!("Node commands (type `<command> --help` for their arguments):");
+    for command in NODE_COMMANDS {
+        println!("    {}", command);
+    }
+    println!();
+    println!("REPL commands:");
+    println!("    node [<address>]          Show or set the default node address");
+    println!("    contacts                  List the address book");
+    println!("    contact <name> <pkh>      Save a public key hash as @<name>");
+    println!("    remove-contact <name>     Remove a contact from the address book");
+    println!("    exit, quit                Leave the REPL");
+}
+
+/// Replace the `@<name>` arguments with the public key hash saved for `<name>`.
+fn expand_contacts(
+    words: &[&str],
+    address_book: &BTreeMap<String, String>,
+) -> Result<Vec<String>, failure::Error> {
+    words
+        .iter()
+        .map(|word| {
+            if word.starts_with('@') {
+                let name = &word[1..];
+                address_book
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| failure::format_err!("No contact named {}", name))
+            } else {
+                Ok(word.to_string())
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_commands_exist() {
+        for command in NODE_COMMANDS {
+            let err = Command::from_iter_safe(vec!["node", *command, "--help"]).unwrap_err();
+            assert_eq!(err.kind, structopt::clap::ErrorKind::HelpDisplayed);
+        }
+    }
+
+    #[test]
+    fn expand_known_contacts() {
+        let pkh = "0123456789abcdef0123456789abcdef01234567";
+        let mut address_book = BTreeMap::new();
+        address_book.insert("alice".to_string(), pkh.to_string());
+
+        assert_eq!(
+            expand_contacts(&["getBalance", "--pkh", "@alice"], &address_book).unwrap(),
+            vec!["getBalance", "--pkh", pkh]
+        );
+        assert!(expand_contacts(&["getBalance", "--pkh", "@bob"], &address_book).is_err());
+    }
+}
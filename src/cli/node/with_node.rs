@@ -5,37 +5,104 @@ use structopt::StructOpt;
 
 use witnet_config::config::Config;
 use witnet_node as node;
+use witnet_util::crash;
 
 use super::json_rpc_client as rpc;
+use super::repl;
 use witnet_data_structures::chain::PublicKeyHash;
 
-pub fn exec_cmd(command: Command, mut config: Config) -> Result<(), failure::Error> {
+pub fn exec_cmd(command: Command, config: Config, json: bool) -> Result<(), failure::Error> {
+    let result = exec(command, config, json);
+    if json {
+        if let Err(err) = &result {
+            rpc::print_json_error(err);
+        }
+    }
+
+    result
+}
+
+pub(super) fn exec(command: Command, mut config: Config, json: bool) -> Result<(), failure::Error> {
     match command {
         Command::Block { node, hash } => {
-            rpc::get_block(node.unwrap_or(config.jsonrpc.server_address), hash)
+            rpc::get_block(node.unwrap_or(config.jsonrpc.server_address), hash, json)
         }
-        Command::BlockChain { node, epoch, limit } => {
-            rpc::get_blockchain(node.unwrap_or(config.jsonrpc.server_address), epoch, limit)
+        Command::BlockByEpoch { node, epoch } => {
+            rpc::get_block_by_epoch(node.unwrap_or(config.jsonrpc.server_address), epoch, json)
+        }
+        Command::BlockChain {
+            node,
+            epoch,
+            limit,
+            all,
+        } => {
+            let node = node.unwrap_or(config.jsonrpc.server_address);
+            if all {
+                rpc::get_blockchain_all(node, epoch, json)
+            } else {
+                rpc::get_blockchain(node, epoch, limit, json)
+            }
         }
         Command::GetBalance { node, pkh } => {
-            rpc::get_balance(node.unwrap_or(config.jsonrpc.server_address), pkh)
+            rpc::get_balance(node.unwrap_or(config.jsonrpc.server_address), pkh, json)
+        }
+        Command::GetPkh { node } => {
+            rpc::get_pkh(node.unwrap_or(config.jsonrpc.server_address), json)
         }
-        Command::GetPkh { node } => rpc::get_pkh(node.unwrap_or(config.jsonrpc.server_address)),
         Command::Output { node, pointer } => {
-            rpc::get_output(node.unwrap_or(config.jsonrpc.server_address), pointer)
+            rpc::get_output(node.unwrap_or(config.jsonrpc.server_address), pointer, json)
         }
         Command::Send {
             node,
             pkh,
             value,
             fee,
+            subtract_fee,
         } => rpc::send_vtt(
             node.unwrap_or(config.jsonrpc.server_address),
             pkh,
             value,
             fee,
+            subtract_fee,
+            json,
         ),
         Command::Raw { node } => rpc::raw(node.unwrap_or(config.jsonrpc.server_address)),
+        Command::Repl => repl::run(config, json),
+        Command::VerifyChain { epoch, db } => {
+            if let Some(db) = db {
+                config.storage.db_path = db;
+            }
+
+            let verification = node::chain_verifier::verify_chain(&config, epoch)?;
+            match verification.first_inconsistency {
+                None if json => {
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "epoch": epoch,
+                            "blocksChecked": verification.blocks_checked,
+                            "superblocksChecked": verification.superblocks_checked,
+                        })
+                    );
+
+                    Ok(())
+                }
+                None => {
+                    println!(
+                        "Checked {} blocks and {} superblocks from epoch {}, no inconsistencies found",
+                        verification.blocks_checked, verification.superblocks_checked, epoch
+                    );
+
+                    Ok(())
+                }
+                Some((inconsistent_epoch, inconsistency)) => Err(failure::format_err!(
+                    "Inconsistency found in block of epoch {} after checking {} blocks: {}",
+                    inconsistent_epoch,
+                    verification.blocks_checked,
+                    inconsistency
+                )),
+            }
+        }
         Command::ShowConfig => {
             // TODO: Implementation requires to make Config serializable
             Ok(())
@@ -59,6 +126,16 @@ pub fn exec_cmd(command: Command, mut config: Config) -> Result<(), failure::Err
 
             config.connections.known_peers.extend(params.known_peers);
 
+            if params.offline {
+                config.connections.offline = true;
+            }
+
+            crash::install_panic_hook(
+                "node",
+                env!("CARGO_PKG_VERSION"),
+                config.log.crash_report_dir.clone(),
+            );
+
             node::actors::node::run(config, || {
                 // FIXME(#72): decide what to do when interrupt signals are received
                 ctrlc::set_handler(move || {
@@ -83,6 +160,11 @@ pub enum Command {
         #[structopt(short = "n", long = "node")]
         node: Option<SocketAddr>,
     },
+    #[structopt(
+        name = "repl",
+        about = "Open an interactive prompt to run the node commands, with history and completion"
+    )]
+    Repl,
     #[structopt(name = "blockchain", about = "Find blockchain hashes ")]
     BlockChain {
         /// Socket address of the Witnet node to query.
@@ -94,6 +176,10 @@ pub enum Command {
         /// Max number of epochs for which to show block hashes.
         #[structopt(long = "limit", default_value = "100")]
         limit: u32,
+        /// Show all the block hashes from the first epoch on, ignoring the limit. They are
+        /// requested in chunks, so big ranges do not time out.
+        #[structopt(long = "all")]
+        all: bool,
     },
     #[structopt(name = "block", about = "Find a block by its hash ")]
     Block {
@@ -103,6 +189,17 @@ pub enum Command {
         #[structopt(name = "hash", help = "SHA-256 block hash in hex format")]
         hash: String,
     },
+    #[structopt(
+        name = "blockByEpoch",
+        about = "Find the block consolidated in an epoch "
+    )]
+    BlockByEpoch {
+        /// Socket address of the Witnet node to query.
+        #[structopt(short = "n", long = "node")]
+        node: Option<SocketAddr>,
+        #[structopt(name = "epoch", help = "Epoch of the block")]
+        epoch: u32,
+    },
     #[structopt(name = "getBalance", about = "Get total balance of the node")]
     GetBalance {
         /// Socket address of the Witnet node to query.
@@ -143,6 +240,20 @@ pub enum Command {
         /// Fee
         #[structopt(long = "fee")]
         fee: u64,
+        /// Subtract the fee from the value instead of paying it on top of it
+        #[structopt(long = "subtract-fee")]
+        subtract_fee: bool,
+    },
+    #[structopt(
+        name = "verifyChain",
+        about = "Re-validate the locally stored chain without connecting to the network. The node must be stopped."
+    )]
+    VerifyChain {
+        /// First epoch from which to verify blocks.
+        #[structopt(long = "epoch", default_value = "0")]
+        epoch: u32,
+        #[structopt(long = "db", raw(help = "NODE_DB_HELP"))]
+        db: Option<std::path::PathBuf>,
     },
     #[structopt(
         name = "show-config",
@@ -167,6 +278,9 @@ pub struct ConfigParams {
     bootstrap_peers_period_seconds: Option<u64>,
     #[structopt(long = "db", raw(help = "NODE_DB_HELP"))]
     db: Option<std::path::PathBuf>,
+    /// Run without connecting to other peers, only storage, JSON-RPC and RAD are available.
+    #[structopt(long = "offline")]
+    offline: bool,
 }
 
 static NODE_DB_HELP: &str = r#"Path to the node database. If not specified will use '.witnet-rust-mainnet' for mainnet, or '.witnet-rust-testnet-N' for testnet number N."#;
@@ -2,7 +2,7 @@ use structopt::StructOpt;
 
 use witnet_config::config::Config;
 
-pub fn exec_cmd(_command: Command, _config: Config) -> Result<(), failure::Error> {
+pub fn exec_cmd(_command: Command, _config: Config, _json: bool) -> Result<(), failure::Error> {
     println!("This executable has been compiled without the ability of running a Witnet node.");
     Ok(())
 }
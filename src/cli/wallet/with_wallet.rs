@@ -3,6 +3,7 @@ use std::net::SocketAddr;
 use structopt::StructOpt;
 
 use witnet_config::{config::Config, loaders};
+use witnet_util::crash;
 use witnet_wallet as wallet;
 
 pub fn exec_cmd(command: Command, mut config: Config) -> Result<(), failure::Error> {
@@ -22,10 +23,22 @@ pub fn exec_cmd(command: Command, mut config: Config) -> Result<(), failure::Err
             }
             config.wallet.testnet = config.wallet.testnet || params.testnet;
 
+            crash::install_panic_hook(
+                "wallet",
+                env!("CARGO_PKG_VERSION"),
+                config.log.crash_report_dir.clone(),
+            );
+
             wallet::run(config)?;
 
             Ok(())
         }
+        Command::AuditLog { path } => {
+            for entry in wallet::read_audit_log(&config, &path)? {
+                println!("{}", entry);
+            }
+            Ok(())
+        }
         Command::ShowConfig => {
             println!(
                 "[wallet]\n{}",
@@ -44,6 +57,15 @@ pub enum Command {
         alias = "run"
     )]
     Run(ConfigParams),
+    #[structopt(
+        name = "audit-log",
+        about = "Decrypt the entries of an audit log file and print them as JSON, one per line."
+    )]
+    AuditLog {
+        /// Path of the audit log file.
+        #[structopt(parse(from_os_str))]
+        path: std::path::PathBuf,
+    },
     #[structopt(
         name = "show-config",
         about = "Dump the loaded config in Toml format to stdout."
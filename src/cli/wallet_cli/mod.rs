@@ -0,0 +1,495 @@
+//! Commands using a running wallet server, so that wallets can be managed from scripts or on
+//! servers without a graphical front-end.
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use witnet_config::config::Config;
+
+use self::ws_client::WalletClient;
+
+mod ws_client;
+
+pub fn exec_cmd(command: Command, config: Config, json: bool) -> Result<(), failure::Error> {
+    let result = exec(command, config, json);
+    if json {
+        if let Err(err) = &result {
+            println!("{}", json_error(err));
+        }
+    }
+
+    result
+}
+
+fn exec(command: Command, config: Config, json: bool) -> Result<(), failure::Error> {
+    let Command { server, cmd } = command;
+    let url = format!(
+        "ws://{}",
+        server.wallet.unwrap_or(config.wallet.server_addr)
+    );
+    let mut client = WalletClient::connect(url, server.api_key)?;
+
+    match cmd {
+        WalletCommand::Create {
+            name,
+            password,
+            mnemonics,
+        } => {
+            let mnemonics = match mnemonics {
+                Some(mnemonics) => mnemonics,
+                None => {
+                    let result = client.call("createMnemonics", json!({ "length": 24 }))?;
+                    string(&result["mnemonics"])
+                }
+            };
+            let result = client.call(
+                "createWallet",
+                json!({
+                    "name": name,
+                    "password": password,
+                    "seedSource": "mnemonics",
+                    "seedData": mnemonics,
+                }),
+            )?;
+            if json {
+                println!(
+                    "{}",
+                    json!({ "walletId": result["walletId"], "mnemonics": mnemonics })
+                );
+            } else {
+                println!("Wallet id: {}", string(&result["walletId"]));
+                println!("Mnemonics: {}", mnemonics);
+            }
+        }
+        WalletCommand::Unlock {
+            wallet_id,
+            password,
+        } => {
+            let result = client.call(
+                "unlockWallet",
+                json!({ "walletId": wallet_id, "password": password }),
+            )?;
+            if json {
+                println!("{}", json!({ "sessionId": result["sessionId"] }));
+            } else {
+                println!("{}", string(&result["sessionId"]));
+            }
+        }
+        WalletCommand::Balance { session } => {
+            let utxos = with_session(&mut client, &session, |client, params| {
+                client.call("getUtxos", params)
+            })?;
+            let (mut total, mut reserved) = (0u64, 0u64);
+            for utxo in utxos.as_array().into_iter().flatten() {
+                let value = utxo["value"]["nanowits"].as_u64().unwrap_or_default();
+                total += value;
+                if utxo["reserved"].as_bool().unwrap_or_default() {
+                    reserved += value;
+                }
+            }
+            if json {
+                println!(
+                    "{}",
+                    json!({ "total": total, "available": total - reserved })
+                );
+            } else {
+                println!("Total: {} nanowits", total);
+                println!("Available: {} nanowits", total - reserved);
+            }
+        }
+        WalletCommand::Send {
+            session,
+            address,
+            amount,
+            fee,
+            label,
+        } => {
+            let transaction_id = with_session(&mut client, &session, |client, mut params| {
+                params["address"] = json!(address);
+                params["label"] = json!(label.unwrap_or_default());
+                params["amount"] = json!(amount);
+                params["fee"] = json!(fee);
+                let result = client.call("createVttRequest", params.clone())?;
+                params["transactionId"] = result["transactionId"].clone();
+                client.call("sendTransaction", params)?;
+
+                Ok(result["transactionId"].clone())
+            })?;
+            if json {
+                println!("{}", json!({ "transactionId": transaction_id }));
+            } else {
+                println!("Sent transaction {}", string(&transaction_id));
+            }
+        }
+        WalletCommand::History {
+            session,
+            offset,
+            limit,
+            address,
+        } => {
+            let result = with_session(&mut client, &session, |client, mut params| {
+                params["offset"] = json!(offset);
+                params["limit"] = json!(limit);
+                params["address"] = json!(address);
+                client.call("getTransactions", params)
+            })?;
+            if json {
+                println!("{}", result);
+            } else {
+                for transaction in result["transactions"].as_array().into_iter().flatten() {
+                    println!(
+                        "{} {:<13} {:>20} {}",
+                        string(&transaction["hash"]),
+                        string(&transaction["kind"]),
+                        transaction["value"]["nanowits"]
+                            .as_u64()
+                            .unwrap_or_default(),
+                        transaction["counterparty"].as_str().unwrap_or("-"),
+                    );
+                }
+                println!("Total: {}", result["total"]);
+            }
+        }
+        WalletCommand::ReceiveAddress {
+            session,
+            label,
+            reuse,
+        } => {
+            let result = with_session(&mut client, &session, |client, mut params| {
+                params["label"] = json!(label);
+                params["reuse"] = json!(reuse);
+                client.call("generateAddress", params)
+            })?;
+            if json {
+                println!("{}", result);
+            } else {
+                println!("{}", string(&result["address"]));
+                if let Some(warning) = result["warning"].as_str() {
+                    eprintln!("Warning: {}", warning);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Calls to the methods of the wallet server
+trait WalletRpc {
+    /// Call a method of the wallet and wait for its result
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, failure::Error>;
+}
+
+impl WalletRpc for WalletClient {
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, failure::Error> {
+        WalletClient::call(self, method, params)
+    }
+}
+
+/// Run `f` with the parameters identifying the session of the wallet. Without a session id, the
+/// wallet is unlocked with its password and the new session is closed afterwards.
+fn with_session<C, F>(client: &mut C, session: &SessionOpts, f: F) -> Result<Value, failure::Error>
+where
+    C: WalletRpc,
+    F: FnOnce(&mut C, Value) -> Result<Value, failure::Error>,
+{
+    let (session_id, temporary) = match (&session.session_id, &session.password) {
+        (Some(session_id), _) => (json!(session_id), false),
+        (None, Some(password)) => {
+            let result = client.call(
+                "unlockWallet",
+                json!({ "walletId": session.wallet_id, "password": password }),
+            )?;
+            (result["sessionId"].clone(), true)
+        }
+        (None, None) => {
+            return Err(failure::format_err!(
+                "Either a session id or the password of the wallet is required"
+            ))
+        }
+    };
+    let params = json!({ "sessionId": session_id, "walletId": session.wallet_id });
+    let result = f(client, params);
+    if temporary {
+        if let Err(e) = client.call("closeSession", json!({ "sessionId": session_id })) {
+            log::warn!("Failed to close the wallet session: {}", e);
+        }
+    }
+
+    result
+}
+
+fn string(value: &Value) -> String {
+    value
+        .as_str()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| value.to_string())
+}
+
+fn json_error(err: &failure::Error) -> Value {
+    let (code, message) = match err.downcast_ref::<ws_client::WalletError>() {
+        Some(e) => (Some(e.code), e.message.clone()),
+        None => (None, err.to_string()),
+    };
+
+    json!({ "error": { "code": code, "message": message } })
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Command {
+    #[structopt(flatten)]
+    server: ServerOpts,
+    #[structopt(subcommand)]
+    cmd: WalletCommand,
+}
+
+#[derive(Debug, StructOpt)]
+struct ServerOpts {
+    /// Address of the wallet server. Defaults to the one in the configuration.
+    #[structopt(short = "w", long = "wallet", raw(global = "true"))]
+    wallet: Option<std::net::SocketAddr>,
+    /// API key of the tenant of the wallet server.
+    #[structopt(long = "api-key", env = "WITNET_WALLET_API_KEY", raw(global = "true"))]
+    api_key: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+struct SessionOpts {
+    /// Id of the wallet.
+    #[structopt(long = "wallet-id")]
+    wallet_id: String,
+    /// Session id returned by the `unlock` command. A new session is opened if not given.
+    #[structopt(long = "session-id", env = "WITNET_WALLET_SESSION")]
+    session_id: Option<String>,
+    /// Password of the wallet, used to open a session for this command.
+    #[structopt(long = "password", env = "WITNET_WALLET_PASSWORD")]
+    password: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+enum WalletCommand {
+    #[structopt(
+        name = "create",
+        about = "Create a wallet from new or existing mnemonics"
+    )]
+    Create {
+        /// Name of the wallet.
+        #[structopt(long = "name")]
+        name: Option<String>,
+        /// Password to encrypt the wallet with, at least 8 characters long.
+        #[structopt(long = "password", env = "WITNET_WALLET_PASSWORD")]
+        password: String,
+        /// Mnemonics to import. New ones are generated and printed if not given.
+        #[structopt(long = "mnemonics")]
+        mnemonics: Option<String>,
+    },
+    #[structopt(
+        name = "unlock",
+        about = "Unlock a wallet and print the id of the new session"
+    )]
+    Unlock {
+        /// Id of the wallet.
+        #[structopt(long = "wallet-id")]
+        wallet_id: String,
+        /// Password of the wallet.
+        #[structopt(long = "password", env = "WITNET_WALLET_PASSWORD")]
+        password: String,
+    },
+    #[structopt(name = "balance", about = "Get the balance of a wallet")]
+    Balance {
+        #[structopt(flatten)]
+        session: SessionOpts,
+    },
+    #[structopt(name = "send", about = "Send a value transfer transaction")]
+    Send {
+        #[structopt(flatten)]
+        session: SessionOpts,
+        /// Address to send the funds to.
+        #[structopt(long = "address")]
+        address: String,
+        /// Amount to send, in nanowits.
+        #[structopt(long = "amount")]
+        amount: u64,
+        /// Fee of the transaction, in nanowits.
+        #[structopt(long = "fee")]
+        fee: u64,
+        /// Label of the transaction.
+        #[structopt(long = "label")]
+        label: Option<String>,
+    },
+    #[structopt(name = "history", about = "List the transactions of a wallet")]
+    History {
+        #[structopt(flatten)]
+        session: SessionOpts,
+        #[structopt(long = "offset")]
+        offset: Option<u32>,
+        #[structopt(long = "limit")]
+        limit: Option<u32>,
+        /// Only list the transactions of this address of the wallet.
+        #[structopt(long = "address")]
+        address: Option<String>,
+    },
+    #[structopt(
+        name = "receive-address",
+        about = "Generate an address to receive funds"
+    )]
+    ReceiveAddress {
+        #[structopt(flatten)]
+        session: SessionOpts,
+        /// Label of the address.
+        #[structopt(long = "label")]
+        label: Option<String>,
+        /// Return the last generated address again if it has not received funds yet.
+        #[structopt(long = "reuse")]
+        reuse: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wallet server recording the calls it receives
+    #[derive(Default)]
+    struct FakeWallet {
+        calls: Vec<(String, Value)>,
+    }
+
+    impl WalletRpc for FakeWallet {
+        fn call(&mut self, method: &str, params: Value) -> Result<Value, failure::Error> {
+            self.calls.push((method.to_string(), params));
+            match method {
+                "unlockWallet" => Ok(json!({ "sessionId": "temporary" })),
+                _ => Ok(json!(null)),
+            }
+        }
+    }
+
+    fn session(session_id: Option<&str>, password: Option<&str>) -> SessionOpts {
+        SessionOpts {
+            wallet_id: "wallet".to_string(),
+            session_id: session_id.map(ToString::to_string),
+            password: password.map(ToString::to_string),
+        }
+    }
+
+    fn methods(wallet: &FakeWallet) -> Vec<&str> {
+        wallet
+            .calls
+            .iter()
+            .map(|(method, _)| method.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn with_session_uses_the_given_session() {
+        let mut wallet = FakeWallet::default();
+        with_session(
+            &mut wallet,
+            &session(Some("open"), None),
+            |client, params| client.call("getUtxos", params),
+        )
+        .unwrap();
+
+        assert_eq!(methods(&wallet), vec!["getUtxos"]);
+        assert_eq!(
+            wallet.calls[0].1,
+            json!({ "sessionId": "open", "walletId": "wallet" })
+        );
+    }
+
+    #[test]
+    fn with_session_closes_the_temporary_session() {
+        let mut wallet = FakeWallet::default();
+        with_session(
+            &mut wallet,
+            &session(None, Some("password")),
+            |client, params| client.call("getUtxos", params),
+        )
+        .unwrap();
+
+        assert_eq!(
+            methods(&wallet),
+            vec!["unlockWallet", "getUtxos", "closeSession"]
+        );
+        assert_eq!(wallet.calls[1].1["sessionId"], json!("temporary"));
+        assert_eq!(wallet.calls[2].1, json!({ "sessionId": "temporary" }));
+
+        // Also when the command fails
+        let mut wallet = FakeWallet::default();
+        let result = with_session(&mut wallet, &session(None, Some("password")), |_, _| {
+            Err(failure::format_err!("failed"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(methods(&wallet), vec!["unlockWallet", "closeSession"]);
+    }
+
+    #[test]
+    fn with_session_requires_a_session_or_a_password() {
+        let mut wallet = FakeWallet::default();
+        let result = with_session(&mut wallet, &session(None, None), |client, params| {
+            client.call("getUtxos", params)
+        });
+
+        assert!(result.is_err());
+        assert!(wallet.calls.is_empty());
+    }
+
+    #[test]
+    fn parse_send() {
+        let command = Command::from_iter_safe(vec![
+            "wallet-cli",
+            "send",
+            "--wallet-id",
+            "wallet",
+            "--session-id",
+            "open",
+            "--address",
+            "twit1",
+            "--amount",
+            "10",
+            "--fee",
+            "1",
+            "--wallet",
+            "127.0.0.1:11212",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            command.server.wallet,
+            Some("127.0.0.1:11212".parse().unwrap())
+        );
+        match command.cmd {
+            WalletCommand::Send {
+                session,
+                amount,
+                fee,
+                label,
+                ..
+            } => {
+                assert_eq!(session.wallet_id, "wallet");
+                assert_eq!(session.session_id, Some("open".to_string()));
+                assert_eq!((amount, fee, label), (10, 1, None));
+            }
+            cmd => panic!("Unexpected command {:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn parse_missing_arguments() {
+        // The amount is required
+        assert!(Command::from_iter_safe(vec![
+            "wallet-cli",
+            "send",
+            "--wallet-id",
+            "wallet",
+            "--address",
+            "twit1",
+            "--fee",
+            "1",
+        ])
+        .is_err());
+        // The wallet id is required
+        assert!(Command::from_iter_safe(vec!["wallet-cli", "balance"]).is_err());
+    }
+}
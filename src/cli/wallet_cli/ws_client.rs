@@ -0,0 +1,125 @@
+//! Blocking JSON-RPC client for the websockets server of the wallet.
+use std::{fmt, sync::mpsc, thread, time::Duration};
+
+use failure::{format_err, Fail};
+use serde_json::{json, Value};
+
+/// How long to wait for the response to a request before giving up
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Connection to the wallet server. Requests are sent one at a time, waiting for their response.
+pub struct WalletClient {
+    sender: ws::Sender,
+    messages: mpsc::Receiver<String>,
+    handle: Option<thread::JoinHandle<ws::Result<()>>>,
+    api_key: Option<String>,
+    next_id: u64,
+}
+
+impl WalletClient {
+    /// Connect to the wallet server listening in `url`. The `api_key`, if any, is sent along with
+    /// every request to select the tenant of the server.
+    pub fn connect(url: String, api_key: Option<String>) -> Result<Self, failure::Error> {
+        log::info!("Connecting to wallet server at {}", url);
+        let (sender_tx, sender_rx) = mpsc::channel();
+        let (messages_tx, messages_rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            ws::connect(url, move |sender| {
+                let _ = sender_tx.send(sender);
+                let messages = messages_tx.clone();
+
+                move |message: ws::Message| -> ws::Result<()> {
+                    if let Ok(text) = message.into_text() {
+                        let _ = messages.send(text);
+                    }
+                    Ok(())
+                }
+            })
+        });
+
+        match sender_rx.recv() {
+            Ok(sender) => Ok(Self {
+                sender,
+                messages: messages_rx,
+                handle: Some(handle),
+                api_key,
+                next_id: 0,
+            }),
+            // The connection thread finished without connecting
+            Err(_) => match handle.join() {
+                Ok(Err(e)) => Err(e.into()),
+                _ => Err(format_err!("Could not connect to the wallet server")),
+            },
+        }
+    }
+
+    /// Call a method of the wallet and wait for its result. The notifications received while
+    /// waiting are discarded.
+    pub fn call(&mut self, method: &str, mut params: Value) -> Result<Value, failure::Error> {
+        if let (Some(api_key), Some(params)) = (&self.api_key, params.as_object_mut()) {
+            params.insert("apiKey".to_string(), json!(api_key));
+        }
+        self.next_id += 1;
+        let id = self.next_id;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": id,
+        });
+        log::debug!("Sending request: {}", request);
+        self.sender.send(request.to_string())?;
+
+        loop {
+            let message = self.messages.recv_timeout(RESPONSE_TIMEOUT).map_err(|_| {
+                format_err!(
+                    "The wallet server did not respond to the {} request",
+                    method
+                )
+            })?;
+            let mut response: Value = serde_json::from_str(&message)?;
+            if response["id"] != json!(id) {
+                log::debug!("Ignoring message: {}", message);
+                continue;
+            }
+            if let Some(error) = response.get("error") {
+                return Err(WalletError {
+                    code: error["code"].as_i64().unwrap_or_default(),
+                    message: error["message"].as_str().unwrap_or_default().to_string(),
+                    data: error.get("data").cloned(),
+                }
+                .into());
+            }
+
+            return Ok(response["result"].take());
+        }
+    }
+}
+
+impl Drop for WalletClient {
+    fn drop(&mut self) {
+        let _ = self.sender.close(ws::CloseCode::Normal);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Error returned by the wallet server
+#[derive(Debug, Fail)]
+pub struct WalletError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+// Required for Fail derive
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Wallet error {}: {}", self.code, self.message)?;
+        if let Some(data) = &self.data {
+            write!(f, " ({})", data)?;
+        }
+        Ok(())
+    }
+}
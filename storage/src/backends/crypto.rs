@@ -68,6 +68,10 @@ impl<T: Storage> Storage for Backend<T> {
     fn delete(&mut self, key: &[u8]) -> Result<()> {
         self.backend.delete(key)
     }
+
+    fn flush(&self) -> Result<()> {
+        self.backend.flush()
+    }
 }
 
 fn get_secret(password: &[u8], salt: &[u8]) -> Protected {
@@ -33,6 +33,11 @@ impl Storage for Backend {
         Backend::delete(self, &key).map_err(Error)?;
         Ok(())
     }
+
+    fn flush(&self) -> Result<()> {
+        Backend::flush(self).map_err(Error)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -98,5 +103,9 @@ mod rocksdb_mock {
             self.search(key).map(|idx| self.data.remove(idx));
             Ok(())
         }
+
+        pub fn flush(&self) -> Result<()> {
+            Ok(())
+        }
     }
 }
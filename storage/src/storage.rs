@@ -22,4 +22,9 @@ pub trait Storage {
 
     /// Delete a value from the storage
     fn delete(&mut self, key: &[u8]) -> Result<()>;
+
+    /// Flush the pending writes to the underlying medium, if the backend buffers them
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
 }
@@ -0,0 +1,174 @@
+use std::{
+    cell::Cell,
+    fmt,
+    num::ParseIntError,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use futures::{Future, Poll};
+
+use crate::timestamp::get_timestamp_nanos;
+
+thread_local! {
+    /// Correlation id of the request being handled in the current thread
+    static CURRENT: Cell<Option<CorrelationId>> = Cell::new(None);
+}
+
+/// Identifier used to relate all the log lines produced while handling the same request, even
+/// when the request goes through several actors
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CorrelationId(u64);
+
+impl CorrelationId {
+    /// Create a new correlation id, unique within the current process
+    pub fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        // Ids start at the time of the first call, so they are not repeated across runs
+        if NEXT.load(Ordering::Relaxed) == 0 {
+            let (seconds, nanoseconds) = get_timestamp_nanos();
+            let start = (seconds as u64) << 32 | u64::from(nanoseconds);
+            let _ = NEXT.compare_exchange(0, start, Ordering::Relaxed, Ordering::Relaxed);
+        }
+
+        CorrelationId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Parse a correlation id from its hexadecimal representation, so the ids received from other
+/// processes can be adopted
+impl FromStr for CorrelationId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(CorrelationId)
+    }
+}
+
+/// Get the correlation id of the request being handled in the current thread, if any
+pub fn current() -> Option<CorrelationId> {
+    CURRENT.with(Cell::get)
+}
+
+/// Set `id` as the correlation id of the current thread until the returned guard is dropped
+pub fn enter(id: CorrelationId) -> Entered {
+    let previous = CURRENT.with(|current| current.replace(Some(id)));
+
+    Entered { previous }
+}
+
+/// Run `f` with `id` as the correlation id of the current thread
+pub fn scope<F, R>(id: CorrelationId, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _entered = enter(id);
+
+    f()
+}
+
+/// Guard returned by `enter`, restores the previous correlation id when dropped
+#[must_use]
+pub struct Entered {
+    previous: Option<CorrelationId>,
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.previous));
+    }
+}
+
+/// Future polled with a correlation id as the one of the current thread, so the log lines of its
+/// continuations keep the id even when they run after the request handler returned
+#[must_use = "futures do nothing unless polled"]
+pub struct Correlated<F> {
+    correlation_id: Option<CorrelationId>,
+    inner: F,
+}
+
+impl<F> Correlated<F> {
+    /// Wrap a future using the correlation id of the request being handled in this thread
+    pub fn new(inner: F) -> Self {
+        Self::with_id(current(), inner)
+    }
+
+    /// Wrap a future using the given correlation id
+    pub fn with_id(correlation_id: Option<CorrelationId>, inner: F) -> Self {
+        Self {
+            correlation_id,
+            inner,
+        }
+    }
+}
+
+impl<F: Future> Future for Correlated<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let _entered = self.correlation_id.map(enter);
+
+        self.inner.poll()
+    }
+}
+
+#[test]
+fn correlation_scope_test() {
+    let id = CorrelationId::new();
+
+    assert_eq!(current(), None);
+    assert_eq!(scope(id, current), Some(id));
+    assert_eq!(current(), None);
+
+    {
+        let _entered = enter(id);
+        assert_eq!(current(), Some(id));
+    }
+    assert_eq!(current(), None);
+}
+
+#[test]
+fn correlation_ids_are_unique_test() {
+    let a = CorrelationId::new();
+    let b = CorrelationId::new();
+
+    assert_ne!(a, b);
+    assert_eq!(a.to_string().len(), 16);
+}
+
+#[test]
+fn correlation_id_from_str_test() {
+    let id = CorrelationId::new();
+
+    assert_eq!(id.to_string().parse(), Ok(id));
+    assert!("not an id".parse::<CorrelationId>().is_err());
+}
+
+#[test]
+fn correlated_future_test() {
+    let id = CorrelationId::new();
+    let mut fut = Correlated::with_id(Some(id), futures::future::lazy(|| Ok::<_, ()>(current())));
+
+    assert_eq!(fut.poll(), Ok(futures::Async::Ready(Some(id))));
+    assert_eq!(current(), None);
+
+    // The id is taken from the current thread when the future is created, not when it is polled
+    let mut fut = scope(id, || {
+        Correlated::new(futures::future::lazy(|| Ok::<_, ()>(current())))
+    });
+    assert_eq!(fut.poll(), Ok(futures::Async::Ready(Some(id))));
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    io::{self, Write},
+    net::{IpAddr, SocketAddr},
+    panic,
+    path::{Path, PathBuf},
+    process,
+    sync::{Mutex, MutexGuard, TryLockError},
+    thread,
+};
+
+use lazy_static::lazy_static;
+
+use crate::{correlation, timestamp};
+
+/// Maximum number of recent log lines included in a crash report
+pub const RECENT_LOGS_CAPACITY: usize = 200;
+
+/// Function called before aborting the process, so the storage can be flushed
+pub type FlushHandler = Box<dyn Fn() + Send>;
+
+lazy_static! {
+    static ref RECENT_LOGS: Mutex<VecDeque<String>> =
+        Mutex::new(VecDeque::with_capacity(RECENT_LOGS_CAPACITY));
+    static ref CONTEXT: Mutex<BTreeMap<&'static str, String>> = Mutex::new(BTreeMap::new());
+    static ref FLUSH_HANDLER: Mutex<Option<FlushHandler>> = Mutex::new(None);
+}
+
+/// Add a line to the ring buffer of recent logs, dropping the oldest one if it is full
+pub fn record_log(line: String) {
+    if let Ok(mut logs) = RECENT_LOGS.lock() {
+        if logs.len() == RECENT_LOGS_CAPACITY {
+            logs.pop_front();
+        }
+        logs.push_back(line);
+    }
+}
+
+/// Set a value of the context included in the crash reports, like the current chain beacon
+pub fn set_context(key: &'static str, value: String) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.insert(key, value);
+    }
+}
+
+/// Set the function used to flush the storage before aborting the process after a panic
+pub fn set_flush_handler(handler: FlushHandler) {
+    if let Ok(mut flush_handler) = FLUSH_HANDLER.lock() {
+        *flush_handler = Some(handler);
+    }
+}
+
+/// Install a panic hook that writes a crash report into `dir`, flushes the storage and aborts the
+/// process.
+///
+/// The panic is still printed by the previous hook, so it shows up in the terminal as usual.
+pub fn install_panic_hook(app: &'static str, version: &'static str, dir: PathBuf) {
+    let previous_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        previous_hook(info);
+
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => (*message).to_string(),
+            None => match info.payload().downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => "Box<Any>".to_string(),
+            },
+        };
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()))
+            .unwrap_or_else(|| "unknown".to_string());
+        let report = crash_report(app, version, &message, &location);
+
+        match write_report(&dir, app, &report) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+
+        if let Some(flush_handler) = try_lock(&FLUSH_HANDLER) {
+            if let Some(flush) = flush_handler.as_ref() {
+                eprintln!("Flushing storage before aborting");
+                flush();
+            }
+        }
+
+        process::abort();
+    }));
+}
+
+/// Contents of a crash report, with the addresses and secrets scrubbed from the panic message and
+/// the recent logs.
+fn crash_report(app: &str, version: &str, message: &str, location: &str) -> String {
+    let (seconds, nanoseconds) = timestamp::get_timestamp_nanos();
+    let mut report = String::new();
+
+    report.push_str(&format!("{} {} crash report\n", app, version));
+    report.push_str(&format!(
+        "time: {}\n",
+        timestamp::pretty_print(seconds, nanoseconds)
+    ));
+    report.push_str(&format!(
+        "thread: {}\n",
+        thread::current().name().unwrap_or("<unnamed>")
+    ));
+    if let Some(correlation_id) = correlation::current() {
+        report.push_str(&format!("correlation id: {}\n", correlation_id));
+    }
+    report.push_str(&format!("location: {}\n", location));
+    report.push_str(&format!("panic: {}\n", scrub(message)));
+
+    report.push_str("\ncontext:\n");
+    if let Some(context) = try_lock(&CONTEXT) {
+        for (key, value) in context.iter() {
+            report.push_str(&format!("  {}: {}\n", key, scrub(value)));
+        }
+    }
+
+    let backtrace = failure::Backtrace::new().to_string();
+    if !backtrace.is_empty() {
+        report.push_str("\nbacktrace:\n");
+        report.push_str(&backtrace);
+        report.push('\n');
+    }
+
+    report.push_str("\nrecent logs:\n");
+    if let Some(logs) = try_lock(&RECENT_LOGS) {
+        for line in logs.iter() {
+            report.push_str(&scrub(line));
+            report.push('\n');
+        }
+    }
+
+    report
+}
+
+fn write_report(dir: &Path, app: &str, report: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("crash-{}-{}.txt", app, timestamp::get_timestamp()));
+    let mut file = fs::File::create(&path)?;
+    file.write_all(report.as_bytes())?;
+
+    Ok(path)
+}
+
+/// Lock a mutex without blocking, because the panic may have happened while it was locked. The
+/// contents of a poisoned mutex are still used.
+fn try_lock<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Replace the IP addresses, Witnet addresses and extended private keys found in `text`, so crash
+/// reports can be shared without revealing who is running the node or which funds it holds.
+pub fn scrub(text: &str) -> String {
+    let is_separator =
+        |c: char| c.is_whitespace() || ['"', '\'', ',', ';', '(', ')', '{', '}', '='].contains(&c);
+    let mut scrubbed = String::with_capacity(text.len());
+    let mut token = String::new();
+
+    for c in text.chars() {
+        if is_separator(c) {
+            scrubbed.push_str(&scrub_token(&token));
+            scrubbed.push(c);
+            token.clear();
+        } else {
+            token.push(c);
+        }
+    }
+    scrubbed.push_str(&scrub_token(&token));
+
+    scrubbed
+}
+
+fn scrub_token(token: &str) -> String {
+    let trimmed = token.trim_end_matches(|c| c == '.' || c == ':');
+    let suffix = &token[trimmed.len()..];
+    let is_bech32 = |prefix: &str| {
+        trimmed.starts_with(prefix)
+            && trimmed.len() > prefix.len() + 20
+            && trimmed.chars().all(|c| c.is_ascii_alphanumeric())
+    };
+
+    let replacement = if trimmed.parse::<SocketAddr>().is_ok() || trimmed.parse::<IpAddr>().is_ok()
+    {
+        "<ip>"
+    } else if is_bech32("wit1") || is_bech32("twit1") {
+        "<address>"
+    } else if trimmed.starts_with("xprv") && trimmed.len() > 20 {
+        "<secret>"
+    } else {
+        return token.to_string();
+    };
+
+    format!("{}{}", replacement, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrub_addresses_and_secrets() {
+        assert_eq!(
+            scrub("Connected to 127.0.0.1:21337, peer [::1]:21337."),
+            "Connected to <ip>, peer <ip>."
+        );
+        assert_eq!(
+            scrub("Sent 10 wits to \"twit1adgt8t2h3xnu358f76zxlph0urf2ev7cd78ggc\""),
+            "Sent 10 wits to \"<address>\""
+        );
+        assert_eq!(
+            scrub("key=xprv1qpujxsyd4hfu0dtwa524vac84e09mjsgnh5h9crl8wrqg58z5wmsuqqcxlqmar"),
+            "key=<secret>"
+        );
+        assert_eq!(scrub("Epoch 1234: block 5e8f"), "Epoch 1234: block 5e8f");
+    }
+
+    #[test]
+    fn recent_logs_are_bounded() {
+        for i in 0..RECENT_LOGS_CAPACITY + 10 {
+            record_log(format!("line {}", i));
+        }
+
+        let logs = RECENT_LOGS.lock().unwrap();
+        assert_eq!(logs.len(), RECENT_LOGS_CAPACITY);
+        assert_eq!(logs.front().unwrap(), "line 10");
+    }
+}
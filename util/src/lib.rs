@@ -8,6 +8,12 @@
 #![deny(unused_mut)]
 #![deny(missing_docs)]
 
+/// Correlation ids to relate the logs of a request across actors
+pub mod correlation;
+
+/// Crash reports written when the process panics
+pub mod crash;
+
 /// Parse utilities
 pub mod parser;
 
@@ -14,18 +14,25 @@ use witnet_data_structures::{
     chain::{
         Block, BlockMerkleRoots, CheckpointBeacon, DataRequestOutput, DataRequestStage,
         DataRequestState, Epoch, Hash, Hashable, Input, KeyedSignature, OutputPointer,
-        PublicKeyHash, RADConsensus, RADRequest, Reputation, ReputationEngine, UnspentOutputsPool,
-        ValueTransferOutput,
+        PublicKeyHash, RADConsensus, RADRequest, RADRetrieve, Reputation, ReputationEngine,
+        UnspentOutputsPool, ValueTransferOutput, MAX_RETRIEVE_FALLBACK_URLS,
     },
     data_request::{calculate_dr_vt_reward, true_revealer, DataRequestPool},
     error::{BlockError, DataRequestError, TransactionError},
     transaction::{
+        transaction_feature_activation_epoch, transaction_version_activation_epoch,
         CommitTransaction, DRTransaction, MintTransaction, RevealTransaction, TallyTransaction,
-        VTTransaction,
+        Transaction, VTTransaction, VersionedTransaction,
     },
+    upgrades::{ProtocolUpgrades, RAD_RETRIEVE_FALLBACK},
     vrf::{BlockEligibilityClaim, DataRequestEligibilityClaim, VrfCtx},
 };
-use witnet_rad::{run_consensus, script::unpack_radon_script, types::RadonTypes};
+use witnet_rad::{
+    error::RadError,
+    run_consensus,
+    script::{required_upgrades, unpack_radon_script},
+    types::RadonTypes,
+};
 
 /// Calculate the sum of the values of the outputs pointed by the
 /// inputs of a transaction. If an input pointed-output is not
@@ -122,11 +129,66 @@ pub fn validate_mint_transaction(
     Ok(())
 }
 
+/// Function to validate the version and feature bits of a versioned transaction
+///
+/// Unknown versions and features are rejected, as well as known ones used before their
+/// activation epoch.
+pub fn validate_transaction_version(
+    tx: &VersionedTransaction,
+    epoch: Epoch,
+) -> Result<(), failure::Error> {
+    match transaction_version_activation_epoch(tx.version) {
+        None => Err(TransactionError::UnknownVersion {
+            version: tx.version,
+        })?,
+        Some(activation_epoch) if epoch < activation_epoch => {
+            Err(TransactionError::InactiveVersion {
+                version: tx.version,
+                activation_epoch,
+            })?
+        }
+        Some(_) => {}
+    }
+
+    for bit in tx.feature_bits() {
+        match transaction_feature_activation_epoch(bit) {
+            Some(activation_epoch) if epoch >= activation_epoch => {}
+            _ => Err(TransactionError::InactiveFeature { bit })?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Function to validate a versioned transaction received to be included in a block of the given
+/// epoch
+///
+/// The version and features of the transaction are checked before the transaction itself, so
+/// transactions relying on formats this node does not support are rejected up front. Only value
+/// transfer and data request transactions can be sent on their own.
+pub fn validate_versioned_transaction<'a>(
+    tx: &'a VersionedTransaction,
+    utxo_diff: &UtxoDiff,
+    protocol_upgrades: &ProtocolUpgrades,
+    epoch: Epoch,
+) -> Result<(Vec<&'a Input>, Vec<&'a ValueTransferOutput>, u64), failure::Error> {
+    validate_transaction_version(tx, epoch)?;
+
+    match &tx.transaction {
+        Transaction::ValueTransfer(vt_tx) => validate_vt_transaction(vt_tx, utxo_diff),
+        Transaction::DataRequest(dr_tx) => {
+            validate_dr_transaction(dr_tx, utxo_diff, protocol_upgrades, epoch)
+        }
+        _ => Err(TransactionError::NotValidTransaction)?,
+    }
+}
+
 /// Function to validate a rad request
 pub fn validate_rad_request(rad_request: &RADRequest) -> Result<(), failure::Error> {
     let retrieval_paths = &rad_request.retrieve;
     for path in retrieval_paths {
         unpack_radon_script(path.script.as_slice())?;
+        validate_fallback_urls(path)?;
     }
 
     let aggregate = &rad_request.aggregate;
@@ -138,6 +200,67 @@ pub fn validate_rad_request(rad_request: &RADRequest) -> Result<(), failure::Err
     Ok(())
 }
 
+/// Function to validate the fallback sources of a retrieval: there can be up to
+/// `MAX_RETRIEVE_FALLBACK_URLS` of them, and they cannot be empty or repeat another source of the
+/// retrieval
+pub fn validate_fallback_urls(retrieve: &RADRetrieve) -> Result<(), DataRequestError> {
+    if retrieve.fallback_urls.len() > MAX_RETRIEVE_FALLBACK_URLS {
+        return Err(DataRequestError::TooManyFallbackUrls {
+            count: retrieve.fallback_urls.len(),
+            max: MAX_RETRIEVE_FALLBACK_URLS,
+        });
+    }
+
+    let mut urls = HashSet::new();
+    urls.insert(&retrieve.url);
+    for url in &retrieve.fallback_urls {
+        if url.is_empty() || !urls.insert(url) {
+            return Err(DataRequestError::InvalidFallbackUrl { url: url.clone() });
+        }
+    }
+
+    Ok(())
+}
+
+/// Function to validate that a rad request only uses the RAD features of the protocol upgrades
+/// active in the given epoch
+pub fn validate_rad_request_upgrades(
+    rad_request: &RADRequest,
+    protocol_upgrades: &ProtocolUpgrades,
+    epoch: Epoch,
+) -> Result<(), failure::Error> {
+    let scripts = rad_request
+        .retrieve
+        .iter()
+        .map(|path| path.script.as_slice())
+        .chain(vec![
+            rad_request.aggregate.script.as_slice(),
+            rad_request.consensus.script.as_slice(),
+        ]);
+    let uses_fallback_urls = rad_request
+        .retrieve
+        .iter()
+        .any(|path| !path.fallback_urls.is_empty());
+    if uses_fallback_urls && !protocol_upgrades.is_active_at(RAD_RETRIEVE_FALLBACK, epoch) {
+        Err(RadError::UpgradeNotActive {
+            upgrade: RAD_RETRIEVE_FALLBACK.to_string(),
+        })?
+    }
+
+    for script in scripts {
+        let script = unpack_radon_script(script)?;
+        for upgrade in required_upgrades(&script) {
+            if !protocol_upgrades.is_active_at(upgrade, epoch) {
+                Err(RadError::UpgradeNotActive {
+                    upgrade: upgrade.to_string(),
+                })?
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Function to validate a tally consensus
 pub fn validate_consensus(
     reveals: &[&[u8]],
@@ -201,30 +324,13 @@ pub fn validate_vt_transaction<'a>(
     ))
 }
 
-/// Function to validate a data request transaction
-pub fn validate_dr_transaction<'a>(
-    dr_tx: &'a DRTransaction,
-    utxo_diff: &UtxoDiff,
-) -> Result<(Vec<&'a Input>, Vec<&'a ValueTransferOutput>, u64), failure::Error> {
-    validate_transaction_signature(
-        &dr_tx.signatures,
-        &dr_tx.body.inputs,
-        dr_tx.hash(),
-        utxo_diff,
-    )?;
-
-    // A value transfer output cannot have zero value
-    for (idx, output) in dr_tx.body.outputs.iter().enumerate() {
-        if output.value == 0 {
-            Err(TransactionError::ZeroValueOutput {
-                tx_hash: dr_tx.hash(),
-                output_id: idx,
-            })?
-        }
-    }
-
-    let fee = dr_transaction_fee(dr_tx, utxo_diff)?;
-
+/// Function to validate the output of a data request transaction included in a block of the given
+/// epoch. Returns the reward to be shared between all the witnesses
+pub fn validate_data_request_output(
+    dr_output: &DataRequestOutput,
+    protocol_upgrades: &ProtocolUpgrades,
+    epoch: Epoch,
+) -> Result<u64, failure::Error> {
     let DataRequestOutput {
         witnesses,
         value: dr_value,
@@ -233,13 +339,16 @@ pub fn validate_dr_transaction<'a>(
         tally_fee,
         ref data_request,
         ..
-    } = dr_tx.body.dr_output;
+    } = *dr_output;
 
     if witnesses < 1 {
         Err(TransactionError::InsufficientWitnesses)?
     }
 
-    let sum_fees = commit_fee + reveal_fee + tally_fee;
+    let sum_fees = commit_fee
+        .checked_add(reveal_fee)
+        .and_then(|fees| fees.checked_add(tally_fee))
+        .ok_or(TransactionError::DataRequestFeesOverflow)?;
 
     // Calculate reward to be shared between all the witnesses, which must be greater than 0
     if dr_value <= sum_fees {
@@ -257,6 +366,38 @@ pub fn validate_dr_transaction<'a>(
     }
 
     validate_rad_request(&data_request)?;
+    validate_rad_request_upgrades(&data_request, protocol_upgrades, epoch)?;
+
+    Ok(total_witness_reward)
+}
+
+/// Function to validate a data request transaction included in a block of the given epoch
+pub fn validate_dr_transaction<'a>(
+    dr_tx: &'a DRTransaction,
+    utxo_diff: &UtxoDiff,
+    protocol_upgrades: &ProtocolUpgrades,
+    epoch: Epoch,
+) -> Result<(Vec<&'a Input>, Vec<&'a ValueTransferOutput>, u64), failure::Error> {
+    validate_transaction_signature(
+        &dr_tx.signatures,
+        &dr_tx.body.inputs,
+        dr_tx.hash(),
+        utxo_diff,
+    )?;
+
+    // A value transfer output cannot have zero value
+    for (idx, output) in dr_tx.body.outputs.iter().enumerate() {
+        if output.value == 0 {
+            Err(TransactionError::ZeroValueOutput {
+                tx_hash: dr_tx.hash(),
+                output_id: idx,
+            })?
+        }
+    }
+
+    let fee = dr_transaction_fee(dr_tx, utxo_diff)?;
+
+    validate_data_request_output(&dr_tx.body.dr_output, protocol_upgrades, epoch)?;
 
     Ok((
         dr_tx.body.inputs.iter().collect(),
@@ -646,6 +787,7 @@ pub fn validate_block_transactions(
     vrf: &mut VrfCtx,
     rep_eng: &ReputationEngine,
     epoch_constants: EpochConstants,
+    protocol_upgrades: &ProtocolUpgrades,
 ) -> Result<Diff, failure::Error> {
     let epoch = block.block_header.beacon.checkpoint;
     let mut utxo_diff = UtxoDiff::new(utxo_set);
@@ -672,7 +814,8 @@ pub fn validate_block_transactions(
     // Validate data request transactions in a block
     let mut dr_mt = ProgressiveMerkleTree::sha256();
     for transaction in &block.txns.data_request_txns {
-        let (inputs, outputs, fee) = validate_dr_transaction(transaction, &utxo_diff)?;
+        let (inputs, outputs, fee) =
+            validate_dr_transaction(transaction, &utxo_diff, protocol_upgrades, epoch)?;
         total_fee += fee;
 
         update_utxo_diff(&mut utxo_diff, inputs, outputs, transaction.hash());
@@ -809,6 +952,7 @@ pub fn validate_block(
     vrf: &mut VrfCtx,
     rep_eng: &ReputationEngine,
     epoch_constants: EpochConstants,
+    protocol_upgrades: &ProtocolUpgrades,
 ) -> Result<Diff, failure::Error> {
     let block_epoch = block.block_header.beacon.checkpoint;
     let hash_prev_block = block.block_header.beacon.hash_prev_block;
@@ -847,6 +991,7 @@ pub fn validate_block(
             vrf,
             rep_eng,
             epoch_constants,
+            protocol_upgrades,
         )
     }
 }
@@ -875,6 +1020,61 @@ pub fn validate_candidate(
     )
 }
 
+/// Function to run the cheap checks of a block candidate, which need neither the chain state nor
+/// any cryptographic verification, so obviously invalid candidates can be dropped as soon as they
+/// are received: the candidate must be from the current epoch, and its eligibility proof must not
+/// be empty and must belong to the same identity that signed the block
+pub fn pre_validate_candidate(block: &Block, current_epoch: Epoch) -> Result<(), BlockError> {
+    let block_epoch = block.block_header.beacon.checkpoint;
+    if block_epoch != current_epoch {
+        return Err(BlockError::CandidateFromDifferentEpoch {
+            block_epoch,
+            current_epoch,
+        });
+    }
+
+    let proof = &block.block_header.proof.proof;
+    if proof.get_proof().is_empty() {
+        return Err(BlockError::NotValidPoe);
+    }
+
+    let proof_pkh = proof.pkh();
+    let signature_pkh = block.block_sig.public_key.pkh();
+    if proof_pkh != signature_pkh {
+        return Err(BlockError::PublicKeyHashMismatch {
+            proof_pkh,
+            signature_pkh,
+        });
+    }
+
+    Ok(())
+}
+
+/// Function to check that the epoch of a block candidate from the future does not start later
+/// than `tolerance` seconds after `current_timestamp`, so the candidate can be evaluated again
+/// once its epoch starts
+pub fn validate_candidate_timestamp(
+    block_epoch: Epoch,
+    epoch_constants: EpochConstants,
+    current_timestamp: i64,
+    tolerance: i64,
+) -> Result<(), BlockError> {
+    let seconds_ahead = epoch_constants
+        .epoch_timestamp(block_epoch)
+        .map(|timestamp| timestamp.saturating_sub(current_timestamp))
+        .unwrap_or_else(|_| i64::max_value());
+
+    if seconds_ahead > tolerance {
+        Err(BlockError::CandidateFromFuture {
+            block_epoch,
+            seconds_ahead,
+            tolerance,
+        })
+    } else {
+        Ok(())
+    }
+}
+
 pub fn calculate_randpoe_threshold(total_identities: u32) -> Hash {
     let max = u32::max_value();
     let target = if total_identities == 0 {
@@ -8,6 +8,9 @@ use witnet_data_structures::{
     data_request::DataRequestPool,
     error::{BlockError, DataRequestError, Secp256k1ConversionError, TransactionError},
     transaction::*,
+    upgrades::{
+        ProtocolUpgrade, ProtocolUpgrades, RAD_RETRIEVE_FALLBACK, RAD_STATISTICS, SIGNALING_WINDOW,
+    },
     vrf::{BlockEligibilityClaim, DataRequestEligibilityClaim, VrfCtx},
 };
 use witnet_protected::Protected;
@@ -134,6 +137,84 @@ fn mint_valid() {
     assert_eq!(x.unwrap(), ());
 }
 
+#[test]
+fn versioned_transaction_valid() {
+    let tx = VersionedTransaction::new(Transaction::Mint(MintTransaction::default()));
+    let x = validate_transaction_version(&tx, 0);
+    assert_eq!(x.unwrap(), ());
+}
+
+#[test]
+fn versioned_transaction_unknown_version() {
+    let tx = VersionedTransaction {
+        version: TRANSACTION_VERSION + 1,
+        ..VersionedTransaction::new(Transaction::Mint(MintTransaction::default()))
+    };
+    let x = validate_transaction_version(&tx, 0);
+    assert_eq!(
+        x.unwrap_err().downcast::<TransactionError>().unwrap(),
+        TransactionError::UnknownVersion {
+            version: TRANSACTION_VERSION + 1
+        }
+    );
+}
+
+#[test]
+fn versioned_transaction_unknown_feature() {
+    let tx = VersionedTransaction {
+        features: 1 << 3,
+        ..VersionedTransaction::new(Transaction::Mint(MintTransaction::default()))
+    };
+    let x = validate_transaction_version(&tx, 0);
+    assert_eq!(
+        x.unwrap_err().downcast::<TransactionError>().unwrap(),
+        TransactionError::InactiveFeature { bit: 3 }
+    );
+}
+
+#[test]
+fn versioned_transaction_is_validated() {
+    let vto = ValueTransferOutput {
+        pkh: MY_PKH.parse().unwrap(),
+        value: 1000,
+    };
+    let utxo_pool = build_utxo_set_with_mint(vec![vto.clone()], None, vec![]);
+    let utxo_diff = UtxoDiff::new(&utxo_pool);
+    let vti = Input::new(utxo_pool.iter().next().unwrap().0.clone());
+    let vt_body =
+        VTTransactionBody::new(vec![vti], vec![ValueTransferOutput { value: 990, ..vto }]);
+    let vts = sign_t(&vt_body);
+    let tx = VersionedTransaction::new(Transaction::ValueTransfer(VTTransaction::new(
+        vt_body,
+        vec![vts],
+    )));
+    let upgrades = ProtocolUpgrades::default();
+
+    let (_, _, fee) = validate_versioned_transaction(&tx, &utxo_diff, &upgrades, 0).unwrap();
+    assert_eq!(fee, 10);
+
+    // The version is checked before the transaction itself
+    let tx = VersionedTransaction {
+        version: TRANSACTION_VERSION + 1,
+        ..tx
+    };
+    let x = validate_versioned_transaction(&tx, &utxo_diff, &upgrades, 0);
+    assert_eq!(
+        x.unwrap_err().downcast::<TransactionError>().unwrap(),
+        TransactionError::UnknownVersion {
+            version: TRANSACTION_VERSION + 1
+        }
+    );
+
+    // Only value transfer and data request transactions can be sent on their own
+    let tx = VersionedTransaction::new(Transaction::Mint(MintTransaction::default()));
+    let x = validate_versioned_transaction(&tx, &utxo_diff, &upgrades, 0);
+    assert_eq!(
+        x.unwrap_err().downcast::<TransactionError>().unwrap(),
+        TransactionError::NotValidTransaction
+    );
+}
+
 #[test]
 fn vtt_no_inputs_no_outputs() {
     let utxo_pool = UnspentOutputsPool::default();
@@ -657,7 +738,7 @@ fn data_request_no_inputs() {
 
     let dr_tx_body = DRTransactionBody::new(vec![], vec![], dr_output);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![]);
-    let x = validate_dr_transaction(&dr_transaction, &utxo_diff);
+    let x = validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0);
     assert_eq!(
         x.unwrap_err().downcast::<TransactionError>().unwrap(),
         TransactionError::NegativeFee
@@ -680,7 +761,7 @@ fn data_request_no_inputs_but_one_signature() {
     let dr_tx_body = DRTransactionBody::new(vec![], vec![], dr_output);
     let drs = sign_t(&dr_tx_body);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
-    let x = validate_dr_transaction(&dr_transaction, &utxo_diff);
+    let x = validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0);
     assert_eq!(
         x.unwrap_err().downcast::<TransactionError>().unwrap(),
         TransactionError::MismatchingSignaturesNumber {
@@ -711,7 +792,7 @@ fn data_request_one_input_but_no_signature() {
 
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![]);
 
-    let x = validate_dr_transaction(&dr_transaction, &utxo_diff);
+    let x = validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0);
     assert_eq!(
         x.unwrap_err().downcast::<TransactionError>().unwrap(),
         TransactionError::MismatchingSignaturesNumber {
@@ -743,7 +824,8 @@ fn data_request_one_input_signatures() {
     test_signature_empty_wrong_bad(dr_tx_body, |dr_tx_body, drs| {
         let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
 
-        validate_dr_transaction(&dr_transaction, &utxo_diff).map(|_| ())
+        validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0)
+            .map(|_| ())
     });
 }
 
@@ -766,7 +848,7 @@ fn data_request_input_not_in_utxo() {
     let dr_tx_body = DRTransactionBody::new(vec![vti], vec![], dr_output);
     let drs = sign_t(&dr_tx_body);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
-    let x = validate_dr_transaction(&dr_transaction, &utxo_diff);
+    let x = validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0);
     assert_eq!(
         x.unwrap_err().downcast::<TransactionError>().unwrap(),
         TransactionError::OutputNotFound {
@@ -796,7 +878,7 @@ fn data_request_input_not_enough_value() {
     let dr_tx_body = DRTransactionBody::new(vec![vti], vec![], dr_output);
     let drs = sign_t(&dr_tx_body);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
-    let x = validate_dr_transaction(&dr_transaction, &utxo_diff);
+    let x = validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0);
     assert_eq!(
         x.unwrap_err().downcast::<TransactionError>().unwrap(),
         TransactionError::NegativeFee
@@ -817,7 +899,8 @@ fn test_drtx(dr_output: DataRequestOutput) -> Result<(), failure::Error> {
     let drs = sign_t(&dr_tx_body);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
 
-    validate_dr_transaction(&dr_transaction, &utxo_diff).map(|_| ())
+    validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0)
+        .map(|_| ())
 }
 
 fn test_rad_request(data_request: RADRequest) -> Result<(), failure::Error> {
@@ -839,6 +922,7 @@ fn example_data_request() -> RADRequest {
             kind: RADType::HttpGet,
             url: "".to_string(),
             script: vec![0x80],
+            fallback_urls: vec![],
         }],
         aggregate: RADAggregate { script: vec![0x80] },
         consensus: RADConsensus { script: vec![0x80] },
@@ -881,6 +965,102 @@ fn data_request_empty_scripts() {
     assert_eq!(x.unwrap(), ());
 }
 
+#[test]
+fn data_request_statistics_before_activation() {
+    // [[ArrayReduce, AverageMedian]]
+    let data_request = RADRequest {
+        aggregate: RADAggregate {
+            script: vec![0x81, 0x82, 0x18, 0x57, 0x05],
+        },
+        ..example_data_request()
+    };
+
+    let x = validate_rad_request_upgrades(&data_request, &ProtocolUpgrades::default(), 0);
+    assert_eq!(
+        x.unwrap_err().downcast::<RadError>().unwrap(),
+        RadError::UpgradeNotActive {
+            upgrade: RAD_STATISTICS.to_string(),
+        }
+    );
+
+    // Activate the upgrade: it is signaled during the first window, locked in during the second
+    // one and active from the third one
+    let upgrades = [ProtocolUpgrade {
+        name: RAD_STATISTICS,
+        bit: 0,
+        start_epoch: 0,
+        timeout_epoch: 10 * SIGNALING_WINDOW,
+        consensus_changes: None,
+    }];
+    let mut protocol_upgrades = ProtocolUpgrades::default();
+    for epoch in 0..SIGNALING_WINDOW {
+        let version = protocol_upgrades.block_version(&upgrades, epoch);
+        protocol_upgrades.process_block(&upgrades, epoch, version);
+    }
+    protocol_upgrades.process_block(&upgrades, SIGNALING_WINDOW, 0);
+    protocol_upgrades.process_block(&upgrades, 2 * SIGNALING_WINDOW, 0);
+
+    let x = validate_rad_request_upgrades(&data_request, &protocol_upgrades, 2 * SIGNALING_WINDOW);
+    assert_eq!(x.unwrap(), ());
+
+    // Data requests of blocks older than the activation are still validated with the old rules
+    let x =
+        validate_rad_request_upgrades(&data_request, &protocol_upgrades, 2 * SIGNALING_WINDOW - 1);
+    assert_eq!(
+        x.unwrap_err().downcast::<RadError>().unwrap(),
+        RadError::UpgradeNotActive {
+            upgrade: RAD_STATISTICS.to_string(),
+        }
+    );
+}
+
+#[test]
+fn data_request_fallback_urls() {
+    let retrieve = |fallback_urls: Vec<&str>| RADRetrieve {
+        kind: RADType::HttpGet,
+        url: "http://example.com".to_string(),
+        script: vec![0x80],
+        fallback_urls: fallback_urls.into_iter().map(String::from).collect(),
+    };
+
+    assert_eq!(
+        validate_fallback_urls(&retrieve(vec!["http://example.org", "http://example.net"])),
+        Ok(())
+    );
+    assert_eq!(
+        validate_fallback_urls(&retrieve(vec!["http://example.org", "http://example.com"])),
+        Err(DataRequestError::InvalidFallbackUrl {
+            url: "http://example.com".to_string(),
+        })
+    );
+    assert_eq!(
+        validate_fallback_urls(&retrieve(vec![""])),
+        Err(DataRequestError::InvalidFallbackUrl {
+            url: "".to_string()
+        })
+    );
+    assert_eq!(
+        validate_fallback_urls(&retrieve(vec!["1", "2", "3", "4", "5"])),
+        Err(DataRequestError::TooManyFallbackUrls {
+            count: 5,
+            max: MAX_RETRIEVE_FALLBACK_URLS,
+        })
+    );
+
+    // Fallback sources cannot be used before the upgrade is active
+    let data_request = RADRequest {
+        retrieve: vec![retrieve(vec!["http://example.org"])],
+        ..example_data_request()
+    };
+    let x = validate_rad_request_upgrades(&data_request, &ProtocolUpgrades::default(), 0);
+    assert_eq!(
+        x.unwrap_err().downcast::<RadError>().unwrap(),
+        RadError::UpgradeNotActive {
+            upgrade: RAD_RETRIEVE_FALLBACK.to_string(),
+        }
+    );
+}
+
 #[test]
 fn data_request_witnesses_0() {
     // A data request with 0 witnesses is invalid
@@ -1085,6 +1265,41 @@ fn data_request_invalid_all_fees() {
     );
 }
 
+#[test]
+fn data_request_fees_overflow() {
+    // The sum of the fees does not fit in a u64
+    let data_request = example_data_request();
+    let x = test_drtx(DataRequestOutput {
+        value: 1000,
+        commit_fee: u64::max_value(),
+        reveal_fee: 1,
+        tally_fee: 0,
+        witnesses: 2,
+        data_request,
+        ..DataRequestOutput::default()
+    });
+    assert_eq!(
+        x.unwrap_err().downcast::<TransactionError>().unwrap(),
+        TransactionError::DataRequestFeesOverflow,
+    );
+}
+
+#[test]
+fn data_request_output_witness_reward() {
+    // 1000 - 250 - 250 - 100 = 400, shared between the 2 witnesses
+    let dr_output = DataRequestOutput {
+        value: 1000,
+        commit_fee: 250,
+        reveal_fee: 250,
+        tally_fee: 100,
+        witnesses: 2,
+        data_request: example_data_request(),
+        ..DataRequestOutput::default()
+    };
+    let x = validate_data_request_output(&dr_output, &ProtocolUpgrades::default(), 0);
+    assert_eq!(x.unwrap(), 400);
+}
+
 #[test]
 fn data_request_negative_value_commit_fee() {
     // 1000 - 1000 = 0, so the witnesses get 0 reward
@@ -1183,9 +1398,10 @@ fn data_request_miner_fee() {
     let drs = sign_t(&dr_tx_body);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
 
-    let dr_miner_fee = validate_dr_transaction(&dr_transaction, &utxo_diff)
-        .map(|(_, _, fee)| fee)
-        .unwrap();
+    let dr_miner_fee =
+        validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0)
+            .map(|(_, _, fee)| fee)
+            .unwrap();
     assert_eq!(dr_miner_fee, 1000 - 750);
 }
 
@@ -1215,9 +1431,10 @@ fn data_request_miner_fee_with_change() {
     let drs = sign_t(&dr_tx_body);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
 
-    let dr_miner_fee = validate_dr_transaction(&dr_transaction, &utxo_diff)
-        .map(|(_, _, fee)| fee)
-        .unwrap();
+    let dr_miner_fee =
+        validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0)
+            .map(|(_, _, fee)| fee)
+            .unwrap();
     assert_eq!(dr_miner_fee, 1000 - 750 - 200);
 }
 
@@ -1247,7 +1464,7 @@ fn data_request_miner_fee_with_too_much_change() {
     let drs = sign_t(&dr_tx_body);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
 
-    let x = validate_dr_transaction(&dr_transaction, &utxo_diff);
+    let x = validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0);
     assert_eq!(
         x.unwrap_err().downcast::<TransactionError>().unwrap(),
         TransactionError::NegativeFee
@@ -1280,7 +1497,7 @@ fn data_request_zero_value_output() {
     let drs = sign_t(&dr_tx_body);
     let dr_transaction = DRTransaction::new(dr_tx_body, vec![drs]);
 
-    let x = validate_dr_transaction(&dr_transaction, &utxo_diff);
+    let x = validate_dr_transaction(&dr_transaction, &utxo_diff, &ProtocolUpgrades::default(), 0);
     assert_eq!(
         x.unwrap_err().downcast::<TransactionError>().unwrap(),
         TransactionError::ZeroValueOutput {
@@ -2735,6 +2952,7 @@ fn test_block<F: FnMut(&mut Block) -> bool>(mut mut_block: F) -> Result<(), fail
         vrf,
         &rep_eng,
         EpochConstants::default(),
+        &ProtocolUpgrades::default(),
     )
     .map(|_| ())
 }
@@ -2795,6 +3013,56 @@ fn block_from_the_past() {
     );
 }
 
+#[test]
+fn candidate_pre_validation() {
+    let vrf = &mut VrfCtx::secp256k1().unwrap();
+    let secret_key = SecretKey {
+        bytes: Protected::from(vec![0xcd; 32]),
+    };
+    let current_epoch = 1000;
+    let mut block_header = BlockHeader::default();
+    block_header.beacon = CheckpointBeacon {
+        checkpoint: current_epoch,
+        hash_prev_block: LAST_BLOCK_HASH.parse().unwrap(),
+    };
+    block_header.proof =
+        BlockEligibilityClaim::create(vrf, &secret_key, block_header.beacon).unwrap();
+    let block_sig = sign_t(&block_header);
+    let block = Block {
+        block_header,
+        block_sig,
+        txns: BlockTransactions::default(),
+    };
+
+    assert_eq!(pre_validate_candidate(&block, current_epoch), Ok(()));
+    assert_eq!(
+        pre_validate_candidate(&block, current_epoch + 1),
+        Err(BlockError::CandidateFromDifferentEpoch {
+            current_epoch: current_epoch + 1,
+            block_epoch: current_epoch,
+        })
+    );
+
+    // Signed by another identity
+    let mut other_signer = block.clone();
+    other_signer.block_sig = sign_t2(&other_signer.block_header);
+    assert_eq!(
+        pre_validate_candidate(&other_signer, current_epoch),
+        Err(BlockError::PublicKeyHashMismatch {
+            proof_pkh: MY_PKH.parse().unwrap(),
+            signature_pkh: MY_PKH_2.parse().unwrap(),
+        })
+    );
+
+    // Without eligibility proof
+    let mut no_proof = block;
+    no_proof.block_header.proof = BlockEligibilityClaim::default();
+    assert_eq!(
+        pre_validate_candidate(&no_proof, current_epoch),
+        Err(BlockError::NotValidPoe)
+    );
+}
+
 #[test]
 fn block_unknown_hash_prev_block() {
     let unknown_hash = "2222222222222222222222222222222222222222222222222222222222222222"
@@ -2946,6 +3214,7 @@ fn block_difficult_proof() {
                 vrf,
                 &rep_eng,
                 EpochConstants::default(),
+                &ProtocolUpgrades::default(),
             )
             .map(|_| ())
         };
@@ -3041,6 +3310,49 @@ fn block_add_vtt_but_dont_update_merkle_tree() {
     );
 }
 
+#[test]
+fn candidate_timestamp_within_tolerance() {
+    let epoch_constants = EpochConstants {
+        checkpoint_zero_timestamp: 1_000,
+        checkpoints_period: 90,
+    };
+
+    // Epoch 2 starts at 1_180
+    assert_eq!(
+        validate_candidate_timestamp(2, epoch_constants, 1_180, 10),
+        Ok(())
+    );
+    assert_eq!(
+        validate_candidate_timestamp(2, epoch_constants, 1_170, 10),
+        Ok(())
+    );
+}
+
+#[test]
+fn candidate_timestamp_beyond_tolerance() {
+    let epoch_constants = EpochConstants {
+        checkpoint_zero_timestamp: 1_000,
+        checkpoints_period: 90,
+    };
+
+    assert_eq!(
+        validate_candidate_timestamp(2, epoch_constants, 1_169, 10),
+        Err(BlockError::CandidateFromFuture {
+            block_epoch: 2,
+            seconds_ahead: 11,
+            tolerance: 10,
+        })
+    );
+    assert_eq!(
+        validate_candidate_timestamp(u32::max_value(), epoch_constants, 1_169, 10),
+        Err(BlockError::CandidateFromFuture {
+            block_epoch: u32::max_value(),
+            seconds_ahead: i64::max_value(),
+            tolerance: 10,
+        })
+    );
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // Malleability tests: can we change a block without invalidating it?
 ///////////////////////////////////////////////////////////////////////////////
@@ -3190,6 +3502,7 @@ fn test_blocks(txns: Vec<(BlockTransactions, u64)>) -> Result<(), failure::Error
             vrf,
             &rep_eng,
             EpochConstants::default(),
+            &ProtocolUpgrades::default(),
         )?;
 
         // FIXME(#685): add sequence validations
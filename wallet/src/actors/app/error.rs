@@ -5,7 +5,37 @@ use serde_json::json;
 use witnet_net::client::tcp;
 
 use super::*;
-use crate::actors;
+use crate::{actors, repository};
+
+/// Stable error codes returned by the wallet JSON-RPC API.
+///
+/// Clients should branch on these codes instead of parsing the error messages.
+pub mod codes {
+    /// The request params did not pass validation. The error data is a list of
+    /// `[field, message]` pairs.
+    pub const VALIDATION: i64 = 400;
+    /// The session does not exist or has expired, or the API key of the tenant is not valid.
+    pub const UNAUTHORIZED: i64 = 401;
+    /// The wallet does not exist or is not unlocked in the session.
+    pub const FORBIDDEN: i64 = 402;
+    /// The client exceeded the rate limits of the wallet. The error data includes the `method`,
+    /// the `limit` that was exceeded (`requestsPerSecond`, `tenantRequestsPerSecond`,
+    /// `tenantSessions` or `concurrency`) and its `max` value.
+    pub const RATE_LIMITED: i64 = 429;
+    /// Unexpected internal error.
+    pub const INTERNAL: i64 = 500;
+    /// The node returned an error.
+    pub const NODE: i64 = 510;
+    /// The wallet is not connected to a node.
+    pub const NODE_NOT_CONNECTED: i64 = 520;
+    /// Reading or writing the wallet database failed.
+    pub const REPOSITORY: i64 = 530;
+    /// Running a RAD request failed.
+    pub const RAD: i64 = 540;
+    /// Signing with the external signer of the session failed. The error data includes the
+    /// `kind` of failure: `notConnected`, `requestNotFound`, `rejected` or `timeout`.
+    pub const SIGNER: i64 = 550;
+}
 
 #[derive(Debug, Fail)]
 pub enum Error {
@@ -19,33 +49,93 @@ pub enum Error {
     NodeNotConnected,
     #[fail(display = "session not found")]
     SessionNotFound,
+    #[fail(display = "invalid API key")]
+    InvalidApiKey,
     #[fail(display = "wallet not found")]
     WalletNotFound,
+    #[fail(display = "rate limit of {} exceeded for method {}", limit, method)]
+    RateLimited {
+        method: String,
+        limit: &'static str,
+        max: u32,
+    },
+    #[fail(display = "repository error")]
+    Repository(repository::Error),
+    #[fail(display = "rad request error")]
+    Rad(witnet_rad::error::RadError),
+    #[fail(display = "no external signer registered for the session")]
+    SignerNotConnected,
+    #[fail(display = "signature request not found")]
+    SignatureRequestNotFound,
+    #[fail(display = "signature request rejected by the external signer: {}", _0)]
+    SignatureRejected(String),
+    #[fail(display = "signature request timed out")]
+    SignatureTimeout,
 }
 
 impl Error {
     pub fn into_parts(self) -> (i64, &'static str, Option<serde_json::Value>) {
         match self {
             Error::Validation(e) => (
-                400,
+                codes::VALIDATION,
                 "Validation Error",
                 Some(serde_json::to_value(e).expect("serialization of errors failed")),
             ),
-            Error::SessionNotFound => (401, "Unauthorized", None),
-            Error::WalletNotFound => (402, "Forbidden", None),
+            Error::SessionNotFound | Error::InvalidApiKey => {
+                (codes::UNAUTHORIZED, "Unauthorized", None)
+            }
+            Error::WalletNotFound => (codes::FORBIDDEN, "Forbidden", None),
+            Error::RateLimited { method, limit, max } => (
+                codes::RATE_LIMITED,
+                "Rate Limited",
+                Some(json!({ "method": method, "limit": limit, "max": max })),
+            ),
             Error::Node(e) => {
                 log::error!("Node Error: {}", &e);
                 (
-                    510,
+                    codes::NODE,
                     "Node Error",
                     Some(json!({ "cause": format!("{}", e) })),
                 )
             }
-            Error::NodeNotConnected => (520, "Node Not Connected", None),
+            Error::NodeNotConnected => (codes::NODE_NOT_CONNECTED, "Node Not Connected", None),
+            Error::Repository(e) => {
+                log::error!("Repository Error: {}", &e);
+                (
+                    codes::REPOSITORY,
+                    "Repository Error",
+                    Some(json!({ "kind": e.kind(), "cause": format!("{}", e) })),
+                )
+            }
+            Error::Rad(e) => (
+                codes::RAD,
+                "Rad Error",
+                Some(json!({ "cause": format!("{}", e) })),
+            ),
+            Error::SignerNotConnected => (
+                codes::SIGNER,
+                "Signer Error",
+                Some(json!({ "kind": "notConnected" })),
+            ),
+            Error::SignatureRequestNotFound => (
+                codes::SIGNER,
+                "Signer Error",
+                Some(json!({ "kind": "requestNotFound" })),
+            ),
+            Error::SignatureRejected(reason) => (
+                codes::SIGNER,
+                "Signer Error",
+                Some(json!({ "kind": "rejected", "cause": reason })),
+            ),
+            Error::SignatureTimeout => (
+                codes::SIGNER,
+                "Signer Error",
+                Some(json!({ "kind": "timeout" })),
+            ),
             Error::Internal(e) => {
                 log::error!("Internal Error: {}", &e);
                 (
-                    500,
+                    codes::INTERNAL,
                     "Internal Error",
                     Some(json!({ "cause": format!("{}", e) })),
                 )
@@ -88,7 +178,13 @@ impl From<actix::MailboxError> for Error {
 
 impl From<actors::worker::Error> for Error {
     fn from(err: actors::worker::Error) -> Self {
-        internal_error(err)
+        match err {
+            actors::worker::Error::Repository(e) => Error::Repository(e),
+            actors::worker::Error::Db(e) => Error::Repository(repository::Error::Db(e)),
+            actors::worker::Error::Rad(e) => Error::Rad(e),
+            actors::worker::Error::WalletNotFound => Error::WalletNotFound,
+            err => internal_error(err),
+        }
     }
 }
 
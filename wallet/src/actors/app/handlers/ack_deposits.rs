@@ -0,0 +1,37 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AckDepositsRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    /// Sequence number of the last processed deposit
+    cursor: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AckDepositsResponse {
+    /// Sequence number of the last acknowledged deposit
+    pub cursor: u64,
+}
+
+impl Message for AckDepositsRequest {
+    type Result = app::Result<AckDepositsResponse>;
+}
+
+impl Handler<AckDepositsRequest> for app::App {
+    type Result = app::ResponseActFuture<AckDepositsResponse>;
+
+    fn handle(&mut self, msg: AckDepositsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self
+            .ack_deposits(msg.session_id, msg.wallet_id, msg.cursor)
+            .map(|cursor, _, _| AckDepositsResponse { cursor });
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactWalletDbRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+pub type CompactWalletDbResponse = model::StorageCompaction;
+
+impl Message for CompactWalletDbRequest {
+    type Result = app::Result<CompactWalletDbResponse>;
+}
+
+impl Handler<CompactWalletDbRequest> for app::App {
+    type Result = app::ResponseActFuture<CompactWalletDbResponse>;
+
+    fn handle(&mut self, msg: CompactWalletDbRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.compact_wallet_db(msg.session_id, msg.wallet_id)
+    }
+}
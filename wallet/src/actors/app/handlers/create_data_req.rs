@@ -1,6 +1,8 @@
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use witnet_rad::script::unpack_radon_script;
+
 use crate::actors::app;
 use crate::types;
 
@@ -8,16 +10,279 @@ use crate::types;
 #[serde(rename_all = "camelCase")]
 pub struct CreateDataReqRequest {
     pub rad_request: types::RADRequest,
+    /// Total value of the data request, fees included. If omitted, it is the reward for the
+    /// witnesses given by the `priority` plus the fees
+    pub value: Option<u64>,
+    /// An omitted number of witnesses is reported as a validation error of this field, like 0
+    #[serde(default)]
+    pub witnesses: u16,
+    #[serde(default)]
+    pub backup_witnesses: u16,
+    /// Fees of the commit, reveal and tally transactions. The omitted ones are given by the
+    /// `priority`
+    pub commit_fee: Option<u64>,
+    pub reveal_fee: Option<u64>,
+    pub tally_fee: Option<u64>,
+    #[serde(default)]
+    pub time_lock: u64,
+    #[serde(default)]
+    pub priority: types::DataRequestPriority,
 }
 
 impl Message for CreateDataReqRequest {
-    type Result = app::Result<()>;
+    type Result = app::Result<types::DataRequestOutput>;
 }
 
 impl Handler<CreateDataReqRequest> for app::App {
-    type Result = <CreateDataReqRequest as Message>::Result;
+    type Result = app::ResponseActFuture<types::DataRequestOutput>;
+
+    fn handle(&mut self, msg: CreateDataReqRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let validated = validate(msg).map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(|dr_output, slf: &mut Self, _| {
+            slf.estimate_data_request_cost(dr_output.clone())
+                .into_actor(slf)
+                .and_then(move |estimate, _, _| {
+                    fut::result(validate_network(&dr_output, &estimate).map(|_| dr_output))
+                })
+        });
+
+        Box::new(f)
+    }
+}
+
+/// Validate `CreateDataReqRequest`, filling the omitted value and fees from its priority: each
+/// witness is rewarded with the reward of the priority, and each commit, reveal and tally
+/// transaction pays the fee of the priority.
+///
+/// To be valid it must pass these checks:
+/// - the scripts of the RAD request can be decoded
+/// - there is at least one witness
+/// - the value is greater than the sum of the fees
+/// - the reward for the witnesses can be shared equally between them
+fn validate(req: CreateDataReqRequest) -> Result<types::DataRequestOutput, app::ValidationErrors> {
+    let CreateDataReqRequest {
+        rad_request,
+        value,
+        witnesses,
+        backup_witnesses,
+        commit_fee,
+        reveal_fee,
+        tally_fee,
+        time_lock,
+        priority,
+    } = req;
+
+    let fee_per_witness = u64::from(witnesses).saturating_mul(priority.fee_per_transaction());
+    let commit_fee = commit_fee.unwrap_or(fee_per_witness);
+    let reveal_fee = reveal_fee.unwrap_or(fee_per_witness);
+    let tally_fee = tally_fee.unwrap_or_else(|| priority.fee_per_transaction());
+    let fees = commit_fee
+        .saturating_add(reveal_fee)
+        .saturating_add(tally_fee);
+    let value = value.unwrap_or_else(|| {
+        u64::from(witnesses)
+            .saturating_mul(priority.reward_per_witness())
+            .saturating_add(fees)
+    });
 
-    fn handle(&mut self, _msg: CreateDataReqRequest, _ctx: &mut Self::Context) -> Self::Result {
+    let scripts_result = rad_request
+        .retrieve
+        .iter()
+        .map(|retrieve| &retrieve.script)
+        .chain(vec![
+            &rad_request.aggregate.script,
+            &rad_request.consensus.script,
+        ])
+        .try_for_each(|script| unpack_radon_script(script).map(|_| ()));
+    let rad_request = match scripts_result {
+        Ok(()) => Ok(rad_request),
+        Err(err) => Err(app::field_error(
+            "radRequest",
+            format!("Invalid RADON script: {}", err),
+        )),
+    };
+    let witnesses = if witnesses == 0 {
+        Err(app::field_error(
+            "witnesses",
+            "At least one witness is required.",
+        ))
+    } else {
+        Ok(witnesses)
+    };
+    let witnesses_reward = if value <= fees {
+        Err(app::field_error(
+            "value",
+            format!("Value must be greater than the sum of the fees ({}).", fees),
+        ))
+    } else {
+        Ok(value - fees)
+    };
+    let witnesses = app::combine_field_errors(witnesses, witnesses_reward, |w, r| (w, r)).and_then(
+        |(witnesses, witnesses_reward)| {
+            if witnesses_reward % u64::from(witnesses) != 0 {
+                Err(app::field_error(
+                    "value",
+                    format!(
+                        "The reward for the witnesses ({}) must be divisible by the number of \
+                         witnesses ({}).",
+                        witnesses_reward, witnesses
+                    ),
+                ))
+            } else {
+                Ok(witnesses)
+            }
+        },
+    );
+    app::combine_field_errors(rad_request, witnesses, move |data_request, witnesses| {
+        types::DataRequestOutput {
+            data_request,
+            value,
+            witnesses,
+            backup_witnesses,
+            commit_fee,
+            reveal_fee,
+            tally_fee,
+            time_lock,
+        }
+    })
+}
+
+/// Validate the complete data request against the current network conditions reported by the
+/// node.
+///
+/// To be valid it must pass these checks:
+/// - the number of witnesses, backup witnesses included, is not greater than the number of
+///   identities active in the witnessing protocol
+/// - the node does not report any other issue, e.g. a RAD request using operators that are not
+///   active yet
+fn validate_network(
+    dr_output: &types::DataRequestOutput,
+    estimate: &types::Json,
+) -> app::Result<()> {
+    let active_identities = estimate["active_identities"]
+        .as_u64()
+        .ok_or_else(|| app::Error::Node(failure::format_err!("Invalid cost estimation")))?;
+    let total_witnesses = u64::from(dr_output.witnesses) + u64::from(dr_output.backup_witnesses);
+
+    if total_witnesses > active_identities {
+        return Err(app::validation_error(app::field_error(
+            "witnesses",
+            format!(
+                "Only {} identities are currently active, the data request would not be resolved.",
+                active_identities
+            ),
+        )));
+    }
+
+    let issues: Vec<_> = estimate["issues"]
+        .as_array()
+        .map(|issues| {
+            issues
+                .iter()
+                .filter_map(|issue| issue.as_str())
+                .map(|issue| ("dataRequest".to_string(), issue.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if issues.is_empty() {
         Ok(())
+    } else {
+        Err(app::validation_error(issues))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{DR_PRIORITY_FEES_PER_TRANSACTION, DR_PRIORITY_REWARDS_PER_WITNESS};
+    use witnet_data_structures::chain::{RADAggregate, RADConsensus, RADRetrieve};
+
+    fn request(priority: types::DataRequestPriority) -> CreateDataReqRequest {
+        CreateDataReqRequest {
+            rad_request: types::RADRequest {
+                not_before: 0,
+                retrieve: vec![RADRetrieve {
+                    kind: types::RADType::HttpGet,
+                    url: "".to_string(),
+                    script: vec![0x80],
+                    fallback_urls: vec![],
+                }],
+                aggregate: RADAggregate { script: vec![0x80] },
+                consensus: RADConsensus { script: vec![0x80] },
+                deliver: vec![],
+            },
+            value: None,
+            witnesses: 3,
+            backup_witnesses: 0,
+            commit_fee: None,
+            reveal_fee: None,
+            tally_fee: None,
+            time_lock: 0,
+            priority,
+        }
+    }
+
+    #[test]
+    fn omitted_amounts_are_filled_from_the_priority() {
+        let dr_output = validate(request(types::DataRequestPriority::High)).unwrap();
+
+        assert_eq!(
+            dr_output.commit_fee,
+            3 * DR_PRIORITY_FEES_PER_TRANSACTION[2]
+        );
+        assert_eq!(
+            dr_output.reveal_fee,
+            3 * DR_PRIORITY_FEES_PER_TRANSACTION[2]
+        );
+        assert_eq!(dr_output.tally_fee, DR_PRIORITY_FEES_PER_TRANSACTION[2]);
+        assert_eq!(
+            dr_output.total_witnesses_reward(),
+            3 * DR_PRIORITY_REWARDS_PER_WITNESS[2]
+        );
+    }
+
+    #[test]
+    fn given_amounts_are_kept() {
+        let dr_output = validate(CreateDataReqRequest {
+            value: Some(3_000 + 7),
+            commit_fee: Some(3),
+            tally_fee: Some(1),
+            ..request(types::DataRequestPriority::Low)
+        })
+        .unwrap();
+
+        assert_eq!(dr_output.value, 3_007);
+        assert_eq!(dr_output.commit_fee, 3);
+        assert_eq!(
+            dr_output.reveal_fee,
+            3 * DR_PRIORITY_FEES_PER_TRANSACTION[0]
+        );
+        assert_eq!(dr_output.tally_fee, 1);
+    }
+
+    #[test]
+    fn value_not_covering_the_fees_is_rejected() {
+        let errors = validate(CreateDataReqRequest {
+            value: Some(10),
+            commit_fee: Some(10),
+            ..request(types::DataRequestPriority::Medium)
+        })
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "value");
+    }
+
+    #[test]
+    fn omitted_witnesses_are_rejected() {
+        let req: CreateDataReqRequest = serde_json::from_value(serde_json::json!({
+            "radRequest": request(types::DataRequestPriority::Medium).rad_request,
+        }))
+        .unwrap();
+        let errors = validate(req).unwrap_err();
+
+        assert!(errors.iter().any(|(field, _)| field == "witnesses"));
     }
 }
@@ -7,6 +7,8 @@ use crate::types;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateMnemonicsRequest {
     pub length: u8,
+    #[serde(default)]
+    pub language: types::MnemonicLang,
 }
 
 #[derive(Debug, Serialize)]
@@ -23,8 +25,8 @@ impl Handler<CreateMnemonicsRequest> for app::App {
 
     fn handle(&mut self, req: CreateMnemonicsRequest, _ctx: &mut Self::Context) -> Self::Result {
         let result = validate(req).map_err(app::validation_error);
-        let f = fut::result(result).and_then(|length, slf: &mut Self, _| {
-            slf.generate_mnemonics(length)
+        let f = fut::result(result).and_then(|(length, language), slf: &mut Self, _| {
+            slf.generate_mnemonics(length, language)
                 .map(|mnemonics| CreateMnemonicsResponse { mnemonics })
                 .into_actor(slf)
         });
@@ -37,8 +39,10 @@ impl Handler<CreateMnemonicsRequest> for app::App {
 ///
 /// To be valid it must pass these checks:
 /// - length must be 12, 15, 18, 21 or 24
-fn validate(req: CreateMnemonicsRequest) -> Result<types::MnemonicLength, app::ValidationErrors> {
-    match req.length {
+fn validate(
+    req: CreateMnemonicsRequest,
+) -> Result<(types::MnemonicLength, types::MnemonicLang), app::ValidationErrors> {
+    let length = match req.length {
         12 => Ok(types::MnemonicLength::Words12),
         15 => Ok(types::MnemonicLength::Words15),
         18 => Ok(types::MnemonicLength::Words18),
@@ -48,5 +52,7 @@ fn validate(req: CreateMnemonicsRequest) -> Result<types::MnemonicLength, app::V
             "length",
             "Invalid Mnemonics Length. Must be 12, 15, 18, 21 or 24",
         )),
-    }
+    }?;
+
+    Ok((length, req.language))
 }
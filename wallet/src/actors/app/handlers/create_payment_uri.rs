@@ -0,0 +1,70 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types, uri};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatePaymentUriRequest {
+    address: String,
+    amount: Option<types::Nanowits>,
+    message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePaymentUriResponse {
+    /// Payment request URI
+    pub uri: String,
+    /// The same URI with the scheme and address in uppercase, which results in smaller QR codes
+    pub qr_uri: String,
+}
+
+impl Message for CreatePaymentUriRequest {
+    type Result = app::Result<CreatePaymentUriResponse>;
+}
+
+impl Handler<CreatePaymentUriRequest> for app::App {
+    type Result = <CreatePaymentUriRequest as Message>::Result;
+
+    fn handle(&mut self, msg: CreatePaymentUriRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let request = validate(msg, self.params.testnet).map_err(app::validation_error)?;
+
+        Ok(CreatePaymentUriResponse {
+            uri: uri::encode(&request),
+            qr_uri: uri::encode_qr(&request),
+        })
+    }
+}
+
+/// Validate `CreatePaymentUriRequest`.
+///
+/// To be valid it must pass these checks:
+/// - address is a valid address of the current environment
+/// - amount, if given, is greater than zero
+fn validate(
+    msg: CreatePaymentUriRequest,
+    testnet: bool,
+) -> Result<model::PaymentRequest, app::ValidationErrors> {
+    let CreatePaymentUriRequest {
+        address,
+        amount,
+        message,
+    } = msg;
+    let address = app::validate_address("address", address, testnet);
+    let amount = match amount {
+        Some(amount) if amount.is_zero() => Err(app::field_error(
+            "amount",
+            "Amount must be greater than zero.",
+        )),
+        amount => Ok(amount),
+    };
+
+    app::combine_field_errors(address, amount, move |address, amount| {
+        model::PaymentRequest {
+            address,
+            amount,
+            message,
+        }
+    })
+}
@@ -0,0 +1,137 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRecurringPaymentRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    address: String,
+    amount: types::Nanowits,
+    #[serde(default)]
+    fee: types::Nanowits,
+    label: Option<String>,
+    interval: types::ScheduleInterval,
+    /// Timestamp of the first payment, defaults to now
+    start_date: Option<i64>,
+    /// Timestamp after which no more payments are made
+    end_date: Option<i64>,
+}
+
+pub type CreateRecurringPaymentResponse = model::RecurringPayment;
+
+impl Message for CreateRecurringPaymentRequest {
+    type Result = app::Result<CreateRecurringPaymentResponse>;
+}
+
+impl Handler<CreateRecurringPaymentRequest> for app::App {
+    type Result = app::ResponseActFuture<CreateRecurringPaymentResponse>;
+
+    fn handle(
+        &mut self,
+        msg: CreateRecurringPaymentRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let session_id = msg.session_id.clone();
+        let wallet_id = msg.wallet_id.clone();
+        let validated = validate(msg, self.params.testnet, self.params.epoch_constants)
+            .map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(move |payment, slf: &mut Self, _ctx| {
+            slf.create_recurring_payment(session_id, wallet_id, payment)
+        });
+
+        Box::new(f)
+    }
+}
+
+/// Validate `CreateRecurringPaymentRequest`.
+///
+/// To be valid it must pass these checks:
+/// - address is a valid address of the current environment
+/// - amount is greater than zero
+/// - interval is at least one epoch long
+/// - start date and end date are not before the first epoch
+/// - end date is not before the start date
+fn validate(
+    msg: CreateRecurringPaymentRequest,
+    testnet: bool,
+    epoch_constants: types::EpochConstants,
+) -> Result<model::RecurringPayment, app::ValidationErrors> {
+    let CreateRecurringPaymentRequest {
+        address,
+        amount,
+        fee,
+        label,
+        interval,
+        start_date,
+        end_date,
+        ..
+    } = msg;
+    let epoch_at = |field, timestamp| {
+        epoch_constants
+            .epoch_at(timestamp)
+            .map_err(|_| app::field_error(field, "Date is before the first epoch."))
+    };
+
+    let address = app::validate_address("address", address, testnet);
+    let amount = if amount.is_zero() {
+        Err(app::field_error(
+            "amount",
+            "Amount must be greater than zero.",
+        ))
+    } else {
+        Ok(amount)
+    };
+    let interval = match interval.epochs(epoch_constants.checkpoints_period) {
+        0 => Err(app::field_error(
+            "interval",
+            "Interval must be at least one epoch long.",
+        )),
+        epochs => Ok((interval, epochs)),
+    };
+    let start_epoch = epoch_at("startDate", start_date.unwrap_or_else(now));
+    let end_epoch = match end_date {
+        Some(end_date) => epoch_at("endDate", end_date).map(Some),
+        None => Ok(None),
+    };
+    let epochs = app::combine_field_errors(start_epoch, end_epoch, |start, end| (start, end))
+        .and_then(|(start, end)| match end {
+            Some(end) if end < start => Err(app::field_error(
+                "endDate",
+                "End date cannot be before the start date.",
+            )),
+            end => Ok((start, end)),
+        });
+
+    app::combine_field_errors(
+        app::combine_field_errors(address, amount, |address, amount| (address, amount)),
+        app::combine_field_errors(interval, epochs, |interval, epochs| (interval, epochs)),
+        move |(address, amount), ((interval, interval_epochs), (next_epoch, end_epoch))| {
+            model::RecurringPayment {
+                id: 0,
+                address,
+                amount,
+                fee,
+                label,
+                interval,
+                interval_epochs,
+                next_epoch,
+                end_epoch,
+            }
+        },
+    )
+}
+
+/// Current timestamp in seconds.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
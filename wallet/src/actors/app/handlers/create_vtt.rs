@@ -2,19 +2,28 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
+use crate::types::{self, Hashable as _};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CreateVttRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
     address: String,
-    label: String,
     amount: u64,
     fee: u64,
+    /// Address paid the change, the one chosen by the `change_address` param of the wallet if
+    /// not given
+    change_address: Option<types::ChangeAddressPolicy>,
+    /// Client-generated id, so retrying the request returns the same transaction
+    idempotency_key: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateVttResponse {
     pub transaction_id: String,
+    pub transaction: types::VTTransaction,
 }
 
 impl Message for CreateVttRequest {
@@ -22,12 +31,42 @@ impl Message for CreateVttRequest {
 }
 
 impl Handler<CreateVttRequest> for app::App {
-    type Result = <CreateVttRequest as Message>::Result;
+    type Result = app::ResponseActFuture<CreateVttResponse>;
+
+    fn handle(&mut self, msg: CreateVttRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let params = (msg.address.clone(), msg.amount, msg.fee, msg.change_address);
+        let CreateVttRequest {
+            session_id,
+            wallet_id,
+            address,
+            amount,
+            fee,
+            change_address,
+            idempotency_key,
+        } = msg;
+
+        self.idempotent(
+            "createVttRequest",
+            &wallet_id.clone(),
+            idempotency_key,
+            &params,
+            move |slf| {
+                let f = slf
+                    .create_vtt(
+                        session_id,
+                        wallet_id,
+                        address,
+                        amount.into(),
+                        fee.into(),
+                        change_address,
+                    )
+                    .map(|transaction, _, _| CreateVttResponse {
+                        transaction_id: transaction.hash().to_string(),
+                        transaction,
+                    });
 
-    fn handle(&mut self, _msg: CreateVttRequest, _ctx: &mut Self::Context) -> Self::Result {
-        Ok(CreateVttResponse {
-            transaction_id: "389a3fa3a1feb8fd8cdc61748ac17dce0aeef39ff9634dec9c20ece69105c264"
-                .to_string(),
-        })
+                Box::new(f)
+            },
+        )
     }
 }
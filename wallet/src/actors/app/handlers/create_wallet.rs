@@ -14,6 +14,8 @@ pub struct CreateWalletRequest {
     password: types::Password,
     seed_source: String,
     seed_data: types::Password,
+    backup_password: Option<types::Password>,
+    mnemonics_language: Option<types::MnemonicLang>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,6 +33,8 @@ impl Handler<CreateWalletRequest> for app::App {
 
     fn handle(&mut self, req: CreateWalletRequest, _ctx: &mut Self::Context) -> Self::Result {
         let validated_params = validate(req).map_err(app::validation_error);
+        // The tenant is only current while the message is handled, not when the wallet is created
+        let tenant = app::current_tenant();
 
         let f = fut::result(validated_params).and_then(|params, slf: &mut Self, _ctx| {
             slf.create_wallet(
@@ -38,6 +42,7 @@ impl Handler<CreateWalletRequest> for app::App {
                 params.seed_source,
                 params.name,
                 params.caption,
+                tenant,
             )
             .map(|wallet_id| CreateWalletResponse { wallet_id })
             .into_actor(slf)
@@ -59,15 +64,27 @@ struct Validated {
 /// To be valid it must pass these checks:
 /// - password is at least 8 characters
 /// - seed_sources has to be `mnemonics | xprv`
+///
+/// When importing mnemonics, their language is detected unless it is given explicitly.
+///
+/// When importing an xprv, it is decrypted with the backup password, which defaults to the wallet
+/// password.
 fn validate(req: CreateWalletRequest) -> Result<Validated, app::ValidationErrors> {
     let name = req.name;
     let caption = req.caption;
     let seed_data = req.seed_data;
+    let backup_password = match req.backup_password {
+        Some(backup_password) => backup_password,
+        None => req.password.clone(),
+    };
     let source = match req.seed_source.as_ref() {
-        "xprv" => Ok(types::SeedSource::Xprv),
-        "mnemonics" => Mnemonic::from_phrase(seed_data)
-            .map_err(|err| app::field_error("seed_data", format!("{}", err)))
-            .map(types::SeedSource::Mnemonics),
+        "xprv" => Ok(types::SeedSource::Xprv(seed_data, backup_password)),
+        "mnemonics" => match req.mnemonics_language {
+            Some(language) => Mnemonic::from_phrase_lang(seed_data, language),
+            None => Mnemonic::from_phrase_detect(seed_data),
+        }
+        .map_err(|err| app::field_error("seed_data", format!("{}", err)))
+        .map(types::SeedSource::Mnemonics),
         _ => Err(app::field_error(
             "seed_source",
             "Seed source has to be mnemonics|xprv.",
@@ -0,0 +1,27 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteContactRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    address: String,
+}
+
+impl Message for DeleteContactRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<DeleteContactRequest> for app::App {
+    type Result = app::ResponseActFuture<()>;
+
+    fn handle(&mut self, msg: DeleteContactRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.delete_contact(msg.session_id, msg.wallet_id, msg.address);
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,31 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteRecurringPaymentRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    id: u32,
+}
+
+impl Message for DeleteRecurringPaymentRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<DeleteRecurringPaymentRequest> for app::App {
+    type Result = app::ResponseActFuture<()>;
+
+    fn handle(
+        &mut self,
+        msg: DeleteRecurringPaymentRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self.delete_recurring_payment(msg.session_id, msg.wallet_id, msg.id);
+
+        Box::new(f)
+    }
+}
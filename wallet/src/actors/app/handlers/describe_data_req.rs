@@ -0,0 +1,103 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use witnet_rad::script::unpack_radon_script;
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeDataReqRequest {
+    dr_hash: String,
+}
+
+pub type DescribeDataReqResponse = model::DataRequestDescription;
+
+impl Message for DescribeDataReqRequest {
+    type Result = app::Result<DescribeDataReqResponse>;
+}
+
+impl Handler<DescribeDataReqRequest> for app::App {
+    type Result = app::ResponseActFuture<DescribeDataReqResponse>;
+
+    fn handle(&mut self, msg: DescribeDataReqRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = fut::result(validate(msg).map_err(app::validation_error))
+            .and_then(|dr_hash, slf: &mut Self, _| {
+                slf.get_data_request_transaction(dr_hash)
+                    .map(move |(block_hash, dr_txn), _, _| (dr_hash, block_hash, dr_txn))
+            })
+            .and_then(|(dr_hash, block_hash, dr_txn), _, _| {
+                fut::result(describe(dr_hash, block_hash, dr_txn.body.dr_output))
+            });
+
+        Box::new(f)
+    }
+}
+
+/// Validate `DescribeDataReqRequest`.
+///
+/// To be valid it must pass these checks:
+/// - drHash must be a valid hash
+fn validate(req: DescribeDataReqRequest) -> Result<types::Hash, app::ValidationErrors> {
+    req.dr_hash
+        .parse()
+        .map_err(|_| app::field_error("drHash", "Invalid data request hash"))
+}
+
+/// Decode the RAD scripts of a data request output into its human-readable description.
+fn describe(
+    dr_hash: types::Hash,
+    block_hash: types::Hash,
+    dr_output: types::DataRequestOutput,
+) -> app::Result<DescribeDataReqResponse> {
+    let request = dr_output.data_request;
+    let sources = request
+        .retrieve
+        .into_iter()
+        .map(|retrieve| {
+            Ok(model::RadSource {
+                kind: retrieve.kind,
+                url: retrieve.url,
+                script: describe_script(&retrieve.script)?,
+            })
+        })
+        .collect::<app::Result<_>>()?;
+
+    Ok(model::DataRequestDescription {
+        dr_hash: dr_hash.to_string(),
+        block_hash: block_hash.to_string(),
+        value: dr_output.value,
+        witnesses: dr_output.witnesses,
+        backup_witnesses: dr_output.backup_witnesses,
+        commit_fee: dr_output.commit_fee,
+        reveal_fee: dr_output.reveal_fee,
+        tally_fee: dr_output.tally_fee,
+        time_lock: dr_output.time_lock,
+        not_before: request.not_before,
+        sources,
+        aggregate: describe_script(&request.aggregate.script)?,
+        tally: describe_script(&request.consensus.script)?,
+    })
+}
+
+/// Decode a packed RADON script into the list of its calls.
+fn describe_script(script: &[u8]) -> app::Result<Vec<model::RadonCall>> {
+    unpack_radon_script(script)
+        .map_err(app::Error::Rad)?
+        .into_iter()
+        .map(|(operator, arguments)| {
+            let arguments = arguments
+                .unwrap_or_default()
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()
+                .map_err(app::internal_error)?;
+
+            Ok(model::RadonCall {
+                operator: operator.to_string(),
+                arguments,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,100 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportAddressReportRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    /// Whether to render the report as CSV too
+    #[serde(default)]
+    csv: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportAddressReportResponse {
+    addresses: Vec<model::AddressReport>,
+    /// The report rendered as CSV, if requested
+    csv: Option<String>,
+}
+
+impl Message for ExportAddressReportRequest {
+    type Result = app::Result<ExportAddressReportResponse>;
+}
+
+impl Handler<ExportAddressReportRequest> for app::App {
+    type Result = app::ResponseActFuture<ExportAddressReportResponse>;
+
+    fn handle(
+        &mut self,
+        msg: ExportAddressReportRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let ExportAddressReportRequest {
+            session_id,
+            wallet_id,
+            csv,
+        } = msg;
+        let epoch_constants = self.params.epoch_constants;
+
+        let f = self
+            .get_address_report(session_id, wallet_id)
+            .map(move |mut addresses, _, _| {
+                let timestamp = |epoch: Option<u32>| {
+                    epoch.and_then(|epoch| epoch_constants.epoch_timestamp(epoch).ok())
+                };
+                for address in &mut addresses {
+                    address.first_payment_at = timestamp(address.first_payment_epoch);
+                    address.last_payment_at = timestamp(address.last_payment_epoch);
+                }
+                let csv = if csv {
+                    Some(render_csv(&addresses))
+                } else {
+                    None
+                };
+
+                ExportAddressReportResponse { addresses, csv }
+            });
+
+        Box::new(f)
+    }
+}
+
+/// Render the report as CSV, with a header row and a row per address. Unknown values are left
+/// empty.
+fn render_csv(addresses: &[model::AddressReport]) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    let mut csv = "address,path,label,createdAt,firstPaymentEpoch,firstPaymentAt,\
+                   lastPaymentEpoch,lastPaymentAt,timesReceived,totalReceived\n"
+        .to_string();
+    for address in addresses {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            address.address,
+            address.path,
+            csv_field(address.label.as_ref().map_or("", String::as_str)),
+            optional(address.created_at.map(|x| x.to_string())),
+            optional(address.first_payment_epoch.map(|x| x.to_string())),
+            optional(address.first_payment_at.map(|x| x.to_string())),
+            optional(address.last_payment_epoch.map(|x| x.to_string())),
+            optional(address.last_payment_at.map(|x| x.to_string())),
+            address.times_received,
+            optional(address.total_received.map(|x| x.to_string())),
+        ));
+    }
+
+    csv
+}
+
+/// Quote a free-text field if it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
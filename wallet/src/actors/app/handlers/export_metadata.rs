@@ -0,0 +1,28 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportMetadataRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+pub type ExportMetadataResponse = model::SignedMetadata;
+
+impl Message for ExportMetadataRequest {
+    type Result = app::Result<ExportMetadataResponse>;
+}
+
+impl Handler<ExportMetadataRequest> for app::App {
+    type Result = app::ResponseActFuture<ExportMetadataResponse>;
+
+    fn handle(&mut self, msg: ExportMetadataRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.export_metadata(msg.session_id, msg.wallet_id);
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,64 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{constants, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportPlaintextRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    password: types::Password,
+    confirm_phrase: String,
+}
+
+impl Message for ExportPlaintextRequest {
+    type Result = app::Result<Vec<model::PlaintextRecord>>;
+}
+
+impl Handler<ExportPlaintextRequest> for app::App {
+    type Result = app::ResponseActFuture<Vec<model::PlaintextRecord>>;
+
+    fn handle(&mut self, msg: ExportPlaintextRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let ExportPlaintextRequest {
+            session_id,
+            wallet_id,
+            password,
+            confirm_phrase,
+        } = msg;
+        let validated =
+            validate(self.params.plaintext_export, &confirm_phrase).map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(move |(), slf: &mut Self, _ctx| {
+            slf.export_plaintext(session_id, wallet_id, password)
+        });
+
+        Box::new(f)
+    }
+}
+
+/// Validate `ExportPlaintextRequest`.
+///
+/// To be valid it must pass these checks:
+/// - plaintext exports are enabled in the wallet configuration
+/// - the confirmation phrase is exactly `PLAINTEXT_EXPORT_CONFIRM_PHRASE`
+fn validate(enabled: bool, confirm_phrase: &str) -> Result<(), app::ValidationErrors> {
+    if !enabled {
+        Err(app::field_error(
+            "walletId",
+            "Plaintext export is disabled, set `dev_plaintext_export = true` in the wallet \
+             configuration to enable it.",
+        ))
+    } else if confirm_phrase != constants::PLAINTEXT_EXPORT_CONFIRM_PHRASE {
+        Err(app::field_error(
+            "confirmPhrase",
+            format!(
+                "Type \"{}\" to confirm the export.",
+                constants::PLAINTEXT_EXPORT_CONFIRM_PHRASE
+            ),
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportXprvRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    /// Password of the wallet, checked again before exporting its keys
+    wallet_password: types::Password,
+    /// Backup password encrypting the exported key
+    password: types::Password,
+    #[serde(default)]
+    depth: types::XprvDepth,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportXprvResponse {
+    pub xprv: String,
+}
+
+impl Message for ExportXprvRequest {
+    type Result = app::Result<ExportXprvResponse>;
+}
+
+impl Handler<ExportXprvRequest> for app::App {
+    type Result = app::ResponseActFuture<ExportXprvResponse>;
+
+    fn handle(&mut self, msg: ExportXprvRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let ExportXprvRequest {
+            session_id,
+            wallet_id,
+            wallet_password,
+            password,
+            depth,
+        } = msg;
+        let validated = validate(password).map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(move |password, slf: &mut Self, _ctx| {
+            slf.export_xprv(session_id, wallet_id, wallet_password, depth, password)
+                .map(|xprv, _, _| ExportXprvResponse { xprv })
+        });
+
+        Box::new(f)
+    }
+}
+
+/// Validate the backup password of `ExportXprvRequest`.
+///
+/// To be valid it must pass these checks:
+/// - password is at least 8 characters
+fn validate(password: types::Password) -> Result<types::Password, app::ValidationErrors> {
+    if <str>::len(password.as_ref()) < 8 {
+        Err(app::field_error(
+            "password",
+            "Password must be at least 8 characters.",
+        ))
+    } else {
+        Ok(password)
+    }
+}
@@ -0,0 +1,34 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportXpubRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    account: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportXpubResponse {
+    pub xpub: String,
+}
+
+impl Message for ExportXpubRequest {
+    type Result = app::Result<ExportXpubResponse>;
+}
+
+impl Handler<ExportXpubRequest> for app::App {
+    type Result = app::ResponseActFuture<ExportXpubResponse>;
+
+    fn handle(&mut self, msg: ExportXpubRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self
+            .export_xpub(msg.session_id, msg.wallet_id, msg.account)
+            .map(|xpub, _, _| ExportXpubResponse { xpub });
+
+        Box::new(f)
+    }
+}
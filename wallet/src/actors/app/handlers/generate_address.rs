@@ -10,12 +10,17 @@ pub struct GenerateAddressRequest {
     session_id: types::SessionId,
     wallet_id: String,
     label: Option<String>,
+    /// Return the last generated address again if it has not received funds yet
+    #[serde(default)]
+    reuse: bool,
 }
 
 #[derive(Debug, Serialize)]
 pub struct GenerateAddressResponse {
     pub address: String,
     pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
 }
 
 impl Message for GenerateAddressRequest {
@@ -26,14 +31,50 @@ impl Handler<GenerateAddressRequest> for app::App {
     type Result = app::ResponseActFuture<GenerateAddressResponse>;
 
     fn handle(&mut self, msg: GenerateAddressRequest, _ctx: &mut Self::Context) -> Self::Result {
-        let f = self
-            .generate_address(msg.session_id, msg.wallet_id, msg.label)
-            .map(
-                |model::Address { address, path, .. }, _, _| GenerateAddressResponse {
+        let policy = self.params.address_reuse;
+        let validated = if msg.reuse && policy == types::AddressReusePolicy::Refuse {
+            Err(app::validation_error(app::field_error(
+                "reuse",
+                "The wallet policy requires a fresh address for every request.",
+            )))
+        } else {
+            Ok(msg)
+        };
+
+        let f = fut::result(validated)
+            .and_then(|msg, slf: &mut Self, _ctx| {
+                slf.generate_address(msg.session_id, msg.wallet_id, msg.label, msg.reuse)
+            })
+            .map(move |address, _, _| {
+                let model::Address {
+                    address,
+                    path,
+                    times_requested,
+                    ..
+                } = address;
+                let reused = times_requested > 1;
+                let warning = if reused && policy == types::AddressReusePolicy::Warn {
+                    log::warn!(
+                        "Address {} handed out {} times without receiving funds",
+                        address,
+                        times_requested
+                    );
+
+                    Some(
+                        "This address has already been handed out and has not received funds \
+                         yet. Payments to the same address can be linked to each other."
+                            .to_string(),
+                    )
+                } else {
+                    None
+                };
+
+                GenerateAddressResponse {
                     address,
                     path,
-                },
-            );
+                    warning,
+                }
+            });
 
         Box::new(f)
     }
@@ -0,0 +1,151 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateStatementRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    /// Month of the statement, as `YYYY-MM` in UTC
+    month: String,
+    /// Whether to render the statement as CSV too
+    #[serde(default)]
+    csv: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateStatementResponse {
+    month: String,
+    #[serde(flatten)]
+    statement: model::Statement,
+    /// The statement rendered as CSV, if requested
+    csv: Option<String>,
+}
+
+impl Message for GenerateStatementRequest {
+    type Result = app::Result<GenerateStatementResponse>;
+}
+
+impl Handler<GenerateStatementRequest> for app::App {
+    type Result = app::ResponseActFuture<GenerateStatementResponse>;
+
+    fn handle(&mut self, msg: GenerateStatementRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let GenerateStatementRequest {
+            session_id,
+            wallet_id,
+            month,
+            csv,
+        } = msg;
+        let validated =
+            validate(&month, self.params.epoch_constants).map_err(app::validation_error);
+
+        let f =
+            fut::result(validated).and_then(move |(from_epoch, to_epoch), slf: &mut Self, _ctx| {
+                slf.get_statement(session_id, wallet_id, from_epoch, to_epoch)
+                    .map(move |statement, _, _| {
+                        let csv = if csv {
+                            Some(render_csv(&month, &statement))
+                        } else {
+                            None
+                        };
+
+                        GenerateStatementResponse {
+                            month,
+                            statement,
+                            csv,
+                        }
+                    })
+            });
+
+        Box::new(f)
+    }
+}
+
+/// Validate the month of a `GenerateStatementRequest`, returning the range of epochs starting
+/// during that month.
+///
+/// To be valid it must pass these checks:
+/// - month has the format `YYYY-MM`
+fn validate(
+    month: &str,
+    epoch_constants: types::EpochConstants,
+) -> Result<(u32, u32), app::ValidationErrors> {
+    let (start, end) = month_timestamps(month)
+        .ok_or_else(|| app::field_error("month", "Month must have the format YYYY-MM."))?;
+    // First epoch starting at or after a timestamp
+    let first_epoch_from = |timestamp: i64| {
+        if timestamp <= epoch_constants.checkpoint_zero_timestamp {
+            0
+        } else {
+            epoch_constants
+                .epoch_at(timestamp - 1)
+                .map(|epoch| epoch.saturating_add(1))
+                .unwrap_or(0)
+        }
+    };
+
+    Ok((first_epoch_from(start), first_epoch_from(end)))
+}
+
+/// Timestamps of the start of a `YYYY-MM` month and the start of the following month.
+fn month_timestamps(month: &str) -> Option<(i64, i64)> {
+    let mut parts = month.splitn(2, '-');
+    let year_part = parts.next()?;
+    let month_part = parts.next()?;
+    let is_number = |part: &str, len| part.len() == len && part.chars().all(|c| c.is_ascii_digit());
+    if !is_number(year_part, 4) || !is_number(month_part, 2) {
+        return None;
+    }
+    let year = year_part.parse::<i64>().ok()?;
+    let month = month_part.parse::<i64>().ok()?;
+    if month < 1 || month > 12 {
+        return None;
+    }
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+
+    Some((
+        days_from_civil(year, month) * 86_400,
+        days_from_civil(next_year, next_month) * 86_400,
+    ))
+}
+
+/// Number of days from 1970-01-01 to the first day of the given month of the proleptic
+/// Gregorian calendar.
+fn days_from_civil(year: i64, month: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Render a statement as CSV, with a header row and a row of values.
+fn render_csv(month: &str, statement: &model::Statement) -> String {
+    format!(
+        "month,account,fromEpoch,toEpoch,openingBalance,closingBalance,totalIn,totalOut,\
+         feesPaid,dataRequestSpend,witnessRewards,movements\n\
+         {},{},{},{},{},{},{},{},{},{},{},{}\n",
+        month,
+        statement.account,
+        statement.from_epoch,
+        statement.to_epoch,
+        statement.opening_balance,
+        statement.closing_balance,
+        statement.total_in,
+        statement.total_out,
+        statement.fees_paid,
+        statement.data_request_spend,
+        statement.witness_rewards,
+        statement.movements,
+    )
+}
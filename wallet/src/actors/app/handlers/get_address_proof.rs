@@ -0,0 +1,53 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+/// Maximum length of the challenge of an address proof.
+const MAX_CHALLENGE_LENGTH: usize = 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetAddressProofRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    /// Index of the address in the current account
+    index: u32,
+    challenge: String,
+}
+
+pub type GetAddressProofResponse = model::AddressProof;
+
+impl Message for GetAddressProofRequest {
+    type Result = app::Result<GetAddressProofResponse>;
+}
+
+impl Handler<GetAddressProofRequest> for app::App {
+    type Result = app::ResponseActFuture<GetAddressProofResponse>;
+
+    fn handle(&mut self, msg: GetAddressProofRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let validated = validate(msg).map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(|msg, slf: &mut Self, _ctx| {
+            slf.get_address_proof(msg.session_id, msg.wallet_id, msg.index, msg.challenge)
+        });
+
+        Box::new(f)
+    }
+}
+
+/// Validate `GetAddressProofRequest`.
+///
+/// To be valid it must pass these checks:
+/// - challenge is not empty and has at most 1024 bytes
+fn validate(msg: GetAddressProofRequest) -> Result<GetAddressProofRequest, app::ValidationErrors> {
+    if msg.challenge.is_empty() || msg.challenge.len() > MAX_CHALLENGE_LENGTH {
+        Err(app::field_error(
+            "challenge",
+            "Challenge must have between 1 and 1024 bytes.",
+        ))
+    } else {
+        Ok(msg)
+    }
+}
@@ -0,0 +1,37 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBlockInfoRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    block_hash: String,
+}
+
+pub type GetBlockInfoResponse = model::BlockInfo;
+
+impl Message for GetBlockInfoRequest {
+    type Result = app::Result<GetBlockInfoResponse>;
+}
+
+impl Handler<GetBlockInfoRequest> for app::App {
+    type Result = app::ResponseActFuture<GetBlockInfoResponse>;
+
+    fn handle(&mut self, msg: GetBlockInfoRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = fut::result(
+            msg.block_hash
+                .parse()
+                .map_err(|_| app::field_error("blockHash", "Invalid block hash"))
+                .map_err(app::validation_error),
+        )
+        .and_then(move |block_hash, slf: &mut Self, _| {
+            slf.get_block_info(msg.session_id, msg.wallet_id, block_hash)
+        });
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,28 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetContactsRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+pub type GetContactsResponse = Vec<model::Contact>;
+
+impl Message for GetContactsRequest {
+    type Result = app::Result<GetContactsResponse>;
+}
+
+impl Handler<GetContactsRequest> for app::App {
+    type Result = app::ResponseActFuture<GetContactsResponse>;
+
+    fn handle(&mut self, msg: GetContactsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.get_contacts(msg.session_id, msg.wallet_id);
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,36 @@
+use std::cmp;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{constants, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetDepositsRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    limit: Option<u32>,
+}
+
+pub type GetDepositsResponse = model::Deposits;
+
+impl Message for GetDepositsRequest {
+    type Result = app::Result<GetDepositsResponse>;
+}
+
+impl Handler<GetDepositsRequest> for app::App {
+    type Result = app::ResponseActFuture<GetDepositsResponse>;
+
+    fn handle(&mut self, msg: GetDepositsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let limit = cmp::min(
+            msg.limit
+                .unwrap_or_else(|| constants::DEFAULT_PAGINATION_LIMIT),
+            constants::MAX_PAGINATION_LIMIT,
+        );
+        let f = self.get_deposits(msg.session_id, msg.wallet_id, limit);
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,32 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetNotificationsSinceRequest {
+    session_id: types::SessionId,
+    /// Sequence number of the last notification received by the client
+    #[serde(default)]
+    seq: u64,
+}
+
+pub type GetNotificationsSinceResponse = model::NotificationReplay;
+
+impl Message for GetNotificationsSinceRequest {
+    type Result = app::Result<GetNotificationsSinceResponse>;
+}
+
+impl Handler<GetNotificationsSinceRequest> for app::App {
+    type Result = <GetNotificationsSinceRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        msg: GetNotificationsSinceRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.get_notifications_since(&msg.session_id, msg.seq)
+    }
+}
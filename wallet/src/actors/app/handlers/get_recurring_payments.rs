@@ -0,0 +1,32 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetRecurringPaymentsRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+pub type GetRecurringPaymentsResponse = Vec<model::RecurringPayment>;
+
+impl Message for GetRecurringPaymentsRequest {
+    type Result = app::Result<GetRecurringPaymentsResponse>;
+}
+
+impl Handler<GetRecurringPaymentsRequest> for app::App {
+    type Result = app::ResponseActFuture<GetRecurringPaymentsResponse>;
+
+    fn handle(
+        &mut self,
+        msg: GetRecurringPaymentsRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self.get_recurring_payments(msg.session_id, msg.wallet_id);
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,32 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetScheduledPaymentsRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+pub type GetScheduledPaymentsResponse = Vec<model::ScheduledPayment>;
+
+impl Message for GetScheduledPaymentsRequest {
+    type Result = app::Result<GetScheduledPaymentsResponse>;
+}
+
+impl Handler<GetScheduledPaymentsRequest> for app::App {
+    type Result = app::ResponseActFuture<GetScheduledPaymentsResponse>;
+
+    fn handle(
+        &mut self,
+        msg: GetScheduledPaymentsRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self.get_scheduled_payments(msg.session_id, msg.wallet_id);
+
+        Box::new(f)
+    }
+}
@@ -13,6 +13,8 @@ pub struct GetTransactionsRequest {
     wallet_id: String,
     offset: Option<u32>,
     limit: Option<u32>,
+    /// Address of the wallet whose movements are listed
+    address: Option<String>,
 }
 
 pub type GetTransactionsResponse = model::Transactions;
@@ -25,15 +27,26 @@ impl Handler<GetTransactionsRequest> for app::App {
     type Result = app::ResponseActFuture<GetTransactionsResponse>;
 
     fn handle(&mut self, msg: GetTransactionsRequest, _ctx: &mut Self::Context) -> Self::Result {
-        let offset = msg
-            .offset
-            .unwrap_or_else(|| constants::DEFAULT_PAGINATION_OFFSET);
+        let GetTransactionsRequest {
+            session_id,
+            wallet_id,
+            offset,
+            limit,
+            address,
+        } = msg;
+        let offset = offset.unwrap_or_else(|| constants::DEFAULT_PAGINATION_OFFSET);
         let limit = cmp::min(
-            msg.offset
-                .unwrap_or_else(|| constants::DEFAULT_PAGINATION_LIMIT),
+            limit.unwrap_or_else(|| constants::DEFAULT_PAGINATION_LIMIT),
             constants::MAX_PAGINATION_LIMIT,
         );
-        let f = self.get_transactions(msg.session_id, msg.wallet_id, offset, limit);
+        let validated = address
+            .map(|address| app::validate_address("address", address, self.params.testnet))
+            .transpose()
+            .map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(move |address, slf: &mut Self, _ctx| {
+            slf.get_transactions(session_id, wallet_id, offset, limit, address)
+        });
 
         Box::new(f)
     }
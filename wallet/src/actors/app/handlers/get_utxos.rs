@@ -0,0 +1,28 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetUtxosRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+pub type GetUtxosResponse = Vec<model::Utxo>;
+
+impl Message for GetUtxosRequest {
+    type Result = app::Result<GetUtxosResponse>;
+}
+
+impl Handler<GetUtxosRequest> for app::App {
+    type Result = app::ResponseActFuture<GetUtxosResponse>;
+
+    fn handle(&mut self, msg: GetUtxosRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.get_utxos(msg.session_id, msg.wallet_id);
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,22 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::model;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetWalletMetricsRequest;
+
+pub type GetWalletMetricsResponse = model::WalletMetrics;
+
+impl Message for GetWalletMetricsRequest {
+    type Result = app::Result<GetWalletMetricsResponse>;
+}
+
+impl Handler<GetWalletMetricsRequest> for app::App {
+    type Result = <GetWalletMetricsRequest as Message>::Result;
+
+    fn handle(&mut self, _msg: GetWalletMetricsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(self.wallet_metrics())
+    }
+}
@@ -0,0 +1,30 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetWalletStorageStatsRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+pub type GetWalletStorageStatsResponse = model::StorageStats;
+
+impl Message for GetWalletStorageStatsRequest {
+    type Result = app::Result<GetWalletStorageStatsResponse>;
+}
+
+impl Handler<GetWalletStorageStatsRequest> for app::App {
+    type Result = app::ResponseActFuture<GetWalletStorageStatsResponse>;
+
+    fn handle(
+        &mut self,
+        msg: GetWalletStorageStatsRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.get_wallet_storage_stats(msg.session_id, msg.wallet_id)
+    }
+}
@@ -0,0 +1,32 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetWatchedAddressesRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+pub type GetWatchedAddressesResponse = Vec<model::WatchedAddress>;
+
+impl Message for GetWatchedAddressesRequest {
+    type Result = app::Result<GetWatchedAddressesResponse>;
+}
+
+impl Handler<GetWatchedAddressesRequest> for app::App {
+    type Result = app::ResponseActFuture<GetWatchedAddressesResponse>;
+
+    fn handle(
+        &mut self,
+        msg: GetWatchedAddressesRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self.get_watched_addresses(msg.session_id, msg.wallet_id);
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,29 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportMetadataRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    metadata: model::SignedMetadata,
+}
+
+pub type ImportMetadataResponse = model::MetadataImport;
+
+impl Message for ImportMetadataRequest {
+    type Result = app::Result<ImportMetadataResponse>;
+}
+
+impl Handler<ImportMetadataRequest> for app::App {
+    type Result = app::ResponseActFuture<ImportMetadataResponse>;
+
+    fn handle(&mut self, msg: ImportMetadataRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.import_metadata(msg.session_id, msg.wallet_id, msg.metadata);
+
+        Box::new(f)
+    }
+}
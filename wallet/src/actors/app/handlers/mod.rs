@@ -1,49 +1,125 @@
+mod ack_deposits;
 mod close_session;
+mod compact_wallet_db;
 mod create_data_req;
 mod create_mnemonics;
+mod create_payment_uri;
+mod create_recurring_payment;
 mod create_vtt;
 mod create_wallet;
+mod delete_contact;
+mod delete_recurring_payment;
+mod describe_data_req;
+mod export_address_report;
+mod export_metadata;
+mod export_plaintext;
+mod export_xprv;
+mod export_xpub;
 mod forward;
 mod generate_address;
+mod generate_statement;
 mod get;
+mod get_address_proof;
 mod get_addresses;
+mod get_block_info;
+mod get_contacts;
+mod get_deposits;
+mod get_notifications_since;
+mod get_recurring_payments;
+mod get_scheduled_payments;
 mod get_transactions;
+mod get_utxos;
 mod get_wallet_infos;
+mod get_wallet_metrics;
+mod get_wallet_storage_stats;
+mod get_watched_addresses;
+mod import_metadata;
 mod import_seed;
 mod lock_wallet;
 mod next_subscription_id;
 mod node_notification;
+mod parse_payment_uri;
+mod recover_wallet;
+mod register_deposit_addresses;
+mod register_signer;
+mod reject_signature_request;
+mod reset_chain_data;
 mod run_rad_req;
+mod save_contact;
+mod search_movements;
 mod send_data_req;
 mod send_transaction;
 mod send_vtt;
 mod set;
+mod sign_transaction;
 mod stop;
+mod submit_signatures;
 mod subscribe;
 mod unlock_wallet;
 mod unsubscribe;
+mod unwatch_address;
+mod validate_mnemonics;
+mod watch_address;
 
+pub use ack_deposits::*;
 pub use close_session::*;
+pub use compact_wallet_db::*;
 pub use create_data_req::*;
 pub use create_mnemonics::*;
+pub use create_payment_uri::*;
+pub use create_recurring_payment::*;
 pub use create_vtt::*;
 pub use create_wallet::*;
+pub use delete_contact::*;
+pub use delete_recurring_payment::*;
+pub use describe_data_req::*;
+pub use export_address_report::*;
+pub use export_metadata::*;
+pub use export_plaintext::*;
+pub use export_xprv::*;
+pub use export_xpub::*;
 pub use forward::*;
 pub use generate_address::*;
+pub use generate_statement::*;
 pub use get::*;
+pub use get_address_proof::*;
 pub use get_addresses::*;
+pub use get_block_info::*;
+pub use get_contacts::*;
+pub use get_deposits::*;
+pub use get_notifications_since::*;
+pub use get_recurring_payments::*;
+pub use get_scheduled_payments::*;
 pub use get_transactions::*;
+pub use get_utxos::*;
 pub use get_wallet_infos::*;
+pub use get_wallet_metrics::*;
+pub use get_wallet_storage_stats::*;
+pub use get_watched_addresses::*;
+pub use import_metadata::*;
 pub use import_seed::*;
 pub use lock_wallet::*;
 pub use next_subscription_id::*;
 pub use node_notification::*;
+pub use parse_payment_uri::*;
+pub use recover_wallet::*;
+pub use register_deposit_addresses::*;
+pub use register_signer::*;
+pub use reject_signature_request::*;
+pub use reset_chain_data::*;
 pub use run_rad_req::*;
+pub use save_contact::*;
+pub use search_movements::*;
 pub use send_data_req::*;
 pub use send_transaction::*;
 pub use send_vtt::*;
 pub use set::*;
+pub use sign_transaction::*;
 pub use stop::*;
+pub use submit_signatures::*;
 pub use subscribe::*;
 pub use unlock_wallet::*;
 pub use unsubscribe::*;
+pub use unwatch_address::*;
+pub use validate_mnemonics::*;
+pub use watch_address::*;
@@ -10,10 +10,12 @@ impl Handler<jsonrpc::Notification> for app::App {
     fn handle(
         &mut self,
         jsonrpc::Notification(value): jsonrpc::Notification,
-        _ctx: &mut Self::Context,
+        ctx: &mut Self::Context,
     ) -> Self::Result {
         match self.handle_block_notification(value) {
-            Ok(()) => (),
+            // Index the blocks that were not notified while the wallet was stalled
+            Ok(true) => self.sync_wallets(ctx),
+            Ok(false) => (),
             Err(err) => log::warn!("Could not index block: {}", err),
         }
     }
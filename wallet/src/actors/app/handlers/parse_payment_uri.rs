@@ -0,0 +1,44 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, uri};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParsePaymentUriRequest {
+    uri: String,
+}
+
+pub type ParsePaymentUriResponse = model::PaymentRequest;
+
+impl Message for ParsePaymentUriRequest {
+    type Result = app::Result<ParsePaymentUriResponse>;
+}
+
+impl Handler<ParsePaymentUriRequest> for app::App {
+    type Result = <ParsePaymentUriRequest as Message>::Result;
+
+    fn handle(&mut self, msg: ParsePaymentUriRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let testnet = self.params.testnet;
+        let request = uri::decode(&msg.uri)
+            .map_err(|err| app::field_error("uri", err))
+            .and_then(|request| {
+                let model::PaymentRequest {
+                    address,
+                    amount,
+                    message,
+                } = request;
+
+                app::validate_address("uri", address, testnet).map(|address| {
+                    model::PaymentRequest {
+                        address,
+                        amount,
+                        message,
+                    }
+                })
+            })
+            .map_err(app::validation_error)?;
+
+        Ok(request)
+    }
+}
@@ -0,0 +1,36 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverWalletRequest {
+    wallet_id: String,
+    password: types::Password,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverWalletResponse {
+    pub wallet_id: String,
+}
+
+impl Message for RecoverWalletRequest {
+    type Result = app::Result<RecoverWalletResponse>;
+}
+
+impl Handler<RecoverWalletRequest> for app::App {
+    type Result = app::ResponseActFuture<RecoverWalletResponse>;
+
+    fn handle(&mut self, req: RecoverWalletRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let wallet_id = req.wallet_id.clone();
+        let f = self
+            .recover_wallet(req.wallet_id, req.password)
+            .map(|()| RecoverWalletResponse { wallet_id })
+            .into_actor(self);
+
+        Box::new(f)
+    }
+}
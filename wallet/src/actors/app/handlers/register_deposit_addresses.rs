@@ -0,0 +1,83 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{constants, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterDepositAddressesRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    addresses: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterDepositAddressesResponse {
+    /// Number of addresses that were not registered yet
+    pub registered: u32,
+}
+
+impl Message for RegisterDepositAddressesRequest {
+    type Result = app::Result<RegisterDepositAddressesResponse>;
+}
+
+impl Handler<RegisterDepositAddressesRequest> for app::App {
+    type Result = app::ResponseActFuture<RegisterDepositAddressesResponse>;
+
+    fn handle(
+        &mut self,
+        msg: RegisterDepositAddressesRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let RegisterDepositAddressesRequest {
+            session_id,
+            wallet_id,
+            addresses,
+        } = msg;
+        let validated =
+            validate_addresses(addresses, self.params.testnet).map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(move |addresses, slf: &mut Self, _ctx| {
+            slf.register_deposit_addresses(session_id, wallet_id, addresses)
+                .map(|registered, _, _| RegisterDepositAddressesResponse { registered })
+        });
+
+        Box::new(f)
+    }
+}
+
+/// Validate every address of the request, reporting all the invalid ones at once.
+fn validate_addresses(
+    addresses: Vec<String>,
+    testnet: bool,
+) -> Result<Vec<String>, app::ValidationErrors> {
+    if addresses.len() > constants::MAX_DEPOSIT_ADDRESSES_PER_REQUEST {
+        return Err(app::field_error(
+            "addresses",
+            format!(
+                "At most {} addresses can be registered at once.",
+                constants::MAX_DEPOSIT_ADDRESSES_PER_REQUEST
+            ),
+        ));
+    }
+
+    let mut errors = vec![];
+    let mut validated = Vec::with_capacity(addresses.len());
+    for (index, address) in addresses.into_iter().enumerate() {
+        match app::validate_address("addresses", address, testnet) {
+            Ok(address) => validated.push(address),
+            Err(err) => errors.extend(
+                err.into_iter()
+                    .map(|(field, msg)| (field, format!("Address {}: {}", index, msg))),
+            ),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(validated)
+    } else {
+        Err(errors)
+    }
+}
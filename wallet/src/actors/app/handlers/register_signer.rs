@@ -0,0 +1,24 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterSignerRequest {
+    session_id: types::SessionId,
+    device: types::SignerDevice,
+}
+
+impl Message for RegisterSignerRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<RegisterSignerRequest> for app::App {
+    type Result = <RegisterSignerRequest as Message>::Result;
+
+    fn handle(&mut self, msg: RegisterSignerRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.register_signer(msg.session_id, msg.device)
+    }
+}
@@ -0,0 +1,29 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectSignatureRequestRequest {
+    session_id: types::SessionId,
+    request_id: u32,
+    reason: String,
+}
+
+impl Message for RejectSignatureRequestRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<RejectSignatureRequestRequest> for app::App {
+    type Result = <RejectSignatureRequestRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        msg: RejectSignatureRequestRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.reject_signature_request(msg.session_id, msg.request_id, msg.reason)
+    }
+}
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetChainDataRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+}
+
+impl Message for ResetChainDataRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<ResetChainDataRequest> for app::App {
+    type Result = app::ResponseActFuture<()>;
+
+    fn handle(&mut self, msg: ResetChainDataRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.reset_chain_data(msg.session_id, msg.wallet_id);
+
+        Box::new(f)
+    }
+}
@@ -11,8 +11,14 @@ pub struct RunRadReqRequest {
 }
 
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct RunRadReqResponse {
     pub result: types::RadonTypes,
+    /// Result encoded as canonical JSON, see `json_format` for its version. Missing if the result
+    /// has no canonical JSON encoding, i.e. it contains a map with a key that is not text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_json: Option<String>,
+    pub json_format: u8,
 }
 
 impl Message for RunRadReqRequest {
@@ -23,10 +29,15 @@ impl Handler<RunRadReqRequest> for app::App {
     type Result = app::ResponseFuture<RunRadReqResponse>;
 
     fn handle(&mut self, msg: RunRadReqRequest, _ctx: &mut Self::Context) -> Self::Result {
-        let f = self
-            .run_rad_request(msg.rad_request)
-            .map_err(app::internal_error)
-            .map(|result| RunRadReqResponse { result });
+        let f = self.run_rad_request(msg.rad_request).map(|result| {
+            let result_json = result.to_canonical_json(types::JsonFormat::LATEST).ok();
+
+            RunRadReqResponse {
+                result,
+                result_json,
+                json_format: types::JsonFormat::LATEST.version(),
+            }
+        });
 
         Box::new(f)
     }
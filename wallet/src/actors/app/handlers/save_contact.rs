@@ -0,0 +1,66 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveContactRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    address: String,
+    name: String,
+    notes: Option<String>,
+}
+
+impl Message for SaveContactRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<SaveContactRequest> for app::App {
+    type Result = app::ResponseActFuture<()>;
+
+    fn handle(&mut self, msg: SaveContactRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let SaveContactRequest {
+            session_id,
+            wallet_id,
+            address,
+            name,
+            notes,
+        } = msg;
+        let validated =
+            validate(address, name, notes, self.params.testnet).map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(move |contact, slf: &mut Self, _ctx| {
+            slf.save_contact(session_id, wallet_id, contact)
+        });
+
+        Box::new(f)
+    }
+}
+
+/// Validate `SaveContactRequest`.
+///
+/// To be valid it must pass these checks:
+/// - address is a valid address of the current environment
+/// - name is not empty
+fn validate(
+    address: String,
+    name: String,
+    notes: Option<String>,
+    testnet: bool,
+) -> Result<model::Contact, app::ValidationErrors> {
+    let address = app::validate_address("address", address, testnet);
+    let name = if name.trim().is_empty() {
+        Err(app::field_error("name", "Contact name cannot be empty."))
+    } else {
+        Ok(name)
+    };
+
+    app::combine_field_errors(address, name, move |address, name| model::Contact {
+        address,
+        name,
+        notes,
+    })
+}
@@ -0,0 +1,75 @@
+use std::cmp;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{constants, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMovementsRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    #[serde(flatten)]
+    filter: model::MovementFilter,
+    offset: Option<u32>,
+    limit: Option<u32>,
+}
+
+pub type SearchMovementsResponse = model::Movements;
+
+impl Message for SearchMovementsRequest {
+    type Result = app::Result<SearchMovementsResponse>;
+}
+
+impl Handler<SearchMovementsRequest> for app::App {
+    type Result = app::ResponseActFuture<SearchMovementsResponse>;
+
+    fn handle(&mut self, msg: SearchMovementsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let SearchMovementsRequest {
+            session_id,
+            wallet_id,
+            filter,
+            offset,
+            limit,
+        } = msg;
+        let offset = offset.unwrap_or_else(|| constants::DEFAULT_PAGINATION_OFFSET);
+        let limit = cmp::min(
+            limit.unwrap_or_else(|| constants::DEFAULT_PAGINATION_LIMIT),
+            constants::MAX_PAGINATION_LIMIT,
+        );
+        let validated = validate(filter, self.params.testnet).map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(move |filter, slf: &mut Self, _ctx| {
+            slf.search_movements(session_id, wallet_id, filter, offset, limit)
+        });
+
+        Box::new(f)
+    }
+}
+
+/// Validate the filter of a `SearchMovementsRequest`.
+///
+/// To be valid it must pass these checks:
+/// - address, if any, is a valid address of the network of the wallet
+/// - fromEpoch, if any, is lower than toEpoch
+fn validate(
+    filter: model::MovementFilter,
+    testnet: bool,
+) -> Result<model::MovementFilter, app::ValidationErrors> {
+    let address = filter
+        .address
+        .map(|address| app::validate_address("address", address, testnet))
+        .transpose()?;
+    if let (Some(from_epoch), Some(to_epoch)) = (filter.from_epoch, filter.to_epoch) {
+        if from_epoch >= to_epoch {
+            return Err(app::field_error(
+                "fromEpoch",
+                "From epoch must be lower than to epoch.",
+            ));
+        }
+    }
+
+    Ok(model::MovementFilter { address, ..filter })
+}
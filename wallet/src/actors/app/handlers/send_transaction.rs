@@ -2,21 +2,55 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
+use crate::types::{self, Hashable as _};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SendTransactionRequest {
-    transaction_id: String,
+    session_id: types::SessionId,
+    wallet_id: String,
+    /// Transaction created with `createVttRequest`
+    transaction: types::VTTransaction,
+    /// Client-generated id, so retrying the request does not send the transaction twice
+    idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendTransactionResponse {
+    pub transaction_id: String,
 }
 
 impl Message for SendTransactionRequest {
-    type Result = app::Result<()>;
+    type Result = app::Result<SendTransactionResponse>;
 }
 
 impl Handler<SendTransactionRequest> for app::App {
-    type Result = <SendTransactionRequest as Message>::Result;
+    type Result = app::ResponseActFuture<SendTransactionResponse>;
+
+    fn handle(&mut self, msg: SendTransactionRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let params = msg.transaction.hash().to_string();
+        let SendTransactionRequest {
+            session_id,
+            wallet_id,
+            transaction,
+            idempotency_key,
+        } = msg;
+
+        self.idempotent(
+            "sendTransaction",
+            &wallet_id.clone(),
+            idempotency_key,
+            &params,
+            move |slf| {
+                let f = slf
+                    .send_transaction(session_id, wallet_id, transaction)
+                    .map(|hash, _, _| SendTransactionResponse {
+                        transaction_id: hash.to_string(),
+                    });
 
-    fn handle(&mut self, _msg: SendTransactionRequest, _ctx: &mut Self::Context) -> Self::Result {
-        Ok(())
+                Box::new(f)
+            },
+        )
     }
 }
@@ -2,24 +2,73 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
+use crate::types;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SendVttRequest {
+    pub session_id: types::SessionId,
     pub wallet_id: String,
-    pub to_address: Vec<u8>,
+    /// Address paid by the transaction
+    pub address: String,
     pub amount: u64,
     pub fee: u64,
-    pub subject: String,
+    /// Address paid the change, the one chosen by the `change_address` param of the wallet if
+    /// not given
+    pub change_address: Option<types::ChangeAddressPolicy>,
+    /// Client-generated id, so retrying the request does not send the transaction twice
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendVttResponse {
+    pub transaction_id: String,
 }
 
 impl Message for SendVttRequest {
-    type Result = app::Result<()>;
+    type Result = app::Result<SendVttResponse>;
 }
 
 impl Handler<SendVttRequest> for app::App {
-    type Result = <SendVttRequest as Message>::Result;
+    type Result = app::ResponseActFuture<SendVttResponse>;
+
+    fn handle(&mut self, msg: SendVttRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let params = (msg.address.clone(), msg.amount, msg.fee, msg.change_address);
+        let SendVttRequest {
+            session_id,
+            wallet_id,
+            address,
+            amount,
+            fee,
+            change_address,
+            idempotency_key,
+        } = msg;
+
+        self.idempotent(
+            "sendVTT",
+            &wallet_id.clone(),
+            idempotency_key,
+            &params,
+            move |slf| {
+                let f = slf
+                    .create_vtt(
+                        session_id.clone(),
+                        wallet_id.clone(),
+                        address,
+                        amount.into(),
+                        fee.into(),
+                        change_address,
+                    )
+                    .and_then(move |transaction, slf: &mut app::App, _| {
+                        slf.send_transaction(session_id, wallet_id, transaction)
+                    })
+                    .map(|hash, _, _| SendVttResponse {
+                        transaction_id: hash.to_string(),
+                    });
 
-    fn handle(&mut self, _msg: SendVttRequest, _ctx: &mut Self::Context) -> Self::Result {
-        Ok(())
+                Box::new(f)
+            },
+        )
     }
 }
@@ -0,0 +1,65 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types::{self, Hashable as _};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignTransactionRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    transaction: UnsignedTransaction,
+}
+
+/// Transaction spending outputs of the wallet, whose inputs have not been signed yet.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnsignedTransaction {
+    ValueTransfer(types::VTTransactionBody),
+    DataRequest(types::DRTransactionBody),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignTransactionResponse {
+    ValueTransfer(types::VTTransaction),
+    DataRequest(types::DRTransaction),
+}
+
+impl Message for SignTransactionRequest {
+    type Result = app::Result<SignTransactionResponse>;
+}
+
+impl Handler<SignTransactionRequest> for app::App {
+    type Result = app::ResponseActFuture<SignTransactionResponse>;
+
+    fn handle(&mut self, msg: SignTransactionRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let SignTransactionRequest {
+            session_id,
+            wallet_id,
+            transaction,
+        } = msg;
+        let (kind, hash, inputs) = match &transaction {
+            UnsignedTransaction::ValueTransfer(body) => {
+                ("valueTransfer", body.hash(), body.inputs.clone())
+            }
+            UnsignedTransaction::DataRequest(body) => {
+                ("dataRequest", body.hash(), body.inputs.clone())
+            }
+        };
+
+        let f = self
+            .request_signatures(session_id, wallet_id, kind, hash, inputs)
+            .map(move |signatures, _, _| match transaction {
+                UnsignedTransaction::ValueTransfer(body) => SignTransactionResponse::ValueTransfer(
+                    types::VTTransaction::new(body, signatures),
+                ),
+                UnsignedTransaction::DataRequest(body) => SignTransactionResponse::DataRequest(
+                    types::DRTransaction::new(body, signatures),
+                ),
+            });
+
+        Box::new(f)
+    }
+}
@@ -0,0 +1,25 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitSignaturesRequest {
+    session_id: types::SessionId,
+    request_id: u32,
+    signatures: Vec<model::ExternalSignature>,
+}
+
+impl Message for SubmitSignaturesRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<SubmitSignaturesRequest> for app::App {
+    type Result = <SubmitSignaturesRequest as Message>::Result;
+
+    fn handle(&mut self, msg: SubmitSignaturesRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.submit_signatures(msg.session_id, msg.request_id, msg.signatures)
+    }
+}
@@ -19,7 +19,7 @@ pub struct UnlockWalletResponse {
     caption: Option<String>,
     available_accounts: Vec<u32>,
     current_account: u32,
-    account_balance: u64,
+    account_balance: types::Nanowits,
     session_expiration_secs: u64,
 }
 
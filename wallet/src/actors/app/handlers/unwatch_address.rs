@@ -0,0 +1,27 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnwatchAddressRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    address: String,
+}
+
+impl Message for UnwatchAddressRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<UnwatchAddressRequest> for app::App {
+    type Result = app::ResponseActFuture<()>;
+
+    fn handle(&mut self, msg: UnwatchAddressRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.unwatch_address(msg.session_id, msg.wallet_id, msg.address);
+
+        Box::new(f)
+    }
+}
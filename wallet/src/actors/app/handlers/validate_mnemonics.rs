@@ -0,0 +1,47 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateMnemonicsRequest {
+    mnemonics: types::Password,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateMnemonicsResponse {
+    /// Whether the mnemonics are valid, i.e. their language was detected and their checksum is
+    /// correct
+    pub valid: bool,
+    /// Detected language of the mnemonics words
+    pub language: Option<types::MnemonicLang>,
+    /// Whether the checksum of the mnemonics is correct
+    pub valid_checksum: bool,
+}
+
+impl Message for ValidateMnemonicsRequest {
+    type Result = app::Result<ValidateMnemonicsResponse>;
+}
+
+impl Handler<ValidateMnemonicsRequest> for app::App {
+    type Result = <ValidateMnemonicsRequest as Message>::Result;
+
+    fn handle(&mut self, msg: ValidateMnemonicsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let response = match types::Mnemonic::detect_lang(msg.mnemonics.as_ref()) {
+            Some((language, valid_checksum)) => ValidateMnemonicsResponse {
+                valid: valid_checksum,
+                language: Some(language),
+                valid_checksum,
+            },
+            None => ValidateMnemonicsResponse {
+                valid: false,
+                language: None,
+                valid_checksum: false,
+            },
+        };
+
+        Ok(response)
+    }
+}
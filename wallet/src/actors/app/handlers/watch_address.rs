@@ -0,0 +1,41 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchAddressRequest {
+    session_id: types::SessionId,
+    wallet_id: String,
+    address: String,
+    label: Option<String>,
+}
+
+pub type WatchAddressResponse = model::WatchedAddress;
+
+impl Message for WatchAddressRequest {
+    type Result = app::Result<WatchAddressResponse>;
+}
+
+impl Handler<WatchAddressRequest> for app::App {
+    type Result = app::ResponseActFuture<WatchAddressResponse>;
+
+    fn handle(&mut self, msg: WatchAddressRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let WatchAddressRequest {
+            session_id,
+            wallet_id,
+            address,
+            label,
+        } = msg;
+        let validated = app::validate_address("address", address, self.params.testnet)
+            .map_err(app::validation_error);
+
+        let f = fut::result(validated).and_then(move |address, slf: &mut Self, _ctx| {
+            slf.watch_address(session_id, wallet_id, address, label)
+        });
+
+        Box::new(f)
+    }
+}
@@ -1,9 +1,15 @@
+use std::time::Duration;
+
 use actix::utils::TimerFunc;
-use futures::future;
+use futures::{future, sync::oneshot};
+use serde_json::json;
 
 use super::*;
 use crate::actors::*;
-use crate::model;
+use crate::types::Hashable as _;
+use crate::{constants, journal, model};
+use witnet_net::client::tcp::{jsonrpc, JsonRpcClient};
+use witnet_util::correlation::{self, CorrelationId};
 
 impl App {
     pub fn start(params: Params) -> Addr<Self> {
@@ -15,6 +21,11 @@ impl App {
         actor.start()
     }
 
+    /// Timing metrics of the wallet repository, shared by all the wallets.
+    pub fn wallet_metrics(&self) -> model::WalletMetrics {
+        self.params.metrics.snapshot()
+    }
+
     /// Return a new subscription id for a session.
     pub fn next_subscription_id(
         &mut self,
@@ -37,7 +48,29 @@ impl App {
         _subscription_id: types::SubscriptionId,
         sink: types::Sink,
     ) -> Result<()> {
-        self.state.subscribe(&session_id, sink)
+        let correlation_id = correlation::current();
+        self.state.subscribe(&session_id, sink)?;
+        let notifier = self.state.session_notifier(&session_id)?;
+
+        // Wallets that finished loading before the subscription are notified now
+        for (wallet_id, wallet) in self.state.session_wallets(&session_id) {
+            self.params.worker.do_send(Traced {
+                correlation_id,
+                message: worker::NotifyWalletReady(wallet_id, wallet, notifier.clone()),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Get the notifications of a session after the one with sequence number `seq`, so a client
+    /// that reconnects can replay the ones it missed.
+    pub fn get_notifications_since(
+        &self,
+        session_id: &types::SessionId,
+        seq: u64,
+    ) -> Result<model::NotificationReplay> {
+        self.state.notifications_since(session_id, seq)
     }
 
     /// Remove a subscription.
@@ -53,12 +86,17 @@ impl App {
         session_id: types::SessionId,
         wallet_id: String,
         label: Option<String>,
+        reuse: bool,
     ) -> ResponseActFuture<model::Address> {
+        let correlation_id = correlation::current();
         let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
             move |wallet, slf: &mut Self, _| {
                 slf.params
                     .worker
-                    .send(worker::GenAddress(wallet, label))
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GenAddress(wallet, label, reuse),
+                    })
                     .flatten()
                     .map_err(From::from)
                     .into_actor(slf)
@@ -76,11 +114,490 @@ impl App {
         offset: u32,
         limit: u32,
     ) -> ResponseActFuture<model::Addresses> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetAddresses(wallet, offset, limit),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Export the master key or the current account key of a wallet as a bech32 `xprv` string,
+    /// encrypted with the given backup password.
+    pub fn export_xprv(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        wallet_password: types::Password,
+        depth: types::XprvDepth,
+        password: types::Password,
+    ) -> ResponseActFuture<String> {
+        let correlation_id = correlation::current();
+        let tenant = current_tenant();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::ExportXprv(
+                            wallet,
+                            wallet_id,
+                            wallet_password,
+                            depth,
+                            password,
+                            tenant,
+                        ),
+                    })
+                    .flatten()
+                    .map_err(|err| match err {
+                        worker::Error::WrongPassword => {
+                            validation_error(field_error("walletPassword", "Wrong password"))
+                        }
+                        err => From::from(err),
+                    })
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Export the extended public key of an account of a wallet as a bech32 `xpub` string.
+    pub fn export_xpub(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        account: u32,
+    ) -> ResponseActFuture<String> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::ExportXpub(wallet, account),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Prove that a wallet controls an address of its current account by signing a challenge.
+    pub fn get_address_proof(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        index: u32,
+        challenge: String,
+    ) -> ResponseActFuture<model::AddressProof> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetAddressProof(wallet, index, challenge),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get the provenance of every address generated by a wallet.
+    pub fn get_address_report(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<Vec<model::AddressReport>> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetAddressReport(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Decrypt every record of the database of a wallet, for debugging and migration tools.
+    pub fn export_plaintext(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        password: types::Password,
+    ) -> ResponseActFuture<Vec<model::PlaintextRecord>> {
+        let correlation_id = correlation::current();
+        let tenant = current_tenant();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |_wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::ExportPlaintext(wallet_id, password, tenant),
+                    })
+                    .flatten()
+                    .map_err(|err| match err {
+                        worker::Error::WrongPassword => {
+                            validation_error(field_error("password", "Wrong password"))
+                        }
+                        err => From::from(err),
+                    })
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get a list of transactions associated to a wallet account.
+    pub fn get_transactions(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        offset: u32,
+        limit: u32,
+        address: Option<String>,
+    ) -> ResponseActFuture<model::Transactions> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetTransactions(wallet, offset, limit, address),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Search the movements of a wallet matching a filter.
+    pub fn search_movements(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        filter: model::MovementFilter,
+        offset: u32,
+        limit: u32,
+    ) -> ResponseActFuture<model::Movements> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::SearchMovements(wallet, filter, offset, limit),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get the aggregate of the movements of the current account of a wallet indexed from blocks
+    /// of epochs in `[from_epoch, to_epoch)`.
+    pub fn get_statement(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        from_epoch: u32,
+        to_epoch: u32,
+    ) -> ResponseActFuture<model::Statement> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetStatement(wallet, from_epoch, to_epoch),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get the contacts saved in the address book of a wallet.
+    pub fn get_contacts(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<Vec<model::Contact>> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetContacts(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Export the signed metadata of a wallet: address labels, contacts and watched addresses.
+    pub fn export_metadata(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<model::SignedMetadata> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::ExportMetadata(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Import the signed metadata exported by a wallet created from the same seed.
+    pub fn import_metadata(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        metadata: model::SignedMetadata,
+    ) -> ResponseActFuture<model::MetadataImport> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::ImportMetadata(wallet, metadata),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Save a contact in the address book of a wallet, replacing any contact with the same
+    /// address.
+    pub fn save_contact(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        contact: model::Contact,
+    ) -> ResponseActFuture<()> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::SaveContact(wallet, contact),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Remove a contact from the address book of a wallet.
+    pub fn delete_contact(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        address: String,
+    ) -> ResponseActFuture<()> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::DeleteContact(wallet, address),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get the addresses watched by a wallet.
+    pub fn get_watched_addresses(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<Vec<model::WatchedAddress>> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetWatchedAddresses(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get the unspent outputs of the current account of a wallet.
+    pub fn get_utxos(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<Vec<model::Utxo>> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetUtxos(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Register addresses whose payments are reported as deposits of a wallet.
+    pub fn register_deposit_addresses(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        addresses: Vec<String>,
+    ) -> ResponseActFuture<u32> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::RegisterDepositAddresses(wallet, addresses),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get the deposits of a wallet that have not been acknowledged yet.
+    pub fn get_deposits(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        limit: u32,
+    ) -> ResponseActFuture<model::Deposits> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetDeposits(wallet, limit),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Acknowledge the deposits of a wallet up to a sequence number.
+    pub fn ack_deposits(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        cursor: u64,
+    ) -> ResponseActFuture<u64> {
+        let correlation_id = correlation::current();
         let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
             move |wallet, slf: &mut Self, _| {
                 slf.params
                     .worker
-                    .send(worker::GetAddresses(wallet, offset, limit))
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::AckDeposits(wallet, cursor),
+                    })
                     .flatten()
                     .map_err(From::from)
                     .into_actor(slf)
@@ -90,34 +607,381 @@ impl App {
         Box::new(f)
     }
 
-    /// Get a list of transactions associated to a wallet account.
-    pub fn get_transactions(
+    /// Count the records stored by a wallet and the bytes they take, by key prefix.
+    pub fn get_wallet_storage_stats(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<model::StorageStats> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetStorageStats(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Delete the records of a wallet superseded by its current state and reclaim the space they
+    /// took in the database.
+    pub fn compact_wallet_db(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<model::StorageCompaction> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::CompactDb(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Make a wallet follow the movements of an address outside of its keychains.
+    pub fn watch_address(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        address: String,
+        label: Option<String>,
+    ) -> ResponseActFuture<model::WatchedAddress> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::WatchAddress(wallet, address, label),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Stop following the movements of a watched address.
+    pub fn unwatch_address(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        address: String,
+    ) -> ResponseActFuture<()> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::UnwatchAddress(wallet, address),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Create a recurring payment for a wallet.
+    pub fn create_recurring_payment(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        payment: model::RecurringPayment,
+    ) -> ResponseActFuture<model::RecurringPayment> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::AddRecurringPayment(wallet, payment),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get the recurring payments of a wallet.
+    pub fn get_recurring_payments(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<Vec<model::RecurringPayment>> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetRecurringPayments(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Remove a recurring payment of a wallet.
+    pub fn delete_recurring_payment(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        id: u32,
+    ) -> ResponseActFuture<()> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::DeleteRecurringPayment(wallet, id),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Get the payments generated by the recurring payments of a wallet.
+    pub fn get_scheduled_payments(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<Vec<model::ScheduledPayment>> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetScheduledPayments(wallet),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Register the hardware-wallet bridge connected through a session as the external signer of
+    /// the wallets unlocked by the session. Signature requests are sent to the bridge through the
+    /// notifications subscription of the session.
+    pub fn register_signer(
+        &mut self,
+        session_id: types::SessionId,
+        device: types::SignerDevice,
+    ) -> Result<()> {
+        self.state.register_signer(&session_id, device)
+    }
+
+    /// Forward a request to sign the inputs of a transaction to the external signer of a session,
+    /// including the derivation path of the key that can spend each input.
+    ///
+    /// The returned signatures are ordered by input index. The request fails if the signer rejects
+    /// it or does not respond in `SIGNATURE_REQUEST_TIMEOUT_SECS` seconds.
+    ///
+    /// The outputs spent by the inputs are reserved while the request is pending and, if it
+    /// succeeds, until the transaction is indexed. The request fails if any of them is already
+    /// reserved by another transaction.
+    pub fn request_signatures(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        transaction_kind: &'static str,
+        transaction_hash: types::Hash,
+        inputs: Vec<types::Input>,
+    ) -> ResponseActFuture<Vec<types::KeyedSignature>> {
+        let correlation_id = correlation::current();
+        let wallet_and_sink = self
+            .state
+            .wallet(&session_id, &wallet_id)
+            .and_then(|wallet| {
+                self.state
+                    .signer_sink(&session_id)
+                    .map(|sink| (wallet, sink))
+            });
+        let output_pointers: Vec<String> = inputs
+            .iter()
+            .map(|input| input.output_pointer().to_string())
+            .collect();
+
+        let f = fut::result(wallet_and_sink)
+            .and_then(move |(wallet, sink), slf: &mut Self, _| {
+                let worker_addr = slf.params.worker.clone();
+
+                worker_addr
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::GetInputPaths(wallet.clone(), inputs.clone()),
+                    })
+                    .flatten()
+                    .and_then(move |paths| {
+                        // the outputs stay reserved until the transaction is indexed, so they are
+                        // not spent twice in the meantime
+                        worker_addr
+                            .send(Traced {
+                                correlation_id,
+                                message: worker::ReserveInputs(wallet.clone(), inputs.clone()),
+                            })
+                            .flatten()
+                            .map(move |()| (wallet, inputs, sink, paths))
+                    })
+                    .map_err(From::from)
+                    .into_actor(slf)
+            })
+            .and_then(move |(wallet, inputs, sink, paths), slf: &mut Self, ctx| {
+                let (paths, input_pkhs): (Vec<String>, Vec<types::PublicKeyHash>) =
+                    paths.into_iter().unzip();
+                let signature_inputs: Vec<model::SignatureInput> = output_pointers
+                    .into_iter()
+                    .zip(paths)
+                    .enumerate()
+                    .map(|(index, (output_pointer, path))| model::SignatureInput {
+                        index: index as u32,
+                        output_pointer,
+                        path,
+                    })
+                    .collect();
+                let (sender, receiver) = oneshot::channel();
+                let request_id = slf.state.add_signature_request(state::SignatureRequest {
+                    session_id,
+                    transaction_hash,
+                    input_pkhs,
+                    sender,
+                });
+                let request = model::SignatureRequest {
+                    request_id,
+                    wallet_id,
+                    transaction_kind,
+                    transaction_hash: transaction_hash.to_string(),
+                    inputs: signature_inputs,
+                };
+
+                ctx.run_later(
+                    Duration::from_secs(constants::SIGNATURE_REQUEST_TIMEOUT_SECS),
+                    move |slf: &mut Self, _| {
+                        if let Some(request) = slf.state.take_signature_request(request_id) {
+                            log::warn!("Signature request {} timed out", request_id);
+                            let _ = request.sender.send(Err(Error::SignatureTimeout));
+                        }
+                    },
+                );
+
+                let payload = json!({ "signatureRequest": request });
+                sink.notify(jsonrpc_core::Params::Array(vec![payload]))
+                    .map_err(|_| Error::SignerNotConnected)
+                    // the request is dropped along with the session, which means the signer is gone
+                    .and_then(|_| receiver.map_err(|_| Error::SignerNotConnected))
+                    .flatten()
+                    .into_actor(slf)
+                    .then(move |result, slf: &mut Self, _| {
+                        if result.is_err() {
+                            slf.params.worker.do_send(Traced {
+                                correlation_id,
+                                message: worker::ReleaseInputs(wallet, inputs),
+                            });
+                        }
+
+                        fut::result(result)
+                    })
+            });
+
+        Box::new(f)
+    }
+
+    /// Resolve a signature request with the signatures returned by the external signer of the
+    /// session.
+    pub fn submit_signatures(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
-        offset: u32,
-        limit: u32,
-    ) -> ResponseActFuture<model::Transactions> {
-        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
-            move |wallet, slf: &mut Self, _| {
-                slf.params
-                    .worker
-                    .send(worker::GetTransactions(wallet, offset, limit))
-                    .flatten()
-                    .map_err(From::from)
-                    .into_actor(slf)
-            },
-        );
+        request_id: u32,
+        signatures: Vec<model::ExternalSignature>,
+    ) -> Result<()> {
+        let request = self.state.signature_request(&session_id, request_id)?;
+        let signatures = validate_signatures(
+            "signatures",
+            signatures,
+            request.transaction_hash,
+            &request.input_pkhs,
+        )
+        .map_err(validation_error)?;
 
-        Box::new(f)
+        if let Some(request) = self.state.take_signature_request(request_id) {
+            let _ = request.sender.send(Ok(signatures));
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a signature request that the external signer of the session refused to sign, e.g.
+    /// because the user did not confirm it on the device.
+    pub fn reject_signature_request(
+        &mut self,
+        session_id: types::SessionId,
+        request_id: u32,
+        reason: String,
+    ) -> Result<()> {
+        self.state.signature_request(&session_id, request_id)?;
+
+        if let Some(request) = self.state.take_signature_request(request_id) {
+            let _ = request.sender.send(Err(Error::SignatureRejected(reason)));
+        }
+
+        Ok(())
     }
 
     /// Run a RADRequest and return the computed result.
     pub fn run_rad_request(&self, req: types::RADRequest) -> ResponseFuture<types::RadonTypes> {
+        let correlation_id = correlation::current();
         let f = self
             .params
             .worker
-            .send(worker::RunRadRequest(req))
+            .send(Traced {
+                correlation_id,
+                message: worker::RunRadRequest(req),
+            })
             .flatten()
             .map_err(From::from);
 
@@ -125,11 +989,19 @@ impl App {
     }
 
     /// Generate a random BIP39 mnemonics sentence
-    pub fn generate_mnemonics(&self, length: types::MnemonicLength) -> ResponseFuture<String> {
+    pub fn generate_mnemonics(
+        &self,
+        length: types::MnemonicLength,
+        language: types::MnemonicLang,
+    ) -> ResponseFuture<String> {
+        let correlation_id = correlation::current();
         let f = self
             .params
             .worker
-            .send(worker::GenMnemonic(length))
+            .send(Traced {
+                correlation_id,
+                message: worker::GenMnemonic(length, language),
+            })
             .map_err(From::from);
 
         Box::new(f)
@@ -146,7 +1018,8 @@ impl App {
                 let req = types::RpcRequest::method(method)
                     .timeout(self.params.requests_timeout)
                     .params(params)
-                    .expect("params failed serialization");
+                    .expect("params failed serialization")
+                    .correlation_id(correlation::current());
                 let f = addr.send(req).flatten().map_err(From::from);
 
                 Box::new(f)
@@ -159,36 +1032,200 @@ impl App {
         }
     }
 
-    /// Get public info of all the wallets stored in the database.
+    /// Fetch a data request transaction from the node, along with the hash of the block that
+    /// included it.
+    pub fn get_data_request_transaction(
+        &mut self,
+        dr_hash: types::Hash,
+    ) -> ResponseActFuture<(types::Hash, types::DRTransaction)> {
+        let hash_params = |hash: types::Hash| {
+            types::RpcParams::Array(vec![types::Json::String(hash.to_string())])
+        };
+        let f = self
+            .forward("dataRequestReport".to_string(), hash_params(dr_hash))
+            .and_then(move |report| -> Result<types::Hash> {
+                let block_hash: Option<types::Hash> =
+                    serde_json::from_value(report["block_hash_dr_tx"].clone())
+                        .map_err(node_error)?;
+
+                block_hash.ok_or_else(|| {
+                    Error::Node(failure::format_err!(
+                        "Data request {} is not included in a block",
+                        dr_hash
+                    ))
+                })
+            })
+            .into_actor(self)
+            .and_then(move |block_hash, slf: &mut Self, _| {
+                slf.forward("getBlock".to_string(), hash_params(block_hash))
+                    .and_then(move |block| -> Result<_> {
+                        let block: types::ChainBlock =
+                            serde_json::from_value(block).map_err(node_error)?;
+                        let dr_txn = block
+                            .txns
+                            .data_request_txns
+                            .into_iter()
+                            .find(|txn| txn.hash() == dr_hash)
+                            .ok_or_else(|| {
+                                Error::Node(failure::format_err!(
+                                    "Data request {} not found in block {}",
+                                    dr_hash,
+                                    block_hash
+                                ))
+                            })?;
+
+                        Ok((block_hash, dr_txn))
+                    })
+                    .into_actor(slf)
+            });
+
+        Box::new(f)
+    }
+
+    /// Fetch a block from the node, annotating the transactions that moved funds of a wallet.
+    pub fn get_block_info(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        block_hash: types::Hash,
+    ) -> ResponseActFuture<model::BlockInfo> {
+        let correlation_id = correlation::current();
+        let params = types::RpcParams::Array(vec![types::Json::String(block_hash.to_string())]);
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id))
+            .and_then(move |wallet, slf: &mut Self, _| {
+                slf.forward("getBlock".to_string(), params)
+                    .map(move |block| (wallet, block))
+                    .into_actor(slf)
+            })
+            .and_then(move |(wallet, block), _, _| {
+                let txn_hashes = serde_json::from_value::<types::ChainBlock>(block.clone())
+                    .map(|chain_block| {
+                        let txns = chain_block.txns;
+                        txns.value_transfer_txns
+                            .iter()
+                            .map(|txn| txn.hash())
+                            .chain(txns.data_request_txns.iter().map(|txn| txn.hash()))
+                            .chain(txns.tally_txns.iter().map(|txn| txn.hash()))
+                            .collect()
+                    })
+                    .map_err(node_error);
+
+                fut::result(txn_hashes).and_then(move |txn_hashes, slf: &mut Self, _| {
+                    slf.params
+                        .worker
+                        .send(Traced {
+                            correlation_id,
+                            message: worker::GetBlockTransactions(wallet, txn_hashes),
+                        })
+                        .flatten()
+                        .map_err(From::from)
+                        .map(move |wallet_transactions| model::BlockInfo {
+                            block,
+                            wallet_transactions,
+                        })
+                        .into_actor(slf)
+                })
+            });
+
+        Box::new(f)
+    }
+
+    /// Ask the node for the cost breakdown of a data request and the current network conditions.
+    pub fn estimate_data_request_cost(
+        &mut self,
+        dr_output: types::DataRequestOutput,
+    ) -> ResponseFuture<types::Json> {
+        match serde_json::to_value(dr_output) {
+            Ok(dr_output) => self.forward(
+                "estimateDataRequestCost".to_string(),
+                types::RpcParams::Array(vec![dr_output]),
+            ),
+            Err(err) => Box::new(future::err(internal_error(err))),
+        }
+    }
+
+    /// Get public info of all the wallets of the current tenant stored in the database.
     pub fn wallet_infos(&self) -> ResponseFuture<Vec<model::Wallet>> {
+        let correlation_id = correlation::current();
         let f = self
             .params
             .worker
-            .send(worker::WalletInfos)
+            .send(Traced {
+                correlation_id,
+                message: worker::WalletInfos(current_tenant()),
+            })
             .flatten()
             .map_err(From::from);
 
         Box::new(f)
     }
 
-    /// Create an empty HD Wallet.
+    /// Create an empty HD Wallet, owned by `tenant` if any.
     pub fn create_wallet(
         &self,
         password: types::Password,
         seed_source: types::SeedSource,
         name: Option<String>,
         caption: Option<String>,
+        tenant: Option<String>,
     ) -> ResponseFuture<String> {
+        let correlation_id = correlation::current();
         let f = self
             .params
             .worker
-            .send(worker::CreateWallet(name, caption, password, seed_source))
+            .send(Traced {
+                correlation_id,
+                message: worker::CreateWallet(name, caption, password, seed_source, tenant),
+            })
             .flatten()
             .map_err(From::from);
 
         Box::new(f)
     }
 
+    /// Download a wallet replicated to the remote storage, so it can be unlocked.
+    pub fn recover_wallet(
+        &self,
+        wallet_id: String,
+        password: types::Password,
+    ) -> ResponseFuture<()> {
+        let correlation_id = correlation::current();
+        let f = self
+            .params
+            .worker
+            .send(Traced {
+                correlation_id,
+                message: worker::RecoverWallet(wallet_id, password, current_tenant()),
+            })
+            .flatten()
+            .map_err(|err| match err {
+                worker::Error::WalletNotFound => validation_error(field_error(
+                    "wallet_id",
+                    "Wallet not found in the remote storage",
+                )),
+                worker::Error::WalletAlreadyExists => {
+                    validation_error(field_error("wallet_id", "Wallet already exists"))
+                }
+                worker::Error::RemoteStorageDisabled => {
+                    validation_error(field_error("wallet_id", "No remote storage is configured"))
+                }
+                worker::Error::InvalidReplica => validation_error(field_error(
+                    "wallet_id",
+                    "The remote copy of the wallet has records of other wallets",
+                )),
+                worker::Error::IncompleteReplica(n) => validation_error(field_error(
+                    "wallet_id",
+                    format!("The remote copy of the wallet is missing its batch {}", n),
+                )),
+                worker::Error::WrongPassword => {
+                    validation_error(field_error("password", "Wrong password"))
+                }
+                err => From::from(err),
+            });
+
+        Box::new(f)
+    }
+
     /// Lock a wallet, that is, remove its encryption/decryption key from the list of known keys and
     /// close the session.
     ///
@@ -204,10 +1241,22 @@ impl App {
         wallet_id: String,
         password: types::Password,
     ) -> ResponseActFuture<types::UnlockedWallet> {
+        let correlation_id = correlation::current();
+        let tenant = current_tenant();
+        if let Err(err) = self.state.check_tenant_sessions(
+            tenant.as_ref().map(String::as_str),
+            self.params.tenant_max_sessions,
+        ) {
+            return Box::new(fut::err(err));
+        }
+
         let f = self
             .params
             .worker
-            .send(worker::UnlockWallet(wallet_id.clone(), password))
+            .send(Traced {
+                correlation_id,
+                message: worker::UnlockWallet(wallet_id.clone(), password, tenant.clone()),
+            })
             .flatten()
             .map_err(|err| match err {
                 worker::Error::WalletNotFound => {
@@ -219,15 +1268,23 @@ impl App {
                 err => From::from(err),
             })
             .into_actor(self)
-            .and_then(move |res, slf: &mut Self, _| {
+            .and_then(move |res, slf: &mut Self, ctx| {
                 let types::UnlockedSessionWallet {
                     wallet,
                     session_id,
                     data,
                 } = res;
+                let wallet = Arc::new(wallet);
 
-                slf.state
-                    .create_session(session_id.clone(), wallet_id, Arc::new(wallet));
+                slf.state.create_session(
+                    session_id.clone(),
+                    wallet_id.clone(),
+                    wallet.clone(),
+                    tenant,
+                );
+
+                // The session can be used right away, while the utxo set and balances are loaded
+                slf.load_wallet(wallet_id, wallet).spawn(ctx);
 
                 fut::ok(types::UnlockedWallet { data, session_id })
             });
@@ -235,12 +1292,314 @@ impl App {
         Box::new(f)
     }
 
+    /// Load the utxo set and balances of an unlocked wallet in the background, notifying the
+    /// session when finished.
+    pub fn load_wallet(
+        &self,
+        wallet_id: String,
+        wallet: types::SessionWallet,
+    ) -> impl ActorFuture<Actor = Self, Item = (), Error = ()> {
+        let correlation_id = correlation::current();
+        self.params
+            .worker
+            .send(Traced {
+                correlation_id,
+                message: worker::LoadWallet(wallet_id.clone(), wallet.clone()),
+            })
+            .flatten()
+            .into_actor(self)
+            .then(move |result, slf: &mut Self, ctx| {
+                match result {
+                    Ok(()) => {
+                        if let Some(network) = slf.state.node_network.clone() {
+                            slf.sync_wallet(wallet_id.clone(), wallet.clone(), network)
+                                .spawn(ctx);
+                        }
+                        if let Some(notifier) = slf.state.wallet_notifier(&wallet_id) {
+                            slf.params.worker.do_send(Traced {
+                                correlation_id,
+                                message: worker::NotifyWalletReady(wallet_id, wallet, notifier),
+                            });
+                        }
+                    }
+                    Err(err) => log::error!("Couldn't load wallet {}: {}", wallet_id, err),
+                }
+
+                fut::ok(())
+            })
+    }
+
+    /// Ask the node for its network, and sync the unlocked wallets with its chain.
+    pub fn fetch_node_network(&mut self) -> impl ActorFuture<Actor = Self, Item = (), Error = ()> {
+        self.forward("getNetwork".to_string(), types::RpcParams::Array(vec![]))
+            .and_then(|value| serde_json::from_value::<types::NetworkId>(value).map_err(node_error))
+            .into_actor(self)
+            .then(|result, slf: &mut Self, ctx| {
+                match result {
+                    Ok(network) => {
+                        log::info!("The node of the wallet belongs to {}", network);
+                        slf.state.node_network = Some(network.clone());
+                        let wallets: Vec<_> = slf
+                            .state
+                            .wallets()
+                            .map(|(id, wallet)| (id.clone(), wallet.clone()))
+                            .collect();
+                        for (wallet_id, wallet) in wallets {
+                            slf.sync_wallet(wallet_id, wallet, network.clone())
+                                .spawn(ctx);
+                        }
+                    }
+                    Err(err) => log::warn!(
+                        "Couldn't get the network of the node, wallets won't be synced: {}",
+                        err
+                    ),
+                }
+
+                fut::ok(())
+            })
+    }
+
+    /// Subscribe to the blocks notified by the node, replacing the previous subscription if any.
+    pub fn subscribe_to_node(&self, ctx: &mut Context<Self>) {
+        if let Some(ref client) = self.params.client {
+            let recipient = ctx.address().recipient();
+            let request = types::RpcRequest::method("witnet_subscribe")
+                .timeout(self.params.requests_timeout)
+                .value(json!(["newBlocks"]));
+
+            client.do_send(jsonrpc::SetSubscriber(recipient, request));
+        }
+    }
+
+    /// Compare the epoch of the last block notified by the node with the beacon of its chain. If
+    /// the node is synchronized but the notifications lag too far behind, the subscription is
+    /// considered stale: the sessions are notified that the wallet stalled and the wallet
+    /// subscribes again. The unlocked wallets index the blocks they missed once the node notifies
+    /// blocks again, see [handle_block_notification](App::handle_block_notification).
+    pub fn check_node_sync(&mut self) -> impl ActorFuture<Actor = Self, Item = (), Error = ()> {
+        self.forward("status".to_string(), types::RpcParams::Array(vec![]))
+            .and_then(|value| {
+                serde_json::from_value::<types::NodeStatus>(value).map_err(node_error)
+            })
+            .into_actor(self)
+            .then(|result, slf: &mut Self, ctx| {
+                match result {
+                    Ok(status) => slf.handle_node_status(status, ctx),
+                    Err(err) => log::warn!("Couldn't check the status of the node: {}", err),
+                }
+
+                fut::ok(())
+            })
+    }
+
+    fn handle_node_status(&mut self, status: types::NodeStatus, ctx: &mut Context<Self>) {
+        let node_epoch = status.chain_beacon.checkpoint;
+        match self.state.check_node_beacon(
+            status.synchronized,
+            node_epoch,
+            self.params.node_stall_epochs,
+        ) {
+            state::NodeCheck::Synced => {}
+            state::NodeCheck::Syncing => {
+                log::debug!(
+                    "The node is synchronizing its chain, at epoch {}",
+                    node_epoch
+                )
+            }
+            state::NodeCheck::SyncFinished => {
+                log::info!("The node finished synchronizing its chain, syncing the wallets");
+                self.sync_wallets(ctx);
+            }
+            state::NodeCheck::Stalled {
+                last_block_epoch,
+                first,
+            } => {
+                log::warn!(
+                    "The node is at epoch {} but the last block it notified is from epoch {}, \
+                     subscribing again",
+                    node_epoch,
+                    last_block_epoch
+                );
+                if first {
+                    self.notify_node_status("stalled", last_block_epoch, node_epoch);
+                }
+                self.subscribe_to_node(ctx);
+            }
+        }
+    }
+
+    /// Sync all the unlocked wallets with the chain of the node, see
+    /// [sync_wallet](App::sync_wallet).
+    pub fn sync_wallets(&mut self, ctx: &mut Context<Self>) {
+        match self.state.node_network.clone() {
+            Some(network) => {
+                let wallets: Vec<_> = self
+                    .state
+                    .wallets()
+                    .map(|(id, wallet)| (id.clone(), wallet.clone()))
+                    .collect();
+                for (wallet_id, wallet) in wallets {
+                    self.sync_wallet(wallet_id, wallet, network.clone())
+                        .spawn(ctx);
+                }
+            }
+            None => self.fetch_node_network().spawn(ctx),
+        }
+    }
+
+    /// Notify all the sessions whether the wallet receives the blocks of the node.
+    fn notify_node_status(&self, status: &str, last_block_epoch: u32, node_epoch: u32) {
+        let payload = json!({
+            "nodeStatus": {
+                "status": status,
+                "lastBlockEpoch": last_block_epoch,
+                "nodeEpoch": node_epoch
+            }
+        });
+
+        for notifier in self.state.session_notifiers() {
+            if let Err(err) = notifier.notify(payload.clone()) {
+                log::warn!("Couldn't notify the status of the node: {}", err);
+            }
+        }
+    }
+
+    /// Sync a wallet with the chain of the network of the node, indexing again the blocks it
+    /// missed or, if it indexed the chain of another network, all the blocks of this one.
+    pub fn sync_wallet(
+        &self,
+        wallet_id: String,
+        wallet: types::SessionWallet,
+        network: types::NetworkId,
+    ) -> impl ActorFuture<Actor = Self, Item = (), Error = ()> {
+        let correlation_id = correlation::current();
+        let log_wallet_id = wallet_id.clone();
+        let notifier = self.state.wallet_notifier(&wallet_id);
+        self.params
+            .worker
+            .send(Traced {
+                correlation_id,
+                message: worker::SyncNetwork(wallet_id.clone(), wallet.clone(), network, notifier),
+            })
+            .flatten()
+            .map_err(From::from)
+            .into_actor(self)
+            .and_then(move |rescan_from, slf: &mut Self, _| {
+                let f: ResponseFuture<()> = match rescan_from {
+                    Some(epoch) => slf.rescan(wallet_id, wallet, epoch),
+                    None => Box::new(future::ok(())),
+                };
+
+                f.into_actor(slf)
+            })
+            .map_err(move |err, _, _| {
+                log::error!("Couldn't sync wallet {}: {}", log_wallet_id, err)
+            })
+    }
+
+    /// Forget the state a wallet indexed from a chain that has been reset, and index the chain of
+    /// the node from the beginning in the background.
+    pub fn reset_chain_data(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+    ) -> ResponseActFuture<()> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::ResetChainData(wallet.clone()),
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+                    .map(move |rescan_from, slf: &mut Self, ctx| {
+                        let log_wallet_id = wallet_id.clone();
+                        slf.rescan(wallet_id, wallet, rescan_from)
+                            .map_err(move |err| {
+                                log::error!("Couldn't sync wallet {}: {}", log_wallet_id, err)
+                            })
+                            .into_actor(slf)
+                            .spawn(ctx);
+                    })
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Index the blocks of the chain of the node consolidated from `from_epoch`, in order.
+    pub fn rescan(
+        &self,
+        wallet_id: String,
+        wallet: types::SessionWallet,
+        from_epoch: u32,
+    ) -> ResponseFuture<()> {
+        let client = match &self.params.client {
+            Some(client) => client.clone(),
+            None => return Box::new(future::err(Error::NodeNotConnected)),
+        };
+        let timeout = self.params.requests_timeout;
+        let worker_addr = self.params.worker.clone();
+        let correlation_id = correlation::current();
+
+        let f = node_request(
+            &client,
+            timeout,
+            "getBlockChain",
+            json!({ "epoch": from_epoch, "limit": 0 }),
+        )
+        .and_then(|value| {
+            serde_json::from_value::<Vec<(u32, types::Hash)>>(value).map_err(node_error)
+        })
+        .and_then(move |blocks| {
+            log::info!(
+                "Indexing {} blocks from epoch {} for wallet {}",
+                blocks.len(),
+                from_epoch,
+                wallet_id
+            );
+
+            futures::stream::iter_ok(blocks).for_each(move |(_epoch, block_hash)| {
+                let worker_addr = worker_addr.clone();
+                let wallet_id = wallet_id.clone();
+                let wallet = wallet.clone();
+
+                node_request(&client, timeout, "getBlock", json!([block_hash]))
+                    .and_then(|value| {
+                        serde_json::from_value::<types::ChainBlock>(value).map_err(node_error)
+                    })
+                    .and_then(move |block| {
+                        worker_addr
+                            .send(Traced {
+                                correlation_id,
+                                message: worker::IndexTxns(
+                                    wallet_id,
+                                    wallet,
+                                    types::BlockTransactions::from(block),
+                                ),
+                            })
+                            .map_err(From::from)
+                    })
+            })
+        });
+
+        Box::new(f)
+    }
+
     /// Perform all the tasks needed to properly stop the application.
     pub fn stop(&self) -> ResponseFuture<()> {
+        let correlation_id = correlation::current();
         let fut = self
             .params
             .worker
-            .send(worker::FlushDb)
+            .send(Traced {
+                correlation_id,
+                message: worker::FlushDb,
+            })
             .map_err(internal_error)
             .and_then(|result| result.map_err(internal_error));
 
@@ -276,11 +1635,15 @@ impl App {
         wallet_id: String,
         key: String,
     ) -> ResponseActFuture<Option<types::RpcValue>> {
+        let correlation_id = correlation::current();
         let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
-            |wallet, slf: &mut Self, _| {
+            move |wallet, slf: &mut Self, _| {
                 slf.params
                     .worker
-                    .send(worker::Get(wallet, key))
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::Get(wallet, key),
+                    })
                     .flatten()
                     .map_err(From::from)
                     .and_then(|opt| match opt {
@@ -306,13 +1669,17 @@ impl App {
         key: String,
         value: types::RpcParams,
     ) -> ResponseActFuture<()> {
+        let correlation_id = correlation::current();
         let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
             move |wallet, _, _| {
                 fut::result(serde_json::to_string(&value).map_err(internal_error)).and_then(
                     move |value, slf: &mut Self, _| {
                         slf.params
                             .worker
-                            .send(worker::Set(wallet, key, value))
+                            .send(Traced {
+                                correlation_id,
+                                message: worker::Set(wallet, key, value),
+                            })
                             .flatten()
                             .map_err(From::from)
                             .into_actor(slf)
@@ -324,8 +1691,192 @@ impl App {
         Box::new(f)
     }
 
+    /// Create a value transfer transaction paying `amount` to `address` with the funds of a
+    /// wallet. The transaction is signed but not sent to the node, see
+    /// [send_transaction](App::send_transaction).
+    pub fn create_vtt(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        address: String,
+        amount: types::Nanowits,
+        fee: types::Nanowits,
+        change_address: Option<types::ChangeAddressPolicy>,
+    ) -> ResponseActFuture<types::VTTransaction> {
+        let correlation_id = correlation::current();
+        let f = fut::result(self.state.wallet(&session_id, &wallet_id)).and_then(
+            move |wallet, slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(Traced {
+                        correlation_id,
+                        message: worker::CreateVtt {
+                            wallet,
+                            address,
+                            amount,
+                            fee,
+                            change_address,
+                        },
+                    })
+                    .flatten()
+                    .map_err(From::from)
+                    .into_actor(slf)
+            },
+        );
+
+        Box::new(f)
+    }
+
+    /// Send a transaction spending outputs of a wallet to the node. The outputs spent by a
+    /// transaction the node does not accept are released, so they can be spent again.
+    pub fn send_transaction(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: String,
+        transaction: types::VTTransaction,
+    ) -> ResponseActFuture<types::Hash> {
+        let client = match &self.params.client {
+            Some(client) => client.clone(),
+            None => return Box::new(fut::err(Error::NodeNotConnected)),
+        };
+        let wallet = match self.state.wallet(&session_id, &wallet_id) {
+            Ok(wallet) => wallet,
+            Err(err) => return Box::new(fut::err(err)),
+        };
+        let correlation_id = correlation::current();
+        let worker = self.params.worker.clone();
+        let hash = transaction.hash();
+        let inputs = transaction.body.inputs.clone();
+        let item =
+            types::InventoryItem::Transaction(types::Transaction::ValueTransfer(transaction));
+        let params = serde_json::to_value(&item).expect("params failed serialization");
+        let f = node_request(&client, self.params.requests_timeout, "inventory", params)
+            .then(move |result| {
+                if let Err(err) = &result {
+                    log::warn!("node did not accept transaction {}: {}", hash, err);
+                    worker.do_send(Traced {
+                        correlation_id,
+                        message: worker::ReleaseInputs(wallet, inputs),
+                    });
+                }
+
+                result.map(|_| hash)
+            })
+            .into_actor(self);
+
+        Box::new(f)
+    }
+
+    /// Handle a request that clients can safely retry by sending an idempotency key: if a request
+    /// to the same method of the same wallet was made with the same key within the idempotency
+    /// window, its response is returned again instead of handling the request another time. Keys
+    /// are scoped to the tenant making the request. Failed requests are not remembered, so they
+    /// can be retried.
+    pub fn idempotent<P, T, F>(
+        &mut self,
+        method: &str,
+        wallet_id: &str,
+        key: Option<String>,
+        params: &P,
+        handle: F,
+    ) -> ResponseActFuture<T>
+    where
+        P: serde::Serialize,
+        T: serde::Serialize + serde::de::DeserializeOwned + 'static,
+        F: FnOnce(&mut Self) -> ResponseActFuture<T>,
+    {
+        let window = self.params.idempotency_window;
+        let key = match key {
+            Some(key) if window > Duration::from_secs(0) => {
+                state::State::idempotency_key(wallet_id, method, key)
+            }
+            _ => return handle(self),
+        };
+        let params = match serde_json::to_string(params) {
+            Ok(params) => params,
+            Err(err) => return Box::new(fut::err(internal_error(err))),
+        };
+
+        match self.state.idempotent_response(&key, &params, window) {
+            Ok(Some(response)) => {
+                log::debug!(
+                    "Returning the previous response of {} with idempotency key {}",
+                    method,
+                    key.3
+                );
+                return Box::new(fut::result(
+                    serde_json::from_value(response).map_err(internal_error),
+                ));
+            }
+            Ok(None) => {}
+            Err(err) => return Box::new(fut::err(err)),
+        }
+
+        let f = handle(self).then(move |result, slf: &mut Self, _| {
+            let response = result
+                .as_ref()
+                .ok()
+                .and_then(|response| serde_json::to_value(response).ok());
+            slf.state.save_idempotent_response(&key, response);
+
+            fut::result(result)
+        });
+
+        Box::new(f)
+    }
+
+    /// Run the recurring payments of the unlocked wallets that are due at `epoch`, sending the
+    /// transactions of their payments to the node.
+    fn run_schedules(&self, epoch: u32, correlation_id: Option<CorrelationId>) {
+        for (id, wallet) in self.state.wallets() {
+            let wallet_id = id.to_owned();
+            let wallet = wallet.clone();
+            let notifier = self.state.wallet_notifier(id);
+            let worker = self.params.worker.clone();
+            let client = self.params.client.clone();
+            let timeout = self.params.requests_timeout;
+
+            let f = worker
+                .send(Traced {
+                    correlation_id,
+                    message: worker::RunSchedules(wallet.clone(), epoch),
+                })
+                .flatten()
+                .map_err(Error::from)
+                .and_then(move |due| {
+                    let _entered = correlation_id.map(correlation::enter);
+                    future::join_all(due.into_iter().map(move |due| {
+                        send_scheduled_payment(
+                            worker.clone(),
+                            client.clone(),
+                            timeout,
+                            wallet.clone(),
+                            due,
+                            notifier.clone(),
+                        )
+                    }))
+                })
+                .map(|_| ())
+                .map_err(move |err| {
+                    log::warn!(
+                        "failed to run recurring payments for wallet {}: {}",
+                        wallet_id,
+                        err
+                    )
+                });
+
+            Arbiter::spawn(f);
+        }
+    }
+
     /// Handle notifications received from the node.
-    pub fn handle_block_notification(&mut self, value: types::Json) -> Result<()> {
+    ///
+    /// Index a block notified by the node in all the unlocked wallets, returning whether the
+    /// wallet was stalled until then, in which case the wallets have to be synced to index the
+    /// blocks that were not notified.
+    pub fn handle_block_notification(&mut self, value: types::Json) -> Result<bool> {
+        let correlation_id = Some(CorrelationId::new());
+        let _entered = correlation_id.map(correlation::enter);
         log::trace!("received block notification");
         let block = serde_json::from_value::<types::ChainBlock>(value).map_err(node_error)?;
         // NOTE: Possible enhancement.
@@ -334,28 +1885,117 @@ impl App {
         // results to be too big, problm is that doing so conflicts
         // with the internal Cell of the txns type which cannot be
         // shared between threads.
-        let txns = block
-            .txns
-            .value_transfer_txns
-            .into_iter()
-            .map(|txn| txn.body)
-            .collect::<Vec<_>>();
+        let txns = types::BlockTransactions::from(block);
+        let epoch = txns.epoch;
+
+        let was_stalled = self.state.block_notified(epoch);
+        if was_stalled {
+            log::info!("The node notified a block again, the wallet is no longer stalled");
+            self.notify_node_status("synced", epoch, epoch);
+        }
 
         for (id, wallet) in self.state.wallets() {
-            self.params.worker.do_send(worker::IndexTxns(
-                id.to_owned(),
-                wallet.clone(),
-                txns.clone(),
-            ));
+            self.params.worker.do_send(Traced {
+                correlation_id,
+                message: worker::IndexTxns(id.to_owned(), wallet.clone(), txns.clone()),
+            });
         }
 
+        self.run_schedules(epoch, correlation_id);
+
         log::trace!("notifying balances to sessions");
-        for (wallet, sink) in self.state.notifiable_wallets() {
-            self.params
-                .worker
-                .do_send(worker::NotifyBalance(wallet, sink));
+        for (wallet, notifier) in self.state.notifiable_wallets() {
+            self.params.worker.do_send(Traced {
+                correlation_id,
+                message: worker::NotifyBalance(wallet, notifier),
+            });
         }
 
-        Ok(())
+        Ok(was_stalled)
     }
 }
+
+/// Send the transaction of a payment generated by a recurring payment to the node, recording its
+/// hash once the node accepts it. The outputs spent by a transaction the node does not accept are
+/// released, and the payment is kept pending to retry it in the next epoch.
+fn send_scheduled_payment(
+    worker: Addr<Worker>,
+    client: Option<Addr<JsonRpcClient>>,
+    timeout: Duration,
+    wallet: types::SessionWallet,
+    due: worker::DuePayment,
+    notifier: Option<journal::Notifier>,
+) -> ResponseFuture<()> {
+    let correlation_id = correlation::current();
+    let worker::DuePayment {
+        index,
+        payment,
+        transaction,
+        retried,
+    } = due;
+    let sent: ResponseFuture<Option<types::Hash>> = match (transaction, client) {
+        (Some(transaction), Some(client)) => {
+            let hash = transaction.hash();
+            let inputs = transaction.body.inputs.clone();
+            let item =
+                types::InventoryItem::Transaction(types::Transaction::ValueTransfer(transaction));
+            let params = serde_json::to_value(&item).expect("params failed serialization");
+            let (release_worker, release_wallet) = (worker.clone(), wallet.clone());
+            let f = node_request(&client, timeout, "inventory", params).then(
+                move |result| -> Result<Option<types::Hash>> {
+                    if let Err(err) = &result {
+                        log::warn!("node did not accept scheduled payment {}: {}", hash, err);
+                        release_worker.do_send(Traced {
+                            correlation_id,
+                            message: worker::ReleaseInputs(release_wallet, inputs),
+                        });
+                    }
+
+                    Ok(result.ok().map(|_| hash))
+                },
+            );
+
+            Box::new(f)
+        }
+        (Some(transaction), None) => {
+            worker.do_send(Traced {
+                correlation_id,
+                message: worker::ReleaseInputs(wallet.clone(), transaction.body.inputs),
+            });
+
+            Box::new(future::ok(None))
+        }
+        (None, _) => Box::new(future::ok(None)),
+    };
+    let f = sent.map(move |transaction_hash| {
+        worker.do_send(Traced {
+            correlation_id,
+            message: worker::RecordScheduledPayment(
+                wallet,
+                index,
+                payment,
+                transaction_hash,
+                retried,
+                notifier,
+            ),
+        })
+    });
+
+    Box::new(f)
+}
+
+/// Send a JSON-RPC request to the node.
+fn node_request(
+    client: &Addr<JsonRpcClient>,
+    timeout: Duration,
+    method: &str,
+    params: types::Json,
+) -> ResponseFuture<types::Json> {
+    let req = types::RpcRequest::method(method)
+        .timeout(timeout)
+        .value(params)
+        .correlation_id(correlation::current());
+    let f = client.send(req).flatten().map_err(From::from);
+
+    Box::new(f)
+}
@@ -1,9 +1,6 @@
 use std::sync::Arc;
 
 use actix::prelude::*;
-use serde_json::json;
-
-use witnet_net::client::tcp::jsonrpc;
 
 use crate::types;
 
@@ -11,14 +8,18 @@ pub mod error;
 pub mod handlers;
 pub mod methods;
 pub mod params;
+pub mod rate_limit;
 pub mod routes;
 mod state;
+pub mod tenants;
 pub mod validation;
 
 pub use error::*;
 pub use handlers::*;
 pub use params::*;
+pub use rate_limit::*;
 pub use routes::*;
+pub use tenants::*;
 pub use validation::*;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -37,13 +38,18 @@ impl Actor for App {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         // Subscribe to node if there's one configured.
-        if let Some(ref client) = self.params.client {
-            let recipient = ctx.address().recipient();
-            let request = types::RpcRequest::method("witnet_subscribe")
-                .timeout(self.params.requests_timeout)
-                .value(json!(["newBlocks"]));
-
-            client.do_send(jsonrpc::SetSubscriber(recipient, request));
+        if self.params.client.is_some() {
+            self.subscribe_to_node(ctx);
+
+            // The wallets are synced with the chain of the node once its network is known
+            self.fetch_node_network().spawn(ctx);
+
+            // Detect a subscription that stopped receiving the blocks of the node
+            if self.params.node_heartbeat > std::time::Duration::from_secs(0) {
+                ctx.run_interval(self.params.node_heartbeat, |slf, ctx| {
+                    slf.check_node_sync().spawn(ctx);
+                });
+            }
         }
     }
 }
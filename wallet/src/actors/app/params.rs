@@ -1,13 +1,28 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use witnet_net::client::tcp::JsonRpcClient;
 
 use super::*;
-use crate::actors;
+use crate::{actors, metrics};
 
 pub struct Params {
     pub worker: Addr<actors::Worker>,
     pub client: Option<Addr<JsonRpcClient>>,
     pub session_expires_in: Duration,
     pub requests_timeout: Duration,
+    pub testnet: bool,
+    pub epoch_constants: types::EpochConstants,
+    pub address_reuse: types::AddressReusePolicy,
+    pub idempotency_window: Duration,
+    /// Whether the databases of the wallets can be exported in plaintext.
+    pub plaintext_export: bool,
+    pub metrics: Arc<metrics::Metrics>,
+    /// How often the wallet checks that it still receives the blocks of the node, never if zero.
+    pub node_heartbeat: Duration,
+    /// Epochs the last block notified by the node can lag behind its beacon before the
+    /// subscription is considered stale.
+    pub node_stall_epochs: u32,
+    /// Maximum number of sessions each tenant can have open at the same time, no limit if zero.
+    pub tenant_max_sessions: u32,
 }
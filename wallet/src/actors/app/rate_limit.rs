@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use jsonrpc_core::Params;
+use jsonrpc_pubsub::Session;
+
+use super::*;
+
+/// Rate limits of the requests made to the wallet, so a misbehaving client can't keep the wallet
+/// busy and starve the indexing of new blocks.
+///
+/// Requests are limited per connection, as the session id of a request is not known to be valid
+/// until the request is handled: each connection can make up to `requests_per_second` requests
+/// every second, and can only have up to the configured number of requests in progress for the
+/// methods in `method_concurrency`. When the wallet serves several tenants, the connections of
+/// each tenant can make up to `tenant_requests_per_second` requests every second altogether.
+pub struct RateLimiter {
+    requests_per_second: u32,
+    tenant_requests_per_second: u32,
+    method_concurrency: BTreeMap<String, u32>,
+    state: Arc<Mutex<State>>,
+}
+
+/// Id given by the rate limiter to a connection, which is never reused by later connections
+pub type ConnectionId = u64;
+
+#[derive(Default)]
+struct State {
+    /// Id of the next connection
+    next_connection_id: ConnectionId,
+    /// Map address of the session of an open connection -> id of the connection
+    connections: HashMap<usize, ConnectionId>,
+    /// Map connection -> (start of the current window, requests made in the window)
+    windows: HashMap<ConnectionId, (Instant, u32)>,
+    /// Map tenant -> (start of the current window, requests made in the window)
+    tenant_windows: HashMap<String, (Instant, u32)>,
+    /// Map (connection, method) -> requests in progress
+    in_progress: HashMap<(ConnectionId, String), u32>,
+}
+
+/// Permission to handle a request, which must be kept until the request is finished.
+pub struct Permit {
+    state: Arc<Mutex<State>>,
+    key: Option<(ConnectionId, String)>,
+}
+
+impl RateLimiter {
+    pub fn new(
+        requests_per_second: u32,
+        tenant_requests_per_second: u32,
+        method_concurrency: BTreeMap<String, u32>,
+    ) -> Self {
+        Self {
+            requests_per_second,
+            tenant_requests_per_second,
+            method_concurrency,
+            state: Default::default(),
+        }
+    }
+
+    /// Id of the connection of the given session. The limits of a connection are forgotten when
+    /// it is closed.
+    pub fn connection_id(&self, session: &Arc<Session>) -> Result<ConnectionId> {
+        let address = &**session as *const Session as usize;
+        let mut state = self.lock()?;
+        if let Some(&connection_id) = state.connections.get(&address) {
+            return Ok(connection_id);
+        }
+
+        let connection_id = state.next_connection_id;
+        state.next_connection_id += 1;
+        state.connections.insert(address, connection_id);
+        let state = self.state.clone();
+        session.on_drop(move || {
+            if let Ok(mut state) = state.lock() {
+                state.connections.remove(&address);
+                state.windows.remove(&connection_id);
+            }
+        });
+
+        Ok(connection_id)
+    }
+
+    /// Check the limits of the connection making the request, and of its tenant if any,
+    /// returning a permit if the request can be handled or a `RateLimited` error otherwise.
+    pub fn acquire(
+        &self,
+        method: &str,
+        tenant: Option<&str>,
+        connection_id: ConnectionId,
+    ) -> Result<Permit> {
+        let mut state = self.lock()?;
+        let now = Instant::now();
+        let second = Duration::from_secs(1);
+
+        if let Some(tenant) = tenant.filter(|_| self.tenant_requests_per_second > 0) {
+            let (start, count) = state
+                .tenant_windows
+                .entry(tenant.to_string())
+                .or_insert((now, 0));
+            if now.duration_since(*start) >= second {
+                *start = now;
+                *count = 0;
+            }
+            if *count >= self.tenant_requests_per_second {
+                return Err(Error::RateLimited {
+                    method: method.to_string(),
+                    limit: "tenantRequestsPerSecond",
+                    max: self.tenant_requests_per_second,
+                });
+            }
+            *count += 1;
+        }
+
+        if self.requests_per_second > 0 {
+            let (start, count) = state.windows.entry(connection_id).or_insert((now, 0));
+            if now.duration_since(*start) >= second {
+                *start = now;
+                *count = 0;
+            }
+            if *count >= self.requests_per_second {
+                return Err(Error::RateLimited {
+                    method: method.to_string(),
+                    limit: "requestsPerSecond",
+                    max: self.requests_per_second,
+                });
+            }
+            *count += 1;
+
+            // forget the connections that have not made requests in the last second
+            state
+                .windows
+                .retain(|_, (start, _)| now.duration_since(*start) < second);
+        }
+
+        let key = match self.method_concurrency.get(method) {
+            Some(&max) => {
+                let key = (connection_id, method.to_string());
+                let in_progress = state.in_progress.entry(key.clone()).or_default();
+                if *in_progress >= max {
+                    return Err(Error::RateLimited {
+                        method: method.to_string(),
+                        limit: "concurrency",
+                        max,
+                    });
+                }
+                *in_progress += 1;
+
+                Some(key)
+            }
+            None => None,
+        };
+
+        Ok(Permit {
+            state: self.state.clone(),
+            key,
+        })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, State>> {
+        self.state
+            .lock()
+            .map_err(|_| Error::Internal(failure::format_err!("rate limiter lock poisoned")))
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            if let Ok(mut state) = self.state.lock() {
+                if let Some(in_progress) = state.in_progress.get_mut(&key) {
+                    *in_progress = in_progress.saturating_sub(1);
+                    if *in_progress == 0 {
+                        state.in_progress.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Session id of a request, if its params include one.
+pub fn session_id(params: &Params) -> Option<String> {
+    match params {
+        Params::Map(map) => map
+            .get("sessionId")
+            .and_then(|value| value.as_str())
+            .map(|session_id| session_id.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::sync::mpsc;
+
+    use super::*;
+
+    fn session() -> Arc<Session> {
+        let (sender, _receiver) = mpsc::channel(1);
+
+        Arc::new(Session::new(sender))
+    }
+
+    #[test]
+    fn requests_are_limited_per_connection() {
+        let limiter = RateLimiter::new(2, 0, BTreeMap::new());
+        let first = limiter.connection_id(&session()).unwrap();
+        let second = limiter.connection_id(&session()).unwrap();
+
+        assert!(limiter.acquire("getBlock", None, first).is_ok());
+        assert!(limiter.acquire("getBlock", None, first).is_ok());
+        match limiter.acquire("getBlock", None, first) {
+            Err(Error::RateLimited { limit, max, .. }) => {
+                assert_eq!(limit, "requestsPerSecond");
+                assert_eq!(max, 2);
+            }
+            _ => panic!("the third request should be rate limited"),
+        }
+
+        // Other connections are not affected
+        assert!(limiter.acquire("getBlock", None, second).is_ok());
+    }
+
+    #[test]
+    fn concurrent_requests_are_limited_per_connection_and_method() {
+        let method_concurrency = vec![("exportXprv".to_string(), 1)].into_iter().collect();
+        let limiter = RateLimiter::new(0, 0, method_concurrency);
+        let first = limiter.connection_id(&session()).unwrap();
+        let second = limiter.connection_id(&session()).unwrap();
+
+        let permit = limiter.acquire("exportXprv", None, first).unwrap();
+        assert!(limiter.acquire("exportXprv", None, first).is_err());
+        assert!(limiter.acquire("getBlock", None, first).is_ok());
+        assert!(limiter.acquire("exportXprv", None, second).is_ok());
+
+        // Finishing the request frees its place
+        drop(permit);
+        assert!(limiter.acquire("exportXprv", None, first).is_ok());
+    }
+
+    #[test]
+    fn requests_are_limited_per_tenant() {
+        let limiter = RateLimiter::new(0, 1, BTreeMap::new());
+        let first = limiter.connection_id(&session()).unwrap();
+        let second = limiter.connection_id(&session()).unwrap();
+
+        assert!(limiter.acquire("getBlock", Some("alice"), first).is_ok());
+        match limiter.acquire("getBlock", Some("alice"), second) {
+            Err(Error::RateLimited { limit, .. }) => assert_eq!(limit, "tenantRequestsPerSecond"),
+            _ => panic!("the connections of a tenant should share its limit"),
+        }
+        assert!(limiter.acquire("getBlock", Some("bob"), second).is_ok());
+    }
+
+    #[test]
+    fn connection_ids_are_not_reused() {
+        let limiter = RateLimiter::new(1, 0, BTreeMap::new());
+        let connection = session();
+        let connection_id = limiter.connection_id(&connection).unwrap();
+        assert_eq!(limiter.connection_id(&connection).unwrap(), connection_id);
+        assert!(limiter.acquire("getBlock", None, connection_id).is_ok());
+
+        // Closing the connection forgets it
+        drop(connection);
+        {
+            let state = limiter.state.lock().unwrap();
+            assert!(state.connections.is_empty());
+            assert!(state.windows.is_empty());
+        }
+
+        let other = limiter.connection_id(&session()).unwrap();
+        assert_ne!(other, connection_id);
+        assert!(limiter.acquire("getBlock", None, other).is_ok());
+    }
+}
@@ -1,74 +1,194 @@
+use std::sync::Arc;
+
 use futures::{future, Future};
 use jsonrpc_core::{Middleware, Params};
 use jsonrpc_pubsub::{PubSubHandler, PubSubMetadata, Subscriber};
 use serde_json::json;
 
+use witnet_util::correlation::{self, Correlated, CorrelationId};
+
 use super::*;
+use crate::{actors::Traced, audit};
 
-/// Helper macro to add multiple JSON-RPC methods at once
+/// Helper macro to add multiple JSON-RPC methods at once.
+///
+/// Requests without the API key of a tenant, if the wallet serves several tenants, or exceeding
+/// the limits of the given rate limiter for their connection are rejected, and all of them are
+/// recorded in the given audit log, if any. Each request gets a new correlation id, which is sent
+/// along with the actor message so the logs of the request can be related, as well as the tenant
+/// of the request. The connection is taken from the metadata of the request, of type `T` as in
+/// `connect_routes`.
 macro_rules! routes {
-    ($io:expr, $api:expr $(,)?) => {};
-    ($io:expr, $api:expr, ($wiki:expr, $method_jsonrpc:expr, $actor_msg:ty $(,)?), $($args:tt)*) => {
+    ($io:expr, $api:expr, $limiter:expr, $tenants:expr, $audit:expr $(,)?) => {};
+    ($io:expr, $api:expr, $limiter:expr, $tenants:expr, $audit:expr, ($wiki:expr, $method_jsonrpc:expr, $actor_msg:ty $(,)?), $($args:tt)*) => {
         {
             let api_addr = $api.clone();
-            $io.add_method($method_jsonrpc, move |params: Params| {
+            let rate_limiter: Arc<RateLimiter> = $limiter;
+            let tenants: Arc<Tenants> = $tenants;
+            let audit_log: Option<Arc<audit::AuditLog>> = $audit;
+            $io.add_method_with_meta($method_jsonrpc, move |mut params: Params, meta: T| {
+                let correlation_id = CorrelationId::new();
+                let _entered = correlation::enter(correlation_id);
                 log::debug!("Handling request for method: {}", $method_jsonrpc);
                 let addr = api_addr.clone();
-                // Try to parse the request params into the actor message
-                future::result(params.parse::<$actor_msg>())
-                    .map_err(|mut err| {
-                        err.data = Some(json!({
-                            "schema": format!("https://github.com/witnet/witnet-rust/wiki/{}", $wiki)
-                        }));
-
-                        err
+                // The API key is removed from the params before recording them
+                let tenant = tenants.resolve(&mut params);
+                let session_id = session_id(&params);
+                let audit_entry = audit_log.clone().map(|audit_log| {
+                    (audit_log, audit::Entry::new($method_jsonrpc, &params))
+                });
+                // The permit is kept until the request is finished
+                let permit = tenant.and_then(|tenant| {
+                    acquire_permit(&rate_limiter, $method_jsonrpc, &tenant, &meta)
+                        .map(|permit| (tenant, permit))
+                });
+                // The request is handled with its correlation id until it is finished
+                let request = future::result(permit)
+                    .map_err(|err| -> jsonrpc_core::Error { err.into() })
+                    .and_then(move |(tenant, permit)| {
+                        // Try to parse the request params into the actor message
+                        params
+                            .parse::<$actor_msg>()
+                            .map(|msg| (tenant, permit, msg))
+                            .map_err(|mut err| {
+                                err.data = Some(json!({
+                                    "schema": format!("https://github.com/witnet/witnet-rust/wiki/{}", $wiki)
+                                }));
+
+                                err
+                            })
                     })
-                    .and_then(move |msg| {
+                    .and_then(move |(tenant, permit, msg)| {
                         log::trace!("=> Handling Request: {:?}", &msg);
+                        // The sessions of a tenant can only be used with its API key
+                        let authorized = match (tenant.clone(), session_id) {
+                            (Some(tenant), Some(session_id)) => future::Either::A(
+                                addr.send(AuthorizeSession(tenant, session_id.into())).flatten()
+                            ),
+                            _ => future::Either::B(future::ok(())),
+                        };
                         // Then send the parsed message to the actor
-                        addr.send(msg)
-                            .flatten()
+                        authorized
+                            .and_then(move |()| {
+                                addr.send(Traced::new(Tenanted(tenant, msg)))
+                                    .flatten()
+                            })
                             .and_then(
                                 |x|
                                 future::result(serde_json::to_value(x))
                                     .map_err(internal_error)
                             )
                             .map_err(|err| err.into())
+                            .then(move |result| {
+                                drop(permit);
+
+                                result
+                            })
                     })
+                    .then(move |result| {
+                        log::debug!("Finished request for method: {}", $method_jsonrpc);
+                        record_audit_entry(audit_entry, &result);
+
+                        result
+                    });
+
+                Correlated::with_id(Some(correlation_id), request)
             });
         }
-        routes!($io, $api, $($args)*);
+        routes!($io, $api, $limiter, $tenants, $audit, $($args)*);
     };
 }
 
-/// Macro to add multiple JSON-RPC methods that forward the request to the Node at once
+/// Macro to add multiple JSON-RPC methods that forward the request to the Node at once.
+///
+/// Requests are rejected, limited and recorded in the given audit log, if any, like in `routes`.
 macro_rules! forwarded_routes {
-    ($io:expr, $api:expr $(,)?) => {};
-    ($io:expr, $api:expr, $method:expr, $($args:tt)*) => {
+    ($io:expr, $api:expr, $limiter:expr, $tenants:expr, $audit:expr $(,)?) => {};
+    ($io:expr, $api:expr, $limiter:expr, $tenants:expr, $audit:expr, $method:expr, $($args:tt)*) => {
         {
             let api_addr = $api.clone();
-            $io.add_method($method, move |params: Params| {
+            let rate_limiter: Arc<RateLimiter> = $limiter;
+            let tenants: Arc<Tenants> = $tenants;
+            let audit_log: Option<Arc<audit::AuditLog>> = $audit;
+            $io.add_method_with_meta($method, move |mut params: Params, meta: T| {
+                let correlation_id = CorrelationId::new();
+                let _entered = correlation::enter(correlation_id);
                 log::debug!("Forwarding request for method: {}", $method);
-                let msg = ForwardRequest {
-                    method: $method.to_string(),
-                    params
-                };
-                api_addr.send(msg)
-                    .flatten()
+                let api_addr = api_addr.clone();
+                // The API key is removed from the params before recording them
+                let tenant = tenants.resolve(&mut params);
+                let audit_entry = audit_log.clone().map(|audit_log| {
+                    (audit_log, audit::Entry::new($method, &params))
+                });
+                // The permit is kept until the request is finished
+                let permit = tenant
+                    .and_then(|tenant| acquire_permit(&rate_limiter, $method, &tenant, &meta));
+                let request = future::result(permit)
+                    .and_then(move |permit| {
+                        let msg = ForwardRequest {
+                            method: $method.to_string(),
+                            params
+                        };
+
+                        api_addr.send(Traced::new(msg)).flatten().then(move |result| {
+                            drop(permit);
+
+                            result
+                        })
+                    })
                     .and_then(|x| {
                         future::result(serde_json::to_value(x)).map_err(internal_error)
                     })
                     .map_err(|err| err.into())
+                    .then(move |result| {
+                        log::debug!("Finished forwarding request for method: {}", $method);
+                        record_audit_entry(audit_entry, &result);
+
+                        result
+                    });
+
+                Correlated::with_id(Some(correlation_id), request)
             });
         }
-        forwarded_routes!($io, $api, $($args)*);
+        forwarded_routes!($io, $api, $limiter, $tenants, $audit, $($args)*);
     };
 }
 
+/// Check the rate limits of a request made by a tenant through the connection of the given
+/// request metadata, returning the permit that must be kept until the request is finished.
+fn acquire_permit<T: PubSubMetadata>(
+    rate_limiter: &RateLimiter,
+    method: &str,
+    tenant: &Option<String>,
+    meta: &T,
+) -> Result<Permit> {
+    let session = meta
+        .session()
+        .ok_or_else(|| Error::Internal(failure::format_err!("request without a connection")))?;
+    let connection_id = rate_limiter.connection_id(&session)?;
+
+    rate_limiter.acquire(method, tenant.as_ref().map(String::as_str), connection_id)
+}
+
+/// Record the result of a request in the audit log it has an entry for, if any.
+fn record_audit_entry(
+    audit_entry: Option<(Arc<audit::AuditLog>, audit::Entry)>,
+    result: &std::result::Result<serde_json::Value, jsonrpc_core::Error>,
+) {
+    if let Some((audit_log, entry)) = audit_entry {
+        if let Err(err) = audit_log.record(entry.with_result(result)) {
+            log::error!("Failed to record request in the audit log: {}", err);
+        }
+    }
+}
+
 pub fn connect_routes<T, S>(
     handler: &mut PubSubHandler<T, S>,
     api: Addr<App>,
     system_arbiter: Arbiter,
+    rate_limiter: Arc<RateLimiter>,
+    tenants: Arc<Tenants>,
+    audit_log: Option<Arc<audit::AuditLog>>,
 ) where
     T: PubSubMetadata,
     S: Middleware<T>,
@@ -77,15 +197,45 @@ pub fn connect_routes<T, S>(
         "notifications",
         ("subscribeNotifications", {
             let addr = api.clone();
-            move |params: Params, _meta, subscriber: Subscriber| {
+            let tenants = tenants.clone();
+            move |mut params: Params, _meta, subscriber: Subscriber| {
+                let addr_authorize = addr.clone();
                 let addr_subscription_id = addr.clone();
                 let addr_subscribe = addr.clone();
-                let f = future::result(params.parse::<SubscribeRequest>())
+                let request = tenants
+                    .resolve(&mut params)
+                    .map_err(|err| err.into())
+                    .and_then(|tenant| {
+                        params
+                            .parse::<SubscribeRequest>()
+                            .map(|request| (tenant, request))
+                            .map_err(|mut err| {
+                                log::trace!("invalid subscription params");
+
+                                err.data = Some(json!({
+                                    "schema": "https://github.com/witnet/witnet-rust/wiki/Subscribe-Notifications".to_string()
+                                }));
+                                err
+                            })
+                    });
+                let f = future::result(request)
                     .then(move |result| match result {
-                        Ok(request) =>
+                        Ok((tenant, request)) =>
                             future::Either::A({
-                                addr_subscription_id.send(NextSubscriptionId(request.session_id.clone()))
-                                .flatten()
+                                // The sessions of a tenant can only be used with its API key
+                                let authorized = match tenant {
+                                    Some(tenant) => future::Either::A(
+                                        addr_authorize
+                                            .send(AuthorizeSession(tenant, request.session_id.clone()))
+                                            .flatten()
+                                    ),
+                                    None => future::Either::B(future::ok(())),
+                                };
+                                let session_id = request.session_id.clone();
+                                authorized
+                                .and_then(move |()| {
+                                    addr_subscription_id.send(NextSubscriptionId(session_id)).flatten()
+                                })
                                 .map_err(|err| err.into())
                                 .then(move |result| match result {
                                     Ok(subscription_id) => future::Either::A(
@@ -110,15 +260,7 @@ pub fn connect_routes<T, S>(
                                     )
                                 })
                         }),
-                        Err(mut err) =>
-                            future::Either::B(subscriber.reject_async({
-                                log::trace!("invalid subscription params");
-
-                                err.data = Some(json!({
-                                    "schema": "https://github.com/witnet/witnet-rust/wiki/Subscribe-Notifications".to_string()
-                                }));
-                                err
-                            }))
+                        Err(err) => future::Either::B(subscriber.reject_async(err)),
                     });
 
                 system_arbiter.send(f);
@@ -138,32 +280,128 @@ pub fn connect_routes<T, S>(
     forwarded_routes!(
         handler,
         api,
+        rate_limiter.clone(),
+        tenants.clone(),
+        None,
         "getBlock",
         "getBlockChain",
         "getOutput",
+    );
+
+    // Forwarded methods that change the state of the node, recorded in the audit log
+    forwarded_routes!(
+        handler,
+        api,
+        rate_limiter.clone(),
+        tenants.clone(),
+        audit_log.clone(),
         "inventory",
     );
 
     routes!(
         handler,
         api,
+        rate_limiter.clone(),
+        tenants.clone(),
+        None,
         ("Get-Wallet-Infos", "getWalletInfos", WalletInfosRequest),
         (
             "Create-Mnemonics",
             "createMnemonics",
             CreateMnemonicsRequest
         ),
-        ("Import-Seed", "importSeed", ImportSeedRequest),
-        ("Create-Wallet", "createWallet", CreateWalletRequest),
-        ("Lock-Wallet", "lockWallet", LockWalletRequest),
-        ("Unlock-Wallet", "unlockWallet", UnlockWalletRequest),
-        ("Lock-Wallet", "lockWallet", LockWalletRequest),
-        ("Close-Session", "closeSession", CloseSessionRequest),
+        (
+            "Validate-Mnemonics",
+            "validateMnemonics",
+            ValidateMnemonicsRequest
+        ),
         (
             "Get-Transactions",
             "getTransactions",
             GetTransactionsRequest
         ),
+        (
+            "Search-Movements",
+            "searchMovements",
+            SearchMovementsRequest
+        ),
+        ("Get-Addresses", "getAddresses", GetAddressesRequest),
+        (
+            "Export-Address-Report",
+            "exportAddressReport",
+            ExportAddressReportRequest
+        ),
+        ("Get-Block-Info", "getBlockInfo", GetBlockInfoRequest),
+        (
+            "Generate-Statement",
+            "generateStatement",
+            GenerateStatementRequest
+        ),
+        (
+            "Create-Payment-Uri",
+            "createPaymentUri",
+            CreatePaymentUriRequest
+        ),
+        (
+            "Parse-Payment-Uri",
+            "parsePaymentUri",
+            ParsePaymentUriRequest
+        ),
+        (
+            "Get-Recurring-Payments",
+            "getRecurringPayments",
+            GetRecurringPaymentsRequest
+        ),
+        (
+            "Get-Scheduled-Payments",
+            "getScheduledPayments",
+            GetScheduledPaymentsRequest
+        ),
+        ("Get-Contacts", "getContacts", GetContactsRequest),
+        ("Get-Utxos", "getUtxos", GetUtxosRequest),
+        ("Get-Deposits", "getDeposits", GetDepositsRequest),
+        (
+            "Get-Watched-Addresses",
+            "getWatchedAddresses",
+            GetWatchedAddressesRequest
+        ),
+        ("Run-Rad-Request", "runRadRequest", RunRadReqRequest),
+        (
+            "Describe-Data-Request",
+            "describeDataRequest",
+            DescribeDataReqRequest
+        ),
+        ("Get", "get", GetRequest),
+        (
+            "Get-Wallet-Metrics",
+            "getWalletMetrics",
+            GetWalletMetricsRequest
+        ),
+        (
+            "Get-Wallet-Storage-Stats",
+            "getWalletStorageStats",
+            GetWalletStorageStatsRequest
+        ),
+        (
+            "Get-Notifications-Since",
+            "getNotificationsSince",
+            GetNotificationsSinceRequest
+        ),
+    );
+
+    // State-changing (or key-revealing) methods, recorded in the audit log
+    routes!(
+        handler,
+        api,
+        rate_limiter.clone(),
+        tenants.clone(),
+        audit_log,
+        ("Import-Seed", "importSeed", ImportSeedRequest),
+        ("Create-Wallet", "createWallet", CreateWalletRequest),
+        ("Recover-Wallet", "recoverWallet", RecoverWalletRequest),
+        ("Lock-Wallet", "lockWallet", LockWalletRequest),
+        ("Unlock-Wallet", "unlockWallet", UnlockWalletRequest),
+        ("Close-Session", "closeSession", CloseSessionRequest),
         ("Send-Vtt", "sendVTT", SendVttRequest),
         (
             "Send-Transaction",
@@ -175,16 +413,69 @@ pub fn connect_routes<T, S>(
             "generateAddress",
             GenerateAddressRequest
         ),
-        ("Get-Addresses", "getAddresses", GetAddressesRequest),
+        ("Export-Xprv", "exportXprv", ExportXprvRequest),
+        ("Export-Xpub", "exportXpub", ExportXpubRequest),
+        (
+            "Get-Address-Proof",
+            "getAddressProof",
+            GetAddressProofRequest
+        ),
+        (
+            "Export-Plaintext",
+            "exportPlaintext",
+            ExportPlaintextRequest
+        ),
+        (
+            "Create-Recurring-Payment",
+            "createRecurringPayment",
+            CreateRecurringPaymentRequest
+        ),
+        (
+            "Delete-Recurring-Payment",
+            "deleteRecurringPayment",
+            DeleteRecurringPaymentRequest
+        ),
+        ("Save-Contact", "saveContact", SaveContactRequest),
+        ("Delete-Contact", "deleteContact", DeleteContactRequest),
+        ("Export-Metadata", "exportMetadata", ExportMetadataRequest),
+        ("Import-Metadata", "importMetadata", ImportMetadataRequest),
+        (
+            "Register-Deposit-Addresses",
+            "registerDepositAddresses",
+            RegisterDepositAddressesRequest
+        ),
+        ("Ack-Deposits", "ackDeposits", AckDepositsRequest),
+        (
+            "Compact-Wallet-Db",
+            "compactWalletDb",
+            CompactWalletDbRequest
+        ),
+        ("Watch-Address", "watchAddress", WatchAddressRequest),
+        ("Unwatch-Address", "unwatchAddress", UnwatchAddressRequest),
+        ("Reset-Chain-Data", "resetChainData", ResetChainDataRequest),
         (
             "Create-Data-Request",
             "createDataRequest",
             CreateDataReqRequest
         ),
         ("Create-Vtt", "createVttRequest", CreateVttRequest),
-        ("Run-Rad-Request", "runRadRequest", RunRadReqRequest),
         ("Send-Data-Request", "sendDataRequest", SendDataReqRequest),
         ("Set", "set", SetRequest),
-        ("Get", "get", GetRequest),
+        ("Register-Signer", "registerSigner", RegisterSignerRequest),
+        (
+            "Sign-Transaction",
+            "signTransaction",
+            SignTransactionRequest
+        ),
+        (
+            "Submit-Signatures",
+            "submitSignatures",
+            SubmitSignaturesRequest
+        ),
+        (
+            "Reject-Signature-Request",
+            "rejectSignatureRequest",
+            RejectSignatureRequestRequest
+        ),
     );
 }
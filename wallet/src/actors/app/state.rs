@@ -1,35 +1,157 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::sync::oneshot;
 
 use super::*;
+use crate::{journal, model};
 
 /// Struct to manage the App actor state and its invariants.
 #[derive(Default)]
 pub struct State {
     sessions: HashMap<types::SessionId, Session>,
     wallets: HashMap<String, types::SessionWallet>,
+    signature_requests: HashMap<u32, SignatureRequest>,
+    next_signature_request_id: u32,
+    /// Map (tenant, wallet id, method, idempotency key) -> response
+    idempotent_responses: HashMap<IdempotencyKey, IdempotentResponse>,
+    /// Network of the node, once known
+    pub node_network: Option<types::NetworkId>,
+    /// Epoch of the last block notified by the node, or of the beacon of the node when it was
+    /// last checked if no block has been notified since
+    last_block_epoch: Option<u32>,
+    /// Whether the sessions were notified that the wallet stopped receiving blocks from the node
+    node_stalled: bool,
+    /// Whether the node was synchronizing its chain when it was last checked
+    node_syncing: bool,
+}
+
+/// Outcome of checking the beacon of the node against the blocks it notified, see
+/// [check_node_beacon](State::check_node_beacon).
+#[derive(Debug, PartialEq)]
+pub enum NodeCheck {
+    /// The wallet receives the blocks of the node
+    Synced,
+    /// The node is synchronizing its chain, and it notifies no blocks meanwhile
+    Syncing,
+    /// The node finished synchronizing its chain, so the wallets have to index the blocks it
+    /// consolidated meanwhile
+    SyncFinished,
+    /// The blocks notified by the node lag too far behind its beacon. `first` tells whether the
+    /// wallet was receiving blocks until this check
+    Stalled { last_block_epoch: u32, first: bool },
 }
 
 #[derive(Default)]
 struct Session {
     wallets: HashMap<String, types::SessionWallet>,
     subscription: Option<types::Sink>,
+    /// Notifications of the session, kept while it is not subscribed too
+    journal: Arc<Mutex<journal::Journal>>,
+    /// Device of the external signer connected through the session, if any
+    signer: Option<types::SignerDevice>,
+    /// Tenant that opened the session, if any
+    tenant: Option<String>,
+}
+
+impl Session {
+    fn notifier(&self) -> journal::Notifier {
+        journal::Notifier {
+            journal: self.journal.clone(),
+            sink: self.subscription.clone(),
+        }
+    }
+}
+
+/// Signature request waiting for the response of an external signer.
+pub struct SignatureRequest {
+    pub session_id: types::SessionId,
+    pub transaction_hash: types::Hash,
+    /// Public key hash of the address of the output spent by each input
+    pub input_pkhs: Vec<types::PublicKeyHash>,
+    pub sender: oneshot::Sender<Result<Vec<types::KeyedSignature>>>,
+}
+
+/// Idempotency key of a request, scoped to the tenant and the wallet of the request, so clients of
+/// different wallets cannot get each other's responses.
+pub type IdempotencyKey = (Option<String>, String, String, String);
+
+/// Response of a request sent with an idempotency key.
+struct IdempotentResponse {
+    /// Params of the request, to detect a key reused for a different request
+    params: String,
+    /// Response of the request, once it has been handled
+    response: Option<types::Json>,
+    created: Instant,
 }
 
 impl State {
+    /// Check the beacon of the node against the last block it notified.
+    ///
+    /// The blocks of the node are not notified while it synchronizes its chain, so a lag is only
+    /// considered a stall if the node is synchronized. Once stalled, the wallet is considered
+    /// stalled again after `stall_epochs` more epochs without blocks.
+    pub fn check_node_beacon(
+        &mut self,
+        synchronized: bool,
+        node_epoch: u32,
+        stall_epochs: u32,
+    ) -> NodeCheck {
+        if !synchronized {
+            self.node_syncing = true;
+
+            return NodeCheck::Syncing;
+        }
+        if self.node_syncing {
+            self.node_syncing = false;
+            self.last_block_epoch = Some(node_epoch);
+
+            return NodeCheck::SyncFinished;
+        }
+
+        let last_block_epoch = match self.last_block_epoch {
+            Some(epoch) => epoch,
+            // No block notified yet, the wallets were synced up to this beacon when they started
+            None => {
+                self.last_block_epoch = Some(node_epoch);
+
+                return NodeCheck::Synced;
+            }
+        };
+        if node_epoch.saturating_sub(last_block_epoch) <= stall_epochs {
+            return NodeCheck::Synced;
+        }
+
+        let first = !self.node_stalled;
+        self.node_stalled = true;
+        self.last_block_epoch = Some(node_epoch);
+
+        NodeCheck::Stalled {
+            last_block_epoch,
+            first,
+        }
+    }
+
+    /// Record a block notified by the node, returning whether the wallet was stalled until then.
+    pub fn block_notified(&mut self, epoch: u32) -> bool {
+        self.last_block_epoch = Some(epoch);
+
+        std::mem::replace(&mut self.node_stalled, false)
+    }
+
     /// Get a list of wallets to which notifications can be sent
-    /// through the returned sink.
-    pub fn notifiable_wallets(&self) -> Vec<(types::SessionWallet, types::Sink)> {
+    /// through the returned notifier.
+    pub fn notifiable_wallets(&self) -> Vec<(types::SessionWallet, journal::Notifier)> {
         self.sessions
             .values()
-            .filter_map(|session| {
-                session.subscription.as_ref().map(|sink| {
-                    session
-                        .wallets
-                        .values()
-                        .map(move |wallet| (wallet.clone(), sink.clone()))
-                })
+            .flat_map(|session| {
+                let notifier = session.notifier();
+                session
+                    .wallets
+                    .values()
+                    .map(move |wallet| (wallet.clone(), notifier.clone()))
             })
-            .flatten()
             .collect()
     }
 
@@ -106,23 +228,426 @@ impl State {
         Ok(())
     }
 
-    /// Insert a new wallet into the state of the session if it is not already present.
+    /// Insert a new wallet into the state of the session if it is not already present, creating
+    /// the session for the given tenant if needed.
     pub fn create_session(
         &mut self,
         session_id: types::SessionId,
         wallet_id: String,
         wallet: types::SessionWallet,
+        tenant: Option<String>,
     ) {
         let entry = self.sessions.entry(session_id.clone());
-        let wallets = &mut entry.or_default().wallets;
+        let wallets = &mut entry
+            .or_insert_with(|| Session {
+                tenant,
+                ..Session::default()
+            })
+            .wallets;
 
         wallets.insert(wallet_id.clone(), wallet.clone());
 
         self.wallets.insert(wallet_id, wallet);
     }
 
+    /// Get the notifier of the session that unlocked a wallet, if any, preferring the
+    /// subscribed sessions.
+    pub fn wallet_notifier(&self, wallet_id: &str) -> Option<journal::Notifier> {
+        self.sessions
+            .values()
+            .filter(|session| session.wallets.contains_key(wallet_id))
+            .max_by_key(|session| session.subscription.is_some())
+            .map(Session::notifier)
+    }
+
+    /// Get the notifiers of all the sessions.
+    pub fn session_notifiers(&self) -> Vec<journal::Notifier> {
+        self.sessions.values().map(Session::notifier).collect()
+    }
+
+    /// Get the tenant that opened a session, if any.
+    pub fn session_tenant(&self, session_id: &types::SessionId) -> Result<Option<&str>> {
+        self.sessions
+            .get(session_id)
+            .map(|session| session.tenant.as_ref().map(String::as_str))
+            .ok_or_else(|| Error::SessionNotFound)
+    }
+
+    /// Check that a session can be used by a tenant: only the tenant that opened a session can
+    /// use it. Sessions of other tenants are reported as not found, like the expired ones.
+    pub fn authorize_session(&self, tenant: &str, session_id: &types::SessionId) -> Result<()> {
+        match self.session_tenant(session_id) {
+            Ok(owner) if owner != Some(tenant) => Err(Error::SessionNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that a tenant can open another session, if its sessions are limited to
+    /// `max_sessions`. Requests without a tenant are not limited, nor are tenants if
+    /// `max_sessions` is 0.
+    pub fn check_tenant_sessions(&self, tenant: Option<&str>, max_sessions: u32) -> Result<()> {
+        match tenant {
+            Some(tenant)
+                if max_sessions > 0 && self.tenant_sessions(tenant) >= max_sessions as usize =>
+            {
+                Err(Error::RateLimited {
+                    method: "unlockWallet".to_string(),
+                    limit: "tenantSessions",
+                    max: max_sessions,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the number of sessions opened by a tenant.
+    fn tenant_sessions(&self, tenant: &str) -> usize {
+        self.sessions
+            .values()
+            .filter(|session| session.tenant.as_ref().map(String::as_str) == Some(tenant))
+            .count()
+    }
+
+    /// Get the notifier of a session.
+    pub fn session_notifier(&self, session_id: &types::SessionId) -> Result<journal::Notifier> {
+        self.sessions
+            .get(session_id)
+            .map(Session::notifier)
+            .ok_or_else(|| Error::SessionNotFound)
+    }
+
+    /// Get the notifications of a session after the one with sequence number `seq`.
+    pub fn notifications_since(
+        &self,
+        session_id: &types::SessionId,
+        seq: u64,
+    ) -> Result<model::NotificationReplay> {
+        let session = self
+            .sessions
+            .get(session_id)
+            .ok_or_else(|| Error::SessionNotFound)?;
+        let journal = session
+            .journal
+            .lock()
+            .expect("notification journal lock poisoned");
+
+        Ok(journal.since(seq))
+    }
+
+    /// Get the wallets unlocked by a session.
+    pub fn session_wallets(
+        &self,
+        session_id: &types::SessionId,
+    ) -> Vec<(String, types::SessionWallet)> {
+        self.sessions
+            .get(session_id)
+            .map(|session| {
+                session
+                    .wallets
+                    .iter()
+                    .map(|(id, wallet)| (id.clone(), wallet.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Return an Iterator over the unlocked wallets.
     pub fn wallets(&self) -> impl Iterator<Item = (&String, &types::SessionWallet)> {
         self.wallets.iter()
     }
+
+    /// Register the external signer connected through a session.
+    pub fn register_signer(
+        &mut self,
+        session_id: &types::SessionId,
+        device: types::SignerDevice,
+    ) -> Result<()> {
+        match self.sessions.get_mut(session_id) {
+            Some(session) => {
+                session.signer = Some(device);
+                Ok(())
+            }
+            None => Err(Error::SessionNotFound),
+        }
+    }
+
+    /// Get the sink through which signature requests are sent to the external signer of a
+    /// session.
+    pub fn signer_sink(&self, session_id: &types::SessionId) -> Result<types::Sink> {
+        let session = self
+            .sessions
+            .get(session_id)
+            .ok_or_else(|| Error::SessionNotFound)?;
+
+        match (&session.signer, &session.subscription) {
+            (Some(_), Some(sink)) => Ok(sink.clone()),
+            _ => Err(Error::SignerNotConnected),
+        }
+    }
+
+    /// Store a signature request until the external signer responds, returning its id.
+    pub fn add_signature_request(&mut self, request: SignatureRequest) -> u32 {
+        let id = self.next_signature_request_id;
+        self.next_signature_request_id = id.wrapping_add(1);
+        self.signature_requests.insert(id, request);
+
+        id
+    }
+
+    /// Get a signature request made to the external signer of a session.
+    pub fn signature_request(
+        &self,
+        session_id: &types::SessionId,
+        request_id: u32,
+    ) -> Result<&SignatureRequest> {
+        self.signature_requests
+            .get(&request_id)
+            .filter(|request| &request.session_id == session_id)
+            .ok_or_else(|| Error::SignatureRequestNotFound)
+    }
+
+    /// Remove a signature request, so it can be resolved.
+    pub fn take_signature_request(&mut self, request_id: u32) -> Option<SignatureRequest> {
+        self.signature_requests.remove(&request_id)
+    }
+
+    /// Idempotency key of a request to `method` of a wallet, made by the current tenant.
+    pub fn idempotency_key(wallet_id: &str, method: &str, key: String) -> IdempotencyKey {
+        (
+            current_tenant(),
+            wallet_id.to_string(),
+            method.to_string(),
+            key,
+        )
+    }
+
+    /// Get the response of a previous request made with the same idempotency key, forgetting
+    /// first the responses older than `window`. If there is none, the key is taken by this
+    /// request until its response is saved with
+    /// [save_idempotent_response](State::save_idempotent_response).
+    ///
+    /// Fails if the key was used for a request with different params, or for a request that is
+    /// still being handled.
+    pub fn idempotent_response(
+        &mut self,
+        key: &IdempotencyKey,
+        params: &str,
+        window: Duration,
+    ) -> Result<Option<types::Json>> {
+        let now = Instant::now();
+        self.idempotent_responses
+            .retain(|_, response| now.duration_since(response.created) < window);
+
+        match self.idempotent_responses.get(key) {
+            Some(response) if response.params != params => Err(validation_error(field_error(
+                "idempotencyKey",
+                "The key has already been used for a different request.",
+            ))),
+            Some(IdempotentResponse { response: None, .. }) => Err(validation_error(field_error(
+                "idempotencyKey",
+                "The request with this key is still being handled.",
+            ))),
+            Some(IdempotentResponse { response, .. }) => Ok(response.clone()),
+            None => {
+                self.idempotent_responses.insert(
+                    key.clone(),
+                    IdempotentResponse {
+                        params: params.to_string(),
+                        response: None,
+                        created: now,
+                    },
+                );
+
+                Ok(None)
+            }
+        }
+    }
+
+    /// Remember the response of a request made with an idempotency key, or release the key if
+    /// the request failed, so it can be retried.
+    pub fn save_idempotent_response(
+        &mut self,
+        key: &IdempotencyKey,
+        response: Option<types::Json>,
+    ) {
+        match response {
+            Some(response) => {
+                if let Some(saved) = self.idempotent_responses.get_mut(key) {
+                    saved.response = Some(response);
+                }
+            }
+            None => {
+                self.idempotent_responses.remove(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stall_is_detected_once_until_a_block_is_notified() {
+        let mut state = State::default();
+
+        assert_eq!(state.check_node_beacon(true, 100, 3), NodeCheck::Synced);
+        assert_eq!(state.check_node_beacon(true, 103, 3), NodeCheck::Synced);
+        assert_eq!(
+            state.check_node_beacon(true, 104, 3),
+            NodeCheck::Stalled {
+                last_block_epoch: 100,
+                first: true
+            }
+        );
+        assert_eq!(state.check_node_beacon(true, 106, 3), NodeCheck::Synced);
+        assert_eq!(
+            state.check_node_beacon(true, 108, 3),
+            NodeCheck::Stalled {
+                last_block_epoch: 104,
+                first: false
+            }
+        );
+
+        assert!(state.block_notified(109));
+        assert!(!state.block_notified(110));
+        assert_eq!(state.check_node_beacon(true, 112, 3), NodeCheck::Synced);
+    }
+
+    #[test]
+    fn syncing_node_is_not_stalled() {
+        let mut state = State::default();
+        state.block_notified(100);
+
+        // The node notifies no blocks while it synchronizes its chain
+        assert_eq!(state.check_node_beacon(false, 500, 3), NodeCheck::Syncing);
+        assert_eq!(state.check_node_beacon(false, 900, 3), NodeCheck::Syncing);
+        assert_eq!(
+            state.check_node_beacon(true, 1000, 3),
+            NodeCheck::SyncFinished
+        );
+        assert_eq!(state.check_node_beacon(true, 1002, 3), NodeCheck::Synced);
+        assert!(!state.block_notified(1003));
+    }
+
+    #[test]
+    fn idempotency_keys_are_scoped_to_the_wallet() {
+        let mut state = State::default();
+        let window = Duration::from_secs(600);
+        let key_a = State::idempotency_key("wallet-a", "sendVTT", "key".to_string());
+        let key_b = State::idempotency_key("wallet-b", "sendVTT", "key".to_string());
+
+        assert_eq!(
+            state.idempotent_response(&key_a, "params", window).unwrap(),
+            None
+        );
+        state.save_idempotent_response(&key_a, Some(types::Json::from("a")));
+
+        // The same key sent for another wallet is a different request
+        assert_eq!(
+            state.idempotent_response(&key_b, "params", window).unwrap(),
+            None
+        );
+        state.save_idempotent_response(&key_b, Some(types::Json::from("b")));
+
+        assert_eq!(
+            state.idempotent_response(&key_a, "params", window).unwrap(),
+            Some(types::Json::from("a"))
+        );
+        assert_eq!(
+            state.idempotent_response(&key_b, "params", window).unwrap(),
+            Some(types::Json::from("b"))
+        );
+    }
+
+    #[test]
+    fn idempotency_key_of_a_pending_or_different_request_is_rejected() {
+        let mut state = State::default();
+        let window = Duration::from_secs(600);
+        let key = State::idempotency_key("wallet", "sendVTT", "key".to_string());
+
+        assert_eq!(
+            state.idempotent_response(&key, "params", window).unwrap(),
+            None
+        );
+        assert!(state.idempotent_response(&key, "params", window).is_err());
+
+        state.save_idempotent_response(&key, Some(types::Json::from("sent")));
+        assert!(state.idempotent_response(&key, "other", window).is_err());
+    }
+
+    #[test]
+    fn idempotency_key_of_a_failed_request_is_released() {
+        let mut state = State::default();
+        let window = Duration::from_secs(600);
+        let key = State::idempotency_key("wallet", "sendVTT", "key".to_string());
+
+        assert_eq!(
+            state.idempotent_response(&key, "params", window).unwrap(),
+            None
+        );
+        state.save_idempotent_response(&key, None);
+
+        // The request can be retried, even with different params
+        assert_eq!(
+            state.idempotent_response(&key, "other", window).unwrap(),
+            None
+        );
+    }
+
+    fn open_session(state: &mut State, session_id: &str, tenant: Option<&str>) {
+        state.sessions.insert(
+            types::SessionId::from(session_id.to_string()),
+            Session {
+                tenant: tenant.map(str::to_string),
+                ..Session::default()
+            },
+        );
+    }
+
+    #[test]
+    fn sessions_are_authorized_for_their_tenant_only() {
+        let mut state = State::default();
+        open_session(&mut state, "alice-session", Some("alice"));
+        open_session(&mut state, "shared-session", None);
+        let session_id = |id: &str| types::SessionId::from(id.to_string());
+
+        assert!(state
+            .authorize_session("alice", &session_id("alice-session"))
+            .is_ok());
+        match state.authorize_session("bob", &session_id("alice-session")) {
+            Err(Error::SessionNotFound) => {}
+            _ => panic!("a tenant used the session of another one"),
+        }
+        // Sessions opened without a tenant can't be used by tenants either
+        assert!(state
+            .authorize_session("bob", &session_id("shared-session"))
+            .is_err());
+        // Missing sessions fail later, when the request is handled
+        assert!(state
+            .authorize_session("bob", &session_id("missing-session"))
+            .is_ok());
+    }
+
+    #[test]
+    fn sessions_are_limited_per_tenant() {
+        let mut state = State::default();
+        open_session(&mut state, "alice-1", Some("alice"));
+        open_session(&mut state, "alice-2", Some("alice"));
+        open_session(&mut state, "bob-1", Some("bob"));
+        open_session(&mut state, "shared-1", None);
+
+        match state.check_tenant_sessions(Some("alice"), 2) {
+            Err(Error::RateLimited { limit, max, .. }) => {
+                assert_eq!(limit, "tenantSessions");
+                assert_eq!(max, 2);
+            }
+            _ => panic!("a tenant opened more sessions than allowed"),
+        }
+        assert!(state.check_tenant_sessions(Some("bob"), 2).is_ok());
+        assert!(state.check_tenant_sessions(Some("alice"), 3).is_ok());
+        // No limit
+        assert!(state.check_tenant_sessions(Some("alice"), 0).is_ok());
+        assert!(state.check_tenant_sessions(None, 1).is_ok());
+    }
 }
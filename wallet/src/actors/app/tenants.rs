@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use actix::dev::MessageResponse;
+use jsonrpc_core::Params;
+
+use super::*;
+
+thread_local! {
+    /// Tenant of the request being handled in the current thread
+    static CURRENT: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Tenants served by the wallet, so several applications can share the same wallet server.
+///
+/// Each tenant is identified by its API key, which must be sent in the `apiKey` param of every
+/// request. The wallets of a tenant are stored under its own prefix of the database, and its
+/// sessions can only be used with its API key, so a tenant cannot see the wallets and sessions of
+/// the others. If no tenants are configured, the wallet serves a single application and requests
+/// have no tenant.
+pub struct Tenants {
+    /// Map API key -> tenant
+    api_keys: BTreeMap<String, String>,
+}
+
+impl Tenants {
+    pub fn new(api_keys: BTreeMap<String, String>) -> Self {
+        Self { api_keys }
+    }
+
+    /// Get the tenant of a request from its API key, removing the key from the params of the
+    /// request. Params left empty without the key are handled as if the request had no params.
+    pub fn resolve(&self, params: &mut Params) -> Result<Option<String>> {
+        let api_key = match params {
+            Params::Map(map) => {
+                let api_key = map.remove("apiKey");
+                if api_key.is_some() && map.is_empty() {
+                    *params = Params::None;
+                }
+
+                api_key
+            }
+            _ => None,
+        };
+
+        if self.api_keys.is_empty() {
+            return Ok(None);
+        }
+
+        api_key
+            .as_ref()
+            .and_then(|api_key| api_key.as_str())
+            .and_then(|api_key| self.api_keys.get(api_key))
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| Error::InvalidApiKey)
+    }
+}
+
+/// Check that a session can be used with the API key of a tenant: only the tenant that opened a
+/// session can use it.
+pub struct AuthorizeSession(pub String, pub types::SessionId);
+
+impl Message for AuthorizeSession {
+    type Result = Result<()>;
+}
+
+impl Handler<AuthorizeSession> for App {
+    type Result = <AuthorizeSession as Message>::Result;
+
+    fn handle(
+        &mut self,
+        AuthorizeSession(tenant, session_id): AuthorizeSession,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.state.authorize_session(&tenant, &session_id)
+    }
+}
+
+/// Get the tenant of the request being handled by the app, if any.
+pub fn current_tenant() -> Option<String> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Wrapper of a message sent on behalf of a tenant, which is the current tenant while the app
+/// handles the message.
+pub struct Tenanted<M>(
+    /// Tenant of the originating request
+    pub Option<String>,
+    /// Wrapped message
+    pub M,
+);
+
+impl<M: Message> Message for Tenanted<M> {
+    type Result = M::Result;
+}
+
+impl<M> Handler<Tenanted<M>> for App
+where
+    M: Message,
+    App: Handler<M>,
+    <App as Handler<M>>::Result: MessageResponse<App, Tenanted<M>>,
+{
+    type Result = <App as Handler<M>>::Result;
+
+    fn handle(
+        &mut self,
+        Tenanted(tenant, msg): Tenanted<M>,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let previous = CURRENT.with(|current| current.replace(tenant));
+        let result = <App as Handler<M>>::handle(self, msg, ctx);
+        CURRENT.with(|current| current.replace(previous));
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn tenants() -> Tenants {
+        Tenants::new(
+            vec![
+                ("alice-key".to_string(), "alice".to_string()),
+                ("bob-key".to_string(), "bob".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    fn params(value: serde_json::Value) -> Params {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn tenant_is_resolved_from_the_api_key() {
+        let mut request = params(json!({"apiKey": "bob-key", "sessionId": "123"}));
+
+        assert_eq!(
+            tenants().resolve(&mut request).unwrap(),
+            Some("bob".to_string())
+        );
+        // The key is removed from the params of the request
+        assert_eq!(request, params(json!({"sessionId": "123"})));
+
+        // Params left empty are handled as no params
+        let mut request = params(json!({"apiKey": "alice-key"}));
+        assert_eq!(
+            tenants().resolve(&mut request).unwrap(),
+            Some("alice".to_string())
+        );
+        assert_eq!(request, Params::None);
+    }
+
+    #[test]
+    fn unknown_or_missing_api_keys_are_rejected() {
+        let invalid = |mut request: Params| match tenants().resolve(&mut request) {
+            Err(Error::InvalidApiKey) => true,
+            _ => false,
+        };
+
+        assert!(invalid(params(json!({"apiKey": "carol-key"}))));
+        assert!(invalid(params(json!({"apiKey": 1}))));
+        assert!(invalid(params(json!({"sessionId": "123"}))));
+        assert!(invalid(params(json!(["alice-key"]))));
+        assert!(invalid(Params::None));
+    }
+
+    #[test]
+    fn requests_have_no_tenant_without_tenants() {
+        let tenants = Tenants::new(BTreeMap::new());
+        let mut request = params(json!({"apiKey": "alice-key", "sessionId": "123"}));
+
+        assert_eq!(tenants.resolve(&mut request).unwrap(), None);
+        assert_eq!(request, params(json!({"sessionId": "123"})));
+        assert_eq!(tenants.resolve(&mut Params::None).unwrap(), None);
+    }
+}
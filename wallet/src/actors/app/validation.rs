@@ -1,3 +1,11 @@
+use std::convert::TryInto as _;
+
+use bech32::FromBase32 as _;
+
+use witnet_crypto::signature;
+
+use crate::{model, types};
+
 /// A list of errors. An error is a pair of (field, error msg).
 pub type ValidationErrors = Vec<(String, String)>;
 
@@ -25,3 +33,199 @@ where
         (Ok(a), Ok(b)) => Ok(combinator(a, b)),
     }
 }
+
+/// Check that an address is a bech32-encoded public key hash of the current environment, that is,
+/// `twit` addresses for testnet and `wit` addresses otherwise.
+pub fn validate_address<F: ToString>(
+    field: F,
+    address: String,
+    testnet: bool,
+) -> Result<String, ValidationErrors> {
+    let expected_hrp = if testnet { "twit" } else { "wit" };
+
+    match bech32::decode(&address) {
+        Ok((hrp, data)) => {
+            if hrp != expected_hrp {
+                Err(field_error(
+                    field,
+                    format!("Address must start with \"{}\".", expected_hrp),
+                ))
+            } else if Vec::<u8>::from_base32(&data).ok().map(|pkh| pkh.len()) != Some(20) {
+                Err(field_error(
+                    field,
+                    "Address is not a valid public key hash.",
+                ))
+            } else {
+                Ok(address)
+            }
+        }
+        Err(_) => Err(field_error(field, "Address is not a valid bech32 string.")),
+    }
+}
+
+/// Check the signatures returned by an external signer for the inputs of a transaction, returning
+/// them ordered by input index.
+///
+/// To be valid there must be exactly one signature for each input, and every signature must be a
+/// valid signature of the transaction hash by the given public key, which must hash to the public
+/// key hash of the address of the output spent by the input, given in `input_pkhs`.
+pub fn validate_signatures<F: ToString>(
+    field: F,
+    signatures: Vec<model::ExternalSignature>,
+    transaction_hash: types::Hash,
+    input_pkhs: &[types::PublicKeyHash],
+) -> Result<Vec<types::KeyedSignature>, ValidationErrors> {
+    let field = field.to_string();
+    let inputs = input_pkhs.len();
+    if signatures.len() != inputs {
+        return Err(field_error(
+            &field,
+            format!("Expected {} signatures, got {}.", inputs, signatures.len()),
+        ));
+    }
+
+    let mut keyed_signatures: Vec<Option<types::KeyedSignature>> = vec![None; inputs];
+    for model::ExternalSignature {
+        index,
+        signature,
+        public_key,
+    } in signatures
+    {
+        let keyed_signature = parse_signature(&signature, &public_key, transaction_hash)
+            .map_err(|msg| field_error(&field, format!("Input {}: {}", index, msg)))?;
+        if let Some(input_pkh) = input_pkhs.get(index as usize) {
+            if keyed_signature.public_key.pkh() != *input_pkh {
+                return Err(field_error(
+                    &field,
+                    format!(
+                        "Input {}: Public key does not match the address of the spent output.",
+                        index
+                    ),
+                ));
+            }
+        }
+        match keyed_signatures.get_mut(index as usize) {
+            Some(slot @ None) => *slot = Some(keyed_signature),
+            Some(Some(_)) => {
+                return Err(field_error(
+                    &field,
+                    format!("Input {} is signed more than once.", index),
+                ))
+            }
+            None => {
+                return Err(field_error(
+                    &field,
+                    format!("Input {} does not exist.", index),
+                ))
+            }
+        }
+    }
+
+    // every index was checked to be in range and signed once, so no input is missing
+    Ok(keyed_signatures.into_iter().flatten().collect())
+}
+
+/// Decode a hex-encoded DER signature and public key, checking that they sign the given hash.
+fn parse_signature(
+    signature: &str,
+    public_key: &str,
+    hash: types::Hash,
+) -> Result<types::KeyedSignature, &'static str> {
+    let der = hex::decode(signature).map_err(|_| "Signature is not a valid hex string.")?;
+    let public_key = hex::decode(public_key)
+        .ok()
+        .and_then(|bytes| types::PublicKey::try_from_slice(&bytes).ok())
+        .ok_or("Public key is not a valid hex-encoded compressed public key.")?;
+    let signature = types::Signature::Secp256k1(types::Secp256k1Signature { der });
+
+    let secp_signature: signature::Signature = signature
+        .clone()
+        .try_into()
+        .map_err(|_| "Signature is not a valid DER signature.")?;
+    let secp_public_key = public_key
+        .clone()
+        .try_into()
+        .map_err(|_| "Public key is not a valid secp256k1 public key.")?;
+    signature::verify(&secp_public_key, hash.as_ref(), &secp_signature)
+        .map_err(|_| "Signature does not match the transaction hash and public key.")?;
+
+    Ok(types::KeyedSignature {
+        signature,
+        public_key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use witnet_crypto::secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+    use super::*;
+
+    fn external_signature(index: u32, key: u8, hash: types::Hash) -> model::ExternalSignature {
+        let secret_key = SecretKey::from_slice(&[key; 32]).unwrap();
+        let public_key = PublicKey::from_secret_key(&Secp256k1::new(), &secret_key);
+
+        model::ExternalSignature {
+            index,
+            signature: hex::encode(signature::sign(secret_key, hash.as_ref()).serialize_der()),
+            public_key: hex::encode(&public_key.serialize()[..]),
+        }
+    }
+
+    fn pkh(key: u8) -> types::PublicKeyHash {
+        let secret_key = SecretKey::from_slice(&[key; 32]).unwrap();
+
+        types::PublicKey::from(PublicKey::from_secret_key(&Secp256k1::new(), &secret_key)).pkh()
+    }
+
+    #[test]
+    fn signatures_are_ordered_by_input() {
+        let hash = types::Hash::SHA256([1; 32]);
+        let signatures = vec![
+            external_signature(1, 0xbb, hash),
+            external_signature(0, 0xaa, hash),
+        ];
+
+        let keyed_signatures =
+            validate_signatures("signatures", signatures, hash, &[pkh(0xaa), pkh(0xbb)]).unwrap();
+        let pkhs: Vec<_> = keyed_signatures
+            .iter()
+            .map(|keyed_signature| keyed_signature.public_key.pkh())
+            .collect();
+        assert_eq!(pkhs, vec![pkh(0xaa), pkh(0xbb)]);
+    }
+
+    #[test]
+    fn signatures_must_be_made_by_the_keys_of_the_inputs() {
+        let hash = types::Hash::SHA256([1; 32]);
+
+        // A valid signature by a key other than the one of the address of the spent output
+        let signatures = vec![external_signature(0, 0xcc, hash)];
+        assert!(validate_signatures("signatures", signatures, hash, &[pkh(0xaa)]).is_err());
+
+        // A signature of another hash
+        let signatures = vec![external_signature(0, 0xaa, types::Hash::SHA256([2; 32]))];
+        assert!(validate_signatures("signatures", signatures, hash, &[pkh(0xaa)]).is_err());
+    }
+
+    #[test]
+    fn every_input_is_signed_once() {
+        let hash = types::Hash::SHA256([1; 32]);
+        let input_pkhs = [pkh(0xaa), pkh(0xaa)];
+
+        let signatures = vec![external_signature(0, 0xaa, hash)];
+        assert!(validate_signatures("signatures", signatures, hash, &input_pkhs).is_err());
+
+        let signatures = vec![
+            external_signature(0, 0xaa, hash),
+            external_signature(0, 0xaa, hash),
+        ];
+        assert!(validate_signatures("signatures", signatures, hash, &input_pkhs).is_err());
+
+        let signatures = vec![
+            external_signature(0, 0xaa, hash),
+            external_signature(2, 0xaa, hash),
+        ];
+        assert!(validate_signatures("signatures", signatures, hash, &input_pkhs).is_err());
+    }
+}
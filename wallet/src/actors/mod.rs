@@ -1,7 +1,9 @@
 pub mod app;
 pub mod controller;
+pub mod traced;
 pub mod worker;
 
 pub use app::App;
 pub use controller::Controller;
+pub use traced::Traced;
 pub use worker::Worker;
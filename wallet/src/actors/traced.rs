@@ -0,0 +1,61 @@
+use actix::{dev::MessageResponse, prelude::*};
+
+use witnet_util::correlation::{self, CorrelationId};
+
+use crate::actors::{App, Worker};
+
+/// Wrapper of a message that carries the correlation id of the JSON-RPC request that originated
+/// it, so the logs of the actors handling it can be related to the request.
+///
+/// It has the same shape as the `Traced` message of the node, which can not be reused here because
+/// the node depends on a different version of actix.
+pub struct Traced<M> {
+    /// Correlation id of the originating request, if any
+    pub correlation_id: Option<CorrelationId>,
+    /// Wrapped message
+    pub message: M,
+}
+
+impl<M> Traced<M> {
+    /// Wrap a message using the correlation id of the request being handled in this thread
+    pub fn new(message: M) -> Self {
+        Self {
+            correlation_id: correlation::current(),
+            message,
+        }
+    }
+}
+
+impl<M: Message> Message for Traced<M> {
+    type Result = M::Result;
+}
+
+impl<M> Handler<Traced<M>> for App
+where
+    M: Message,
+    App: Handler<M>,
+    <App as Handler<M>>::Result: MessageResponse<App, Traced<M>>,
+{
+    type Result = <App as Handler<M>>::Result;
+
+    fn handle(&mut self, msg: Traced<M>, ctx: &mut Self::Context) -> Self::Result {
+        let _entered = msg.correlation_id.map(correlation::enter);
+
+        <App as Handler<M>>::handle(self, msg.message, ctx)
+    }
+}
+
+impl<M> Handler<Traced<M>> for Worker
+where
+    M: Message,
+    Worker: Handler<M>,
+    <Worker as Handler<M>>::Result: MessageResponse<Worker, Traced<M>>,
+{
+    type Result = <Worker as Handler<M>>::Result;
+
+    fn handle(&mut self, msg: Traced<M>, ctx: &mut Self::Context) -> Self::Result {
+        let _entered = msg.correlation_id.map(correlation::enter);
+
+        <Worker as Handler<M>>::handle(self, msg.message, ctx)
+    }
+}
@@ -23,6 +23,14 @@ pub enum Error {
     WrongPassword,
     #[fail(display = "wallet not found")]
     WalletNotFound,
+    #[fail(display = "wallet already exists")]
+    WalletAlreadyExists,
+    #[fail(display = "no remote storage is configured")]
+    RemoteStorageDisabled,
+    #[fail(display = "remote copy of the wallet has records of other wallets")]
+    InvalidReplica,
+    #[fail(display = "remote copy of the wallet is missing its batch {}", _0)]
+    IncompleteReplica(u64),
     #[fail(display = "send error: {}", _0)]
     Send(#[cause] futures::sync::mpsc::SendError<std::string::String>),
 }
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct AckDeposits(
+    pub types::SessionWallet,
+    /// Sequence number of the last processed deposit
+    pub u64,
+);
+
+impl Message for AckDeposits {
+    type Result = worker::Result<u64>;
+}
+
+impl Handler<AckDeposits> for worker::Worker {
+    type Result = <AckDeposits as Message>::Result;
+
+    fn handle(
+        &mut self,
+        AckDeposits(wallet, cursor): AckDeposits,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.ack_deposits(&wallet, cursor)
+    }
+}
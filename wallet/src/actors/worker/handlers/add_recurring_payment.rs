@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct AddRecurringPayment(pub types::SessionWallet, pub model::RecurringPayment);
+
+impl Message for AddRecurringPayment {
+    type Result = worker::Result<model::RecurringPayment>;
+}
+
+impl Handler<AddRecurringPayment> for worker::Worker {
+    type Result = <AddRecurringPayment as Message>::Result;
+
+    fn handle(
+        &mut self,
+        AddRecurringPayment(wallet, payment): AddRecurringPayment,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.add_recurring_payment(&wallet, payment)
+    }
+}
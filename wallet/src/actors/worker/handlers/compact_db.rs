@@ -0,0 +1,18 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct CompactDb(pub types::SessionWallet);
+
+impl Message for CompactDb {
+    type Result = worker::Result<model::StorageCompaction>;
+}
+
+impl Handler<CompactDb> for worker::Worker {
+    type Result = <CompactDb as Message>::Result;
+
+    fn handle(&mut self, CompactDb(wallet): CompactDb, _ctx: &mut Self::Context) -> Self::Result {
+        self.compact_db(&wallet)
+    }
+}
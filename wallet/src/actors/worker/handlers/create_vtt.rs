@@ -0,0 +1,36 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct CreateVtt {
+    pub wallet: types::SessionWallet,
+    /// Address paid by the transaction
+    pub address: String,
+    pub amount: types::Nanowits,
+    pub fee: types::Nanowits,
+    /// Address paid the change, the one chosen by the params of the wallet if not given
+    pub change_address: Option<types::ChangeAddressPolicy>,
+}
+
+impl Message for CreateVtt {
+    type Result = worker::Result<types::VTTransaction>;
+}
+
+impl Handler<CreateVtt> for worker::Worker {
+    type Result = <CreateVtt as Message>::Result;
+
+    fn handle(
+        &mut self,
+        CreateVtt {
+            wallet,
+            address,
+            amount,
+            fee,
+            change_address,
+        }: CreateVtt,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.create_vtt(&wallet, &address, amount, fee, change_address)
+    }
+}
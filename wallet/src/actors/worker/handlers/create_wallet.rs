@@ -10,6 +10,8 @@ pub struct CreateWallet(
     pub Option<String>,
     pub types::Password,
     pub types::SeedSource,
+    /// Tenant that owns the wallet
+    pub Option<String>,
 );
 
 impl Message for CreateWallet {
@@ -21,9 +23,15 @@ impl Handler<CreateWallet> for worker::Worker {
 
     fn handle(
         &mut self,
-        CreateWallet(name, caption, password, seed_source): CreateWallet,
+        CreateWallet(name, caption, password, seed_source, tenant): CreateWallet,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.create_wallet(name, caption, password.as_ref(), &seed_source)
+        self.create_wallet(
+            tenant.as_ref().map(String::as_str),
+            name,
+            caption,
+            password.as_ref(),
+            &seed_source,
+        )
     }
 }
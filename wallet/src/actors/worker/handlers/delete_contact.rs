@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct DeleteContact(
+    pub types::SessionWallet,
+    /// Address
+    pub String,
+);
+
+impl Message for DeleteContact {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<DeleteContact> for worker::Worker {
+    type Result = <DeleteContact as Message>::Result;
+
+    fn handle(
+        &mut self,
+        DeleteContact(wallet, address): DeleteContact,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.delete_contact(&wallet, &address)
+    }
+}
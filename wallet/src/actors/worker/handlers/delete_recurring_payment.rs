@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct DeleteRecurringPayment(
+    pub types::SessionWallet,
+    /// Recurring payment id
+    pub u32,
+);
+
+impl Message for DeleteRecurringPayment {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<DeleteRecurringPayment> for worker::Worker {
+    type Result = <DeleteRecurringPayment as Message>::Result;
+
+    fn handle(
+        &mut self,
+        DeleteRecurringPayment(wallet, id): DeleteRecurringPayment,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.delete_recurring_payment(&wallet, id)
+    }
+}
@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct ExportMetadata(pub types::SessionWallet);
+
+impl Message for ExportMetadata {
+    type Result = worker::Result<model::SignedMetadata>;
+}
+
+impl Handler<ExportMetadata> for worker::Worker {
+    type Result = <ExportMetadata as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ExportMetadata(wallet): ExportMetadata,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.export_metadata(&wallet)
+    }
+}
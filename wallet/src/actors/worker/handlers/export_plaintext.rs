@@ -0,0 +1,33 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct ExportPlaintext(
+    /// Wallet id
+    pub String,
+    /// Wallet password
+    pub types::Password,
+    /// Tenant that owns the wallet
+    pub Option<String>,
+);
+
+impl Message for ExportPlaintext {
+    type Result = worker::Result<Vec<model::PlaintextRecord>>;
+}
+
+impl Handler<ExportPlaintext> for worker::Worker {
+    type Result = <ExportPlaintext as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ExportPlaintext(wallet_id, password, tenant): ExportPlaintext,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.export_plaintext(
+            tenant.as_ref().map(String::as_str),
+            &wallet_id,
+            password.as_ref(),
+        )
+    }
+}
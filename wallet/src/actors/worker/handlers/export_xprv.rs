@@ -0,0 +1,40 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct ExportXprv(
+    pub types::SessionWallet,
+    /// Wallet id
+    pub String,
+    /// Wallet password
+    pub types::Password,
+    pub types::XprvDepth,
+    /// Backup password
+    pub types::Password,
+    /// Tenant that owns the wallet
+    pub Option<String>,
+);
+
+impl Message for ExportXprv {
+    type Result = worker::Result<String>;
+}
+
+impl Handler<ExportXprv> for worker::Worker {
+    type Result = <ExportXprv as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ExportXprv(wallet, wallet_id, wallet_password, depth, password, tenant): ExportXprv,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.export_xprv(
+            &wallet,
+            tenant.as_ref().map(String::as_str),
+            &wallet_id,
+            wallet_password.as_ref(),
+            depth,
+            password.as_ref(),
+        )
+    }
+}
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct ExportXpub(
+    pub types::SessionWallet,
+    /// Account index
+    pub u32,
+);
+
+impl Message for ExportXpub {
+    type Result = worker::Result<String>;
+}
+
+impl Handler<ExportXpub> for worker::Worker {
+    type Result = <ExportXpub as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ExportXpub(wallet, account): ExportXpub,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.export_xpub(&wallet, account)
+    }
+}
@@ -3,7 +3,7 @@ use actix::prelude::*;
 use crate::actors::worker;
 use crate::types;
 
-pub struct GenMnemonic(pub types::MnemonicLength);
+pub struct GenMnemonic(pub types::MnemonicLength, pub types::MnemonicLang);
 
 impl Message for GenMnemonic {
     type Result = String;
@@ -14,9 +14,9 @@ impl Handler<GenMnemonic> for worker::Worker {
 
     fn handle(
         &mut self,
-        GenMnemonic(length): GenMnemonic,
+        GenMnemonic(length, language): GenMnemonic,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.gen_mnemonic(length)
+        self.gen_mnemonic(length, language)
     }
 }
@@ -0,0 +1,28 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetAddressProof(
+    pub types::SessionWallet,
+    /// Address index
+    pub u32,
+    /// Challenge
+    pub String,
+);
+
+impl Message for GetAddressProof {
+    type Result = worker::Result<model::AddressProof>;
+}
+
+impl Handler<GetAddressProof> for worker::Worker {
+    type Result = <GetAddressProof as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetAddressProof(wallet, index, challenge): GetAddressProof,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.address_proof(&wallet, index, challenge)
+    }
+}
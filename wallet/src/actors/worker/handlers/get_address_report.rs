@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetAddressReport(pub types::SessionWallet);
+
+impl Message for GetAddressReport {
+    type Result = worker::Result<Vec<model::AddressReport>>;
+}
+
+impl Handler<GetAddressReport> for worker::Worker {
+    type Result = <GetAddressReport as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetAddressReport(wallet): GetAddressReport,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.address_report(&wallet)
+    }
+}
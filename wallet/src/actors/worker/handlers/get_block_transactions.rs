@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetBlockTransactions(
+    pub types::SessionWallet,
+    /// Hashes of the transactions of the block
+    pub Vec<types::Hash>,
+);
+
+impl Message for GetBlockTransactions {
+    type Result = worker::Result<Vec<model::BlockTransaction>>;
+}
+
+impl Handler<GetBlockTransactions> for worker::Worker {
+    type Result = <GetBlockTransactions as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetBlockTransactions(wallet, txn_hashes): GetBlockTransactions,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.block_transactions(&wallet, &txn_hashes)
+    }
+}
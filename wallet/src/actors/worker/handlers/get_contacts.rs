@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetContacts(pub types::SessionWallet);
+
+impl Message for GetContacts {
+    type Result = worker::Result<Vec<model::Contact>>;
+}
+
+impl Handler<GetContacts> for worker::Worker {
+    type Result = <GetContacts as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetContacts(wallet): GetContacts,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.contacts(&wallet)
+    }
+}
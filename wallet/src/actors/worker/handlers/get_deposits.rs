@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetDeposits(
+    pub types::SessionWallet,
+    /// Maximum number of deposits to return
+    pub u32,
+);
+
+impl Message for GetDeposits {
+    type Result = worker::Result<model::Deposits>;
+}
+
+impl Handler<GetDeposits> for worker::Worker {
+    type Result = <GetDeposits as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetDeposits(wallet, limit): GetDeposits,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.deposits(&wallet, limit)
+    }
+}
@@ -0,0 +1,27 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct GetInputPaths(
+    pub types::SessionWallet,
+    /// Inputs spending outputs of the wallet
+    pub Vec<types::Input>,
+);
+
+impl Message for GetInputPaths {
+    /// Derivation path and public key hash of the address of the output spent by each input
+    type Result = worker::Result<Vec<(String, types::PublicKeyHash)>>;
+}
+
+impl Handler<GetInputPaths> for worker::Worker {
+    type Result = <GetInputPaths as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetInputPaths(wallet, inputs): GetInputPaths,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.input_paths(&wallet, &inputs)
+    }
+}
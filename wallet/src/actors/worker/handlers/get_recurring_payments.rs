@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetRecurringPayments(pub types::SessionWallet);
+
+impl Message for GetRecurringPayments {
+    type Result = worker::Result<Vec<model::RecurringPayment>>;
+}
+
+impl Handler<GetRecurringPayments> for worker::Worker {
+    type Result = <GetRecurringPayments as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetRecurringPayments(wallet): GetRecurringPayments,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.recurring_payments(&wallet)
+    }
+}
@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetScheduledPayments(pub types::SessionWallet);
+
+impl Message for GetScheduledPayments {
+    type Result = worker::Result<Vec<model::ScheduledPayment>>;
+}
+
+impl Handler<GetScheduledPayments> for worker::Worker {
+    type Result = <GetScheduledPayments as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetScheduledPayments(wallet): GetScheduledPayments,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.scheduled_payments(&wallet)
+    }
+}
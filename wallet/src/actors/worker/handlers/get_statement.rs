@@ -0,0 +1,28 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetStatement(
+    pub types::SessionWallet,
+    /// First epoch of the period
+    pub u32,
+    /// First epoch after the period
+    pub u32,
+);
+
+impl Message for GetStatement {
+    type Result = worker::Result<model::Statement>;
+}
+
+impl Handler<GetStatement> for worker::Worker {
+    type Result = <GetStatement as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetStatement(wallet, from_epoch, to_epoch): GetStatement,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.statement(&wallet, from_epoch, to_epoch)
+    }
+}
@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetStorageStats(pub types::SessionWallet);
+
+impl Message for GetStorageStats {
+    type Result = worker::Result<model::StorageStats>;
+}
+
+impl Handler<GetStorageStats> for worker::Worker {
+    type Result = <GetStorageStats as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetStorageStats(wallet): GetStorageStats,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.storage_stats(&wallet)
+    }
+}
@@ -9,6 +9,8 @@ pub struct GetTransactions(
     pub u32,
     /// Limit
     pub u32,
+    /// Address whose movements are listed, if any
+    pub Option<String>,
 );
 
 impl Message for GetTransactions {
@@ -20,9 +22,9 @@ impl Handler<GetTransactions> for worker::Worker {
 
     fn handle(
         &mut self,
-        GetTransactions(wallet, offset, limit): GetTransactions,
+        GetTransactions(wallet, offset, limit, address): GetTransactions,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.transactions(&wallet, offset, limit)
+        self.transactions(&wallet, offset, limit, address)
     }
 }
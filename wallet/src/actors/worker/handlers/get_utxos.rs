@@ -0,0 +1,18 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetUtxos(pub types::SessionWallet);
+
+impl Message for GetUtxos {
+    type Result = worker::Result<Vec<model::Utxo>>;
+}
+
+impl Handler<GetUtxos> for worker::Worker {
+    type Result = <GetUtxos as Message>::Result;
+
+    fn handle(&mut self, GetUtxos(wallet): GetUtxos, _ctx: &mut Self::Context) -> Self::Result {
+        self.utxos(&wallet)
+    }
+}
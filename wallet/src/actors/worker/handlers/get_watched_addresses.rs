@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetWatchedAddresses(pub types::SessionWallet);
+
+impl Message for GetWatchedAddresses {
+    type Result = worker::Result<Vec<model::WatchedAddress>>;
+}
+
+impl Handler<GetWatchedAddresses> for worker::Worker {
+    type Result = <GetWatchedAddresses as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetWatchedAddresses(wallet): GetWatchedAddresses,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.watched_addresses(&wallet)
+    }
+}
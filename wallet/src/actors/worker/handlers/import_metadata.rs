@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct ImportMetadata(pub types::SessionWallet, pub model::SignedMetadata);
+
+impl Message for ImportMetadata {
+    type Result = worker::Result<model::MetadataImport>;
+}
+
+impl Handler<ImportMetadata> for worker::Worker {
+    type Result = <ImportMetadata as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ImportMetadata(wallet, metadata): ImportMetadata,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.import_metadata(&wallet, metadata)
+    }
+}
@@ -6,7 +6,7 @@ use crate::types;
 pub struct IndexTxns(
     pub String,
     pub types::SessionWallet,
-    pub Vec<types::VTTransactionBody>,
+    pub types::BlockTransactions,
 );
 
 impl Message for IndexTxns {
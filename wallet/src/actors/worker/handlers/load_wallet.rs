@@ -0,0 +1,25 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct LoadWallet(pub String, pub types::SessionWallet);
+
+impl Message for LoadWallet {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<LoadWallet> for worker::Worker {
+    type Result = <LoadWallet as Message>::Result;
+
+    fn handle(
+        &mut self,
+        LoadWallet(wallet_id, wallet): LoadWallet,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.load_wallet(&wallet).map_err(|err| {
+            log::warn!("failed to load wallet {}: {}", wallet_id, err);
+            err
+        })
+    }
+}
@@ -1,27 +1,101 @@
+pub mod ack_deposits;
+pub mod add_recurring_payment;
+pub mod compact_db;
+pub mod create_vtt;
 pub mod create_wallet;
+pub mod delete_contact;
+pub mod delete_recurring_payment;
+pub mod export_metadata;
+pub mod export_plaintext;
+pub mod export_xprv;
+pub mod export_xpub;
 pub mod flush_db;
 pub mod gen_address;
 pub mod gen_mnemonic;
 pub mod get;
+pub mod get_address_proof;
+pub mod get_address_report;
 pub mod get_addresses;
+pub mod get_block_transactions;
+pub mod get_contacts;
+pub mod get_deposits;
+pub mod get_input_paths;
+pub mod get_recurring_payments;
+pub mod get_scheduled_payments;
+pub mod get_statement;
+pub mod get_storage_stats;
 pub mod get_transactions;
+pub mod get_utxos;
+pub mod get_watched_addresses;
+pub mod import_metadata;
 pub mod index_txns;
+pub mod load_wallet;
 pub mod notify_balance;
+pub mod notify_wallet_ready;
+pub mod record_scheduled_payment;
+pub mod recover_wallet;
+pub mod register_deposit_addresses;
+pub mod release_inputs;
+pub mod reserve_inputs;
+pub mod reset_chain_data;
 pub mod run_rad_request;
+pub mod run_schedules;
+pub mod save_contact;
+pub mod search_movements;
 pub mod set;
+pub mod sync_network;
 pub mod unlock_wallet;
+pub mod unwatch_address;
 pub mod wallet_infos;
+pub mod watch_address;
 
+pub use ack_deposits::*;
+pub use add_recurring_payment::*;
+pub use compact_db::*;
+pub use create_vtt::*;
 pub use create_wallet::*;
+pub use delete_contact::*;
+pub use delete_recurring_payment::*;
+pub use export_metadata::*;
+pub use export_plaintext::*;
+pub use export_xprv::*;
+pub use export_xpub::*;
 pub use flush_db::*;
 pub use gen_address::*;
 pub use gen_mnemonic::*;
 pub use get::*;
+pub use get_address_proof::*;
+pub use get_address_report::*;
 pub use get_addresses::*;
+pub use get_block_transactions::*;
+pub use get_contacts::*;
+pub use get_deposits::*;
+pub use get_input_paths::*;
+pub use get_recurring_payments::*;
+pub use get_scheduled_payments::*;
+pub use get_statement::*;
+pub use get_storage_stats::*;
 pub use get_transactions::*;
+pub use get_utxos::*;
+pub use get_watched_addresses::*;
+pub use import_metadata::*;
 pub use index_txns::*;
+pub use load_wallet::*;
 pub use notify_balance::*;
+pub use notify_wallet_ready::*;
+pub use record_scheduled_payment::*;
+pub use recover_wallet::*;
+pub use register_deposit_addresses::*;
+pub use release_inputs::*;
+pub use reserve_inputs::*;
+pub use reset_chain_data::*;
 pub use run_rad_request::*;
+pub use run_schedules::*;
+pub use save_contact::*;
+pub use search_movements::*;
 pub use set::*;
+pub use sync_network::*;
 pub use unlock_wallet::*;
+pub use unwatch_address::*;
 pub use wallet_infos::*;
+pub use watch_address::*;
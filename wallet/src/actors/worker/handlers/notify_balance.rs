@@ -1,9 +1,9 @@
 use actix::prelude::*;
 
 use crate::actors::worker;
-use crate::types;
+use crate::{journal, types};
 
-pub struct NotifyBalance(pub types::SessionWallet, pub types::Sink);
+pub struct NotifyBalance(pub types::SessionWallet, pub journal::Notifier);
 
 impl Message for NotifyBalance {
     type Result = ();
@@ -14,10 +14,10 @@ impl Handler<NotifyBalance> for worker::Worker {
 
     fn handle(
         &mut self,
-        NotifyBalance(wallet, sink): NotifyBalance,
+        NotifyBalance(wallet, notifier): NotifyBalance,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        if let Err(err) = self.notify_balance(&wallet, &sink) {
+        if let Err(err) = self.notify_balance(&wallet, &notifier) {
             log::warn!("failed to notify balance of wallet: {}", err);
         }
     }
@@ -0,0 +1,24 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{journal, types};
+
+pub struct NotifyWalletReady(pub String, pub types::SessionWallet, pub journal::Notifier);
+
+impl Message for NotifyWalletReady {
+    type Result = ();
+}
+
+impl Handler<NotifyWalletReady> for worker::Worker {
+    type Result = <NotifyWalletReady as Message>::Result;
+
+    fn handle(
+        &mut self,
+        NotifyWalletReady(wallet_id, wallet, notifier): NotifyWalletReady,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        if let Err(err) = self.notify_wallet_ready(&wallet_id, &wallet, &notifier) {
+            log::warn!("failed to notify wallet {} is ready: {}", wallet_id, err);
+        }
+    }
+}
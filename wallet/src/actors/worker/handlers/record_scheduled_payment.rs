@@ -0,0 +1,42 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{journal, model, types};
+
+pub struct RecordScheduledPayment(
+    pub types::SessionWallet,
+    /// Position of the payment in the payments of the wallet
+    pub u32,
+    pub model::ScheduledPayment,
+    /// Hash of the transaction that made the payment, if the node accepted it
+    pub Option<types::Hash>,
+    /// Whether the payment was left pending by a previous run
+    pub bool,
+    /// Notifier of the session that unlocked the wallet, to notify the payment to
+    pub Option<journal::Notifier>,
+);
+
+impl Message for RecordScheduledPayment {
+    type Result = ();
+}
+
+impl Handler<RecordScheduledPayment> for worker::Worker {
+    type Result = <RecordScheduledPayment as Message>::Result;
+
+    fn handle(
+        &mut self,
+        RecordScheduledPayment(wallet, index, payment, transaction_hash, retried, notifier): RecordScheduledPayment,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        if let Err(err) = self.record_scheduled_payment(
+            &wallet,
+            index,
+            payment,
+            transaction_hash,
+            retried,
+            notifier.as_ref(),
+        ) {
+            log::warn!("failed to record scheduled payment {}: {}", index, err);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct RecoverWallet(
+    /// Wallet id
+    pub String,
+    /// Wallet password
+    pub types::Password,
+    /// Tenant that owns the wallet
+    pub Option<String>,
+);
+
+impl Message for RecoverWallet {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<RecoverWallet> for worker::Worker {
+    type Result = <RecoverWallet as Message>::Result;
+
+    fn handle(
+        &mut self,
+        RecoverWallet(id, password, tenant): RecoverWallet,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.recover_wallet(tenant.as_ref().map(String::as_str), &id, password.as_ref())
+    }
+}
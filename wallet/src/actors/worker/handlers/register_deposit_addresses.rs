@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct RegisterDepositAddresses(
+    pub types::SessionWallet,
+    /// Addresses whose payments are reported as deposits
+    pub Vec<String>,
+);
+
+impl Message for RegisterDepositAddresses {
+    type Result = worker::Result<u32>;
+}
+
+impl Handler<RegisterDepositAddresses> for worker::Worker {
+    type Result = <RegisterDepositAddresses as Message>::Result;
+
+    fn handle(
+        &mut self,
+        RegisterDepositAddresses(wallet, addresses): RegisterDepositAddresses,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.register_deposit_addresses(&wallet, addresses)
+    }
+}
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct ReleaseInputs(
+    pub types::SessionWallet,
+    /// Inputs of the transaction that was not signed
+    pub Vec<types::Input>,
+);
+
+impl Message for ReleaseInputs {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<ReleaseInputs> for worker::Worker {
+    type Result = <ReleaseInputs as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ReleaseInputs(wallet, inputs): ReleaseInputs,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.release_inputs(&wallet, &inputs)
+    }
+}
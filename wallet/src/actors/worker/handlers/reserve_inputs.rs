@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct ReserveInputs(
+    pub types::SessionWallet,
+    /// Inputs of the transaction being signed
+    pub Vec<types::Input>,
+);
+
+impl Message for ReserveInputs {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<ReserveInputs> for worker::Worker {
+    type Result = <ReserveInputs as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ReserveInputs(wallet, inputs): ReserveInputs,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.reserve_inputs(&wallet, &inputs)
+    }
+}
@@ -0,0 +1,24 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+/// Forget the state a wallet indexed from a chain that has been reset, returning the epoch from
+/// which the blocks of the new chain have to be indexed.
+pub struct ResetChainData(pub types::SessionWallet);
+
+impl Message for ResetChainData {
+    type Result = worker::Result<u32>;
+}
+
+impl Handler<ResetChainData> for worker::Worker {
+    type Result = <ResetChainData as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ResetChainData(wallet): ResetChainData,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.reset_chain_data(&wallet)
+    }
+}
@@ -0,0 +1,37 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct RunSchedules(
+    pub types::SessionWallet,
+    /// Epoch
+    pub u32,
+);
+
+/// Payment generated by a recurring payment, along with the transaction that makes it, if it
+/// could be created.
+pub struct DuePayment {
+    /// Position of the payment in the payments of the wallet
+    pub index: u32,
+    pub payment: model::ScheduledPayment,
+    pub transaction: Option<types::VTTransaction>,
+    /// Whether the payment was left pending by a previous run, so it was already notified
+    pub retried: bool,
+}
+
+impl Message for RunSchedules {
+    type Result = worker::Result<Vec<DuePayment>>;
+}
+
+impl Handler<RunSchedules> for worker::Worker {
+    type Result = <RunSchedules as Message>::Result;
+
+    fn handle(
+        &mut self,
+        RunSchedules(wallet, epoch): RunSchedules,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.run_schedules(&wallet, epoch)
+    }
+}
@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct SaveContact(pub types::SessionWallet, pub model::Contact);
+
+impl Message for SaveContact {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<SaveContact> for worker::Worker {
+    type Result = <SaveContact as Message>::Result;
+
+    fn handle(
+        &mut self,
+        SaveContact(wallet, contact): SaveContact,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.save_contact(&wallet, contact)
+    }
+}
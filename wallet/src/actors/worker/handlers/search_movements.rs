@@ -0,0 +1,29 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct SearchMovements(
+    pub types::SessionWallet,
+    pub model::MovementFilter,
+    /// Offset
+    pub u32,
+    /// Limit
+    pub u32,
+);
+
+impl Message for SearchMovements {
+    type Result = worker::Result<model::Movements>;
+}
+
+impl Handler<SearchMovements> for worker::Worker {
+    type Result = <SearchMovements as Message>::Result;
+
+    fn handle(
+        &mut self,
+        SearchMovements(wallet, filter, offset, limit): SearchMovements,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.search_movements(&wallet, &filter, offset, limit)
+    }
+}
@@ -0,0 +1,33 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{journal, types};
+
+/// Sync a wallet with the chain of the network of its node, returning the epoch from which the
+/// blocks have to be indexed again, if any.
+pub struct SyncNetwork(
+    pub String,
+    pub types::SessionWallet,
+    pub types::NetworkId,
+    pub Option<journal::Notifier>,
+);
+
+impl Message for SyncNetwork {
+    type Result = worker::Result<Option<u32>>;
+}
+
+impl Handler<SyncNetwork> for worker::Worker {
+    type Result = <SyncNetwork as Message>::Result;
+
+    fn handle(
+        &mut self,
+        SyncNetwork(wallet_id, wallet, network, notifier): SyncNetwork,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.sync_network(&wallet_id, &wallet, &network, notifier.as_ref())
+            .map_err(|err| {
+                log::warn!("failed to sync wallet {} with the node: {}", wallet_id, err);
+                err
+            })
+    }
+}
@@ -8,6 +8,8 @@ pub struct UnlockWallet(
     pub String,
     /// Wallet password
     pub types::Password,
+    /// Tenant that owns the wallet
+    pub Option<String>,
 );
 
 impl Message for UnlockWallet {
@@ -19,9 +21,9 @@ impl Handler<UnlockWallet> for worker::Worker {
 
     fn handle(
         &mut self,
-        UnlockWallet(id, password): UnlockWallet,
+        UnlockWallet(id, password, tenant): UnlockWallet,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.unlock_wallet(&id, password.as_ref())
+        self.unlock_wallet(tenant.as_ref().map(String::as_str), &id, password.as_ref())
     }
 }
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::types;
+
+pub struct UnwatchAddress(
+    pub types::SessionWallet,
+    /// Address
+    pub String,
+);
+
+impl Message for UnwatchAddress {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<UnwatchAddress> for worker::Worker {
+    type Result = <UnwatchAddress as Message>::Result;
+
+    fn handle(
+        &mut self,
+        UnwatchAddress(wallet, address): UnwatchAddress,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.unwatch_address(&wallet, &address)
+    }
+}
@@ -3,7 +3,10 @@ use actix::prelude::*;
 use crate::actors::worker;
 use crate::model;
 
-pub struct WalletInfos;
+pub struct WalletInfos(
+    /// Tenant whose wallets are listed
+    pub Option<String>,
+);
 
 impl Message for WalletInfos {
     type Result = worker::Result<Vec<model::Wallet>>;
@@ -12,7 +15,11 @@ impl Message for WalletInfos {
 impl Handler<WalletInfos> for worker::Worker {
     type Result = <WalletInfos as Message>::Result;
 
-    fn handle(&mut self, _msg: WalletInfos, _ctx: &mut Self::Context) -> Self::Result {
-        self.wallet_infos()
+    fn handle(
+        &mut self,
+        WalletInfos(tenant): WalletInfos,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.wallet_infos(tenant.as_ref().map(String::as_str))
     }
 }
@@ -0,0 +1,28 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct WatchAddress(
+    pub types::SessionWallet,
+    /// Address
+    pub String,
+    /// Label
+    pub Option<String>,
+);
+
+impl Message for WatchAddress {
+    type Result = worker::Result<model::WatchedAddress>;
+}
+
+impl Handler<WatchAddress> for worker::Worker {
+    type Result = <WatchAddress as Message>::Result;
+
+    fn handle(
+        &mut self,
+        WatchAddress(wallet, address, label): WatchAddress,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.watch_address(&wallet, address, label)
+    }
+}
@@ -1,11 +1,11 @@
 use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use jsonrpc_core as rpc;
 use rayon::prelude::*;
 use serde_json::json;
 
 use super::*;
-use crate::{account, constants, crypto, db::Database as _, model, params};
+use crate::{account, constants, crypto, db::Database as _, journal, model, params};
 
 impl Worker {
     pub fn start(concurrency: usize, db: Arc<rocksdb::DB>, params: params::Params) -> Addr<Self> {
@@ -48,8 +48,15 @@ impl Worker {
         Ok(value)
     }
 
-    pub fn gen_mnemonic(&self, length: types::MnemonicLength) -> String {
-        let mnemonic = types::MnemonicGen::new().with_len(length).generate();
+    pub fn gen_mnemonic(
+        &self,
+        length: types::MnemonicLength,
+        language: types::MnemonicLang,
+    ) -> String {
+        let mnemonic = types::MnemonicGen::new()
+            .with_len(length)
+            .with_lang(language)
+            .generate();
         let words = mnemonic.words();
 
         words.to_string()
@@ -61,14 +68,17 @@ impl Worker {
         Ok(())
     }
 
-    pub fn wallet_infos(&self) -> Result<Vec<model::Wallet>> {
-        let wallets = self.wallets.infos()?;
+    pub fn wallet_infos(&self, tenant: Option<&str>) -> Result<Vec<model::Wallet>> {
+        let wallets = self.wallets.infos(tenant)?;
 
         Ok(wallets)
     }
 
+    /// Create a wallet, owned by `tenant` if any. The id of the wallets of a tenant is derived
+    /// from the tenant too, so the same seed gives a different wallet to each tenant.
     pub fn create_wallet(
         &mut self,
+        tenant: Option<&str>,
         name: Option<String>,
         caption: Option<String>,
         password: &[u8],
@@ -79,30 +89,45 @@ impl Worker {
             self.params.master_key_salt.as_ref(),
             source,
         )?;
+        let id_salt = match tenant {
+            Some(tenant) => [self.params.master_key_salt.as_ref(), tenant.as_bytes()].concat(),
+            None => self.params.master_key_salt.clone(),
+        };
         let id = crypto::gen_wallet_id(
             &self.params.id_hash_function,
             &master_key,
-            self.params.master_key_salt.as_ref(),
+            &id_salt,
             self.params.id_hash_iterations,
         );
+        let storage_id = repository::wallet_storage_id(tenant, &id);
 
         let default_account_index = 0;
         let default_account =
             account::gen_account(&self.engine, default_account_index, &master_key)?;
 
         // This is for storage encryption
-        let prefix = id.as_bytes().to_vec();
+        let prefix = storage_id.as_bytes().to_vec();
         let salt = crypto::salt(&mut self.rng, self.params.db_salt_length);
         let iv = crypto::salt(&mut self.rng, self.params.db_iv_length);
         let key = crypto::key_from_password(password, &salt, self.params.db_hash_iterations);
 
-        let wallet_db = db::EncryptedDb::new(self.db.clone(), prefix, key, iv.clone());
+        let mut wallet_db = db::EncryptedDb::new(self.db.clone(), prefix, key, iv.clone());
+        if let Some(remote_replication) = &self.params.remote_replication {
+            let header = db::ReplicaHeader {
+                salt: salt.clone(),
+                iv: iv.clone(),
+            };
+            remote_replication.put_header(&storage_id, &header)?;
+            wallet_db =
+                wallet_db.with_replica(remote_replication.replica(&self.db, &storage_id, 0)?);
+        }
         wallet_db.put(
             constants::ENCRYPTION_CHECK_KEY,
             constants::ENCRYPTION_CHECK_VALUE,
         )?; // used when unlocking to check if the password is correct
 
         self.wallets.create(
+            tenant,
             wallet_db,
             types::CreateWalletData {
                 name,
@@ -110,6 +135,7 @@ impl Worker {
                 iv,
                 salt,
                 id: &id,
+                master_key: &master_key,
                 account: &default_account,
             },
         )?;
@@ -119,16 +145,17 @@ impl Worker {
 
     pub fn unlock_wallet(
         &mut self,
+        tenant: Option<&str>,
         wallet_id: &str,
         password: &[u8],
     ) -> Result<types::UnlockedSessionWallet> {
-        let (salt, iv) = self
-            .wallets
-            .wallet_salt_and_iv(wallet_id)
-            .map_err(|err| match err {
-                repository::Error::Db(db::Error::DbKeyNotFound) => Error::WalletNotFound,
-                err => Error::Repository(err),
-            })?;
+        let (salt, iv) =
+            self.wallets
+                .wallet_salt_and_iv(tenant, wallet_id)
+                .map_err(|err| match err {
+                    repository::Error::Db(db::Error::DbKeyNotFound) => Error::WalletNotFound,
+                    err => Error::Repository(err),
+                })?;
         let key = crypto::key_from_password(password, &salt, self.params.db_hash_iterations);
         let session_id = From::from(crypto::gen_session_id(
             &mut self.rng,
@@ -137,7 +164,8 @@ impl Worker {
             &salt,
             self.params.id_hash_iterations,
         ));
-        let prefix = wallet_id.as_bytes().to_vec();
+        let storage_id = repository::wallet_storage_id(tenant, wallet_id);
+        let prefix = storage_id.as_bytes().to_vec();
         let wallet_db = db::EncryptedDb::new(self.db.clone(), prefix, key, iv);
 
         // Check if password-derived key is able to read the special stored value
@@ -148,6 +176,31 @@ impl Worker {
                 err => Error::Db(err),
             })?;
 
+        // Only the wallets created while the remote storage is configured are replicated, because
+        // the writes done before enabling it were never pushed
+        let next_batch: Option<u64> =
+            wallet_db.get_or_default(constants::REPLICA_NEXT_BATCH_KEY)?;
+        let wallet_db = match (&self.params.remote_replication, next_batch) {
+            (Some(remote_replication), Some(next_batch)) => wallet_db
+                .with_replica(remote_replication.replica(&self.db, &storage_id, next_batch)?),
+            (Some(_), None) => {
+                log::warn!(
+                    "Wallet {} is not replicated to the remote storage because it was created \
+                     before enabling it",
+                    wallet_id
+                );
+                wallet_db
+            }
+            (None, Some(_)) => {
+                // The writes done while the remote storage is disabled would be missing from it,
+                // so the replication of this wallet cannot be resumed
+                wallet_db.put(constants::REPLICA_NEXT_BATCH_KEY, None::<u64>)?;
+                db::discard_pending_batches(&self.db, &storage_id)?;
+                wallet_db
+            }
+            (None, None) => wallet_db,
+        };
+
         let wallet = repository::Wallet::new(wallet_db, self.params.clone(), self.engine.clone());
 
         let data = wallet.unlock()?;
@@ -159,16 +212,101 @@ impl Worker {
         })
     }
 
+    /// Download a wallet from the remote storage and add it to the local database.
+    pub fn recover_wallet(
+        &mut self,
+        tenant: Option<&str>,
+        wallet_id: &str,
+        password: &[u8],
+    ) -> Result<()> {
+        let remote_replication = self
+            .params
+            .remote_replication
+            .as_ref()
+            .ok_or(Error::RemoteStorageDisabled)?;
+        if self.wallets.contains(tenant, wallet_id)? {
+            return Err(Error::WalletAlreadyExists);
+        }
+
+        let storage_id = repository::wallet_storage_id(tenant, wallet_id);
+        let (header, batches) = remote_replication
+            .download(&storage_id)
+            .map_err(|err| match err {
+                db::Error::MissingBatch(n) => Error::IncompleteReplica(n),
+                err => Error::Db(err),
+            })?
+            .ok_or(Error::WalletNotFound)?;
+
+        // Nothing is written locally unless the password-derived key can read the whole replica
+        // and all its records belong to this wallet
+        let key = crypto::key_from_password(password, &header.salt, self.params.db_hash_iterations);
+        let prefix = storage_id.as_bytes().to_vec();
+        let entries = db::validate_replica(prefix.clone(), key, header.iv.clone(), batches)
+            .map_err(|err| match err {
+                db::Error::DbKeyNotFound => Error::WrongPassword,
+                db::Error::ForeignRecord => Error::InvalidReplica,
+                err => Error::Db(err),
+            })?;
+        db::restore(&self.db, &prefix, entries)?;
+
+        self.wallets
+            .register(tenant, wallet_id, &header.salt, &header.iv)?;
+
+        Ok(())
+    }
+
     pub fn gen_address(
         &mut self,
         wallet: &types::Wallet,
         label: Option<String>,
+        reuse: bool,
     ) -> Result<model::Address> {
-        let address = wallet.gen_address(label)?;
+        let address = wallet.gen_address(label, reuse, now())?;
 
         Ok(address)
     }
 
+    /// Export a key of a wallet encrypted with the backup `password`, once the `wallet_password`
+    /// of the wallet is checked again.
+    pub fn export_xprv(
+        &mut self,
+        wallet: &types::Wallet,
+        tenant: Option<&str>,
+        wallet_id: &str,
+        wallet_password: &[u8],
+        depth: types::XprvDepth,
+        password: &[u8],
+    ) -> Result<String> {
+        self.wallet_db(tenant, wallet_id, wallet_password)?;
+
+        let key = match depth {
+            types::XprvDepth::Master => wallet.master_key()?,
+            types::XprvDepth::Account => wallet.account_key()?,
+        };
+        let salt = crypto::salt(&mut self.rng, constants::XPRV_SALT_LENGTH);
+        let iv = crypto::salt(&mut self.rng, constants::XPRV_IV_LENGTH);
+        let xprv = crypto::encode_xprv(&key, depth.depth(), password, &salt, &iv)?;
+
+        Ok(xprv)
+    }
+
+    pub fn export_xpub(&mut self, wallet: &types::Wallet, account: u32) -> Result<String> {
+        let xpub = wallet.export_xpub(account)?;
+
+        Ok(xpub)
+    }
+
+    pub fn address_proof(
+        &mut self,
+        wallet: &types::Wallet,
+        index: u32,
+        challenge: String,
+    ) -> Result<model::AddressProof> {
+        let proof = wallet.address_proof(index, challenge)?;
+
+        Ok(proof)
+    }
+
     pub fn addresses(
         &mut self,
         wallet: &types::Wallet,
@@ -180,51 +318,52 @@ impl Worker {
         Ok(addresses)
     }
 
+    pub fn address_report(&mut self, wallet: &types::Wallet) -> Result<Vec<model::AddressReport>> {
+        let report = wallet.address_report()?;
+
+        Ok(report)
+    }
+
     pub fn transactions(
         &mut self,
-        _wallet: &types::Wallet,
-        _offset: u32,
-        _limit: u32,
+        wallet: &types::Wallet,
+        offset: u32,
+        limit: u32,
+        address: Option<String>,
     ) -> Result<model::Transactions> {
-        let transactions = vec![
-            model::Transaction {
-                hash: "4f369107485dd195d477818a27d27027b758572cce82078f6789aa6df7d1f295"
-                    .to_string(),
-                value: 341_958,
-                kind: model::TransactionKind::Debit,
-            },
-            model::Transaction {
-                hash: "16c447832f337f78ae282a2e0143368d95ba83f1bf7829b52a853fd0c126b434"
-                    .to_string(),
-                value: 2349,
-                kind: model::TransactionKind::Credit,
-            },
-            model::Transaction {
-                hash: "67086e92250362daeb114ceacc0cbee5fbdd2cb40c2718a6b0b6879702d52d43"
-                    .to_string(),
-                value: 12,
-                kind: model::TransactionKind::Debit,
-            },
-            model::Transaction {
-                hash: "36a50cf934f58255c748e6f1d12f572c5c426a186387f806a1be55ff8fe1b171"
-                    .to_string(),
-                value: u64::max_value(),
-                kind: model::TransactionKind::Credit,
-            },
-            model::Transaction {
-                hash: "ea5d0f4187403bf085937ff8d1fba862923b1b40d4ae188bc52006d895c334df"
-                    .to_string(),
-                value: 1,
-                kind: model::TransactionKind::Debit,
-            },
-            model::Transaction {
-                hash: "4f369107485dd195d477818a27d27027b758572cce82078f6789aa6df7d1f295"
-                    .to_string(),
-                value: 3958,
-                kind: model::TransactionKind::Credit,
-            },
-        ];
-        let total = 20;
+        let (transactions, total) = match address {
+            Some(address) => {
+                let transactions = wallet.address_transactions(&address, offset, limit)?;
+
+                (transactions.transactions, transactions.total)
+            }
+            None => {
+                let mut transactions = wallet.transactions(offset, limit)?;
+
+                // Show the movements of the watched addresses, flagged as watch-only
+                transactions
+                    .transactions
+                    .extend(wallet.watched_transactions()?);
+
+                (transactions.transactions, transactions.total)
+            }
+        };
+
+        // Show the contact name of the known counterparties
+        let transactions = transactions
+            .into_iter()
+            .map(|transaction| {
+                let contact = match &transaction.counterparty {
+                    Some(address) => wallet.contact_name(address)?,
+                    None => None,
+                };
+
+                Ok(model::Transaction {
+                    contact,
+                    ..transaction
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(model::Transactions {
             transactions,
@@ -232,6 +371,338 @@ impl Worker {
         })
     }
 
+    pub fn search_movements(
+        &self,
+        wallet: &types::Wallet,
+        filter: &model::MovementFilter,
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::Movements> {
+        let movements = wallet.search_movements(filter, offset, limit)?;
+
+        Ok(movements)
+    }
+
+    pub fn storage_stats(&self, wallet: &types::Wallet) -> Result<model::StorageStats> {
+        let stats = wallet.storage_stats()?;
+
+        Ok(stats)
+    }
+
+    pub fn compact_db(&self, wallet: &types::Wallet) -> Result<model::StorageCompaction> {
+        let compaction = wallet.compact()?;
+
+        Ok(compaction)
+    }
+
+    pub fn statement(
+        &self,
+        wallet: &types::Wallet,
+        from_epoch: u32,
+        to_epoch: u32,
+    ) -> Result<model::Statement> {
+        let statement = wallet.statement(from_epoch, to_epoch)?;
+
+        Ok(statement)
+    }
+
+    pub fn block_transactions(
+        &self,
+        wallet: &types::Wallet,
+        txn_hashes: &[types::Hash],
+    ) -> Result<Vec<model::BlockTransaction>> {
+        let transactions = wallet.block_transactions(txn_hashes)?;
+
+        Ok(transactions)
+    }
+
+    pub fn contacts(&self, wallet: &types::Wallet) -> Result<Vec<model::Contact>> {
+        let contacts = wallet.contacts()?;
+
+        Ok(contacts)
+    }
+
+    pub fn save_contact(&self, wallet: &types::Wallet, contact: model::Contact) -> Result<()> {
+        wallet.save_contact(contact)?;
+
+        Ok(())
+    }
+
+    pub fn delete_contact(&self, wallet: &types::Wallet, address: &str) -> Result<()> {
+        wallet.delete_contact(address)?;
+
+        Ok(())
+    }
+
+    /// Decrypt every record of the database of a wallet. The password is checked again, so an
+    /// unlocked session is not enough to read the plaintext records.
+    pub fn export_plaintext(
+        &self,
+        tenant: Option<&str>,
+        wallet_id: &str,
+        password: &[u8],
+    ) -> Result<Vec<model::PlaintextRecord>> {
+        let records = self
+            .wallet_db(tenant, wallet_id, password)?
+            .export_plaintext()?
+            .into_iter()
+            .map(|(key, value)| model::PlaintextRecord {
+                key: String::from_utf8_lossy(&key).into_owned(),
+                key_hex: hex::encode(&key),
+                value_hex: hex::encode(&value),
+            })
+            .collect();
+
+        Ok(records)
+    }
+
+    /// Open the database of a wallet with the key derived from its password, failing with
+    /// `WrongPassword` if the key can not read it.
+    fn wallet_db(
+        &self,
+        tenant: Option<&str>,
+        wallet_id: &str,
+        password: &[u8],
+    ) -> Result<db::EncryptedDb> {
+        let (salt, iv) =
+            self.wallets
+                .wallet_salt_and_iv(tenant, wallet_id)
+                .map_err(|err| match err {
+                    repository::Error::Db(db::Error::DbKeyNotFound) => Error::WalletNotFound,
+                    err => Error::Repository(err),
+                })?;
+        let key = crypto::key_from_password(password, &salt, self.params.db_hash_iterations);
+        let prefix = repository::wallet_storage_id(tenant, wallet_id)
+            .as_bytes()
+            .to_vec();
+        let wallet_db = db::EncryptedDb::new(self.db.clone(), prefix, key, iv);
+
+        // Check if password-derived key is able to read the special stored value
+        wallet_db
+            .get(constants::ENCRYPTION_CHECK_KEY)
+            .map_err(|err| match err {
+                db::Error::DbKeyNotFound => Error::WrongPassword,
+                err => Error::Db(err),
+            })?;
+
+        Ok(wallet_db)
+    }
+
+    pub fn export_metadata(&self, wallet: &types::Wallet) -> Result<model::SignedMetadata> {
+        let metadata = wallet.export_metadata()?;
+
+        Ok(metadata)
+    }
+
+    pub fn import_metadata(
+        &self,
+        wallet: &types::Wallet,
+        metadata: model::SignedMetadata,
+    ) -> Result<model::MetadataImport> {
+        let imported = wallet.import_metadata(metadata)?;
+
+        Ok(imported)
+    }
+
+    pub fn watched_addresses(&self, wallet: &types::Wallet) -> Result<Vec<model::WatchedAddress>> {
+        let watched = wallet.watched_addresses()?;
+
+        Ok(watched)
+    }
+
+    pub fn watch_address(
+        &self,
+        wallet: &types::Wallet,
+        address: String,
+        label: Option<String>,
+    ) -> Result<model::WatchedAddress> {
+        let watched = wallet.watch_address(address, label)?;
+
+        Ok(watched)
+    }
+
+    pub fn unwatch_address(&self, wallet: &types::Wallet, address: &str) -> Result<()> {
+        wallet.unwatch_address(address)?;
+
+        Ok(())
+    }
+
+    pub fn input_paths(
+        &self,
+        wallet: &types::Wallet,
+        inputs: &[types::Input],
+    ) -> Result<Vec<(String, types::PublicKeyHash)>> {
+        let paths = wallet.input_paths(inputs)?;
+        let pkhs = wallet.input_pkhs(inputs)?;
+
+        Ok(paths.into_iter().zip(pkhs).collect())
+    }
+
+    pub fn reserve_inputs(&self, wallet: &types::Wallet, inputs: &[types::Input]) -> Result<()> {
+        wallet.reserve_inputs(inputs, now())?;
+
+        Ok(())
+    }
+
+    /// Create a value transfer transaction paying `amount` to `address`, signed with the keys of
+    /// the wallet. Its inputs are reserved until it is indexed or released. The change is paid to
+    /// the address chosen by `change_address`, or by the params of the wallet if not given.
+    pub fn create_vtt(
+        &self,
+        wallet: &types::Wallet,
+        address: &str,
+        amount: types::Nanowits,
+        fee: types::Nanowits,
+        change_address: Option<types::ChangeAddressPolicy>,
+    ) -> Result<types::VTTransaction> {
+        let change_address = change_address.unwrap_or(self.params.change_address);
+        let transaction = wallet.create_vtt(address, amount, fee, change_address, now())?;
+
+        Ok(transaction)
+    }
+
+    pub fn release_inputs(&self, wallet: &types::Wallet, inputs: &[types::Input]) -> Result<()> {
+        wallet.release_inputs(inputs)?;
+
+        Ok(())
+    }
+
+    pub fn utxos(&self, wallet: &types::Wallet) -> Result<Vec<model::Utxo>> {
+        let utxos = wallet.utxos(now())?;
+
+        Ok(utxos)
+    }
+
+    pub fn register_deposit_addresses(
+        &self,
+        wallet: &types::Wallet,
+        addresses: Vec<String>,
+    ) -> Result<u32> {
+        let registered = wallet.register_deposit_addresses(addresses)?;
+
+        Ok(registered)
+    }
+
+    pub fn deposits(&self, wallet: &types::Wallet, limit: u32) -> Result<model::Deposits> {
+        let deposits = wallet.deposits(limit)?;
+
+        Ok(deposits)
+    }
+
+    pub fn ack_deposits(&self, wallet: &types::Wallet, cursor: u64) -> Result<u64> {
+        let cursor = wallet.ack_deposits(cursor)?;
+
+        Ok(cursor)
+    }
+
+    pub fn add_recurring_payment(
+        &self,
+        wallet: &types::Wallet,
+        payment: model::RecurringPayment,
+    ) -> Result<model::RecurringPayment> {
+        let payment = wallet.add_recurring_payment(payment)?;
+
+        Ok(payment)
+    }
+
+    pub fn recurring_payments(
+        &self,
+        wallet: &types::Wallet,
+    ) -> Result<Vec<model::RecurringPayment>> {
+        let payments = wallet.recurring_payments()?;
+
+        Ok(payments)
+    }
+
+    pub fn delete_recurring_payment(&self, wallet: &types::Wallet, id: u32) -> Result<()> {
+        wallet.delete_recurring_payment(id)?;
+
+        Ok(())
+    }
+
+    pub fn scheduled_payments(
+        &self,
+        wallet: &types::Wallet,
+    ) -> Result<Vec<model::ScheduledPayment>> {
+        let payments = wallet.scheduled_payments()?;
+
+        Ok(payments)
+    }
+
+    /// Generate the payments of the recurring payments due at `epoch`, creating and signing the
+    /// transaction of each of them, and of the payments left pending by previous runs. The
+    /// payments whose transaction cannot be created, e.g. because the wallet has not enough
+    /// funds, are kept pending.
+    pub fn run_schedules(&self, wallet: &types::Wallet, epoch: u32) -> Result<Vec<DuePayment>> {
+        let now = now();
+        let due = wallet
+            .run_schedules(epoch)?
+            .into_iter()
+            .map(|(index, payment, retried)| {
+                let transaction = match wallet.create_vtt(
+                    &payment.address,
+                    payment.amount,
+                    payment.fee,
+                    self.params.change_address,
+                    now,
+                ) {
+                    Ok(transaction) => Some(transaction),
+                    Err(err) => {
+                        log::warn!(
+                            "payment of recurring payment {} due at epoch {} kept pending: {}",
+                            payment.recurring_payment_id,
+                            payment.epoch,
+                            err
+                        );
+                        None
+                    }
+                };
+
+                DuePayment {
+                    index,
+                    payment,
+                    transaction,
+                    retried,
+                }
+            })
+            .collect();
+
+        Ok(due)
+    }
+
+    /// Record the transaction that made a payment generated by a recurring payment, if the node
+    /// accepted it, or keep the payment pending to retry it otherwise. New and sent payments are
+    /// notified to the session that unlocked the wallet, if any.
+    pub fn record_scheduled_payment(
+        &self,
+        wallet: &types::Wallet,
+        index: u32,
+        payment: model::ScheduledPayment,
+        transaction_hash: Option<types::Hash>,
+        retried: bool,
+        notifier: Option<&journal::Notifier>,
+    ) -> Result<()> {
+        let payment = match transaction_hash {
+            Some(hash) => wallet.set_scheduled_payment_transaction(index, hash.to_string())?,
+            None => {
+                wallet.retry_scheduled_payment(index)?;
+                if retried {
+                    return Ok(());
+                }
+
+                payment
+            }
+        };
+
+        if let Some(notifier) = notifier {
+            let payload = json!({ "scheduledPayments": [payment] });
+
+            notifier.notify(payload)?;
+        }
+
+        Ok(())
+    }
+
     pub fn get(&self, wallet: &types::Wallet, key: &str) -> Result<Option<String>> {
         let value = wallet.db_get(key)?;
 
@@ -247,14 +718,86 @@ impl Worker {
     pub fn index_txns(
         &self,
         wallet: &types::Wallet,
-        txns: &[types::VTTransactionBody],
+        txns: &types::BlockTransactions,
     ) -> Result<()> {
         wallet.index_txns(txns)?;
 
         Ok(())
     }
 
-    pub fn notify_balance(&self, wallet: &types::Wallet, sink: &types::Sink) -> Result<()> {
+    pub fn load_wallet(&self, wallet: &types::Wallet) -> Result<()> {
+        wallet.load()?;
+
+        Ok(())
+    }
+
+    /// Sync a wallet with the network of its node, notifying the session that unlocked it, if
+    /// any, when the chain of the network has been reset.
+    pub fn sync_network(
+        &self,
+        wallet_id: &str,
+        wallet: &types::Wallet,
+        network: &types::NetworkId,
+        notifier: Option<&journal::Notifier>,
+    ) -> Result<Option<u32>> {
+        match wallet.sync_network(network)? {
+            types::NetworkSync::Rescan(rescan_from) => Ok(rescan_from),
+            types::NetworkSync::ChainReset(indexed) => {
+                if let Some(notifier) = notifier {
+                    let payload = json!({
+                        "chainReset": {
+                            "walletId": wallet_id,
+                            "network": network.network,
+                            "indexedGenesisHash": indexed.genesis_hash,
+                            "genesisHash": network.genesis_hash
+                        }
+                    });
+
+                    notifier.notify(payload)?;
+                }
+
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn reset_chain_data(&self, wallet: &types::Wallet) -> Result<u32> {
+        let rescan_from = wallet.reset_chain_data()?;
+
+        Ok(rescan_from)
+    }
+
+    /// Notify that the wallet has been loaded and its balance is known. Nothing is sent if it is
+    /// still loading, as the notification will be sent once it finishes.
+    pub fn notify_wallet_ready(
+        &self,
+        wallet_id: &str,
+        wallet: &types::Wallet,
+        notifier: &journal::Notifier,
+    ) -> Result<()> {
+        if !wallet.is_loaded()? {
+            return Ok(());
+        }
+
+        let (account, balance) = wallet.balance()?;
+        let payload = json!({
+            "walletReady": {
+                "walletId": wallet_id,
+                "account": account,
+                "balance": balance
+            }
+        });
+
+        notifier.notify(payload)?;
+
+        Ok(())
+    }
+
+    pub fn notify_balance(
+        &self,
+        wallet: &types::Wallet,
+        notifier: &journal::Notifier,
+    ) -> Result<()> {
         let (account, balance) = wallet.balance()?;
         let payload = json!({
             "accountBalance": {
@@ -262,10 +805,17 @@ impl Worker {
                 "balance": balance
             }
         });
-        let send = sink.notify(rpc::Params::Array(vec![payload]));
 
-        send.wait()?;
+        notifier.notify(payload)?;
 
         Ok(())
     }
 }
+
+/// Current timestamp in seconds.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
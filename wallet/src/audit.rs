@@ -0,0 +1,384 @@
+//! # Audit log
+//!
+//! Append-only log of the state-changing requests handled by the wallet server, so operator
+//! actions can be reconstructed afterwards.
+//!
+//! The first line of a log file holds the hex-encoded salt used to derive the encryption key from
+//! the audit log password. Every other line is an entry, encrypted and hex-encoded as
+//! `iv || ciphertext`. Once a file grows over the configured maximum size it is renamed, adding
+//! the current timestamp as suffix, followed by a counter if the log was already rotated in the
+//! same second, and a new file is started.
+//!
+//! Entries are chained: each one holds the hash of the previous entry, the last one of the
+//! rotated file for the first entry of a new file, so removing or reordering entries is detected
+//! when the log is read.
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use failure::format_err;
+use jsonrpc_core as rpc;
+use serde::{Deserialize, Serialize};
+
+use witnet_crypto::cipher;
+use witnet_protected::ProtectedString;
+
+use crate::{constants, crypto, types};
+
+/// Result type for audit log operations that can fail.
+pub type Result<T> = std::result::Result<T, failure::Error>;
+
+/// A request recorded in the audit log.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// JSON-RPC method of the request.
+    pub method: String,
+    /// Hex-encoded SHA-256 hash of the request params.
+    pub params_hash: String,
+    /// Session the request was made with, if any.
+    pub session_id: Option<types::SessionId>,
+    /// Timestamp in seconds of the request.
+    pub timestamp: i64,
+    /// Hex-encoded SHA-256 hash of the result if the request succeeded.
+    #[serde(default)]
+    pub result_hash: Option<String>,
+    /// Error message if the request failed.
+    pub error: Option<String>,
+    /// Hex-encoded SHA-256 hash of the previous entry of the log, if any.
+    #[serde(default)]
+    pub prev_hash: Option<String>,
+}
+
+impl Entry {
+    /// Create the entry of a request that has not been handled yet.
+    pub fn new(method: &str, params: &rpc::Params) -> Self {
+        let params_bytes = serde_json::to_vec(params).unwrap_or_default();
+        let session_id = match params {
+            rpc::Params::Map(map) => map
+                .get("sessionId")
+                .and_then(|value| value.as_str())
+                .map(|session_id| session_id.to_string().into()),
+            _ => None,
+        };
+
+        Self {
+            method: method.to_string(),
+            params_hash: hex::encode(crypto::calculate_sha256(&params_bytes)),
+            session_id,
+            timestamp: now(),
+            result_hash: None,
+            error: None,
+            prev_hash: None,
+        }
+    }
+
+    /// Set the result of the request.
+    pub fn with_result<T>(mut self, result: &std::result::Result<T, rpc::Error>) -> Self
+    where
+        T: Serialize,
+    {
+        match result {
+            Ok(value) => {
+                let result_bytes = serde_json::to_vec(value).unwrap_or_default();
+                self.result_hash = Some(hex::encode(crypto::calculate_sha256(&result_bytes)));
+            }
+            Err(err) => self.error = Some(err.message.clone()),
+        }
+        self
+    }
+}
+
+/// Encrypted and rotated audit log file.
+pub struct AuditLog {
+    path: PathBuf,
+    password: ProtectedString,
+    hash_iterations: u32,
+    max_size: u64,
+    file: Mutex<LogFile>,
+}
+
+struct LogFile {
+    file: File,
+    key: types::Secret,
+    size: u64,
+    /// Hash of the last entry written, to which the next one is chained
+    last_hash: Option<String>,
+}
+
+impl AuditLog {
+    /// Open the audit log at the given path, creating it if it does not exist.
+    pub fn open(
+        path: PathBuf,
+        password: ProtectedString,
+        hash_iterations: u32,
+        max_size: u64,
+    ) -> Result<Self> {
+        let file = open_file(&path, &password, hash_iterations)?;
+
+        Ok(Self {
+            path,
+            password,
+            hash_iterations,
+            max_size,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append an entry to the log, chained to the previous one, rotating the log file if it's
+    /// full.
+    pub fn record(&self, mut entry: Entry) -> Result<()> {
+        let mut log_file = self
+            .file
+            .lock()
+            .map_err(|_| format_err!("audit log lock poisoned"))?;
+        entry.prev_hash = log_file.last_hash.clone();
+        let plaintext = serde_json::to_vec(&entry)?;
+        let iv = crypto::salt(&mut rand::rngs::OsRng, constants::AUDIT_LOG_IV_LENGTH);
+        let ciphertext = cipher::encrypt_aes_cbc(log_file.key.as_ref(), &plaintext, &iv)?;
+        let line = format!("{}\n", hex::encode([&iv[..], &ciphertext[..]].concat()));
+
+        if log_file.size + line.len() as u64 > self.max_size {
+            log_file.file.sync_all()?;
+            let rotated_path = rotated_path(&self.path, now());
+            fs::rename(&self.path, &rotated_path)?;
+            log::info!("Audit log rotated to {:?}", rotated_path);
+            let last_hash = log_file.last_hash.take();
+            *log_file = open_file(&self.path, &self.password, self.hash_iterations)?;
+            log_file.last_hash = last_hash;
+        }
+
+        log_file.file.write_all(line.as_bytes())?;
+        log_file.file.flush()?;
+        log_file.size += line.len() as u64;
+        log_file.last_hash = Some(entry_hash(&plaintext));
+
+        Ok(())
+    }
+}
+
+/// Decrypt all the entries of an audit log file, checking that each entry is chained to the
+/// previous one. The first entry is chained to the last one of the previous file, which is not
+/// checked.
+///
+/// Entries written before they were chained are not checked either.
+pub fn read(path: &Path, password: &ProtectedString, hash_iterations: u32) -> Result<Vec<Entry>> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let salt = match lines.next() {
+        Some(line) => hex::decode(line?)?,
+        None => return Ok(vec![]),
+    };
+    let key = crypto::key_from_password(password.as_ref(), &salt, hash_iterations);
+
+    let mut entries = vec![];
+    let mut last_hash = None;
+    for line in lines {
+        let plaintext = decrypt_line(&key, &line?)?;
+        let entry: Entry = serde_json::from_slice(&plaintext)?;
+        if last_hash.is_some() && entry.prev_hash.is_some() && entry.prev_hash != last_hash {
+            Err(format_err!(
+                "audit log entry {} is not chained to the previous one",
+                entries.len() + 1
+            ))?
+        }
+        last_hash = Some(entry_hash(&plaintext));
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Decrypt a hex-encoded entry of a log file.
+fn decrypt_line(key: &types::Secret, line: &str) -> Result<Vec<u8>> {
+    let bytes = hex::decode(line)?;
+    if bytes.len() <= constants::AUDIT_LOG_IV_LENGTH {
+        Err(format_err!("invalid audit log entry length"))?
+    }
+    let (iv, ciphertext) = bytes.split_at(constants::AUDIT_LOG_IV_LENGTH);
+    let plaintext = cipher::decrypt_aes_cbc(key.as_ref(), ciphertext, iv)
+        .map_err(|_| format_err!("wrong audit log password"))?;
+
+    Ok(plaintext)
+}
+
+/// Hex-encoded hash of the plaintext of an entry, which the next entry is chained to.
+fn entry_hash(plaintext: &[u8]) -> String {
+    hex::encode(crypto::calculate_sha256(plaintext))
+}
+
+/// Open a log file for appending, writing the key salt first if the file is new, or reading the
+/// last entry of the file to chain the next one to it otherwise.
+fn open_file(path: &Path, password: &ProtectedString, hash_iterations: u32) -> Result<LogFile> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?;
+    let size = file.metadata()?.len();
+
+    if size == 0 {
+        let salt = crypto::salt(&mut rand::rngs::OsRng, constants::AUDIT_LOG_SALT_LENGTH);
+        let header = format!("{}\n", hex::encode(&salt));
+        file.write_all(header.as_bytes())?;
+        file.flush()?;
+        let key = crypto::key_from_password(password.as_ref(), &salt, hash_iterations);
+
+        return Ok(LogFile {
+            file,
+            key,
+            size: header.len() as u64,
+            last_hash: None,
+        });
+    }
+
+    let mut lines = BufReader::new(&file).lines();
+    let salt = match lines.next() {
+        Some(line) => hex::decode(line?)?,
+        None => Err(format_err!("invalid audit log header"))?,
+    };
+    let key = crypto::key_from_password(password.as_ref(), &salt, hash_iterations);
+    let last_hash = match lines.last() {
+        Some(line) => Some(entry_hash(&decrypt_line(&key, &line?)?)),
+        None => None,
+    };
+
+    Ok(LogFile {
+        file,
+        key,
+        size,
+        last_hash,
+    })
+}
+
+/// Path a full log file is renamed to when rotated at the given timestamp, which is not used by
+/// any previous rotation.
+fn rotated_path(path: &Path, timestamp: i64) -> PathBuf {
+    (0..)
+        .map(|counter| {
+            let mut rotated_path = path.to_path_buf().into_os_string();
+            rotated_path.push(format!(".{}", timestamp));
+            if counter > 0 {
+                rotated_path.push(format!(".{}", counter));
+            }
+
+            PathBuf::from(rotated_path)
+        })
+        .find(|rotated_path| !rotated_path.exists())
+        .expect("there is always a free rotated path")
+}
+
+/// Current timestamp in seconds.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory for the log files of a test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "witnet-wallet-audit-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn entry(method: &str) -> Entry {
+        Entry::new(method, &rpc::Params::None)
+    }
+
+    #[test]
+    fn entries_are_read_back() {
+        let path = test_dir("read").join("audit.log");
+        let password = ProtectedString::from("audit password");
+        let log = AuditLog::open(path.clone(), password.clone(), 1, 1024 * 1024).unwrap();
+        log.record(entry("sendVTT").with_result(&Ok("txid")))
+            .unwrap();
+        log.record(entry("exportXprv").with_result::<()>(&Err(rpc::Error::internal_error())))
+            .unwrap();
+
+        let entries = read(&path, &password, 1).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].method, "sendVTT");
+        assert_eq!(
+            entries[0].result_hash,
+            Some(hex::encode(crypto::calculate_sha256(b"\"txid\"")))
+        );
+        assert_eq!(entries[0].error, None);
+        assert_eq!(entries[1].method, "exportXprv");
+        assert_eq!(entries[1].result_hash, None);
+        assert!(entries[1].error.is_some());
+
+        assert!(read(&path, &"wrong password".into(), 1).is_err());
+    }
+
+    #[test]
+    fn rotations_in_the_same_second_keep_every_file() {
+        let dir = test_dir("rotate");
+        let path = dir.join("audit.log");
+        let password = ProtectedString::from("audit password");
+        // Every entry fills a file, so each record rotates the log
+        let log = AuditLog::open(path.clone(), password.clone(), 1, 1).unwrap();
+        for _ in 0..4 {
+            log.record(entry("sendVTT")).unwrap();
+        }
+
+        let mut entries = 0;
+        for file in fs::read_dir(&dir).unwrap() {
+            entries += read(&file.unwrap().path(), &password, 1).unwrap().len();
+        }
+        assert_eq!(entries, 4);
+    }
+
+    #[test]
+    fn entries_are_chained() {
+        let path = test_dir("chain").join("audit.log");
+        let password = ProtectedString::from("audit password");
+        let log = AuditLog::open(path.clone(), password.clone(), 1, 1024 * 1024).unwrap();
+        log.record(entry("createWallet")).unwrap();
+        log.record(entry("unlockWallet")).unwrap();
+        drop(log);
+        // The chain goes on when the log is opened again
+        let log = AuditLog::open(path.clone(), password.clone(), 1, 1024 * 1024).unwrap();
+        log.record(entry("sendVTT")).unwrap();
+
+        let entries = read(&path, &password, 1).unwrap();
+        assert_eq!(entries[0].prev_hash, None);
+        assert!(entries[1].prev_hash.is_some());
+        assert!(entries[2].prev_hash.is_some());
+
+        // Removing an entry breaks the chain
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        fs::write(&path, format!("{}\n{}\n{}\n", lines[0], lines[1], lines[3])).unwrap();
+        assert!(read(&path, &password, 1).is_err());
+    }
+
+    #[test]
+    fn rotated_paths_are_not_reused() {
+        let dir = test_dir("paths");
+        let path = dir.join("audit.log");
+
+        let first = rotated_path(&path, 1234);
+        assert_eq!(first, dir.join("audit.log.1234"));
+        fs::write(&first, "").unwrap();
+
+        let second = rotated_path(&path, 1234);
+        assert_eq!(second, dir.join("audit.log.1234.1"));
+        fs::write(&second, "").unwrap();
+
+        assert_eq!(rotated_path(&path, 1234), dir.join("audit.log.1234.2"));
+        assert_eq!(rotated_path(&path, 1235), dir.join("audit.log.1235"));
+    }
+}
@@ -13,15 +13,92 @@ pub static DEFAULT_PAGINATION_LIMIT: u32 = 25;
 /// results.
 pub static MAX_PAGINATION_LIMIT: u32 = 1000;
 
+/// Maximum number of deposit addresses that can be registered in a single request.
+pub static MAX_DEPOSIT_ADDRESSES_PER_REQUEST: usize = 10_000;
+
+/// Seconds an output spent by a signed transaction stays reserved if no block spending it is
+/// indexed, e.g. because the transaction was never broadcast.
+pub static UTXO_RESERVATION_SECS: i64 = 600;
+
+/// Scheme of the payment request URIs.
+pub static PAYMENT_URI_SCHEME: &str = "wit";
+
 /// Purpose section for an account keypath.
 pub static KEYPATH_PURPOSE: u32 = 3;
 
 /// Coin-type section for an account keypath.
 pub static KEYPATH_COIN_TYPE: u32 = 4919;
 
+/// Account section of the keypath of the key used to sign exported wallet metadata. It is the last
+/// hardened account index, so it is never used by a regular account.
+pub static METADATA_KEYPATH_ACCOUNT: u32 = 0x7fff_ffff;
+
+/// Version of the exported wallet metadata documents.
+pub static METADATA_VERSION: u32 = 1;
+
+/// Human readable part of the bech32-encoded extended private keys.
+pub static XPRV_HRP: &str = "xprv";
+
+/// Human readable part of the bech32-encoded extended public keys.
+pub static XPUB_HRP: &str = "xpub";
+
+/// Prefix of the challenges signed to prove the control of an address, so the signature of an
+/// address proof can never be valid for a transaction.
+pub static ADDRESS_PROOF_PREFIX: &str = "Witnet address proof:";
+
+/// Length in bytes of the salt used to encrypt an exported extended private key.
+pub static XPRV_SALT_LENGTH: usize = 32;
+
+/// Length in bytes of the iv used to encrypt an exported extended private key.
+pub static XPRV_IV_LENGTH: usize = 16;
+
+/// Number of pbkdf2 iterations used to derive the encryption key of an exported extended private
+/// key from its backup password.
+pub static XPRV_HASH_ITERATIONS: u32 = 10_000;
+
+/// Length in bytes of the salt used to derive the encryption key of the audit log.
+pub static AUDIT_LOG_SALT_LENGTH: usize = 32;
+
+/// Length in bytes of the iv used to encrypt each audit log entry.
+pub static AUDIT_LOG_IV_LENGTH: usize = 16;
+
+/// Phrase that must be typed to confirm a plaintext export of a wallet database.
+pub static PLAINTEXT_EXPORT_CONFIRM_PHRASE: &str =
+    "I understand my wallet will be exported unencrypted";
+
 /// Special key used to check if a decryption key is the correct one
 /// for a wallet.
 pub static ENCRYPTION_CHECK_KEY: &str = "ENC_KEY";
 
 /// Special value stored with `ENCRYPTION_CHECK_KEY`.
 pub static ENCRYPTION_CHECK_VALUE: () = ();
+
+/// Seconds to wait before retrying a failed upload to the remote storage, doubled on every
+/// consecutive failure.
+pub static REMOTE_UPLOAD_BACKOFF_BASE_SECS: u64 = 5;
+
+/// Maximum seconds to wait before retrying a failed upload to the remote storage.
+pub static REMOTE_UPLOAD_BACKOFF_MAX_SECS: u64 = 600;
+
+/// Special key storing the number of the next write batch of a wallet to be replicated to the
+/// remote storage. It is only present in the wallets replicated since their creation.
+pub static REPLICA_NEXT_BATCH_KEY: &str = "REPLICA_NEXT_BATCH";
+
+/// Prefix of the unencrypted keys of the write batches waiting to be uploaded to the remote
+/// storage, followed by the id of their wallet and their number.
+pub static REPLICA_PENDING_BATCH_PREFIX: &str = "REPLICA_PENDING_BATCH/";
+
+/// Seconds to wait for an external signer to respond to a signature request, which may need to be
+/// confirmed by the user on the device.
+pub static SIGNATURE_REQUEST_TIMEOUT_SECS: u64 = 300;
+
+/// Maximum number of notifications kept in the journal of a session for `getNotificationsSince`.
+pub static NOTIFICATION_JOURNAL_CAPACITY: usize = 1_000;
+
+/// Reward for each witness of the data requests of low, medium and high priority, in nanowits.
+/// Miners commit first to the data requests with the highest reward per witness.
+pub static DR_PRIORITY_REWARDS_PER_WITNESS: [u64; 3] = [1_000, 10_000, 100_000];
+
+/// Fee for the miner of each commit, reveal and tally transaction of the data requests of low,
+/// medium and high priority, in nanowits.
+pub static DR_PRIORITY_FEES_PER_TRANSACTION: [u64; 3] = [1, 10, 100];
@@ -1,7 +1,11 @@
+use bech32::{FromBase32 as _, ToBase32 as _};
+use failure::format_err;
+
 pub use witnet_crypto::hash::calculate_sha256;
-use witnet_crypto::{hash::HashFunction, key::MasterKeyGen, pbkdf2::pbkdf2_sha256};
+use witnet_crypto::{cipher, hash::HashFunction, key::MasterKeyGen, pbkdf2::pbkdf2_sha256};
+use witnet_protected::Protected;
 
-use crate::types;
+use crate::{constants, types};
 
 /// Result type for cryptographic operations that can fail.
 pub type Result<T> = std::result::Result<T, failure::Error>;
@@ -20,15 +24,191 @@ pub fn gen_master_key(
 
             MasterKeyGen::new(seed).with_key(salt).generate()?
         }
-        types::SeedSource::Xprv => {
-            // TODO: Implement key generation from xprv
-            unimplemented!("xprv not implemented yet")
+        types::SeedSource::Xprv(xprv, password) => {
+            let (key, depth) = decode_xprv(xprv.as_ref(), password.as_ref())?;
+
+            if depth != types::XprvDepth::Master.depth() {
+                Err(format_err!("xprv is not a master key (depth {})", depth))?
+            }
+
+            key
         }
     };
 
     Ok(key)
 }
 
+/// Encode an extended private key as a bech32 `xprv` string.
+///
+/// The key is encrypted with a key derived from the backup password, and the encoded payload is
+/// `salt || iv || ciphertext`, where the plaintext is the BIP-32 depth of the key followed by its
+/// secret and its chain code.
+pub fn encode_xprv(
+    key: &types::ExtendedSK,
+    depth: u8,
+    password: &[u8],
+    salt: &[u8],
+    iv: &[u8],
+) -> Result<String> {
+    let secret = key_from_password(password, salt, constants::XPRV_HASH_ITERATIONS);
+    let plaintext = Protected::from([&[depth][..], key.concat().as_ref()].concat());
+    let ciphertext = cipher::encrypt_aes_cbc(secret.as_ref(), plaintext.as_ref(), iv)?;
+    let payload = [salt, iv, &ciphertext[..]].concat();
+
+    Ok(encode_long_bech32(constants::XPRV_HRP, &payload))
+}
+
+/// Decode a bech32 `xprv` string encoded with [encode_xprv](encode_xprv).
+///
+/// Returns the extended private key and its BIP-32 depth.
+pub fn decode_xprv(xprv: &str, password: &[u8]) -> Result<(types::ExtendedSK, u8)> {
+    let (hrp, payload) = decode_long_bech32(xprv)?;
+
+    if hrp != constants::XPRV_HRP {
+        Err(format_err!("invalid xprv prefix: {}", hrp))?
+    }
+
+    let header_length = constants::XPRV_SALT_LENGTH + constants::XPRV_IV_LENGTH;
+
+    if payload.len() <= header_length {
+        Err(format_err!("invalid xprv length"))?
+    }
+
+    let (salt, rest) = payload.split_at(constants::XPRV_SALT_LENGTH);
+    let (iv, ciphertext) = rest.split_at(constants::XPRV_IV_LENGTH);
+    let secret = key_from_password(password, salt, constants::XPRV_HASH_ITERATIONS);
+    let plaintext = Protected::from(
+        cipher::decrypt_aes_cbc(secret.as_ref(), ciphertext, iv)
+            .map_err(|_| format_err!("wrong xprv backup password"))?,
+    );
+    let bytes: &[u8] = plaintext.as_ref();
+
+    // depth (1 byte) + secret key (32 bytes) + chain code (32 bytes)
+    if bytes.len() != 65 {
+        Err(format_err!("invalid xprv length"))?
+    }
+
+    let secret_key = types::SK::from_slice(&bytes[1..33])?;
+    let chain_code = Protected::from(&bytes[33..]);
+
+    Ok((types::ExtendedSK::new(secret_key, chain_code), bytes[0]))
+}
+
+/// Encode an extended public key as a bech32 `xpub` string.
+///
+/// The encoded payload is the BIP-32 depth of the key followed by its compressed public key and
+/// its chain code.
+pub fn encode_xpub(key: &types::ExtendedPK, depth: u8) -> Result<String> {
+    let payload = [
+        &[depth][..],
+        key.key.serialize().as_ref(),
+        key.chain_code.as_ref(),
+    ]
+    .concat();
+
+    Ok(encode_long_bech32(constants::XPUB_HRP, &payload))
+}
+
+/// Characters of the bech32 alphabet, by value
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Encode bytes as a bech32 string.
+///
+/// Extended keys do not fit in the 90 characters allowed by `bech32::encode` and
+/// `bech32::decode`, which are meant for addresses, so they are encoded with this function
+/// instead. The checksum is the same, so the strings can be decoded by any bech32 decoder that
+/// accepts longer strings.
+fn encode_long_bech32(hrp: &str, payload: &[u8]) -> String {
+    let data: Vec<u8> = payload.to_base32().into_iter().map(|u| u.to_u8()).collect();
+    let values = [bech32_expand_hrp(hrp), data.clone(), vec![0; 6]].concat();
+    let checksum = bech32_polymod(&values) ^ 1;
+    let checksum = (0..6).map(|i| ((checksum >> (5 * (5 - i))) & 31) as u8);
+
+    let mut encoded = format!("{}1", hrp);
+    encoded.extend(
+        data.into_iter()
+            .chain(checksum)
+            .map(|value| char::from(BECH32_CHARSET[usize::from(value)])),
+    );
+
+    encoded
+}
+
+/// Decode a bech32 string encoded with [encode_long_bech32](encode_long_bech32), returning its
+/// human-readable part and its payload.
+fn decode_long_bech32(encoded: &str) -> Result<(String, Vec<u8>)> {
+    if encoded.to_lowercase() != encoded && encoded.to_uppercase() != encoded {
+        Err(format_err!("bech32 string has mixed case"))?
+    }
+    let encoded = encoded.to_lowercase();
+    let separator = encoded
+        .rfind('1')
+        .ok_or_else(|| format_err!("bech32 string has no separator"))?;
+    let (hrp, data) = (&encoded[..separator], &encoded[separator + 1..]);
+    if hrp.is_empty() || data.len() < 6 {
+        Err(format_err!("invalid bech32 length"))?
+    }
+
+    let data = data
+        .bytes()
+        .map(|c| {
+            BECH32_CHARSET
+                .iter()
+                .position(|&value| value == c)
+                .map(|value| value as u8)
+                .ok_or_else(|| format_err!("invalid bech32 character {:?}", char::from(c)))
+        })
+        .collect::<Result<Vec<u8>>>()?;
+    if bech32_polymod(&[bech32_expand_hrp(hrp), data.clone()].concat()) != 1 {
+        Err(format_err!("invalid bech32 checksum"))?
+    }
+
+    let data = data[..data.len() - 6]
+        .iter()
+        .map(|&value| bech32::u5::try_from_u8(value))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let payload = Vec::<u8>::from_base32(&data)?;
+
+    Ok((hrp.to_string(), payload))
+}
+
+/// Checksum of the bech32 values, as defined in BIP-173.
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+
+    values.iter().fold(1, |checksum, &value| {
+        let top = checksum >> 25;
+        let checksum = (checksum & 0x01ff_ffff) << 5 ^ u32::from(value);
+
+        GENERATOR
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(checksum, |checksum, (_, generator)| checksum ^ generator)
+    })
+}
+
+/// Human-readable part of a bech32 string expanded into the values covered by the checksum.
+fn bech32_expand_hrp(hrp: &str) -> Vec<u8> {
+    hrp.bytes()
+        .map(|c| c >> 5)
+        .chain(Some(0))
+        .chain(hrp.bytes().map(|c| c & 31))
+        .collect()
+}
+
+/// Hash signed to prove the control of an address: the SHA-256 hash of the address proof prefix
+/// followed by the challenge.
+pub fn address_proof_hash(challenge: &str) -> witnet_crypto::hash::Sha256 {
+    calculate_sha256(format!("{}{}", constants::ADDRESS_PROOF_PREFIX, challenge).as_bytes())
+}
+
 /// Generate an encryption key using pbkdf2.
 pub fn key_from_password(password: &[u8], salt: &[u8], iterations: u32) -> types::Secret {
     pbkdf2_sha256(password, salt, iterations)
@@ -84,3 +264,94 @@ where
 
     bytes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn master_key() -> types::ExtendedSK {
+        MasterKeyGen::new([7; 32]).generate().unwrap()
+    }
+
+    #[test]
+    fn long_bech32_is_bech32() {
+        let address = bech32::encode("twit", [0xaa; 20].to_base32()).unwrap();
+
+        assert_eq!(encode_long_bech32("twit", &[0xaa; 20]), address);
+        assert_eq!(
+            decode_long_bech32(&address).unwrap(),
+            ("twit".to_string(), vec![0xaa; 20])
+        );
+    }
+
+    #[test]
+    fn xprv_roundtrip() {
+        let key = master_key();
+        let xprv = encode_xprv(&key, 0, b"backup password", &[1; 32], &[2; 16]).unwrap();
+        assert!(xprv.starts_with("xprv1"));
+
+        let (decoded, depth) = decode_xprv(&xprv, b"backup password").unwrap();
+        assert_eq!(depth, 0);
+        assert_eq!(decoded.concat().as_ref(), key.concat().as_ref());
+
+        // Uppercase strings are valid bech32 as well
+        let (decoded, _) = decode_xprv(&xprv.to_uppercase(), b"backup password").unwrap();
+        assert_eq!(decoded.concat().as_ref(), key.concat().as_ref());
+    }
+
+    #[test]
+    fn xprv_is_checked() {
+        let xprv = encode_xprv(&master_key(), 0, b"backup password", &[1; 32], &[2; 16]).unwrap();
+
+        assert!(decode_xprv(&xprv, b"another password").is_err());
+
+        // A single changed character breaks the checksum
+        let mut corrupted = xprv.into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+        let corrupted = String::from_utf8(corrupted).unwrap();
+        assert!(decode_xprv(&corrupted, b"backup password").is_err());
+    }
+
+    #[test]
+    fn exported_master_key_is_imported() {
+        let key = master_key();
+        let xprv = encode_xprv(&key, 0, b"backup password", &[1; 32], &[2; 16]).unwrap();
+        let source = types::SeedSource::Xprv(xprv.into(), "backup password".into());
+
+        let imported = gen_master_key("", &[], &source).unwrap();
+        assert_eq!(imported.concat().as_ref(), key.concat().as_ref());
+
+        // Account keys can not be imported as the master key of a wallet
+        let xprv = encode_xprv(&key, 3, b"backup password", &[1; 32], &[2; 16]).unwrap();
+        let source = types::SeedSource::Xprv(xprv.into(), "backup password".into());
+        assert!(gen_master_key("", &[], &source).is_err());
+    }
+
+    #[test]
+    fn xpub_payload_is_the_depth_key_and_chain_code() {
+        let engine = types::SignEngine::signing_only();
+        let key = types::ExtendedPK::from_secret_key(&engine, &master_key());
+        let xpub = encode_xpub(&key, 3).unwrap();
+        assert!(xpub.starts_with("xpub1"));
+
+        let (hrp, payload) = decode_long_bech32(&xpub).unwrap();
+        assert_eq!(hrp, constants::XPUB_HRP);
+        assert_eq!(payload.len(), 1 + 33 + 32);
+        assert_eq!(payload[0], 3);
+        assert_eq!(&payload[1..34], key.key.serialize().as_ref());
+        assert_eq!(&payload[34..], key.chain_code.as_ref());
+    }
+
+    #[test]
+    fn address_proof_hash_is_prefixed() {
+        assert_eq!(
+            address_proof_hash("challenge").as_ref(),
+            calculate_sha256(b"Witnet address proof:challenge").as_ref()
+        );
+        assert_ne!(
+            address_proof_hash("challenge").as_ref(),
+            calculate_sha256(b"challenge").as_ref()
+        );
+    }
+}
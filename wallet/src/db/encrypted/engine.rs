@@ -26,9 +26,16 @@ impl CryptoEngine {
     where
         T: serde::de::DeserializeOwned,
     {
-        let decrypted = cipher::decrypt_aes_cbc(self.key.as_ref(), bytes, &self.iv)?;
+        let decrypted = self.decrypt_bytes(bytes)?;
         let value = bincode::deserialize(&decrypted)?;
 
         Ok(value)
     }
+
+    /// Decrypt without deserializing, returning the bincode-serialized value.
+    pub fn decrypt_bytes(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let decrypted = cipher::decrypt_aes_cbc(self.key.as_ref(), bytes, &self.iv)?;
+
+        Ok(decrypted)
+    }
 }
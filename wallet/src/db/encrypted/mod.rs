@@ -3,10 +3,10 @@ use std::sync::Arc;
 use witnet_crypto::cipher;
 
 use super::*;
-use crate::types;
+use crate::{constants, types};
 
 mod engine;
-mod prefix;
+pub(super) mod prefix;
 mod write_batch;
 
 pub use write_batch::*;
@@ -16,6 +16,7 @@ pub struct EncryptedDb {
     engine: engine::CryptoEngine,
     db: Arc<rocksdb::DB>,
     prefixer: prefix::Prefixer,
+    replica: Option<Replica>,
 }
 
 impl EncryptedDb {
@@ -26,8 +27,126 @@ impl EncryptedDb {
             db,
             engine,
             prefixer: prefix::Prefixer::new(prefix),
+            replica: None,
         }
     }
+
+    /// Push every write batch to the remote storage before writing it to the local database.
+    pub fn with_replica(self, replica: Replica) -> Self {
+        Self {
+            replica: Some(replica),
+            ..self
+        }
+    }
+}
+
+impl EncryptedDb {
+    /// Decrypt every record of this database, returning the keys without their prefix and the
+    /// bincode-serialized values.
+    pub fn export_plaintext(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut records = vec![];
+        for (key, _, enc_val) in self.encrypted_records()? {
+            let value = self.engine.decrypt_bytes(&enc_val)?;
+            records.push((key, value));
+        }
+
+        Ok(records)
+    }
+
+    /// Records of this database, found through its index: the keys without their prefix, and the
+    /// encrypted keys and values.
+    fn encrypted_records(&self) -> Result<Vec<(Vec<u8>, Vec<u8>, Vec<u8>)>> {
+        self.build_index()?;
+
+        let index_prefix = self.prefixer.index_key(b"");
+        let mut records = vec![];
+        let iter = self.as_ref().iterator(rocksdb::IteratorMode::From(
+            &index_prefix,
+            rocksdb::Direction::Forward,
+        ));
+        for (index_key, _) in iter {
+            if !index_key.starts_with(&index_prefix) {
+                break;
+            }
+            let enc_key = &index_key[index_prefix.len()..];
+            let enc_val = match self.as_ref().get(enc_key)? {
+                Some(enc_val) => enc_val.to_vec(),
+                // Not expected, as the index is written along with the records
+                None => continue,
+            };
+            let prefix_key: Vec<u8> = self.engine.decrypt(enc_key)?;
+            if let Some(key) = self.prefixer.strip(&prefix_key) {
+                records.push((key.to_vec(), enc_key.to_vec(), enc_val));
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Index the records written before the index existed, or restored from the remote storage.
+    /// This needs to decrypt the keys of the whole database once, skipping the records of other
+    /// wallets, which cannot be decrypted with this key.
+    fn build_index(&self) -> Result<()> {
+        let marker = self.prefixer.index_marker();
+        if self.as_ref().get(&marker)?.is_some() {
+            return Ok(());
+        }
+
+        let mut batch = rocksdb::WriteBatch::default();
+        for (enc_key, _) in self.as_ref().iterator(rocksdb::IteratorMode::Start) {
+            let prefix_key: Vec<u8> = match self.engine.decrypt(&enc_key) {
+                Ok(prefix_key) => prefix_key,
+                Err(_) => continue,
+            };
+            if self.prefixer.strip(&prefix_key).is_some() {
+                batch.put(self.prefixer.index_key(&enc_key), b"")?;
+            }
+        }
+        batch.put(marker, b"")?;
+        self.as_ref().write(batch)?;
+
+        Ok(())
+    }
+}
+
+/// Check the batches of a wallet downloaded from the remote storage before writing them into the
+/// local database, returning their entries still encrypted. Fails with `DbKeyNotFound` if the
+/// password-derived `key` cannot read the special check value, and with `ForeignRecord` if any of
+/// the entries cannot be decrypted or does not belong to the wallet with the given `prefix`.
+pub fn validate_replica(
+    prefix: Vec<u8>,
+    key: types::Secret,
+    iv: Vec<u8>,
+    batches: Vec<RemoteBatch>,
+) -> Result<RemoteBatch> {
+    let engine = engine::CryptoEngine::new(key, iv);
+    let prefixer = prefix::Prefixer::new(prefix);
+    let entries: RemoteBatch = batches.into_iter().flatten().collect();
+
+    // Check if the key is able to read the special stored value
+    let check_key = engine.encrypt(&prefixer.prefix(constants::ENCRYPTION_CHECK_KEY))?;
+    let check_value = entries
+        .iter()
+        .rev()
+        .find(|(enc_key, _)| *enc_key == check_key)
+        .map(|(_, enc_val)| enc_val)
+        .ok_or(Error::DbKeyNotFound)?;
+    engine.decrypt::<()>(check_value)?;
+
+    for (enc_key, enc_val) in &entries {
+        let prefix_key: Vec<u8> = engine.decrypt(enc_key).map_err(|_| Error::ForeignRecord)?;
+        if prefixer.strip(&prefix_key).is_none() {
+            return Err(Error::ForeignRecord);
+        }
+        // Empty values are deletions
+        if !enc_val.is_empty() {
+            engine
+                .decrypt_bytes(enc_val)
+                .map_err(|_| Error::ForeignRecord)?;
+        }
+    }
+
+    Ok(entries)
 }
 
 impl AsRef<rocksdb::DB> for EncryptedDb {
@@ -63,17 +182,39 @@ impl Database for EncryptedDb {
         K: AsRef<[u8]>,
         V: serde::Serialize,
     {
-        let prefix_key = self.prefixer.prefix(&key);
-        let enc_key = self.engine.encrypt(&prefix_key)?;
-        let enc_val = self.engine.encrypt(&value)?;
+        let mut batch = self.batch();
+        batch.put(key, value)?;
 
-        self.as_ref().put(enc_key, enc_val)?;
+        self.write(batch)
+    }
 
-        Ok(())
+    fn delete<K>(&self, key: K) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+    {
+        let mut batch = self.batch();
+        batch.delete(key)?;
+
+        self.write(batch)
     }
 
-    fn write(&self, batch: Self::WriteBatch) -> Result<()> {
-        self.as_ref().write(batch.into())?;
+    fn write(&self, mut batch: Self::WriteBatch) -> Result<()> {
+        match &self.replica {
+            Some(replica) => {
+                // The batch is queued for upload once it is written locally, so writes never wait
+                // for the remote storage, and it is kept in the local database until uploaded.
+                // Holding the lock keeps the batches numbered in the order they are written.
+                let mut next_batch = replica.lock()?;
+                batch.put(constants::REPLICA_NEXT_BATCH_KEY, Some(*next_batch + 1))?;
+                let entries = batch.entries().to_vec();
+                let mut write_batch: rocksdb::WriteBatch = batch.into();
+                let body = replica.keep(&mut write_batch, *next_batch, &entries)?;
+                self.as_ref().write(write_batch)?;
+                replica.push(*next_batch, body)?;
+                *next_batch += 1;
+            }
+            None => self.as_ref().write(batch.into())?,
+        }
 
         Ok(())
     }
@@ -84,7 +225,240 @@ impl Database for EncryptedDb {
         Ok(())
     }
 
+    /// Keys of the records of this database without their prefix, listed through its index.
+    fn records(&self) -> Result<Vec<(Vec<u8>, usize)>> {
+        let records = self
+            .encrypted_records()?
+            .into_iter()
+            .map(|(key, enc_key, enc_val)| (key, enc_key.len() + enc_val.len()))
+            .collect();
+
+        Ok(records)
+    }
+
+    /// Compact the whole database, which is shared by all the wallets.
+    fn compact(&self) -> Result<()> {
+        self.as_ref().compact_range::<&[u8], &[u8]>(None, None);
+
+        Ok(())
+    }
+
     fn batch(&self) -> Self::WriteBatch {
-        EncryptedWriteBatch::new(self.prefixer.clone(), self.engine.clone())
+        EncryptedWriteBatch::new(
+            self.prefixer.clone(),
+            self.engine.clone(),
+            self.replica.is_some(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto;
+
+    /// Encrypted entries of a wallet database with the given prefix and password
+    fn replica_entries(prefix: &[u8], password: &[u8], keys: &[&str]) -> RemoteBatch {
+        let key = crypto::key_from_password(password, b"salt", 1);
+        let engine = engine::CryptoEngine::new(key, vec![0; 16]);
+        let prefixer = prefix::Prefixer::new(prefix.to_vec());
+
+        keys.iter()
+            .map(|key| {
+                let enc_key = engine.encrypt(&prefixer.prefix(key)).unwrap();
+                let enc_val = if *key == constants::ENCRYPTION_CHECK_KEY {
+                    engine.encrypt(&constants::ENCRYPTION_CHECK_VALUE).unwrap()
+                } else {
+                    engine.encrypt(&1u32).unwrap()
+                };
+
+                (enc_key, enc_val)
+            })
+            .collect()
+    }
+
+    fn is_foreign_record(result: &Result<RemoteBatch>) -> bool {
+        match result {
+            Err(Error::ForeignRecord) => true,
+            _ => false,
+        }
+    }
+
+    fn validate(prefix: &[u8], password: &[u8], batches: Vec<RemoteBatch>) -> Result<RemoteBatch> {
+        let key = crypto::key_from_password(password, b"salt", 1);
+
+        validate_replica(prefix.to_vec(), key, vec![0; 16], batches)
+    }
+
+    #[test]
+    fn validate_replica_of_the_wallet() {
+        let batches = vec![
+            replica_entries(b"wallet", b"password", &[constants::ENCRYPTION_CHECK_KEY]),
+            replica_entries(b"wallet", b"password", &["name", "balance"]),
+        ];
+
+        let entries = validate(b"wallet", b"password", batches).unwrap();
+
+        assert_eq!(entries.len(), 3);
+    }
+
+    #[test]
+    fn validate_replica_with_wrong_password() {
+        let batches = vec![replica_entries(
+            b"wallet",
+            b"password",
+            &[constants::ENCRYPTION_CHECK_KEY, "name"],
+        )];
+
+        let result = validate(b"wallet", b"wrong password", batches);
+
+        match result {
+            Err(Error::DbKeyNotFound) => {}
+            _ => panic!("the replica was read with the wrong password"),
+        }
+    }
+
+    #[test]
+    fn validate_replica_with_foreign_keys() {
+        let own = replica_entries(b"wallet", b"password", &[constants::ENCRYPTION_CHECK_KEY]);
+
+        // Records of another wallet encrypted with the same password
+        let other_wallet = replica_entries(b"other", b"password", &["name"]);
+        let result = validate(b"wallet", b"password", vec![own.clone(), other_wallet]);
+        assert!(is_foreign_record(&result));
+
+        // Records encrypted with another key, e.g. the list of wallet ids
+        let other_key = replica_entries(b"wallet", b"other password", &["name"]);
+        let result = validate(b"wallet", b"password", vec![own.clone(), other_key]);
+        assert!(is_foreign_record(&result));
+
+        // Unencrypted records
+        let plain = vec![(b"wallet_ids".to_vec(), b"[]".to_vec())];
+        let result = validate(b"wallet", b"password", vec![own, plain]);
+        assert!(is_foreign_record(&result));
+    }
+
+    /// Empty rocksdb database for a test
+    fn test_db(name: &str) -> Arc<rocksdb::DB> {
+        let path =
+            std::env::temp_dir().join(format!("witnet-wallet-db-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&path);
+
+        Arc::new(rocksdb::DB::open_default(path).unwrap())
+    }
+
+    fn wallet_db(db: &Arc<rocksdb::DB>, prefix: &[u8], password: &[u8]) -> EncryptedDb {
+        let key = crypto::key_from_password(password, b"salt", 1);
+
+        EncryptedDb::new(db.clone(), prefix.to_vec(), key, vec![0; 16])
+    }
+
+    fn record_keys(db: &EncryptedDb) -> Vec<Vec<u8>> {
+        let mut keys: Vec<_> = db
+            .records()
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        keys.sort();
+
+        keys
+    }
+
+    #[test]
+    fn records_of_each_wallet() {
+        let db = test_db("records");
+        let wallet = wallet_db(&db, b"wallet", b"password");
+        let other = wallet_db(&db, b"other", b"other password");
+        // A prefix starting with the prefix of the wallet, with the same key
+        let prefixed = wallet_db(&db, b"wallet-x", b"password");
+        wallet.put("name", "wallet").unwrap();
+        let mut batch = wallet.batch();
+        batch.put("balance", 1u64).unwrap();
+        wallet.write(batch).unwrap();
+        other.put("name", "other").unwrap();
+        prefixed.put("name", "prefixed").unwrap();
+
+        assert_eq!(
+            record_keys(&wallet),
+            vec![b"balance".to_vec(), b"name".to_vec()]
+        );
+        assert_eq!(record_keys(&other), vec![b"name".to_vec()]);
+        assert_eq!(record_keys(&prefixed), vec![b"name".to_vec()]);
+        let exported = wallet.export_plaintext().unwrap();
+        assert_eq!(exported.len(), 2);
+
+        wallet.delete("name").unwrap();
+        assert_eq!(record_keys(&wallet), vec![b"balance".to_vec()]);
+        assert_eq!(record_keys(&other), vec![b"name".to_vec()]);
+    }
+
+    #[test]
+    fn export_plaintext_of_a_wallet() {
+        let db = test_db("export");
+        let wallet = wallet_db(&db, b"wallet", b"password");
+        let other = wallet_db(&db, b"other", b"password");
+        wallet.put("name", "wallet").unwrap();
+        wallet.put("balance", 1u64).unwrap();
+        other.put("name", "other").unwrap();
+
+        let mut exported = wallet.export_plaintext().unwrap();
+        exported.sort();
+
+        // The keys are returned without their prefix, and the values bincode-serialized
+        assert_eq!(
+            exported,
+            vec![
+                (b"balance".to_vec(), bincode::serialize(&1u64).unwrap()),
+                (b"name".to_vec(), bincode::serialize("wallet").unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn deletes_are_replicated() {
+        let db = test_db("deletes");
+        let wallet = wallet_db(&db, b"wallet", b"password");
+        let mut batch =
+            EncryptedWriteBatch::new(wallet.prefixer.clone(), wallet.engine.clone(), true);
+        batch
+            .put(
+                constants::ENCRYPTION_CHECK_KEY,
+                constants::ENCRYPTION_CHECK_VALUE,
+            )
+            .unwrap();
+        batch.put("name", "wallet").unwrap();
+        batch.put("balance", 1u64).unwrap();
+        batch.delete("name").unwrap();
+        let entries = batch.entries().to_vec();
+
+        let recovered_db = test_db("deletes-recovered");
+        let entries = validate(b"wallet", b"password", vec![entries]).unwrap();
+        restore(&recovered_db, b"wallet", entries).unwrap();
+
+        let recovered = wallet_db(&recovered_db, b"wallet", b"password");
+        assert_eq!(
+            record_keys(&recovered),
+            vec![
+                constants::ENCRYPTION_CHECK_KEY.as_bytes().to_vec(),
+                b"balance".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn records_written_without_the_index() {
+        let db = test_db("restored");
+        let wallet = wallet_db(&db, b"wallet", b"password");
+        wallet.put("name", "wallet").unwrap();
+        assert_eq!(record_keys(&wallet), vec![b"name".to_vec()]);
+
+        // Records restored from the remote storage rebuild the index
+        let entries = replica_entries(b"wallet", b"password", &["balance"]);
+        restore(&db, b"wallet", entries).unwrap();
+        assert_eq!(
+            record_keys(&wallet),
+            vec![b"balance".to_vec(), b"name".to_vec()]
+        );
     }
 }
@@ -14,4 +14,60 @@ impl Prefixer {
     {
         [self.prefix.as_slice(), key.as_ref()].concat()
     }
+
+    /// Unencrypted key marking that the index of the records with this prefix is complete. The
+    /// keys of the index are this marker followed by `-` and the encrypted key of a record, so the
+    /// records of a wallet can be listed without decrypting the keys of the other wallets.
+    pub fn index_marker(&self) -> Vec<u8> {
+        // The length of the prefix keeps a prefix from matching the index of a longer one
+        [
+            format!("record-index-{}-", self.prefix.len()).as_bytes(),
+            self.prefix.as_slice(),
+        ]
+        .concat()
+    }
+
+    /// Unencrypted key of the entry of the index for the record with the given encrypted key.
+    pub fn index_key(&self, enc_key: &[u8]) -> Vec<u8> {
+        [self.index_marker().as_slice(), b"-", enc_key].concat()
+    }
+
+    /// Remove the prefix from a key, if the key has it.
+    pub fn strip<'a>(&self, key: &'a [u8]) -> Option<&'a [u8]> {
+        if key.starts_with(&self.prefix) {
+            Some(&key[self.prefix.len()..])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_the_prefix() {
+        let prefixer = Prefixer::new(b"wallet".to_vec());
+
+        assert_eq!(prefixer.prefix("name"), b"walletname".to_vec());
+        assert_eq!(prefixer.strip(b"walletname"), Some(&b"name"[..]));
+        assert_eq!(prefixer.strip(b"wallet"), Some(&b""[..]));
+        assert_eq!(prefixer.strip(b"otherwallet"), None);
+        assert_eq!(prefixer.strip(b"wall"), None);
+    }
+
+    #[test]
+    fn index_of_a_longer_prefix_is_not_matched() {
+        let prefixer = Prefixer::new(b"wallet".to_vec());
+        let longer = Prefixer::new(b"wallet-x".to_vec());
+
+        assert_eq!(
+            prefixer.index_key(b"key"),
+            b"record-index-6-wallet-key".to_vec()
+        );
+        assert!(!longer
+            .index_key(b"key")
+            .starts_with(&prefixer.index_key(b"")));
+    }
 }
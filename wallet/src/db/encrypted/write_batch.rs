@@ -4,16 +4,41 @@ pub struct EncryptedWriteBatch {
     prefixer: prefix::Prefixer,
     batch: rocksdb::WriteBatch,
     engine: engine::CryptoEngine,
+    /// Encrypted entries of the batch, kept only if it is going to be replicated.
+    entries: Option<RemoteBatch>,
 }
 
 impl EncryptedWriteBatch {
-    pub fn new(prefixer: prefix::Prefixer, engine: engine::CryptoEngine) -> Self {
+    pub fn new(prefixer: prefix::Prefixer, engine: engine::CryptoEngine, replicated: bool) -> Self {
         Self {
             prefixer,
             engine,
             batch: Default::default(),
+            entries: if replicated { Some(Vec::new()) } else { None },
         }
     }
+
+    /// Encrypted entries of the batch, empty if it is not going to be replicated.
+    pub fn entries(&self) -> &[(Vec<u8>, Vec<u8>)] {
+        self.entries.as_ref().map_or(&[][..], Vec::as_slice)
+    }
+
+    /// Delete a record. It is replicated as an entry with an empty value.
+    pub fn delete<K>(&mut self, key: K) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+    {
+        let prefix_key = self.prefixer.prefix(key.as_ref());
+        let enc_key = self.engine.encrypt(&prefix_key)?;
+
+        if let Some(entries) = &mut self.entries {
+            entries.push((enc_key.clone(), vec![]));
+        }
+        self.batch.delete(self.prefixer.index_key(&enc_key))?;
+        self.batch.delete(enc_key)?;
+
+        Ok(())
+    }
 }
 
 impl WriteBatch for EncryptedWriteBatch {
@@ -26,6 +51,11 @@ impl WriteBatch for EncryptedWriteBatch {
         let enc_key = self.engine.encrypt(&prefix_key)?;
         let enc_val = self.engine.encrypt(&value)?;
 
+        if let Some(entries) = &mut self.entries {
+            entries.push((enc_key.clone(), enc_val.clone()));
+        }
+        // The index is not replicated, it is rebuilt from the records of a recovered wallet
+        self.batch.put(self.prefixer.index_key(&enc_key), b"")?;
         self.batch.put(enc_key, enc_val)?;
 
         Ok(())
@@ -15,6 +15,16 @@ pub enum Error {
     Cipher(#[cause] witnet_crypto::cipher::Error),
     #[fail(display = "{}", _0)]
     Failure(#[cause] failure::Error),
+    #[fail(display = "remote storage request failed: {}", _0)]
+    Remote(#[cause] reqwest::Error),
+    #[fail(display = "remote storage responded with status {}", _0)]
+    RemoteStatus(u16),
+    #[fail(display = "remote storage upload queue is closed")]
+    RemoteQueueClosed,
+    #[fail(display = "batch {} is missing from the remote storage", _0)]
+    MissingBatch(u64),
+    #[fail(display = "record does not belong to the wallet database")]
+    ForeignRecord,
 }
 
 impl From<rocksdb::Error> for Error {
@@ -29,6 +39,12 @@ impl From<bincode::Error> for Error {
     }
 }
 
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Remote(err)
+    }
+}
+
 impl From<failure::Error> for Error {
     fn from(err: failure::Error) -> Self {
         Error::Failure(err)
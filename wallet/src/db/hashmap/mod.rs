@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::*;
+
+mod write_batch;
+
+pub use write_batch::*;
+
+/// In-memory database, used to test the wallet repository without RocksDB.
+///
+/// Clones share the same storage, so a wallet can be reloaded from the data persisted by
+/// another one.
+#[derive(Clone, Default)]
+pub struct HashMapDb {
+    rep: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>>,
+}
+
+impl Database for HashMapDb {
+    type WriteBatch = HashMapWriteBatch;
+
+    fn get_opt<K, V>(&self, key: &K) -> Result<Option<V>>
+    where
+        K: AsRef<[u8]> + ?Sized,
+        V: serde::de::DeserializeOwned,
+    {
+        match self.rep.read()?.get(key.as_ref()) {
+            Some(bytes) => {
+                let value = bincode::deserialize(bytes)?;
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn put<K, V>(&self, key: K, value: V) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+        V: serde::Serialize,
+    {
+        let bytes = bincode::serialize(&value)?;
+
+        self.rep.write()?.insert(key.as_ref().to_vec(), bytes);
+
+        Ok(())
+    }
+
+    fn delete<K>(&self, key: K) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.rep.write()?.remove(key.as_ref());
+
+        Ok(())
+    }
+
+    fn write(&self, batch: Self::WriteBatch) -> Result<()> {
+        let mut rep = self.rep.write()?;
+        for (key, value) in batch.into_iter() {
+            rep.insert(key, value);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn records(&self) -> Result<Vec<(Vec<u8>, usize)>> {
+        let records = self
+            .rep
+            .read()?
+            .iter()
+            .map(|(key, value)| (key.clone(), key.len() + value.len()))
+            .collect();
+
+        Ok(records)
+    }
+
+    fn compact(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn batch(&self) -> Self::WriteBatch {
+        HashMapWriteBatch::default()
+    }
+}
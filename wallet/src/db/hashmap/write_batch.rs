@@ -0,0 +1,29 @@
+use super::*;
+
+#[derive(Default)]
+pub struct HashMapWriteBatch {
+    batch: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl WriteBatch for HashMapWriteBatch {
+    fn put<K, V>(&mut self, key: K, value: V) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+        V: serde::Serialize,
+    {
+        let bytes = bincode::serialize(&value)?;
+
+        self.batch.push((key.as_ref().to_vec(), bytes));
+
+        Ok(())
+    }
+}
+
+impl IntoIterator for HashMapWriteBatch {
+    type Item = (Vec<u8>, Vec<u8>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.batch.into_iter()
+    }
+}
@@ -1,10 +1,16 @@
 mod encrypted;
 mod error;
+#[cfg(test)]
+mod hashmap;
 mod plain;
+mod remote;
 
 pub use encrypted::*;
 pub use error::Error;
+#[cfg(test)]
+pub use hashmap::*;
 pub use plain::*;
+pub use remote::*;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -41,10 +47,21 @@ pub trait Database {
         K: AsRef<[u8]>,
         V: serde::Serialize;
 
+    fn delete<K>(&self, key: K) -> Result<()>
+    where
+        K: AsRef<[u8]>;
+
     fn write(&self, batch: Self::WriteBatch) -> Result<()>;
 
     fn flush(&self) -> Result<()>;
 
+    /// Keys of the records of this database, along with the number of bytes each record takes in
+    /// storage.
+    fn records(&self) -> Result<Vec<(Vec<u8>, usize)>>;
+
+    /// Reclaim the space of deleted and overwritten records.
+    fn compact(&self) -> Result<()>;
+
     fn batch(&self) -> Self::WriteBatch;
 }
 
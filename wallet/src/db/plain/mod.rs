@@ -53,6 +53,15 @@ impl Database for PlainDb {
         Ok(())
     }
 
+    fn delete<K>(&self, key: K) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+    {
+        self.as_ref().delete(key)?;
+
+        Ok(())
+    }
+
     fn write(&self, batch: Self::WriteBatch) -> Result<()> {
         self.as_ref().write(batch.into())?;
 
@@ -65,6 +74,26 @@ impl Database for PlainDb {
         Ok(())
     }
 
+    fn records(&self) -> Result<Vec<(Vec<u8>, usize)>> {
+        let records = self
+            .as_ref()
+            .iterator(rocksdb::IteratorMode::Start)
+            .map(|(key, value)| {
+                let size = key.len() + value.len();
+
+                (key.to_vec(), size)
+            })
+            .collect();
+
+        Ok(records)
+    }
+
+    fn compact(&self) -> Result<()> {
+        self.as_ref().compact_range::<&[u8], &[u8]>(None, None);
+
+        Ok(())
+    }
+
     fn batch(&self) -> Self::WriteBatch {
         PlainWriteBatch::default()
     }
@@ -0,0 +1,499 @@
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::{cmp, thread, time::Duration};
+
+use reqwest::{header, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use witnet_protected::ProtectedString;
+
+use super::*;
+use crate::constants;
+
+/// Entries of a write batch as they are stored in the database, with encrypted keys and values. An
+/// entry with an empty value is the deletion of its key.
+pub type RemoteBatch = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// Object storage where the writes of the wallet databases are replicated, e.g. a WebDAV server
+/// or an S3 bucket.
+pub trait RemoteStorage: Send + Sync {
+    /// Upload an object, replacing it if it already exists.
+    fn put(&self, name: &str, body: Vec<u8>) -> Result<()>;
+
+    /// Download an object, `None` if it does not exist.
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>>;
+}
+
+/// Remote storage accessed with HTTP `PUT` and `GET` requests to the object names appended to a
+/// base URL.
+pub struct HttpRemoteStorage {
+    client: reqwest::Client,
+    url: String,
+    authorization: Option<ProtectedString>,
+}
+
+impl HttpRemoteStorage {
+    pub fn new(url: &str, authorization: Option<ProtectedString>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            authorization,
+        }
+    }
+
+    fn request(&self, method: Method, name: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/{}", self.url, name).as_str());
+
+        match &self.authorization {
+            Some(authorization) => {
+                let authorization: &str = authorization.as_ref();
+                request.header(header::AUTHORIZATION, authorization)
+            }
+            None => request,
+        }
+    }
+}
+
+impl RemoteStorage for HttpRemoteStorage {
+    fn put(&self, name: &str, body: Vec<u8>) -> Result<()> {
+        let response = self.request(Method::PUT, name).body(body).send()?;
+        let status = response.status();
+
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(Error::RemoteStatus(status.as_u16()))
+        }
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let mut response = self.request(Method::GET, name).send()?;
+        let status = response.status();
+
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(Error::RemoteStatus(status.as_u16()));
+        }
+
+        let mut body = Vec::new();
+        response.copy_to(&mut body)?;
+
+        Ok(Some(body))
+    }
+}
+
+/// Public parameters needed to derive the encryption key of a replicated wallet from its
+/// password.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplicaHeader {
+    pub salt: Vec<u8>,
+    pub iv: Vec<u8>,
+}
+
+/// Object waiting to be uploaded to the remote storage.
+struct Upload {
+    name: String,
+    body: Vec<u8>,
+    batch: Option<BatchUpload>,
+}
+
+/// Write batch of a wallet waiting to be uploaded, which is kept in the local database until it
+/// reaches the remote storage.
+struct BatchUpload {
+    db: Arc<rocksdb::DB>,
+    /// Key of the batch in the local database
+    pending_key: Vec<u8>,
+    /// Name of the object with the number of batches of the wallet uploaded so far
+    uploaded_name: String,
+    /// Number of batches of the wallet uploaded once this batch is
+    uploaded: u64,
+}
+
+/// Replication of the wallet databases to a remote storage.
+///
+/// The objects of a wallet are named after its id: `<wallet_id>/header` contains its
+/// `ReplicaHeader`, `<wallet_id>/<n>` the n-th write batch, starting from 0, and
+/// `<wallet_id>/uploaded` the number of batches uploaded, so a missing batch can be told apart from
+/// the end of the replica.
+///
+/// Objects are uploaded in the background by a single thread, in the same order they are queued.
+/// The batches are written to the local database along with the records, and deleted from it once
+/// uploaded, so the ones not uploaded before the wallet is closed are queued again the next time
+/// it is unlocked.
+#[derive(Clone)]
+pub struct RemoteReplication {
+    storage: Arc<dyn RemoteStorage>,
+    uploads: Arc<Mutex<mpsc::Sender<Upload>>>,
+    next_batches: Arc<Mutex<HashMap<String, Arc<Mutex<u64>>>>>,
+}
+
+impl RemoteReplication {
+    pub fn new(storage: Arc<dyn RemoteStorage>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let uploader = storage.clone();
+        thread::spawn(move || upload_queued(uploader.as_ref(), receiver));
+
+        Self {
+            storage,
+            uploads: Arc::new(Mutex::new(sender)),
+            next_batches: Default::default(),
+        }
+    }
+
+    /// Queue the upload of an object.
+    fn queue(&self, name: String, body: Vec<u8>, batch: Option<BatchUpload>) -> Result<()> {
+        self.uploads
+            .lock()?
+            .send(Upload { name, body, batch })
+            .map_err(|_| Error::RemoteQueueClosed)
+    }
+
+    /// Queue the upload of the header of a wallet. It must be done before any batch of the wallet
+    /// is pushed.
+    pub fn put_header(&self, wallet_id: &str, header: &ReplicaHeader) -> Result<()> {
+        let body = bincode::serialize(header)?;
+
+        self.queue(format!("{}/header", wallet_id), body, None)
+    }
+
+    /// Get the replica of a wallet whose next batch to push is `next_batch`. All the replicas of
+    /// the same wallet share their batch counter, so the wallet can be unlocked more than once.
+    ///
+    /// The first time a wallet is unlocked, the batches of the wallet in the local database `db`
+    /// which were not uploaded are queued again, before any new batch.
+    pub fn replica(
+        &self,
+        db: &Arc<rocksdb::DB>,
+        wallet_id: &str,
+        next_batch: u64,
+    ) -> Result<Replica> {
+        let mut next_batches = self.next_batches.lock()?;
+        let (next_batch, resumed) = match next_batches.get(wallet_id) {
+            Some(next_batch) => (next_batch.clone(), true),
+            None => (Arc::new(Mutex::new(next_batch)), false),
+        };
+        let replica = Replica {
+            replication: self.clone(),
+            db: db.clone(),
+            wallet_id: wallet_id.to_string(),
+            next_batch: next_batch.clone(),
+        };
+        if !resumed {
+            // Holding the lock of the counters, no batch of the wallet is pushed meanwhile
+            replica.queue_pending()?;
+            next_batches.insert(wallet_id.to_string(), next_batch);
+        }
+
+        Ok(replica)
+    }
+
+    /// Download the header and all the batches of a wallet, `None` if the wallet is not in the
+    /// remote storage.
+    pub fn download(&self, wallet_id: &str) -> Result<Option<(ReplicaHeader, Vec<RemoteBatch>)>> {
+        let header = match self.storage.get(&format!("{}/header", wallet_id))? {
+            Some(body) => bincode::deserialize(&body)?,
+            None => return Ok(None),
+        };
+
+        // The batches after the last one known to be uploaded are downloaded until the first
+        // missing one, as the number of uploaded batches is updated after uploading them
+        let uploaded: u64 = match self.storage.get(&format!("{}/uploaded", wallet_id))? {
+            Some(body) => bincode::deserialize(&body)?,
+            None => 0,
+        };
+        let mut batches = Vec::new();
+        loop {
+            let n = batches.len() as u64;
+            match self.storage.get(&format!("{}/{}", wallet_id, n))? {
+                Some(body) => batches.push(bincode::deserialize(&body)?),
+                None if n < uploaded => return Err(Error::MissingBatch(n)),
+                None => break,
+            }
+        }
+
+        Ok(Some((header, batches)))
+    }
+}
+
+/// Upload the queued objects until the queue is closed. Failed uploads are retried with an
+/// exponential backoff, without uploading the next objects in the meantime, so a batch never
+/// reaches the remote storage before the previous ones.
+fn upload_queued(storage: &dyn RemoteStorage, uploads: mpsc::Receiver<Upload>) {
+    for upload in uploads {
+        put_with_backoff(storage, &upload.name, upload.body);
+        if let Some(batch) = upload.batch {
+            let uploaded =
+                bincode::serialize(&batch.uploaded).expect("serializing a number never fails");
+            put_with_backoff(storage, &batch.uploaded_name, uploaded);
+            if let Err(err) = batch.db.delete(&batch.pending_key) {
+                log::warn!(
+                    "Failed to delete the uploaded batch {} from the local database: {}",
+                    upload.name,
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Upload an object, retrying with an exponential backoff until it succeeds.
+fn put_with_backoff(storage: &dyn RemoteStorage, name: &str, body: Vec<u8>) {
+    let mut backoff = Duration::from_secs(constants::REMOTE_UPLOAD_BACKOFF_BASE_SECS);
+    while let Err(err) = storage.put(name, body.clone()) {
+        log::warn!(
+            "Failed to upload {} to the remote storage, retrying in {:?}: {}",
+            name,
+            backoff,
+            err
+        );
+        thread::sleep(backoff);
+        backoff = cmp::min(
+            backoff * 2,
+            Duration::from_secs(constants::REMOTE_UPLOAD_BACKOFF_MAX_SECS),
+        );
+    }
+}
+
+/// Prefix of the keys of the batches of a wallet waiting to be uploaded in the local database.
+fn pending_prefix(wallet_id: &str) -> Vec<u8> {
+    format!("{}{}/", constants::REPLICA_PENDING_BATCH_PREFIX, wallet_id).into_bytes()
+}
+
+/// Key of the n-th batch of a wallet waiting to be uploaded in the local database. The number is
+/// big-endian so the batches are sorted by it.
+fn pending_key(wallet_id: &str, n: u64) -> Vec<u8> {
+    [pending_prefix(wallet_id), n.to_be_bytes().to_vec()].concat()
+}
+
+/// Keys and contents of the batches of a wallet waiting to be uploaded, in order.
+fn pending_batches(db: &rocksdb::DB, wallet_id: &str) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let prefix = pending_prefix(wallet_id);
+
+    db.iterator(rocksdb::IteratorMode::From(
+        &prefix,
+        rocksdb::Direction::Forward,
+    ))
+    .take_while(|(key, _)| key.starts_with(&prefix))
+    .filter(|(key, _)| key.len() == prefix.len() + 8)
+    .map(|(key, body)| (key.to_vec(), body.to_vec()))
+    .collect()
+}
+
+/// Delete the batches of a wallet waiting to be uploaded, once its replication has stopped.
+pub fn discard_pending_batches(db: &rocksdb::DB, wallet_id: &str) -> Result<()> {
+    let mut write_batch = rocksdb::WriteBatch::default();
+    for (key, _) in pending_batches(db, wallet_id) {
+        write_batch.delete(key)?;
+    }
+    db.write(write_batch)?;
+
+    Ok(())
+}
+
+/// Replication of the writes of a wallet database.
+#[derive(Clone)]
+pub struct Replica {
+    replication: RemoteReplication,
+    db: Arc<rocksdb::DB>,
+    wallet_id: String,
+    next_batch: Arc<Mutex<u64>>,
+}
+
+impl Replica {
+    /// Lock the number of the next batch to push, so batches are pushed and written in order.
+    pub fn lock(&self) -> Result<MutexGuard<'_, u64>> {
+        let next_batch = self.next_batch.lock()?;
+
+        Ok(next_batch)
+    }
+
+    /// Add the n-th batch of the wallet to a local write batch, so it is kept in the local
+    /// database until it is uploaded, returning its body to be pushed once written.
+    pub fn keep(
+        &self,
+        write_batch: &mut rocksdb::WriteBatch,
+        n: u64,
+        entries: &[(Vec<u8>, Vec<u8>)],
+    ) -> Result<Vec<u8>> {
+        let body = bincode::serialize(entries)?;
+        write_batch.put(pending_key(&self.wallet_id, n), &body)?;
+
+        Ok(body)
+    }
+
+    /// Queue the upload of the n-th batch of the wallet, once kept in the local database.
+    pub fn push(&self, n: u64, body: Vec<u8>) -> Result<()> {
+        self.queue_batch(pending_key(&self.wallet_id, n), n, body)
+    }
+
+    fn queue_batch(&self, pending_key: Vec<u8>, n: u64, body: Vec<u8>) -> Result<()> {
+        let batch = BatchUpload {
+            db: self.db.clone(),
+            pending_key,
+            uploaded_name: format!("{}/uploaded", self.wallet_id),
+            uploaded: n + 1,
+        };
+
+        self.replication
+            .queue(format!("{}/{}", self.wallet_id, n), body, Some(batch))
+    }
+
+    /// Queue the upload of the batches of the wallet kept in the local database.
+    fn queue_pending(&self) -> Result<()> {
+        let prefix_len = pending_prefix(&self.wallet_id).len();
+        for (key, body) in pending_batches(&self.db, &self.wallet_id) {
+            let mut n = [0; 8];
+            n.copy_from_slice(&key[prefix_len..]);
+            self.queue_batch(key, u64::from_be_bytes(n), body)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write the entries of a replica, once checked with `validate_replica`, into the local database
+/// in a single write batch. The index of the records with the given prefix is rebuilt the next
+/// time they are listed.
+pub fn restore(db: &rocksdb::DB, prefix: &[u8], entries: RemoteBatch) -> Result<()> {
+    let mut write_batch = rocksdb::WriteBatch::default();
+    for (key, value) in entries {
+        if value.is_empty() {
+            write_batch.delete(key)?;
+        } else {
+            write_batch.put(key, value)?;
+        }
+    }
+    write_batch.delete(encrypted::prefix::Prefixer::new(prefix.to_vec()).index_marker())?;
+    db.write(write_batch)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Remote storage keeping the objects in memory, which fails every upload if unavailable
+    #[derive(Default)]
+    struct MemoryStorage {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+        unavailable: bool,
+    }
+
+    impl RemoteStorage for MemoryStorage {
+        fn put(&self, name: &str, body: Vec<u8>) -> Result<()> {
+            if self.unavailable {
+                return Err(Error::RemoteStatus(503));
+            }
+            self.objects.lock()?.insert(name.to_string(), body);
+
+            Ok(())
+        }
+
+        fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.objects.lock()?.get(name).cloned())
+        }
+    }
+
+    /// Empty rocksdb database for a test
+    fn test_db(name: &str) -> Arc<rocksdb::DB> {
+        let path = std::env::temp_dir().join(format!(
+            "witnet-wallet-remote-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+
+        Arc::new(rocksdb::DB::open_default(path).unwrap())
+    }
+
+    /// Wait for the background uploads to meet a condition
+    fn wait_for<F: Fn() -> bool>(condition: F) {
+        for _ in 0..100 {
+            if condition() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("the uploads did not finish in time");
+    }
+
+    fn header() -> ReplicaHeader {
+        ReplicaHeader {
+            salt: vec![1],
+            iv: vec![2],
+        }
+    }
+
+    #[test]
+    fn batches_are_kept_until_uploaded() {
+        let db = test_db("pending");
+        let entries = vec![(b"key".to_vec(), b"value".to_vec())];
+
+        // The batches are kept while the remote storage is unavailable
+        let unavailable = RemoteReplication::new(Arc::new(MemoryStorage {
+            unavailable: true,
+            ..MemoryStorage::default()
+        }));
+        let replica = unavailable.replica(&db, "wallet", 0).unwrap();
+        for n in 0..2 {
+            let mut write_batch = rocksdb::WriteBatch::default();
+            let body = replica.keep(&mut write_batch, n, &entries).unwrap();
+            db.write(write_batch).unwrap();
+            replica.push(n, body).unwrap();
+        }
+        assert_eq!(pending_batches(&db, "wallet").len(), 2);
+        assert!(pending_batches(&db, "other").is_empty());
+
+        // And uploaded in order the next time the wallet is unlocked
+        let storage = Arc::new(MemoryStorage::default());
+        let replication = RemoteReplication::new(storage.clone());
+        replication.put_header("wallet", &header()).unwrap();
+        replication.replica(&db, "wallet", 2).unwrap();
+        wait_for(|| pending_batches(&db, "wallet").is_empty());
+
+        let (_, batches) = replication.download("wallet").unwrap().unwrap();
+        assert_eq!(batches, vec![entries.clone(), entries]);
+        let uploaded: u64 =
+            bincode::deserialize(&storage.get("wallet/uploaded").unwrap().unwrap()).unwrap();
+        assert_eq!(uploaded, 2);
+    }
+
+    #[test]
+    fn download_detects_missing_batches() {
+        let storage = Arc::new(MemoryStorage::default());
+        let replication = RemoteReplication::new(storage.clone());
+        let batch: RemoteBatch = vec![(b"key".to_vec(), b"value".to_vec())];
+        let body = bincode::serialize(&batch).unwrap();
+        storage
+            .put("wallet/header", bincode::serialize(&header()).unwrap())
+            .unwrap();
+        storage.put("wallet/0", body.clone()).unwrap();
+        storage.put("wallet/2", body.clone()).unwrap();
+
+        // Without knowing how many batches were uploaded, the replica ends at the first missing
+        let (_, batches) = replication.download("wallet").unwrap().unwrap();
+        assert_eq!(batches.len(), 1);
+
+        storage
+            .put("wallet/uploaded", bincode::serialize(&3u64).unwrap())
+            .unwrap();
+        match replication.download("wallet") {
+            Err(Error::MissingBatch(1)) => {}
+            result => panic!(
+                "downloaded a replica with a missing batch: {:?}",
+                result.map(|_| ())
+            ),
+        }
+
+        // The batches uploaded after updating the count are downloaded too
+        storage.put("wallet/1", body.clone()).unwrap();
+        storage.put("wallet/3", body).unwrap();
+        let (_, batches) = replication.download("wallet").unwrap().unwrap();
+        assert_eq!(batches.len(), 4);
+    }
+}
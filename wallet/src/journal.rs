@@ -0,0 +1,194 @@
+//! # Notification journal
+//!
+//! Bounded log of the notifications of a session, so a client that reconnects can replay the ones
+//! it missed with `getNotificationsSince` instead of refreshing everything.
+//!
+//! Every notification gets a sequence number, increasing by one and starting at `1`, which is also
+//! added to the notification sent through the subscription under the `seq` field. Once the
+//! journal is full the oldest notifications are dropped, and a replay that would need them is
+//! reported as incomplete.
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use futures::{executor, Async};
+use jsonrpc_core as rpc;
+
+use crate::{constants, model, types};
+
+/// Notifications of a session, by sequence number.
+pub struct Journal {
+    entries: VecDeque<model::Notification>,
+    last_seq: u64,
+    capacity: usize,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Self::with_capacity(constants::NOTIFICATION_JOURNAL_CAPACITY)
+    }
+}
+
+impl Journal {
+    /// Create a journal keeping at most `capacity` notifications.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            last_seq: 0,
+            capacity,
+        }
+    }
+
+    /// Record a notification, returning its sequence number.
+    pub fn record(&mut self, payload: types::Json) -> u64 {
+        self.last_seq += 1;
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(model::Notification {
+            seq: self.last_seq,
+            payload,
+        });
+
+        self.last_seq
+    }
+
+    /// Get the notifications recorded after the one with sequence number `seq`.
+    pub fn since(&self, seq: u64) -> model::NotificationReplay {
+        let oldest_seq = self
+            .entries
+            .front()
+            .map_or(self.last_seq + 1, |entry| entry.seq);
+
+        model::NotificationReplay {
+            notifications: self
+                .entries
+                .iter()
+                .filter(|entry| entry.seq > seq)
+                .cloned()
+                .collect(),
+            last_seq: self.last_seq,
+            complete: seq.saturating_add(1) >= oldest_seq,
+        }
+    }
+}
+
+/// Destination of the notifications of a session: they are always recorded in its journal, and
+/// also sent to the client if it is subscribed.
+#[derive(Clone)]
+pub struct Notifier {
+    pub journal: Arc<Mutex<Journal>>,
+    pub sink: Option<types::Sink>,
+}
+
+impl Notifier {
+    /// Record a notification and send it to the subscribed client, if any.
+    ///
+    /// The notification is not waited to be sent: if the client is not keeping up with its
+    /// notifications it is dropped, and the client can replay it from the journal.
+    pub fn notify(
+        &self,
+        mut payload: types::Json,
+    ) -> Result<(), futures::sync::mpsc::SendError<String>> {
+        let seq = self
+            .journal
+            .lock()
+            .expect("notification journal lock poisoned")
+            .record(payload.clone());
+
+        match &self.sink {
+            Some(sink) => {
+                if let Some(object) = payload.as_object_mut() {
+                    object.insert("seq".to_string(), seq.into());
+                }
+                let mut send = executor::spawn(sink.notify(rpc::Params::Array(vec![payload])));
+                match send.poll_future_notify(&Arc::new(NoopNotify), 0)? {
+                    Async::Ready(_) => {}
+                    Async::NotReady => log::debug!(
+                        "The client is not keeping up with its notifications, dropped {}",
+                        seq
+                    ),
+                }
+
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Wakes no task, as the notifications are sent without waiting for the client.
+struct NoopNotify;
+
+impl executor::Notify for NoopNotify {
+    fn notify(&self, _id: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seqs(replay: &model::NotificationReplay) -> Vec<u64> {
+        replay.notifications.iter().map(|entry| entry.seq).collect()
+    }
+
+    #[test]
+    fn empty_journal_replays_nothing() {
+        let journal = Journal::with_capacity(3);
+        let replay = journal.since(0);
+
+        assert!(replay.notifications.is_empty());
+        assert_eq!(replay.last_seq, 0);
+        assert!(replay.complete);
+    }
+
+    #[test]
+    fn notifications_are_replayed_after_a_sequence_number() {
+        let mut journal = Journal::with_capacity(3);
+        assert_eq!(journal.record(types::Json::from("a")), 1);
+        assert_eq!(journal.record(types::Json::from("b")), 2);
+        assert_eq!(journal.record(types::Json::from("c")), 3);
+
+        let replay = journal.since(1);
+        assert_eq!(seqs(&replay), vec![2, 3]);
+        assert_eq!(replay.notifications[0].payload, types::Json::from("b"));
+        assert_eq!(replay.last_seq, 3);
+        assert!(replay.complete);
+
+        // A client that has seen every notification gets none
+        let replay = journal.since(3);
+        assert!(replay.notifications.is_empty());
+        assert!(replay.complete);
+    }
+
+    #[test]
+    fn replay_needing_dropped_notifications_is_incomplete() {
+        let mut journal = Journal::with_capacity(3);
+        for n in 0..5 {
+            journal.record(types::Json::from(n));
+        }
+
+        // The notifications 1 and 2 were dropped to make room for the newer ones
+        let replay = journal.since(0);
+        assert_eq!(seqs(&replay), vec![3, 4, 5]);
+        assert!(!replay.complete);
+        assert!(!journal.since(1).complete);
+
+        let replay = journal.since(2);
+        assert_eq!(seqs(&replay), vec![3, 4, 5]);
+        assert!(replay.complete);
+    }
+
+    #[test]
+    fn notifications_are_recorded_without_a_subscription() {
+        let notifier = Notifier {
+            journal: Arc::new(Mutex::new(Journal::with_capacity(3))),
+            sink: None,
+        };
+        notifier.notify(types::Json::from("a")).unwrap();
+        notifier.notify(types::Json::from("b")).unwrap();
+
+        let replay = notifier.journal.lock().unwrap().since(0);
+        assert_eq!(seqs(&replay), vec![1, 2]);
+        assert_eq!(replay.notifications[1].payload, types::Json::from("b"));
+    }
+}
@@ -23,17 +23,22 @@ use jsonrpc_pubsub as pubsub;
 
 use witnet_config::config::Config;
 use witnet_net::{client::tcp::JsonRpcClient, server::ws::Server};
+use witnet_util::crash;
 
 mod account;
 mod actors;
+mod audit;
 mod constants;
 mod crypto;
 mod db;
+mod journal;
+mod metrics;
 mod model;
 mod params;
 mod repository;
 mod signal;
 mod types;
+mod uri;
 
 /// Run the Witnet wallet application.
 pub fn run(conf: Config) -> Result<(), Error> {
@@ -53,12 +58,73 @@ pub fn run(conf: Config) -> Result<(), Error> {
     // Whether wallet is in testnet mode or not
     let testnet = conf.wallet.testnet;
 
+    // Used to convert between epochs and timestamps, e.g. for recurring payments
+    let epoch_constants = types::EpochConstants {
+        checkpoint_zero_timestamp: conf.consensus_constants.checkpoint_zero_timestamp,
+        checkpoints_period: conf.consensus_constants.checkpoints_period,
+    };
+
     // Master-key generation params
     let seed_password = conf.wallet.seed_password;
     let master_key_salt = conf.wallet.master_key_salt;
     let id_hash_iterations = conf.wallet.id_hash_iterations;
     let id_hash_function = conf.wallet.id_hash_function;
 
+    // Audit log params
+    let audit_log_path = conf.wallet.audit_log_path;
+    let audit_log_password = conf.wallet.audit_log_password;
+    let audit_log_max_size = conf.wallet.audit_log_max_size;
+
+    // Remote storage where the wallet databases are replicated
+    let remote_storage_authorization = conf.wallet.remote_storage_authorization;
+    let remote_replication = conf.wallet.remote_storage_url.map(|url| {
+        let storage = db::HttpRemoteStorage::new(&url, remote_storage_authorization);
+
+        db::RemoteReplication::new(Arc::new(storage))
+    });
+
+    // Address reuse policy
+    let address_reuse = conf.wallet.address_reuse;
+
+    // Address paid the change of the transactions
+    let change_address = conf.wallet.change_address;
+
+    // Responses of the requests sent with an idempotency key
+    let idempotency_window = Duration::from_secs(conf.wallet.idempotency_window_seconds);
+
+    // Developer tool to export the databases of the wallets in plaintext
+    let plaintext_export = conf.wallet.dev_plaintext_export;
+
+    // Detection of a stale subscription to the blocks of the node
+    let node_heartbeat = Duration::from_secs(conf.wallet.node_heartbeat_seconds);
+    let node_stall_epochs = conf.wallet.node_stall_epochs;
+
+    // Rate limits
+    let rate_limiter = Arc::new(actors::app::RateLimiter::new(
+        conf.wallet.session_requests_per_second,
+        conf.wallet.tenant_requests_per_second,
+        conf.wallet.method_concurrency,
+    ));
+
+    // Tenants served by the wallet, whose names are part of the keys of their wallets
+    if let Some(tenant) = conf.wallet.tenants.values().find(|tenant| {
+        tenant.is_empty()
+            || !tenant
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }) {
+        return Err(failure::format_err!(
+            "Invalid tenant name {:?}: only letters, digits and underscores are allowed",
+            tenant
+        ));
+    }
+    let tenant_max_sessions = conf.wallet.tenant_max_sessions;
+    let tenants = Arc::new(actors::app::Tenants::new(conf.wallet.tenants));
+
+    // Timing metrics of the wallet repository, optionally served to Prometheus
+    let metrics = Arc::new(metrics::Metrics::default());
+    let metrics_addr = conf.wallet.metrics_addr;
+
     // Wallet concurrency
     let concurrency = conf.wallet.concurrency.unwrap_or_else(num_cpus::get);
 
@@ -73,6 +139,13 @@ pub fn run(conf: Config) -> Result<(), Error> {
         ::rocksdb::DB::open(&rocksdb_opts, db_path.join(db_file_name))
             .map_err(|e| failure::format_err!("{}", e))?,
     );
+    let flush_db = db.clone();
+    crash::set_flush_handler(Box::new(move || {
+        if let Err(e) = flush_db.flush() {
+            log::error!("Failed to flush database: {}", e);
+        }
+    }));
+
     let params = params::Params {
         testnet,
         seed_password,
@@ -82,6 +155,24 @@ pub fn run(conf: Config) -> Result<(), Error> {
         db_hash_iterations,
         db_iv_length,
         db_salt_length,
+        change_address,
+        remote_replication,
+        metrics: metrics.clone(),
+    };
+
+    let audit_log = match (audit_log_path, audit_log_password) {
+        (Some(path), Some(password)) => Some(Arc::new(audit::AuditLog::open(
+            path,
+            password,
+            db_hash_iterations,
+            audit_log_max_size,
+        )?)),
+        (Some(_), None) => {
+            return Err(failure::format_err!(
+                "The audit log is enabled but no audit log password was configured"
+            ));
+        }
+        (None, _) => None,
     };
 
     let worker = actors::Worker::start(concurrency, db.clone(), params);
@@ -91,10 +182,30 @@ pub fn run(conf: Config) -> Result<(), Error> {
         client,
         session_expires_in,
         requests_timeout,
+        testnet,
+        epoch_constants,
+        address_reuse,
+        idempotency_window,
+        plaintext_export,
+        metrics: metrics.clone(),
+        node_heartbeat,
+        node_stall_epochs,
+        tenant_max_sessions,
     });
     let mut handler = pubsub::PubSubHandler::new(rpc::MetaIoHandler::default());
 
-    actors::app::connect_routes(&mut handler, app.clone(), Arbiter::current());
+    actors::app::connect_routes(
+        &mut handler,
+        app.clone(),
+        Arbiter::current(),
+        rate_limiter,
+        tenants,
+        audit_log,
+    );
+
+    if let Some(addr) = metrics_addr {
+        metrics::serve(addr, metrics)?;
+    }
 
     let server = Server::build().handler(handler).addr(server_addr).start()?;
     let controller = actors::Controller::start(server, app);
@@ -113,3 +224,18 @@ pub fn run(conf: Config) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Decrypt the entries of a wallet audit log file, returning them as JSON strings.
+pub fn read_audit_log(conf: &Config, path: &std::path::Path) -> Result<Vec<String>, Error> {
+    let password = conf
+        .wallet
+        .audit_log_password
+        .as_ref()
+        .ok_or_else(|| failure::format_err!("No audit log password was configured"))?;
+    let entries = audit::read(path, password, conf.wallet.db_encrypt_hash_iterations)?;
+
+    entries
+        .iter()
+        .map(|entry| serde_json::to_string(entry).map_err(From::from))
+        .collect()
+}
@@ -0,0 +1,239 @@
+//! Timing metrics of the wallet repository, used to diagnose slow synchronizations.
+//!
+//! The metrics are shared by all the wallets and can be queried with the `getWalletMetrics`
+//! method or scraped by Prometheus if `metrics_addr` is configured.
+use std::fmt::Write as _;
+use std::io::{Read as _, Write as _};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::model;
+
+/// Aggregated durations of an operation.
+#[derive(Default)]
+pub struct Timer {
+    count: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+}
+
+impl Timer {
+    /// Run `f`, recording how long it took.
+    pub fn time<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(start);
+
+        result
+    }
+
+    fn record(&self, start: Instant) {
+        let elapsed = start.elapsed();
+        let micros = elapsed
+            .as_secs()
+            .saturating_mul(1_000_000)
+            .saturating_add(u64::from(elapsed.subsec_micros()));
+
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        let mut max = self.max_micros.load(Ordering::Relaxed);
+        while micros > max {
+            match self.max_micros.compare_exchange_weak(
+                max,
+                micros,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => max = current,
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> model::TimerMetrics {
+        model::TimerMetrics {
+            count: self.count.load(Ordering::Relaxed),
+            total_micros: self.total_micros.load(Ordering::Relaxed),
+            max_micros: self.max_micros.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Timing metrics of the wallet repository.
+#[derive(Default)]
+pub struct Metrics {
+    /// Indexing the transactions of a block.
+    pub block_index: Timer,
+    /// Writing a batch to the database of a wallet, including its remote replication.
+    pub db_write: Timer,
+    /// Waiting to acquire the locks of the in-memory state of a wallet while indexing.
+    pub lock_wait: Timer,
+}
+
+impl Metrics {
+    pub fn snapshot(&self) -> model::WalletMetrics {
+        model::WalletMetrics {
+            block_index: self.block_index.snapshot(),
+            db_write: self.db_write.snapshot(),
+            lock_wait: self.lock_wait.snapshot(),
+        }
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let timers = [
+            (
+                "block_index",
+                "Time spent indexing the transactions of a block",
+                &self.block_index,
+            ),
+            (
+                "db_write",
+                "Time spent writing batches to the wallet databases",
+                &self.db_write,
+            ),
+            (
+                "lock_wait",
+                "Time spent waiting for the wallet locks while indexing",
+                &self.lock_wait,
+            ),
+        ];
+
+        let mut text = String::new();
+        for (name, help, timer) in timers.iter() {
+            let metrics = timer.snapshot();
+            let _ = writeln!(text, "# HELP witnet_wallet_{}_seconds {}", name, help);
+            let _ = writeln!(text, "# TYPE witnet_wallet_{}_seconds summary", name);
+            let _ = writeln!(
+                text,
+                "witnet_wallet_{}_seconds_sum {}",
+                name,
+                seconds(metrics.total_micros)
+            );
+            let _ = writeln!(
+                text,
+                "witnet_wallet_{}_seconds_count {}",
+                name, metrics.count
+            );
+            let _ = writeln!(
+                text,
+                "# HELP witnet_wallet_{}_seconds_max Longest {}",
+                name,
+                help.to_lowercase()
+            );
+            let _ = writeln!(text, "# TYPE witnet_wallet_{}_seconds_max gauge", name);
+            let _ = writeln!(
+                text,
+                "witnet_wallet_{}_seconds_max {}",
+                name,
+                seconds(metrics.max_micros)
+            );
+        }
+
+        text
+    }
+}
+
+fn seconds(micros: u64) -> String {
+    format!("{}.{:06}", micros / 1_000_000, micros % 1_000_000)
+}
+
+/// Time a Prometheus scraper has to send its request and read the response, so an idle
+/// connection does not block the other scrapers.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serve the metrics to Prometheus at `addr` from a dedicated thread. Every request gets the
+/// metrics as response, regardless of its path.
+pub fn serve(addr: SocketAddr, metrics: Arc<Metrics>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    log::info!("Serving wallet metrics at http://{}/metrics", addr);
+
+    spawn_server(listener, metrics, CONNECTION_TIMEOUT)
+}
+
+/// Answer the connections of `listener` with the metrics from a dedicated thread, one at a time,
+/// dropping the connections that take longer than `timeout` to send a request or read the
+/// response.
+fn spawn_server(
+    listener: TcpListener,
+    metrics: Arc<Metrics>,
+    timeout: Duration,
+) -> std::io::Result<()> {
+    thread::Builder::new()
+        .name("wallet-metrics".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|mut stream| respond(&mut stream, &metrics, timeout));
+                if let Err(e) = result {
+                    log::warn!("Failed to send metrics: {}", e);
+                }
+            }
+        })?;
+
+    Ok(())
+}
+
+fn respond(stream: &mut TcpStream, metrics: &Metrics, timeout: Duration) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    // The request is not needed, but it has to be read before answering
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request)?;
+
+    let body = metrics.to_prometheus();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prometheus_text_has_every_timer() {
+        let metrics = Metrics::default();
+        metrics.block_index.time(|| ());
+        metrics.block_index.time(|| ());
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains("witnet_wallet_block_index_seconds_count 2\n"));
+        assert!(text.contains("witnet_wallet_db_write_seconds_count 0\n"));
+        assert!(text.contains("witnet_wallet_lock_wait_seconds_max 0.000000\n"));
+        assert_eq!(seconds(1_500_000), "1.500000");
+    }
+
+    #[test]
+    fn idle_connection_does_not_block_the_metrics() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.db_write.time(|| ());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        spawn_server(listener, metrics, Duration::from_millis(100)).unwrap();
+
+        // A client that connects without sending its request
+        let _idle = TcpStream::connect(addr).unwrap();
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("witnet_wallet_db_write_seconds_count 1\n"));
+    }
+}
@@ -1,6 +1,10 @@
 //! Types that are serializable and can be returned as a response.
 
-use serde::Serialize;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Wallet {
@@ -23,6 +27,51 @@ pub struct Address {
     pub address: String,
     pub path: String,
     pub label: Option<String>,
+    /// Number of times the address has been handed out to a client
+    pub times_requested: u32,
+    /// Number of payments received by the address
+    pub times_received: u32,
+}
+
+/// Proof that a wallet controls one of its addresses, which can be verified without access to the
+/// wallet.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressProof {
+    pub address: String,
+    /// Derivation path of the key of the address
+    pub path: String,
+    /// Hex-encoded compressed public key of the address
+    pub public_key: String,
+    pub challenge: String,
+    /// Hex-encoded DER signature of the SHA-256 hash of the address proof prefix followed by the
+    /// challenge
+    pub signature: String,
+}
+
+/// Provenance of an address of the wallet, as exported for accounting and audit tools.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressReport {
+    pub address: String,
+    pub path: String,
+    pub label: Option<String>,
+    /// Timestamp of the generation of the address, unknown for the addresses generated before it
+    /// was recorded
+    pub created_at: Option<i64>,
+    /// Epoch of the block including the first payment received by the address
+    pub first_payment_epoch: Option<u32>,
+    /// Timestamp of the start of the epoch of the first payment
+    pub first_payment_at: Option<i64>,
+    /// Epoch of the block including the last payment received by the address
+    pub last_payment_epoch: Option<u32>,
+    /// Timestamp of the start of the epoch of the last payment
+    pub last_payment_at: Option<i64>,
+    /// Number of payments received by the address
+    pub times_received: u32,
+    /// Total value of the payments received by the address, unknown if they were indexed before
+    /// it was recorded
+    pub total_received: Option<types::Nanowits>,
 }
 
 #[derive(Debug, Serialize)]
@@ -31,17 +80,34 @@ pub struct Addresses {
     pub total: u32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub hash: String,
-    pub value: u64,
+    pub value: types::Nanowits,
     pub kind: TransactionKind,
+    /// Address of the other party of the transaction, if known
+    pub counterparty: Option<String>,
+    /// Name of the counterparty, if it is saved in the wallet contacts
+    pub contact: Option<String>,
+    /// Hash of the data request a witness reward or a refund comes from
+    pub dr_hash: Option<String>,
+    /// Whether the transaction moves funds of a watched address, which can't be spent by the
+    /// wallet
+    #[serde(default)]
+    pub watch_only: bool,
+    /// Watched address whose funds are moved by the transaction
+    #[serde(default)]
+    pub watched_address: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TransactionKind {
     Debit,
     Credit,
+    /// Reward for witnessing a data request
+    WitnessReward,
+    /// Tally change of a data request created by the wallet
+    Refund,
 }
 
 #[derive(Debug, Serialize)]
@@ -49,3 +115,417 @@ pub struct Transactions {
     pub transactions: Vec<Transaction>,
     pub total: u32,
 }
+
+/// A movement of funds of a wallet account.
+#[derive(Debug, Clone, Serialize)]
+pub struct Movement {
+    pub account: u32,
+    pub id: u32,
+    /// Hash of the transaction, unknown for the movements indexed before it was recorded
+    pub hash: Option<String>,
+    pub kind: TransactionKind,
+    pub value: types::Nanowits,
+    /// Epoch of the block that included the transaction
+    pub epoch: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Movements {
+    pub movements: Vec<Movement>,
+    pub total: u32,
+}
+
+/// Criteria the movements returned by `searchMovements` have to match, all of them optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovementFilter {
+    /// Address of the wallet the movements paid to or spent from
+    pub address: Option<String>,
+    pub kind: Option<TransactionKind>,
+    /// First epoch of the period
+    pub from_epoch: Option<u32>,
+    /// First epoch after the period
+    pub to_epoch: Option<u32>,
+}
+
+/// Aggregate of the movements of a wallet account during a period of epochs.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    pub account: u32,
+    /// First epoch of the period
+    pub from_epoch: u32,
+    /// First epoch after the period
+    pub to_epoch: u32,
+    pub opening_balance: types::Nanowits,
+    pub closing_balance: types::Nanowits,
+    /// Value received from others, witness rewards and refunds included
+    pub total_in: types::Nanowits,
+    /// Value sent to others, fees and data requests included. The change paid back to the wallet
+    /// is not counted
+    pub total_out: types::Nanowits,
+    /// Fees paid by the transactions created by the wallet
+    pub fees_paid: types::Nanowits,
+    /// Value of the data requests created by the wallet
+    pub data_request_spend: types::Nanowits,
+    pub witness_rewards: types::Nanowits,
+    /// Number of movements during the period
+    pub movements: u32,
+}
+
+/// A request for a payment to an address, which can be shared as a URI.
+#[derive(Debug, Serialize)]
+pub struct PaymentRequest {
+    pub address: String,
+    /// Amount in nanowits
+    pub amount: Option<types::Nanowits>,
+    pub message: Option<String>,
+}
+
+/// A value transfer that the wallet repeats every `interval`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringPayment {
+    pub id: u32,
+    pub address: String,
+    pub amount: types::Nanowits,
+    pub fee: types::Nanowits,
+    pub label: Option<String>,
+    /// Interval between payments, in epochs or in days as given when it was created
+    pub interval: types::ScheduleInterval,
+    /// Number of epochs between payments
+    pub interval_epochs: u32,
+    /// Epoch of the next payment
+    pub next_epoch: u32,
+    /// Last epoch in which a payment can be made, if any
+    pub end_epoch: Option<u32>,
+}
+
+/// A recurring payment as stored by older versions, whose interval was always given in epochs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacyRecurringPayment {
+    pub id: u32,
+    pub address: String,
+    pub amount: types::Nanowits,
+    pub fee: types::Nanowits,
+    pub label: Option<String>,
+    /// Number of epochs between payments
+    pub interval: u32,
+    pub next_epoch: u32,
+    pub end_epoch: Option<u32>,
+}
+
+impl From<LegacyRecurringPayment> for RecurringPayment {
+    fn from(payment: LegacyRecurringPayment) -> Self {
+        Self {
+            id: payment.id,
+            address: payment.address,
+            amount: payment.amount,
+            fee: payment.fee,
+            label: payment.label,
+            interval: types::ScheduleInterval::Epochs(payment.interval),
+            interval_epochs: payment.interval,
+            next_epoch: payment.next_epoch,
+            end_epoch: payment.end_epoch,
+        }
+    }
+}
+
+impl RecurringPayment {
+    /// Whether the next payment is due at `epoch`.
+    pub fn is_due(&self, epoch: u32) -> bool {
+        self.next_epoch <= epoch && self.end_epoch.map_or(true, |end| self.next_epoch <= end)
+    }
+}
+
+/// A payment generated by a recurring payment when it was due.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledPayment {
+    /// Id of the recurring payment that generated this payment
+    pub recurring_payment_id: u32,
+    /// Epoch in which the payment was due
+    pub epoch: u32,
+    pub address: String,
+    pub amount: types::Nanowits,
+    pub fee: types::Nanowits,
+    /// Hash of the transaction that made the payment, `None` while the payment is pending
+    pub transaction_hash: Option<String>,
+}
+
+/// A named recipient address saved in the wallet address book.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    pub address: String,
+    pub name: String,
+    pub notes: Option<String>,
+}
+
+/// An address outside of the wallet keychains whose movements are followed by the wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedAddress {
+    pub address: String,
+    pub label: Option<String>,
+    /// Funds received by the address since it's being watched, which are not part of the wallet
+    /// balance
+    pub balance: types::Nanowits,
+}
+
+/// Payment to a deposit address of the wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deposit {
+    /// Position of the payment in the stream of deposits of the wallet, starting at `1`
+    pub seq: u64,
+    pub address: String,
+    pub transaction_hash: String,
+    pub output_index: u32,
+    pub value: types::Nanowits,
+    /// Epoch of the block including the payment
+    pub epoch: u32,
+    /// Number of blocks indexed since the one including the payment, counting it
+    pub confirmations: u32,
+}
+
+/// Deposits of the wallet not acknowledged by the client yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deposits {
+    pub deposits: Vec<Deposit>,
+    /// Sequence number of the last acknowledged deposit, `0` if none
+    pub cursor: u64,
+    /// Sequence number of the last detected deposit, `0` if none
+    pub last_seq: u64,
+}
+
+/// Number of records of the same kind stored by a wallet, and the bytes they take.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RecordStats {
+    pub records: u64,
+    pub bytes: u64,
+}
+
+/// Storage used by a wallet in the database.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StorageStats {
+    pub records: u64,
+    pub bytes: u64,
+    /// Records by key prefix, with the indexes and hashes of the keys replaced by `*`
+    pub prefixes: BTreeMap<String, RecordStats>,
+}
+
+/// Outcome of the compaction of the storage of a wallet.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageCompaction {
+    /// Number of superseded records deleted
+    pub pruned: u32,
+    /// Storage used by the wallet after the compaction
+    pub stats: StorageStats,
+}
+
+/// Unspent output of an account of the wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Utxo {
+    /// Output pointer, as `<transaction hash>:<output index>`
+    pub output_pointer: String,
+    pub value: types::Nanowits,
+    /// Whether the output is spent by a signed transaction that has not been indexed yet
+    pub reserved: bool,
+}
+
+/// Metadata of a wallet that is not derived from its seed, which can be carried over to another
+/// wallet created from the same seed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletMetadata {
+    /// Version of the metadata document format
+    pub version: u32,
+    /// Map address -> label of the generated addresses
+    pub address_labels: BTreeMap<String, String>,
+    pub contacts: Vec<Contact>,
+    /// Map address -> label of the watched addresses
+    pub watched_addresses: BTreeMap<String, Option<String>>,
+}
+
+/// Wallet metadata signed with the metadata key of the wallet that exported it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedMetadata {
+    pub metadata: WalletMetadata,
+    /// Hex-encoded compressed public key of the metadata key
+    pub public_key: String,
+    /// Hex-encoded DER signature of the SHA-256 hash of the JSON-serialized metadata
+    pub signature: String,
+}
+
+/// Number of entries of a metadata document imported into a wallet.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataImport {
+    pub address_labels: u32,
+    /// Labels of addresses that have not been generated by the wallet yet, which are not imported
+    pub skipped_address_labels: u32,
+    pub contacts: u32,
+    pub watched_addresses: u32,
+}
+
+/// Request sent to the external signer of a session to sign the inputs of a transaction.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureRequest {
+    pub request_id: u32,
+    pub wallet_id: String,
+    /// Kind of transaction being signed: `valueTransfer` or `dataRequest`
+    pub transaction_kind: &'static str,
+    /// Hash of the transaction, which is the message signed for every input
+    pub transaction_hash: String,
+    pub inputs: Vec<SignatureInput>,
+}
+
+/// Input of a transaction that must be signed by an external signer.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInput {
+    pub index: u32,
+    /// Output spent by the input, as `<transaction hash>:<output index>`
+    pub output_pointer: String,
+    /// Derivation path of the key that can spend the output
+    pub path: String,
+}
+
+/// Signature of a transaction input, as returned by an external signer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalSignature {
+    pub index: u32,
+    /// Hex-encoded DER signature
+    pub signature: String,
+    /// Hex-encoded compressed public key
+    pub public_key: String,
+}
+
+/// Human-readable description of a data request and its RAD request.
+#[derive(Debug, Serialize)]
+pub struct DataRequestDescription {
+    pub dr_hash: String,
+    /// Hash of the block that included the data request transaction
+    pub block_hash: String,
+    /// Total value of the data request, fees included
+    pub value: u64,
+    pub witnesses: u16,
+    pub backup_witnesses: u16,
+    pub commit_fee: u64,
+    pub reveal_fee: u64,
+    pub tally_fee: u64,
+    pub time_lock: u64,
+    /// Timestamp before which the sources should not be retrieved
+    pub not_before: u64,
+    pub sources: Vec<RadSource>,
+    /// Script that aggregates the values retrieved from the sources
+    pub aggregate: Vec<RadonCall>,
+    /// Script that reaches consensus on the values revealed by the witnesses
+    pub tally: Vec<RadonCall>,
+}
+
+/// A source of a RAD request and the script applied to the retrieved data.
+#[derive(Debug, Serialize)]
+pub struct RadSource {
+    pub kind: types::RADType,
+    pub url: String,
+    pub script: Vec<RadonCall>,
+}
+
+/// A call of a RADON script.
+#[derive(Debug, Serialize)]
+pub struct RadonCall {
+    /// Name of the operator, e.g. `ArrayReduce`
+    pub operator: String,
+    pub arguments: Vec<types::Json>,
+}
+
+/// Aggregated durations of an operation of the wallet repository.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TimerMetrics {
+    /// Number of times the operation was performed
+    pub count: u64,
+    /// Sum of the durations, in microseconds
+    pub total_micros: u64,
+    /// Longest duration, in microseconds
+    pub max_micros: u64,
+}
+
+/// Timing metrics of the wallet repository since the wallet server was started.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WalletMetrics {
+    /// Indexing the transactions of a block
+    pub block_index: TimerMetrics,
+    /// Writing a batch to the database of a wallet
+    pub db_write: TimerMetrics,
+    /// Waiting for the locks of the wallet state while indexing
+    pub lock_wait: TimerMetrics,
+}
+
+/// A transaction of a block moving funds of the wallet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTransaction {
+    pub hash: String,
+    pub direction: TransactionDirection,
+    /// Movements of the wallet accounts recorded for the transaction
+    pub movements: Vec<MovementId>,
+}
+
+/// Whether a transaction spends funds of the wallet or only pays to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TransactionDirection {
+    Incoming,
+    Outgoing,
+}
+
+/// Identifier of a movement of a wallet account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MovementId {
+    pub account: u32,
+    pub id: u32,
+}
+
+/// A block as returned by the node, along with the transactions moving funds of the wallet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockInfo {
+    pub block: types::Json,
+    pub wallet_transactions: Vec<BlockTransaction>,
+}
+
+/// A notification recorded in the journal of a session.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// Sequence number of the notification in the session
+    pub seq: u64,
+    /// Notification as sent through the subscription, without its `seq` field
+    pub payload: types::Json,
+}
+
+/// Notifications returned by `getNotificationsSince`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationReplay {
+    /// Notifications after the requested sequence number, oldest first
+    pub notifications: Vec<Notification>,
+    /// Sequence number of the last notification of the session, `0` if there are none
+    pub last_seq: u64,
+    /// Whether all the notifications after the requested sequence number are still in the
+    /// journal. If not, the client should refresh its state instead of relying on the replay
+    pub complete: bool,
+}
+
+/// A record of the database of a wallet, decrypted by `exportPlaintext`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaintextRecord {
+    /// Key of the record, lossily decoded as UTF-8
+    pub key: String,
+    /// Hex-encoded key of the record
+    pub key_hex: String,
+    /// Hex-encoded bincode serialization of the value of the record
+    pub value_hex: String,
+}
@@ -1,4 +1,6 @@
-use crate::types;
+use std::sync::Arc;
+
+use crate::{db, metrics, types};
 
 /// Cryptographic params that can be changed for each wallet.
 #[derive(Clone)]
@@ -11,6 +13,12 @@ pub struct Params {
     pub db_hash_iterations: u32,
     pub db_iv_length: usize,
     pub db_salt_length: usize,
+    /// Address paid the change of the transactions, unless a request chooses another one.
+    pub change_address: types::ChangeAddressPolicy,
+    /// Replication of the wallet databases, if a remote storage is configured.
+    pub remote_replication: Option<db::RemoteReplication>,
+    /// Timing metrics of the wallet repository, shared by all the wallets.
+    pub metrics: Arc<metrics::Metrics>,
 }
 
 impl Default for Params {
@@ -24,6 +32,9 @@ impl Default for Params {
             db_hash_iterations: 10_000,
             db_iv_length: 16,
             db_salt_length: 32,
+            change_address: types::ChangeAddressPolicy::default(),
+            remote_replication: None,
+            metrics: Default::default(),
         }
     }
 }
@@ -7,14 +7,46 @@ use crate::{db, types};
 pub enum Error {
     #[fail(display = "maximum key index reached for account")]
     IndexOverflow,
-    #[fail(display = "account balance overflowed")]
-    BalanceOverflow,
+    #[fail(display = "transaction value overflowed the account balance")]
+    TransactionBalanceOverflow,
     #[fail(display = "account balance underflowed")]
     BalanceUnderflow,
     #[fail(display = "maximum transaction id reached for account")]
     TransactionIdOverflow,
     #[fail(display = "an input points to an utxo that's not present")]
     NoUtxoForInput,
+    #[fail(display = "contact not found")]
+    ContactNotFound,
+    #[fail(display = "watched address not found")]
+    WatchedAddressNotFound,
+    #[fail(display = "recurring payment not found")]
+    RecurringPaymentNotFound,
+    #[fail(display = "maximum recurring payment id reached")]
+    RecurringPaymentIdOverflow,
+    #[fail(display = "unsupported metadata version {}", _0)]
+    MetadataVersion(u32),
+    #[fail(display = "metadata was not signed by a wallet created from the same seed")]
+    MetadataSignature,
+    #[fail(display = "output {} is reserved by another transaction", _0)]
+    UtxoReserved(String),
+    #[fail(display = "deposit {} has not been detected yet", _0)]
+    DepositNotFound(u64),
+    #[fail(display = "no reset of the chain of the node has been detected")]
+    NoChainReset,
+    #[fail(display = "account {} not found", _0)]
+    AccountNotFound(u32),
+    #[fail(display = "the master key of the wallet is not stored, recreate it from its seed")]
+    MasterKeyNotStored,
+    #[fail(display = "address {} has not been generated yet", _0)]
+    AddressNotFound(u32),
+    #[fail(display = "insufficient funds: {} available, {} needed", _0, _1)]
+    InsufficientFunds(types::Nanowits, types::Nanowits),
+    #[fail(display = "invalid key path {}", _0)]
+    InvalidKeyPath(String),
+    #[fail(display = "invalid public key hash length {}", _0)]
+    InvalidPkh(usize),
+    #[fail(display = "address {} is from another network", _0)]
+    InvalidAddress(String),
     #[fail(display = "mutex poison error")]
     MutexPoison,
     #[fail(display = "database failed: {}", _0)]
@@ -29,6 +61,41 @@ pub enum Error {
     Bech32(#[cause] bech32::Error),
 }
 
+impl Error {
+    /// Stable identifier of the kind of error, included in the wallet API error payloads.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::IndexOverflow => "indexOverflow",
+            Error::TransactionBalanceOverflow => "transactionBalanceOverflow",
+            Error::BalanceUnderflow => "balanceUnderflow",
+            Error::TransactionIdOverflow => "transactionIdOverflow",
+            Error::NoUtxoForInput => "noUtxoForInput",
+            Error::ContactNotFound => "contactNotFound",
+            Error::WatchedAddressNotFound => "watchedAddressNotFound",
+            Error::RecurringPaymentNotFound => "recurringPaymentNotFound",
+            Error::RecurringPaymentIdOverflow => "recurringPaymentIdOverflow",
+            Error::MetadataVersion(_) => "metadataVersion",
+            Error::MetadataSignature => "metadataSignature",
+            Error::UtxoReserved(_) => "utxoReserved",
+            Error::DepositNotFound(_) => "depositNotFound",
+            Error::NoChainReset => "noChainReset",
+            Error::AccountNotFound(_) => "accountNotFound",
+            Error::MasterKeyNotStored => "masterKeyNotStored",
+            Error::AddressNotFound(_) => "addressNotFound",
+            Error::InsufficientFunds(..) => "insufficientFunds",
+            Error::InvalidKeyPath(_) => "invalidKeyPath",
+            Error::InvalidPkh(_) => "invalidPkh",
+            Error::InvalidAddress(_) => "invalidAddress",
+            Error::MutexPoison => "mutexPoison",
+            Error::Db(_) => "db",
+            Error::Cipher(_) => "cipher",
+            Error::Failure(_) => "failure",
+            Error::KeyDerivation(_) => "keyDerivation",
+            Error::Bech32(_) => "bech32",
+        }
+    }
+}
+
 impl From<failure::Error> for Error {
     fn from(err: failure::Error) -> Self {
         Error::Failure(err)
@@ -4,6 +4,25 @@ pub fn wallet_ids() -> &'static str {
     "wallets"
 }
 
+/// The list of wallet ids of a tenant, or of the wallets created without tenant.
+#[inline]
+pub fn tenant_wallet_ids(tenant: Option<&str>) -> String {
+    match tenant {
+        Some(tenant) => format!("tenant-{}-{}", tenant, wallet_ids()),
+        None => wallet_ids().to_string(),
+    }
+}
+
+/// The id under which the records of a wallet are stored: the wallets of a tenant are stored
+/// under the prefix of the tenant, so they never share records with the wallets of another one.
+#[inline]
+pub fn wallet_storage_id(tenant: Option<&str>, wallet_id: &str) -> String {
+    match tenant {
+        Some(tenant) => format!("tenant-{}-{}", tenant, wallet_id),
+        None => wallet_id.to_string(),
+    }
+}
+
 /// A wallet's name.
 #[inline]
 pub fn wallet_name() -> &'static str {
@@ -22,6 +41,12 @@ pub fn wallet_pkhs() -> &'static str {
     "pkhs"
 }
 
+/// A wallet's number of outputs received by each of its pkhs.
+#[inline]
+pub fn wallet_pkh_payments() -> &'static str {
+    "pkh-payments"
+}
+
 /// A wallet's utxo set.
 #[inline]
 pub fn wallet_utxo_set() -> &'static str {
@@ -34,6 +59,155 @@ pub fn wallet_transactions_count() -> &'static str {
     "transactions-count"
 }
 
+/// A wallet's address book.
+#[inline]
+pub fn wallet_contacts() -> &'static str {
+    "contacts"
+}
+
+/// A wallet's watched addresses.
+#[inline]
+pub fn wallet_watched_addresses() -> &'static str {
+    "watched-addresses"
+}
+
+/// A wallet's transactions moving funds of its watched addresses.
+#[inline]
+pub fn wallet_watched_transactions() -> &'static str {
+    "watched-transactions"
+}
+
+/// A wallet's deposit addresses.
+#[inline]
+pub fn wallet_deposit_addresses() -> &'static str {
+    "deposit-addresses"
+}
+
+/// The sequence number of the last payment to a deposit address detected by a wallet.
+#[inline]
+pub fn wallet_deposits_last_seq() -> &'static str {
+    "deposits-last-seq"
+}
+
+/// The sequence number of the last deposit acknowledged by a wallet's client.
+#[inline]
+pub fn wallet_deposits_cursor() -> &'static str {
+    "deposits-cursor"
+}
+
+/// A payment to a deposit address, by sequence number.
+#[inline]
+pub fn deposit(seq: u64) -> String {
+    format!("deposit-{}", seq)
+}
+
+/// The height of the block including a payment to a deposit address, by sequence number.
+#[inline]
+pub fn deposit_height(seq: u64) -> String {
+    format!("deposit-{}-height", seq)
+}
+
+/// The output paying to a deposit address, once it has been reported as a deposit.
+#[inline]
+pub fn deposit_output(txn_hash: &[u8], output_index: u32) -> String {
+    format!("deposit-output-{}-{}", hex::encode(txn_hash), output_index)
+}
+
+/// The number of blocks indexed by a wallet from the chain it is synced with.
+#[inline]
+pub fn wallet_indexed_height() -> &'static str {
+    "indexed-height"
+}
+
+/// A wallet's recurring payments, all of them in a single record, as stored by older versions.
+#[inline]
+pub fn wallet_recurring_payments() -> &'static str {
+    "recurring-payments"
+}
+
+/// The ids of a wallet's recurring payments whose interval was always given in epochs, as stored
+/// by older versions.
+#[inline]
+pub fn wallet_legacy_recurring_payment_ids() -> &'static str {
+    "recurring-payment-ids"
+}
+
+/// A wallet's recurring payment whose interval was always given in epochs, by id, as stored by
+/// older versions.
+#[inline]
+pub fn legacy_recurring_payment(id: u32) -> String {
+    format!("recurring-payment-{}", id)
+}
+
+/// The ids of a wallet's recurring payments.
+#[inline]
+pub fn wallet_recurring_payment_ids() -> &'static str {
+    "recurring-payment-ids-v2"
+}
+
+/// A wallet's recurring payment, by id.
+#[inline]
+pub fn recurring_payment(id: u32) -> String {
+    format!("recurring-payment-v2-{}", id)
+}
+
+/// A wallet's next id to use for a recurring payment.
+#[inline]
+pub fn wallet_recurring_payments_next_id() -> &'static str {
+    "recurring-payments-next-id"
+}
+
+/// A wallet's payments generated by its recurring payments, all of them in a single record, as
+/// stored by older versions.
+#[inline]
+pub fn wallet_scheduled_payments() -> &'static str {
+    "scheduled-payments"
+}
+
+/// The number of payments generated by a wallet's recurring payments.
+#[inline]
+pub fn wallet_scheduled_payments_count() -> &'static str {
+    "scheduled-payments-count"
+}
+
+/// A payment generated by a wallet's recurring payments, by position.
+#[inline]
+pub fn scheduled_payment(index: u32) -> String {
+    format!("scheduled-payment-{}", index)
+}
+
+/// The positions of the payments generated by a wallet's recurring payments that are still
+/// pending, to be retried the next time the recurring payments are run.
+#[inline]
+pub fn wallet_pending_scheduled_payments() -> &'static str {
+    "pending-scheduled-payments"
+}
+
+/// The last epoch in which a wallet's recurring payments were run.
+#[inline]
+pub fn wallet_schedules_last_epoch() -> &'static str {
+    "schedules-last-epoch"
+}
+
+/// The network whose chain a wallet's indexed state belongs to.
+#[inline]
+pub fn wallet_network() -> &'static str {
+    "network"
+}
+
+/// A wallet's number of resets of its indexed state, done when it is synced with another network.
+#[inline]
+pub fn wallet_index_generation() -> &'static str {
+    "index-generation"
+}
+
+/// The last block a wallet indexed from the chain of a network, identified by its name and the
+/// hash of its genesis block.
+#[inline]
+pub fn wallet_sync_cursor(network: &str, genesis_hash: &str) -> String {
+    format!("sync-cursor-{}-{}", network, genesis_hash)
+}
+
 /// A wallet's balances for all created accounts.
 #[inline]
 pub fn wallet_account_balances() -> &'static str {
@@ -64,6 +238,12 @@ pub fn wallet_id_iv(wallet_id: &str) -> String {
     format!("{}iv", wallet_id)
 }
 
+/// A wallet's master key.
+#[inline]
+pub fn wallet_master_key() -> &'static str {
+    "master-key"
+}
+
 /// A wallet's generated account indexes.
 #[inline]
 pub fn wallet_accounts() -> &'static str {
@@ -94,6 +274,12 @@ pub fn account_next_ek_index(account_index: u32) -> String {
     format!("account-{}-next-ek-index", account_index)
 }
 
+/// An account's next index to use for generating an internal key.
+#[inline]
+pub fn account_next_ik_index(account_index: u32) -> String {
+    format!("account-{}-next-ik-index", account_index)
+}
+
 /// A wallet's account address.
 #[inline]
 pub fn address(account_index: u32, key_index: u32) -> String {
@@ -112,6 +298,24 @@ pub fn address_label(account_index: u32, key_index: u32) -> String {
     format!("account-{}-key-{}-address-label", account_index, key_index)
 }
 
+/// Number of times an address has been handed out to a client.
+#[inline]
+pub fn address_times_requested(account_index: u32, key_index: u32) -> String {
+    format!(
+        "account-{}-key-{}-address-times-requested",
+        account_index, key_index
+    )
+}
+
+/// Timestamp of the generation of an address.
+#[inline]
+pub fn address_created_at(account_index: u32, key_index: u32) -> String {
+    format!(
+        "account-{}-key-{}-address-created-at",
+        account_index, key_index
+    )
+}
+
 /// An custom key decided by the client to store something.
 #[inline]
 pub fn custom(key: &str) -> String {
@@ -130,6 +334,105 @@ pub fn transaction_type(account_index: u32, id: u32) -> String {
     format!("account-{}-transaction-{}-type", account_index, id)
 }
 
+/// The hash of the data request a transaction's reward or refund comes from.
+#[inline]
+pub fn transaction_dr_hash(account_index: u32, id: u32) -> String {
+    format!("account-{}-transaction-{}-dr-hash", account_index, id)
+}
+
+/// The hash of the transaction a movement was recorded for.
+#[inline]
+pub fn transaction_hash(account_index: u32, id: u32) -> String {
+    format!("account-{}-transaction-{}-hash", account_index, id)
+}
+
+/// The address a transaction of the wallet paid to.
+#[inline]
+pub fn transaction_counterparty(account_index: u32, id: u32) -> String {
+    format!("account-{}-transaction-{}-counterparty", account_index, id)
+}
+
+/// The epoch of the block that included a transaction.
+#[inline]
+pub fn transaction_epoch(account_index: u32, id: u32) -> String {
+    format!("account-{}-transaction-{}-epoch", account_index, id)
+}
+
+/// The fee paid by a transaction created by the wallet.
+#[inline]
+pub fn transaction_fee(account_index: u32, id: u32) -> String {
+    format!("account-{}-transaction-{}-fee", account_index, id)
+}
+
+/// The value of a data request created by the wallet, fees of the witnesses included.
+#[inline]
+pub fn transaction_dr_value(account_index: u32, id: u32) -> String {
+    format!("account-{}-transaction-{}-dr-value", account_index, id)
+}
+
+/// A transaction moving funds of the wallet or of a watched address that has been indexed.
+///
+/// The transactions indexed before the first reset of the indexed state use the key without
+/// generation.
+#[inline]
+pub fn indexed_transaction(generation: u32, txn_hash: &[u8]) -> String {
+    if generation == 0 {
+        format!("indexed-transaction-{}", hex::encode(txn_hash))
+    } else {
+        format!(
+            "indexed-transaction-{}-{}",
+            generation,
+            hex::encode(txn_hash)
+        )
+    }
+}
+
+/// The account and id of the transactions recorded for a transaction moving funds of the wallet.
+#[inline]
+pub fn transaction_movements(generation: u32, txn_hash: &[u8]) -> String {
+    if generation == 0 {
+        format!("transaction-{}-movements", hex::encode(txn_hash))
+    } else {
+        format!(
+            "transaction-{}-{}-movements",
+            generation,
+            hex::encode(txn_hash)
+        )
+    }
+}
+
+/// The account and id of the transactions recorded for the outputs paying to or spent from a
+/// pkh of the wallet, as they were stored before being stored one per key.
+#[inline]
+pub fn pkh_movements(generation: u32, pkh: &[u8]) -> String {
+    format!("pkh-{}-{}-movements", generation, hex::encode(pkh))
+}
+
+/// Number of transactions recorded for the outputs paying to or spent from a pkh of the wallet.
+#[inline]
+pub fn pkh_movements_count(generation: u32, pkh: &[u8]) -> String {
+    format!("pkh-{}-{}-movements-count", generation, hex::encode(pkh))
+}
+
+/// The account and id of the n-th transaction recorded for the outputs paying to or spent from
+/// a pkh of the wallet.
+#[inline]
+pub fn pkh_movement(generation: u32, pkh: &[u8], n: u32) -> String {
+    format!("pkh-{}-{}-movement-{}", generation, hex::encode(pkh), n)
+}
+
+/// Total value and first and last epoch of the outputs paying to a pkh of the wallet.
+#[inline]
+pub fn pkh_receipts(generation: u32, pkh: &[u8]) -> String {
+    format!("pkh-{}-{}-receipts", generation, hex::encode(pkh))
+}
+
+/// A data request created by the wallet, i.e. spending some of its outputs.
+#[inline]
+pub fn own_data_request(dr_hash: &[u8]) -> String {
+    format!("own-data-request-{}", hex::encode(dr_hash))
+}
+
 /// The account a transaction's is bound to.
 #[inline]
 pub fn transaction_output_recipient(txn_hash: &[u8], output_index: u32) -> Vec<u8> {
@@ -139,3 +442,27 @@ pub fn transaction_output_recipient(txn_hash: &[u8], output_index: u32) -> Vec<u
 
     key
 }
+
+/// The watched pkh an output pays to, along with its value.
+#[inline]
+pub fn watched_output(txn_hash: &[u8], output_index: u32) -> String {
+    format!("watched-output-{}-{}", hex::encode(txn_hash), output_index)
+}
+
+/// The derivation path of the key a pkh of the wallet belongs to.
+#[inline]
+pub fn pkh_path(pkh: &[u8]) -> String {
+    format!("pkh-{}-path", hex::encode(pkh))
+}
+
+/// The derivation path of the key that can spend an output paying to the wallet.
+#[inline]
+pub fn output_path(txn_hash: &[u8], output_index: u32) -> String {
+    format!("output-{}-{}-path", hex::encode(txn_hash), output_index)
+}
+
+/// The pkh of the wallet an output pays to.
+#[inline]
+pub fn output_pkh(txn_hash: &[u8], output_index: u32) -> String {
+    format!("output-{}-{}-pkh", hex::encode(txn_hash), output_index)
+}
@@ -4,6 +4,7 @@ mod wallet;
 mod wallets;
 
 pub use error::Error;
+pub use keys::wallet_storage_id;
 pub use wallet::Wallet;
 pub use wallets::Wallets;
 
@@ -1,14 +1,17 @@
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ops::Deref as _;
-use std::sync::{Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
-use bech32::ToBase32 as _;
+use bech32::{FromBase32 as _, ToBase32 as _};
+use serde::{Deserialize, Serialize};
+use witnet_crypto::{hash::Sha256, secp256k1, signature};
 
 use super::*;
 use crate::types::Hashable as _;
 use crate::{
-    crypto,
-    db::{Database, WriteBatch as _},
+    constants, crypto,
+    db::{Database, WriteBatch},
     model,
     params::Params,
     types,
@@ -16,7 +19,7 @@ use crate::{
 
 type AccountIndex = u32;
 type TransactionId = u32;
-type Balance = u64;
+type Balance = types::Nanowits;
 type Pkh = Vec<u8>;
 type Index = u32;
 type Utxo = (Pkh, Index);
@@ -26,16 +29,44 @@ pub struct Wallet<T> {
     params: Params,
     engine: types::SignEngine,
     gen_address_mutex: Mutex<()>,
+    schedules_mutex: Mutex<()>,
     /// Current account being used by the client.
     current_account: RwLock<u32>,
-    /// Number of transactions per account
-    transactions_count: RwLock<HashMap<AccountIndex, TransactionId>>,
+    /// Number of transactions per account. This map and the ones below that are also part of the
+    /// `Snapshot` are shared with it, and only copied when they are modified while shared
+    transactions_count: RwLock<Arc<HashMap<AccountIndex, TransactionId>>>,
     /// Account balances for the wallet
-    account_balances: RwLock<HashMap<AccountIndex, Balance>>,
+    account_balances: RwLock<Arc<HashMap<AccountIndex, Balance>>>,
     /// Map pkh -> account index
     pkhs: RwLock<HashMap<Pkh, AccountIndex>>,
+    /// Map pkh -> number of outputs received
+    pkh_payments: RwLock<Arc<HashMap<Pkh, u32>>>,
     /// Map account index -> utxo set, which maps output pointer -> value
-    utxo_set: RwLock<HashMap<AccountIndex, HashMap<Utxo, Balance>>>,
+    utxo_set: RwLock<Arc<HashMap<AccountIndex, HashMap<Utxo, Balance>>>>,
+    /// Map output pointer -> timestamp until which the output is reserved by a signed transaction
+    /// that has not been indexed yet
+    reserved: RwLock<HashMap<Utxo, i64>>,
+    /// Map address -> contact
+    contacts: RwLock<HashMap<String, model::Contact>>,
+    /// Map pkh -> watched address
+    watched: RwLock<HashMap<Pkh, model::WatchedAddress>>,
+    /// Map pkh -> deposit address
+    deposit_addresses: RwLock<HashMap<Pkh, String>>,
+    /// Sequence number of the last payment to a deposit address
+    deposits_last_seq: RwLock<u64>,
+    /// Sequence number of the last deposit acknowledged by the client
+    deposits_cursor: RwLock<u64>,
+    /// Number of resets of the indexed state, which scopes the keys of the indexed transactions
+    index_generation: RwLock<u32>,
+    /// Network of the node the wallet is synced with, if known
+    network: RwLock<Option<types::NetworkId>>,
+    /// Network of the node, if its chain is a reset of the one indexed by the wallet and the
+    /// client has not reset the chain data yet
+    chain_reset: RwLock<Option<types::NetworkId>>,
+    /// Whether the utxo set, balances and transactions count have been loaded
+    loaded: Mutex<bool>,
+    /// Last published copy of the indexed state, read by the queries
+    snapshot: RwLock<Arc<Snapshot>>,
 }
 
 impl<T> Wallet<T>
@@ -49,13 +80,30 @@ where
             engine,
             current_account: Default::default(),
             gen_address_mutex: Default::default(),
+            schedules_mutex: Default::default(),
             transactions_count: Default::default(),
             account_balances: Default::default(),
             pkhs: Default::default(),
+            pkh_payments: Default::default(),
             utxo_set: Default::default(),
+            reserved: Default::default(),
+            contacts: Default::default(),
+            watched: Default::default(),
+            deposit_addresses: Default::default(),
+            deposits_last_seq: Default::default(),
+            deposits_cursor: Default::default(),
+            index_generation: Default::default(),
+            network: Default::default(),
+            chain_reset: Default::default(),
+            loaded: Default::default(),
+            snapshot: Default::default(),
         }
     }
 
+    /// Load the wallet metadata and the balance of its current account, so it can be used right
+    /// away.
+    ///
+    /// The utxo set, which can be big, is loaded afterwards with [load](Wallet::load).
     pub fn unlock(&self) -> Result<types::WalletData> {
         let name: Option<String> = self.db.get_opt(keys::wallet_name())?;
         let caption: Option<String> = self.db.get_opt(keys::wallet_caption())?;
@@ -68,36 +116,53 @@ where
             .get_opt(keys::wallet_accounts())?
             .unwrap_or_else(|| vec![account]);
         let wallet_pkhs: HashMap<Pkh, AccountIndex> = self.db.get_or_default(keys::wallet_pkhs())?;
-        let wallet_utxo_set: HashMap<AccountIndex, HashMap<Utxo, Balance>> =
-            self.db.get_or_default(keys::wallet_utxo_set())?;
-        let wallet_transactions_count: HashMap<AccountIndex, TransactionId> =
-            self.db.get_or_default(keys::wallet_transactions_count())?;
+        let wallet_pkh_payments: HashMap<Pkh, u32> =
+            self.db.get_or_default(keys::wallet_pkh_payments())?;
+        let wallet_contacts: HashMap<String, model::Contact> =
+            self.db.get_or_default(keys::wallet_contacts())?;
+        let wallet_watched: HashMap<Pkh, model::WatchedAddress> =
+            self.db.get_or_default(keys::wallet_watched_addresses())?;
+        let wallet_deposit_addresses: HashMap<Pkh, String> =
+            self.db.get_or_default(keys::wallet_deposit_addresses())?;
+        let wallet_deposits_last_seq: u64 =
+            self.db.get_or_default(keys::wallet_deposits_last_seq())?;
+        let wallet_deposits_cursor: u64 = self.db.get_or_default(keys::wallet_deposits_cursor())?;
+        // The balances are small, unlike the utxo set, so the balance is known right away
         let wallet_account_balances: HashMap<AccountIndex, Balance> =
             self.db.get_or_default(keys::wallet_account_balances())?;
         let balance = wallet_account_balances
             .get(&account)
             .cloned()
-            .unwrap_or_else(|| 0);
+            .unwrap_or_default();
 
         let mut current_account = self.current_account.write()?;
         *current_account = account;
         drop(current_account);
 
-        let mut transactions_count = self.transactions_count.write()?;
-        *transactions_count = wallet_transactions_count;
-        drop(transactions_count);
-
-        let mut account_balances = self.account_balances.write()?;
-        *account_balances = wallet_account_balances;
-        drop(account_balances);
-
         let mut pkhs = self.pkhs.write()?;
         *pkhs = wallet_pkhs;
         drop(pkhs);
 
-        let mut utxo_set = self.utxo_set.write()?;
-        *utxo_set = wallet_utxo_set;
-        drop(utxo_set);
+        let mut pkh_payments = self.pkh_payments.write()?;
+        *pkh_payments = Arc::new(wallet_pkh_payments);
+        drop(pkh_payments);
+
+        let mut contacts = self.contacts.write()?;
+        *contacts = wallet_contacts;
+        drop(contacts);
+
+        let mut watched = self.watched.write()?;
+        *watched = wallet_watched;
+        drop(watched);
+
+        let mut deposit_addresses = self.deposit_addresses.write()?;
+        *deposit_addresses = wallet_deposit_addresses;
+        drop(deposit_addresses);
+
+        *self.deposits_last_seq.write()? = wallet_deposits_last_seq;
+        *self.deposits_cursor.write()? = wallet_deposits_cursor;
+
+        self.publish_snapshot()?;
 
         let wallet = types::WalletData {
             name,
@@ -110,18 +175,93 @@ where
         Ok(wallet)
     }
 
-    pub fn gen_address(&self, label: Option<String>) -> Result<model::Address> {
+    /// Load the utxo set, balances and transactions count of the wallet, if not loaded yet.
+    ///
+    /// Returns whether they were loaded by this call. Methods depending on them call this first,
+    /// so they wait for a load in progress instead of using incomplete data.
+    pub fn load(&self) -> Result<bool> {
+        let mut loaded = self.loaded.lock()?;
+        if *loaded {
+            return Ok(false);
+        }
+
+        let wallet_utxo_set: HashMap<AccountIndex, HashMap<Utxo, Balance>> =
+            self.db.get_or_default(keys::wallet_utxo_set())?;
+        let wallet_transactions_count: HashMap<AccountIndex, TransactionId> =
+            self.db.get_or_default(keys::wallet_transactions_count())?;
+        let wallet_account_balances: HashMap<AccountIndex, Balance> =
+            self.db.get_or_default(keys::wallet_account_balances())?;
+        let wallet_index_generation: u32 =
+            self.db.get_or_default(keys::wallet_index_generation())?;
+
+        let mut transactions_count = self.transactions_count.write()?;
+        *transactions_count = Arc::new(wallet_transactions_count);
+        drop(transactions_count);
+
+        let mut account_balances = self.account_balances.write()?;
+        *account_balances = Arc::new(wallet_account_balances);
+        drop(account_balances);
+
+        let mut utxo_set = self.utxo_set.write()?;
+        *utxo_set = Arc::new(wallet_utxo_set);
+        drop(utxo_set);
+
+        let mut index_generation = self.index_generation.write()?;
+        *index_generation = wallet_index_generation;
+        drop(index_generation);
+
+        // published before flagging the state as loaded, so no query can see the state unloaded
+        self.publish_snapshot()?;
+        *loaded = true;
+
+        Ok(true)
+    }
+
+    /// Whether the utxo set, balances and transactions count have been loaded.
+    pub fn is_loaded(&self) -> Result<bool> {
+        let loaded = *self.loaded.lock()?;
+
+        Ok(loaded)
+    }
+
+    /// Generate a new address for the current account.
+    ///
+    /// If `reuse` is set and the last generated address has not received funds yet, that address
+    /// is returned again instead. New addresses record `now` as the time they were generated.
+    pub fn gen_address(
+        &self,
+        label: Option<String>,
+        reuse: bool,
+        now: i64,
+    ) -> Result<model::Address> {
         let account_index: u32 = self.db.get(keys::wallet_default_account())?;
         let addresses_counter_key = keys::account_next_ek_index(account_index);
         let external_key: types::ExtendedSK = self.db.get(&keys::account_ek(account_index))?;
         // FIXME: Use a merge operator or rocksdb transaction when available in rocksdb crate
         let lock = self.gen_address_mutex.lock()?;
         let address_index: u32 = self.db.get_or_default(&addresses_counter_key)?;
+
+        if let (true, Some(last_index)) = (reuse, address_index.checked_sub(1)) {
+            let last_address = self.address_at(account_index, last_index)?;
+            if last_address.times_received == 0 {
+                let times_requested = last_address.times_requested.saturating_add(1);
+                self.db.put(
+                    keys::address_times_requested(account_index, last_index),
+                    times_requested,
+                )?;
+                drop(lock);
+
+                return Ok(model::Address {
+                    times_requested,
+                    ..last_address
+                });
+            }
+        }
+
         let address_next_index = address_index
             .checked_add(1)
             .ok_or_else(|| Error::IndexOverflow)?;
         self.db.put(addresses_counter_key, address_next_index)?;
-        drop(lock);
 
         let extended_sk = external_key.derive(
             &self.engine,
@@ -142,21 +282,141 @@ where
 
         batch.put(keys::address(account_index, address_index), &address)?;
         batch.put(keys::address_path(account_index, address_index), &path)?;
+        batch.put(keys::pkh_path(&pkh), &path)?;
         if let Some(label) = &label {
             batch.put(keys::address_label(account_index, address_index), label)?;
         }
+        batch.put(
+            keys::address_times_requested(account_index, address_index),
+            1,
+        )?;
+        batch.put(keys::address_created_at(account_index, address_index), now)?;
 
         let mut pkhs = self.pkhs.write()?;
         pkhs.insert(pkh, account_index);
         batch.put(keys::wallet_pkhs(), pkhs.deref())?;
         drop(pkhs);
 
-        self.db.write(batch)?;
+        self.write_batch(batch)?;
+        drop(lock);
 
         Ok(model::Address {
             address,
             path,
             label,
+            times_requested: 1,
+            times_received: 0,
+        })
+    }
+
+    /// Derive a fresh address of the internal keychain of an account, to pay the change of a
+    /// transaction to it. It is not listed among the addresses of the wallet, but the outputs
+    /// paying to it are indexed like those of any other address.
+    fn gen_change_pkh(&self, account_index: u32) -> Result<Pkh> {
+        let counter_key = keys::account_next_ik_index(account_index);
+        let internal_key: types::ExtendedSK = self.db.get(&keys::account_ik(account_index))?;
+        let lock = self.gen_address_mutex.lock()?;
+        let index: u32 = self.db.get_or_default(&counter_key)?;
+        let next_index = index.checked_add(1).ok_or_else(|| Error::IndexOverflow)?;
+
+        let extended_sk =
+            internal_key.derive(&self.engine, &types::KeyPath::default().index(index))?;
+        let types::ExtendedPK { key, .. } =
+            types::ExtendedPK::from_secret_key(&self.engine, &extended_sk);
+
+        let bytes = crypto::calculate_sha256(&key.serialize_uncompressed());
+        let pkh = bytes.as_ref()[..20].to_vec();
+        let path = format!("{}/1/{}", account_keypath(account_index), index);
+
+        let mut batch = self.db.batch();
+
+        batch.put(counter_key, next_index)?;
+        batch.put(keys::pkh_path(&pkh), &path)?;
+
+        let mut pkhs = self.pkhs.write()?;
+        pkhs.insert(pkh.clone(), account_index);
+        batch.put(keys::wallet_pkhs(), pkhs.deref())?;
+        drop(pkhs);
+
+        self.write_batch(batch)?;
+        drop(lock);
+
+        Ok(pkh)
+    }
+
+    /// Retrieve the master key of the wallet.
+    ///
+    /// Wallets created before their master key was stored only have the keys of their default
+    /// account, from which the master key can not be derived, so they must be created again from
+    /// their seed to use it.
+    pub fn master_key(&self) -> Result<types::ExtendedSK> {
+        self.db
+            .get_opt(keys::wallet_master_key())?
+            .ok_or(Error::MasterKeyNotStored)
+    }
+
+    /// Derive the account-level key of the current wallet account.
+    pub fn account_key(&self) -> Result<types::ExtendedSK> {
+        let account_index = *self.current_account.read()?;
+        let master_key = self.master_key()?;
+        let account_key = master_key.derive(&self.engine, &account_keypath(account_index))?;
+
+        Ok(account_key)
+    }
+
+    /// Export the extended public key of an account as a bech32 `xpub` string, from which all the
+    /// addresses of the account can be derived but none of its funds spent.
+    pub fn export_xpub(&self, account_index: u32) -> Result<String> {
+        let current_account = *self.current_account.read()?;
+        let accounts: Vec<u32> = self
+            .db
+            .get_opt(keys::wallet_accounts())?
+            .unwrap_or_else(|| vec![current_account]);
+        if !accounts.contains(&account_index) {
+            return Err(Error::AccountNotFound(account_index));
+        }
+
+        let master_key = self.master_key()?;
+        let account_key = master_key.derive(&self.engine, &account_keypath(account_index))?;
+        let account_key = types::ExtendedPK::from_secret_key(&self.engine, &account_key);
+        let xpub = crypto::encode_xpub(&account_key, types::XprvDepth::Account.depth())?;
+
+        Ok(xpub)
+    }
+
+    /// Prove that the wallet controls the address with the given index of the default account,
+    /// where [gen_address](Wallet::gen_address) generates the addresses, by signing a challenge
+    /// with the key of the address.
+    pub fn address_proof(
+        &self,
+        address_index: u32,
+        challenge: String,
+    ) -> Result<model::AddressProof> {
+        let account_index: u32 = self.db.get(keys::wallet_default_account())?;
+        let address: String = self
+            .db
+            .get_opt(&keys::address(account_index, address_index))?
+            .ok_or_else(|| Error::AddressNotFound(address_index))?;
+        let path: String = self
+            .db
+            .get(&keys::address_path(account_index, address_index))?;
+
+        let external_key: types::ExtendedSK = self.db.get(&keys::account_ek(account_index))?;
+        let key: types::SK = external_key
+            .derive(
+                &self.engine,
+                &types::KeyPath::default().index(address_index),
+            )?
+            .into();
+        let public_key = secp256k1::PublicKey::from_secret_key(&self.engine, &key);
+        let signature = signature::sign(key, crypto::address_proof_hash(&challenge).as_ref());
+
+        Ok(model::AddressProof {
+            address,
+            path,
+            public_key: hex::encode(public_key.serialize().as_ref()),
+            challenge,
+            signature: hex::encode(signature.serialize_der()),
         })
     }
 
@@ -172,19 +432,7 @@ where
         let mut addresses = Vec::with_capacity(range.len());
 
         for address_index in range.rev() {
-            let address = self.db.get(&keys::address(account_index, address_index))?;
-            let path = self
-                .db
-                .get(&keys::address_path(account_index, address_index))?;
-            let label = self
-                .db
-                .get(&keys::address_label(account_index, address_index))?;
-
-            addresses.push(model::Address {
-                address,
-                path,
-                label,
-            });
+            addresses.push(self.address_at(account_index, address_index)?);
         }
 
         Ok(model::Addresses {
@@ -193,164 +441,2411 @@ where
         })
     }
 
-    pub fn db_get(&self, key: &str) -> Result<Option<String>> {
-        let value = self.db.get_opt(&keys::custom(key))?;
+    /// Retrieve the provenance of every address generated for the current account: when it was
+    /// generated and the payments it has received, for accounting and audit tools.
+    pub fn address_report(&self) -> Result<Vec<model::AddressReport>> {
+        self.load()?;
+        let account_index: u32 = self.db.get(keys::wallet_default_account())?;
+        let last_index: u32 = self
+            .db
+            .get_or_default(&keys::account_next_ek_index(account_index))?;
+        let generation = self.snapshot()?.index_generation;
 
-        Ok(value)
+        (0..last_index)
+            .map(|address_index| {
+                let model::Address {
+                    address,
+                    path,
+                    label,
+                    times_received,
+                    ..
+                } = self.address_at(account_index, address_index)?;
+                // Addresses generated before the timestamp was recorded have none
+                let created_at = self
+                    .db
+                    .get_opt(&keys::address_created_at(account_index, address_index))?;
+                let (_, data) = bech32::decode(&address)?;
+                let pkh = Vec::<u8>::from_base32(&data)?;
+                let receipts: Option<AddressReceipts> =
+                    self.db.get_opt(&keys::pkh_receipts(generation, &pkh))?;
+                // Payments indexed before the receipts were recorded have no receipts, so their
+                // total is unknown until the chain data is reset and indexed again
+                let total_received = match receipts {
+                    Some(receipts) => Some(receipts.total),
+                    None if times_received > 0 => None,
+                    None => Some(Balance::ZERO),
+                };
+
+                Ok(model::AddressReport {
+                    address,
+                    path,
+                    label,
+                    created_at,
+                    first_payment_epoch: receipts.map(|receipts| receipts.first_epoch),
+                    first_payment_at: None,
+                    last_payment_epoch: receipts.map(|receipts| receipts.last_epoch),
+                    last_payment_at: None,
+                    times_received,
+                    total_received,
+                })
+            })
+            .collect()
     }
 
-    pub fn db_set(&self, key: &str, value: &str) -> Result<()> {
-        self.db.put(&keys::custom(key), value)?;
+    /// Retrieve a generated address of an account, along with its usage counters.
+    fn address_at(&self, account_index: u32, address_index: u32) -> Result<model::Address> {
+        let address: String = self.db.get(&keys::address(account_index, address_index))?;
+        let path = self
+            .db
+            .get(&keys::address_path(account_index, address_index))?;
+        let label = self
+            .db
+            .get(&keys::address_label(account_index, address_index))?;
+        // Addresses generated before the counter existed were handed out once
+        let times_requested = self
+            .db
+            .get_opt(&keys::address_times_requested(account_index, address_index))?
+            .unwrap_or(1);
+        let (_, data) = bech32::decode(&address)?;
+        let pkh = Vec::<u8>::from_base32(&data)?;
+        let times_received = self
+            .snapshot()?
+            .pkh_payments
+            .get(&pkh)
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(model::Address {
+            address,
+            path,
+            label,
+            times_requested,
+            times_received,
+        })
+    }
+
+    /// Retrieve all the contacts of the wallet address book, sorted by name.
+    pub fn contacts(&self) -> Result<Vec<model::Contact>> {
+        let mut contacts: Vec<model::Contact> = self.contacts.read()?.values().cloned().collect();
+        contacts.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.address.cmp(&b.address)));
+
+        Ok(contacts)
+    }
+
+    /// Retrieve the name of the contact saved for an address, if any.
+    pub fn contact_name(&self, address: &str) -> Result<Option<String>> {
+        let name = self
+            .contacts
+            .read()?
+            .get(address)
+            .map(|contact| contact.name.clone());
+
+        Ok(name)
+    }
+
+    /// Save a contact in the wallet address book, replacing the existing contact for the same
+    /// address if there is one.
+    pub fn save_contact(&self, contact: model::Contact) -> Result<()> {
+        let mut contacts = self.contacts.write()?;
+        let mut saved = contacts.clone();
+        saved.insert(contact.address.clone(), contact);
+        self.db.put(keys::wallet_contacts(), &saved)?;
+        *contacts = saved;
 
         Ok(())
     }
 
-    pub fn index_txns(&self, txns: &[types::VTTransactionBody]) -> Result<()> {
-        let mut batch = self.db.batch();
+    /// Remove the contact saved for an address from the wallet address book.
+    pub fn delete_contact(&self, address: &str) -> Result<()> {
+        let mut contacts = self.contacts.write()?;
+        let mut saved = contacts.clone();
+        saved
+            .remove(address)
+            .ok_or_else(|| Error::ContactNotFound)?;
+        self.db.put(keys::wallet_contacts(), &saved)?;
+        *contacts = saved;
 
-        for txn in txns {
-            let txn_hash = txn.hash().as_ref().to_vec();
+        Ok(())
+    }
 
-            for input in &txn.inputs {
-                let p = input.output_pointer();
-                let pointed_txn_hash = p.transaction_id.as_ref().to_vec();
-                let pointed_output_index = p.output_index;
-
-                if let Some(account_index) =
-                    self.db
-                        .get_opt::<_, u32>(&keys::transaction_output_recipient(
-                            &pointed_txn_hash,
-                            pointed_output_index,
-                        ))?
-                {
-                    let utxo_key = (pointed_txn_hash, pointed_output_index);
-
-                    // remove the UTXO from the utxo set
-                    let mut utxo_set = self.utxo_set.write()?;
-                    let account_utxo_set = utxo_set
-                        .get_mut(&account_index)
-                        .expect("utxo set not found for account_index");
-                    let value = match account_utxo_set.remove(&utxo_key) {
-                        Some(value) => value,
-                        None => Err(Error::NoUtxoForInput)?,
-                    };
-                    drop(utxo_set);
-
-                    // record transaction for this account
-                    let txn_id = self.next_transaction_id(account_index)?;
-                    batch.put(&keys::transaction_value(account_index, txn_id), value)?;
-                    batch.put(&keys::transaction_type(account_index, txn_id), "debit")?;
-
-                    // update balance
-                    self.update_account_balance(account_index, value, BalanceOp::Sub)?;
-                }
+    /// Retrieve all the addresses watched by the wallet, sorted by address.
+    pub fn watched_addresses(&self) -> Result<Vec<model::WatchedAddress>> {
+        let mut watched: Vec<model::WatchedAddress> =
+            self.watched.read()?.values().cloned().collect();
+        watched.sort_by(|a, b| a.address.cmp(&b.address));
+
+        Ok(watched)
+    }
+
+    /// Start watching an address outside of the wallet keychains, or update its label if it's
+    /// already being watched.
+    ///
+    /// Only the movements of blocks indexed from now on are followed.
+    pub fn watch_address(
+        &self,
+        address: String,
+        label: Option<String>,
+    ) -> Result<model::WatchedAddress> {
+        let pkh = self.address_pkh(&address)?;
+        let mut watched = self.watched.write()?;
+        let watched_address = watched.entry(pkh).or_insert_with(|| model::WatchedAddress {
+            address,
+            label: None,
+            balance: types::Nanowits::ZERO,
+        });
+        watched_address.label = label;
+        let watched_address = watched_address.clone();
+        self.db
+            .put(keys::wallet_watched_addresses(), watched.deref())?;
+
+        Ok(watched_address)
+    }
+
+    /// Stop watching an address.
+    pub fn unwatch_address(&self, address: &str) -> Result<()> {
+        let pkh = self.address_pkh(address)?;
+        let mut watched = self.watched.write()?;
+        watched
+            .remove(&pkh)
+            .ok_or_else(|| Error::WatchedAddressNotFound)?;
+        self.db
+            .put(keys::wallet_watched_addresses(), watched.deref())?;
+
+        Ok(())
+    }
+
+    /// Decode the pkh of an address of the network of the wallet.
+    fn address_pkh(&self, address: &str) -> Result<Pkh> {
+        let (hrp, data) = bech32::decode(address)?;
+        if hrp != if self.params.testnet { "twit" } else { "wit" } {
+            return Err(Error::InvalidAddress(address.to_string()));
+        }
+        let pkh = Vec::<u8>::from_base32(&data)?;
+        if pkh.len() != 20 {
+            return Err(Error::InvalidPkh(pkh.len()));
+        }
+
+        Ok(pkh)
+    }
+
+    /// Retrieve the transactions that moved funds of the watched addresses.
+    pub fn watched_transactions(&self) -> Result<Vec<model::Transaction>> {
+        let transactions = self
+            .db
+            .get_or_default(keys::wallet_watched_transactions())?;
+
+        Ok(transactions)
+    }
+
+    /// Register addresses whose payments are reported as deposits, returning how many of them
+    /// were not registered yet.
+    ///
+    /// Only the payments of blocks indexed from now on are reported.
+    pub fn register_deposit_addresses(&self, addresses: Vec<String>) -> Result<u32> {
+        let pkhs = addresses
+            .into_iter()
+            .map(|address| -> Result<(Pkh, String)> {
+                let (_, data) = bech32::decode(&address)?;
+                let pkh = Vec::<u8>::from_base32(&data)?;
+
+                Ok((pkh, address))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut deposit_addresses = self.lock_wait(|| self.deposit_addresses.write())?;
+        let mut registered = 0;
+        for (pkh, address) in pkhs {
+            if deposit_addresses.insert(pkh, address).is_none() {
+                registered += 1;
             }
+        }
+        self.db
+            .put(keys::wallet_deposit_addresses(), deposit_addresses.deref())?;
 
-            for (output_index, output) in txn.outputs.iter().enumerate() {
-                let pkh = output.pkh.as_ref();
-                let value = output.value;
+        Ok(registered)
+    }
 
-                if let Some(account_index) = self.pkhs.read()?.get(pkh).cloned() {
-                    // add UTXO to the utxo set
-                    let mut utxo_set = self.utxo_set.write()?;
-                    let account_utxo_set = utxo_set
-                        .get_mut(&account_index)
-                        .expect("utxo set not found for account");
-                    account_utxo_set.insert((txn_hash.clone(), output_index as u32), value);
-                    drop(utxo_set);
+    /// Get at most `limit` of the deposits not acknowledged yet, in the order they were detected.
+    ///
+    /// Deposits are returned until they are acknowledged with
+    /// [ack_deposits](Wallet::ack_deposits), even after the wallet is restarted.
+    pub fn deposits(&self, limit: u32) -> Result<model::Deposits> {
+        let cursor = *self.lock_wait(|| self.deposits_cursor.read())?;
+        let last_seq = *self.lock_wait(|| self.deposits_last_seq.read())?;
+        let last_epoch = self.last_indexed_epoch()?;
+        let last_height: u32 = self.db.get_or_default(keys::wallet_indexed_height())?;
+        let to_seq = cmp::min(last_seq, cursor.saturating_add(u64::from(limit)));
 
-                    // record transaction for this account
-                    let txn_id = self.next_transaction_id(account_index)?;
-                    batch.put(&keys::transaction_value(account_index, txn_id), value)?;
-                    batch.put(&keys::transaction_type(account_index, txn_id), "credit")?;
+        let deposits = (cursor + 1..=to_seq)
+            .map(|seq| -> Result<model::Deposit> {
+                let mut deposit: model::Deposit = self.db.get(&keys::deposit(seq))?;
+                let height: Option<u32> = self.db.get_opt(&keys::deposit_height(seq))?;
+                deposit.confirmations = match height {
+                    Some(height) if height <= last_height => last_height - height + 1,
+                    Some(_) => 0,
+                    // deposits detected before their height was recorded count epochs instead
+                    None => last_epoch.map_or(0, |last_epoch| {
+                        if last_epoch < deposit.epoch {
+                            0
+                        } else {
+                            (last_epoch - deposit.epoch).saturating_add(1)
+                        }
+                    }),
+                };
 
-                    self.db.put(
-                        &keys::transaction_output_recipient(&txn_hash, output_index as u32),
-                        account_index,
-                    )?;
+                Ok(deposit)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-                    // update balance
-                    self.update_account_balance(account_index, value, BalanceOp::Add)?;
-                }
+        Ok(model::Deposits {
+            deposits,
+            cursor,
+            last_seq,
+        })
+    }
+
+    /// Acknowledge the deposits up to the one with sequence number `cursor`, so they are not
+    /// returned anymore and their records are deleted. Acknowledging deposits that were already
+    /// acknowledged does nothing.
+    ///
+    /// Returns the sequence number of the last acknowledged deposit.
+    pub fn ack_deposits(&self, cursor: u64) -> Result<u64> {
+        let last_seq = *self.lock_wait(|| self.deposits_last_seq.read())?;
+        if cursor > last_seq {
+            return Err(Error::DepositNotFound(cursor));
+        }
+
+        let mut deposits_cursor = self.lock_wait(|| self.deposits_cursor.write())?;
+        if cursor > *deposits_cursor {
+            self.db.put(keys::wallet_deposits_cursor(), cursor)?;
+            for seq in *deposits_cursor + 1..=cursor {
+                self.db.delete(&keys::deposit(seq))?;
+                self.db.delete(&keys::deposit_height(seq))?;
             }
+            *deposits_cursor = cursor;
         }
 
-        // persist modified utxo set
-        let utxo_set_guard = self.utxo_set.read()?;
-        let utxo_set = utxo_set_guard.deref();
-        self.db.put(keys::wallet_utxo_set(), utxo_set)?;
+        Ok(*deposits_cursor)
+    }
 
-        // persist modified transactions count per account
-        let transactions_count_guard = self.transactions_count.read()?;
-        let transactions_count = transactions_count_guard.deref();
-        self.db
-            .put(keys::wallet_transactions_count(), transactions_count)?;
+    /// Retrieve the derivation paths of the keys that can spend the outputs pointed by the given
+    /// inputs, so they can be signed by an external signer.
+    pub fn input_paths(&self, inputs: &[types::Input]) -> Result<Vec<String>> {
+        inputs
+            .iter()
+            .map(|input| {
+                let p = input.output_pointer();
 
-        // persist transactions
-        self.db.write(batch)?;
+                self.db
+                    .get_opt(&keys::output_path(
+                        p.transaction_id.as_ref(),
+                        p.output_index,
+                    ))?
+                    .ok_or_else(|| Error::NoUtxoForInput)
+            })
+            .collect()
+    }
+
+    /// Retrieve the public key hashes of the addresses of the outputs pointed by the given
+    /// inputs, which the keys signing the inputs must hash to.
+    pub fn input_pkhs(&self, inputs: &[types::Input]) -> Result<Vec<types::PublicKeyHash>> {
+        inputs
+            .iter()
+            .map(|input| {
+                let p = input.output_pointer();
+                let pkh: Pkh = self
+                    .db
+                    .get_opt(&keys::output_pkh(p.transaction_id.as_ref(), p.output_index))?
+                    .ok_or_else(|| Error::NoUtxoForInput)?;
+
+                pkh_from_bytes(&pkh)
+            })
+            .collect()
+    }
+
+    /// Reserve the outputs spent by the inputs of a transaction until a block spending them is
+    /// indexed, so another transaction of the wallet does not spend them again in the meantime.
+    ///
+    /// Reservations expire after `UTXO_RESERVATION_SECS` seconds, e.g. if the transaction is never
+    /// broadcast. Nothing is reserved if any of the outputs is already reserved.
+    pub fn reserve_inputs(&self, inputs: &[types::Input], now: i64) -> Result<()> {
+        let mut reserved = self.lock_wait(|| self.reserved.write())?;
+        reserved.retain(|_, expiry| *expiry > now);
+
+        if let Some(input) = inputs
+            .iter()
+            .find(|input| reserved.contains_key(&input_utxo(input)))
+        {
+            return Err(Error::UtxoReserved(input.output_pointer().to_string()));
+        }
+
+        let expiry = now.saturating_add(constants::UTXO_RESERVATION_SECS);
+        reserved.extend(inputs.iter().map(|input| (input_utxo(input), expiry)));
 
         Ok(())
     }
 
-    /// Retrieve the balance for the current wallet account.
-    pub fn balance(&self) -> Result<(AccountIndex, Balance)> {
-        let account = *self.current_account.read()?;
-        let balance = self
-            .account_balances
-            .read()?
-            .get(&account)
-            .cloned()
-            .expect("balance not found for account");
+    /// Release the outputs reserved for a transaction that was not signed after all.
+    pub fn release_inputs(&self, inputs: &[types::Input]) -> Result<()> {
+        let mut reserved = self.lock_wait(|| self.reserved.write())?;
+        for input in inputs {
+            reserved.remove(&input_utxo(input));
+        }
 
-        Ok((account, balance))
+        Ok(())
     }
 
-    fn next_transaction_id(&self, account_index: u32) -> Result<u32> {
-        let transactions_count = self.transactions_count.write()?;
-        let next_id = transactions_count
-            .get(&account_index)
-            .expect("transactions count not found for account index");
-        let id = *next_id;
+    /// Get the unspent outputs of the current wallet account, flagging the ones reserved by a
+    /// signed transaction that has not been indexed yet.
+    pub fn utxos(&self, now: i64) -> Result<Vec<model::Utxo>> {
+        self.load()?;
+        let snapshot = self.snapshot()?;
+        let reserved = self.lock_wait(|| self.reserved.read())?;
+        let mut utxos: Vec<model::Utxo> = snapshot
+            .utxo_set
+            .get(&snapshot.current_account)
+            .map(|account_utxo_set| {
+                account_utxo_set
+                    .iter()
+                    .map(|((txn_hash, output_index), value)| model::Utxo {
+                        output_pointer: format!("{}:{}", hex::encode(txn_hash), output_index),
+                        value: *value,
+                        reserved: reserved
+                            .get(&(txn_hash.clone(), *output_index))
+                            .map_or(false, |expiry| *expiry > now),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        utxos.sort_by(|a, b| a.output_pointer.cmp(&b.output_pointer));
 
-        next_id
-            .checked_add(1)
-            .ok_or_else(|| Error::TransactionIdOverflow)?;
+        Ok(utxos)
+    }
 
-        Ok(id)
+    /// Export the metadata of the wallet that is not derived from its seed: address labels,
+    /// contacts and watched addresses.
+    ///
+    /// The document is signed with the metadata key of the wallet, so it can only be imported into
+    /// a wallet created from the same seed.
+    pub fn export_metadata(&self) -> Result<model::SignedMetadata> {
+        let account_index: u32 = self.db.get(keys::wallet_default_account())?;
+        let mut address_labels = BTreeMap::new();
+        for (address_index, address) in self.account_addresses(account_index)? {
+            let label: Option<String> = self
+                .db
+                .get_opt(&keys::address_label(account_index, address_index))?;
+            if let Some(label) = label {
+                address_labels.insert(address, label);
+            }
+        }
+        let watched_addresses = self
+            .watched_addresses()?
+            .into_iter()
+            .map(|watched| (watched.address, watched.label))
+            .collect();
+
+        let metadata = model::WalletMetadata {
+            version: constants::METADATA_VERSION,
+            address_labels,
+            contacts: self.contacts()?,
+            watched_addresses,
+        };
+
+        self.sign_metadata(metadata)
     }
 
-    fn update_account_balance(&self, account_index: u32, value: u64, op: BalanceOp) -> Result<()> {
-        let mut account_balances = self.account_balances.write()?;
-        let balance = account_balances
-            .get_mut(&account_index)
-            .expect("balance not found for account index");
+    /// Sign a metadata document with the metadata key of the wallet.
+    fn sign_metadata(&self, metadata: model::WalletMetadata) -> Result<model::SignedMetadata> {
+        let key: types::SK = self.metadata_key()?.into();
+        let public_key = secp256k1::PublicKey::from_secret_key(&self.engine, &key);
+        let signature = signature::sign(key, metadata_hash(&metadata)?.as_ref());
 
-        match op {
-            BalanceOp::Add => {
-                balance
-                    .checked_add(value)
-                    .ok_or_else(|| Error::BalanceOverflow)?;
-            }
-            BalanceOp::Sub => {
-                balance
-                    .checked_sub(1)
-                    .ok_or_else(|| Error::BalanceUnderflow)?;
+        Ok(model::SignedMetadata {
+            metadata,
+            public_key: hex::encode(public_key.serialize().as_ref()),
+            signature: hex::encode(signature.serialize_der()),
+        })
+    }
+
+    /// Import a metadata document exported by a wallet created from the same seed.
+    ///
+    /// Contacts and watched addresses replace the existing ones for the same address. Address
+    /// labels are only imported for the addresses already generated by this wallet. The document
+    /// is imported in a single write batch, so either all of it is imported or none of it is.
+    pub fn import_metadata(&self, signed: model::SignedMetadata) -> Result<model::MetadataImport> {
+        let model::SignedMetadata {
+            metadata,
+            public_key,
+            signature,
+        } = signed;
+        if metadata.version != constants::METADATA_VERSION {
+            return Err(Error::MetadataVersion(metadata.version));
+        }
+
+        let key: types::SK = self.metadata_key()?.into();
+        let own_public_key = secp256k1::PublicKey::from_secret_key(&self.engine, &key);
+        if hex::encode(own_public_key.serialize().as_ref()) != public_key {
+            return Err(Error::MetadataSignature);
+        }
+        let signature = hex::decode(&signature)
+            .ok()
+            .and_then(|der| secp256k1::Signature::from_der(&der).ok())
+            .ok_or_else(|| Error::MetadataSignature)?;
+        signature::verify(
+            &own_public_key,
+            metadata_hash(&metadata)?.as_ref(),
+            &signature,
+        )
+        .map_err(|_| Error::MetadataSignature)?;
+
+        let mut imported = model::MetadataImport::default();
+
+        let account_index: u32 = self.db.get(keys::wallet_default_account())?;
+        let address_indexes: HashMap<String, u32> = self
+            .account_addresses(account_index)?
+            .into_iter()
+            .map(|(address_index, address)| (address, address_index))
+            .collect();
+        let mut batch = self.db.batch();
+        for (address, label) in &metadata.address_labels {
+            match address_indexes.get(address) {
+                Some(address_index) => {
+                    batch.put(keys::address_label(account_index, *address_index), label)?;
+                    imported.address_labels += 1;
+                }
+                None => imported.skipped_address_labels += 1,
             }
         }
 
-        Ok(())
+        let mut contacts = self.contacts.write()?;
+        let mut imported_contacts = contacts.clone();
+        for contact in metadata.contacts {
+            imported_contacts.insert(contact.address.clone(), contact);
+            imported.contacts += 1;
+        }
+        batch.put(keys::wallet_contacts(), &imported_contacts)?;
+
+        let mut watched = self.watched.write()?;
+        let mut imported_watched = watched.clone();
+        for (address, label) in metadata.watched_addresses {
+            let pkh = self.address_pkh(&address)?;
+            let watched_address =
+                imported_watched
+                    .entry(pkh)
+                    .or_insert_with(|| model::WatchedAddress {
+                        address,
+                        label: None,
+                        balance: types::Nanowits::ZERO,
+                    });
+            watched_address.label = label;
+            imported.watched_addresses += 1;
+        }
+        batch.put(keys::wallet_watched_addresses(), &imported_watched)?;
+
+        // The state in memory is only updated once the whole document is written
+        self.write_batch(batch)?;
+        *contacts = imported_contacts;
+        *watched = imported_watched;
+
+        Ok(imported)
     }
-}
 
-enum BalanceOp {
-    Add,
-    Sub,
-}
+    /// Derive the key used to sign the metadata exported by the wallet.
+    fn metadata_key(&self) -> Result<types::ExtendedSK> {
+        let master_key = self.master_key()?;
+        let metadata_key = master_key.derive(
+            &self.engine,
+            &account_keypath(constants::METADATA_KEYPATH_ACCOUNT),
+        )?;
 
-#[inline]
-fn account_keypath(index: u32) -> types::KeyPath {
-    types::KeyPath::default()
-        .hardened(3)
-        .hardened(4919)
-        .hardened(index)
-}
+        Ok(metadata_key)
+    }
+
+    /// Retrieve the generated addresses of an account, along with their key index.
+    fn account_addresses(&self, account_index: u32) -> Result<Vec<(u32, String)>> {
+        let last_index: u32 = self
+            .db
+            .get_or_default(&keys::account_next_ek_index(account_index))?;
+
+        (0..last_index)
+            .map(|address_index| {
+                let address = self.db.get(&keys::address(account_index, address_index))?;
+
+                Ok((address_index, address))
+            })
+            .collect()
+    }
+
+    /// Save a new recurring payment, assigning it a new id.
+    pub fn add_recurring_payment(
+        &self,
+        payment: model::RecurringPayment,
+    ) -> Result<model::RecurringPayment> {
+        let lock = self.schedules_mutex.lock()?;
+        self.migrate_schedules()?;
+        let id: u32 = self
+            .db
+            .get_or_default(keys::wallet_recurring_payments_next_id())?;
+        let next_id = id
+            .checked_add(1)
+            .ok_or_else(|| Error::RecurringPaymentIdOverflow)?;
+        let mut ids: BTreeSet<u32> = self
+            .db
+            .get_or_default(keys::wallet_recurring_payment_ids())?;
+        ids.insert(id);
+        let payment = model::RecurringPayment { id, ..payment };
+
+        let mut batch = self.db.batch();
+        batch.put(keys::wallet_recurring_payments_next_id(), next_id)?;
+        batch.put(keys::wallet_recurring_payment_ids(), &ids)?;
+        batch.put(keys::recurring_payment(id), &payment)?;
+        self.write_batch(batch)?;
+        drop(lock);
+
+        Ok(payment)
+    }
+
+    /// Retrieve all the recurring payments of the wallet, sorted by id.
+    pub fn recurring_payments(&self) -> Result<Vec<model::RecurringPayment>> {
+        let lock = self.schedules_mutex.lock()?;
+        self.migrate_schedules()?;
+        let ids: BTreeSet<u32> = self
+            .db
+            .get_or_default(keys::wallet_recurring_payment_ids())?;
+        let payments: Vec<model::RecurringPayment> = ids
+            .into_iter()
+            .map(|id| self.db.get(&keys::recurring_payment(id)))
+            .collect::<std::result::Result<_, _>>()?;
+        drop(lock);
+
+        Ok(payments)
+    }
+
+    /// Remove a recurring payment. The payments it already generated are kept, but the pending
+    /// ones are not retried anymore.
+    pub fn delete_recurring_payment(&self, id: u32) -> Result<()> {
+        let lock = self.schedules_mutex.lock()?;
+        self.migrate_schedules()?;
+        let mut ids: BTreeSet<u32> = self
+            .db
+            .get_or_default(keys::wallet_recurring_payment_ids())?;
+        if !ids.remove(&id) {
+            return Err(Error::RecurringPaymentNotFound);
+        }
+        let mut pending: BTreeSet<u32> = self
+            .db
+            .get_or_default(keys::wallet_pending_scheduled_payments())?;
+        for index in pending.clone() {
+            let scheduled: model::ScheduledPayment =
+                self.db.get(&keys::scheduled_payment(index))?;
+            if scheduled.recurring_payment_id == id {
+                pending.remove(&index);
+            }
+        }
+        let mut batch = self.db.batch();
+        batch.put(keys::wallet_recurring_payment_ids(), &ids)?;
+        batch.put(keys::wallet_pending_scheduled_payments(), &pending)?;
+        self.write_batch(batch)?;
+        self.db.delete(&keys::recurring_payment(id))?;
+        drop(lock);
+
+        Ok(())
+    }
+
+    /// Retrieve all the payments generated by the recurring payments of the wallet, in the order
+    /// they were generated.
+    pub fn scheduled_payments(&self) -> Result<Vec<model::ScheduledPayment>> {
+        let lock = self.schedules_mutex.lock()?;
+        self.migrate_schedules()?;
+        let count: u32 = self
+            .db
+            .get_or_default(keys::wallet_scheduled_payments_count())?;
+        let payments: Vec<model::ScheduledPayment> = (0..count)
+            .map(|index| self.db.get(&keys::scheduled_payment(index)))
+            .collect::<std::result::Result<_, _>>()?;
+        drop(lock);
+
+        Ok(payments)
+    }
+
+    /// Generate the payments of the recurring payments that are due at `epoch`, returning them
+    /// along with their position in the payments of the wallet and whether they were already
+    /// generated. The payments left pending by previous runs, see
+    /// [retry_scheduled_payment](Wallet::retry_scheduled_payment), are returned first.
+    ///
+    /// Each recurring payment generates at most one payment per epoch, and its recurring payments
+    /// only run once per epoch: the payments missed while the wallet was locked are caught up one
+    /// per epoch, starting with the oldest one.
+    pub fn run_schedules(&self, epoch: u32) -> Result<Vec<(u32, model::ScheduledPayment, bool)>> {
+        let lock = self.schedules_mutex.lock()?;
+        self.migrate_schedules()?;
+        let last_epoch: Option<u32> = self.db.get_opt(keys::wallet_schedules_last_epoch())?;
+        if last_epoch.map_or(false, |last_epoch| last_epoch >= epoch) {
+            return Ok(vec![]);
+        }
+
+        let ids: BTreeSet<u32> = self
+            .db
+            .get_or_default(keys::wallet_recurring_payment_ids())?;
+        let mut count: u32 = self
+            .db
+            .get_or_default(keys::wallet_scheduled_payments_count())?;
+        let mut batch = self.db.batch();
+        let pending: BTreeSet<u32> = self
+            .db
+            .get_or_default(keys::wallet_pending_scheduled_payments())?;
+        let mut due = pending
+            .into_iter()
+            .map(|index| -> Result<_> {
+                let scheduled: model::ScheduledPayment =
+                    self.db.get(&keys::scheduled_payment(index))?;
+
+                Ok((index, scheduled, true))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for id in ids {
+            let mut payment: model::RecurringPayment = self.db.get(&keys::recurring_payment(id))?;
+            if !payment.is_due(epoch) {
+                continue;
+            }
+
+            let scheduled = model::ScheduledPayment {
+                recurring_payment_id: payment.id,
+                epoch: payment.next_epoch,
+                address: payment.address.clone(),
+                amount: payment.amount,
+                fee: payment.fee,
+                transaction_hash: None,
+            };
+            match payment
+                .next_epoch
+                .checked_add(payment.interval_epochs.max(1))
+            {
+                Some(next_epoch) => payment.next_epoch = next_epoch,
+                // there is no epoch for another payment, so this one is the last
+                None => payment.end_epoch = Some(payment.next_epoch - 1),
+            }
+            batch.put(keys::recurring_payment(id), &payment)?;
+            batch.put(keys::scheduled_payment(count), &scheduled)?;
+            due.push((count, scheduled, false));
+            count = count
+                .checked_add(1)
+                .ok_or_else(|| Error::RecurringPaymentIdOverflow)?;
+        }
+
+        batch.put(keys::wallet_scheduled_payments_count(), count)?;
+        batch.put(
+            keys::wallet_pending_scheduled_payments(),
+            BTreeSet::<u32>::new(),
+        )?;
+        batch.put(keys::wallet_schedules_last_epoch(), epoch)?;
+        self.write_batch(batch)?;
+        drop(lock);
+
+        Ok(due)
+    }
+
+    /// Record the hash of the transaction that made a payment generated by a recurring payment.
+    pub fn set_scheduled_payment_transaction(
+        &self,
+        index: u32,
+        transaction_hash: String,
+    ) -> Result<model::ScheduledPayment> {
+        let lock = self.schedules_mutex.lock()?;
+        let mut payment: model::ScheduledPayment = self.db.get(&keys::scheduled_payment(index))?;
+        payment.transaction_hash = Some(transaction_hash);
+        self.db.put(&keys::scheduled_payment(index), &payment)?;
+        drop(lock);
+
+        Ok(payment)
+    }
+
+    /// Keep a payment generated by a recurring payment pending, e.g. because its transaction
+    /// could not be created or the node did not accept it, so it is retried the next time the
+    /// recurring payments are run.
+    pub fn retry_scheduled_payment(&self, index: u32) -> Result<()> {
+        let lock = self.schedules_mutex.lock()?;
+        let mut pending: BTreeSet<u32> = self
+            .db
+            .get_or_default(keys::wallet_pending_scheduled_payments())?;
+        pending.insert(index);
+        self.db
+            .put(keys::wallet_pending_scheduled_payments(), &pending)?;
+        drop(lock);
+
+        Ok(())
+    }
+
+    /// Store the recurring payments and the payments they generated one per record, as older
+    /// versions kept all of them in a single record, and the interval of the recurring payments
+    /// along with the unit it was given in, as older versions only kept it in epochs.
+    ///
+    /// Must be called holding the schedules lock.
+    fn migrate_schedules(&self) -> Result<()> {
+        let legacy_payments: Option<BTreeMap<u32, model::LegacyRecurringPayment>> =
+            self.db.get_opt(keys::wallet_recurring_payments())?;
+        if let Some(legacy_payments) = legacy_payments {
+            let migrated = self
+                .db
+                .get_opt::<_, BTreeSet<u32>>(keys::wallet_legacy_recurring_payment_ids())?
+                .is_some()
+                || self
+                    .db
+                    .get_opt::<_, BTreeSet<u32>>(keys::wallet_recurring_payment_ids())?
+                    .is_some();
+            if !migrated {
+                let mut batch = self.db.batch();
+                for (id, payment) in legacy_payments.clone() {
+                    let payment = model::RecurringPayment::from(payment);
+                    batch.put(keys::recurring_payment(id), &payment)?;
+                }
+                let ids: BTreeSet<u32> = legacy_payments.keys().cloned().collect();
+                batch.put(keys::wallet_recurring_payment_ids(), ids)?;
+                self.write_batch(batch)?;
+            }
+            self.db.delete(keys::wallet_recurring_payments())?;
+        }
+
+        let legacy_ids: Option<BTreeSet<u32>> = self
+            .db
+            .get_opt(keys::wallet_legacy_recurring_payment_ids())?;
+        if let Some(legacy_ids) = legacy_ids {
+            let mut ids: BTreeSet<u32> = self
+                .db
+                .get_or_default(keys::wallet_recurring_payment_ids())?;
+            let mut batch = self.db.batch();
+            for id in &legacy_ids {
+                let legacy_payment: Option<model::LegacyRecurringPayment> =
+                    self.db.get_opt(&keys::legacy_recurring_payment(*id))?;
+                if let Some(legacy_payment) = legacy_payment {
+                    let payment = model::RecurringPayment::from(legacy_payment);
+                    batch.put(keys::recurring_payment(*id), &payment)?;
+                    ids.insert(*id);
+                }
+            }
+            batch.put(keys::wallet_recurring_payment_ids(), ids)?;
+            self.write_batch(batch)?;
+            for id in &legacy_ids {
+                self.db.delete(&keys::legacy_recurring_payment(*id))?;
+            }
+            self.db
+                .delete(keys::wallet_legacy_recurring_payment_ids())?;
+        }
+
+        let legacy_scheduled: Option<Vec<model::ScheduledPayment>> =
+            self.db.get_opt(keys::wallet_scheduled_payments())?;
+        if let Some(legacy_scheduled) = legacy_scheduled {
+            let migrated = self
+                .db
+                .get_opt::<_, u32>(keys::wallet_scheduled_payments_count())?
+                .is_some();
+            if !migrated {
+                let mut batch = self.db.batch();
+                for (index, payment) in legacy_scheduled.iter().enumerate() {
+                    batch.put(keys::scheduled_payment(index as u32), payment)?;
+                }
+                batch.put(
+                    keys::wallet_scheduled_payments_count(),
+                    legacy_scheduled.len() as u32,
+                )?;
+                self.write_batch(batch)?;
+            }
+            self.db.delete(keys::wallet_scheduled_payments())?;
+        }
+
+        Ok(())
+    }
+
+    /// Create a value transfer transaction paying `amount` to `address` from the unspent outputs
+    /// of the current account, signed with the keys of the wallet.
+    ///
+    /// The largest outputs not reserved by another transaction are spent first, and the change is
+    /// paid to the address chosen by `change_address`. The spent outputs are reserved until the
+    /// transaction is indexed, see [reserve_inputs](Wallet::reserve_inputs).
+    pub fn create_vtt(
+        &self,
+        address: &str,
+        amount: Balance,
+        fee: Balance,
+        change_address: types::ChangeAddressPolicy,
+        now: i64,
+    ) -> Result<types::VTTransaction> {
+        self.load()?;
+        let (_, data) = bech32::decode(address)?;
+        let recipient = pkh_from_bytes(&Vec::<u8>::from_base32(&data)?)?;
+        let needed = amount
+            .checked_add(fee)
+            .ok_or_else(|| Error::TransactionBalanceOverflow)?;
+
+        let snapshot = self.snapshot()?;
+        let mut utxos: Vec<(&Utxo, &Balance)> = {
+            let reserved = self.lock_wait(|| self.reserved.read())?;
+            snapshot
+                .utxo_set
+                .get(&snapshot.current_account)
+                .map(|account_utxo_set| {
+                    account_utxo_set
+                        .iter()
+                        .filter(|(utxo, _)| {
+                            reserved.get(*utxo).map_or(true, |expiry| *expiry <= now)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        utxos.sort_by(|(a_utxo, a_value), (b_utxo, b_value)| {
+            b_value.cmp(a_value).then_with(|| a_utxo.cmp(b_utxo))
+        });
+
+        let mut spent = Vec::new();
+        let mut available = Balance::ZERO;
+        for (utxo, value) in utxos {
+            if available >= needed {
+                break;
+            }
+            available = available.saturating_add(*value);
+            spent.push(utxo.clone());
+        }
+        if available < needed {
+            return Err(Error::InsufficientFunds(available, needed));
+        }
+
+        let mut outputs = vec![types::ValueTransferOutput {
+            pkh: recipient,
+            value: amount.into(),
+        }];
+        let change = available.saturating_sub(needed);
+        if !change.is_zero() {
+            let change_pkh: Pkh = match change_address {
+                types::ChangeAddressPolicy::Fresh => {
+                    self.gen_change_pkh(snapshot.current_account)?
+                }
+                types::ChangeAddressPolicy::Input => {
+                    let (txn_hash, output_index) = &spent[0];
+                    self.db.get(&keys::output_pkh(txn_hash, *output_index))?
+                }
+            };
+            // The change cannot be time-locked to the current epoch against fee sniping, as value
+            // transfer outputs have no `time_lock` in this version of the protocol
+            outputs.push(types::ValueTransferOutput {
+                pkh: pkh_from_bytes(&change_pkh)?,
+                value: change.into(),
+            });
+        }
+        let inputs: Vec<types::Input> = spent
+            .iter()
+            .map(|(txn_hash, output_index)| {
+                let mut hash = [0; 32];
+                hash.copy_from_slice(txn_hash);
+
+                types::Input::new(types::OutputPointer {
+                    transaction_id: types::Hash::SHA256(hash),
+                    output_index: *output_index,
+                })
+            })
+            .collect();
+
+        let body = types::VTTransactionBody::new(inputs.clone(), outputs);
+        let txn_hash = body.hash();
+        let signatures = self
+            .input_paths(&inputs)?
+            .iter()
+            .map(|path| {
+                let key = self.path_key(path)?;
+                let public_key = secp256k1::PublicKey::from_secret_key(&self.engine, &key);
+                let signature = signature::sign(key, txn_hash.as_ref());
+
+                Ok(types::KeyedSignature {
+                    signature: signature.into(),
+                    public_key: public_key.into(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.reserve_inputs(&inputs, now)?;
+
+        Ok(types::VTTransaction::new(body, signatures))
+    }
+
+    /// Derive the secret key of an address of the wallet from its derivation path, e.g.
+    /// `m/3'/4919'/0'/0/1`.
+    fn path_key(&self, path: &str) -> Result<types::SK> {
+        let invalid = || Error::InvalidKeyPath(path.to_string());
+        let mut levels = path.split('/');
+        if levels.next() != Some("m") {
+            return Err(invalid());
+        }
+        let keypath = levels.try_fold(types::KeyPath::default(), |keypath, level| {
+            let (index, hardened) = match level.trim_end_matches('\'') {
+                index if index.len() < level.len() => (index, true),
+                index => (index, false),
+            };
+            match index.parse::<u32>() {
+                Ok(index) if index < 0x8000_0000 && hardened => Ok(keypath.hardened(index)),
+                Ok(index) if index < 0x8000_0000 => Ok(keypath.index(index)),
+                _ => Err(invalid()),
+            }
+        })?;
+        let key = self.master_key()?.derive(&self.engine, &keypath)?;
+
+        Ok(key.into())
+    }
+
+    pub fn db_get(&self, key: &str) -> Result<Option<String>> {
+        let value = self.db.get_opt(&keys::custom(key))?;
+
+        Ok(value)
+    }
+
+    pub fn db_set(&self, key: &str, value: &str) -> Result<()> {
+        self.db.put(&keys::custom(key), value)?;
+
+        Ok(())
+    }
+
+    /// Count the records stored by the wallet and the bytes they take, by key prefix.
+    pub fn storage_stats(&self) -> Result<model::StorageStats> {
+        let mut stats = model::StorageStats::default();
+        for (key, size) in self.db.records()? {
+            let size = size as u64;
+            let prefix = stats.prefixes.entry(key_prefix(&key)).or_default();
+            prefix.records += 1;
+            prefix.bytes += size;
+            stats.records += 1;
+            stats.bytes += size;
+        }
+
+        Ok(stats)
+    }
+
+    /// Delete the records superseded by the current state of the wallet, and reclaim the space
+    /// they took in the database.
+    pub fn compact(&self) -> Result<model::StorageCompaction> {
+        let pruned = self.prune()?;
+        self.db.compact()?;
+
+        Ok(model::StorageCompaction {
+            pruned,
+            stats: self.storage_stats()?,
+        })
+    }
+
+    /// Delete the records superseded by the current state of the wallet, returning how many were
+    /// deleted. It is done every time the indexed state is reset:
+    /// - the transactions indexed before the last reset of the indexed state,
+    /// - the acknowledged deposits,
+    /// - the sync cursors of the chains the wallet is no longer synced with.
+    fn prune(&self) -> Result<u32> {
+        self.load()?;
+        let generation = *self.index_generation.read()?;
+        let deposits_cursor = *self.deposits_cursor.read()?;
+        let sync_cursor = self.network.read()?.as_ref().map(|network| {
+            keys::wallet_sync_cursor(&network.network, &network.genesis_hash.to_string())
+        });
+
+        let mut pruned = 0;
+        for (key, _) in self.db.records()? {
+            let superseded = match std::str::from_utf8(&key) {
+                Ok(key) => is_superseded(key, generation, deposits_cursor, sync_cursor.as_ref()),
+                Err(_) => false,
+            };
+            if superseded {
+                self.db.delete(&key)?;
+                pruned += 1;
+            }
+        }
+
+        Ok(pruned)
+    }
+
+    /// Index the transactions of a block, updating the UTXO set and balances of the wallet and
+    /// recording its movements.
+    ///
+    /// Transactions that were already indexed, e.g. because the node notified the same block
+    /// twice, are skipped.
+    pub fn index_txns(&self, txns: &types::BlockTransactions) -> Result<()> {
+        self.load()?;
+        // The blocks of a reset chain would be mixed with the ones of the old chain
+        if self.lock_wait(|| self.chain_reset.read())?.is_some() {
+            return Ok(());
+        }
+
+        self.params
+            .metrics
+            .block_index
+            .time(|| self.index_block_txns(txns))
+    }
+
+    fn index_block_txns(&self, txns: &types::BlockTransactions) -> Result<()> {
+        let mut batch = self.db.batch();
+        let epoch = txns.epoch;
+        let generation = *self.lock_wait(|| self.index_generation.read())?;
+        // a block notified again, e.g. after a restart, does not add to the height of the chain
+        let last_height: u32 = self.db.get_or_default(keys::wallet_indexed_height())?;
+        let new_block = self
+            .last_indexed_epoch()?
+            .map_or(true, |last_epoch| last_epoch < epoch);
+        let block = BlockPosition {
+            epoch,
+            height: if new_block {
+                last_height.saturating_add(1)
+            } else {
+                last_height
+            },
+        };
+        let mut pkh_movements = HashMap::new();
+        let mut pkh_receipts = HashMap::new();
+        let mut block_movements = BlockMovements::default();
+        let mut indexed = false;
+
+        for txn in &txns.value_transfer {
+            let txn_hash = txn.hash().as_ref().to_vec();
+            if self.is_indexed(generation, &txn_hash)? {
+                continue;
+            }
+
+            let spent = self.index_inputs(
+                &mut batch,
+                &mut block_movements,
+                &txn_hash,
+                &txn.inputs,
+                epoch,
+            )?;
+            let credited = self.index_outputs(
+                &mut batch,
+                &mut block_movements,
+                &txn_hash,
+                &txn.outputs,
+                "credit",
+                None,
+                block,
+            )?;
+            if let Some((account_index, txn_id)) = spent.debits.last().cloned() {
+                if spent.count == txn.inputs.len() {
+                    let fee = spent.value.saturating_sub(outputs_value(&txn.outputs));
+                    batch.put(&keys::transaction_fee(account_index, txn_id), fee)?;
+                }
+            }
+            if let Some(counterparty) = self.counterparty(&txn.outputs, &spent)? {
+                for (account_index, txn_id) in &spent.debits {
+                    batch.put(
+                        &keys::transaction_counterparty(*account_index, *txn_id),
+                        &counterparty,
+                    )?;
+                }
+            }
+            if spent.count > 0 || spent.watched || credited.any() {
+                batch.put(&keys::indexed_transaction(generation, &txn_hash), ())?;
+                indexed = true;
+                put_movements(
+                    &mut batch,
+                    &mut pkh_movements,
+                    generation,
+                    &txn_hash,
+                    &spent,
+                    &credited,
+                )?;
+                collect_receipts(&mut pkh_receipts, &credited, epoch);
+            }
+        }
+
+        for txn in &txns.data_requests {
+            let txn_hash = txn.hash().as_ref().to_vec();
+            if self.is_indexed(generation, &txn_hash)? {
+                continue;
+            }
+
+            // Remember the data requests created by this wallet, so their tally change can be
+            // told apart from witness rewards
+            let spent = self.index_inputs(
+                &mut batch,
+                &mut block_movements,
+                &txn_hash,
+                &txn.inputs,
+                epoch,
+            )?;
+            if let Some((account_index, txn_id)) = spent.debits.last().cloned() {
+                batch.put(&keys::own_data_request(&txn_hash), ())?;
+
+                let dr_value = types::Nanowits::from(txn.dr_output.value);
+                batch.put(&keys::transaction_dr_value(account_index, txn_id), dr_value)?;
+                if spent.count == txn.inputs.len() {
+                    let fee = spent
+                        .value
+                        .saturating_sub(outputs_value(&txn.outputs))
+                        .saturating_sub(dr_value);
+                    batch.put(&keys::transaction_fee(account_index, txn_id), fee)?;
+                }
+            }
+            let credited = self.index_outputs(
+                &mut batch,
+                &mut block_movements,
+                &txn_hash,
+                &txn.outputs,
+                "credit",
+                None,
+                block,
+            )?;
+            if spent.count > 0 || spent.watched || credited.any() {
+                batch.put(&keys::indexed_transaction(generation, &txn_hash), ())?;
+                indexed = true;
+                put_movements(
+                    &mut batch,
+                    &mut pkh_movements,
+                    generation,
+                    &txn_hash,
+                    &spent,
+                    &credited,
+                )?;
+                collect_receipts(&mut pkh_receipts, &credited, epoch);
+            }
+        }
+
+        for txn in &txns.tallies {
+            let txn_hash = txn.hash().as_ref().to_vec();
+            if self.is_indexed(generation, &txn_hash)? {
+                continue;
+            }
+            let dr_hash = txn.dr_pointer.as_ref().to_vec();
+            let kind = match self
+                .db
+                .get_opt::<_, ()>(&keys::own_data_request(&dr_hash))?
+            {
+                Some(()) => "refund",
+                None => "witness_reward",
+            };
+
+            let credited = self.index_outputs(
+                &mut batch,
+                &mut block_movements,
+                &txn_hash,
+                &txn.outputs,
+                kind,
+                Some(&dr_hash),
+                block,
+            )?;
+            if credited.any() {
+                batch.put(&keys::indexed_transaction(generation, &txn_hash), ())?;
+                indexed = true;
+                put_movements(
+                    &mut batch,
+                    &mut pkh_movements,
+                    generation,
+                    &txn_hash,
+                    &SpentOutputs::default(),
+                    &credited,
+                )?;
+                collect_receipts(&mut pkh_receipts, &credited, epoch);
+            }
+        }
+
+        // persist modified utxo set
+        let utxo_set_guard = self.lock_wait(|| self.utxo_set.read())?;
+        let utxo_set = utxo_set_guard.deref();
+        self.db.put(keys::wallet_utxo_set(), utxo_set)?;
+
+        // persist modified balances
+        let account_balances_guard = self.lock_wait(|| self.account_balances.read())?;
+        let account_balances = account_balances_guard.deref();
+        self.db
+            .put(keys::wallet_account_balances(), account_balances)?;
+
+        // persist modified number of payments per pkh
+        let pkh_payments_guard = self.lock_wait(|| self.pkh_payments.read())?;
+        let pkh_payments = pkh_payments_guard.deref();
+        self.db.put(keys::wallet_pkh_payments(), pkh_payments)?;
+
+        // persist modified balances of the watched addresses along with their movements
+        let watched_guard = self.lock_wait(|| self.watched.read())?;
+        let watched = watched_guard.deref();
+        batch.put(keys::wallet_watched_addresses(), watched)?;
+        for ((txn_hash, output_index), output) in block_movements.watched_outputs {
+            batch.put(&keys::watched_output(&txn_hash, output_index), output)?;
+        }
+        if !block_movements.watched_transactions.is_empty() {
+            let mut transactions = self.watched_transactions()?;
+            transactions.extend(block_movements.watched_transactions);
+            batch.put(keys::wallet_watched_transactions(), transactions)?;
+        }
+
+        // persist the sequence number of the last deposit along with the deposits
+        let deposits_last_seq = *self.lock_wait(|| self.deposits_last_seq.read())?;
+        batch.put(keys::wallet_deposits_last_seq(), deposits_last_seq)?;
+        if new_block {
+            batch.put(keys::wallet_indexed_height(), block.height)?;
+        }
+
+        // persist modified transactions count per account
+        let transactions_count_guard = self.lock_wait(|| self.transactions_count.read())?;
+        let transactions_count = transactions_count_guard.deref();
+        self.db
+            .put(keys::wallet_transactions_count(), transactions_count)?;
+
+        // persist the position of the wallet in the chain of the network it is synced with
+        if let Some(network) = self.lock_wait(|| self.network.read())?.as_ref() {
+            let cursor_key =
+                keys::wallet_sync_cursor(&network.network, &network.genesis_hash.to_string());
+            let cursor: Option<types::SyncCursor> = self.db.get_opt(&cursor_key)?;
+            if cursor.map_or(true, |cursor| cursor.epoch <= epoch) {
+                batch.put(
+                    &cursor_key,
+                    types::SyncCursor {
+                        epoch,
+                        block_hash: txns.block_hash,
+                    },
+                )?;
+            }
+        }
+
+        // persist the movements of the addresses along with the transactions, one per key, so
+        // the ones already recorded are not written again
+        for (pkh, movements) in pkh_movements {
+            let count_key = keys::pkh_movements_count(generation, &pkh);
+            let mut count: u32 = self.db.get_or_default(&count_key)?;
+            for movement in movements {
+                batch.put(&keys::pkh_movement(generation, &pkh, count), movement)?;
+                count = count.saturating_add(1);
+            }
+            batch.put(&count_key, count)?;
+        }
+
+        // persist the payments received by the addresses along with the transactions
+        for (pkh, receipts) in pkh_receipts {
+            let key = keys::pkh_receipts(generation, &pkh);
+            let indexed: Option<AddressReceipts> = self.db.get_opt(&key)?;
+            let receipts = indexed.map_or(receipts, |indexed| indexed.merge(receipts));
+            batch.put(&key, receipts)?;
+        }
+
+        // persist transactions
+        self.write_batch(batch)?;
+
+        // the queries only see the block once it has been persisted
+        if indexed {
+            self.publish_snapshot()?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether a transaction moving funds of the wallet or of a watched address has already been
+    /// indexed.
+    fn is_indexed(&self, generation: u32, txn_hash: &[u8]) -> Result<bool> {
+        let indexed = self
+            .db
+            .get_opt::<_, ()>(&keys::indexed_transaction(generation, txn_hash))?
+            .is_some();
+
+        Ok(indexed)
+    }
+
+    /// Remove the wallet UTXOs spent by the given inputs, recording a debit for each of them.
+    fn index_inputs(
+        &self,
+        batch: &mut T::WriteBatch,
+        block_movements: &mut BlockMovements,
+        txn_hash: &[u8],
+        inputs: &[types::Input],
+        epoch: u32,
+    ) -> Result<SpentOutputs> {
+        let mut spent = SpentOutputs::default();
+
+        for input in inputs {
+            let p = input.output_pointer();
+            let pointed_txn_hash = p.transaction_id.as_ref().to_vec();
+            let pointed_output_index = p.output_index;
+
+            if let Some((account_index, pkh)) = self.wallet_output(
+                block_movements,
+                pointed_txn_hash.clone(),
+                pointed_output_index,
+            )? {
+                let utxo_key = (pointed_txn_hash, pointed_output_index);
+
+                // remove the UTXO from the utxo set
+                let mut utxo_set = self.lock_wait(|| self.utxo_set.write())?;
+                let value = match Arc::make_mut(&mut utxo_set)
+                    .get_mut(&account_index)
+                    .and_then(|account_utxo_set| account_utxo_set.remove(&utxo_key))
+                {
+                    Some(value) => value,
+                    None => Err(Error::NoUtxoForInput)?,
+                };
+                drop(utxo_set);
+
+                // the UTXO is spent, so its reservation is no longer needed
+                self.lock_wait(|| self.reserved.write())?.remove(&utxo_key);
+
+                // record transaction for this account
+                let txn_id = self.next_transaction_id(account_index)?;
+                batch.put(&keys::transaction_value(account_index, txn_id), value)?;
+                batch.put(&keys::transaction_type(account_index, txn_id), "debit")?;
+                batch.put(&keys::transaction_epoch(account_index, txn_id), epoch)?;
+                batch.put(
+                    &keys::transaction_hash(account_index, txn_id),
+                    hex::encode(txn_hash),
+                )?;
+
+                // update balance
+                self.update_account_balance(account_index, value, BalanceOp::Sub)?;
+                spent.count += 1;
+                spent.value = spent.value.saturating_add(value);
+                spent.debits.push((account_index, txn_id));
+                // outputs indexed before their pkh was recorded are not in the address index
+                if let Some(pkh) = pkh {
+                    spent.addresses.push((pkh, (account_index, txn_id)));
+                }
+            } else if let Some((pkh, value)) =
+                self.watched_output(block_movements, pointed_txn_hash, pointed_output_index)?
+            {
+                self.index_watched_movement(
+                    block_movements,
+                    &pkh,
+                    txn_hash,
+                    value,
+                    model::TransactionKind::Debit,
+                )?;
+                spent.watched = true;
+            }
+        }
+
+        Ok(spent)
+    }
+
+    /// Add the outputs paying to this wallet to its UTXO set, recording a transaction of the
+    /// given kind for each of them, and a deposit for each output paying to a deposit address
+    /// that was not reported yet.
+    ///
+    /// Returns the transactions recorded for the outputs paying to this wallet, and whether any
+    /// of the outputs paid to a watched or a deposit address.
+    fn index_outputs(
+        &self,
+        batch: &mut T::WriteBatch,
+        block_movements: &mut BlockMovements,
+        txn_hash: &[u8],
+        outputs: &[types::ValueTransferOutput],
+        kind: &str,
+        dr_hash: Option<&[u8]>,
+        block: BlockPosition,
+    ) -> Result<CreditedOutputs> {
+        let epoch = block.epoch;
+        let mut credited = CreditedOutputs::default();
+
+        for (output_index, output) in outputs.iter().enumerate() {
+            let pkh = output.pkh.as_ref();
+            let value = types::Nanowits::from(output.value);
+
+            let deposit_address = self
+                .lock_wait(|| self.deposit_addresses.read())?
+                .get(pkh)
+                .cloned();
+            // the same output may be notified again after a rollback or a reset of the indexed
+            // state, and must not be reported, and credited by the client, twice
+            let output_key = keys::deposit_output(txn_hash, output_index as u32);
+            let reported = self.db.get_opt::<_, ()>(&output_key)?.is_some();
+            if let (Some(address), false) = (deposit_address, reported) {
+                let seq = self.next_deposit_seq()?;
+                let deposit = model::Deposit {
+                    seq,
+                    address,
+                    transaction_hash: hex::encode(txn_hash),
+                    output_index: output_index as u32,
+                    value,
+                    epoch,
+                    confirmations: 0,
+                };
+                batch.put(&keys::deposit(seq), deposit)?;
+                batch.put(&keys::deposit_height(seq), block.height)?;
+                batch.put(&output_key, ())?;
+                credited.deposits = true;
+            }
+
+            if let Some(account_index) = self.lock_wait(|| self.pkhs.read())?.get(pkh).cloned() {
+                // add UTXO to the utxo set
+                let mut utxo_set = self.lock_wait(|| self.utxo_set.write())?;
+                Arc::make_mut(&mut utxo_set)
+                    .entry(account_index)
+                    .or_default()
+                    .insert((txn_hash.to_vec(), output_index as u32), value);
+                drop(utxo_set);
+                credited.received.push((pkh.to_vec(), value));
+
+                // record transaction for this account
+                let txn_id = self.next_transaction_id(account_index)?;
+                batch.put(&keys::transaction_value(account_index, txn_id), value)?;
+                batch.put(&keys::transaction_type(account_index, txn_id), kind)?;
+                batch.put(&keys::transaction_epoch(account_index, txn_id), epoch)?;
+                batch.put(
+                    &keys::transaction_hash(account_index, txn_id),
+                    hex::encode(txn_hash),
+                )?;
+                if let Some(dr_hash) = dr_hash {
+                    batch.put(
+                        &keys::transaction_dr_hash(account_index, txn_id),
+                        hex::encode(dr_hash),
+                    )?;
+                }
+
+                batch.put(
+                    &keys::transaction_output_recipient(txn_hash, output_index as u32),
+                    account_index,
+                )?;
+                batch.put(
+                    &keys::output_pkh(txn_hash, output_index as u32),
+                    pkh.to_vec(),
+                )?;
+                if let Some(path) = self.db.get_opt::<_, String>(&keys::pkh_path(pkh))? {
+                    batch.put(&keys::output_path(txn_hash, output_index as u32), path)?;
+                }
+                block_movements.wallet_outputs.insert(
+                    (txn_hash.to_vec(), output_index as u32),
+                    (account_index, pkh.to_vec()),
+                );
+
+                // count the payment received by the address
+                let mut pkh_payments = self.lock_wait(|| self.pkh_payments.write())?;
+                let payments = Arc::make_mut(&mut pkh_payments)
+                    .entry(pkh.to_vec())
+                    .or_default();
+                *payments = payments.saturating_add(1);
+                drop(pkh_payments);
+
+                // update balance
+                self.update_account_balance(account_index, value, BalanceOp::Add)?;
+                credited.credits.push((account_index, txn_id));
+                credited
+                    .addresses
+                    .push((pkh.to_vec(), (account_index, txn_id)));
+            } else if self.lock_wait(|| self.watched.read())?.contains_key(pkh) {
+                block_movements.watched_outputs.insert(
+                    (txn_hash.to_vec(), output_index as u32),
+                    (pkh.to_vec(), value),
+                );
+                self.index_watched_movement(
+                    block_movements,
+                    pkh,
+                    txn_hash,
+                    value,
+                    model::TransactionKind::Credit,
+                )?;
+                credited.watched = true;
+            }
+        }
+
+        Ok(credited)
+    }
+
+    /// Address paid by a transaction spending outputs of the wallet: the first of its outputs not
+    /// paying to the wallet, if any.
+    fn counterparty(
+        &self,
+        outputs: &[types::ValueTransferOutput],
+        spent: &SpentOutputs,
+    ) -> Result<Option<String>> {
+        if spent.debits.is_empty() {
+            return Ok(None);
+        }
+        let pkhs = self.lock_wait(|| self.pkhs.read())?;
+        let pkh = match outputs
+            .iter()
+            .map(|output| output.pkh.as_ref())
+            .find(|pkh| !pkhs.contains_key(*pkh))
+        {
+            Some(pkh) => pkh,
+            None => return Ok(None),
+        };
+        let address = bech32::encode(
+            if self.params.testnet { "twit" } else { "wit" },
+            pkh.to_base32(),
+        )?;
+
+        Ok(Some(address))
+    }
+
+    /// Retrieve the account and pkh of an output paying to the wallet, either indexed from a
+    /// previous block or from the block being indexed. The pkh is unknown for the outputs indexed
+    /// before it was recorded.
+    fn wallet_output(
+        &self,
+        block_movements: &BlockMovements,
+        txn_hash: Vec<u8>,
+        output_index: u32,
+    ) -> Result<Option<(AccountIndex, Option<Pkh>)>> {
+        let key = (txn_hash, output_index);
+        if let Some((account_index, pkh)) = block_movements.wallet_outputs.get(&key) {
+            return Ok(Some((*account_index, Some(pkh.clone()))));
+        }
+
+        let account_index = self
+            .db
+            .get_opt::<_, u32>(&keys::transaction_output_recipient(&key.0, key.1))?;
+        match account_index {
+            Some(account_index) => {
+                let pkh = self.db.get_opt(&keys::output_pkh(&key.0, key.1))?;
+
+                Ok(Some((account_index, pkh)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the pkh and value of an output paying to a watched address, either indexed from a
+    /// previous block or from the block being indexed.
+    fn watched_output(
+        &self,
+        block_movements: &BlockMovements,
+        txn_hash: Vec<u8>,
+        output_index: u32,
+    ) -> Result<Option<(Pkh, Balance)>> {
+        let key = (txn_hash, output_index);
+        if let Some(output) = block_movements.watched_outputs.get(&key) {
+            return Ok(Some(output.clone()));
+        }
+
+        let output = self.db.get_opt(&keys::watched_output(&key.0, key.1))?;
+
+        Ok(output)
+    }
+
+    /// Update the balance of a watched address, recording the transaction that moved its funds.
+    ///
+    /// Watched addresses are not part of the wallet, so neither its balance nor its UTXO set are
+    /// modified.
+    fn index_watched_movement(
+        &self,
+        block_movements: &mut BlockMovements,
+        pkh: &[u8],
+        txn_hash: &[u8],
+        value: Balance,
+        kind: model::TransactionKind,
+    ) -> Result<()> {
+        let mut watched = self.lock_wait(|| self.watched.write())?;
+        let watched_address = match watched.get_mut(pkh) {
+            Some(watched_address) => watched_address,
+            // the address is not being watched anymore
+            None => return Ok(()),
+        };
+        watched_address.balance = match kind {
+            model::TransactionKind::Debit => watched_address.balance.saturating_sub(value),
+            _ => watched_address
+                .balance
+                .checked_add(value)
+                .ok_or_else(|| Error::TransactionBalanceOverflow)?,
+        };
+        let address = watched_address.address.clone();
+        drop(watched);
+
+        block_movements
+            .watched_transactions
+            .push(model::Transaction {
+                hash: hex::encode(txn_hash),
+                value,
+                kind,
+                counterparty: None,
+                contact: None,
+                dr_hash: None,
+                watch_only: true,
+                watched_address: Some(address),
+            });
+
+        Ok(())
+    }
+
+    /// Retrieve the balance for the current wallet account.
+    pub fn balance(&self) -> Result<(AccountIndex, Balance)> {
+        self.load()?;
+        let snapshot = self.snapshot()?;
+        let account = snapshot.current_account;
+        let balance = snapshot
+            .account_balances
+            .get(&account)
+            .cloned()
+            .unwrap_or_default();
+
+        Ok((account, balance))
+    }
+
+    /// Aggregate the movements of the current wallet account that were indexed from blocks of
+    /// epochs in `[from_epoch, to_epoch)`.
+    ///
+    /// Movements indexed before their epoch was recorded are counted as previous to any period.
+    /// The movements of each transaction are netted, so the change paid back to the wallet by its
+    /// own transactions is neither received nor sent.
+    pub fn statement(&self, from_epoch: u32, to_epoch: u32) -> Result<model::Statement> {
+        self.load()?;
+        let snapshot = self.snapshot()?;
+        let account = snapshot.current_account;
+        let count = snapshot
+            .transactions_count
+            .get(&account)
+            .cloned()
+            .unwrap_or_default();
+        let mut statement = model::Statement {
+            account,
+            from_epoch,
+            to_epoch,
+            ..model::Statement::default()
+        };
+
+        // Map transaction hash -> (value received, value spent) during the period
+        let mut transactions: HashMap<String, (Balance, Balance)> = HashMap::new();
+        for id in 0..count {
+            let epoch: u32 = self
+                .db
+                .get_or_default(&keys::transaction_epoch(account, id))?;
+            if epoch >= to_epoch {
+                continue;
+            }
+            let value: Balance = self.db.get(&keys::transaction_value(account, id))?;
+            let kind: String = self.db.get(&keys::transaction_type(account, id))?;
+
+            if epoch < from_epoch {
+                statement.opening_balance = match kind.as_str() {
+                    "debit" => statement.opening_balance.saturating_sub(value),
+                    _ => statement.opening_balance.saturating_add(value),
+                };
+                continue;
+            }
+
+            statement.movements += 1;
+            let hash: String = self.db.get(&keys::transaction_hash(account, id))?;
+            let (received, spent) = transactions.entry(hash).or_default();
+            match kind.as_str() {
+                "debit" => {
+                    *spent = spent.saturating_add(value);
+                    let fee: Balance = self
+                        .db
+                        .get_or_default(&keys::transaction_fee(account, id))?;
+                    let dr_value: Balance = self
+                        .db
+                        .get_or_default(&keys::transaction_dr_value(account, id))?;
+                    statement.fees_paid = statement.fees_paid.saturating_add(fee);
+                    statement.data_request_spend =
+                        statement.data_request_spend.saturating_add(dr_value);
+                }
+                "witness_reward" => {
+                    *received = received.saturating_add(value);
+                    statement.witness_rewards = statement.witness_rewards.saturating_add(value);
+                }
+                _ => {
+                    *received = received.saturating_add(value);
+                }
+            }
+        }
+
+        for (received, spent) in transactions.values() {
+            if received >= spent {
+                statement.total_in = statement
+                    .total_in
+                    .saturating_add(received.saturating_sub(*spent));
+            } else {
+                statement.total_out = statement
+                    .total_out
+                    .saturating_add(spent.saturating_sub(*received));
+            }
+        }
+
+        statement.closing_balance = statement
+            .opening_balance
+            .saturating_add(statement.total_in)
+            .saturating_sub(statement.total_out);
+
+        Ok(statement)
+    }
+
+    /// Retrieve the movements of the wallet that paid to or spent from an address, newest first,
+    /// using the index of movements by address.
+    ///
+    /// Movements indexed before the index existed are not returned.
+    pub fn address_transactions(
+        &self,
+        address: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::Transactions> {
+        self.load()?;
+        let ids = self.address_movements(address)?;
+        let total = ids.len() as u32;
+
+        let transactions = ids
+            .into_iter()
+            .rev()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|(account, id)| self.transaction(account, id))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(model::Transactions {
+            transactions,
+            total,
+        })
+    }
+
+    /// Retrieve the movements of the current account of the wallet, newest first, with their
+    /// kind and the data request of the witness rewards and refunds.
+    pub fn transactions(&self, offset: u32, limit: u32) -> Result<model::Transactions> {
+        self.load()?;
+        let snapshot = self.snapshot()?;
+        let account = snapshot.current_account;
+        let total = snapshot
+            .transactions_count
+            .get(&account)
+            .cloned()
+            .unwrap_or_default();
+
+        let transactions = (0..total)
+            .rev()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|id| self.transaction(account, id))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(model::Transactions {
+            transactions,
+            total,
+        })
+    }
+
+    /// Search the movements matching a filter, newest first.
+    ///
+    /// Movements filtered by address are looked up in the index of movements by address, so they
+    /// can belong to any account. Otherwise, the movements of the current account are scanned.
+    pub fn search_movements(
+        &self,
+        filter: &model::MovementFilter,
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::Movements> {
+        self.load()?;
+        let ids = match &filter.address {
+            Some(address) => self.address_movements(address)?,
+            None => {
+                let snapshot = self.snapshot()?;
+                let account = snapshot.current_account;
+                let count = snapshot
+                    .transactions_count
+                    .get(&account)
+                    .cloned()
+                    .unwrap_or_default();
+
+                (0..count).map(|id| (account, id)).collect()
+            }
+        };
+
+        let mut movements = vec![];
+        for (account, id) in ids.into_iter().rev() {
+            let movement = self.movement(account, id)?;
+            let matches = filter
+                .kind
+                .as_ref()
+                .map_or(true, |kind| *kind == movement.kind)
+                && filter
+                    .from_epoch
+                    .map_or(true, |from_epoch| movement.epoch >= from_epoch)
+                && filter
+                    .to_epoch
+                    .map_or(true, |to_epoch| movement.epoch < to_epoch);
+            if matches {
+                movements.push(movement);
+            }
+        }
+        let total = movements.len() as u32;
+
+        Ok(model::Movements {
+            movements: movements
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect(),
+            total,
+        })
+    }
+
+    /// Account and id of the movements recorded for the outputs paying to or spent from an
+    /// address, in the order they were indexed.
+    fn address_movements(&self, address: &str) -> Result<Vec<(AccountIndex, TransactionId)>> {
+        let (_, data) = bech32::decode(address)?;
+        let pkh = Vec::<u8>::from_base32(&data)?;
+        let generation = self.snapshot()?.index_generation;
+        // the movements recorded before they were stored one per key come first
+        let mut movements: Vec<(AccountIndex, TransactionId)> = self
+            .db
+            .get_or_default(&keys::pkh_movements(generation, &pkh))?;
+        let count: u32 = self
+            .db
+            .get_or_default(&keys::pkh_movements_count(generation, &pkh))?;
+        for n in 0..count {
+            movements.push(self.db.get(&keys::pkh_movement(generation, &pkh, n))?);
+        }
+
+        Ok(movements)
+    }
+
+    fn transaction(&self, account: AccountIndex, id: TransactionId) -> Result<model::Transaction> {
+        let movement = self.movement(account, id)?;
+
+        Ok(model::Transaction {
+            hash: movement.hash.unwrap_or_default(),
+            value: movement.value,
+            kind: movement.kind,
+            counterparty: self
+                .db
+                .get_opt(&keys::transaction_counterparty(account, id))?,
+            contact: None,
+            dr_hash: self.db.get_opt(&keys::transaction_dr_hash(account, id))?,
+            watch_only: false,
+            watched_address: None,
+        })
+    }
+
+    fn movement(&self, account: AccountIndex, id: TransactionId) -> Result<model::Movement> {
+        let kind: String = self.db.get(&keys::transaction_type(account, id))?;
+
+        Ok(model::Movement {
+            account,
+            id,
+            hash: self.db.get_opt(&keys::transaction_hash(account, id))?,
+            kind: transaction_kind(&kind),
+            value: self.db.get(&keys::transaction_value(account, id))?,
+            epoch: self
+                .db
+                .get_or_default(&keys::transaction_epoch(account, id))?,
+        })
+    }
+
+    /// Sync the wallet with the chain of the network of its node.
+    ///
+    /// Returns the epoch from which the blocks of the chain have to be indexed again: the epoch
+    /// after the last indexed block if the wallet was already synced with this network, or the
+    /// genesis epoch if it was synced with another one, in which case the indexed state is reset.
+    /// Wallets that were never synced with a network adopt it without indexing any block again.
+    ///
+    /// If the network is the same but its genesis block is not, the chain has been reset: the
+    /// indexed state is kept, and no block is indexed, until the client decides to reset it with
+    /// [reset_chain_data](Wallet::reset_chain_data).
+    pub fn sync_network(&self, network: &types::NetworkId) -> Result<types::NetworkSync> {
+        self.load()?;
+        let previous: Option<types::NetworkId> = self.db.get_opt(keys::wallet_network())?;
+
+        match previous {
+            Some(ref previous)
+                if previous.network == network.network
+                    && previous.genesis_hash != network.genesis_hash =>
+            {
+                log::warn!(
+                    "Wallet indexed the chain of {} but its node belongs to {}, which has been \
+                     reset, waiting for the client to reset its chain data",
+                    previous,
+                    network
+                );
+                *self.network.write()? = None;
+                *self.chain_reset.write()? = Some(network.clone());
+
+                return Ok(types::NetworkSync::ChainReset(previous.clone()));
+            }
+            _ => {
+                *self.chain_reset.write()? = None;
+            }
+        }
+
+        let mut reset = false;
+        let rescan_from = match previous {
+            None => {
+                self.db.put(keys::wallet_network(), network)?;
+
+                None
+            }
+            Some(ref previous) if previous == network => {
+                let cursor: Option<types::SyncCursor> = self.db.get_opt(
+                    &keys::wallet_sync_cursor(&network.network, &network.genesis_hash.to_string()),
+                )?;
+
+                Some(cursor.map_or(0, |cursor| cursor.epoch.saturating_add(1)))
+            }
+            Some(previous) => {
+                log::warn!(
+                    "Wallet indexed the chain of {} but its node belongs to {}, indexing it again",
+                    previous,
+                    network
+                );
+                self.reset_indexed_state()?;
+                self.db.put(keys::wallet_network(), network)?;
+                reset = true;
+
+                Some(0)
+            }
+        };
+
+        *self.network.write()? = Some(network.clone());
+        // Once the network is set, the cursors of the previous one are superseded too
+        if reset {
+            self.prune()?;
+        }
+
+        Ok(types::NetworkSync::Rescan(rescan_from))
+    }
+
+    /// Forget the state indexed from the chain of a network that has been reset, so the chain of
+    /// the node can be indexed instead.
+    ///
+    /// Returns the epoch from which the blocks of the chain have to be indexed.
+    pub fn reset_chain_data(&self) -> Result<u32> {
+        let network = self
+            .chain_reset
+            .read()?
+            .clone()
+            .ok_or_else(|| Error::NoChainReset)?;
+        self.load()?;
+        self.reset_indexed_state()?;
+        self.db.put(keys::wallet_network(), &network)?;
+
+        *self.network.write()? = Some(network);
+        *self.chain_reset.write()? = None;
+        self.prune()?;
+
+        Ok(0)
+    }
+
+    /// Forget the utxo set, balances and movements indexed from the blocks of a chain, so the
+    /// blocks of another chain can be indexed instead.
+    fn reset_indexed_state(&self) -> Result<()> {
+        let mut index_generation = self.index_generation.write()?;
+        let mut utxo_set = self.utxo_set.write()?;
+        let mut reserved = self.reserved.write()?;
+        let mut account_balances = self.account_balances.write()?;
+        let mut transactions_count = self.transactions_count.write()?;
+        let mut pkh_payments = self.pkh_payments.write()?;
+        let mut watched = self.watched.write()?;
+
+        *index_generation = index_generation.saturating_add(1);
+        *utxo_set = Arc::default();
+        reserved.clear();
+        *account_balances = Arc::default();
+        *transactions_count = Arc::default();
+        *pkh_payments = Arc::default();
+        for watched_address in watched.values_mut() {
+            watched_address.balance = Balance::ZERO;
+        }
+
+        let mut batch = self.db.batch();
+        batch.put(keys::wallet_index_generation(), *index_generation)?;
+        batch.put(keys::wallet_indexed_height(), 0u32)?;
+        batch.put(keys::wallet_utxo_set(), &*utxo_set)?;
+        batch.put(keys::wallet_account_balances(), &*account_balances)?;
+        batch.put(keys::wallet_transactions_count(), &*transactions_count)?;
+        batch.put(keys::wallet_pkh_payments(), &*pkh_payments)?;
+        batch.put(keys::wallet_watched_addresses(), &*watched)?;
+        batch.put(
+            keys::wallet_watched_transactions(),
+            Vec::<model::Transaction>::new(),
+        )?;
+        self.write_batch(batch)?;
+        drop((
+            index_generation,
+            utxo_set,
+            reserved,
+            account_balances,
+            transactions_count,
+            pkh_payments,
+            watched,
+        ));
+
+        self.publish_snapshot()?;
+
+        Ok(())
+    }
+
+    /// Annotate the transactions of a block that moved funds of the wallet, in the order given.
+    /// Transactions indexed before their movements were recorded by hash are not annotated.
+    pub fn block_transactions(
+        &self,
+        txn_hashes: &[types::Hash],
+    ) -> Result<Vec<model::BlockTransaction>> {
+        self.load()?;
+        let generation = *self.index_generation.read()?;
+        let mut transactions = vec![];
+
+        for txn_hash in txn_hashes {
+            let movements: Vec<(AccountIndex, TransactionId)> = match self
+                .db
+                .get_opt(&keys::transaction_movements(generation, txn_hash.as_ref()))?
+            {
+                Some(movements) => movements,
+                None => continue,
+            };
+
+            let mut direction = model::TransactionDirection::Incoming;
+            for (account, id) in &movements {
+                let kind: String = self.db.get(&keys::transaction_type(*account, *id))?;
+                if kind == "debit" {
+                    direction = model::TransactionDirection::Outgoing;
+                }
+            }
+
+            transactions.push(model::BlockTransaction {
+                hash: txn_hash.to_string(),
+                direction,
+                movements: movements
+                    .into_iter()
+                    .map(|(account, id)| model::MovementId { account, id })
+                    .collect(),
+            });
+        }
+
+        Ok(transactions)
+    }
+
+    /// Write a batch to the database, recording how long it took.
+    fn write_batch(&self, batch: T::WriteBatch) -> Result<()> {
+        self.params.metrics.db_write.time(|| self.db.write(batch))?;
+
+        Ok(())
+    }
+
+    /// Acquire a lock of the wallet state, recording how long it took. Used while indexing,
+    /// where the locks can be contended by the requests of the clients.
+    fn lock_wait<G, F: FnOnce() -> G>(&self, lock: F) -> G {
+        self.params.metrics.lock_wait.time(lock)
+    }
+
+    /// Last published copy of the indexed state.
+    fn snapshot(&self) -> Result<Arc<Snapshot>> {
+        let snapshot = self.snapshot.read()?;
+
+        Ok(Arc::clone(&snapshot))
+    }
+
+    /// Publish the current state for the queries. Queries in progress keep using the snapshot they
+    /// started with. The maps are shared with the snapshot instead of copied, so the ones that are
+    /// not modified by the next blocks are never copied.
+    fn publish_snapshot(&self) -> Result<()> {
+        let snapshot = Snapshot {
+            current_account: *self.lock_wait(|| self.current_account.read())?,
+            index_generation: *self.lock_wait(|| self.index_generation.read())?,
+            transactions_count: Arc::clone(&*self.lock_wait(|| self.transactions_count.read())?),
+            account_balances: Arc::clone(&*self.lock_wait(|| self.account_balances.read())?),
+            pkh_payments: Arc::clone(&*self.lock_wait(|| self.pkh_payments.read())?),
+            utxo_set: Arc::clone(&*self.lock_wait(|| self.utxo_set.read())?),
+        };
+        *self.lock_wait(|| self.snapshot.write())? = Arc::new(snapshot);
+
+        Ok(())
+    }
+
+    fn next_deposit_seq(&self) -> Result<u64> {
+        let mut last_seq = self.lock_wait(|| self.deposits_last_seq.write())?;
+        *last_seq += 1;
+
+        Ok(*last_seq)
+    }
+
+    /// Epoch of the last block indexed from the chain of the network the wallet is synced with.
+    fn last_indexed_epoch(&self) -> Result<Option<u32>> {
+        match self.lock_wait(|| self.network.read())?.as_ref() {
+            Some(network) => {
+                let cursor: Option<types::SyncCursor> = self.db.get_opt(
+                    &keys::wallet_sync_cursor(&network.network, &network.genesis_hash.to_string()),
+                )?;
+
+                Ok(cursor.map(|cursor| cursor.epoch))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_transaction_id(&self, account_index: u32) -> Result<u32> {
+        let mut transactions_count = self.lock_wait(|| self.transactions_count.write())?;
+        let next_id = Arc::make_mut(&mut transactions_count)
+            .entry(account_index)
+            .or_default();
+        let id = *next_id;
+
+        *next_id = next_id
+            .checked_add(1)
+            .ok_or_else(|| Error::TransactionIdOverflow)?;
+
+        Ok(id)
+    }
+
+    fn update_account_balance(
+        &self,
+        account_index: u32,
+        value: Balance,
+        op: BalanceOp,
+    ) -> Result<()> {
+        let mut account_balances = self.lock_wait(|| self.account_balances.write())?;
+        let balance = Arc::make_mut(&mut account_balances)
+            .entry(account_index)
+            .or_default();
+
+        *balance = match op {
+            BalanceOp::Add => balance
+                .checked_add(value)
+                .ok_or_else(|| Error::TransactionBalanceOverflow)?,
+            BalanceOp::Sub => balance
+                .checked_sub(value)
+                .ok_or_else(|| Error::BalanceUnderflow)?,
+        };
+
+        Ok(())
+    }
+}
+
+enum BalanceOp {
+    Add,
+    Sub,
+}
+
+/// Read-only copy of the state indexed from the blocks, published after every change.
+///
+/// Listing the movements of a wallet reads the database once per movement. Queries clone the
+/// `Arc` of the last snapshot instead of holding the locks of the state while doing so, so they
+/// never block the indexing of a block. The database is written before publishing a snapshot, so
+/// every movement counted by a snapshot can be read from the database.
+#[derive(Default)]
+struct Snapshot {
+    /// Current account being used by the client
+    current_account: AccountIndex,
+    /// Number of resets of the indexed state
+    index_generation: u32,
+    /// Number of transactions per account
+    transactions_count: Arc<HashMap<AccountIndex, TransactionId>>,
+    /// Account balances for the wallet
+    account_balances: Arc<HashMap<AccountIndex, Balance>>,
+    /// Map pkh -> number of outputs received
+    pkh_payments: Arc<HashMap<Pkh, u32>>,
+    /// Map account index -> utxo set, which maps output pointer -> value
+    utxo_set: Arc<HashMap<AccountIndex, HashMap<Utxo, Balance>>>,
+}
+
+/// Outputs of the wallet spent by the inputs of a transaction.
+#[derive(Default)]
+struct SpentOutputs {
+    /// Number of inputs spending an output of the wallet
+    count: usize,
+    /// Whether any of the inputs spent an output of a watched address
+    watched: bool,
+    /// Total value of the spent outputs
+    value: Balance,
+    /// Account and id of the debits recorded for the transaction
+    debits: Vec<(AccountIndex, TransactionId)>,
+    /// Pkh of the spent output of each debit, if known
+    addresses: Vec<(Pkh, (AccountIndex, TransactionId))>,
+}
+
+/// Movements found while indexing a block, persisted along with it.
+#[derive(Default)]
+struct BlockMovements {
+    /// Account and pkh of the outputs paying to the wallet, by output pointer, so they can be
+    /// spent in the same block
+    wallet_outputs: HashMap<(Vec<u8>, u32), (AccountIndex, Pkh)>,
+    /// Pkh and value of the outputs paying to a watched address, by output pointer
+    watched_outputs: HashMap<(Vec<u8>, u32), (Pkh, Balance)>,
+    /// Transactions that moved funds of a watched address
+    watched_transactions: Vec<model::Transaction>,
+}
+
+/// Position in the chain of the block being indexed.
+#[derive(Clone, Copy)]
+struct BlockPosition {
+    epoch: u32,
+    /// Number of blocks indexed from the chain up to this one, counting it
+    height: u32,
+}
+
+/// Outputs of a transaction paying to the wallet or to a watched address.
+#[derive(Default)]
+struct CreditedOutputs {
+    /// Whether any of the outputs paid to a watched address
+    watched: bool,
+    /// Whether any of the outputs paid to a deposit address
+    deposits: bool,
+    /// Account and id of the credits recorded for the transaction
+    credits: Vec<(AccountIndex, TransactionId)>,
+    /// Pkh of the output of each credit
+    addresses: Vec<(Pkh, (AccountIndex, TransactionId))>,
+    /// Pkh and value of each output paying to the wallet
+    received: Vec<(Pkh, Balance)>,
+}
+
+impl CreditedOutputs {
+    fn any(&self) -> bool {
+        self.watched || self.deposits || !self.credits.is_empty()
+    }
+}
+
+/// Remember the movements recorded for a transaction, so they can be found by its hash, and
+/// collect them by the pkh whose funds they moved, so they can be found by address.
+fn put_movements<B: WriteBatch>(
+    batch: &mut B,
+    pkh_movements: &mut HashMap<Pkh, Vec<(AccountIndex, TransactionId)>>,
+    generation: u32,
+    txn_hash: &[u8],
+    spent: &SpentOutputs,
+    credited: &CreditedOutputs,
+) -> Result<()> {
+    let movements: Vec<(AccountIndex, TransactionId)> = spent
+        .debits
+        .iter()
+        .chain(&credited.credits)
+        .cloned()
+        .collect();
+    if !movements.is_empty() {
+        batch.put(
+            &keys::transaction_movements(generation, txn_hash),
+            movements,
+        )?;
+    }
+    for (pkh, movement) in spent.addresses.iter().chain(&credited.addresses) {
+        pkh_movements
+            .entry(pkh.clone())
+            .or_insert_with(Vec::new)
+            .push(*movement);
+    }
+
+    Ok(())
+}
+
+/// Payments received by a pkh of the wallet.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct AddressReceipts {
+    total: Balance,
+    first_epoch: u32,
+    last_epoch: u32,
+}
+
+impl AddressReceipts {
+    /// Add up the payments received by the same pkh.
+    fn merge(self, other: AddressReceipts) -> AddressReceipts {
+        AddressReceipts {
+            total: self.total.saturating_add(other.total),
+            first_epoch: cmp::min(self.first_epoch, other.first_epoch),
+            last_epoch: cmp::max(self.last_epoch, other.last_epoch),
+        }
+    }
+}
+
+/// Collect the payments received by the pkhs of the wallet in the outputs of a transaction.
+fn collect_receipts(
+    pkh_receipts: &mut HashMap<Pkh, AddressReceipts>,
+    credited: &CreditedOutputs,
+    epoch: u32,
+) {
+    for (pkh, value) in &credited.received {
+        let received = AddressReceipts {
+            total: *value,
+            first_epoch: epoch,
+            last_epoch: epoch,
+        };
+        let receipts = match pkh_receipts.get(pkh) {
+            Some(receipts) => receipts.merge(received),
+            None => received,
+        };
+        pkh_receipts.insert(pkh.clone(), receipts);
+    }
+}
+
+/// Kind of a movement, given the type recorded for it.
+fn transaction_kind(kind: &str) -> model::TransactionKind {
+    match kind {
+        "debit" => model::TransactionKind::Debit,
+        "witness_reward" => model::TransactionKind::WitnessReward,
+        "refund" => model::TransactionKind::Refund,
+        _ => model::TransactionKind::Credit,
+    }
+}
+
+/// Prefix of a key used to group the records in the storage stats: the key with its indexes and
+/// hashes, i.e. the parts containing digits, replaced by `*`.
+fn key_prefix(key: &[u8]) -> String {
+    match std::str::from_utf8(key) {
+        Ok(key) if key.starts_with("custom-") => "custom-*".to_string(),
+        Ok(key) => key
+            .split('-')
+            .map(|part| {
+                if part.chars().any(|c| c.is_ascii_digit()) {
+                    "*"
+                } else {
+                    part
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("-"),
+        // The recipients of the outputs are the only keys that are not strings
+        Err(_) => "*".to_string(),
+    }
+}
+
+/// Whether a record is superseded by the current state of the wallet, given the current index
+/// generation, the last acknowledged deposit and the sync cursor of the current chain.
+fn is_superseded(
+    key: &str,
+    generation: u32,
+    deposits_cursor: u64,
+    sync_cursor: Option<&String>,
+) -> bool {
+    let older_generation = |part: &str| part.parse::<u32>().map_or(false, |part| part < generation);
+    let parts: Vec<&str> = key.split('-').collect();
+
+    match parts.as_slice() {
+        // keys of the first generation, which have no generation
+        ["indexed", "transaction", _] | ["transaction", _, "movements"] => generation > 0,
+        ["indexed", "transaction", part, _]
+        | ["transaction", part, _, "movements"]
+        | ["pkh", part, _, "movements"]
+        | ["pkh", part, _, "movements", "count"]
+        | ["pkh", part, _, "movement", _]
+        | ["pkh", part, _, "receipts"] => older_generation(part),
+        ["deposit", seq] | ["deposit", seq, "height"] => seq
+            .parse::<u64>()
+            .map_or(false, |seq| seq <= deposits_cursor),
+        _ if key.starts_with("sync-cursor-") => {
+            sync_cursor.map_or(false, |sync_cursor| key != sync_cursor)
+        }
+        _ => false,
+    }
+}
+
+/// Public key hash of an address from its bytes.
+fn pkh_from_bytes(bytes: &[u8]) -> Result<types::PublicKeyHash> {
+    if bytes.len() != 20 {
+        return Err(Error::InvalidPkh(bytes.len()));
+    }
+    let mut hash = [0; 20];
+    hash.copy_from_slice(bytes);
+
+    Ok(types::PublicKeyHash { hash })
+}
+
+/// Total value of the given outputs.
+fn outputs_value(outputs: &[types::ValueTransferOutput]) -> Balance {
+    outputs.iter().fold(Balance::ZERO, |total, output| {
+        total.saturating_add(types::Nanowits::from(output.value))
+    })
+}
+
+/// Output of the utxo set spent by an input.
+fn input_utxo(input: &types::Input) -> Utxo {
+    let p = input.output_pointer();
+
+    (p.transaction_id.as_ref().to_vec(), p.output_index)
+}
+
+/// SHA-256 hash of the JSON-serialized metadata, which is the message signed when exporting it.
+fn metadata_hash(metadata: &model::WalletMetadata) -> Result<Sha256> {
+    let bytes = serde_json::to_vec(metadata).map_err(failure::Error::from)?;
+
+    Ok(crypto::calculate_sha256(&bytes))
+}
+
+#[inline]
+fn account_keypath(index: u32) -> types::KeyPath {
+    types::KeyPath::default()
+        .hardened(3)
+        .hardened(4919)
+        .hardened(index)
+}
+
+#[cfg(test)]
+mod tests;
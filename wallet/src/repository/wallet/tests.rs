@@ -0,0 +1,945 @@
+//! Tests of the wallet repository.
+//!
+//! The property tests indexing random chains of blocks are in `properties`, and only run with
+//! `cargo test -p witnet_wallet --features property-tests`.
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto as _;
+
+use bech32::{FromBase32 as _, ToBase32 as _};
+
+use witnet_crypto::key::MasterKeyGen;
+use witnet_data_structures::chain::{OutputPointer, PublicKeyHash};
+
+use super::*;
+use crate::db::HashMapDb;
+use crate::types::Hashable as _;
+
+#[cfg(feature = "property-tests")]
+mod properties;
+
+fn account_balance(wallet: &Wallet<HashMapDb>, account: AccountIndex) -> Balance {
+    wallet
+        .account_balances
+        .read()
+        .unwrap()
+        .get(&account)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn account_utxos(wallet: &Wallet<HashMapDb>, account: AccountIndex) -> HashMap<Utxo, Balance> {
+    wallet
+        .utxo_set
+        .read()
+        .unwrap()
+        .get(&account)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn transactions_count(wallet: &Wallet<HashMapDb>) -> HashMap<AccountIndex, TransactionId> {
+    HashMap::clone(&wallet.transactions_count.read().unwrap())
+}
+
+/// Wallet with the keys of a seed, whose first address has been paid outputs of the given values
+fn funded_wallet(values: &[u64]) -> (Wallet<HashMapDb>, PublicKeyHash) {
+    let db = HashMapDb::default();
+    let engine = types::SignEngine::signing_only();
+    let master_key = MasterKeyGen::new([7; 32]).generate().unwrap();
+    let account = crate::account::gen_account(&engine, 0, &master_key).unwrap();
+    db.put(keys::wallet_master_key(), &master_key).unwrap();
+    db.put(keys::wallet_default_account(), 0u32).unwrap();
+    db.put(keys::account_ek(0), &account.external).unwrap();
+    db.put(keys::account_ik(0), &account.internal).unwrap();
+
+    let wallet = Wallet::new(db, Params::default(), engine);
+    wallet.unlock().unwrap();
+    wallet.load().unwrap();
+    let address = wallet.gen_address(None, false, 0).unwrap();
+    let (_, data) = bech32::decode(&address.address).unwrap();
+    let pkh = pkh_from_bytes(&Vec::<u8>::from_base32(&data).unwrap()).unwrap();
+
+    let txn = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: types::Hash::SHA256([9; 32]),
+            output_index: 0,
+        })],
+        values
+            .iter()
+            .map(|value| types::ValueTransferOutput { pkh, value: *value })
+            .collect(),
+    );
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 1,
+            value_transfer: vec![txn],
+            ..Default::default()
+        })
+        .unwrap();
+
+    (wallet, pkh)
+}
+
+/// Testnet address of a pkh foreign to the wallets of the tests
+fn foreign_address() -> String {
+    bech32::encode("twit", [0xaa; 20].to_base32()).unwrap()
+}
+
+fn recurring_payment(next_epoch: u32, interval: u32) -> model::RecurringPayment {
+    model::RecurringPayment {
+        id: 0,
+        address: foreign_address(),
+        amount: Balance::from(10),
+        fee: Balance::from(1),
+        label: None,
+        interval: types::ScheduleInterval::Epochs(interval),
+        interval_epochs: interval,
+        next_epoch,
+        end_epoch: None,
+    }
+}
+
+fn legacy_recurring_payment(id: u32) -> model::LegacyRecurringPayment {
+    model::LegacyRecurringPayment {
+        id,
+        address: foreign_address(),
+        amount: Balance::from(10),
+        fee: Balance::from(1),
+        label: None,
+        interval: 10,
+        next_epoch: 0,
+        end_epoch: None,
+    }
+}
+
+#[test]
+fn snapshots_share_the_unmodified_state() {
+    let (wallet, _pkh) = funded_wallet(&[100]);
+    let previous = wallet.snapshot().unwrap();
+
+    // A block without movements of the wallet does not modify its state
+    let txn = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: types::Hash::SHA256([8; 32]),
+            output_index: 0,
+        })],
+        vec![types::ValueTransferOutput {
+            pkh: pkh_from_bytes(&[0xaa; 20]).unwrap(),
+            value: 10,
+        }],
+    );
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 2,
+            value_transfer: vec![txn],
+            ..Default::default()
+        })
+        .unwrap();
+    let snapshot = wallet.snapshot().unwrap();
+
+    assert!(Arc::ptr_eq(&previous.utxo_set, &snapshot.utxo_set));
+    assert!(Arc::ptr_eq(
+        &previous.account_balances,
+        &snapshot.account_balances
+    ));
+    assert!(Arc::ptr_eq(&previous.pkh_payments, &snapshot.pkh_payments));
+    assert!(Arc::ptr_eq(
+        &previous.transactions_count,
+        &snapshot.transactions_count
+    ));
+
+    // Modifying the state does not modify the published snapshots
+    let (wallet, pkh) = funded_wallet(&[100]);
+    let previous = wallet.snapshot().unwrap();
+    let txn = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: types::Hash::SHA256([8; 32]),
+            output_index: 0,
+        })],
+        vec![types::ValueTransferOutput { pkh, value: 10 }],
+    );
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 2,
+            value_transfer: vec![txn],
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert_eq!(previous.utxo_set[&0].len(), 1);
+    assert_eq!(wallet.snapshot().unwrap().utxo_set[&0].len(), 2);
+}
+
+#[test]
+fn recurring_payments_run_once_per_epoch() {
+    let (wallet, _) = funded_wallet(&[]);
+    let payment = wallet
+        .add_recurring_payment(recurring_payment(0, 10))
+        .unwrap();
+
+    // The payments missed until epoch 25 are caught up one per epoch
+    let due_epochs = |epoch| -> Vec<u32> {
+        wallet
+            .run_schedules(epoch)
+            .unwrap()
+            .into_iter()
+            .map(|(_, scheduled, _)| scheduled.epoch)
+            .collect()
+    };
+    assert_eq!(due_epochs(25), vec![0]);
+    assert_eq!(due_epochs(25), Vec::<u32>::new());
+    assert_eq!(due_epochs(26), vec![10]);
+    assert_eq!(due_epochs(27), vec![20]);
+    assert_eq!(due_epochs(28), Vec::<u32>::new());
+
+    // A recurring payment whose next payment would overflow the epochs ends with its last one
+    let last = wallet
+        .add_recurring_payment(recurring_payment(u32::max_value() - 1, 5))
+        .unwrap();
+    assert_eq!(due_epochs(u32::max_value()), vec![30, u32::max_value() - 1]);
+    let payments = wallet.recurring_payments().unwrap();
+    assert_eq!(
+        payments
+            .iter()
+            .map(|payment| payment.id)
+            .collect::<Vec<_>>(),
+        vec![payment.id, last.id]
+    );
+    assert!(!payments[1].is_due(u32::max_value()));
+
+    let scheduled = wallet.scheduled_payments().unwrap();
+    assert_eq!(scheduled.len(), 5);
+    assert!(scheduled
+        .iter()
+        .all(|scheduled| scheduled.transaction_hash.is_none()));
+
+    let hash = "389a3fa3a1feb8fd8cdc61748ac17dce0aeef39ff9634dec9c20ece69105c264".to_string();
+    wallet
+        .set_scheduled_payment_transaction(1, hash.clone())
+        .unwrap();
+    assert_eq!(
+        wallet.scheduled_payments().unwrap()[1].transaction_hash,
+        Some(hash)
+    );
+
+    wallet.delete_recurring_payment(payment.id).unwrap();
+    assert_eq!(wallet.recurring_payments().unwrap().len(), 1);
+    assert_eq!(wallet.scheduled_payments().unwrap().len(), 5);
+}
+
+#[test]
+fn pending_scheduled_payments_are_retried() {
+    let (wallet, _) = funded_wallet(&[]);
+    let payment = wallet
+        .add_recurring_payment(recurring_payment(0, 10))
+        .unwrap();
+    let due = |epoch| -> Vec<(u32, bool)> {
+        wallet
+            .run_schedules(epoch)
+            .unwrap()
+            .into_iter()
+            .map(|(index, _, retried)| (index, retried))
+            .collect()
+    };
+
+    assert_eq!(due(0), vec![(0, false)]);
+    // The payment could not be sent, so it is retried along with the next one
+    wallet.retry_scheduled_payment(0).unwrap();
+    assert_eq!(due(0), vec![]);
+    assert_eq!(due(10), vec![(0, true), (1, false)]);
+    assert_eq!(due(11), vec![]);
+
+    // The pending payments of a deleted recurring payment are not retried
+    wallet.retry_scheduled_payment(1).unwrap();
+    wallet.delete_recurring_payment(payment.id).unwrap();
+    assert_eq!(due(12), vec![]);
+    assert_eq!(wallet.scheduled_payments().unwrap().len(), 2);
+}
+
+#[test]
+fn recurring_payments_are_migrated_to_one_record_each() {
+    let (wallet, _) = funded_wallet(&[]);
+    let mut legacy = BTreeMap::new();
+    legacy.insert(3, legacy_recurring_payment(3));
+    wallet
+        .db
+        .put(keys::wallet_recurring_payments(), &legacy)
+        .unwrap();
+
+    let payments = wallet.recurring_payments().unwrap();
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].id, 3);
+    assert!(wallet
+        .db
+        .get_opt::<_, BTreeMap<u32, model::LegacyRecurringPayment>>(
+            keys::wallet_recurring_payments()
+        )
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn recurring_payments_are_migrated_to_keep_the_unit_of_their_interval() {
+    let (wallet, _) = funded_wallet(&[]);
+    let ids: BTreeSet<u32> = vec![3].into_iter().collect();
+    wallet
+        .db
+        .put(keys::wallet_legacy_recurring_payment_ids(), &ids)
+        .unwrap();
+    wallet
+        .db
+        .put(
+            &keys::legacy_recurring_payment(3),
+            legacy_recurring_payment(3),
+        )
+        .unwrap();
+
+    let payments = wallet.recurring_payments().unwrap();
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].id, 3);
+    assert_eq!(payments[0].interval, types::ScheduleInterval::Epochs(10));
+    assert_eq!(payments[0].interval_epochs, 10);
+    assert!(wallet
+        .db
+        .get_opt::<_, model::LegacyRecurringPayment>(&keys::legacy_recurring_payment(3))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn vtt_spends_the_largest_outputs_and_is_signed() {
+    let (wallet, pkh) = funded_wallet(&[100, 50, 30]);
+    let recipient = foreign_address();
+
+    let txn = wallet
+        .create_vtt(
+            &recipient,
+            Balance::from(120),
+            Balance::from(10),
+            types::ChangeAddressPolicy::Input,
+            0,
+        )
+        .unwrap();
+    let mut spent: Vec<u32> = txn
+        .body
+        .inputs
+        .iter()
+        .map(|input| input.output_pointer().output_index)
+        .collect();
+    spent.sort();
+    assert_eq!(spent, vec![0, 1]);
+    assert_eq!(txn.body.outputs.len(), 2);
+    assert_eq!(txn.body.outputs[0].value, 120);
+    assert_eq!(
+        txn.body.outputs[1],
+        types::ValueTransferOutput { pkh, value: 20 }
+    );
+
+    assert_eq!(txn.signatures.len(), txn.body.inputs.len());
+    for keyed_signature in &txn.signatures {
+        let public_key: secp256k1::PublicKey =
+            keyed_signature.public_key.clone().try_into().unwrap();
+        let sig: secp256k1::Signature = keyed_signature.signature.clone().try_into().unwrap();
+        signature::verify(&public_key, txn.body.hash().as_ref(), &sig).unwrap();
+    }
+
+    // The spent outputs are reserved, so the next transaction spends the remaining one
+    let txn = wallet
+        .create_vtt(
+            &recipient,
+            Balance::from(20),
+            Balance::from(0),
+            types::ChangeAddressPolicy::Input,
+            0,
+        )
+        .unwrap();
+    assert_eq!(txn.body.inputs.len(), 1);
+    assert_eq!(txn.body.inputs[0].output_pointer().output_index, 2);
+
+    match wallet.create_vtt(
+        &recipient,
+        Balance::from(1),
+        Balance::from(0),
+        types::ChangeAddressPolicy::Input,
+        0,
+    ) {
+        Err(Error::InsufficientFunds(_, _)) => {}
+        result => panic!("spent reserved outputs: {:?}", result),
+    }
+}
+
+#[test]
+fn vtt_pays_the_change_to_a_fresh_internal_address() {
+    let (wallet, pkh) = funded_wallet(&[100]);
+
+    let txn = wallet
+        .create_vtt(
+            &foreign_address(),
+            Balance::from(60),
+            Balance::from(10),
+            types::ChangeAddressPolicy::Fresh,
+            0,
+        )
+        .unwrap();
+    let change_pkh = txn.body.outputs[1].pkh;
+    assert_ne!(change_pkh, pkh);
+    assert_eq!(txn.body.outputs[1].value, 30);
+    let path: String = wallet.db.get(&keys::pkh_path(change_pkh.as_ref())).unwrap();
+    assert_eq!(path, "m/3'/4919'/0'/1/0");
+
+    // The change is indexed as an output of the wallet, and spent with the key of its path
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 2,
+            value_transfer: vec![txn.body],
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(wallet.balance().unwrap().1, Balance::from(30));
+    let txn = wallet
+        .create_vtt(
+            &foreign_address(),
+            Balance::from(10),
+            Balance::from(0),
+            types::ChangeAddressPolicy::Fresh,
+            0,
+        )
+        .unwrap();
+    assert_eq!(txn.signatures[0].public_key.pkh(), change_pkh);
+
+    // Every change gets a new address
+    assert_ne!(txn.body.outputs[1].pkh, change_pkh);
+}
+
+#[test]
+fn input_pkhs_are_the_addresses_of_the_spent_outputs() {
+    let (wallet, pkh) = funded_wallet(&[100, 50]);
+
+    let txn = wallet
+        .create_vtt(
+            &foreign_address(),
+            Balance::from(120),
+            Balance::from(0),
+            types::ChangeAddressPolicy::Input,
+            0,
+        )
+        .unwrap();
+    assert_eq!(wallet.input_pkhs(&txn.body.inputs).unwrap(), vec![pkh, pkh]);
+    // The keys of the derivation paths of the inputs hash to their pkhs
+    for keyed_signature in &txn.signatures {
+        assert_eq!(keyed_signature.public_key.pkh(), pkh);
+    }
+
+    let unknown = types::Input::new(OutputPointer {
+        transaction_id: types::Hash::SHA256([9; 32]),
+        output_index: 0,
+    });
+    match wallet.input_pkhs(&[unknown]) {
+        Err(Error::NoUtxoForInput) => {}
+        result => panic!("found the pkh of an unknown output: {:?}", result),
+    }
+}
+
+#[test]
+fn address_report_does_not_know_the_receipts_indexed_before_recording_them() {
+    let (wallet, pkh) = funded_wallet(&[100, 50]);
+    wallet.gen_address(None, false, 0).unwrap();
+
+    let report = wallet.address_report().unwrap();
+    assert_eq!(report.len(), 2);
+    assert_eq!(report[0].times_received, 2);
+    assert_eq!(report[0].total_received, Some(Balance::from(150)));
+    assert_eq!(report[0].first_payment_epoch, Some(1));
+    // Addresses without payments have received nothing
+    assert_eq!(report[1].times_received, 0);
+    assert_eq!(report[1].total_received, Some(Balance::ZERO));
+
+    // As if the payments had been indexed by a version not recording the receipts
+    let generation = wallet.snapshot().unwrap().index_generation;
+    wallet
+        .db
+        .delete(&keys::pkh_receipts(generation, &pkh.hash))
+        .unwrap();
+    let report = wallet.address_report().unwrap();
+    assert_eq!(report[0].times_received, 2);
+    assert_eq!(report[0].total_received, None);
+    assert_eq!(report[0].first_payment_epoch, None);
+}
+
+#[test]
+fn wallets_without_master_key_can_not_export_it() {
+    let db = HashMapDb::default();
+    db.put(keys::wallet_default_account(), 0u32).unwrap();
+    let wallet = Wallet::new(db, Params::default(), types::SignEngine::signing_only());
+    wallet.unlock().unwrap();
+
+    let not_stored = |result: Result<types::ExtendedSK>| match result {
+        Err(Error::MasterKeyNotStored) => true,
+        _ => false,
+    };
+    assert!(not_stored(wallet.master_key()));
+    assert!(not_stored(wallet.account_key()));
+
+    // The master key of the wallets created with it is exported
+    let (wallet, _) = funded_wallet(&[]);
+    let master_key = MasterKeyGen::new([7; 32]).generate().unwrap();
+    assert_eq!(
+        wallet.master_key().unwrap().concat().as_ref(),
+        master_key.concat().as_ref()
+    );
+}
+
+#[test]
+fn statement_does_not_count_the_change() {
+    let (wallet, _) = funded_wallet(&[100]);
+    let txn = wallet
+        .create_vtt(
+            &foreign_address(),
+            Balance::from(60),
+            Balance::from(10),
+            types::ChangeAddressPolicy::Input,
+            0,
+        )
+        .unwrap();
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 2,
+            value_transfer: vec![txn.body],
+            ..Default::default()
+        })
+        .unwrap();
+
+    let statement = wallet.statement(0, 3).unwrap();
+    assert_eq!(statement.opening_balance, Balance::from(0));
+    assert_eq!(statement.total_in, Balance::from(100));
+    assert_eq!(statement.total_out, Balance::from(70));
+    assert_eq!(statement.fees_paid, Balance::from(10));
+    assert_eq!(statement.closing_balance, Balance::from(30));
+
+    // The change of the payment is not received during its period either
+    let statement = wallet.statement(2, 3).unwrap();
+    assert_eq!(statement.opening_balance, Balance::from(100));
+    assert_eq!(statement.total_in, Balance::from(0));
+    assert_eq!(statement.total_out, Balance::from(70));
+    assert_eq!(statement.closing_balance, Balance::from(30));
+}
+
+#[test]
+fn outputs_spent_in_the_same_block() {
+    let (wallet, pkh) = funded_wallet(&[100]);
+    let address = bech32::encode("twit", pkh.as_ref().to_base32()).unwrap();
+
+    let credit = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: types::Hash::SHA256([8; 32]),
+            output_index: 0,
+        })],
+        vec![
+            types::ValueTransferOutput { pkh, value: 30 },
+            types::ValueTransferOutput { pkh, value: 20 },
+        ],
+    );
+    let debit = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: credit.hash(),
+            output_index: 0,
+        })],
+        vec![],
+    );
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 2,
+            value_transfer: vec![credit, debit],
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert_eq!(wallet.balance().unwrap().1, Balance::from(120));
+    let transactions = wallet
+        .address_transactions(&address, 0, u32::max_value())
+        .unwrap();
+    assert_eq!(transactions.total, 4);
+
+    // Every movement of the address is stored in its own record
+    let count: u32 = wallet
+        .db
+        .get(&keys::pkh_movements_count(0, pkh.as_ref()))
+        .unwrap();
+    assert_eq!(count, 4);
+    assert!(wallet
+        .db
+        .get_opt::<_, Vec<(AccountIndex, TransactionId)>>(&keys::pkh_movements(0, pkh.as_ref()))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn payments_know_their_counterparty() {
+    let (wallet, pkh) = funded_wallet(&[100]);
+    let address = bech32::encode("twit", pkh.as_ref().to_base32()).unwrap();
+    wallet
+        .save_contact(model::Contact {
+            address: foreign_address(),
+            name: "Alice".to_string(),
+            notes: None,
+        })
+        .unwrap();
+
+    // The output created by `funded_wallet`
+    let funding = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: types::Hash::SHA256([9; 32]),
+            output_index: 0,
+        })],
+        vec![types::ValueTransferOutput { pkh, value: 100 }],
+    );
+    let payment = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: funding.hash(),
+            output_index: 0,
+        })],
+        vec![
+            types::ValueTransferOutput { pkh, value: 40 },
+            types::ValueTransferOutput {
+                pkh: pkh_from_bytes(&[0xaa; 20]).unwrap(),
+                value: 60,
+            },
+        ],
+    );
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 2,
+            value_transfer: vec![payment],
+            ..Default::default()
+        })
+        .unwrap();
+
+    let transactions = wallet
+        .address_transactions(&address, 0, u32::max_value())
+        .unwrap()
+        .transactions;
+    let counterparties: Vec<_> = transactions
+        .iter()
+        .map(|txn| (txn.kind.clone(), txn.counterparty.clone()))
+        .collect();
+    // Newest first, the change is not a counterparty and the payer of a credit is not known
+    assert_eq!(
+        counterparties,
+        vec![
+            (model::TransactionKind::Credit, None),
+            (model::TransactionKind::Debit, Some(foreign_address())),
+            (model::TransactionKind::Credit, None),
+        ]
+    );
+    assert_eq!(
+        wallet.contact_name(&foreign_address()).unwrap(),
+        Some("Alice".to_string())
+    );
+
+    // Deleting a missing contact leaves the address book as it was
+    assert!(wallet.delete_contact(&address).is_err());
+    assert_eq!(wallet.contacts().unwrap().len(), 1);
+}
+
+#[test]
+fn tally_outputs_are_listed_as_rewards_or_refunds() {
+    let (wallet, pkh) = funded_wallet(&[100]);
+
+    // The output created by `funded_wallet` pays for a data request of the wallet
+    let funding = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: types::Hash::SHA256([9; 32]),
+            output_index: 0,
+        })],
+        vec![types::ValueTransferOutput { pkh, value: 100 }],
+    );
+    let data_request = types::DRTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: funding.hash(),
+            output_index: 0,
+        })],
+        vec![],
+        types::DataRequestOutput::default(),
+    );
+    let own_dr_hash = data_request.hash();
+    let foreign_dr_hash = types::Hash::SHA256([8; 32]);
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 2,
+            data_requests: vec![data_request],
+            ..Default::default()
+        })
+        .unwrap();
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 3,
+            tallies: vec![
+                types::TallyTransaction::new(
+                    own_dr_hash,
+                    vec![],
+                    vec![types::ValueTransferOutput { pkh, value: 30 }],
+                ),
+                types::TallyTransaction::new(
+                    foreign_dr_hash,
+                    vec![],
+                    vec![types::ValueTransferOutput { pkh, value: 20 }],
+                ),
+            ],
+            ..Default::default()
+        })
+        .unwrap();
+
+    let transactions = wallet.transactions(0, u32::max_value()).unwrap();
+    let kinds: Vec<_> = transactions
+        .transactions
+        .iter()
+        .map(|txn| (txn.kind.clone(), txn.value, txn.dr_hash.clone()))
+        .collect();
+    // Newest first
+    assert_eq!(transactions.total, 4);
+    assert_eq!(
+        kinds,
+        vec![
+            (
+                model::TransactionKind::WitnessReward,
+                Balance::from(20),
+                Some(foreign_dr_hash.to_string())
+            ),
+            (
+                model::TransactionKind::Refund,
+                Balance::from(30),
+                Some(own_dr_hash.to_string())
+            ),
+            (model::TransactionKind::Debit, Balance::from(100), None),
+            (model::TransactionKind::Credit, Balance::from(100), None),
+        ]
+    );
+
+    // Pages are taken from the newest transactions
+    let page = wallet.transactions(1, 2).unwrap();
+    assert_eq!(page.total, 4);
+    assert_eq!(
+        page.transactions
+            .iter()
+            .map(|txn| txn.kind.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            model::TransactionKind::Refund,
+            model::TransactionKind::Debit
+        ]
+    );
+}
+
+#[test]
+fn address_proof_is_verified_with_its_public_key() {
+    let (wallet, pkh) = funded_wallet(&[]);
+    let proof = wallet
+        .address_proof(0, "audit-challenge".to_string())
+        .unwrap();
+
+    let public_key =
+        secp256k1::PublicKey::from_slice(&hex::decode(&proof.public_key).unwrap()).unwrap();
+    let proof_signature =
+        secp256k1::Signature::from_der(&hex::decode(&proof.signature).unwrap()).unwrap();
+    let hash = crypto::address_proof_hash("audit-challenge");
+    assert!(signature::verify(&public_key, hash.as_ref(), &proof_signature).is_ok());
+    // The signature is not valid for another challenge
+    let other_hash = crypto::address_proof_hash("another-challenge");
+    assert!(signature::verify(&public_key, other_hash.as_ref(), &proof_signature).is_err());
+
+    // The public key is the key of the address
+    let public_key_hash = crypto::calculate_sha256(&public_key.serialize_uncompressed());
+    assert_eq!(&public_key_hash.as_ref()[..20], pkh.as_ref());
+    assert_eq!(
+        proof.address,
+        bech32::encode("twit", pkh.as_ref().to_base32()).unwrap()
+    );
+    assert!(proof.path.ends_with("/0/0"));
+
+    match wallet.address_proof(1, "audit-challenge".to_string()) {
+        Err(Error::AddressNotFound(1)) => {}
+        _ => panic!("proved an address that was not generated"),
+    }
+}
+
+#[test]
+fn metadata_is_carried_over_to_a_wallet_of_the_same_seed() {
+    let (exporter, _) = funded_wallet(&[]);
+    let labelled = exporter
+        .gen_address(Some("Donations".to_string()), false, 0)
+        .unwrap();
+    exporter
+        .save_contact(model::Contact {
+            address: foreign_address(),
+            name: "Alice".to_string(),
+            notes: Some("Rent".to_string()),
+        })
+        .unwrap();
+    exporter
+        .watch_address(foreign_address(), Some("Cold storage".to_string()))
+        .unwrap();
+    let signed = exporter.export_metadata().unwrap();
+
+    // The labelled address has not been generated by the importer yet
+    let (importer, _) = funded_wallet(&[]);
+    let imported = importer.import_metadata(signed.clone()).unwrap();
+    assert_eq!(imported.address_labels, 0);
+    assert_eq!(imported.skipped_address_labels, 1);
+    assert_eq!(imported.contacts, 1);
+    assert_eq!(imported.watched_addresses, 1);
+    assert_eq!(
+        importer.contact_name(&foreign_address()).unwrap(),
+        Some("Alice".to_string())
+    );
+    let watched = importer.watched_addresses().unwrap();
+    assert_eq!(watched.len(), 1);
+    assert_eq!(watched[0].label, Some("Cold storage".to_string()));
+
+    let address = importer.gen_address(None, false, 0).unwrap();
+    assert_eq!(address.address, labelled.address);
+    let imported = importer.import_metadata(signed.clone()).unwrap();
+    assert_eq!(imported.address_labels, 1);
+    assert_eq!(imported.skipped_address_labels, 0);
+
+    // Modified documents are rejected
+    let mut tampered = signed;
+    tampered.metadata.contacts[0].name = "Mallory".to_string();
+    match importer.import_metadata(tampered) {
+        Err(Error::MetadataSignature) => {}
+        result => panic!("imported a modified document: {:?}", result),
+    }
+}
+
+#[test]
+fn metadata_import_is_atomic() {
+    let (wallet, _) = funded_wallet(&[]);
+    let mut watched_addresses = BTreeMap::new();
+    watched_addresses.insert(foreign_address(), None);
+    // An address of another network, which cannot be watched
+    watched_addresses.insert(bech32::encode("wit", [0xaa; 20].to_base32()).unwrap(), None);
+    let metadata = model::WalletMetadata {
+        version: constants::METADATA_VERSION,
+        address_labels: BTreeMap::new(),
+        contacts: vec![model::Contact {
+            address: foreign_address(),
+            name: "Alice".to_string(),
+            notes: None,
+        }],
+        watched_addresses,
+    };
+    let signed = wallet.sign_metadata(metadata).unwrap();
+
+    match wallet.import_metadata(signed) {
+        Err(Error::InvalidAddress(_)) => {}
+        result => panic!("imported an address of another network: {:?}", result),
+    }
+    // Nothing is imported, neither in memory nor in the database
+    assert!(wallet.contacts().unwrap().is_empty());
+    assert!(wallet.watched_addresses().unwrap().is_empty());
+    assert!(wallet
+        .db
+        .get_opt::<_, HashMap<String, model::Contact>>(keys::wallet_contacts())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn watched_addresses_follow_their_movements() {
+    let (wallet, _) = funded_wallet(&[]);
+    let address = foreign_address();
+    let pkh = pkh_from_bytes(&[0xaa; 20]).unwrap();
+    wallet.watch_address(address.clone(), None).unwrap();
+
+    // The output paying to the watched address is spent in the same block
+    let credit = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: types::Hash::SHA256([8; 32]),
+            output_index: 0,
+        })],
+        vec![
+            types::ValueTransferOutput { pkh, value: 40 },
+            types::ValueTransferOutput { pkh, value: 2 },
+        ],
+    );
+    let debit = types::VTTransactionBody::new(
+        vec![types::Input::new(OutputPointer {
+            transaction_id: credit.hash(),
+            output_index: 0,
+        })],
+        vec![],
+    );
+    wallet
+        .index_txns(&types::BlockTransactions {
+            epoch: 2,
+            value_transfer: vec![credit.clone(), debit.clone()],
+            ..Default::default()
+        })
+        .unwrap();
+
+    let movements: Vec<_> = wallet
+        .watched_transactions()
+        .unwrap()
+        .into_iter()
+        .map(|txn| (txn.hash, txn.kind, txn.value))
+        .collect();
+    assert_eq!(
+        movements,
+        vec![
+            (
+                hex::encode(credit.hash()),
+                model::TransactionKind::Credit,
+                Balance::from(40)
+            ),
+            (
+                hex::encode(credit.hash()),
+                model::TransactionKind::Credit,
+                Balance::from(2)
+            ),
+            (
+                hex::encode(debit.hash()),
+                model::TransactionKind::Debit,
+                Balance::from(40)
+            ),
+        ]
+    );
+    assert_eq!(
+        wallet.watched_addresses().unwrap()[0].balance,
+        Balance::from(2)
+    );
+    assert_eq!(wallet.balance().unwrap().1, Balance::ZERO);
+
+    // Addresses of another network are not watched
+    let mainnet_address = bech32::encode("wit", [0xaa; 20].to_base32()).unwrap();
+    match wallet.watch_address(mainnet_address, None) {
+        Err(Error::InvalidAddress(_)) => {}
+        result => panic!("watched an address of another network: {:?}", result),
+    }
+}
+
+#[test]
+fn unlocked_wallet_knows_its_balance_before_loading() {
+    let (wallet, _) = funded_wallet(&[100, 50]);
+
+    let reopened = Wallet::new(
+        wallet.db.clone(),
+        Params::default(),
+        types::SignEngine::signing_only(),
+    );
+    let data = reopened.unlock().unwrap();
+    assert_eq!(data.balance, Balance::from(150));
+    assert!(!reopened.is_loaded().unwrap());
+
+    // The utxo set is loaded once
+    assert!(reopened.load().unwrap());
+    assert!(!reopened.load().unwrap());
+    assert_eq!(reopened.balance().unwrap().1, Balance::from(150));
+    assert_eq!(
+        account_utxos(&reopened, 0)
+            .values()
+            .cloned()
+            .collect::<HashSet<_>>(),
+        vec![Balance::from(100), Balance::from(50)]
+            .into_iter()
+            .collect()
+    );
+}
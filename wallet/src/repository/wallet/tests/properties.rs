@@ -0,0 +1,834 @@
+//! Property tests of the indexing of blocks.
+//!
+//! Random chains of blocks are indexed by a wallet, checking after every block that:
+//! - the balance of every account equals the sum of its UTXOs, and the balance expected by a
+//!   simple model of the wallet,
+//! - indexing the same block again does not change the balances, UTXOs or movements,
+//! - a wallet reloaded from the database has the same balances and UTXOs,
+//! - the UTXOs reserved by signed transactions are released once a block spends them,
+//! - every payment to a deposit address is delivered as a deposit until it is acknowledged,
+//!   with its confirmations counted in blocks, and never delivered twice,
+//! - every movement is found by the address whose funds it moved,
+//! - the payments received by every address add up to the value of the outputs paying to it,
+//! - queries see the state of the last indexed block, and a snapshot taken before indexing a
+//!   block does not change,
+//! - the records superseded by the state of the wallet are deleted when the indexed state is
+//!   reset, and compacting the storage deletes no other record.
+//!
+//! The watched addresses are checked to follow the movements of their outputs, even when they
+//! are spent in the block that created them, without adding to the balance of the wallet.
+//!
+//! Along with them, the recurring payments are checked to generate at most one payment per epoch,
+//! made with transactions spending the largest unreserved outputs, signed with the wallet keys.
+//!
+//! Run them with `cargo test -p witnet_wallet --features property-tests`.
+use bech32::ToBase32 as _;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::*;
+use crate::types::Hashable as _;
+
+/// Number of random chains indexed by every property
+const CHAINS: u64 = 20;
+
+/// Number of blocks of every random chain
+const BLOCKS_PER_CHAIN: u32 = 50;
+
+/// Number of addresses of the wallet in every account
+const PKHS_PER_ACCOUNT: u8 = 3;
+
+/// Accounts of the wallet
+const ACCOUNTS: [AccountIndex; 2] = [0, 1];
+
+/// Expected state of the wallet: map output pointer -> (account, value) of its UTXOs
+#[derive(Default)]
+struct Model {
+    utxos: HashMap<OutputPointer, (AccountIndex, u64)>,
+}
+
+impl Model {
+    fn balance(&self, account: AccountIndex) -> Balance {
+        self.utxos
+            .values()
+            .filter(|(utxo_account, _)| *utxo_account == account)
+            .fold(Balance::ZERO, |total, (_, value)| {
+                total.saturating_add(Balance::from(*value))
+            })
+    }
+}
+
+/// Generator of random blocks spending and paying to the addresses of the wallet
+struct Generator {
+    rng: StdRng,
+    /// Map pkh -> account of the wallet addresses
+    pkhs: Vec<(PublicKeyHash, AccountIndex)>,
+}
+
+impl Generator {
+    fn new(seed: u64) -> Self {
+        let pkhs = ACCOUNTS
+            .iter()
+            .flat_map(|account| {
+                (0..PKHS_PER_ACCOUNT).map(move |i| {
+                    let mut hash = [0; 20];
+                    hash[0] = *account as u8;
+                    hash[1] = i;
+                    hash[19] = 0xff;
+
+                    (PublicKeyHash { hash }, *account)
+                })
+            })
+            .collect();
+
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            pkhs,
+        }
+    }
+
+    fn random_hash(&mut self) -> types::Hash {
+        types::Hash::SHA256(self.rng.gen())
+    }
+
+    /// Output paying either to the wallet or to a foreign address, along with the account of the
+    /// wallet it pays to
+    fn random_output(&mut self) -> (types::ValueTransferOutput, Option<AccountIndex>) {
+        let value = self.rng.gen_range(1, 1_000_000);
+        if self.rng.gen_bool(0.5) {
+            let (pkh, account) = self.pkhs[self.rng.gen_range(0, self.pkhs.len())];
+
+            (types::ValueTransferOutput { pkh, value }, Some(account))
+        } else {
+            let pkh = PublicKeyHash {
+                hash: self.rng.gen(),
+            };
+
+            (types::ValueTransferOutput { pkh, value }, None)
+        }
+    }
+
+    fn random_outputs(
+        &mut self,
+    ) -> (
+        Vec<types::ValueTransferOutput>,
+        Vec<(u32, AccountIndex, u64)>,
+    ) {
+        let mut outputs = vec![];
+        let mut own_outputs = vec![];
+        for index in 0..self.rng.gen_range(1, 4) {
+            let (output, account) = self.random_output();
+            if let Some(account) = account {
+                own_outputs.push((index, account, output.value));
+            }
+            outputs.push(output);
+        }
+
+        (outputs, own_outputs)
+    }
+
+    /// Random block spending some of the UTXOs of the model, which is updated with the expected
+    /// state of the wallet after indexing it.
+    ///
+    /// Only the UTXOs of previous blocks are spent, as the wallet indexes the value transfers of a
+    /// block before its tallies.
+    fn random_block(&mut self, epoch: u32, model: &mut Model) -> types::BlockTransactions {
+        let mut spendable: Vec<OutputPointer> = model.utxos.keys().cloned().collect();
+        spendable.sort_by_key(|pointer| (pointer.transaction_id, pointer.output_index));
+        let mut new_utxos = vec![];
+        let mut block = types::BlockTransactions {
+            epoch,
+            ..Default::default()
+        };
+
+        for _ in 0..self.rng.gen_range(0, 5) {
+            // Every transaction spends a foreign output, so their hashes are unique
+            let mut inputs = vec![types::Input::new(OutputPointer {
+                transaction_id: self.random_hash(),
+                output_index: 0,
+            })];
+            for _ in 0..self.rng.gen_range(0, 3) {
+                if spendable.is_empty() {
+                    break;
+                }
+                let pointer = spendable.swap_remove(self.rng.gen_range(0, spendable.len()));
+                model.utxos.remove(&pointer);
+                inputs.push(types::Input::new(pointer));
+            }
+            let (outputs, own_outputs) = self.random_outputs();
+            let txn = types::VTTransactionBody::new(inputs, outputs);
+            let txn_hash = txn.hash();
+            new_utxos.extend(own_outputs.into_iter().map(|(index, account, value)| {
+                (
+                    OutputPointer {
+                        transaction_id: txn_hash,
+                        output_index: index,
+                    },
+                    (account, value),
+                )
+            }));
+            block.value_transfer.push(txn);
+        }
+
+        for _ in 0..self.rng.gen_range(0, 3) {
+            let dr_pointer = self.random_hash();
+            let (outputs, own_outputs) = self.random_outputs();
+            let txn = types::TallyTransaction::new(dr_pointer, vec![], outputs);
+            let txn_hash = txn.hash();
+            new_utxos.extend(own_outputs.into_iter().map(|(index, account, value)| {
+                (
+                    OutputPointer {
+                        transaction_id: txn_hash,
+                        output_index: index,
+                    },
+                    (account, value),
+                )
+            }));
+            block.tallies.push(txn);
+        }
+
+        model.utxos.extend(new_utxos);
+
+        block
+    }
+}
+
+fn new_wallet(db: HashMapDb, generator: &Generator) -> Wallet<HashMapDb> {
+    let pkhs: HashMap<Pkh, AccountIndex> = generator
+        .pkhs
+        .iter()
+        .map(|(pkh, account)| (pkh.as_ref().to_vec(), *account))
+        .collect();
+    db.put(keys::wallet_pkhs(), pkhs).unwrap();
+
+    let wallet = Wallet::new(db, Params::default(), types::SignEngine::signing_only());
+    wallet.unlock().unwrap();
+    wallet.load().unwrap();
+
+    wallet
+}
+
+/// Run a property over `CHAINS` random chains, reporting the seed of the failing chain
+fn for_random_chains<F>(property: F)
+where
+    F: Fn(&mut Generator, &Wallet<HashMapDb>, HashMapDb),
+{
+    for seed in 0..CHAINS {
+        let mut generator = Generator::new(seed);
+        let db = HashMapDb::default();
+        let wallet = new_wallet(db.clone(), &generator);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            property(&mut generator, &wallet, db)
+        }));
+        if let Err(err) = result {
+            eprintln!("Property failed for the chain with seed {}", seed);
+            std::panic::resume_unwind(err);
+        }
+    }
+}
+
+#[test]
+fn balance_equals_utxo_sum() {
+    for_random_chains(|generator, wallet, _db| {
+        let mut model = Model::default();
+
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+
+            for account in ACCOUNTS.iter().cloned() {
+                let utxo_sum = account_utxos(wallet, account)
+                    .values()
+                    .fold(Balance::ZERO, |total, value| total.saturating_add(*value));
+
+                assert_eq!(account_balance(wallet, account), utxo_sum);
+                assert_eq!(account_balance(wallet, account), model.balance(account));
+            }
+        }
+    });
+}
+
+#[test]
+fn queries_read_published_snapshots() {
+    for_random_chains(|generator, wallet, _db| {
+        let mut model = Model::default();
+
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let previous = wallet.snapshot().unwrap();
+            let previous_balances: Vec<_> = ACCOUNTS
+                .iter()
+                .map(|account| previous.account_balances.get(account).cloned())
+                .collect();
+
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+
+            let snapshot = wallet.snapshot().unwrap();
+            for (i, account) in ACCOUNTS.iter().enumerate() {
+                assert_eq!(
+                    previous.account_balances.get(account).cloned(),
+                    previous_balances[i]
+                );
+                assert_eq!(
+                    snapshot
+                        .account_balances
+                        .get(account)
+                        .cloned()
+                        .unwrap_or_default(),
+                    account_balance(wallet, *account)
+                );
+                assert_eq!(
+                    snapshot.utxo_set.get(account).cloned().unwrap_or_default(),
+                    account_utxos(wallet, *account)
+                );
+            }
+            assert_eq!(*snapshot.transactions_count, transactions_count(wallet));
+        }
+    });
+}
+
+#[test]
+fn reindexing_a_block_is_idempotent() {
+    for_random_chains(|generator, wallet, _db| {
+        let mut model = Model::default();
+
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+
+            let balances: Vec<_> = ACCOUNTS
+                .iter()
+                .map(|account| account_balance(wallet, *account))
+                .collect();
+            let utxos: Vec<_> = ACCOUNTS
+                .iter()
+                .map(|account| account_utxos(wallet, *account))
+                .collect();
+            let count = transactions_count(wallet);
+
+            wallet.index_txns(&block).unwrap();
+
+            for (i, account) in ACCOUNTS.iter().enumerate() {
+                assert_eq!(account_balance(wallet, *account), balances[i]);
+                assert_eq!(account_utxos(wallet, *account), utxos[i]);
+            }
+            assert_eq!(transactions_count(wallet), count);
+        }
+    });
+}
+
+#[test]
+fn reloaded_wallet_keeps_balances_and_utxos() {
+    for_random_chains(|generator, wallet, db| {
+        let mut model = Model::default();
+
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+        }
+
+        let reloaded = new_wallet(db, generator);
+        for account in ACCOUNTS.iter().cloned() {
+            assert_eq!(
+                account_balance(&reloaded, account),
+                account_balance(wallet, account)
+            );
+            assert_eq!(
+                account_utxos(&reloaded, account),
+                account_utxos(wallet, account)
+            );
+        }
+        assert_eq!(transactions_count(&reloaded), transactions_count(wallet));
+    });
+}
+
+#[test]
+fn block_transactions_annotate_every_movement() {
+    for_random_chains(|generator, wallet, _db| {
+        let mut model = Model::default();
+
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            let count_before = transactions_count(wallet);
+            wallet.index_txns(&block).unwrap();
+
+            let txn_hashes: Vec<types::Hash> = block
+                .value_transfer
+                .iter()
+                .map(|txn| txn.hash())
+                .chain(block.tallies.iter().map(|txn| txn.hash()))
+                .collect();
+            let movements: usize = wallet
+                .block_transactions(&txn_hashes)
+                .unwrap()
+                .iter()
+                .map(|txn| txn.movements.len())
+                .sum();
+            let new_movements: u32 = transactions_count(wallet)
+                .iter()
+                .map(|(account, count)| count - count_before.get(account).cloned().unwrap_or(0))
+                .sum();
+
+            assert_eq!(movements, new_movements as usize);
+        }
+    });
+}
+
+#[test]
+fn changing_network_resets_the_indexed_chain() {
+    for_random_chains(|generator, wallet, _db| {
+        let network = |name: &str| types::NetworkId {
+            network: name.to_string(),
+            genesis_hash: types::Hash::default(),
+        };
+        assert_eq!(
+            wallet.sync_network(&network("testnet")).unwrap(),
+            types::NetworkSync::Rescan(None)
+        );
+
+        let mut model = Model::default();
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+        }
+        let balances: Vec<_> = ACCOUNTS
+            .iter()
+            .map(|account| account_balance(wallet, *account))
+            .collect();
+
+        // The same network keeps the indexed chain and resumes after its last block
+        assert_eq!(
+            wallet.sync_network(&network("testnet")).unwrap(),
+            types::NetworkSync::Rescan(Some(BLOCKS_PER_CHAIN))
+        );
+        for (i, account) in ACCOUNTS.iter().enumerate() {
+            assert_eq!(account_balance(wallet, *account), balances[i]);
+        }
+
+        // Another network forgets it and indexes its chain from the beginning
+        assert_eq!(
+            wallet.sync_network(&network("mainnet")).unwrap(),
+            types::NetworkSync::Rescan(Some(0))
+        );
+        for account in ACCOUNTS.iter().cloned() {
+            assert_eq!(account_balance(wallet, account), Balance::ZERO);
+            assert!(account_utxos(wallet, account).is_empty());
+        }
+        assert!(transactions_count(wallet).values().all(|count| *count == 0));
+
+        let mut model = Model::default();
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+        }
+        for account in ACCOUNTS.iter().cloned() {
+            assert_eq!(account_balance(wallet, account), model.balance(account));
+        }
+    });
+}
+
+#[test]
+fn chain_reset_waits_for_the_client() {
+    for_random_chains(|generator, wallet, _db| {
+        let network = |genesis: u8| types::NetworkId {
+            network: "testnet".to_string(),
+            genesis_hash: types::Hash::SHA256([genesis; 32]),
+        };
+        assert_eq!(
+            wallet.sync_network(&network(1)).unwrap(),
+            types::NetworkSync::Rescan(None)
+        );
+        assert!(wallet.reset_chain_data().is_err());
+
+        let mut model = Model::default();
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+        }
+        let balances: Vec<_> = ACCOUNTS
+            .iter()
+            .map(|account| account_balance(wallet, *account))
+            .collect();
+
+        // Another genesis block of the same network keeps the indexed chain and indexes nothing
+        assert_eq!(
+            wallet.sync_network(&network(2)).unwrap(),
+            types::NetworkSync::ChainReset(network(1))
+        );
+        let block = generator.random_block(BLOCKS_PER_CHAIN, &mut model);
+        wallet.index_txns(&block).unwrap();
+        for (i, account) in ACCOUNTS.iter().enumerate() {
+            assert_eq!(account_balance(wallet, *account), balances[i]);
+        }
+
+        // Until the client resets the chain data
+        assert_eq!(wallet.reset_chain_data().unwrap(), 0);
+        for account in ACCOUNTS.iter().cloned() {
+            assert_eq!(account_balance(wallet, account), Balance::ZERO);
+            assert!(account_utxos(wallet, account).is_empty());
+        }
+        assert_eq!(
+            wallet.sync_network(&network(2)).unwrap(),
+            types::NetworkSync::Rescan(Some(0))
+        );
+    });
+}
+
+#[test]
+fn reserved_outputs_are_released_when_spent() {
+    for_random_chains(|generator, wallet, _db| {
+        let mut model = Model::default();
+        let mut reserved: Vec<OutputPointer> = vec![];
+
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            // Reserve the outputs received since the previous block
+            let received: Vec<types::Input> = model
+                .utxos
+                .keys()
+                .filter(|pointer| !reserved.contains(pointer))
+                .cloned()
+                .map(types::Input::new)
+                .collect();
+            wallet.reserve_inputs(&received, 0).unwrap();
+            reserved.extend(received.iter().map(|input| input.output_pointer().clone()));
+            if let Some(input) = received.first() {
+                match wallet.reserve_inputs(&[input.clone()], 0) {
+                    Err(Error::UtxoReserved(_)) => {}
+                    result => panic!("reserved output was reserved again: {:?}", result),
+                }
+            }
+
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+            reserved.retain(|pointer| model.utxos.contains_key(pointer));
+
+            let mut expected: Vec<Utxo> = ACCOUNTS
+                .iter()
+                .flat_map(|account| account_utxos(wallet, *account).into_iter())
+                .map(|(utxo, _)| utxo)
+                .collect();
+            let mut actual: Vec<Utxo> = wallet.reserved.read().unwrap().keys().cloned().collect();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+
+        // The reservations expire
+        assert!(wallet.utxos(0).unwrap().iter().all(|utxo| utxo.reserved));
+        let expiry = constants::UTXO_RESERVATION_SECS;
+        assert!(wallet
+            .utxos(expiry)
+            .unwrap()
+            .iter()
+            .all(|utxo| !utxo.reserved));
+    });
+}
+
+#[test]
+fn deposits_are_delivered_until_acknowledged() {
+    for_random_chains(|generator, wallet, db| {
+        let network = types::NetworkId {
+            network: "testnet".to_string(),
+            genesis_hash: types::Hash::SHA256([1; 32]),
+        };
+        wallet.sync_network(&network).unwrap();
+        let addresses: Vec<String> = generator
+            .pkhs
+            .iter()
+            .map(|(pkh, _)| bech32::encode("twit", pkh.as_ref().to_base32()).unwrap())
+            .collect();
+        assert_eq!(
+            wallet
+                .register_deposit_addresses(addresses.clone())
+                .unwrap(),
+            addresses.len() as u32
+        );
+        assert_eq!(wallet.register_deposit_addresses(addresses).unwrap(), 0);
+
+        // Epoch and position in the chain of the block of every expected deposit, in the order
+        // they are indexed. Some epochs have no block, which must not count as confirmations
+        let mut expected = vec![];
+        let mut model = Model::default();
+        for i in 0..BLOCKS_PER_CHAIN {
+            let epoch = i * 2;
+            let block = generator.random_block(epoch, &mut model);
+            let outputs = block
+                .value_transfer
+                .iter()
+                .flat_map(|txn| txn.outputs.iter())
+                .chain(block.tallies.iter().flat_map(|txn| txn.outputs.iter()));
+            for output in outputs {
+                if generator.pkhs.iter().any(|(pkh, _)| *pkh == output.pkh) {
+                    expected.push((epoch, i));
+                }
+            }
+            wallet.index_txns(&block).unwrap();
+            wallet.index_txns(&block).unwrap();
+        }
+
+        let deposits = wallet.deposits(u32::max_value()).unwrap();
+        assert_eq!(deposits.cursor, 0);
+        assert_eq!(deposits.last_seq, expected.len() as u64);
+        for (i, deposit) in deposits.deposits.iter().enumerate() {
+            assert_eq!(deposit.seq, i as u64 + 1);
+            let (epoch, block_index) = expected[i];
+            assert_eq!(deposit.epoch, epoch);
+            assert_eq!(deposit.confirmations, BLOCKS_PER_CHAIN - block_index);
+        }
+
+        let half = deposits.last_seq / 2;
+        assert_eq!(wallet.ack_deposits(half).unwrap(), half);
+        assert_eq!(wallet.ack_deposits(0).unwrap(), half);
+        match wallet.ack_deposits(deposits.last_seq + 1) {
+            Err(Error::DepositNotFound(_)) => {}
+            result => panic!("acknowledged a deposit not detected yet: {:?}", result),
+        }
+
+        // The deposits not acknowledged are delivered again after a restart
+        let reloaded = new_wallet(db, generator);
+        reloaded.sync_network(&network).unwrap();
+        let remaining = reloaded.deposits(u32::max_value()).unwrap();
+        assert_eq!(remaining.cursor, half);
+        assert_eq!(remaining.last_seq, deposits.last_seq);
+        assert_eq!(
+            remaining.deposits,
+            deposits.deposits[half as usize..].to_vec()
+        );
+    });
+}
+
+#[test]
+fn deposits_are_not_delivered_twice() {
+    for_random_chains(|generator, wallet, _db| {
+        let network = types::NetworkId {
+            network: "testnet".to_string(),
+            genesis_hash: types::Hash::SHA256([1; 32]),
+        };
+        wallet.sync_network(&network).unwrap();
+        let addresses: Vec<String> = generator
+            .pkhs
+            .iter()
+            .map(|(pkh, _)| bech32::encode("twit", pkh.as_ref().to_base32()).unwrap())
+            .collect();
+        wallet.register_deposit_addresses(addresses).unwrap();
+
+        let mut model = Model::default();
+        let blocks: Vec<_> = (0..BLOCKS_PER_CHAIN)
+            .map(|epoch| generator.random_block(epoch, &mut model))
+            .collect();
+        for block in &blocks {
+            wallet.index_txns(block).unwrap();
+        }
+        let deposits = wallet.deposits(u32::max_value()).unwrap();
+        wallet.ack_deposits(deposits.last_seq).unwrap();
+
+        // Syncing with another network resets the indexed state, and the same outputs are
+        // indexed again
+        let other_network = types::NetworkId {
+            network: "mainnet".to_string(),
+            genesis_hash: types::Hash::SHA256([2; 32]),
+        };
+        assert_eq!(
+            wallet.sync_network(&other_network).unwrap(),
+            types::NetworkSync::Rescan(Some(0))
+        );
+        for block in &blocks {
+            wallet.index_txns(block).unwrap();
+        }
+
+        let reindexed = wallet.deposits(u32::max_value()).unwrap();
+        assert_eq!(reindexed.last_seq, deposits.last_seq);
+        assert!(reindexed.deposits.is_empty());
+    });
+}
+
+#[test]
+fn address_index_finds_every_movement() {
+    for_random_chains(|generator, wallet, _db| {
+        let mut model = Model::default();
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+            wallet.index_txns(&block).unwrap();
+        }
+
+        // The movements of the addresses of an account are all of its movements, and they add up
+        // to its balance
+        let mut indexed = 0;
+        let mut totals: HashMap<AccountIndex, (Balance, Balance)> = HashMap::new();
+        for (pkh, account) in &generator.pkhs {
+            let address = bech32::encode("twit", pkh.as_ref().to_base32()).unwrap();
+            let filter = model::MovementFilter {
+                address: Some(address.clone()),
+                ..model::MovementFilter::default()
+            };
+            let movements = wallet
+                .search_movements(&filter, 0, u32::max_value())
+                .unwrap();
+            let transactions = wallet
+                .address_transactions(&address, 0, u32::max_value())
+                .unwrap();
+            assert_eq!(movements.total, transactions.total);
+            assert_eq!(movements.movements.len(), transactions.transactions.len());
+
+            indexed += movements.total;
+            let (credits, debits) = totals.entry(*account).or_default();
+            for movement in movements.movements {
+                assert_eq!(movement.account, *account);
+                match movement.kind {
+                    model::TransactionKind::Debit => {
+                        *debits = debits.saturating_add(movement.value)
+                    }
+                    _ => *credits = credits.saturating_add(movement.value),
+                }
+            }
+        }
+        assert_eq!(indexed, transactions_count(wallet).values().sum::<u32>());
+        for account in ACCOUNTS.iter() {
+            let (credits, debits) = totals.get(account).cloned().unwrap_or_default();
+            assert_eq!(
+                credits.saturating_sub(debits),
+                account_balance(wallet, *account)
+            );
+        }
+
+        // Filtering the movements of the current account gives the same result as scanning them
+        let all = wallet
+            .search_movements(&model::MovementFilter::default(), 0, u32::max_value())
+            .unwrap();
+        let filter = model::MovementFilter {
+            kind: Some(model::TransactionKind::Debit),
+            from_epoch: Some(BLOCKS_PER_CHAIN / 4),
+            to_epoch: Some(BLOCKS_PER_CHAIN / 2),
+            ..model::MovementFilter::default()
+        };
+        let expected: Vec<(AccountIndex, TransactionId)> = all
+            .movements
+            .iter()
+            .filter(|movement| {
+                movement.kind == model::TransactionKind::Debit
+                    && movement.epoch >= BLOCKS_PER_CHAIN / 4
+                    && movement.epoch < BLOCKS_PER_CHAIN / 2
+            })
+            .map(|movement| (movement.account, movement.id))
+            .collect();
+        let debits = wallet.search_movements(&filter, 1, 2).unwrap();
+        assert_eq!(debits.total as usize, expected.len());
+        assert_eq!(
+            debits
+                .movements
+                .iter()
+                .map(|movement| (movement.account, movement.id))
+                .collect::<Vec<_>>(),
+            expected.into_iter().skip(1).take(2).collect::<Vec<_>>()
+        );
+    });
+}
+
+#[test]
+fn address_receipts_add_up_every_payment() {
+    for_random_chains(|generator, wallet, _db| {
+        let own_pkhs: HashSet<Pkh> = generator
+            .pkhs
+            .iter()
+            .map(|(pkh, _)| pkh.as_ref().to_vec())
+            .collect();
+        // Map pkh -> (total, first epoch, last epoch) of the payments to the wallet addresses
+        let mut expected: HashMap<Pkh, (Balance, u32, u32)> = HashMap::new();
+        let mut model = Model::default();
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            let outputs = block
+                .value_transfer
+                .iter()
+                .flat_map(|txn| &txn.outputs)
+                .chain(block.tallies.iter().flat_map(|txn| &txn.outputs));
+            for output in outputs {
+                let pkh = output.pkh.as_ref().to_vec();
+                if own_pkhs.contains(&pkh) {
+                    let (total, _, last) = expected.entry(pkh).or_insert((Balance::ZERO, epoch, 0));
+                    *total = total.saturating_add(Balance::from(output.value));
+                    *last = epoch;
+                }
+            }
+            wallet.index_txns(&block).unwrap();
+            wallet.index_txns(&block).unwrap();
+        }
+
+        let generation = wallet.snapshot().unwrap().index_generation;
+        for pkh in &own_pkhs {
+            let receipts: Option<AddressReceipts> = wallet
+                .db
+                .get_opt(&keys::pkh_receipts(generation, pkh))
+                .unwrap();
+            assert_eq!(
+                receipts.map(|r| (r.total, r.first_epoch, r.last_epoch)),
+                expected.get(pkh).cloned()
+            );
+        }
+    });
+}
+
+#[test]
+fn superseded_records_are_deleted() {
+    for_random_chains(|generator, wallet, db| {
+        let network = |name: &str| types::NetworkId {
+            network: name.to_string(),
+            genesis_hash: types::Hash::SHA256([1; 32]),
+        };
+        wallet.sync_network(&network("testnet")).unwrap();
+        let addresses: Vec<String> = generator
+            .pkhs
+            .iter()
+            .map(|(pkh, _)| bech32::encode("twit", pkh.as_ref().to_base32()).unwrap())
+            .collect();
+        wallet.register_deposit_addresses(addresses).unwrap();
+
+        let mut model = Model::default();
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+        }
+
+        // Acknowledged deposits are deleted right away
+        let deposits = wallet.deposits(u32::max_value()).unwrap();
+        let half = deposits.last_seq / 2;
+        wallet.ack_deposits(half).unwrap();
+        let stats = wallet.storage_stats().unwrap();
+        assert_eq!(
+            stats
+                .prefixes
+                .get("deposit-*")
+                .map_or(0, |prefix| prefix.records),
+            deposits.last_seq - half
+        );
+
+        // The chain indexed before changing network is superseded by the new one, and deleted
+        // as soon as the wallet is synced with it
+        let before = wallet.storage_stats().unwrap();
+        wallet.sync_network(&network("mainnet")).unwrap();
+        let after = wallet.storage_stats().unwrap();
+        assert!(after.records < before.records);
+        assert!(!after.prefixes.contains_key("sync-cursor-testnet-*"));
+
+        let mut model = Model::default();
+        for epoch in 0..BLOCKS_PER_CHAIN {
+            let block = generator.random_block(epoch, &mut model);
+            wallet.index_txns(&block).unwrap();
+        }
+
+        // So compacting the storage has nothing left to delete
+        let before = wallet.storage_stats().unwrap();
+        let compaction = wallet.compact().unwrap();
+        assert_eq!(compaction.pruned, 0);
+        assert_eq!(compaction.stats.records, before.records);
+
+        // The state of the wallet is not affected
+        let reloaded = new_wallet(db, generator);
+        reloaded.sync_network(&network("mainnet")).unwrap();
+        for account in ACCOUNTS.iter().cloned() {
+            assert_eq!(account_balance(&reloaded, account), model.balance(account));
+        }
+        assert_eq!(transactions_count(&reloaded), transactions_count(wallet));
+    });
+}
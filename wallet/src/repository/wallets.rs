@@ -25,13 +25,15 @@ impl<T: Database> Wallets<T> {
         Ok(())
     }
 
-    pub fn infos(&self) -> Result<Vec<model::Wallet>> {
-        let ids: Vec<String> = self.db.get_or_default(keys::wallet_ids())?;
+    /// Get the public info of the wallets of a tenant, or of the wallets created without tenant.
+    pub fn infos(&self, tenant: Option<&str>) -> Result<Vec<model::Wallet>> {
+        let ids: Vec<String> = self.db.get_or_default(&keys::tenant_wallet_ids(tenant))?;
         let mut wallets = Vec::with_capacity(ids.len());
 
         for id in ids {
-            let name = self.db.get_opt(&keys::wallet_id_name(&id))?;
-            let caption = self.db.get_opt(&keys::wallet_id_caption(&id))?;
+            let storage_id = keys::wallet_storage_id(tenant, &id);
+            let name = self.db.get_opt(&keys::wallet_id_name(&storage_id))?;
+            let caption = self.db.get_opt(&keys::wallet_id_caption(&storage_id))?;
 
             wallets.push(model::Wallet { id, name, caption })
         }
@@ -41,6 +43,7 @@ impl<T: Database> Wallets<T> {
 
     pub fn create<'a, D: Database>(
         &self,
+        tenant: Option<&str>,
         wallet_db: D,
         wallet_data: types::CreateWalletData<'a>,
     ) -> Result<()> {
@@ -50,6 +53,7 @@ impl<T: Database> Wallets<T> {
             caption,
             iv,
             salt,
+            master_key,
             account,
         } = wallet_data;
         let mut wbatch = wallet_db.batch();
@@ -60,23 +64,32 @@ impl<T: Database> Wallets<T> {
         if let Some(caption) = caption {
             wbatch.put(keys::wallet_caption(), caption)?;
         }
+        wbatch.put(keys::wallet_master_key(), master_key)?;
         wbatch.put(keys::wallet_default_account(), account.index)?;
         wbatch.put(keys::account_ek(account.index), &account.external)?;
         wbatch.put(keys::account_ik(account.index), &account.internal)?;
 
         wallet_db.write(wbatch)?;
 
+        self.register(tenant, id, &salt, &iv)
+    }
+
+    /// Add a wallet whose database already exists to the list of wallets, storing the params
+    /// needed to derive its encryption key.
+    pub fn register(&self, tenant: Option<&str>, id: &str, salt: &[u8], iv: &[u8]) -> Result<()> {
+        let storage_id = keys::wallet_storage_id(tenant, id);
+        let ids_key = keys::tenant_wallet_ids(tenant);
         let mut batch = self.db.batch();
-        batch.put(keys::wallet_id_salt(&id), &salt)?;
-        batch.put(keys::wallet_id_iv(&id), &iv)?;
+        batch.put(keys::wallet_id_salt(&storage_id), salt)?;
+        batch.put(keys::wallet_id_iv(&storage_id), iv)?;
 
         // // FIXME: Use merge operator or a transaction when available in rocksdb crate
         let wallet_id = id.to_string();
         let lock = self.wallets_mutex.lock()?;
-        let mut ids: Vec<String> = self.db.get_or_default(&keys::wallet_ids())?;
+        let mut ids: Vec<String> = self.db.get_or_default(&ids_key)?;
         if !ids.contains(&wallet_id) {
             ids.push(wallet_id);
-            batch.put(keys::wallet_ids(), ids)?;
+            batch.put(ids_key, ids)?;
             self.db.write(batch)?;
         }
         drop(lock);
@@ -84,9 +97,16 @@ impl<T: Database> Wallets<T> {
         Ok(())
     }
 
-    pub fn wallet_salt_and_iv(&self, id: &str) -> Result<(Vec<u8>, Vec<u8>)> {
-        let salt = self.db.get(&keys::wallet_id_salt(id))?;
-        let iv = self.db.get(&keys::wallet_id_iv(id))?;
+    pub fn contains(&self, tenant: Option<&str>, id: &str) -> Result<bool> {
+        let ids: Vec<String> = self.db.get_or_default(&keys::tenant_wallet_ids(tenant))?;
+
+        Ok(ids.iter().any(|wallet_id| wallet_id == id))
+    }
+
+    pub fn wallet_salt_and_iv(&self, tenant: Option<&str>, id: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+        let storage_id = keys::wallet_storage_id(tenant, id);
+        let salt = self.db.get(&keys::wallet_id_salt(&storage_id))?;
+        let iv = self.db.get(&keys::wallet_id_iv(&storage_id))?;
 
         Ok((salt, iv))
     }
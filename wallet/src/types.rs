@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::fmt;
 use std::sync::Arc;
 
@@ -6,20 +7,29 @@ pub use jsonrpc_pubsub::{Sink, SinkResult, Subscriber, SubscriptionId};
 use serde::{Deserialize, Serialize};
 pub use serde_json::Value as Json;
 
+pub use witnet_config::config::{AddressReusePolicy, ChangeAddressPolicy};
 pub use witnet_crypto::{
     hash::HashFunction,
-    key::{ExtendedPK, ExtendedSK, KeyDerivationError, KeyPath, SignEngine},
-    mnemonic::{Length as MnemonicLength, Mnemonic, MnemonicGen},
+    key::{ExtendedPK, ExtendedSK, KeyDerivationError, KeyPath, SignEngine, SK},
+    mnemonic::{Lang as MnemonicLang, Length as MnemonicLength, Mnemonic, MnemonicGen},
 };
 pub use witnet_data_structures::{
-    chain::{Block as ChainBlock, Hashable, RADRequest, ValueTransferOutput},
-    transaction::VTTransactionBody,
+    chain::{
+        Block as ChainBlock, CheckpointBeacon, DataRequestOutput, EpochConstants, Hash, Hashable,
+        Input, InventoryItem, KeyedSignature, OutputPointer, PublicKey, PublicKeyHash, RADRequest,
+        RADType, Secp256k1Signature, Signature, ValueTransferOutput,
+    },
+    transaction::{
+        DRTransaction, DRTransactionBody, TallyTransaction, Transaction, VTTransaction,
+        VTTransactionBody,
+    },
+    types::NetworkId,
 };
 pub use witnet_net::client::tcp::jsonrpc::Request as RpcRequest;
 use witnet_protected::{Protected, ProtectedString};
-pub use witnet_rad::types::RadonTypes;
+pub use witnet_rad::types::{canonical_json::JsonFormat, RadonTypes};
 
-use super::{db, repository};
+use super::{constants, db, repository};
 
 pub type Password = ProtectedString;
 
@@ -58,9 +68,215 @@ impl From<String> for SessionId {
     }
 }
 
+/// Amount of wits, expressed in nanowits, the smallest unit of account.
+///
+/// Arithmetic is only exposed through checked and saturating operations, so balances can never
+/// silently wrap around.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Nanowits(u64);
+
+impl Nanowits {
+    /// Zero nanowits.
+    pub const ZERO: Nanowits = Nanowits(0);
+
+    /// Whether the amount is zero.
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Add two amounts, returning `None` on overflow.
+    pub fn checked_add(self, other: Nanowits) -> Option<Nanowits> {
+        self.0.checked_add(other.0).map(Nanowits)
+    }
+
+    /// Subtract two amounts, returning `None` on underflow.
+    pub fn checked_sub(self, other: Nanowits) -> Option<Nanowits> {
+        self.0.checked_sub(other.0).map(Nanowits)
+    }
+
+    /// Add two amounts, saturating at the maximum amount.
+    pub fn saturating_add(self, other: Nanowits) -> Nanowits {
+        Nanowits(self.0.saturating_add(other.0))
+    }
+
+    /// Subtract two amounts, saturating at zero.
+    pub fn saturating_sub(self, other: Nanowits) -> Nanowits {
+        Nanowits(self.0.saturating_sub(other.0))
+    }
+}
+
+impl From<u64> for Nanowits {
+    fn from(nanowits: u64) -> Self {
+        Nanowits(nanowits)
+    }
+}
+
+impl From<Nanowits> for u64 {
+    fn from(nanowits: Nanowits) -> Self {
+        nanowits.0
+    }
+}
+
+impl fmt::Display for Nanowits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 pub enum SeedSource {
     Mnemonics(Mnemonic),
-    Xprv,
+    /// Bech32-encoded xprv and the backup password used to encrypt it
+    Xprv(Password, Password),
+}
+
+/// Derivation depth of an exported extended private key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum XprvDepth {
+    /// Master key, `m`
+    Master,
+    /// Account-level key, `m/3'/4919'/account'`
+    Account,
+}
+
+impl XprvDepth {
+    /// BIP-32 depth of the keys exported with this variant.
+    pub fn depth(self) -> u8 {
+        match self {
+            XprvDepth::Master => 0,
+            XprvDepth::Account => 3,
+        }
+    }
+}
+
+impl Default for XprvDepth {
+    fn default() -> Self {
+        XprvDepth::Master
+    }
+}
+
+/// Hardware wallet used as external signer through a bridge connected to the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignerDevice {
+    Trezor,
+    Ledger,
+}
+
+/// Priority of a data request, used to fill the reward and the fees omitted by the caller. Miners
+/// commit first to the data requests with the highest reward per witness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataRequestPriority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Default for DataRequestPriority {
+    fn default() -> Self {
+        DataRequestPriority::Medium
+    }
+}
+
+impl DataRequestPriority {
+    /// Reward for each witness, in nanowits, see `DR_PRIORITY_REWARDS_PER_WITNESS`.
+    pub fn reward_per_witness(self) -> u64 {
+        constants::DR_PRIORITY_REWARDS_PER_WITNESS[self as usize]
+    }
+
+    /// Fee for the miner of each commit, reveal and tally transaction, in nanowits, see
+    /// `DR_PRIORITY_FEES_PER_TRANSACTION`.
+    pub fn fee_per_transaction(self) -> u64 {
+        constants::DR_PRIORITY_FEES_PER_TRANSACTION[self as usize]
+    }
+}
+
+/// Interval between the payments of a recurring payment.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleInterval {
+    Epochs(u32),
+    Days(u32),
+}
+
+impl ScheduleInterval {
+    /// Number of epochs of the interval, given the duration of an epoch in seconds.
+    pub fn epochs(self, checkpoints_period: u16) -> u32 {
+        match self {
+            ScheduleInterval::Epochs(epochs) => epochs,
+            ScheduleInterval::Days(days) => {
+                let seconds = u64::from(days) * 24 * 60 * 60;
+                let epochs = seconds / u64::from(checkpoints_period.max(1));
+
+                u32::try_from(epochs).unwrap_or(u32::max_value())
+            }
+        }
+    }
+}
+
+/// Transactions of a block that can change the balance of a wallet.
+#[derive(Debug, Clone, Default)]
+pub struct BlockTransactions {
+    /// Epoch of the block
+    pub epoch: u32,
+    /// Hash of the block
+    pub block_hash: Hash,
+    pub value_transfer: Vec<VTTransactionBody>,
+    pub data_requests: Vec<DRTransactionBody>,
+    pub tallies: Vec<TallyTransaction>,
+}
+
+impl From<ChainBlock> for BlockTransactions {
+    fn from(block: ChainBlock) -> Self {
+        Self {
+            epoch: block.block_header.beacon.checkpoint,
+            block_hash: block.hash(),
+            value_transfer: block
+                .txns
+                .value_transfer_txns
+                .into_iter()
+                .map(|txn| txn.body)
+                .collect(),
+            data_requests: block
+                .txns
+                .data_request_txns
+                .into_iter()
+                .map(|txn| txn.body)
+                .collect(),
+            tallies: block.txns.tally_txns,
+        }
+    }
+}
+
+/// Last block indexed by a wallet from the chain of a network.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncCursor {
+    pub epoch: u32,
+    pub block_hash: Hash,
+}
+
+/// Outcome of syncing a wallet with the network of its node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkSync {
+    /// The blocks of the chain have to be indexed again from this epoch, if any
+    Rescan(Option<u32>),
+    /// The node belongs to the same network as the chain indexed by the wallet, given here, but
+    /// its genesis block is a different one, e.g. after a testnet reset. Nothing is indexed until
+    /// the client resets the chain data of the wallet
+    ChainReset(NetworkId),
+}
+
+/// Status of the node, as returned by its `status` method, of which the wallet only needs the
+/// beacon of its chain.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStatus {
+    pub chain_beacon: CheckpointBeacon,
+    pub synchronized: bool,
 }
 
 pub struct UnlockedSessionWallet {
@@ -83,7 +299,7 @@ pub struct Account {
 pub struct WalletData {
     pub name: Option<String>,
     pub caption: Option<String>,
-    pub balance: u64,
+    pub balance: Nanowits,
     pub current_account: u32,
     pub available_accounts: Vec<u32>,
 }
@@ -94,5 +310,40 @@ pub struct CreateWalletData<'a> {
     pub caption: Option<String>,
     pub iv: Vec<u8>,
     pub salt: Vec<u8>,
+    pub master_key: &'a ExtendedSK,
     pub account: &'a Account,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nanowits_are_plain_numbers_on_the_wire() {
+        let amount = Nanowits::from(1_500_000_000);
+
+        assert_eq!(
+            serde_json::to_value(amount).unwrap(),
+            serde_json::json!(1_500_000_000u64)
+        );
+        assert_eq!(
+            serde_json::from_value::<Nanowits>(serde_json::json!(1_500_000_000u64)).unwrap(),
+            amount
+        );
+        assert_eq!(
+            bincode::deserialize::<Nanowits>(&bincode::serialize(&amount).unwrap()).unwrap(),
+            amount
+        );
+    }
+
+    #[test]
+    fn nanowits_arithmetic_does_not_wrap() {
+        let max = Nanowits::from(u64::max_value());
+        let one = Nanowits::from(1);
+
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(Nanowits::ZERO.checked_sub(one), None);
+        assert_eq!(max.saturating_add(one), max);
+        assert_eq!(Nanowits::ZERO.saturating_sub(one), Nanowits::ZERO);
+    }
+}
@@ -0,0 +1,280 @@
+//! Payment request URIs, following the syntax of Bitcoin's BIP-21:
+//!
+//! ```text
+//! wit:<address>[?amount=<amount>][&message=<message>]
+//! ```
+//!
+//! where `amount` is a positive number of nanowits, written only with decimal digits, and
+//! `message` is percent-encoded.
+use failure::Fail;
+
+use crate::{constants, model};
+
+/// Errors that can occur when parsing a payment request URI.
+#[derive(Debug, Fail, PartialEq)]
+pub enum Error {
+    #[fail(display = "the URI scheme must be \"{}\"", _0)]
+    InvalidScheme(&'static str),
+    #[fail(display = "the URI does not contain an address")]
+    MissingAddress,
+    #[fail(display = "the amount is not a positive number of nanowits: {}", _0)]
+    InvalidAmount(String),
+    #[fail(display = "the URI contains a malformed percent-encoded value")]
+    InvalidEncoding,
+    #[fail(display = "the URI contains the param \"{}\" more than once", _0)]
+    DuplicatedParam(String),
+    #[fail(display = "the URI contains an unsupported required param \"{}\"", _0)]
+    UnsupportedRequiredParam(String),
+}
+
+/// Encode a payment request as a URI.
+pub fn encode(request: &model::PaymentRequest) -> String {
+    let mut params = Vec::new();
+    if let Some(amount) = request.amount {
+        params.push(format!("amount={}", amount));
+    }
+    if let Some(message) = &request.message {
+        params.push(format!("message={}", percent_encode(message)));
+    }
+
+    let mut uri = format!("{}:{}", constants::PAYMENT_URI_SCHEME, request.address);
+    if !params.is_empty() {
+        uri.push('?');
+        uri.push_str(&params.join("&"));
+    }
+
+    uri
+}
+
+/// Encode a payment request as a URI suitable for QR codes.
+///
+/// The scheme and the address are uppercase so they can be encoded using the more compact
+/// alphanumeric mode of QR codes. Bech32 addresses are case-insensitive, so the URI is still
+/// valid.
+pub fn encode_qr(request: &model::PaymentRequest) -> String {
+    let uri = encode(request);
+
+    match uri.find('?') {
+        Some(index) => format!("{}{}", uri[..index].to_uppercase(), &uri[index..]),
+        None => uri.to_uppercase(),
+    }
+}
+
+/// Decode a payment request URI.
+///
+/// The address is returned in lowercase but it is not validated. Unknown params are ignored,
+/// unless they are prefixed with `req-`, meaning that they must be understood to process the
+/// payment.
+pub fn decode(uri: &str) -> Result<model::PaymentRequest, Error> {
+    let uri = uri.trim();
+    let scheme_len = constants::PAYMENT_URI_SCHEME.len();
+    if uri.len() <= scheme_len
+        || !uri.is_char_boundary(scheme_len)
+        || !uri[..scheme_len].eq_ignore_ascii_case(constants::PAYMENT_URI_SCHEME)
+        || !uri[scheme_len..].starts_with(':')
+    {
+        return Err(Error::InvalidScheme(constants::PAYMENT_URI_SCHEME));
+    }
+
+    let rest = &uri[scheme_len + 1..];
+    let (address, query) = match rest.find('?') {
+        Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+        None => (rest, None),
+    };
+    if address.is_empty() {
+        return Err(Error::MissingAddress);
+    }
+
+    let mut request = model::PaymentRequest {
+        address: address.to_lowercase(),
+        amount: None,
+        message: None,
+    };
+
+    for param in query.into_iter().flat_map(|q| q.split('&')) {
+        if param.is_empty() {
+            continue;
+        }
+        let (key, value) = match param.find('=') {
+            Some(index) => (&param[..index], &param[index + 1..]),
+            None => (param, ""),
+        };
+
+        match key {
+            "amount" => {
+                if request.amount.is_some() {
+                    return Err(Error::DuplicatedParam(key.to_string()));
+                }
+                request.amount = Some(decode_amount(value)?.into());
+            }
+            "message" => {
+                if request.message.is_some() {
+                    return Err(Error::DuplicatedParam(key.to_string()));
+                }
+                request.message = Some(percent_decode(value)?);
+            }
+            key if key.starts_with("req-") => {
+                return Err(Error::UnsupportedRequiredParam(key.to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(request)
+}
+
+/// Decode an amount, which must be a positive number of nanowits written only with decimal
+/// digits, so every client reads it in the same way.
+fn decode_amount(value: &str) -> Result<u64, Error> {
+    let invalid = || Error::InvalidAmount(value.to_string());
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    match value.parse::<u64>() {
+        Ok(amount) if amount > 0 => Ok(amount),
+        _ => Err(invalid()),
+    }
+}
+
+/// Percent-encode all the bytes of a string except the unreserved characters of RFC 3986.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(char::from(byte))
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Decode a percent-encoded UTF-8 string.
+fn percent_decode(value: &str) -> Result<String, Error> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .ok_or(Error::InvalidEncoding)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| Error::InvalidEncoding)?;
+            let byte = u8::from_str_radix(hex, 16).map_err(|_| Error::InvalidEncoding)?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| Error::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "twit1lgmqrrnsqwv5rpkvq8ccaj76jcfhv6vpz9qqgd";
+
+    fn request(amount: Option<u64>, message: Option<&str>) -> model::PaymentRequest {
+        model::PaymentRequest {
+            address: ADDRESS.to_string(),
+            amount: amount.map(Into::into),
+            message: message.map(String::from),
+        }
+    }
+
+    #[test]
+    fn uri_roundtrip() {
+        let payment = request(Some(1_500), Some("Invoice #42 €"));
+        let uri = encode(&payment);
+        assert_eq!(
+            uri,
+            format!(
+                "wit:{}?amount=1500&message=Invoice%20%2342%20%E2%82%AC",
+                ADDRESS
+            )
+        );
+
+        let decoded = decode(&uri).unwrap();
+        assert_eq!(decoded.address, ADDRESS);
+        assert_eq!(decoded.amount, payment.amount);
+        assert_eq!(decoded.message, payment.message);
+
+        // The QR code variant is decoded to the same request
+        let decoded = decode(&encode_qr(&payment)).unwrap();
+        assert_eq!(decoded.address, ADDRESS);
+        assert_eq!(decoded.amount, payment.amount);
+    }
+
+    #[test]
+    fn uri_without_params() {
+        let decoded = decode(&format!("WIT:{}", ADDRESS.to_uppercase())).unwrap();
+        assert_eq!(decoded.address, ADDRESS);
+        assert_eq!(decoded.amount, None);
+        assert_eq!(decoded.message, None);
+
+        assert_eq!(
+            decode("bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT").map(|_| ()),
+            Err(Error::InvalidScheme("wit"))
+        );
+        assert_eq!(
+            decode("wit:?amount=1").map(|_| ()),
+            Err(Error::MissingAddress)
+        );
+    }
+
+    #[test]
+    fn amounts_are_positive_decimal_numbers() {
+        for amount in &[
+            "+1",
+            "0",
+            "000",
+            "-1",
+            "1.5",
+            "1e3",
+            " 1",
+            "",
+            "18446744073709551616",
+        ] {
+            assert_eq!(
+                decode(&format!("wit:{}?amount={}", ADDRESS, amount)).map(|_| ()),
+                Err(Error::InvalidAmount(amount.to_string())),
+                "amount {:?}",
+                amount
+            );
+        }
+
+        let decoded = decode(&format!("wit:{}?amount=007", ADDRESS)).unwrap();
+        assert_eq!(decoded.amount, Some(crate::types::Nanowits::from(7)));
+    }
+
+    #[test]
+    fn params_are_checked() {
+        assert_eq!(
+            decode(&format!("wit:{}?amount=1&amount=2", ADDRESS)).map(|_| ()),
+            Err(Error::DuplicatedParam("amount".to_string()))
+        );
+        assert_eq!(
+            decode(&format!("wit:{}?req-expires=1", ADDRESS)).map(|_| ()),
+            Err(Error::UnsupportedRequiredParam("req-expires".to_string()))
+        );
+        for message in &["%", "%2", "%+1", "%zz", "%FF"] {
+            assert_eq!(
+                decode(&format!("wit:{}?message={}", ADDRESS, message)).map(|_| ()),
+                Err(Error::InvalidEncoding),
+                "message {:?}",
+                message
+            );
+        }
+
+        // Unknown params are ignored
+        assert!(decode(&format!("wit:{}?label=shop", ADDRESS)).is_ok());
+    }
+}